@@ -1,5 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use gsl_rust::fft::fft64_packed;
+use gsl_rust::integrate::{qag_ext, GaussKronrodRule, IntegrationWorkspace};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     // Prepare data
@@ -13,6 +14,21 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             fft64_packed(y.as_mut())
         })
     });
+
+    // Many small adaptive integrals, with and without reusing the workspace
+    gsl_rust::disable_error_handler();
+    let f = |x: f64| (x * x).sin() / (1.0 + x);
+    c.bench_function("qag gk61 new workspace", |b| {
+        b.iter(|| qag_ext(16, 0.0, 2.0, 1.0e-9, 0.0, GaussKronrodRule::Gauss61, f).unwrap())
+    });
+    let mut workspace = IntegrationWorkspace::new(16).unwrap();
+    c.bench_function("qag gk61 reused workspace", |b| {
+        b.iter(|| {
+            workspace
+                .qag(0.0, 2.0, 1.0e-9, 0.0, GaussKronrodRule::Gauss61, f)
+                .unwrap()
+        })
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
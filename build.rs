@@ -2,6 +2,11 @@ use std::env;
 use std::path::PathBuf;
 
 fn main() {
+    // Only the pure Rust modules are built without GSL
+    if env::var_os("CARGO_FEATURE_GSL").is_none() {
+        return;
+    }
+
     std::env::remove_var("NUM_JOBS");
     println!("cargo:rerun-if-changed=wrapper.h");
 
@@ -0,0 +1,62 @@
+/*
+    damped_oscillator.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use gsl_rust::nonlinear_fit::nonlinear_fit;
+use gsl_rust::rng::{Rng, RngType};
+use gsl_rust::util::linspace;
+use gsl_rust::*;
+
+/*
+
+    Fits `y = A exp(-γ t) cos(ω t + φ)` to noisy samples of a damped oscillator.
+
+    The covariance of the fit assumes unit errors, so the uncertainties are scaled
+    by the standard deviation of the residuals.
+
+*/
+
+fn model(t: &f64, [a, gamma, omega, phi]: [f64; 4]) -> Result<f64> {
+    Ok(a * (-gamma * t).exp() * (omega * t + phi).cos())
+}
+
+fn main() -> Result<()> {
+    disable_error_handler();
+
+    let truth = [2.0, 0.3, 3.0, 0.5];
+    let mut rng = Rng::with_seed(RngType::Mt19937, 1);
+    let t = linspace(0.0, 10.0, 200);
+    let y = t
+        .iter()
+        .map(|t| Ok(model(t, truth)? + rng.gaussian(0.02)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let fit = nonlinear_fit([1.5, 0.2, 2.9, 0.3], &t, &y, model)?;
+    let sigma = (fit.final_residual_squared / (t.len() - 4) as f64).sqrt();
+    for (i, name) in ["A", "γ", "ω", "φ"].iter().enumerate() {
+        let uncertainty = sigma * fit.uncertainty(i);
+        println!("{} = {:.4} ± {:.4}", name, fit.params[i], uncertainty);
+        assert!((fit.params[i] - truth[i]).abs() < 5.0 * uncertainty);
+    }
+    println!("residual standard deviation {:.4}", sigma);
+    Ok(())
+}
+
+#[test]
+fn damped_oscillator() {
+    main().unwrap();
+}
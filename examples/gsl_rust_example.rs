@@ -0,0 +1,94 @@
+/*
+    gsl_rust_example.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use gsl_rust::integrate::qag;
+use gsl_rust::nonlinear_fit::nonlinear_fit;
+use gsl_rust::{disable_error_handler, GSLError};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/*
+
+    Python module `gsl_rust_example` with an exponential fit and an integration,
+    as a template for embedding the crate into Python workflows. Build and import it with
+
+        cargo build --release --example gsl_rust_example --features python
+        cp target/release/examples/libgsl_rust_example.so gsl_rust_example.so
+        python -c "import gsl_rust_example; print(gsl_rust_example.integrate(abs, -1.0, 1.0))"
+
+    (on macOS the library is `libgsl_rust_example.dylib`, on Windows `gsl_rust_example.dll`, to be renamed to `.pyd`).
+
+    Python lists convert to and from `Vec<f64>`, and tuples to and from Rust tuples,
+    so results cross as `Vec<f64>` and `(value, error)` pairs using the `From` conversions of the crate.
+    Errors of GSL are raised as `ValueError`, exceptions raised by Python callbacks are passed on.
+
+*/
+
+/// Fits `amplitude * exp(-rate * x)` to the points `(x, y)`,
+/// returning the parameters `[amplitude, rate]` and their covariance matrix.
+#[pyfunction]
+fn fit_exponential(
+    x: Vec<f64>,
+    y: Vec<f64>,
+    amplitude: f64,
+    rate: f64,
+) -> PyResult<(Vec<f64>, Vec<Vec<f64>>)> {
+    let fit = nonlinear_fit([amplitude, rate], &x, &y, |&x, [a, k]| {
+        Ok(a * (-k * x).exp())
+    })
+    .map_err(value_error)?;
+    let covariance = fit.covariance_rows();
+    Ok((fit.into(), covariance))
+}
+
+/// Integral of the Python function `f` from `a` to `b`, returning the value and its error estimate.
+#[pyfunction]
+fn integrate(py: Python<'_>, f: PyObject, a: f64, b: f64) -> PyResult<(f64, f64)> {
+    // The integrand can't fail, so the first exception stops the evaluation by returning NaN
+    let mut exception = None;
+    let result = qag(a, b, |x| {
+        if exception.is_some() {
+            return f64::NAN;
+        }
+        match f.call1(py, (x,)).and_then(|y| y.extract::<f64>(py)) {
+            Ok(y) => y,
+            Err(e) => {
+                exception = Some(e);
+                f64::NAN
+            }
+        }
+    });
+    if let Some(e) = exception {
+        return Err(e);
+    }
+    Ok(result.map_err(value_error)?.into())
+}
+
+fn value_error(e: GSLError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+#[pymodule]
+fn gsl_rust_example(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    // Errors have to come back as exceptions instead of aborting the interpreter
+    disable_error_handler();
+
+    m.add_function(wrap_pyfunction!(fit_exponential, m)?)?;
+    m.add_function(wrap_pyfunction!(integrate, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+/*
+    kde.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use gsl_rust::rng::{Rng, RngType};
+use gsl_rust::stats;
+use gsl_rust::util::{linspace, write_csv};
+use gsl_rust::*;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufWriter;
+
+/*
+
+    Gaussian kernel density estimate of samples from a mixture of two normal distributions,
+    with the bandwidth from Silverman's rule of thumb `1.06 σ n^(-1/5)`.
+
+    The estimate and the true density are exported to `kde.csv` in the temporary directory,
+    with one line per point of the grid.
+
+*/
+
+fn normal(x: f64, mean: f64, sigma: f64) -> f64 {
+    (-0.5 * ((x - mean) / sigma).powi(2)).exp() / (sigma * (2.0 * PI).sqrt())
+}
+
+fn density(x: f64) -> f64 {
+    0.3 * normal(x, -2.0, 0.5) + 0.7 * normal(x, 1.0, 1.0)
+}
+
+fn main() -> Result<()> {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(RngType::Mt19937, 4);
+    let samples = (0..2000)
+        .map(|_| {
+            if rng.uniform() < 0.3 {
+                -2.0 + rng.gaussian(0.5)
+            } else {
+                1.0 + rng.gaussian(1.0)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let bandwidth = 1.06 * stats::sd(&samples) * (samples.len() as f64).powf(-0.2);
+    let x = linspace(-6.0, 6.0, 241);
+    let estimate = x
+        .iter()
+        .map(|&x| {
+            samples
+                .iter()
+                .map(|&s| normal(x, s, bandwidth))
+                .sum::<f64>()
+                / samples.len() as f64
+        })
+        .collect::<Vec<_>>();
+    let exact = x.iter().map(|&x| density(x)).collect::<Vec<_>>();
+
+    // Trapezoid rule
+    let step = x[1] - x[0];
+    let integral = estimate
+        .windows(2)
+        .map(|w| 0.5 * (w[0] + w[1]))
+        .sum::<f64>()
+        * step;
+    let max_error = estimate
+        .iter()
+        .zip(&exact)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+    println!(
+        "bandwidth {:.4}, integral {:.6}, largest error {:.4}",
+        bandwidth, integral, max_error
+    );
+    assert!((integral - 1.0).abs() < 1.0e-3);
+    assert!(max_error < 0.05);
+
+    let path = std::env::temp_dir().join("kde.csv");
+    write_csv(
+        BufWriter::new(File::create(&path).unwrap()),
+        &["x", "estimate", "exact"],
+        &[&x, &estimate, &exact],
+    )
+    .unwrap();
+    println!("density written to {}", path.display());
+    Ok(())
+}
+
+#[test]
+fn kde() {
+    main().unwrap();
+}
@@ -0,0 +1,93 @@
+/*
+    kepler_orbit.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use gsl_rust::ode;
+use gsl_rust::util::{linspace, write_csv};
+use gsl_rust::*;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufWriter;
+
+/*
+
+    Integrates one period of an eccentric Kepler orbit, in units with `GM = 1` and semi-major axis 1,
+    and exports the orbit to `kepler_orbit.csv` in the temporary directory.
+
+    The state is `(x, y, vx, vy)`, starting at perihelion. After a period of `2π` the body is back
+    at its starting point, and the energy `-1/2` is conserved along the way.
+
+*/
+
+const ECCENTRICITY: f64 = 0.5;
+
+fn energy(state: &[f64]) -> f64 {
+    let r = state[0].hypot(state[1]);
+    0.5 * (state[2] * state[2] + state[3] * state[3]) - 1.0 / r
+}
+
+fn main() -> Result<()> {
+    disable_error_handler();
+
+    let perihelion = 1.0 - ECCENTRICITY;
+    let speed = ((1.0 + ECCENTRICITY) / perihelion).sqrt();
+    let y0 = [perihelion, 0.0, 0.0, speed];
+
+    let solution = ode::solve(
+        |_, y, dydt| {
+            let r3 = y[0].hypot(y[1]).powi(3);
+            dydt[0] = y[2];
+            dydt[1] = y[3];
+            dydt[2] = -y[0] / r3;
+            dydt[3] = -y[1] / r3;
+        },
+        &y0,
+        0.0,
+        2.0 * PI,
+    )?;
+
+    let end = solution.final_state();
+    println!("{} steps, final state {:?}", solution.t().len(), end);
+    for (a, b) in end.iter().zip(&y0) {
+        assert!((a - b).abs() < 1.0e-6);
+    }
+    for i in 0..solution.t().len() {
+        assert!((energy(solution.y(i)) + 0.5).abs() < 1.0e-8);
+    }
+
+    let t = linspace(0.0, 2.0 * PI, 201);
+    let states = t
+        .iter()
+        .map(|&t| solution.eval(t))
+        .collect::<Result<Vec<_>>>()?;
+    let x = states.iter().map(|s| s[0]).collect::<Vec<_>>();
+    let y = states.iter().map(|s| s[1]).collect::<Vec<_>>();
+    let path = std::env::temp_dir().join("kepler_orbit.csv");
+    write_csv(
+        BufWriter::new(File::create(&path).unwrap()),
+        &["t", "x", "y"],
+        &[&t, &x, &y],
+    )
+    .unwrap();
+    println!("orbit written to {}", path.display());
+    Ok(())
+}
+
+#[test]
+fn kepler_orbit() {
+    main().unwrap();
+}
@@ -0,0 +1,53 @@
+/*
+    monte_carlo_pi.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use gsl_rust::rng::{Rng, RngType};
+use gsl_rust::*;
+use std::f64::consts::PI;
+
+/*
+
+    Estimates π from the fraction of uniform points in the unit square that fall inside the quarter circle.
+    The estimate has the binomial standard deviation `4 sqrt(p (1 - p) / n)` with `p = π / 4`.
+
+*/
+
+fn main() -> Result<()> {
+    disable_error_handler();
+
+    let n = 1_000_000;
+    let mut rng = Rng::with_seed(RngType::Mt19937, 3);
+    let inside = (0..n)
+        .filter(|_| {
+            let (x, y) = (rng.uniform(), rng.uniform());
+            x * x + y * y < 1.0
+        })
+        .count();
+
+    let p = inside as f64 / n as f64;
+    let estimate = 4.0 * p;
+    let sigma = 4.0 * (p * (1.0 - p) / n as f64).sqrt();
+    println!("π ≈ {:.5} ± {:.5}", estimate, sigma);
+    assert!((estimate - PI).abs() < 5.0 * sigma);
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_pi() {
+    main().unwrap();
+}
@@ -0,0 +1,87 @@
+/*
+    spectrum_peak.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use gsl_rust::nonlinear_fit::nonlinear_fit;
+use gsl_rust::rng::{Rng, RngType};
+use gsl_rust::spectrum::Spectrum;
+use gsl_rust::util::linspace;
+use gsl_rust::*;
+
+/*
+
+    Fits a Gaussian peak on a linear background to a binned spectrum with counting errors.
+
+    Dividing both the model and the data by the errors of the bins turns the unweighted
+    least squares fit into a weighted one, with correctly scaled uncertainties.
+
+*/
+
+/// Expected counts per bin at `x`, for the peak amplitude, centroid and width,
+/// and the offset and slope of the background
+fn model(x: f64, [amplitude, centroid, width, offset, slope]: [f64; 5]) -> f64 {
+    amplitude * (-0.5 * ((x - centroid) / width).powi(2)).exp() + offset + slope * x
+}
+
+fn main() -> Result<()> {
+    disable_error_handler();
+
+    let truth = [500.0, 6.2, 0.4, 50.0, -2.0];
+    let mut rng = Rng::with_seed(RngType::Mt19937, 2);
+    let edges = linspace(0.0, 12.0, 241);
+    let (values, errors): (Vec<f64>, Vec<f64>) = edges
+        .windows(2)
+        .map(|w| {
+            let expected = model(0.5 * (w[0] + w[1]), truth);
+            let counts = (expected + rng.gaussian(expected.sqrt())).round().max(0.0);
+            (counts, counts.sqrt().max(1.0))
+        })
+        .unzip();
+    let spectrum = Spectrum::new(&edges, &values, &errors)?;
+
+    let centers = spectrum
+        .edges()
+        .windows(2)
+        .map(|w| 0.5 * (w[0] + w[1]))
+        .collect::<Vec<_>>();
+    let bins = (0..spectrum.n()).collect::<Vec<_>>();
+    let scaled = bins
+        .iter()
+        .map(|&i| spectrum.values()[i] / spectrum.errors()[i])
+        .collect::<Vec<_>>();
+
+    let fit = nonlinear_fit([400.0, 6.0, 0.5, 40.0, 0.0], &bins, &scaled, |&i, p| {
+        Ok(model(centers[i], p) / spectrum.errors()[i])
+    })?;
+
+    let chi2_per_dof = fit.final_residual_squared / (bins.len() - 5) as f64;
+    println!("χ² / dof = {:.3}", chi2_per_dof);
+    let [amplitude, centroid, width, ..] = fit.params;
+    println!("amplitude {:.1} ± {:.1}", amplitude, fit.uncertainty(0));
+    println!("centroid {:.4} ± {:.4}", centroid, fit.uncertainty(1));
+    println!("width {:.4} ± {:.4}", width.abs(), fit.uncertainty(2));
+
+    assert!(chi2_per_dof < 2.0);
+    assert!((centroid - truth[1]).abs() < 5.0 * fit.uncertainty(1));
+    assert!((width.abs() - truth[2]).abs() < 5.0 * fit.uncertainty(2));
+    Ok(())
+}
+
+#[test]
+fn spectrum_peak() {
+    main().unwrap();
+}
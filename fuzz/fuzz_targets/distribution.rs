@@ -0,0 +1,77 @@
+#![no_main]
+
+use gsl_rust::randist::*;
+use gsl_rust::rng::{Rng, RngType};
+use gsl_rust_fuzz::*;
+use libfuzzer_sys::arbitrary::{Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    gsl_rust::disable_error_handler();
+
+    let _ = run(&mut Unstructured::new(data));
+});
+
+fn run(u: &mut Unstructured<'_>) -> Result<()> {
+    let Extreme(a) = u.arbitrary()?;
+    let Extreme(b) = u.arbitrary()?;
+    let Extreme(c) = u.arbitrary()?;
+    let Extreme(d) = u.arbitrary()?;
+    let Extreme(x) = u.arbitrary()?;
+    let Extreme(p) = u.arbitrary()?;
+    let mut rng = Rng::with_seed(RngType::Mt19937, u.arbitrary()?);
+    let data = values(u)?;
+
+    if let Ok(stable) = Stable::new(a, b, c, d) {
+        let _ = consistent(|| stable.pdf(x));
+        let _ = consistent(|| stable.cdf(x));
+        let _ = consistent(|| stable.log_likelihood(&data));
+        stable.sample(&mut rng);
+    }
+    if let Ok(gev) = GeneralizedExtremeValue::new(a, b, c) {
+        consistent(|| gev.pdf(x));
+        consistent(|| gev.cdf(x));
+        let _ = consistent(|| gev.quantile(p));
+        gev.sample(&mut rng);
+    }
+    if let Ok(gpd) = GeneralizedPareto::new(a, b, c) {
+        consistent(|| gpd.pdf(x));
+        consistent(|| gpd.cdf(x));
+        let _ = consistent(|| gpd.quantile(p));
+        gpd.sample(&mut rng);
+    }
+    let _ = consistent(|| GeneralizedExtremeValue::fit_pwm(&data));
+    let _ = consistent(|| GeneralizedPareto::fit_pwm(&data, a));
+
+    if let Ok(chi2) = NoncentralChiSquared::new(a, b) {
+        let _ = consistent(|| chi2.pdf(x));
+        let _ = consistent(|| chi2.cdf(x));
+        let _ = consistent(|| chi2.quantile(p));
+    }
+    if let Ok(t) = NoncentralT::new(a, b) {
+        let _ = consistent(|| t.pdf(x));
+        let _ = consistent(|| t.cdf(x));
+        let _ = consistent(|| t.quantile(p));
+    }
+    if let Ok(f) = NoncentralF::new(a, b, c) {
+        let _ = consistent(|| f.pdf(x));
+        let _ = consistent(|| f.cdf(x));
+        let _ = consistent(|| f.quantile(p));
+    }
+
+    if let Ok(discrete) = DiscreteDistribution::new(&data) {
+        assert!(discrete.sample(&mut rng) < discrete.n());
+    }
+    if let Ok(fisher) = Fisher::new([a, b, c], d) {
+        let _ = consistent(|| fisher.pdf([x, p, 1.0]));
+        fisher.sample(&mut rng);
+    }
+    let directions = data
+        .chunks_exact(3)
+        .map(|v| [v[0], v[1], v[2]])
+        .collect::<Vec<_>>();
+    let _ = consistent(|| Fisher::fit(&directions));
+
+    consistent(|| bivariate_gaussian_pdf(x, p, a, b, c));
+    Ok(())
+}
@@ -0,0 +1,34 @@
+#![no_main]
+
+use gsl_rust::integrate::{self, GaussKronrodRule, IntegrationWorkspace};
+use gsl_rust::roots;
+use gsl_rust_fuzz::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: ([Extreme; 4], [Extreme; 4], u8)| {
+    gsl_rust::disable_error_handler();
+
+    // Polynomial integrand with extreme coefficients, limits and tolerances
+    let ([c0, c1, c2, c3], [a, b, epsabs, epsrel], size) = input;
+    let f = |x: f64| c0.0 + x * (c1.0 + x * (c2.0 + x * c3.0));
+    let (a, b, epsabs, epsrel) = (a.0, b.0, epsabs.0, epsrel.0);
+    let size = size as usize % MAX_LEN;
+
+    for rule in [GaussKronrodRule::Gauss15, GaussKronrodRule::Gauss61] {
+        check_error_estimate(&consistent(|| {
+            integrate::qag_ext(size, a, b, epsabs, epsrel, rule, f)
+        }));
+    }
+    check_error_estimate(&consistent(|| {
+        integrate::qagiu_ext(size, a, epsabs, epsrel, f)
+    }));
+    let _ = consistent(|| integrate::qng(a, b, epsabs, epsrel, f));
+    let _ = consistent(|| integrate::cquad(a, b, f));
+    let _ = consistent(|| integrate::romberg(a, b, f));
+    if let Ok(mut workspace) = IntegrationWorkspace::new(size) {
+        check_error_estimate(&consistent(|| workspace.qags(a, b, epsabs, epsrel, f)));
+        check_error_estimate(&consistent(|| workspace.qagi(epsabs, epsrel, f)));
+    }
+
+    let _ = consistent(|| roots::find_root_ext(size, f, a, b, epsabs, epsrel));
+});
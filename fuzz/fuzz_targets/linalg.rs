@@ -0,0 +1,35 @@
+#![no_main]
+
+use gsl_rust::{eigen, linalg};
+use gsl_rust_fuzz::*;
+use libfuzzer_sys::arbitrary::{Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    gsl_rust::disable_error_handler();
+
+    let _ = run(&mut Unstructured::new(data));
+});
+
+fn run(u: &mut Unstructured<'_>) -> Result<()> {
+    // Dimensions that don't match the data have to give errors as well
+    let n = u.int_in_range(0..=8)?;
+    let a = values(u)?;
+    let b = values(u)?;
+
+    consistent(|| linalg::norm_1(n, n, &a));
+    consistent(|| linalg::norm_inf(n, n, &a));
+    consistent(|| linalg::norm_frobenius(n, n, &a));
+    let _ = consistent(|| linalg::solve(n, &a, &b));
+    let _ = consistent(|| linalg::cond_1_estimate(n, &a));
+    let _ = consistent(|| eigen::nonsymmetric(n, &a));
+
+    // Symmetrized
+    if a.len() == n * n {
+        let symmetric = (0..n * n)
+            .map(|k| 0.5 * (a[k] + a[k % n * n + k / n]))
+            .collect::<Vec<_>>();
+        let _ = consistent(|| eigen::symmetric(n, &symmetric));
+    }
+    Ok(())
+}
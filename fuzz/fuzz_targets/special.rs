@@ -0,0 +1,57 @@
+#![no_main]
+
+use gsl_rust::sf::{self, bessel};
+use gsl_rust_fuzz::*;
+use libfuzzer_sys::fuzz_target;
+use num_complex::Complex64;
+
+fuzz_target!(|input: (Extreme, Extreme, Extreme, i32, u16)| {
+    gsl_rust::disable_error_handler();
+
+    let (Extreme(x), Extreme(a), Extreme(b), n, s) = input;
+    let results = [
+        consistent(|| sf::gamma(x)),
+        consistent(|| sf::ln_gamma(x)),
+        consistent(|| sf::gamma_inc_p(a, x)),
+        consistent(|| sf::gamma_inc_q(a, x)),
+        consistent(|| sf::beta_inc(a, b, x)),
+        consistent(|| sf::erfc(x)),
+        consistent(|| sf::hurwitz_zeta(a, x)),
+        consistent(|| bessel::j_n(n, x)),
+        consistent(|| bessel::y_n(n, x)),
+        consistent(|| bessel::i_n(n, x)),
+        consistent(|| bessel::i_n_scaled(n, x)),
+        consistent(|| bessel::k_n(n, x)),
+        consistent(|| bessel::k_n_scaled(n, x)),
+        consistent(|| bessel::i_nu(a, x)),
+        consistent(|| bessel::i_nu_scaled(a, x)),
+        consistent(|| bessel::j_l(n, x)),
+        consistent(|| bessel::y_l(n, x)),
+        consistent(|| bessel::i_l_scaled(n, x)),
+        consistent(|| bessel::zero_j0(s as u32)),
+        consistent(|| bessel::zero_j1(s as u32)),
+        consistent(|| bessel::zero_j_nu(a, s as u32)),
+    ];
+    for result in &results {
+        check_error_estimate(result);
+    }
+
+    let z = Complex64::new(a, b);
+    let _ = consistent(|| sf::gamma_complex(z));
+    let _ = consistent(|| sf::ln_gamma_complex(z));
+
+    // The recurrences take time linear in the orders, which are kept small
+    let n_min = (n as u32 % MAX_LEN as u32) as usize;
+    let n_max = s as usize % MAX_LEN;
+    let _ = consistent(|| bessel::j_n_array(n_min, n_max, x));
+    let _ = consistent(|| bessel::y_n_array(n_min, n_max, x));
+    let _ = consistent(|| bessel::i_n_array(n_min, n_max, x));
+    let _ = consistent(|| bessel::i_n_scaled_array(n_min, n_max, x));
+    let _ = consistent(|| bessel::k_n_array(n_min, n_max, x));
+    let _ = consistent(|| bessel::k_n_scaled_array(n_min, n_max, x));
+    let _ = consistent(|| bessel::j_l_array(n_max, x));
+    let _ = consistent(|| bessel::j_l_steed_array(n_max, x));
+    let _ = consistent(|| bessel::y_l_array(n_max, x));
+    let _ = consistent(|| bessel::i_l_scaled_array(n_max, x));
+    let _ = consistent(|| bessel::zeros_j_nu(a, n_max));
+});
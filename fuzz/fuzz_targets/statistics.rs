@@ -0,0 +1,55 @@
+#![no_main]
+
+use gsl_rust::interp::{self, Algorithm, Derivative};
+use gsl_rust::{sort, stats};
+use gsl_rust_fuzz::*;
+use libfuzzer_sys::arbitrary::{Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    gsl_rust::disable_error_handler();
+
+    let _ = run(&mut Unstructured::new(data));
+});
+
+fn run(u: &mut Unstructured<'_>) -> Result<()> {
+    let x = values(u)?;
+    let y = values(u)?;
+    let p = u.arbitrary::<Extreme>()?.0;
+    let k = u.int_in_range(0..=MAX_LEN)?;
+
+    consistent(|| stats::mean(&x));
+    // The Rust variance normalizes by n - 1, which underflows without data
+    if !x.is_empty() {
+        consistent(|| stats::variance(&x));
+    }
+    consistent(|| stats::sd(&x));
+    consistent(|| stats::skew(&x));
+    consistent(|| stats::kurtosis(&x));
+    let _ = consistent(|| stats::lag1_autocorrelation(&x));
+    let _ = consistent(|| stats::min(&x));
+    let _ = consistent(|| stats::max_index(&x));
+    let _ = consistent(|| stats::median(&x));
+    let _ = consistent(|| stats::mad(&x));
+    let _ = consistent(|| stats::covariance(&x, &y));
+    let _ = consistent(|| stats::correlation(&x, &y));
+    let _ = consistent(|| stats::spearman(&x, &y));
+    let _ = consistent(|| stats::weighted_mean(&x, &y));
+    let _ = consistent(|| stats::weighted_variance(&x, &y));
+
+    let _ = consistent(|| sort::select(&x, k));
+    let _ = consistent(|| sort::smallest(&x, k));
+    let _ = consistent(|| sort::largest_index(&x, k));
+    let mut sorted = x.clone();
+    sort::sort(&mut sorted);
+    let _ = consistent(|| stats::median_sorted(&sorted));
+    let _ = consistent(|| stats::quantile_sorted(&sorted, p));
+    let _ = consistent(|| stats::trimmed_mean_sorted(&sorted, p));
+
+    for algorithm in [Algorithm::Linear, Algorithm::Steffen] {
+        let _ = consistent(|| {
+            interp::interpolate(algorithm, Derivative::First, x.clone(), y.clone(), &[p])
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+/*
+    lib.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use gsl_rust::ValWithError;
+use std::fmt::Debug;
+
+/*
+
+    Shared inputs and checks of the fuzz targets, run with `cargo fuzz run <target>` from this directory.
+
+    Every target disables the GSL error handler first, like users of the crate have to,
+    so errors inside GSL have to come back as `Err` instead of aborting.
+    A crash, abort or timeout is a bug in the wrapper.
+
+    `Extreme` picks boundary values half of the time, since uniformly random bits rarely hit them.
+    Sizes are kept small, so the fuzzer spends its time on values instead of allocations.
+
+*/
+
+/// Largest length of the generated slices
+pub const MAX_LEN: usize = 64;
+
+const BOUNDARY: [f64; 16] = [
+    0.0,
+    -0.0,
+    1.0,
+    -1.0,
+    0.5,
+    f64::NAN,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::MIN_POSITIVE,
+    -f64::MIN_POSITIVE,
+    // Smallest subnormal
+    5.0e-324,
+    f64::MAX,
+    f64::MIN,
+    f64::EPSILON,
+    1.0e300,
+    -1.0e300,
+];
+
+/// Value that is often NaN, infinite, subnormal, zero or huge
+#[derive(Copy, Clone, Debug)]
+pub struct Extreme(pub f64);
+
+impl<'a> Arbitrary<'a> for Extreme {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.arbitrary()? {
+            Ok(Extreme(*u.choose(&BOUNDARY)?))
+        } else {
+            Ok(Extreme(f64::from_bits(u.arbitrary()?)))
+        }
+    }
+}
+
+/// Values of at most `MAX_LEN` extreme values
+pub fn values(u: &mut Unstructured<'_>) -> Result<Vec<f64>> {
+    let len = u.int_in_range(0..=MAX_LEN)?;
+    (0..len).map(|_| Ok(Extreme::arbitrary(u)?.0)).collect()
+}
+
+/// Evaluates `f` twice, which has to give the same result, including the same error
+pub fn consistent<T: Debug, F: FnMut() -> T>(mut f: F) -> T {
+    let first = f();
+    let second = f();
+    // Debug output compares NaN equal to itself
+    assert_eq!(format!("{:?}", first), format!("{:?}", second));
+    first
+}
+
+/// Checks that the error estimate of a successful evaluation isn't negative
+pub fn check_error_estimate(result: &gsl_rust::Result<ValWithError<f64>>) {
+    if let Ok(result) = result {
+        assert!(
+            result.err >= 0.0 || result.err.is_nan(),
+            "negative error estimate {:?}",
+            result
+        );
+    }
+}
@@ -0,0 +1,75 @@
+/*
+    gsl_rust.h
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+    C interface of gsl_rust, built with the `capi` feature:
+
+        cargo rustc --release --features capi --crate-type cdylib
+
+    Every function returns a GSL status code, 0 on success or one of the error codes of gsl_errno.h,
+    and only writes its outputs on success. The aborting GSL error handler is turned off on first use.
+*/
+
+#ifndef GSL_RUST_H
+#define GSL_RUST_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Largest amount of parameters of gsl_rust_nonlinear_fit */
+#define GSL_RUST_MAX_PARAMS 16
+
+/* Fit of the straight line y = c0 + c1 x */
+typedef struct {
+    double c0;
+    double c1;
+    double cov00;
+    double cov01;
+    double cov11;
+    /* Sum of squared residuals, weighted for gsl_rust_fit_line_weighted */
+    double chisq;
+} gsl_rust_line_fit;
+
+/* f(x, data) */
+typedef double (*gsl_rust_function)(double x, void *data);
+
+/* f(x, params, data) */
+typedef double (*gsl_rust_model)(double x, const double *params, void *data);
+
+int gsl_rust_fit_line(const double *x, const double *y, size_t n, gsl_rust_line_fit *fit);
+
+int gsl_rust_fit_line_weighted(const double *x, const double *w, const double *y, size_t n,
+                               gsl_rust_line_fit *fit);
+
+/* Fits y = f(x, params) starting at the p parameters in params, which hold the fitted parameters
+   on success. covariance (p * p doubles, row major) and chisq are optional and may be NULL. */
+int gsl_rust_nonlinear_fit(gsl_rust_model f, void *data, const double *x, const double *y, size_t n,
+                           size_t p, double *params, double *covariance, double *chisq);
+
+/* Integral of f from a to b, either of which may be infinite. abserr may be NULL. */
+int gsl_rust_integrate(gsl_rust_function f, void *data, double a, double b, double epsabs,
+                       double epsrel, double *result, double *abserr);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,227 @@
+/*
+    binary.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::histogram::Histogram;
+use crate::*;
+use std::io::{self, Read, Write};
+
+/*
+
+    Readers and writers for the binary files of `gsl_vector_fwrite`, `gsl_matrix_fwrite` and
+    `gsl_histogram_fwrite`, so C programs and this crate can exchange data through files.
+
+    These files are the raw doubles without any header, so the sizes have to be known when reading:
+    a vector is its `n` elements, a matrix its `m × n` elements in row major order,
+    and a histogram its `n + 1` ranges followed by its `n` bins.
+
+    GSL writes the doubles in the byte order of the machine. `ByteOrder::Native` reads files written
+    on the same kind of machine, the other orders read files moved between machines.
+
+    A file that ends early fails with `GSLError::EOF`, other I/O errors with `GSLError::Failed`.
+    Data after the expected doubles is left unread, so several objects can be read from one file.
+    The text files of `gsl_vector_fprintf` and friends are in `text`.
+
+*/
+
+/// Byte order of the doubles in a file
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Byte order of this machine, which is what GSL writes
+    #[default]
+    Native,
+    LittleEndian,
+    BigEndian,
+}
+
+/// Reads the `n` elements of a vector written by `gsl_vector_fwrite`
+pub fn read_vector<R: Read>(reader: R, n: usize, order: ByteOrder) -> Result<Box<[f64]>> {
+    read_doubles(reader, n, order)
+}
+
+/// Reads the row major `m × n` elements of a matrix written by `gsl_matrix_fwrite`
+pub fn read_matrix<R: Read>(reader: R, m: usize, n: usize, order: ByteOrder) -> Result<Box<[f64]>> {
+    let len = m.checked_mul(n).ok_or(GSLError::Invalid)?;
+    read_doubles(reader, len, order)
+}
+
+/// Reads a histogram with `n` bins written by `gsl_histogram_fwrite`.
+/// Fails with `GSLError::Domain` if the ranges read aren't strictly increasing.
+pub fn read_histogram<R: Read>(mut reader: R, n: usize, order: ByteOrder) -> Result<Histogram> {
+    if n == 0 {
+        return Err(GSLError::Invalid);
+    }
+    let ranges = read_doubles(&mut reader, n + 1, order)?;
+    let bins = read_doubles(&mut reader, n, order)?;
+
+    let mut histogram = Histogram::with_ranges(&ranges)?;
+    for (&lower, &bin) in ranges.iter().zip(bins.iter()) {
+        // The lower end of a bin lies inside it
+        histogram.accumulate(lower, bin)?;
+    }
+    Ok(histogram)
+}
+
+/// Writes a vector like `gsl_vector_fwrite`
+pub fn write_vector<W: Write>(writer: W, x: &[f64], order: ByteOrder) -> Result<()> {
+    write_doubles(writer, x, order)
+}
+
+/// Writes the row major `m × n` matrix `a` like `gsl_matrix_fwrite`
+pub fn write_matrix<W: Write>(
+    writer: W,
+    m: usize,
+    n: usize,
+    a: &[f64],
+    order: ByteOrder,
+) -> Result<()> {
+    if m.checked_mul(n) != Some(a.len()) {
+        return Err(GSLError::BadLength);
+    }
+    write_doubles(writer, a, order)
+}
+
+/// Writes a histogram like `gsl_histogram_fwrite`
+pub fn write_histogram<W: Write>(
+    mut writer: W,
+    histogram: &Histogram,
+    order: ByteOrder,
+) -> Result<()> {
+    write_doubles(&mut writer, &histogram.ranges(), order)?;
+    write_doubles(&mut writer, &histogram.bins(), order)
+}
+
+fn read_doubles<R: Read>(mut reader: R, n: usize, order: ByteOrder) -> Result<Box<[f64]>> {
+    let len = n
+        .checked_mul(std::mem::size_of::<f64>())
+        .ok_or(GSLError::Invalid)?;
+    let mut bytes = Vec::new();
+    reader
+        .by_ref()
+        .take(len as u64)
+        .read_to_end(&mut bytes)
+        .map_err(io_error)?;
+    if bytes.len() < len {
+        return Err(GSLError::EOF);
+    }
+
+    Ok(bytes
+        .chunks_exact(8)
+        .map(|chunk| {
+            let bytes = chunk.try_into().unwrap();
+            match order {
+                ByteOrder::Native => f64::from_ne_bytes(bytes),
+                ByteOrder::LittleEndian => f64::from_le_bytes(bytes),
+                ByteOrder::BigEndian => f64::from_be_bytes(bytes),
+            }
+        })
+        .collect())
+}
+
+fn write_doubles<W: Write>(mut writer: W, x: &[f64], order: ByteOrder) -> Result<()> {
+    let bytes = x
+        .iter()
+        .flat_map(|x| match order {
+            ByteOrder::Native => x.to_ne_bytes(),
+            ByteOrder::LittleEndian => x.to_le_bytes(),
+            ByteOrder::BigEndian => x.to_be_bytes(),
+        })
+        .collect::<Vec<_>>();
+    writer.write_all(&bytes).map_err(io_error)?;
+    writer.flush().map_err(io_error)
+}
+
+pub(crate) fn io_error(e: io::Error) -> GSLError {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => GSLError::EOF,
+        _ => GSLError::Failed,
+    }
+}
+
+#[test]
+fn test_binary() {
+    disable_error_handler();
+
+    // 1.0 and -2.5 as written by GSL on a little and a big endian machine
+    let le = [0, 0, 0, 0, 0, 0, 0xf0, 0x3f, 0, 0, 0, 0, 0, 0, 0x04, 0xc0];
+    let be = [0x3f, 0xf0, 0, 0, 0, 0, 0, 0, 0xc0, 0x04, 0, 0, 0, 0, 0, 0];
+    assert_eq!(
+        *read_vector(&le[..], 2, ByteOrder::LittleEndian).unwrap(),
+        [1.0, -2.5]
+    );
+    assert_eq!(
+        *read_vector(&be[..], 2, ByteOrder::BigEndian).unwrap(),
+        [1.0, -2.5]
+    );
+    let native = if cfg!(target_endian = "little") {
+        le
+    } else {
+        be
+    };
+    assert_eq!(
+        *read_vector(&native[..], 2, ByteOrder::Native).unwrap(),
+        [1.0, -2.5]
+    );
+
+    // Round trips, with several objects in one file
+    let a = [1.0, 2.0, 3.0, 4.0, 5.0, f64::NAN];
+    let mut h = Histogram::with_ranges(&[0.0, 1.0, 10.0]).unwrap();
+    h.accumulate(0.5, 3.0).unwrap();
+    h.accumulate(5.0, 0.25).unwrap();
+    for order in [
+        ByteOrder::Native,
+        ByteOrder::LittleEndian,
+        ByteOrder::BigEndian,
+    ] {
+        let mut file = Vec::new();
+        write_matrix(&mut file, 2, 3, &a, order).unwrap();
+        write_histogram(&mut file, &h, order).unwrap();
+        assert_eq!(file.len(), 8 * (6 + 3 + 2));
+
+        let mut reader = &file[..];
+        let b = read_matrix(&mut reader, 2, 3, order).unwrap();
+        assert_eq!(b[..5], a[..5]);
+        assert!(b[5].is_nan());
+        let g = read_histogram(&mut reader, 2, order).unwrap();
+        assert_eq!(g.ranges(), h.ranges());
+        assert_eq!(g.bins(), h.bins());
+        assert!(reader.is_empty());
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // File too short
+    let file = [0u8; 20];
+    assert_eq!(
+        read_vector(&file[..], 3, ByteOrder::Native).unwrap_err(),
+        GSLError::EOF
+    );
+    read_histogram(&file[..], 1, ByteOrder::Native).unwrap_err();
+
+    // Ranges not increasing
+    let mut file = Vec::new();
+    write_vector(&mut file, &[1.0, 0.0, 2.0], ByteOrder::Native).unwrap();
+    read_histogram(&file[..], 1, ByteOrder::Native).unwrap_err();
+
+    // Bad sizes
+    read_histogram(&file[..], 0, ByteOrder::Native).unwrap_err();
+    read_matrix(&file[..], usize::MAX, 2, ByteOrder::Native).unwrap_err();
+    write_matrix(Vec::new(), 2, 2, &[1.0; 3], ByteOrder::Native).unwrap_err();
+}
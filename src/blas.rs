@@ -0,0 +1,176 @@
+/*
+    blas.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::*;
+
+/*
+
+    Level 1 BLAS operations on slices, the building blocks of iterative solvers and optimizers,
+    e.g. the updates of the conjugate gradient method.
+
+    The updates work in place and the reductions make a single pass, so none of them allocate.
+    Combined updates like `axpby` read and write every element once, instead of once per operation.
+    Operations on two slices fail with `GSLError::BadLength` if their lengths differ.
+
+    `nrm2` and `dist2` scale the sum of squares like the reference BLAS,
+    so they don't overflow or underflow for values near the limits of `f64`.
+
+*/
+
+/// `x · y`
+pub fn dot(x: &[f64], y: &[f64]) -> Result<f64> {
+    check_lengths(x, y)?;
+    Ok(x.iter().zip(y).map(|(x, y)| x * y).sum())
+}
+
+/// Euclidean norm `|x|`
+pub fn nrm2(x: &[f64]) -> f64 {
+    scaled_norm(x.iter().copied())
+}
+
+/// Euclidean distance `|x - y|`
+pub fn dist2(x: &[f64], y: &[f64]) -> Result<f64> {
+    check_lengths(x, y)?;
+    Ok(scaled_norm(x.iter().zip(y).map(|(x, y)| x - y)))
+}
+
+/// Sum of the absolute values
+pub fn asum(x: &[f64]) -> f64 {
+    x.iter().map(|x| x.abs()).sum()
+}
+
+/// `x = alpha x`
+pub fn scale(alpha: f64, x: &mut [f64]) {
+    x.iter_mut().for_each(|x| *x *= alpha);
+}
+
+/// `y = alpha x + y`
+pub fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) -> Result<()> {
+    zip_map(y, x, |y, x| alpha * x + y)
+}
+
+/// `y = alpha x + beta y`
+pub fn axpby(alpha: f64, x: &[f64], beta: f64, y: &mut [f64]) -> Result<()> {
+    zip_map(y, x, |y, x| alpha * x + beta * y)
+}
+
+/// `x = alpha x + y`, e.g. the update of the search direction in the conjugate gradient method
+pub fn scale_add(alpha: f64, x: &mut [f64], y: &[f64]) -> Result<()> {
+    zip_map(x, y, |x, y| alpha * x + y)
+}
+
+/// `x[i] = f(x[i])`
+pub fn map<F: FnMut(f64) -> f64>(x: &mut [f64], mut f: F) {
+    x.iter_mut().for_each(|x| *x = f(*x));
+}
+
+/// `x[i] = f(x[i], y[i])`
+pub fn zip_map<F: FnMut(f64, f64) -> f64>(x: &mut [f64], y: &[f64], mut f: F) -> Result<()> {
+    check_lengths(x, y)?;
+    x.iter_mut().zip(y).for_each(|(x, &y)| *x = f(*x, y));
+    Ok(())
+}
+
+fn check_lengths(x: &[f64], y: &[f64]) -> Result<()> {
+    if x.len() == y.len() {
+        Ok(())
+    } else {
+        Err(GSLError::BadLength)
+    }
+}
+
+/// `sqrt(sum(x²))` as `scale sqrt(ssq)`, with the largest `|x|` as `scale`
+fn scaled_norm<I: Iterator<Item = f64>>(x: I) -> f64 {
+    let mut scale = 0.0;
+    let mut ssq = 1.0;
+    let mut infinite = false;
+    for x in x {
+        let a = x.abs();
+        if a.is_nan() {
+            return f64::NAN;
+        } else if a == f64::INFINITY {
+            infinite = true;
+        } else if a > scale {
+            ssq = 1.0 + ssq * (scale / a).powi(2);
+            scale = a;
+        } else if a > 0.0 {
+            ssq += (a / scale).powi(2);
+        }
+    }
+    if infinite {
+        f64::INFINITY
+    } else {
+        scale * ssq.sqrt()
+    }
+}
+
+#[test]
+fn test_blas() {
+    use approx::assert_relative_eq;
+
+    let x = [1.0, -2.0, 3.0];
+    let mut y = [4.0, 5.0, -6.0];
+    assert_eq!(dot(&x, &y).unwrap(), -24.0);
+    assert_relative_eq!(nrm2(&x), 14.0f64.sqrt(), max_relative = 1.0e-15);
+    assert_relative_eq!(
+        dist2(&x, &y).unwrap(),
+        139.0f64.sqrt(),
+        max_relative = 1.0e-15
+    );
+    assert_eq!(asum(&x), 6.0);
+
+    axpy(2.0, &x, &mut y).unwrap();
+    assert_eq!(y, [6.0, 1.0, 0.0]);
+    axpby(1.0, &x, -1.0, &mut y).unwrap();
+    assert_eq!(y, [-5.0, -3.0, 3.0]);
+    scale_add(2.0, &mut y, &x).unwrap();
+    assert_eq!(y, [-9.0, -8.0, 9.0]);
+    scale(0.5, &mut y);
+    assert_eq!(y, [-4.5, -4.0, 4.5]);
+    map(&mut y, f64::abs);
+    assert_eq!(y, [4.5, 4.0, 4.5]);
+    zip_map(&mut y, &x, f64::max).unwrap();
+    assert_eq!(y, [4.5, 4.0, 4.5]);
+
+    // No overflow or underflow in between
+    assert_relative_eq!(nrm2(&[3.0e200, 4.0e200]), 5.0e200, max_relative = 1.0e-15);
+    assert_relative_eq!(
+        nrm2(&[3.0e-200, 4.0e-200]),
+        5.0e-200,
+        max_relative = 1.0e-15
+    );
+    assert_eq!(nrm2(&[]), 0.0);
+    assert_eq!(
+        nrm2(&[f64::INFINITY, 1.0, f64::NEG_INFINITY]),
+        f64::INFINITY
+    );
+    assert!(nrm2(&[f64::INFINITY, f64::NAN]).is_nan());
+}
+
+#[test]
+fn test_invalid_params() {
+    // Length mismatch
+    let mut y = [1.0, 2.0];
+    dot(&[1.0], &y).unwrap_err();
+    dist2(&[1.0], &y).unwrap_err();
+    axpy(1.0, &[1.0], &mut y).unwrap_err();
+    axpby(1.0, &[1.0], 1.0, &mut y).unwrap_err();
+    scale_add(1.0, &mut y, &[1.0]).unwrap_err();
+    zip_map(&mut y, &[1.0, 2.0, 3.0], |x, _| x).unwrap_err();
+    assert_eq!(y, [1.0, 2.0]);
+}
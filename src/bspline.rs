@@ -17,12 +17,167 @@
 */
 
 use crate::bindings::*;
+use crate::memory::Footprint;
 use crate::*;
-use drop_guard::guard;
 use linear_fit::*;
 use std::fmt;
 
-/// `k + 1` is equal to the spline order
+/// Basis of B-splines of order `k` (`k = 4` gives cubic splines) over a set of breakpoints
+pub struct BSplineBasis {
+    workspace: *mut gsl_bspline_workspace,
+    footprint: Footprint,
+}
+
+impl BSplineBasis {
+    /// `nbreak` uniformly spaced breakpoints on `[a, b]`
+    pub fn uniform(k: usize, a: f64, b: f64, nbreak: usize) -> Result<Self> {
+        unsafe {
+            if k == 0 {
+                return Err(GSLError::Invalid);
+            }
+            if nbreak < 2 {
+                return Err(GSLError::Invalid);
+            }
+            if b <= a {
+                return Err(GSLError::Domain);
+            }
+
+            let basis = Self::alloc(k, nbreak);
+
+            // The domain is equal to [x_k, x_{n-k}]
+            // Thus if we want to pass through a and b,
+            // we need to have k repetitions of the first and last knot.
+            // knots(1:k) = a
+            // knots(k+1:k+l-1) = a + i*delta, i = 1 .. l - 1
+            // knots(n+1:n+k) = b
+            GSLError::from_raw(gsl_bspline_knots_uniform(a, b, basis.workspace))?;
+
+            Ok(basis)
+        }
+    }
+
+    /// Custom breakpoints, which have to be finite and strictly increasing.
+    /// The first and last breakpoint are repeated `k` times in the knot vector.
+    pub fn with_breakpoints(k: usize, breakpoints: &[f64]) -> Result<Self> {
+        unsafe {
+            if k == 0 {
+                return Err(GSLError::Invalid);
+            }
+            if breakpoints.len() < 2 {
+                return Err(GSLError::Invalid);
+            }
+            if breakpoints.iter().any(|b| !b.is_finite())
+                || breakpoints.windows(2).any(|w| w[1] <= w[0])
+            {
+                return Err(GSLError::Domain);
+            }
+
+            let basis = Self::alloc(k, breakpoints.len());
+            let breakpoints = gsl_vector::from(breakpoints);
+            GSLError::from_raw(gsl_bspline_knots(&breakpoints, basis.workspace))?;
+
+            Ok(basis)
+        }
+    }
+
+    unsafe fn alloc(k: usize, nbreak: usize) -> Self {
+        let workspace = gsl_bspline_alloc(k as u64, nbreak as u64);
+        assert!(!workspace.is_null());
+
+        // The knots, and the buffers of one evaluation of k basis functions and their derivatives
+        let knots = nbreak + 2 * k - 2;
+        let footprint = Footprint::of::<gsl_bspline_workspace>(knots + 3 * k + k * (2 * k + 1));
+
+        BSplineBasis {
+            workspace,
+            footprint,
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        unsafe { gsl_bspline_order(self.workspace) as usize }
+    }
+
+    pub fn nbreak(&self) -> usize {
+        unsafe { gsl_bspline_nbreak(self.workspace) as usize }
+    }
+
+    /// Number of basis functions, `nbreak + k - 2`
+    pub fn ncoeffs(&self) -> usize {
+        unsafe { gsl_bspline_ncoeffs(self.workspace) as usize }
+    }
+
+    /// Approximate memory held by the GSL workspace, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    pub fn breakpoints(&self) -> Box<[f64]> {
+        unsafe {
+            (0..self.nbreak())
+                .map(|i| gsl_bspline_breakpoint(i as u64, self.workspace))
+                .collect()
+        }
+    }
+
+    /// Greville abscissae, the averages of `k - 1` consecutive interior knots.
+    /// These are the natural interpolation sites, one for every basis function.
+    pub fn greville_abscissae(&self) -> Box<[f64]> {
+        unsafe {
+            (0..self.ncoeffs())
+                .map(|i| gsl_bspline_greville_abscissa(i as u64, self.workspace))
+                .collect()
+        }
+    }
+
+    /// Values of all `ncoeffs` basis functions at `x`
+    pub fn eval(&mut self, x: f64) -> Result<Box<[f64]>> {
+        unsafe {
+            let mut b = Vector::zeroes(self.ncoeffs());
+            GSLError::from_raw(gsl_bspline_eval(x, b.as_gsl_mut(), self.workspace))?;
+            Ok(b.to_boxed_slice())
+        }
+    }
+
+    /// Values of all basis functions and their derivatives up to order `nderiv` at `x`,
+    /// as a row-major `ncoeffs x (nderiv + 1)` matrix.
+    /// Column `j` contains the `j`th derivative.
+    pub fn deriv_eval(&mut self, x: f64, nderiv: usize) -> Result<Box<[f64]>> {
+        unsafe {
+            let mut db = Matrix::zeroes(self.ncoeffs(), nderiv + 1);
+            GSLError::from_raw(gsl_bspline_deriv_eval(
+                x,
+                nderiv as u64,
+                db.as_gsl_mut(),
+                self.workspace,
+            ))?;
+            Ok(db.to_boxed_slice())
+        }
+    }
+}
+
+impl fmt::Debug for BSplineBasis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BSplineBasis")
+            .field("order", &self.order())
+            .field("breakpoints", &self.breakpoints())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for BSplineBasis {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_bspline_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe. Not Sync, since `BSpline::eval` uses the workspace as scratch memory
+unsafe impl Send for BSplineBasis {}
+
+/// Least squares fit of B-splines of order `k` (`k = 4` gives cubic splines)
+/// with `nbreak` uniformly spaced breakpoints on `[a, b]`
 pub fn fit_bspline(
     k: usize,
     a: f64,
@@ -31,32 +186,14 @@ pub fn fit_bspline(
     x: &[f64],
     y: &[f64],
 ) -> Result<BSpline> {
-    unsafe {
-        if k == 0 {
-            return Err(GSLError::Invalid);
-        }
-        if nbreak < 2 {
-            return Err(GSLError::Invalid);
-        }
-        if b <= a {
-            return Err(GSLError::Domain);
-        }
-
-        // Allocate workspace
-        let workspace = guard(gsl_bspline_alloc(k as u64, nbreak as u64), |workspace| {
-            gsl_bspline_free(workspace);
-        });
-        assert!(!workspace.is_null());
-        let ncoeffs = gsl_bspline_ncoeffs(*workspace) as usize;
+    fit_bspline_basis(BSplineBasis::uniform(k, a, b, nbreak)?, x, y)
+}
 
-        // Calculate knots
-        // The domain is equal to [x_k, x_{n-k}]
-        // Thus if we want to pass through a and b,
-        // we need to have k repetitions of the first and last knot.
-        // knots(1:k) = a
-        // knots(k+1:k+l-1) = a + i*delta, i = 1 .. l - 1
-        // knots(n+1:n+k) = b
-        GSLError::from_raw(gsl_bspline_knots_uniform(a, b, *workspace))?;
+/// Least squares smoothing fit of `y(x)` as a linear combination of the functions in `basis`.
+/// All `x` have to lie between the first and last breakpoint.
+pub fn fit_bspline_basis(basis: BSplineBasis, x: &[f64], y: &[f64]) -> Result<BSpline> {
+    unsafe {
+        let ncoeffs = basis.ncoeffs();
 
         // Cache vector for basis spline values
         let mut b = Vector::zeroes(ncoeffs);
@@ -64,24 +201,18 @@ pub fn fit_bspline(
         // Build the linear system and fit it
         let fit = linear_fit(ncoeffs, x, y, |&x, p| {
             // Evaluate all basis splines at this position and store them in b
-            GSLError::from_raw(gsl_bspline_eval(x, b.as_gsl_mut(), *workspace))?;
+            GSLError::from_raw(gsl_bspline_eval(x, b.as_gsl_mut(), basis.workspace))?;
             p.copy_from_slice(&b);
             Ok(())
         })?;
 
-        let raw = *workspace;
-        std::mem::forget(workspace);
-
-        Ok(BSpline {
-            fit,
-            workspace: raw,
-        })
+        Ok(BSpline { fit, basis })
     }
 }
 
 pub struct BSpline {
     pub fit: FitResult,
-    workspace: *mut gsl_bspline_workspace,
+    pub(crate) basis: BSplineBasis,
 }
 
 impl BSpline {
@@ -89,9 +220,17 @@ impl BSpline {
         fit_bspline(k, a, b, nbreak, x, y)
     }
 
+    pub fn fit_basis(basis: BSplineBasis, x: &[f64], y: &[f64]) -> Result<Self> {
+        fit_bspline_basis(basis, x, y)
+    }
+
+    pub fn basis(&self) -> &BSplineBasis {
+        &self.basis
+    }
+
     pub fn eval<const DV: usize>(&self, x: &[f64]) -> Result<BSplineEvaluation<DV>> {
         unsafe {
-            let ncoeffs = gsl_bspline_ncoeffs(self.workspace) as usize;
+            let ncoeffs = self.basis.ncoeffs();
 
             let mut db = Matrix::zeroes(ncoeffs, DV + 1);
             let mut b = Vector::zeroes(ncoeffs);
@@ -110,7 +249,7 @@ impl BSpline {
                     x,
                     DV as u64,
                     db.as_gsl_mut(),
-                    self.workspace,
+                    self.basis.workspace,
                 ))?;
 
                 // 0th order derivative is special cased for convenience
@@ -162,18 +301,6 @@ impl fmt::Debug for BSpline {
     }
 }
 
-impl Drop for BSpline {
-    fn drop(&mut self) {
-        unsafe {
-            gsl_bspline_free(self.workspace);
-        }
-    }
-}
-
-// GSL is thread safe
-unsafe impl Send for BSpline {}
-unsafe impl Sync for BSpline {}
-
 #[test]
 fn test_bspline_fit_1() {
     disable_error_handler();
@@ -215,6 +342,70 @@ fn test_bspline_fit_1() {
     }
 }
 
+#[test]
+fn test_bspline_basis() {
+    disable_error_handler();
+
+    let mut basis = BSplineBasis::with_breakpoints(4, &[0.0, 0.5, 1.0, 3.0]).unwrap();
+    assert_eq!(basis.order(), 4);
+    assert_eq!(basis.nbreak(), 4);
+    assert_eq!(basis.ncoeffs(), 6);
+    assert_eq!(&*basis.breakpoints(), &[0.0, 0.5, 1.0, 3.0]);
+
+    // Greville abscissae of a clamped knot vector start and end at the boundaries
+    let greville = basis.greville_abscissae();
+    assert_eq!(greville.len(), 6);
+    approx::assert_abs_diff_eq!(greville[0], 0.0);
+    approx::assert_abs_diff_eq!(greville[1], 0.5 / 3.0, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(greville[5], 3.0);
+
+    // Partition of unity, so the derivatives sum to zero
+    for x in [0.0, 0.3, 0.75, 2.0, 3.0] {
+        let b = basis.eval(x).unwrap();
+        approx::assert_abs_diff_eq!(b.iter().sum::<f64>(), 1.0, epsilon = 1.0e-14);
+
+        let db = basis.deriv_eval(x, 2).unwrap();
+        for j in 0..3 {
+            let sum = (0..6).map(|i| db[i * 3 + j]).sum::<f64>();
+            approx::assert_abs_diff_eq!(sum, if j == 0 { 1.0 } else { 0.0 }, epsilon = 1.0e-12);
+        }
+    }
+
+    // Greville abscissae reproduce linear functions
+    let mut uniform = BSplineBasis::uniform(3, -1.0, 1.0, 5).unwrap();
+    let greville = uniform.greville_abscissae();
+    for x in [-1.0, -0.3, 0.2, 1.0] {
+        let b = uniform.eval(x).unwrap();
+        let y = b
+            .iter()
+            .zip(greville.iter())
+            .map(|(b, g)| b * g)
+            .sum::<f64>();
+        approx::assert_abs_diff_eq!(y, x, epsilon = 1.0e-14);
+    }
+}
+
+#[test]
+fn test_bspline_fit_breakpoints() {
+    disable_error_handler();
+
+    // Breakpoints concentrated where the function varies quickly
+    let breakpoints = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 1.0, 2.0];
+    let basis = BSplineBasis::with_breakpoints(4, &breakpoints).unwrap();
+
+    let f = |x: f64| (-10.0 * x).exp();
+    let x = (0..200).map(|i| i as f64 / 100.0).collect::<Vec<_>>();
+    let y = x.iter().map(|&x| f(x)).collect::<Vec<_>>();
+
+    let spline = BSpline::fit_basis(basis, &x, &y).unwrap();
+    assert_eq!(spline.basis().ncoeffs(), 10);
+
+    let evaluation = spline.eval::<0>(&[0.05, 0.45, 1.5]).unwrap();
+    for (x, y) in [0.05, 0.45, 1.5].iter().zip(evaluation.y.iter()) {
+        approx::assert_abs_diff_eq!(f(*x), y, epsilon = 5.0e-3);
+    }
+}
+
 #[test]
 fn test_invalid_params() {
     disable_error_handler();
@@ -230,4 +421,14 @@ fn test_invalid_params() {
 
     // Empty domain
     fit_bspline(4, 0.0, 0.0, 10, &[0.0, 1.0, 2.0], &[0.0, 0.0, 0.0]).unwrap_err();
+
+    // Breakpoints not increasing
+    BSplineBasis::with_breakpoints(4, &[0.0, 1.0, 1.0]).unwrap_err();
+    BSplineBasis::with_breakpoints(4, &[0.0]).unwrap_err();
+    BSplineBasis::with_breakpoints(4, &[f64::NAN, 1.0, 2.0]).unwrap_err();
+    BSplineBasis::with_breakpoints(4, &[0.0, 1.0, f64::INFINITY]).unwrap_err();
+
+    // Evaluation outside of the breakpoints
+    let mut basis = BSplineBasis::uniform(4, 0.0, 1.0, 10).unwrap();
+    basis.eval(1.5).unwrap_err();
 }
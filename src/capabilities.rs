@@ -0,0 +1,131 @@
+/*
+    capabilities.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use std::ffi::CStr;
+
+/*
+
+    What this build of the crate can do, to check once at startup instead of running into
+    `GSLError::Unsupported` or `GSLError::Unimplemented` halfway through a computation.
+
+    The GSL version is the one of the linked library. Some modules wrap parts of GSL that were
+    added in later releases, see `REQUIREMENTS`; these are unavailable if GSL is older.
+
+*/
+
+/// Modules and functions that need at least the given GSL version, as `(path, major, minor)`
+pub const REQUIREMENTS: &[(&str, u32, u32)] = &[
+    ("rstat", 2, 0),
+    ("sf::legendre", 2, 0),
+    ("multilarge", 2, 1),
+    ("nonlinear_fit", 2, 2),
+    ("multilarge_nlinear", 2, 2),
+    ("integrate::FixedQuadrature", 2, 4),
+    ("integrate::romberg", 2, 5),
+    ("filter", 2, 5),
+    ("movstat", 2, 5),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Version of the linked GSL, e.g. `"2.7"`
+    pub gsl_version: &'static str,
+    pub gsl_major: u32,
+    pub gsl_minor: u32,
+    /// Version of this crate
+    pub crate_version: &'static str,
+    /// Whether the `rand` feature is enabled, which connects `Rng` to `rand_core`
+    pub rand: bool,
+    /// Whether the `gsl_thread_safe` feature is enabled, which turns off the aborting GSL error handler at startup
+    pub gsl_thread_safe: bool,
+}
+
+impl Capabilities {
+    /// Whether the linked GSL is at least version `major.minor`
+    pub fn gsl_at_least(&self, major: u32, minor: u32) -> bool {
+        (self.gsl_major, self.gsl_minor) >= (major, minor)
+    }
+
+    /// Whether the module or function at `path`, e.g. `"movstat"` or `"integrate::romberg"`, is available.
+    /// Paths without a requirement in `REQUIREMENTS` are always available.
+    pub fn available(&self, path: &str) -> bool {
+        REQUIREMENTS
+            .iter()
+            .filter(|(p, _, _)| path == *p || path.starts_with(&format!("{}::", p)))
+            .all(|&(_, major, minor)| self.gsl_at_least(major, minor))
+    }
+
+    /// Paths in `REQUIREMENTS` that need a newer GSL than the linked one
+    pub fn missing(&self) -> Vec<&'static str> {
+        REQUIREMENTS
+            .iter()
+            .filter(|&&(_, major, minor)| !self.gsl_at_least(major, minor))
+            .map(|(path, _, _)| *path)
+            .collect()
+    }
+}
+
+/// Capabilities of this build
+pub fn capabilities() -> Capabilities {
+    let version = unsafe { CStr::from_ptr(gsl_version) }
+        .to_str()
+        .unwrap_or("");
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|part| part.parse().unwrap_or(0));
+
+    Capabilities {
+        gsl_version: version,
+        gsl_major: parts.next().unwrap_or(0),
+        gsl_minor: parts.next().unwrap_or(0),
+        crate_version: env!("CARGO_PKG_VERSION"),
+        rand: cfg!(feature = "rand"),
+        gsl_thread_safe: cfg!(feature = "gsl_thread_safe"),
+    }
+}
+
+#[test]
+fn test_capabilities() {
+    let caps = capabilities();
+    assert!(caps.gsl_major >= 2, "{:?}", caps);
+    assert!(caps
+        .gsl_version
+        .starts_with(&format!("{}.{}", caps.gsl_major, caps.gsl_minor)));
+    assert_eq!(caps.rand, cfg!(feature = "rand"));
+    assert_eq!(caps.gsl_thread_safe, cfg!(feature = "gsl_thread_safe"));
+
+    // The bundled GSL has everything
+    assert!(caps.missing().is_empty());
+    assert!(caps.available("movstat"));
+    assert!(caps.available("integrate::romberg"));
+    assert!(caps.available("fft"));
+
+    let old = Capabilities {
+        gsl_major: 2,
+        gsl_minor: 4,
+        ..caps
+    };
+    assert!(old.gsl_at_least(1, 16));
+    assert!(!old.gsl_at_least(2, 5));
+    assert!(old.available("integrate::FixedQuadrature"));
+    assert!(!old.available("integrate::romberg"));
+    assert!(!old.available("movstat::mean"));
+    assert!(old.available("integrate::qag"));
+    assert_eq!(old.missing(), ["integrate::romberg", "filter", "movstat"]);
+}
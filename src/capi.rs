@@ -0,0 +1,360 @@
+/*
+    capi.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::integrate::IntegrationWorkspace;
+use crate::linear_fit::{fit_line, fit_line_weighted, LineFit};
+use crate::nonlinear_fit::nonlinear_fit;
+use crate::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/*
+
+    C interface to the fitting and integration drivers, enabled with the `capi` feature.
+    The declarations are in `include/gsl_rust.h`. Build a shared or static library with
+
+        cargo rustc --release --features capi --crate-type cdylib
+        cargo rustc --release --features capi --crate-type staticlib
+
+    Every function returns a GSL status code, `GSL_SUCCESS` (0) or one of the error codes of `gsl_errno.h`,
+    and only writes its outputs on success. The aborting GSL error handler is turned off on first use,
+    so errors come back as status codes. A null pointer for a required array fails with `GSL_EFAULT`.
+
+    Callbacks get the opaque `data` pointer they were registered with. A NaN from a callback
+    makes the driver fail, a C callback can't unwind through the driver in any other way.
+
+*/
+
+/// Largest amount of parameters of `gsl_rust_nonlinear_fit`
+pub const MAX_PARAMS: usize = 16;
+
+/// `f(x, data)`
+pub type Function = Option<unsafe extern "C" fn(x: f64, data: *mut c_void) -> f64>;
+
+/// `f(x, params, data)`
+pub type Model = Option<unsafe extern "C" fn(x: f64, params: *const f64, data: *mut c_void) -> f64>;
+
+/// Fits the straight line `y = c0 + c1 x` to the `n` points `(x, y)`, see `linear_fit::fit_line`.
+///
+/// # Safety
+/// `x` and `y` must point to `n` doubles and `fit` to a writable `gsl_rust_line_fit`.
+#[no_mangle]
+pub unsafe extern "C" fn gsl_rust_fit_line(
+    x: *const f64,
+    y: *const f64,
+    n: usize,
+    fit: *mut LineFit,
+) -> c_int {
+    guard(|| {
+        let result = fit_line(slice(x, n)?, slice(y, n)?)?;
+        write(fit, result)
+    })
+}
+
+/// Fits the straight line `y = c0 + c1 x` to the `n` points `(x, y)` with the weights `w`,
+/// see `linear_fit::fit_line_weighted`.
+///
+/// # Safety
+/// `x`, `w` and `y` must point to `n` doubles and `fit` to a writable `gsl_rust_line_fit`.
+#[no_mangle]
+pub unsafe extern "C" fn gsl_rust_fit_line_weighted(
+    x: *const f64,
+    w: *const f64,
+    y: *const f64,
+    n: usize,
+    fit: *mut LineFit,
+) -> c_int {
+    guard(|| {
+        let result = fit_line_weighted(slice(x, n)?, slice(w, n)?, slice(y, n)?)?;
+        write(fit, result)
+    })
+}
+
+/// Fits `y ≈ f(x, params)` to the `n` points `(x, y)`, with the `p` parameters starting at `params`,
+/// see `nonlinear_fit::nonlinear_fit`. Fails with `GSL_EINVAL` for more than `MAX_PARAMS` parameters.
+///
+/// On success, `params` holds the fitted parameters, and the optional `covariance`
+/// the row major `p × p` covariance matrix, and the optional `chisq` the sum of squared residuals.
+///
+/// # Safety
+/// `x` and `y` must point to `n` doubles, `params` to `p` doubles, and `covariance` to `p * p` doubles or be null.
+/// `chisq` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn gsl_rust_nonlinear_fit(
+    f: Model,
+    data: *mut c_void,
+    x: *const f64,
+    y: *const f64,
+    n: usize,
+    p: usize,
+    params: *mut f64,
+    covariance: *mut f64,
+    chisq: *mut f64,
+) -> c_int {
+    macro_rules! dispatch {
+        ($($P:literal)*) => {
+            match p {
+                $($P => fit_p::<$P>(f, data, x, y, n, params, covariance, chisq),)*
+                _ => Err(GSLError::Invalid),
+            }
+        };
+    }
+    guard(|| dispatch!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16))
+}
+
+unsafe fn fit_p<const P: usize>(
+    f: Model,
+    data: *mut c_void,
+    x: *const f64,
+    y: *const f64,
+    n: usize,
+    params: *mut f64,
+    covariance: *mut f64,
+    chisq: *mut f64,
+) -> Result<()> {
+    let f = f.ok_or(GSLError::Fault)?;
+    let p0 = slice(params, P)?.try_into().unwrap();
+    let fit = nonlinear_fit(p0, slice(x, n)?, slice(y, n)?, |&x, params: [f64; P]| {
+        Ok(f(x, params.as_ptr(), data))
+    })?;
+
+    std::ptr::copy_nonoverlapping(fit.params.as_ptr(), params, P);
+    if !covariance.is_null() {
+        std::ptr::copy_nonoverlapping(fit.covariance.as_ptr() as *const f64, covariance, P * P);
+    }
+    if !chisq.is_null() {
+        *chisq = fit.final_residual_squared;
+    }
+    Ok(())
+}
+
+/// Integral of `f` from `a` to `b` to the absolute or relative tolerance `epsabs` or `epsrel`,
+/// with the error estimate in `abserr`. Either limit can be infinite, which picks the matching
+/// GSL routine: QAGS for a finite range, QAGI, QAGIU or QAGIL for infinite ones.
+///
+/// # Safety
+/// `result` and `abserr` must be writable, `abserr` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn gsl_rust_integrate(
+    f: Function,
+    data: *mut c_void,
+    a: f64,
+    b: f64,
+    epsabs: f64,
+    epsrel: f64,
+    result: *mut f64,
+    abserr: *mut f64,
+) -> c_int {
+    guard(|| {
+        let f = f.ok_or(GSLError::Fault)?;
+        if a.is_nan() || b.is_nan() {
+            return Err(GSLError::Domain);
+        }
+
+        // Integrate upwards, flipping the sign for a reversed range
+        let (lower, upper, sign) = if a <= b { (a, b, 1.0) } else { (b, a, -1.0) };
+        let mut workspace = IntegrationWorkspace::new(1000)?;
+        let f = |x| f(x, data);
+        let integral = match (lower.is_finite(), upper.is_finite()) {
+            _ if lower == upper => ValWithError { val: 0.0, err: 0.0 },
+            (true, true) => workspace.qags(lower, upper, epsabs, epsrel, f)?,
+            (true, false) => workspace.qagiu(lower, epsabs, epsrel, f)?,
+            (false, true) => workspace.qagil(upper, epsabs, epsrel, f)?,
+            (false, false) => workspace.qagi(epsabs, epsrel, f)?,
+        };
+
+        write(result, sign * integral.val)?;
+        if !abserr.is_null() {
+            *abserr = integral.err;
+        }
+        Ok(())
+    })
+}
+
+/// Runs `f` with the GSL error handler turned off, converting its result and any panic into a status code
+fn guard<F: FnOnce() -> Result<()>>(f: F) -> c_int {
+    disable_error_handler();
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => bindings::GSL_SUCCESS,
+        Ok(Err(e)) => e.into(),
+        Err(_) => bindings::GSL_FAILURE,
+    }
+}
+
+unsafe fn slice<'a>(x: *const f64, n: usize) -> Result<&'a [f64]> {
+    if n == 0 {
+        Ok(&[])
+    } else if x.is_null() {
+        Err(GSLError::Fault)
+    } else {
+        Ok(std::slice::from_raw_parts(x, n))
+    }
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<()> {
+    if out.is_null() {
+        return Err(GSLError::Fault);
+    }
+    out.write(value);
+    Ok(())
+}
+
+#[cfg(test)]
+unsafe extern "C" fn gaussian(x: f64, _data: *mut c_void) -> f64 {
+    (-x * x).exp()
+}
+
+#[cfg(test)]
+unsafe extern "C" fn exponential(x: f64, params: *const f64, data: *mut c_void) -> f64 {
+    *(data as *mut usize) += 1;
+    *params * (-*params.add(1) * x).exp()
+}
+
+#[test]
+fn test_capi() {
+    use std::f64::consts::PI;
+
+    unsafe {
+        let x = [0.0, 1.0, 2.0, 3.0];
+        let y = [1.0, 3.0, 5.0, 7.0];
+        let mut fit = std::mem::MaybeUninit::<LineFit>::uninit();
+        assert_eq!(
+            gsl_rust_fit_line(x.as_ptr(), y.as_ptr(), 4, fit.as_mut_ptr()),
+            0
+        );
+        let fit = fit.assume_init();
+        approx::assert_abs_diff_eq!(fit.c0, 1.0, epsilon = 1.0e-12);
+        approx::assert_abs_diff_eq!(fit.c1, 2.0, epsilon = 1.0e-12);
+
+        // All combinations of finite and infinite limits, in both directions
+        let mut result = 0.0;
+        let mut abserr = 0.0;
+        let inf = f64::INFINITY;
+        for (a, b, expected) in [
+            (0.0, inf, PI.sqrt() / 2.0),
+            (-inf, 0.0, PI.sqrt() / 2.0),
+            (-inf, inf, PI.sqrt()),
+            (inf, -inf, -PI.sqrt()),
+            (1.0, -1.0, -1.493648265624854),
+            (2.0, 2.0, 0.0),
+        ] {
+            let status = gsl_rust_integrate(
+                Some(gaussian),
+                std::ptr::null_mut(),
+                a,
+                b,
+                1.0e-12,
+                0.0,
+                &mut result,
+                &mut abserr,
+            );
+            assert_eq!(status, 0);
+            approx::assert_abs_diff_eq!(result, expected, epsilon = 1.0e-10);
+            assert!(abserr <= 1.0e-10);
+        }
+
+        let x = (0..50).map(|i| i as f64 / 10.0).collect::<Vec<_>>();
+        let y = x.iter().map(|x| 3.0 * (-0.5 * x).exp()).collect::<Vec<_>>();
+        let mut params = [1.0, 1.0];
+        let mut covariance = [f64::NAN; 4];
+        let mut calls = 0usize;
+        let status = gsl_rust_nonlinear_fit(
+            Some(exponential),
+            &mut calls as *mut usize as *mut c_void,
+            x.as_ptr(),
+            y.as_ptr(),
+            x.len(),
+            2,
+            params.as_mut_ptr(),
+            covariance.as_mut_ptr(),
+            std::ptr::null_mut(),
+        );
+        assert_eq!(status, 0);
+        approx::assert_abs_diff_eq!(params[0], 3.0, epsilon = 1.0e-6);
+        approx::assert_abs_diff_eq!(params[1], 0.5, epsilon = 1.0e-6);
+        assert_eq!(covariance[1], covariance[2]);
+        assert!(calls > 0);
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    unsafe {
+        let x = [0.0, 1.0];
+        let mut fit = std::mem::MaybeUninit::<LineFit>::uninit();
+        let mut result = 0.0;
+
+        // Null pointers
+        assert_eq!(
+            gsl_rust_fit_line(x.as_ptr(), std::ptr::null(), 2, fit.as_mut_ptr()),
+            bindings::GSL_EFAULT
+        );
+        assert_eq!(
+            gsl_rust_fit_line(x.as_ptr(), x.as_ptr(), 2, std::ptr::null_mut()),
+            bindings::GSL_EFAULT
+        );
+        assert_eq!(
+            gsl_rust_integrate(
+                None,
+                std::ptr::null_mut(),
+                0.0,
+                1.0,
+                0.0,
+                1.0e-9,
+                &mut result,
+                std::ptr::null_mut()
+            ),
+            bindings::GSL_EFAULT
+        );
+
+        // Errors of the drivers
+        assert_eq!(
+            gsl_rust_fit_line(x.as_ptr(), x.as_ptr(), 1, fit.as_mut_ptr()),
+            bindings::GSL_EINVAL
+        );
+        assert_eq!(
+            gsl_rust_integrate(
+                Some(gaussian),
+                std::ptr::null_mut(),
+                f64::NAN,
+                1.0,
+                0.0,
+                1.0e-9,
+                &mut result,
+                std::ptr::null_mut()
+            ),
+            bindings::GSL_EDOM
+        );
+
+        // Too many parameters
+        let mut params = [1.0; MAX_PARAMS + 1];
+        assert_eq!(
+            gsl_rust_nonlinear_fit(
+                Some(exponential),
+                std::ptr::null_mut(),
+                x.as_ptr(),
+                x.as_ptr(),
+                2,
+                MAX_PARAMS + 1,
+                params.as_mut_ptr(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            ),
+            bindings::GSL_EINVAL
+        );
+    }
+}
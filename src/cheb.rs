@@ -0,0 +1,222 @@
+/*
+    cheb.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+use std::fmt;
+
+/// Chebyshev series approximating a function on `[a, b]`
+pub struct ChebSeries {
+    order: usize,
+    a: f64,
+    b: f64,
+    series: *mut gsl_cheb_series,
+}
+
+impl ChebSeries {
+    /// Approximates `f` on `[a, b]` by a Chebyshev series of the given order.
+    pub fn new<F: FnMut(f64) -> f64>(order: usize, a: f64, b: f64, mut f: F) -> Result<Self> {
+        unsafe {
+            if order == 0 {
+                return Err(GSLError::Invalid);
+            }
+            if b <= a {
+                return Err(GSLError::Domain);
+            }
+
+            let mut series = Self::alloc(order);
+            series.a = a;
+            series.b = b;
+
+            let gsl_f = gsl_function_struct {
+                function: Some(trampoline::<F>),
+                params: &mut f as *mut _ as *mut _,
+            };
+
+            GSLError::from_raw(gsl_cheb_init(series.series, &gsl_f, a, b))?;
+
+            // The trampoline turns panics into NaN
+            if series.coefficients().iter().any(|c| !c.is_finite()) {
+                return Err(GSLError::BadFunction);
+            }
+
+            Ok(series)
+        }
+    }
+
+    /// Series with the `order + 1` given coefficients on `[a, b]`
+    pub fn from_coefficients(a: f64, b: f64, coefficients: &[f64]) -> Result<Self> {
+        unsafe {
+            if coefficients.len() < 2 {
+                return Err(GSLError::Invalid);
+            }
+            if b <= a || a.is_nan() || b.is_nan() {
+                return Err(GSLError::Domain);
+            }
+
+            let order = coefficients.len() - 1;
+            let mut series = Self::alloc(order);
+            series.a = a;
+            series.b = b;
+            // What gsl_cheb_init sets besides the coefficients
+            (*series.series).a = a;
+            (*series.series).b = b;
+            (*series.series).order_sp = order as _;
+            std::slice::from_raw_parts_mut(gsl_cheb_coeffs(series.series), order + 1)
+                .copy_from_slice(coefficients);
+            Ok(series)
+        }
+    }
+
+    unsafe fn alloc(order: usize) -> Self {
+        let series = gsl_cheb_alloc(order as u64);
+        assert!(!series.is_null());
+
+        ChebSeries {
+            order,
+            a: 0.0,
+            b: 0.0,
+            series,
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn domain(&self) -> (f64, f64) {
+        (self.a, self.b)
+    }
+
+    /// The `order + 1` series coefficients
+    pub fn coefficients(&self) -> &[f64] {
+        unsafe { std::slice::from_raw_parts(gsl_cheb_coeffs(self.series), self.order + 1) }
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        unsafe { gsl_cheb_eval(self.series, x) }
+    }
+
+    pub fn eval_err(&self, x: f64) -> Result<ValWithError<f64>> {
+        unsafe {
+            let mut val = 0.0;
+            let mut err = 0.0;
+            GSLError::from_raw(gsl_cheb_eval_err(self.series, x, &mut val, &mut err))?;
+            Ok(ValWithError { val, err })
+        }
+    }
+
+    /// Evaluates the series truncated to at most `order`.
+    pub fn eval_n_err(&self, order: usize, x: f64) -> Result<ValWithError<f64>> {
+        unsafe {
+            let mut val = 0.0;
+            let mut err = 0.0;
+            GSLError::from_raw(gsl_cheb_eval_n_err(
+                self.series,
+                order as u64,
+                x,
+                &mut val,
+                &mut err,
+            ))?;
+            Ok(ValWithError { val, err })
+        }
+    }
+
+    /// Series of the derivative
+    pub fn derivative(&self) -> Result<Self> {
+        unsafe {
+            let mut out = Self::alloc(self.order);
+            GSLError::from_raw(gsl_cheb_calc_deriv(out.series, self.series))?;
+            out.a = self.a;
+            out.b = self.b;
+            Ok(out)
+        }
+    }
+
+    /// Series of the integral, which vanishes at `a`
+    pub fn integral(&self) -> Result<Self> {
+        unsafe {
+            let mut out = Self::alloc(self.order);
+            GSLError::from_raw(gsl_cheb_calc_integ(out.series, self.series))?;
+            out.a = self.a;
+            out.b = self.b;
+            Ok(out)
+        }
+    }
+}
+
+impl fmt::Debug for ChebSeries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChebSeries")
+            .field("order", &self.order)
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .field("coefficients", &self.coefficients())
+            .finish()
+    }
+}
+
+impl Drop for ChebSeries {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_cheb_free(self.series);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for ChebSeries {}
+unsafe impl Sync for ChebSeries {}
+
+#[test]
+fn test_cheb_series() {
+    disable_error_handler();
+
+    let series = ChebSeries::new(40, 0.0, std::f64::consts::PI, |x| x.sin()).unwrap();
+    let derivative = series.derivative().unwrap();
+    let integral = series.integral().unwrap();
+
+    for i in 0..=10 {
+        let x = i as f64 / 10.0 * std::f64::consts::PI;
+
+        approx::assert_abs_diff_eq!(series.eval(x), x.sin(), epsilon = 1.0e-12);
+        approx::assert_abs_diff_eq!(derivative.eval(x), x.cos(), epsilon = 1.0e-9);
+        approx::assert_abs_diff_eq!(integral.eval(x), 1.0 - x.cos(), epsilon = 1.0e-9);
+
+        let y = series.eval_err(x).unwrap();
+        assert!((y.val - x.sin()).abs() <= y.err.max(1.0e-12));
+    }
+
+    // Low order truncation is less accurate
+    let truncated = series.eval_n_err(3, 1.0).unwrap();
+    assert!(truncated.err > series.eval_err(1.0).unwrap().err);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Empty domain
+    ChebSeries::new(10, 1.0, 1.0, |x| x).unwrap_err();
+
+    // Zeroth order
+    ChebSeries::new(0, 0.0, 1.0, |x| x).unwrap_err();
+
+    // Panicking function
+    ChebSeries::new(10, 0.0, 1.0, |_| panic!()).unwrap_err();
+}
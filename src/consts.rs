@@ -0,0 +1,595 @@
+/*
+    consts.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+
+    Physical constants and units of GSL, in the MKSA system (meters, kilograms, seconds, amperes)
+    and the CGSM system (centimeters, grams, seconds, gauss) with electromagnetic units in abamperes.
+    Dimensionless constants and the SI prefixes are in `num`.
+
+    The values are the ones of the linked GSL, from CODATA. To convert a quantity into a unit,
+    divide by the constant of the unit: `x / mksa::INCH` is the length `x` in meters expressed in inches.
+
+    The constants are grouped as in the GSL manual. CGSM leaves out the electromagnetic constants
+    that have no simple form in that system.
+
+*/
+
+/// Constants in meters, kilograms, seconds and amperes
+pub mod mksa {
+    use crate::bindings::*;
+
+    // Fundamental constants
+
+    /// Speed of light in vacuum `c`, in m / s
+    pub const SPEED_OF_LIGHT: f64 = GSL_CONST_MKSA_SPEED_OF_LIGHT;
+    /// Permeability of free space `μ0`, in kg m / A^2 s^2
+    pub const VACUUM_PERMEABILITY: f64 = GSL_CONST_MKSA_VACUUM_PERMEABILITY;
+    /// Permittivity of free space `ε0`, in A^2 s^4 / kg m^3
+    pub const VACUUM_PERMITTIVITY: f64 = GSL_CONST_MKSA_VACUUM_PERMITTIVITY;
+    /// Planck's constant `h`, in kg m^2 / s
+    pub const PLANCKS_CONSTANT_H: f64 = GSL_CONST_MKSA_PLANCKS_CONSTANT_H;
+    /// Reduced Planck's constant `ħ = h / 2π`, in kg m^2 / s
+    pub const PLANCKS_CONSTANT_HBAR: f64 = GSL_CONST_MKSA_PLANCKS_CONSTANT_HBAR;
+    /// Molar charge of 1 Faraday, in A s / mol
+    pub const FARADAY: f64 = GSL_CONST_MKSA_FARADAY;
+    /// Boltzmann constant `k`, in kg m^2 / K s^2
+    pub const BOLTZMANN: f64 = GSL_CONST_MKSA_BOLTZMANN;
+    /// Molar gas constant `R0`, in kg m^2 / K mol s^2
+    pub const MOLAR_GAS: f64 = GSL_CONST_MKSA_MOLAR_GAS;
+    /// Volume of one mole of ideal gas at standard temperature and pressure, in m^3 / mol
+    pub const STANDARD_GAS_VOLUME: f64 = GSL_CONST_MKSA_STANDARD_GAS_VOLUME;
+    /// Stefan-Boltzmann radiation constant `σ`, in kg / K^4 s^3
+    pub const STEFAN_BOLTZMANN_CONSTANT: f64 = GSL_CONST_MKSA_STEFAN_BOLTZMANN_CONSTANT;
+    /// Magnetic field of 1 Gauss, in kg / A s^2
+    pub const GAUSS: f64 = GSL_CONST_MKSA_GAUSS;
+
+    // Astronomy and astrophysics
+
+    /// Astronomical unit, the mean Earth-Sun distance, in m
+    pub const ASTRONOMICAL_UNIT: f64 = GSL_CONST_MKSA_ASTRONOMICAL_UNIT;
+    /// Gravitational constant `G`, in m^3 / kg s^2
+    pub const GRAVITATIONAL_CONSTANT: f64 = GSL_CONST_MKSA_GRAVITATIONAL_CONSTANT;
+    /// Distance travelled by light in vacuum in one year, in m
+    pub const LIGHT_YEAR: f64 = GSL_CONST_MKSA_LIGHT_YEAR;
+    /// Parsec, in m
+    pub const PARSEC: f64 = GSL_CONST_MKSA_PARSEC;
+    /// Standard gravitational acceleration on Earth `g`, in m / s^2
+    pub const GRAV_ACCEL: f64 = GSL_CONST_MKSA_GRAV_ACCEL;
+    /// Mass of the Sun, in kg
+    pub const SOLAR_MASS: f64 = GSL_CONST_MKSA_SOLAR_MASS;
+
+    // Atomic and nuclear physics
+
+    /// Charge of the electron `e`, in A s
+    pub const ELECTRON_CHARGE: f64 = GSL_CONST_MKSA_ELECTRON_CHARGE;
+    /// Energy of 1 electron volt, in kg m^2 / s^2
+    pub const ELECTRON_VOLT: f64 = GSL_CONST_MKSA_ELECTRON_VOLT;
+    /// Unified atomic mass unit, in kg
+    pub const UNIFIED_ATOMIC_MASS: f64 = GSL_CONST_MKSA_UNIFIED_ATOMIC_MASS;
+    /// Mass of the electron, in kg
+    pub const MASS_ELECTRON: f64 = GSL_CONST_MKSA_MASS_ELECTRON;
+    /// Mass of the muon, in kg
+    pub const MASS_MUON: f64 = GSL_CONST_MKSA_MASS_MUON;
+    /// Mass of the proton, in kg
+    pub const MASS_PROTON: f64 = GSL_CONST_MKSA_MASS_PROTON;
+    /// Mass of the neutron, in kg
+    pub const MASS_NEUTRON: f64 = GSL_CONST_MKSA_MASS_NEUTRON;
+    /// Rydberg constant `Ry = h c R∞`, in kg m^2 / s^2
+    pub const RYDBERG: f64 = GSL_CONST_MKSA_RYDBERG;
+    /// Bohr radius `a0`, in m
+    pub const BOHR_RADIUS: f64 = GSL_CONST_MKSA_BOHR_RADIUS;
+    /// Length of 1 angstrom, in m
+    pub const ANGSTROM: f64 = GSL_CONST_MKSA_ANGSTROM;
+    /// Area of 1 barn, in m^2
+    pub const BARN: f64 = GSL_CONST_MKSA_BARN;
+    /// Bohr magneton `μB`, in A m^2
+    pub const BOHR_MAGNETON: f64 = GSL_CONST_MKSA_BOHR_MAGNETON;
+    /// Nuclear magneton `μN`, in A m^2
+    pub const NUCLEAR_MAGNETON: f64 = GSL_CONST_MKSA_NUCLEAR_MAGNETON;
+    /// Absolute value of the magnetic moment of the electron `μe`, in A m^2
+    pub const ELECTRON_MAGNETIC_MOMENT: f64 = GSL_CONST_MKSA_ELECTRON_MAGNETIC_MOMENT;
+    /// Magnetic moment of the proton `μp`, in A m^2
+    pub const PROTON_MAGNETIC_MOMENT: f64 = GSL_CONST_MKSA_PROTON_MAGNETIC_MOMENT;
+    /// Thomson cross section `σT`, in m^2
+    pub const THOMSON_CROSS_SECTION: f64 = GSL_CONST_MKSA_THOMSON_CROSS_SECTION;
+    /// Electric dipole moment of 1 Debye, in A s^2 / m^2
+    pub const DEBYE: f64 = GSL_CONST_MKSA_DEBYE;
+
+    // Measurement of time
+
+    /// Number of seconds in 1 minute, in s
+    pub const MINUTE: f64 = GSL_CONST_MKSA_MINUTE;
+    /// Number of seconds in 1 hour, in s
+    pub const HOUR: f64 = GSL_CONST_MKSA_HOUR;
+    /// Number of seconds in 1 day, in s
+    pub const DAY: f64 = GSL_CONST_MKSA_DAY;
+    /// Number of seconds in 1 week, in s
+    pub const WEEK: f64 = GSL_CONST_MKSA_WEEK;
+
+    // Imperial units
+
+    /// Length of 1 inch, in m
+    pub const INCH: f64 = GSL_CONST_MKSA_INCH;
+    /// Length of 1 foot, in m
+    pub const FOOT: f64 = GSL_CONST_MKSA_FOOT;
+    /// Length of 1 yard, in m
+    pub const YARD: f64 = GSL_CONST_MKSA_YARD;
+    /// Length of 1 mile, in m
+    pub const MILE: f64 = GSL_CONST_MKSA_MILE;
+    /// Length of 1 mil, 1/1000th of an inch, in m
+    pub const MIL: f64 = GSL_CONST_MKSA_MIL;
+
+    // Speed and nautical units
+
+    /// Speed of 1 kilometer per hour, in m / s
+    pub const KILOMETERS_PER_HOUR: f64 = GSL_CONST_MKSA_KILOMETERS_PER_HOUR;
+    /// Speed of 1 mile per hour, in m / s
+    pub const MILES_PER_HOUR: f64 = GSL_CONST_MKSA_MILES_PER_HOUR;
+    /// Length of 1 nautical mile, in m
+    pub const NAUTICAL_MILE: f64 = GSL_CONST_MKSA_NAUTICAL_MILE;
+    /// Length of 1 fathom, in m
+    pub const FATHOM: f64 = GSL_CONST_MKSA_FATHOM;
+    /// Speed of 1 knot, in m / s
+    pub const KNOT: f64 = GSL_CONST_MKSA_KNOT;
+
+    // Printers units
+
+    /// Length of 1 printer's point, 1/72 inch, in m
+    pub const POINT: f64 = GSL_CONST_MKSA_POINT;
+    /// Length of 1 TeX point, 1/72.27 inch, in m
+    pub const TEXPOINT: f64 = GSL_CONST_MKSA_TEXPOINT;
+
+    // Volume, area and length
+
+    /// Length of 1 micron, in m
+    pub const MICRON: f64 = GSL_CONST_MKSA_MICRON;
+    /// Area of 1 hectare, in m^2
+    pub const HECTARE: f64 = GSL_CONST_MKSA_HECTARE;
+    /// Area of 1 acre, in m^2
+    pub const ACRE: f64 = GSL_CONST_MKSA_ACRE;
+    /// Volume of 1 liter, in m^3
+    pub const LITER: f64 = GSL_CONST_MKSA_LITER;
+    /// Volume of 1 US gallon, in m^3
+    pub const US_GALLON: f64 = GSL_CONST_MKSA_US_GALLON;
+    /// Volume of 1 Canadian gallon, in m^3
+    pub const CANADIAN_GALLON: f64 = GSL_CONST_MKSA_CANADIAN_GALLON;
+    /// Volume of 1 UK gallon, in m^3
+    pub const UK_GALLON: f64 = GSL_CONST_MKSA_UK_GALLON;
+    /// Volume of 1 quart, in m^3
+    pub const QUART: f64 = GSL_CONST_MKSA_QUART;
+    /// Volume of 1 pint, in m^3
+    pub const PINT: f64 = GSL_CONST_MKSA_PINT;
+
+    // Mass and weight
+
+    /// Mass of 1 pound, in kg
+    pub const POUND_MASS: f64 = GSL_CONST_MKSA_POUND_MASS;
+    /// Mass of 1 ounce, in kg
+    pub const OUNCE_MASS: f64 = GSL_CONST_MKSA_OUNCE_MASS;
+    /// Mass of 1 ton, in kg
+    pub const TON: f64 = GSL_CONST_MKSA_TON;
+    /// Mass of 1 metric ton, 1000 kg, in kg
+    pub const METRIC_TON: f64 = GSL_CONST_MKSA_METRIC_TON;
+    /// Mass of 1 UK ton, in kg
+    pub const UK_TON: f64 = GSL_CONST_MKSA_UK_TON;
+    /// Mass of 1 troy ounce, in kg
+    pub const TROY_OUNCE: f64 = GSL_CONST_MKSA_TROY_OUNCE;
+    /// Mass of 1 carat, in kg
+    pub const CARAT: f64 = GSL_CONST_MKSA_CARAT;
+    /// Force of 1 gram weight, in kg m / s^2
+    pub const GRAM_FORCE: f64 = GSL_CONST_MKSA_GRAM_FORCE;
+    /// Force of 1 pound weight, in kg m / s^2
+    pub const POUND_FORCE: f64 = GSL_CONST_MKSA_POUND_FORCE;
+    /// Force of 1 kilopound weight, in kg m / s^2
+    pub const KILOPOUND_FORCE: f64 = GSL_CONST_MKSA_KILOPOUND_FORCE;
+    /// Force of 1 poundal, in kg m / s^2
+    pub const POUNDAL: f64 = GSL_CONST_MKSA_POUNDAL;
+
+    // Thermal energy and power
+
+    /// Energy of 1 calorie, in kg m^2 / s^2
+    pub const CALORIE: f64 = GSL_CONST_MKSA_CALORIE;
+    /// Energy of 1 British Thermal Unit, in kg m^2 / s^2
+    pub const BTU: f64 = GSL_CONST_MKSA_BTU;
+    /// Energy of 1 Therm, in kg m^2 / s^2
+    pub const THERM: f64 = GSL_CONST_MKSA_THERM;
+    /// Power of 1 horsepower, in kg m^2 / s^3
+    pub const HORSEPOWER: f64 = GSL_CONST_MKSA_HORSEPOWER;
+
+    // Pressure
+
+    /// Pressure of 1 bar, in kg / m s^2
+    pub const BAR: f64 = GSL_CONST_MKSA_BAR;
+    /// Pressure of 1 standard atmosphere, in kg / m s^2
+    pub const STD_ATMOSPHERE: f64 = GSL_CONST_MKSA_STD_ATMOSPHERE;
+    /// Pressure of 1 torr, in kg / m s^2
+    pub const TORR: f64 = GSL_CONST_MKSA_TORR;
+    /// Pressure of 1 meter of mercury, in kg / m s^2
+    pub const METER_OF_MERCURY: f64 = GSL_CONST_MKSA_METER_OF_MERCURY;
+    /// Pressure of 1 inch of mercury, in kg / m s^2
+    pub const INCH_OF_MERCURY: f64 = GSL_CONST_MKSA_INCH_OF_MERCURY;
+    /// Pressure of 1 inch of water, in kg / m s^2
+    pub const INCH_OF_WATER: f64 = GSL_CONST_MKSA_INCH_OF_WATER;
+    /// Pressure of 1 pound per square inch, in kg / m s^2
+    pub const PSI: f64 = GSL_CONST_MKSA_PSI;
+
+    // Viscosity
+
+    /// Dynamic viscosity of 1 poise, in kg / m s
+    pub const POISE: f64 = GSL_CONST_MKSA_POISE;
+    /// Kinematic viscosity of 1 stokes, in m^2 / s
+    pub const STOKES: f64 = GSL_CONST_MKSA_STOKES;
+
+    // Light and illumination
+
+    /// Luminance of 1 stilb, in cd / m^2
+    pub const STILB: f64 = GSL_CONST_MKSA_STILB;
+    /// Luminous flux of 1 lumen, in cd sr
+    pub const LUMEN: f64 = GSL_CONST_MKSA_LUMEN;
+    /// Illuminance of 1 lux, in cd sr / m^2
+    pub const LUX: f64 = GSL_CONST_MKSA_LUX;
+    /// Illuminance of 1 phot, in cd sr / m^2
+    pub const PHOT: f64 = GSL_CONST_MKSA_PHOT;
+    /// Illuminance of 1 footcandle, in cd sr / m^2
+    pub const FOOTCANDLE: f64 = GSL_CONST_MKSA_FOOTCANDLE;
+    /// Luminance of 1 lambert, in cd sr / m^2
+    pub const LAMBERT: f64 = GSL_CONST_MKSA_LAMBERT;
+    /// Luminance of 1 footlambert, in cd sr / m^2
+    pub const FOOTLAMBERT: f64 = GSL_CONST_MKSA_FOOTLAMBERT;
+
+    // Radioactivity
+
+    /// Activity of 1 curie, in 1 / s
+    pub const CURIE: f64 = GSL_CONST_MKSA_CURIE;
+    /// Exposure of 1 roentgen, in A s / kg
+    pub const ROENTGEN: f64 = GSL_CONST_MKSA_ROENTGEN;
+    /// Absorbed dose of 1 rad, in m^2 / s^2
+    pub const RAD: f64 = GSL_CONST_MKSA_RAD;
+
+    // Force and energy
+
+    /// SI unit of force, 1 newton, in kg m / s^2
+    pub const NEWTON: f64 = GSL_CONST_MKSA_NEWTON;
+    /// Force of 1 dyne, in kg m / s^2
+    pub const DYNE: f64 = GSL_CONST_MKSA_DYNE;
+    /// SI unit of energy, 1 joule, in kg m^2 / s^2
+    pub const JOULE: f64 = GSL_CONST_MKSA_JOULE;
+    /// Energy of 1 erg, in kg m^2 / s^2
+    pub const ERG: f64 = GSL_CONST_MKSA_ERG;
+}
+
+/// Constants in centimeters, grams, seconds and gauss, with currents in abamperes
+pub mod cgsm {
+    use crate::bindings::*;
+
+    // Fundamental constants
+
+    /// Speed of light in vacuum `c`, in cm / s
+    pub const SPEED_OF_LIGHT: f64 = GSL_CONST_CGSM_SPEED_OF_LIGHT;
+    /// Planck's constant `h`, in g cm^2 / s
+    pub const PLANCKS_CONSTANT_H: f64 = GSL_CONST_CGSM_PLANCKS_CONSTANT_H;
+    /// Reduced Planck's constant `ħ = h / 2π`, in g cm^2 / s
+    pub const PLANCKS_CONSTANT_HBAR: f64 = GSL_CONST_CGSM_PLANCKS_CONSTANT_HBAR;
+    /// Molar charge of 1 Faraday, in abA s / mol
+    pub const FARADAY: f64 = GSL_CONST_CGSM_FARADAY;
+    /// Boltzmann constant `k`, in g cm^2 / K s^2
+    pub const BOLTZMANN: f64 = GSL_CONST_CGSM_BOLTZMANN;
+    /// Molar gas constant `R0`, in g cm^2 / K mol s^2
+    pub const MOLAR_GAS: f64 = GSL_CONST_CGSM_MOLAR_GAS;
+    /// Volume of one mole of ideal gas at standard temperature and pressure, in cm^3 / mol
+    pub const STANDARD_GAS_VOLUME: f64 = GSL_CONST_CGSM_STANDARD_GAS_VOLUME;
+    /// Stefan-Boltzmann radiation constant `σ`, in g / K^4 s^3
+    pub const STEFAN_BOLTZMANN_CONSTANT: f64 = GSL_CONST_CGSM_STEFAN_BOLTZMANN_CONSTANT;
+
+    // Astronomy and astrophysics
+
+    /// Astronomical unit, the mean Earth-Sun distance, in cm
+    pub const ASTRONOMICAL_UNIT: f64 = GSL_CONST_CGSM_ASTRONOMICAL_UNIT;
+    /// Gravitational constant `G`, in cm^3 / g s^2
+    pub const GRAVITATIONAL_CONSTANT: f64 = GSL_CONST_CGSM_GRAVITATIONAL_CONSTANT;
+    /// Distance travelled by light in vacuum in one year, in cm
+    pub const LIGHT_YEAR: f64 = GSL_CONST_CGSM_LIGHT_YEAR;
+    /// Parsec, in cm
+    pub const PARSEC: f64 = GSL_CONST_CGSM_PARSEC;
+    /// Standard gravitational acceleration on Earth `g`, in cm / s^2
+    pub const GRAV_ACCEL: f64 = GSL_CONST_CGSM_GRAV_ACCEL;
+    /// Mass of the Sun, in g
+    pub const SOLAR_MASS: f64 = GSL_CONST_CGSM_SOLAR_MASS;
+
+    // Atomic and nuclear physics
+
+    /// Charge of the electron `e`, in abA s
+    pub const ELECTRON_CHARGE: f64 = GSL_CONST_CGSM_ELECTRON_CHARGE;
+    /// Energy of 1 electron volt, in g cm^2 / s^2
+    pub const ELECTRON_VOLT: f64 = GSL_CONST_CGSM_ELECTRON_VOLT;
+    /// Unified atomic mass unit, in g
+    pub const UNIFIED_ATOMIC_MASS: f64 = GSL_CONST_CGSM_UNIFIED_ATOMIC_MASS;
+    /// Mass of the electron, in g
+    pub const MASS_ELECTRON: f64 = GSL_CONST_CGSM_MASS_ELECTRON;
+    /// Mass of the muon, in g
+    pub const MASS_MUON: f64 = GSL_CONST_CGSM_MASS_MUON;
+    /// Mass of the proton, in g
+    pub const MASS_PROTON: f64 = GSL_CONST_CGSM_MASS_PROTON;
+    /// Mass of the neutron, in g
+    pub const MASS_NEUTRON: f64 = GSL_CONST_CGSM_MASS_NEUTRON;
+    /// Rydberg constant `Ry = h c R∞`, in g cm^2 / s^2
+    pub const RYDBERG: f64 = GSL_CONST_CGSM_RYDBERG;
+    /// Bohr radius `a0`, in cm
+    pub const BOHR_RADIUS: f64 = GSL_CONST_CGSM_BOHR_RADIUS;
+    /// Length of 1 angstrom, in cm
+    pub const ANGSTROM: f64 = GSL_CONST_CGSM_ANGSTROM;
+    /// Area of 1 barn, in cm^2
+    pub const BARN: f64 = GSL_CONST_CGSM_BARN;
+    /// Bohr magneton `μB`, in abA cm^2
+    pub const BOHR_MAGNETON: f64 = GSL_CONST_CGSM_BOHR_MAGNETON;
+    /// Nuclear magneton `μN`, in abA cm^2
+    pub const NUCLEAR_MAGNETON: f64 = GSL_CONST_CGSM_NUCLEAR_MAGNETON;
+    /// Absolute value of the magnetic moment of the electron `μe`, in abA cm^2
+    pub const ELECTRON_MAGNETIC_MOMENT: f64 = GSL_CONST_CGSM_ELECTRON_MAGNETIC_MOMENT;
+    /// Magnetic moment of the proton `μp`, in abA cm^2
+    pub const PROTON_MAGNETIC_MOMENT: f64 = GSL_CONST_CGSM_PROTON_MAGNETIC_MOMENT;
+    /// Thomson cross section `σT`, in cm^2
+    pub const THOMSON_CROSS_SECTION: f64 = GSL_CONST_CGSM_THOMSON_CROSS_SECTION;
+
+    // Measurement of time
+
+    /// Number of seconds in 1 minute, in s
+    pub const MINUTE: f64 = GSL_CONST_CGSM_MINUTE;
+    /// Number of seconds in 1 hour, in s
+    pub const HOUR: f64 = GSL_CONST_CGSM_HOUR;
+    /// Number of seconds in 1 day, in s
+    pub const DAY: f64 = GSL_CONST_CGSM_DAY;
+    /// Number of seconds in 1 week, in s
+    pub const WEEK: f64 = GSL_CONST_CGSM_WEEK;
+
+    // Imperial units
+
+    /// Length of 1 inch, in cm
+    pub const INCH: f64 = GSL_CONST_CGSM_INCH;
+    /// Length of 1 foot, in cm
+    pub const FOOT: f64 = GSL_CONST_CGSM_FOOT;
+    /// Length of 1 yard, in cm
+    pub const YARD: f64 = GSL_CONST_CGSM_YARD;
+    /// Length of 1 mile, in cm
+    pub const MILE: f64 = GSL_CONST_CGSM_MILE;
+    /// Length of 1 mil, 1/1000th of an inch, in cm
+    pub const MIL: f64 = GSL_CONST_CGSM_MIL;
+
+    // Speed and nautical units
+
+    /// Speed of 1 kilometer per hour, in cm / s
+    pub const KILOMETERS_PER_HOUR: f64 = GSL_CONST_CGSM_KILOMETERS_PER_HOUR;
+    /// Speed of 1 mile per hour, in cm / s
+    pub const MILES_PER_HOUR: f64 = GSL_CONST_CGSM_MILES_PER_HOUR;
+    /// Length of 1 nautical mile, in cm
+    pub const NAUTICAL_MILE: f64 = GSL_CONST_CGSM_NAUTICAL_MILE;
+    /// Length of 1 fathom, in cm
+    pub const FATHOM: f64 = GSL_CONST_CGSM_FATHOM;
+    /// Speed of 1 knot, in cm / s
+    pub const KNOT: f64 = GSL_CONST_CGSM_KNOT;
+
+    // Printers units
+
+    /// Length of 1 printer's point, 1/72 inch, in cm
+    pub const POINT: f64 = GSL_CONST_CGSM_POINT;
+    /// Length of 1 TeX point, 1/72.27 inch, in cm
+    pub const TEXPOINT: f64 = GSL_CONST_CGSM_TEXPOINT;
+
+    // Volume, area and length
+
+    /// Length of 1 micron, in cm
+    pub const MICRON: f64 = GSL_CONST_CGSM_MICRON;
+    /// Area of 1 hectare, in cm^2
+    pub const HECTARE: f64 = GSL_CONST_CGSM_HECTARE;
+    /// Area of 1 acre, in cm^2
+    pub const ACRE: f64 = GSL_CONST_CGSM_ACRE;
+    /// Volume of 1 liter, in cm^3
+    pub const LITER: f64 = GSL_CONST_CGSM_LITER;
+    /// Volume of 1 US gallon, in cm^3
+    pub const US_GALLON: f64 = GSL_CONST_CGSM_US_GALLON;
+    /// Volume of 1 Canadian gallon, in cm^3
+    pub const CANADIAN_GALLON: f64 = GSL_CONST_CGSM_CANADIAN_GALLON;
+    /// Volume of 1 UK gallon, in cm^3
+    pub const UK_GALLON: f64 = GSL_CONST_CGSM_UK_GALLON;
+    /// Volume of 1 quart, in cm^3
+    pub const QUART: f64 = GSL_CONST_CGSM_QUART;
+    /// Volume of 1 pint, in cm^3
+    pub const PINT: f64 = GSL_CONST_CGSM_PINT;
+
+    // Mass and weight
+
+    /// Mass of 1 pound, in g
+    pub const POUND_MASS: f64 = GSL_CONST_CGSM_POUND_MASS;
+    /// Mass of 1 ounce, in g
+    pub const OUNCE_MASS: f64 = GSL_CONST_CGSM_OUNCE_MASS;
+    /// Mass of 1 ton, in g
+    pub const TON: f64 = GSL_CONST_CGSM_TON;
+    /// Mass of 1 metric ton, 1000 kg, in g
+    pub const METRIC_TON: f64 = GSL_CONST_CGSM_METRIC_TON;
+    /// Mass of 1 UK ton, in g
+    pub const UK_TON: f64 = GSL_CONST_CGSM_UK_TON;
+    /// Mass of 1 troy ounce, in g
+    pub const TROY_OUNCE: f64 = GSL_CONST_CGSM_TROY_OUNCE;
+    /// Mass of 1 carat, in g
+    pub const CARAT: f64 = GSL_CONST_CGSM_CARAT;
+    /// Force of 1 gram weight, in g cm / s^2
+    pub const GRAM_FORCE: f64 = GSL_CONST_CGSM_GRAM_FORCE;
+    /// Force of 1 pound weight, in g cm / s^2
+    pub const POUND_FORCE: f64 = GSL_CONST_CGSM_POUND_FORCE;
+    /// Force of 1 kilopound weight, in g cm / s^2
+    pub const KILOPOUND_FORCE: f64 = GSL_CONST_CGSM_KILOPOUND_FORCE;
+    /// Force of 1 poundal, in g cm / s^2
+    pub const POUNDAL: f64 = GSL_CONST_CGSM_POUNDAL;
+
+    // Thermal energy and power
+
+    /// Energy of 1 calorie, in g cm^2 / s^2
+    pub const CALORIE: f64 = GSL_CONST_CGSM_CALORIE;
+    /// Energy of 1 British Thermal Unit, in g cm^2 / s^2
+    pub const BTU: f64 = GSL_CONST_CGSM_BTU;
+    /// Energy of 1 Therm, in g cm^2 / s^2
+    pub const THERM: f64 = GSL_CONST_CGSM_THERM;
+    /// Power of 1 horsepower, in g cm^2 / s^3
+    pub const HORSEPOWER: f64 = GSL_CONST_CGSM_HORSEPOWER;
+
+    // Pressure
+
+    /// Pressure of 1 bar, in g / cm s^2
+    pub const BAR: f64 = GSL_CONST_CGSM_BAR;
+    /// Pressure of 1 standard atmosphere, in g / cm s^2
+    pub const STD_ATMOSPHERE: f64 = GSL_CONST_CGSM_STD_ATMOSPHERE;
+    /// Pressure of 1 torr, in g / cm s^2
+    pub const TORR: f64 = GSL_CONST_CGSM_TORR;
+    /// Pressure of 1 meter of mercury, in g / cm s^2
+    pub const METER_OF_MERCURY: f64 = GSL_CONST_CGSM_METER_OF_MERCURY;
+    /// Pressure of 1 inch of mercury, in g / cm s^2
+    pub const INCH_OF_MERCURY: f64 = GSL_CONST_CGSM_INCH_OF_MERCURY;
+    /// Pressure of 1 inch of water, in g / cm s^2
+    pub const INCH_OF_WATER: f64 = GSL_CONST_CGSM_INCH_OF_WATER;
+    /// Pressure of 1 pound per square inch, in g / cm s^2
+    pub const PSI: f64 = GSL_CONST_CGSM_PSI;
+
+    // Viscosity
+
+    /// Dynamic viscosity of 1 poise, in g / cm s
+    pub const POISE: f64 = GSL_CONST_CGSM_POISE;
+    /// Kinematic viscosity of 1 stokes, in cm^2 / s
+    pub const STOKES: f64 = GSL_CONST_CGSM_STOKES;
+
+    // Light and illumination
+
+    /// Luminance of 1 stilb, in cd / cm^2
+    pub const STILB: f64 = GSL_CONST_CGSM_STILB;
+    /// Luminous flux of 1 lumen, in cd sr
+    pub const LUMEN: f64 = GSL_CONST_CGSM_LUMEN;
+    /// Illuminance of 1 lux, in cd sr / cm^2
+    pub const LUX: f64 = GSL_CONST_CGSM_LUX;
+    /// Illuminance of 1 phot, in cd sr / cm^2
+    pub const PHOT: f64 = GSL_CONST_CGSM_PHOT;
+    /// Illuminance of 1 footcandle, in cd sr / cm^2
+    pub const FOOTCANDLE: f64 = GSL_CONST_CGSM_FOOTCANDLE;
+    /// Luminance of 1 lambert, in cd sr / cm^2
+    pub const LAMBERT: f64 = GSL_CONST_CGSM_LAMBERT;
+    /// Luminance of 1 footlambert, in cd sr / cm^2
+    pub const FOOTLAMBERT: f64 = GSL_CONST_CGSM_FOOTLAMBERT;
+
+    // Radioactivity
+
+    /// Activity of 1 curie, in 1 / s
+    pub const CURIE: f64 = GSL_CONST_CGSM_CURIE;
+    /// Exposure of 1 roentgen, in abA s / g
+    pub const ROENTGEN: f64 = GSL_CONST_CGSM_ROENTGEN;
+    /// Absorbed dose of 1 rad, in cm^2 / s^2
+    pub const RAD: f64 = GSL_CONST_CGSM_RAD;
+
+    // Force and energy
+
+    /// SI unit of force, 1 newton, in g cm / s^2
+    pub const NEWTON: f64 = GSL_CONST_CGSM_NEWTON;
+    /// Force of 1 dyne, in g cm / s^2
+    pub const DYNE: f64 = GSL_CONST_CGSM_DYNE;
+    /// SI unit of energy, 1 joule, in g cm^2 / s^2
+    pub const JOULE: f64 = GSL_CONST_CGSM_JOULE;
+    /// Energy of 1 erg, in g cm^2 / s^2
+    pub const ERG: f64 = GSL_CONST_CGSM_ERG;
+}
+
+/// Dimensionless constants and SI prefixes
+pub mod num {
+    use crate::bindings::*;
+
+    /// Fine structure constant `α`
+    pub const FINE_STRUCTURE: f64 = GSL_CONST_NUM_FINE_STRUCTURE;
+    /// Avogadro's number `Na`, in 1 / mol
+    pub const AVOGADRO: f64 = GSL_CONST_NUM_AVOGADRO;
+    /// Prefix yotta, `10^24`
+    pub const YOTTA: f64 = GSL_CONST_NUM_YOTTA;
+    /// Prefix zetta, `10^21`
+    pub const ZETTA: f64 = GSL_CONST_NUM_ZETTA;
+    /// Prefix exa, `10^18`
+    pub const EXA: f64 = GSL_CONST_NUM_EXA;
+    /// Prefix peta, `10^15`
+    pub const PETA: f64 = GSL_CONST_NUM_PETA;
+    /// Prefix tera, `10^12`
+    pub const TERA: f64 = GSL_CONST_NUM_TERA;
+    /// Prefix giga, `10^9`
+    pub const GIGA: f64 = GSL_CONST_NUM_GIGA;
+    /// Prefix mega, `10^6`
+    pub const MEGA: f64 = GSL_CONST_NUM_MEGA;
+    /// Prefix kilo, `10^3`
+    pub const KILO: f64 = GSL_CONST_NUM_KILO;
+    /// Prefix milli, `10^-3`
+    pub const MILLI: f64 = GSL_CONST_NUM_MILLI;
+    /// Prefix micro, `10^-6`
+    pub const MICRO: f64 = GSL_CONST_NUM_MICRO;
+    /// Prefix nano, `10^-9`
+    pub const NANO: f64 = GSL_CONST_NUM_NANO;
+    /// Prefix pico, `10^-12`
+    pub const PICO: f64 = GSL_CONST_NUM_PICO;
+    /// Prefix femto, `10^-15`
+    pub const FEMTO: f64 = GSL_CONST_NUM_FEMTO;
+    /// Prefix atto, `10^-18`
+    pub const ATTO: f64 = GSL_CONST_NUM_ATTO;
+    /// Prefix zepto, `10^-21`
+    pub const ZEPTO: f64 = GSL_CONST_NUM_ZEPTO;
+    /// Prefix yocto, `10^-24`
+    pub const YOCTO: f64 = GSL_CONST_NUM_YOCTO;
+}
+
+#[test]
+fn test_consts() {
+    // Exact by definition
+    assert_eq!(mksa::SPEED_OF_LIGHT, 2.99792458e8);
+    assert_eq!(cgsm::SPEED_OF_LIGHT, 2.99792458e10);
+    assert_eq!(mksa::MINUTE, 60.0);
+    assert_eq!(mksa::INCH, 0.0254);
+    assert_eq!(num::KILO, 1.0e3);
+    assert_eq!(mksa::NEWTON, 1.0);
+    approx::assert_relative_eq!(mksa::DYNE, 1.0e-5, max_relative = 1.0e-15);
+
+    // The systems agree
+    approx::assert_relative_eq!(cgsm::INCH, 2.54, max_relative = 1.0e-15);
+    approx::assert_relative_eq!(
+        cgsm::MASS_ELECTRON,
+        1.0e3 * mksa::MASS_ELECTRON,
+        max_relative = 1.0e-15
+    );
+    approx::assert_relative_eq!(
+        cgsm::ELECTRON_CHARGE,
+        0.1 * mksa::ELECTRON_CHARGE,
+        max_relative = 1.0e-15
+    );
+
+    // Relations between constants
+    approx::assert_relative_eq!(
+        2.0 * std::f64::consts::PI * mksa::PLANCKS_CONSTANT_HBAR,
+        mksa::PLANCKS_CONSTANT_H,
+        max_relative = 1.0e-9
+    );
+    approx::assert_relative_eq!(
+        num::AVOGADRO * mksa::BOLTZMANN,
+        mksa::MOLAR_GAS,
+        max_relative = 1.0e-6
+    );
+    approx::assert_relative_eq!(
+        mksa::VACUUM_PERMITTIVITY * mksa::VACUUM_PERMEABILITY * mksa::SPEED_OF_LIGHT.powi(2),
+        1.0,
+        max_relative = 1.0e-9
+    );
+}
@@ -0,0 +1,651 @@
+/*
+    cubature.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::*;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::f64::consts::PI;
+
+/// Integrates `f` over the hyper-rectangle `[lower, upper]` using a Smolyak sparse grid.
+/// See `SparseGrid`.
+pub fn sparse_grid<F: FnMut(&[f64]) -> f64>(
+    level: usize,
+    lower: &[f64],
+    upper: &[f64],
+    f: F,
+) -> Result<f64> {
+    SparseGrid::new(lower.len(), level)?.integrate(lower, upper, f)
+}
+
+/// Smolyak sparse grid built from nested Clenshaw-Curtis rules on `[-1, 1]^dim`.
+///
+/// Level `l` uses one dimensional rules with up to `2^(l - 1) + 1` points,
+/// and integrates polynomials of total degree `2l - 1` exactly.
+/// The number of points grows only polynomially with the dimension,
+/// which makes this suitable for smooth integrands in roughly 3 to 10 dimensions.
+#[derive(Clone, Debug)]
+pub struct SparseGrid {
+    dim: usize,
+    level: usize,
+    /// Row major, one point per row
+    nodes: Box<[f64]>,
+    weights: Box<[f64]>,
+}
+
+impl SparseGrid {
+    pub const MAX_LEVEL: usize = 16;
+
+    pub fn new(dim: usize, level: usize) -> Result<Self> {
+        if dim == 0 || level == 0 || level > Self::MAX_LEVEL {
+            return Err(GSLError::Invalid);
+        }
+
+        // Nodes are identified by their index on the finest one dimensional grid,
+        // such that shared points of the nested rules are merged.
+        let rules = (1..=level).map(clenshaw_curtis).collect::<Vec<_>>();
+        let finest = rules[level - 1].0.len() - 1;
+
+        // Combination technique, with q = dim + level - 1:
+        // sum over q - dim + 1 <= |i| <= q of (-1)^(q - |i|) binom(dim - 1, q - |i|) U^i_1 x ... x U^i_dim
+        let q = dim + level - 1;
+        let mut points = BTreeMap::<Vec<usize>, f64>::new();
+        for_each_multi_index(dim, level, &mut |index| {
+            let norm = index.iter().sum::<usize>();
+            if norm + dim <= q {
+                return;
+            }
+            let coefficient = (-1.0f64).powi((q - norm) as i32) * binomial(dim - 1, q - norm);
+
+            // Iterate over the tensor product of the selected rules
+            let sizes = index
+                .iter()
+                .map(|&l| rules[l - 1].0.len())
+                .collect::<Vec<_>>();
+            let mut j = vec![0; dim];
+            loop {
+                let mut key = Vec::with_capacity(dim);
+                let mut weight = coefficient;
+                for k in 0..dim {
+                    let m = sizes[k];
+                    key.push(if m == 1 {
+                        finest / 2
+                    } else {
+                        j[k] * finest / (m - 1)
+                    });
+                    weight *= rules[index[k] - 1].1[j[k]];
+                }
+                *points.entry(key).or_insert(0.0) += weight;
+
+                // Advance the odometer
+                let mut k = 0;
+                while k < dim {
+                    j[k] += 1;
+                    if j[k] < sizes[k] {
+                        break;
+                    }
+                    j[k] = 0;
+                    k += 1;
+                }
+                if k == dim {
+                    break;
+                }
+            }
+        });
+
+        let finest_nodes = &rules[level - 1].0;
+        let mut nodes = Vec::with_capacity(points.len() * dim);
+        let mut weights = Vec::with_capacity(points.len());
+        for (key, weight) in points {
+            // Cancellation in the combination technique can leave points with zero weight
+            if weight == 0.0 {
+                continue;
+            }
+            nodes.extend(key.iter().map(|&i| finest_nodes[i]));
+            weights.push(weight);
+        }
+
+        Ok(SparseGrid {
+            dim,
+            level,
+            nodes: nodes.into_boxed_slice(),
+            weights: weights.into_boxed_slice(),
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Number of points
+    pub fn n(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Points on `[-1, 1]^dim`, row major with one point per row
+    pub fn nodes(&self) -> &[f64] {
+        &self.nodes
+    }
+
+    /// Weights for `[-1, 1]^dim`, which sum to `2^dim`. Some weights are negative.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Integrates `f` over the hyper-rectangle `[lower, upper]`.
+    pub fn integrate<F: FnMut(&[f64]) -> f64>(
+        &self,
+        lower: &[f64],
+        upper: &[f64],
+        mut f: F,
+    ) -> Result<f64> {
+        if lower.len() != self.dim || upper.len() != self.dim {
+            return Err(GSLError::BadLength);
+        }
+        if lower
+            .iter()
+            .zip(upper)
+            .any(|(a, b)| b <= a || a.is_nan() || b.is_nan())
+        {
+            return Err(GSLError::Domain);
+        }
+
+        let half_width = lower
+            .iter()
+            .zip(upper)
+            .map(|(a, b)| 0.5 * (b - a))
+            .collect::<Vec<_>>();
+        let center = lower
+            .iter()
+            .zip(upper)
+            .map(|(a, b)| 0.5 * (a + b))
+            .collect::<Vec<_>>();
+        let volume = half_width.iter().product::<f64>();
+
+        let mut x = vec![0.0; self.dim];
+        let mut sum = 0.0;
+        for (node, weight) in self.nodes.chunks_exact(self.dim).zip(self.weights.iter()) {
+            for k in 0..self.dim {
+                x[k] = center[k] + half_width[k] * node[k];
+            }
+            sum += weight * f(&x);
+        }
+
+        if !sum.is_finite() {
+            return Err(GSLError::BadFunction);
+        }
+
+        Ok(volume * sum)
+    }
+}
+
+/// Integrates `f` over the hyper-rectangle `[lower, upper]`, of at least 2 dimensions,
+/// using adaptive subdivision with the Genz-Malik rule. See `adaptive_ext`.
+/// Uses the tolerances of the `NumericPolicy` and its `max_iter` as the maximum amount of evaluations,
+/// by default `epsabs = 1e-9`, `epsrel = 1e-6` and 10⁷ evaluations.
+pub fn adaptive<F: FnMut(&[f64]) -> f64>(
+    lower: &[f64],
+    upper: &[f64],
+    f: F,
+) -> Result<ValWithError<f64>> {
+    adaptive_with_policy(policy::policy(), lower, upper, f)
+}
+
+/// `adaptive` with `policy` instead of the policy of this thread
+pub fn adaptive_with_policy<F: FnMut(&[f64]) -> f64>(
+    policy: policy::NumericPolicy,
+    lower: &[f64],
+    upper: &[f64],
+    mut f: F,
+) -> Result<ValWithError<f64>> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-9, 1.0e-6);
+        let max_eval = policy.iterations(10_000_000);
+        let result = adaptive_ext(1, lower, upper, epsabs, epsrel, max_eval, |x, y| {
+            y[0] = f(x)
+        })?;
+        Ok(result[0])
+    })
+}
+
+/// Integrates the vector valued function `f`, with `fdim` components, over the hyper-rectangle `[lower, upper]`.
+/// The function writes its value at `x` into `y`.
+///
+/// The region with the largest error is repeatedly bisected along the dimension in which the integrand varies most,
+/// until every component satisfies `err <= max(epsabs, epsrel * |val|)`.
+/// Every region is integrated with the degree 7 Genz-Malik rule, using an embedded degree 5 rule for the error estimate.
+/// For one dimensional integrands, use the `integration` module instead.
+///
+/// Fails with `GSLError::MaxIteration` if the tolerance is not reached within `max_eval` function evaluations.
+pub fn adaptive_ext<F: FnMut(&[f64], &mut [f64])>(
+    fdim: usize,
+    lower: &[f64],
+    upper: &[f64],
+    epsabs: f64,
+    epsrel: f64,
+    max_eval: usize,
+    mut f: F,
+) -> Result<Box<[ValWithError<f64>]>> {
+    let dim = lower.len();
+    if dim < 2 || fdim == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if upper.len() != dim {
+        return Err(GSLError::BadLength);
+    }
+    if lower
+        .iter()
+        .zip(upper)
+        .any(|(a, b)| b <= a || a.is_nan() || b.is_nan())
+    {
+        return Err(GSLError::Domain);
+    }
+    if epsabs <= 0.0 && epsrel <= 0.0 {
+        return Err(GSLError::BadTolerance);
+    }
+
+    let rule = GenzMalik::new(dim, fdim);
+    let center = lower
+        .iter()
+        .zip(upper)
+        .map(|(a, b)| 0.5 * (a + b))
+        .collect::<Vec<_>>();
+    let half_width = lower
+        .iter()
+        .zip(upper)
+        .map(|(a, b)| 0.5 * (b - a))
+        .collect::<Vec<_>>();
+
+    let mut regions = BinaryHeap::new();
+    regions.push(rule.evaluate(center, half_width, &mut f)?);
+    let mut evaluations = rule.points;
+
+    let mut val = regions.peek().unwrap().val.clone();
+    let mut err = regions.peek().unwrap().err.clone();
+
+    loop {
+        let converged = val
+            .iter()
+            .zip(err.iter())
+            .all(|(val, err)| *err <= epsabs.max(epsrel * val.abs()));
+        if converged {
+            break;
+        }
+        if evaluations + 2 * rule.points > max_eval {
+            policy::converged(Err(GSLError::MaxIteration))?;
+            break;
+        }
+
+        // Bisect the worst region
+        let region = regions.pop().unwrap();
+        let mut half_width = region.half_width.clone();
+        half_width[region.split] *= 0.5;
+        let mut left = region.center.clone();
+        left[region.split] -= half_width[region.split];
+        let mut right = region.center.clone();
+        right[region.split] += half_width[region.split];
+
+        let left = rule.evaluate(left, half_width.clone(), &mut f)?;
+        let right = rule.evaluate(right, half_width, &mut f)?;
+        evaluations += 2 * rule.points;
+
+        for i in 0..fdim {
+            val[i] += left.val[i] + right.val[i] - region.val[i];
+            err[i] += left.err[i] + right.err[i] - region.err[i];
+        }
+        regions.push(left);
+        regions.push(right);
+    }
+
+    // Sum again to avoid accumulated rounding errors from the updates
+    let mut result = vec![ValWithError { val: 0.0, err: 0.0 }; fdim];
+    for region in regions.iter() {
+        for (result, (val, err)) in result.iter_mut().zip(region.val.iter().zip(&region.err)) {
+            result.val += val;
+            result.err += err;
+        }
+    }
+
+    Ok(result.into_boxed_slice())
+}
+
+/// Degree 7 rule with an embedded degree 5 rule, see
+/// A.C. Genz and A.A. Malik, "An adaptive algorithm for numerical integration over an n-dimensional rectangular region",
+/// J. Comput. Appl. Math. 6 (1980).
+struct GenzMalik {
+    dim: usize,
+    fdim: usize,
+    points: usize,
+    /// Degree 7 weights for the 5 point classes
+    weights: [f64; 5],
+    /// Degree 5 weights for the first 4 point classes
+    weights_embedded: [f64; 4],
+}
+
+const LAMBDA_2: f64 = 0.358_568_582_800_318_1; // sqrt(9 / 70)
+const LAMBDA_4: f64 = 0.948_683_298_050_513_8; // sqrt(9 / 10)
+const LAMBDA_5: f64 = 0.688_247_201_611_685_3; // sqrt(9 / 19)
+
+impl GenzMalik {
+    fn new(dim: usize, fdim: usize) -> Self {
+        let n = dim as f64;
+        GenzMalik {
+            dim,
+            fdim,
+            points: 1 + 4 * dim + 2 * dim * (dim - 1) + (1 << dim),
+            weights: [
+                (12824.0 - 9120.0 * n + 400.0 * n * n) / 19683.0,
+                980.0 / 6561.0,
+                (1820.0 - 400.0 * n) / 19683.0,
+                200.0 / 19683.0,
+                6859.0 / 19683.0 / (1u64 << dim) as f64,
+            ],
+            weights_embedded: [
+                (729.0 - 950.0 * n + 50.0 * n * n) / 729.0,
+                245.0 / 486.0,
+                (265.0 - 100.0 * n) / 1458.0,
+                25.0 / 729.0,
+            ],
+        }
+    }
+
+    fn evaluate<F: FnMut(&[f64], &mut [f64])>(
+        &self,
+        center: Vec<f64>,
+        half_width: Vec<f64>,
+        f: &mut F,
+    ) -> Result<Region> {
+        let (dim, fdim) = (self.dim, self.fdim);
+        let mut sums = vec![vec![0.0; fdim]; 5];
+        let mut y = vec![0.0; fdim];
+        let mut x = center.clone();
+
+        let mut eval = |x: &[f64], sum: &mut [f64]| -> Result<()> {
+            f(x, &mut y);
+            if y.iter().any(|y| !y.is_finite()) {
+                return Err(GSLError::BadFunction);
+            }
+            blas::axpy(1.0, &y, sum)?;
+            Ok(())
+        };
+
+        eval(&x, &mut sums[0])?;
+        let f_center = sums[0].clone();
+
+        // Fourth differences along every axis decide the split direction
+        let mut split = 0;
+        let mut max_difference = -1.0;
+        for i in 0..dim {
+            let mut s2 = vec![0.0; fdim];
+            let mut s3 = vec![0.0; fdim];
+            for (lambda, s) in [(LAMBDA_2, &mut s2), (LAMBDA_4, &mut s3)] {
+                x[i] = center[i] - lambda * half_width[i];
+                eval(&x, s)?;
+                x[i] = center[i] + lambda * half_width[i];
+                eval(&x, s)?;
+            }
+            x[i] = center[i];
+
+            let difference = (0..fdim)
+                .map(|k| {
+                    let d2 = s2[k] - 2.0 * f_center[k];
+                    let d3 = s3[k] - 2.0 * f_center[k];
+                    (d2 - d3 * (LAMBDA_2 / LAMBDA_4).powi(2)).abs()
+                })
+                .sum::<f64>();
+            if difference > max_difference
+                || (difference == max_difference && half_width[i] > half_width[split])
+            {
+                split = i;
+                max_difference = difference;
+            }
+
+            blas::axpy(1.0, &s2, &mut sums[1])?;
+            blas::axpy(1.0, &s3, &mut sums[2])?;
+        }
+
+        for i in 0..dim {
+            for j in i + 1..dim {
+                for (si, sj) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                    x[i] = center[i] + si * LAMBDA_4 * half_width[i];
+                    x[j] = center[j] + sj * LAMBDA_4 * half_width[j];
+                    eval(&x, &mut sums[3])?;
+                }
+                x[j] = center[j];
+            }
+            x[i] = center[i];
+        }
+
+        for corner in 0..1usize << dim {
+            for i in 0..dim {
+                let sign = if corner & (1 << i) == 0 { -1.0 } else { 1.0 };
+                x[i] = center[i] + sign * LAMBDA_5 * half_width[i];
+            }
+            eval(&x, &mut sums[4])?;
+        }
+
+        let volume = half_width.iter().map(|h| 2.0 * h).product::<f64>();
+        let mut val = vec![0.0; fdim];
+        let mut err = vec![0.0; fdim];
+        for k in 0..fdim {
+            let degree_7 = (0..5).map(|c| self.weights[c] * sums[c][k]).sum::<f64>();
+            let degree_5 = (0..4)
+                .map(|c| self.weights_embedded[c] * sums[c][k])
+                .sum::<f64>();
+            val[k] = volume * degree_7;
+            err[k] = volume * (degree_7 - degree_5).abs();
+        }
+
+        Ok(Region {
+            center,
+            half_width,
+            split,
+            val,
+            err,
+        })
+    }
+}
+
+/// Subregion ordered by its largest error
+struct Region {
+    center: Vec<f64>,
+    half_width: Vec<f64>,
+    split: usize,
+    val: Vec<f64>,
+    err: Vec<f64>,
+}
+
+impl Region {
+    fn max_err(&self) -> f64 {
+        self.err.iter().copied().fold(0.0, f64::max)
+    }
+}
+
+impl PartialEq for Region {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Region {}
+
+impl PartialOrd for Region {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Region {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.max_err().total_cmp(&other.max_err())
+    }
+}
+
+/// Clenshaw-Curtis nodes and weights on `[-1, 1]` for the given level,
+/// using 1 point for level 1 and `2^(level - 1) + 1` points otherwise.
+fn clenshaw_curtis(level: usize) -> (Vec<f64>, Vec<f64>) {
+    if level == 1 {
+        return (vec![0.0], vec![2.0]);
+    }
+
+    let n = 1 << (level - 1);
+    let nodes = (0..=n)
+        .map(|j| {
+            // Exact symmetry around the center
+            if 2 * j == n {
+                0.0
+            } else {
+                -(PI * j as f64 / n as f64).cos()
+            }
+        })
+        .collect();
+    let weights = (0..=n)
+        .map(|j| {
+            let c = if j == 0 || j == n { 1.0 } else { 2.0 };
+            let sum = (1..=n / 2)
+                .map(|k| {
+                    let b = if 2 * k == n { 1.0 } else { 2.0 };
+                    b / (4.0 * (k * k) as f64 - 1.0) * (2.0 * PI * (k * j) as f64 / n as f64).cos()
+                })
+                .sum::<f64>();
+            c / n as f64 * (1.0 - sum)
+        })
+        .collect();
+
+    (nodes, weights)
+}
+
+/// Calls `f` for every multi index in `[1, max]^dim` with `|i| <= dim + max - 1`
+fn for_each_multi_index<F: FnMut(&[usize])>(dim: usize, max: usize, f: &mut F) {
+    fn recurse<F: FnMut(&[usize])>(index: &mut Vec<usize>, dim: usize, budget: usize, f: &mut F) {
+        if index.len() == dim {
+            f(index);
+            return;
+        }
+        // Every remaining entry needs at least 1
+        let remaining = dim - index.len() - 1;
+        for l in 1..=budget - remaining {
+            index.push(l);
+            recurse(index, dim, budget - l, f);
+            index.pop();
+        }
+    }
+
+    recurse(&mut Vec::with_capacity(dim), dim, dim + max - 1, f);
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+#[test]
+fn test_clenshaw_curtis() {
+    for level in 1..=6 {
+        let (nodes, weights) = clenshaw_curtis(level);
+        approx::assert_abs_diff_eq!(weights.iter().sum::<f64>(), 2.0, epsilon = 1.0e-14);
+
+        // Exact for polynomials up to degree n
+        let n = nodes.len() - 1;
+        for degree in (0..=n).step_by(2) {
+            let integral = nodes
+                .iter()
+                .zip(&weights)
+                .map(|(x, w)| w * x.powi(degree as i32))
+                .sum::<f64>();
+            approx::assert_abs_diff_eq!(integral, 2.0 / (degree + 1) as f64, epsilon = 1.0e-14);
+        }
+    }
+}
+
+#[test]
+fn test_sparse_grid() {
+    // Level 2 adds the two boundary points in every direction
+    let grid = SparseGrid::new(3, 2).unwrap();
+    assert_eq!(grid.n(), 7);
+    approx::assert_abs_diff_eq!(grid.weights().iter().sum::<f64>(), 8.0, epsilon = 1.0e-12);
+
+    // Exact for total degree 2 * level - 1
+    let integral = sparse_grid(3, &[0.0; 4], &[1.0; 4], |x| {
+        x[0] * x[0] * x[1] * x[2] * x[3] + x[1].powi(4) + x[2].powi(5)
+    })
+    .unwrap();
+    approx::assert_abs_diff_eq!(integral, 1.0 / 24.0 + 0.2 + 1.0 / 6.0, epsilon = 1.0e-13);
+
+    // Smooth integrand in 6 dimensions
+    let dim = 6;
+    let exact = (1.0f64.exp() - 1.0).powi(dim as i32);
+    let grid = SparseGrid::new(dim, 7).unwrap();
+    let integral = grid
+        .integrate(&[0.0; 6], &[1.0; 6], |x| x.iter().sum::<f64>().exp())
+        .unwrap();
+    approx::assert_relative_eq!(integral, exact, max_relative = 1.0e-9);
+
+    // Level 7 uses 65 points per dimension, and the sparse grid has fewer
+    // points than even a 3-dimensional tensor product of those
+    assert!(grid.n() < 65usize.pow(3));
+}
+
+#[test]
+fn test_adaptive() {
+    // Gaussian peak in 3 dimensions
+    let result = adaptive(&[-5.0; 3], &[5.0; 3], |x| {
+        (-x.iter().map(|x| x * x).sum::<f64>()).exp()
+    })
+    .unwrap();
+    approx::assert_relative_eq!(result.val, PI.powf(1.5), max_relative = 1.0e-6);
+    assert!(result.err <= 1.0e-6 * result.val);
+
+    // Vector integrand with a relative tolerance
+    let result = adaptive_ext(2, &[0.0; 2], &[1.0, 2.0], 0.0, 1.0e-8, 1_000_000, |x, y| {
+        y[0] = x[0] * x[1];
+        y[1] = (x[0] + x[1]).sin();
+    })
+    .unwrap();
+    approx::assert_abs_diff_eq!(result[0].val, 1.0, epsilon = 1.0e-12);
+    let exact = 1.0f64.sin() + 2.0f64.sin() - 3.0f64.sin();
+    approx::assert_relative_eq!(result[1].val, exact, max_relative = 1.0e-8);
+}
+
+#[test]
+fn test_invalid_params() {
+    // Dimension mismatch
+    sparse_grid(2, &[0.0; 2], &[1.0; 3], |_| 1.0).unwrap_err();
+
+    // Empty domain
+    sparse_grid(2, &[0.0; 2], &[1.0, 0.0], |_| 1.0).unwrap_err();
+
+    // Zero level or dimension
+    SparseGrid::new(3, 0).unwrap_err();
+    SparseGrid::new(0, 3).unwrap_err();
+
+    // Non finite function
+    sparse_grid(2, &[0.0; 2], &[1.0; 2], |_| f64::NAN).unwrap_err();
+    adaptive(&[0.0; 2], &[1.0; 2], |_| f64::NAN).unwrap_err();
+
+    // One dimensional
+    adaptive(&[0.0], &[1.0], |x| x[0]).unwrap_err();
+
+    // Singular integrand which cannot reach the tolerance
+    adaptive_ext(1, &[0.0; 2], &[1.0; 2], 1.0e-12, 0.0, 10_000, |x, y| {
+        y[0] = 1.0 / (x[0] * x[0] + x[1] * x[1]).sqrt()
+    })
+    .unwrap_err();
+}
@@ -24,8 +24,10 @@ use std::iter::FromIterator;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
-pub(crate) struct Vector {
+/// Owned vector that GSL functions use in place, see `as_gsl`
+pub struct Vector {
     // We own this data on the heap via Box.
     // It is stored as a pointer to avoid aliasing issues when handing out a *mut
     // Also, we store the gsl field on the heap to avoid accidentally moving the Vector
@@ -95,6 +97,12 @@ impl FromIterator<f64> for Vector {
     }
 }
 
+impl From<Vector> for Vec<f64> {
+    fn from(v: Vector) -> Self {
+        v.to_vec()
+    }
+}
+
 impl Deref for Vector {
     type Target = [f64];
 
@@ -122,6 +130,10 @@ impl Clone for Vector {
     }
 }
 
+// Owns its data like a Box<[f64]>
+unsafe impl Send for Vector {}
+unsafe impl Sync for Vector {}
+
 impl Drop for Vector {
     fn drop(&mut self) {
         unsafe {
@@ -132,7 +144,9 @@ impl Drop for Vector {
     }
 }
 
-pub(crate) struct Matrix {
+/// Owned row major matrix that GSL functions use in place, see `as_gsl`.
+/// The constructors and the block operations panic on mismatched sizes, like slice indexing.
+pub struct Matrix {
     // We own this data on the heap via Box.
     // It is stored as a pointer to avoid aliasing issues when handing out a *mut
     // Also, we store the gsl field on the heap to avoid accidentally moving the Vector
@@ -240,6 +254,154 @@ impl Matrix {
     pub fn as_gsl_mut(&mut self) -> *mut gsl_matrix {
         self.gsl
     }
+
+    /// `(m, n)`: column length and row length
+    pub fn size(&self) -> (usize, usize) {
+        (self.m, self.n)
+    }
+
+    /// Sets element `X_ij`, see `elem_ij`
+    pub fn set_ij(&mut self, i: usize, j: usize, x: f64) {
+        let n = self.n;
+        self.deref_mut()[i * n + j] = x;
+    }
+
+    /// `n` by `m` transpose
+    pub fn transpose(&self) -> Matrix {
+        unsafe {
+            let mut out = Matrix::zeroes(self.n, self.m);
+            gsl_matrix_transpose_memcpy(out.as_gsl_mut(), self.as_gsl());
+            out
+        }
+    }
+
+    /// Reinterprets the row major data as an `m` by `n` matrix
+    pub fn reshape(self, m: usize, n: usize) -> Matrix {
+        assert_eq!(m * n, self.m * self.n);
+        Matrix::new(self.to_boxed_slice().into_vec(), m, n)
+    }
+
+    /// Borrows the `m` by `n` block starting at `X_ij` without copying
+    pub fn submatrix(&self, i: usize, j: usize, m: usize, n: usize) -> MatrixView<'_> {
+        assert!(m > 0);
+        assert!(n > 0);
+        assert!(i + m <= self.m);
+        assert!(j + n <= self.n);
+
+        MatrixView {
+            gsl: gsl_matrix {
+                size1: m as u64,
+                size2: n as u64,
+                tda: self.n as u64, // Rows of the block are strided by the parent row length
+                data: unsafe { (self.data as *mut f64).add(i * self.n + j) },
+                block: std::ptr::null_mut(),
+                owner: 0,
+            },
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn row(&self, i: usize) -> Vector {
+        assert!(i < self.m);
+        unsafe {
+            let mut out = Vector::zeroes(self.n);
+            gsl_matrix_get_row(out.as_gsl_mut(), self.as_gsl(), i as u64);
+            out
+        }
+    }
+
+    pub fn col(&self, j: usize) -> Vector {
+        assert!(j < self.n);
+        unsafe {
+            let mut out = Vector::zeroes(self.m);
+            gsl_matrix_get_col(out.as_gsl_mut(), self.as_gsl(), j as u64);
+            out
+        }
+    }
+
+    pub fn set_row(&mut self, i: usize, row: &[f64]) {
+        assert!(i < self.m);
+        assert_eq!(row.len(), self.n);
+        unsafe {
+            let gsl_row = gsl_vector::from(row);
+            gsl_matrix_set_row(self.as_gsl_mut(), i as u64, &gsl_row);
+        }
+    }
+
+    pub fn set_col(&mut self, j: usize, col: &[f64]) {
+        assert!(j < self.n);
+        assert_eq!(col.len(), self.m);
+        unsafe {
+            let gsl_col = gsl_vector::from(col);
+            gsl_matrix_set_col(self.as_gsl_mut(), j as u64, &gsl_col);
+        }
+    }
+
+    /// Places the matrices side by side. All matrices must have the same column length.
+    pub fn hstack(matrices: &[&Matrix]) -> Matrix {
+        assert!(!matrices.is_empty());
+        let m = matrices[0].m;
+        assert!(matrices.iter().all(|x| x.m == m));
+
+        let n = matrices.iter().map(|x| x.n).sum();
+        let data = (0..m)
+            .flat_map(|i| {
+                matrices
+                    .iter()
+                    .flat_map(move |x| x.iter().skip(i * x.n).take(x.n).copied())
+            })
+            .collect::<Vec<_>>();
+        Matrix::new(data, m, n)
+    }
+
+    /// Places the matrices on top of each other. All matrices must have the same row length.
+    pub fn vstack(matrices: &[&Matrix]) -> Matrix {
+        assert!(!matrices.is_empty());
+        let n = matrices[0].n;
+        assert!(matrices.iter().all(|x| x.n == n));
+
+        let m = matrices.iter().map(|x| x.m).sum();
+        Matrix::new(matrices.iter().flat_map(|x| x.iter().copied()), m, n)
+    }
+}
+
+/// Borrowed block of a `Matrix`, see `Matrix::submatrix`
+pub struct MatrixView<'a> {
+    gsl: gsl_matrix,
+    _phantom: PhantomData<&'a [f64]>,
+}
+
+// Shares its data like a &[f64]
+unsafe impl Send for MatrixView<'_> {}
+unsafe impl Sync for MatrixView<'_> {}
+
+impl<'a> MatrixView<'a> {
+    pub fn size(&self) -> (usize, usize) {
+        (self.gsl.size1 as usize, self.gsl.size2 as usize)
+    }
+
+    pub fn elem_ij(&self, i: usize, j: usize) -> f64 {
+        let (m, n) = self.size();
+        assert!(i < m);
+        assert!(j < n);
+        unsafe { *self.gsl.data.add(i * self.gsl.tda as usize + j) }
+    }
+
+    /// Copies the block into a contiguous matrix
+    pub fn to_matrix(&self) -> Matrix {
+        let (m, n) = self.size();
+        Matrix::new(
+            (0..m)
+                .flat_map(|i| (0..n).map(move |j| (i, j)))
+                .map(|(i, j)| self.elem_ij(i, j)),
+            m,
+            n,
+        )
+    }
+
+    pub fn as_gsl(&self) -> *const gsl_matrix {
+        &self.gsl
+    }
 }
 
 impl fmt::Debug for Matrix {
@@ -248,6 +410,13 @@ impl fmt::Debug for Matrix {
     }
 }
 
+/// Row major elements
+impl From<Matrix> for Vec<f64> {
+    fn from(m: Matrix) -> Self {
+        m.to_vec()
+    }
+}
+
 impl Deref for Matrix {
     type Target = [f64];
 
@@ -281,6 +450,10 @@ impl<const M: usize, const N: usize> From<[[f64; N]; M]> for Matrix {
     }
 }
 
+// Owns its data like a Box<[f64]>
+unsafe impl Send for Matrix {}
+unsafe impl Sync for Matrix {}
+
 impl Drop for Matrix {
     fn drop(&mut self) {
         unsafe {
@@ -361,6 +534,21 @@ impl From<&[f64]> for gsl_vector {
     }
 }
 
+impl From<&mut [f64]> for gsl_vector {
+    fn from(data: &mut [f64]) -> Self {
+        assert!(!data.is_empty());
+
+        let size = data.len() as u64;
+        gsl_vector {
+            size,
+            stride: 1,
+            data: data.as_mut_ptr(),
+            block: std::ptr::null_mut(),
+            owner: 0,
+        }
+    }
+}
+
 impl gsl_matrix {
     pub fn from_slice(data: &[f64], m: usize, n: usize) -> Self {
         assert_eq!(m * n, data.len());
@@ -425,6 +613,7 @@ impl<const M: usize, const N: usize> From<&[[f64; N]; M]> for gsl_matrix {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValWithError<T> {
     pub val: T,
     pub err: T,
@@ -439,6 +628,144 @@ impl From<gsl_sf_result> for ValWithError<f64> {
     }
 }
 
+impl<T: Copy> ValWithError<T> {
+    pub fn value(&self) -> T {
+        self.val
+    }
+
+    /// Absolute error estimate
+    pub fn error(&self) -> T {
+        self.err
+    }
+
+    /// The value and the error
+    pub fn into_inner(self) -> (T, T) {
+        (self.val, self.err)
+    }
+
+    /// Applies `f` to both the value and the error, e.g. to change units or precision.
+    /// This is only correct for `f` linear in its argument.
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> ValWithError<U> {
+        ValWithError {
+            val: f(self.val),
+            err: f(self.err),
+        }
+    }
+}
+
+impl ValWithError<f64> {
+    /// `err / |val|`, which is infinite or NaN for a zero value
+    pub fn relative_error(&self) -> f64 {
+        self.err / self.val.abs()
+    }
+
+    /// Whether the values agree within their combined error estimates,
+    /// plus the relative tolerance `tol` of the largest value
+    pub fn approx_eq(&self, other: &Self, tol: f64) -> bool {
+        (self.val - other.val).abs()
+            <= self.err + other.err + tol * self.val.abs().max(other.val.abs())
+    }
+}
+
+impl<T> From<ValWithError<T>> for (T, T) {
+    fn from(x: ValWithError<T>) -> Self {
+        (x.val, x.err)
+    }
+}
+
+impl From<ValWithError<f64>> for f64 {
+    /// Drops the error
+    fn from(x: ValWithError<f64>) -> Self {
+        x.val
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for ValWithError<T> {
+    /// `val ± err`, with the formatting options applied to both
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.val.fmt(f)?;
+        write!(f, " ± ")?;
+        self.err.fmt(f)
+    }
+}
+
+/*
+
+    Arithmetic on values with an absolute error estimate, following GSL's special functions:
+    the errors add linearly to first order, and every operation adds the rounding error
+    `2 ε |result|`. The bounds stay conservative through chained calculations.
+    Multiplication agrees with `sf::multiply_err` up to rounding in the error estimate,
+    but only the latter checks for overflow and underflow.
+
+*/
+
+impl From<f64> for ValWithError<f64> {
+    /// Exact value without error
+    fn from(val: f64) -> Self {
+        ValWithError { val, err: 0.0 }
+    }
+}
+
+impl ValWithError<f64> {
+    fn rounded(val: f64, err: f64) -> Self {
+        ValWithError {
+            val,
+            err: err + 2.0 * f64::EPSILON * val.abs(),
+        }
+    }
+}
+
+impl Neg for ValWithError<f64> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        ValWithError {
+            val: -self.val,
+            err: self.err,
+        }
+    }
+}
+
+impl Add for ValWithError<f64> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::rounded(self.val + rhs.val, self.err + rhs.err)
+    }
+}
+
+impl Sub for ValWithError<f64> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::rounded(self.val - rhs.val, self.err + rhs.err)
+    }
+}
+
+impl Mul for ValWithError<f64> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::rounded(
+            self.val * rhs.val,
+            (self.err * rhs.val).abs() + (rhs.err * self.val).abs(),
+        )
+    }
+}
+
+impl Div for ValWithError<f64> {
+    type Output = Self;
+
+    /// The error is infinite if `rhs` is zero
+    fn div(self, rhs: Self) -> Self {
+        let val = self.val / rhs.val;
+        Self::rounded(
+            val,
+            (self.err.abs() + (val * rhs.err).abs()) / rhs.val.abs(),
+        )
+    }
+}
+
 #[test]
 fn test_gsl_vector_wrapper() {
     unsafe {
@@ -592,6 +919,65 @@ fn miri_test_gsl_matrix_wrapper() {
     }
 }
 
+#[test]
+fn test_matrix_manipulation() {
+    let x = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+
+    // Transpose
+    let t = x.transpose();
+    assert_eq!(t.size(), (3, 2));
+    assert_eq!(
+        t.to_2d_array::<3, 2>(),
+        [[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]]
+    );
+
+    // Reshape keeps the row major data
+    let r = x.clone().reshape(3, 2);
+    assert_eq!(
+        r.to_2d_array::<3, 2>(),
+        [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]
+    );
+
+    // Views honour the parent stride
+    let view = x.submatrix(0, 1, 2, 2);
+    assert_eq!(view.elem_ij(1, 0), 5.0);
+    assert_eq!(
+        view.to_matrix().to_2d_array::<2, 2>(),
+        [[2.0, 3.0], [5.0, 6.0]]
+    );
+    unsafe {
+        assert_eq!(gsl_matrix_get(view.as_gsl(), 1, 1), 6.0);
+    }
+
+    // Rows and columns
+    assert_eq!(x.row(1).to_array::<3>(), [4.0, 5.0, 6.0]);
+    assert_eq!(x.col(2).to_array::<2>(), [3.0, 6.0]);
+    let mut y = x.clone();
+    y.set_row(0, &[7.0, 8.0, 9.0]);
+    y.set_col(0, &[0.0, 0.0]);
+    y.set_ij(1, 1, -1.0);
+    assert_eq!(y.to_2d_array::<2, 3>(), [[0.0, 8.0, 9.0], [0.0, -1.0, 6.0]]);
+
+    // Concatenation
+    let h = Matrix::hstack(&[&x, &t.transpose()]);
+    assert_eq!(
+        h.to_2d_array::<2, 6>(),
+        [
+            [1.0, 2.0, 3.0, 1.0, 2.0, 3.0],
+            [4.0, 5.0, 6.0, 4.0, 5.0, 6.0]
+        ]
+    );
+    let v = Matrix::vstack(&[&x, &y]);
+    assert_eq!(v.size(), (4, 3));
+    assert_eq!(v.elem_ij(2, 1), 8.0);
+}
+
+#[test]
+#[should_panic]
+fn test_submatrix_out_of_bounds() {
+    Matrix::zeroes(2, 2).submatrix(1, 1, 2, 1);
+}
+
 #[test]
 #[should_panic]
 fn test_zero_sized_vector() {
@@ -621,3 +1007,50 @@ fn test_zero_sized_matrix_ref() {
 fn test_zero_sized_matrix_ref2() {
     let _ = gsl_matrix::from(&[[], []]);
 }
+
+#[test]
+fn test_val_with_error() {
+    let x = ValWithError {
+        val: -2.0,
+        err: 0.1,
+    };
+    assert_eq!(x.value(), -2.0);
+    assert_eq!(x.error(), 0.1);
+    assert_eq!(x.into_inner(), (-2.0, 0.1));
+    assert_eq!(x.relative_error(), 0.05);
+    assert_eq!(f64::from(x), -2.0);
+    assert_eq!(
+        x.map(|x| x * 10.0),
+        ValWithError {
+            val: -20.0,
+            err: 1.0
+        }
+    );
+    assert_eq!(x.map(|x| x as f32).val, -2.0f32);
+
+    assert!(x.approx_eq(
+        &ValWithError {
+            val: -2.15,
+            err: 0.1
+        },
+        0.0
+    ));
+    assert!(!x.approx_eq(
+        &ValWithError {
+            val: -2.25,
+            err: 0.1
+        },
+        0.0
+    ));
+    assert!(x.approx_eq(
+        &ValWithError {
+            val: -2.25,
+            err: 0.1
+        },
+        0.1
+    ));
+
+    assert_eq!(x.to_string(), "-2 ± 0.1");
+    assert_eq!(format!("{:.3}", x), "-2.000 ± 0.100");
+    assert!(ValWithError::from(0.0).relative_error().is_nan());
+}
@@ -0,0 +1,124 @@
+/*
+    deriv.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+
+/*
+
+    GSL estimates derivatives with a 5-point rule (central) or a 4-point rule (forward, backward),
+    and adapts the initial step size `h` to balance the truncation and rounding errors.
+    The one-sided rules only evaluate `f` on one side of `x`, which is useful at the edge of its domain.
+
+*/
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Difference {
+    Central,
+    Forward,
+    Backward,
+}
+
+/// Derivative of `f` at `x` using central differences, see `derivative_ext`
+pub fn derivative<F: FnMut(f64) -> f64>(f: F, x: f64, h: f64) -> Result<f64> {
+    derivative_ext(Difference::Central, f, x, h).map(|(df, _)| df)
+}
+
+/// Derivative of `f` at `x` with initial step size `h`, together with an estimate of its absolute error
+pub fn derivative_ext<F: FnMut(f64) -> f64>(
+    difference: Difference,
+    mut f: F,
+    x: f64,
+    h: f64,
+) -> Result<(f64, f64)> {
+    unsafe {
+        if !(h > 0.0 && h.is_finite() && x.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+
+        let gsl_f = gsl_function_struct {
+            function: Some(trampoline::<F>),
+            params: &mut f as *mut _ as *mut _,
+        };
+
+        let deriv = match difference {
+            Difference::Central => gsl_deriv_central,
+            Difference::Forward => gsl_deriv_forward,
+            Difference::Backward => gsl_deriv_backward,
+        };
+
+        let mut df = 0.0;
+        let mut abserr = 0.0;
+        GSLError::from_raw(deriv(&gsl_f, x, h, &mut df, &mut abserr))?;
+
+        if df.is_nan() {
+            return Err(GSLError::BadFunction);
+        }
+        Ok((df, abserr))
+    }
+}
+
+/// Gradient of `f` at `x` using central differences with initial step size `h` in every direction
+pub fn gradient<F: FnMut(&[f64]) -> f64>(mut f: F, x: &[f64], h: f64) -> Result<Box<[f64]>> {
+    let mut point = x.to_vec();
+    let mut gradient = vec![0.0; x.len()];
+    for i in 0..x.len() {
+        gradient[i] = derivative(
+            |xi| {
+                point[i] = xi;
+                f(&point)
+            },
+            x[i],
+            h,
+        )?;
+        point[i] = x[i];
+    }
+    Ok(gradient.into_boxed_slice())
+}
+
+#[test]
+fn test_derivative() {
+    disable_error_handler();
+
+    let (df, abserr) = derivative_ext(Difference::Central, f64::sin, 1.0, 1.0e-3).unwrap();
+    approx::assert_abs_diff_eq!(df, 1.0f64.cos(), epsilon = 1.0e-9);
+    assert!(abserr < 1.0e-6);
+
+    // One-sided differences at the edge of the domain
+    let half_parabola = |x: f64| if x < 0.0 { f64::NAN } else { x * x };
+    let (df, _) = derivative_ext(Difference::Forward, half_parabola, 0.0, 1.0e-3).unwrap();
+    approx::assert_abs_diff_eq!(df, 0.0, epsilon = 1.0e-8);
+    let (df, _) = derivative_ext(Difference::Backward, f64::ln, 1.0, 1.0e-3).unwrap();
+    approx::assert_abs_diff_eq!(df, 1.0, epsilon = 1.0e-6);
+
+    let g = gradient(|x| x[0] * x[0] + 3.0 * x[0] * x[1], &[1.0, 2.0], 1.0e-3).unwrap();
+    approx::assert_abs_diff_eq!(&*g, &[8.0, 3.0][..], epsilon = 1.0e-8);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Nonpositive step size
+    derivative(f64::sin, 1.0, 0.0).unwrap_err();
+    derivative(f64::sin, 1.0, -1.0e-3).unwrap_err();
+
+    // Not finite
+    derivative(f64::sin, f64::NAN, 1.0e-3).unwrap_err();
+    derivative(|_| f64::NAN, 1.0, 1.0e-3).unwrap_err();
+}
@@ -0,0 +1,422 @@
+/*
+    dual.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/*
+
+    Forward-mode automatic differentiation, to give fits and minimizers exact derivatives
+    without deriving them by hand.
+
+    A `Dual<N>` carries a value and its gradient with respect to `N` variables, which are propagated
+    together through every operation, so a single evaluation gives the full gradient.
+    Models are written once, generic over `Scalar`, and then evaluated with `f64` or `Dual<N>`.
+    `nonlinear_fit_autodiff` and `multimin_autodiff` take such models.
+
+    Comparisons only look at the value, so branches in a model follow the values like they would for `f64`.
+    At a kink, like `abs(0)`, the derivative of one side is taken.
+
+*/
+
+/// Real numbers a model can be evaluated with
+pub trait Scalar:
+    Copy
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + Add<f64, Output = Self>
+    + Sub<f64, Output = Self>
+    + Mul<f64, Output = Self>
+    + Div<f64, Output = Self>
+{
+    /// Constant with a zero gradient
+    fn constant(x: f64) -> Self;
+    fn value(self) -> f64;
+
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, p: f64) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn atan(self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+}
+
+impl Scalar for f64 {
+    fn constant(x: f64) -> Self {
+        x
+    }
+
+    fn value(self) -> f64 {
+        self
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        f64::powi(self, n)
+    }
+
+    fn powf(self, p: f64) -> Self {
+        f64::powf(self, p)
+    }
+
+    fn sin(self) -> Self {
+        f64::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        f64::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        f64::tan(self)
+    }
+
+    fn atan(self) -> Self {
+        f64::atan(self)
+    }
+
+    fn sinh(self) -> Self {
+        f64::sinh(self)
+    }
+
+    fn cosh(self) -> Self {
+        f64::cosh(self)
+    }
+
+    fn tanh(self) -> Self {
+        f64::tanh(self)
+    }
+}
+
+/// Value `val` with the gradient `grad` with respect to `N` variables
+#[derive(Copy, Clone, Debug)]
+pub struct Dual<const N: usize> {
+    pub val: f64,
+    pub grad: [f64; N],
+}
+
+impl<const N: usize> Dual<N> {
+    /// The `i`th variable, with value `x`
+    pub fn variable(x: f64, i: usize) -> Self {
+        let mut grad = [0.0; N];
+        grad[i] = 1.0;
+        Dual { val: x, grad }
+    }
+
+    /// All `N` variables, with the values `x`
+    pub fn variables(x: [f64; N]) -> [Self; N] {
+        std::array::from_fn(|i| Self::variable(x[i], i))
+    }
+
+    /// `f(self)`, with the derivative `df` of `f` at `self.val`
+    fn chain(self, f: f64, df: f64) -> Self {
+        Dual {
+            val: f,
+            grad: self.grad.map(|g| df * g),
+        }
+    }
+}
+
+impl<const N: usize> Scalar for Dual<N> {
+    fn constant(x: f64) -> Self {
+        Dual {
+            val: x,
+            grad: [0.0; N],
+        }
+    }
+
+    fn value(self) -> f64 {
+        self.val
+    }
+
+    fn abs(self) -> Self {
+        if self.val < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        let s = self.val.sqrt();
+        self.chain(s, 0.5 / s)
+    }
+
+    fn exp(self) -> Self {
+        let e = self.val.exp();
+        self.chain(e, e)
+    }
+
+    fn ln(self) -> Self {
+        self.chain(self.val.ln(), 1.0 / self.val)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        if n == 0 {
+            return Self::constant(1.0);
+        }
+        self.chain(self.val.powi(n), n as f64 * self.val.powi(n - 1))
+    }
+
+    fn powf(self, p: f64) -> Self {
+        self.chain(self.val.powf(p), p * self.val.powf(p - 1.0))
+    }
+
+    fn sin(self) -> Self {
+        self.chain(self.val.sin(), self.val.cos())
+    }
+
+    fn cos(self) -> Self {
+        self.chain(self.val.cos(), -self.val.sin())
+    }
+
+    fn tan(self) -> Self {
+        let t = self.val.tan();
+        self.chain(t, 1.0 + t * t)
+    }
+
+    fn atan(self) -> Self {
+        self.chain(self.val.atan(), 1.0 / (1.0 + self.val * self.val))
+    }
+
+    fn sinh(self) -> Self {
+        self.chain(self.val.sinh(), self.val.cosh())
+    }
+
+    fn cosh(self) -> Self {
+        self.chain(self.val.cosh(), self.val.sinh())
+    }
+
+    fn tanh(self) -> Self {
+        let t = self.val.tanh();
+        self.chain(t, 1.0 - t * t)
+    }
+}
+
+impl<const N: usize> PartialEq for Dual<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.val == other.val
+    }
+}
+
+impl<const N: usize> PartialOrd for Dual<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.val.partial_cmp(&other.val)
+    }
+}
+
+impl<const N: usize> Neg for Dual<N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.chain(-self.val, -1.0)
+    }
+}
+
+impl<const N: usize> Add for Dual<N> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Dual {
+            val: self.val + other.val,
+            grad: std::array::from_fn(|i| self.grad[i] + other.grad[i]),
+        }
+    }
+}
+
+impl<const N: usize> Sub for Dual<N> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Dual {
+            val: self.val - other.val,
+            grad: std::array::from_fn(|i| self.grad[i] - other.grad[i]),
+        }
+    }
+}
+
+impl<const N: usize> Mul for Dual<N> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, other: Self) -> Self {
+        Dual {
+            val: self.val * other.val,
+            grad: std::array::from_fn(|i| self.grad[i] * other.val + self.val * other.grad[i]),
+        }
+    }
+}
+
+impl<const N: usize> Div for Dual<N> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, other: Self) -> Self {
+        let val = self.val / other.val;
+        Dual {
+            val,
+            grad: std::array::from_fn(|i| (self.grad[i] - val * other.grad[i]) / other.val),
+        }
+    }
+}
+
+impl<const N: usize> Add<f64> for Dual<N> {
+    type Output = Self;
+
+    fn add(self, other: f64) -> Self {
+        self.chain(self.val + other, 1.0)
+    }
+}
+
+impl<const N: usize> Sub<f64> for Dual<N> {
+    type Output = Self;
+
+    fn sub(self, other: f64) -> Self {
+        self.chain(self.val - other, 1.0)
+    }
+}
+
+impl<const N: usize> Mul<f64> for Dual<N> {
+    type Output = Self;
+
+    fn mul(self, other: f64) -> Self {
+        self.chain(self.val * other, other)
+    }
+}
+
+impl<const N: usize> Div<f64> for Dual<N> {
+    type Output = Self;
+
+    fn div(self, other: f64) -> Self {
+        self.chain(self.val / other, 1.0 / other)
+    }
+}
+
+impl<const N: usize> Add<Dual<N>> for f64 {
+    type Output = Dual<N>;
+
+    fn add(self, other: Dual<N>) -> Dual<N> {
+        other + self
+    }
+}
+
+impl<const N: usize> Sub<Dual<N>> for f64 {
+    type Output = Dual<N>;
+
+    fn sub(self, other: Dual<N>) -> Dual<N> {
+        other.chain(self - other.val, -1.0)
+    }
+}
+
+impl<const N: usize> Mul<Dual<N>> for f64 {
+    type Output = Dual<N>;
+
+    fn mul(self, other: Dual<N>) -> Dual<N> {
+        other * self
+    }
+}
+
+impl<const N: usize> Div<Dual<N>> for f64 {
+    type Output = Dual<N>;
+
+    fn div(self, other: Dual<N>) -> Dual<N> {
+        let val = self / other.val;
+        other.chain(val, -val / other.val)
+    }
+}
+
+#[test]
+fn test_dual() {
+    use approx::assert_relative_eq;
+
+    fn model<T: Scalar>([a, b]: [T; 2], x: f64) -> T {
+        (a * x).exp() * (b * x + 1.0).sin() / (a * a + b.powi(3)).sqrt() - T::constant(2.0) / b
+    }
+
+    // Exact derivatives
+    let (a, b, x) = (0.3, 1.7, 0.9);
+    let result = model(Dual::variables([a, b]), x);
+    let s = (a * a + b * b * b).sqrt();
+    let e = (a * x).exp();
+    let (sin, cos) = (b * x + 1.0).sin_cos();
+    assert_eq!(result.val, model([a, b], x));
+    assert_relative_eq!(
+        result.grad[0],
+        x * e * sin / s - e * sin * a / s.powi(3),
+        max_relative = 1.0e-14
+    );
+    assert_relative_eq!(
+        result.grad[1],
+        x * e * cos / s - 1.5 * e * sin * b * b / s.powi(3) + 2.0 / (b * b),
+        max_relative = 1.0e-14
+    );
+
+    // Elementary functions against central differences
+    let functions: [fn(Dual<1>) -> Dual<1>; 11] = [
+        Scalar::abs,
+        Scalar::sqrt,
+        Scalar::ln,
+        |x| x.powf(2.5),
+        Scalar::tan,
+        Scalar::atan,
+        Scalar::sinh,
+        Scalar::cosh,
+        Scalar::tanh,
+        |x| 3.0 - x,
+        |x| 1.0 / x.cos(),
+    ];
+    for f in functions {
+        let x = 0.7;
+        let h = 1.0e-6;
+        let diff = (f(Dual::constant(x + h)).val - f(Dual::constant(x - h)).val) / (2.0 * h);
+        assert_relative_eq!(f(Dual::variable(x, 0)).grad[0], diff, max_relative = 1.0e-8);
+    }
+
+    // Comparisons ignore the gradient
+    assert_eq!(Dual::variable(1.0, 0), Dual::<1>::constant(1.0));
+    assert!(Dual::<1>::constant(1.0) < Dual::variable(2.0, 0));
+    assert_eq!(Dual::<2>::constant(5.0).powi(0).grad, [0.0, 0.0]);
+}
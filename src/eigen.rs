@@ -0,0 +1,643 @@
+/*
+    eigen.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+use drop_guard::guard;
+use num_complex::Complex64;
+
+/// Solves `A v = λ v` for a real symmetric `n` by `n` matrix `A`, stored row major.
+///
+/// Eigenvalues are sorted in ascending order.
+pub fn symmetric(n: usize, a: &[f64]) -> Result<Eigen> {
+    unsafe {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if a.len() != n * n {
+            return Err(GSLError::BadLength);
+        }
+
+        let workspace = guard(gsl_eigen_symmv_alloc(n as u64), |workspace| {
+            gsl_eigen_symmv_free(workspace);
+        });
+        assert!(!workspace.is_null());
+
+        // The input matrix is destroyed by GSL
+        let mut a = Matrix::new(a.iter().copied(), n, n);
+        let mut values = Vector::zeroes(n);
+        let mut vectors = Matrix::zeroes(n, n);
+
+        GSLError::from_raw(gsl_eigen_symmv(
+            a.as_gsl_mut(),
+            values.as_gsl_mut(),
+            vectors.as_gsl_mut(),
+            *workspace,
+        ))?;
+        GSLError::from_raw(gsl_eigen_symmv_sort(
+            values.as_gsl_mut(),
+            vectors.as_gsl_mut(),
+            gsl_eigen_sort_t_GSL_EIGEN_SORT_VAL_ASC,
+        ))?;
+
+        Ok(Eigen {
+            values: values.to_boxed_slice(),
+            vectors: vectors.to_boxed_slice(),
+        })
+    }
+}
+
+/// Solves `A v = λ B v` for real symmetric `n` by `n` matrices `A` and `B`, stored row major.
+/// `B` must be positive definite.
+///
+/// Eigenvalues are sorted in ascending order.
+/// Eigenvectors are normalized to unit length, like those of `symmetric`, not to `v^T B v = 1`.
+pub fn generalized_symmetric(n: usize, a: &[f64], b: &[f64]) -> Result<Eigen> {
+    unsafe {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if a.len() != n * n || b.len() != n * n {
+            return Err(GSLError::BadLength);
+        }
+
+        let workspace = guard(gsl_eigen_gensymmv_alloc(n as u64), |workspace| {
+            gsl_eigen_gensymmv_free(workspace);
+        });
+        assert!(!workspace.is_null());
+
+        // The input matrices are destroyed by GSL
+        let mut a = Matrix::new(a.iter().copied(), n, n);
+        let mut b = Matrix::new(b.iter().copied(), n, n);
+        let mut values = Vector::zeroes(n);
+        let mut vectors = Matrix::zeroes(n, n);
+
+        GSLError::from_raw(gsl_eigen_gensymmv(
+            a.as_gsl_mut(),
+            b.as_gsl_mut(),
+            values.as_gsl_mut(),
+            vectors.as_gsl_mut(),
+            *workspace,
+        ))?;
+        GSLError::from_raw(gsl_eigen_gensymmv_sort(
+            values.as_gsl_mut(),
+            vectors.as_gsl_mut(),
+            gsl_eigen_sort_t_GSL_EIGEN_SORT_VAL_ASC,
+        ))?;
+
+        Ok(Eigen {
+            values: values.to_boxed_slice(),
+            vectors: vectors.to_boxed_slice(),
+        })
+    }
+}
+
+/// Computes the (possibly complex) eigenvalues of a real `n` by `n` matrix `A`, stored row major.
+///
+/// Eigenvalues are sorted by descending magnitude.
+pub fn nonsymmetric(n: usize, a: &[f64]) -> Result<Vec<Complex64>> {
+    unsafe {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if a.len() != n * n {
+            return Err(GSLError::BadLength);
+        }
+
+        let workspace = guard(gsl_eigen_nonsymm_alloc(n as u64), |workspace| {
+            gsl_eigen_nonsymm_free(workspace);
+        });
+        assert!(!workspace.is_null());
+
+        let values = guard(gsl_vector_complex_alloc(n as u64), |values| {
+            gsl_vector_complex_free(values);
+        });
+        assert!(!values.is_null());
+
+        // The input matrix is destroyed by GSL
+        let mut a = Matrix::new(a.iter().copied(), n, n);
+        GSLError::from_raw(gsl_eigen_nonsymm(a.as_gsl_mut(), *values, *workspace))?;
+
+        // Freshly allocated, so the stride is 1 and the data is stored as [re, im] pairs
+        let data = std::slice::from_raw_parts((**values).data, 2 * n);
+        let mut values = data
+            .chunks_exact(2)
+            .map(|z| Complex64::new(z[0], z[1]))
+            .collect::<Vec<_>>();
+        values.sort_by(|a, b| b.norm().total_cmp(&a.norm()));
+
+        Ok(values)
+    }
+}
+
+/// Solves `A v = λ B v` for symmetric banded matrices `A` and `B`. `B` must be positive definite.
+///
+/// The bands are expanded to dense storage before being handed to GSL.
+pub fn generalized_symmetric_banded(a: &SymmetricBanded, b: &SymmetricBanded) -> Result<Eigen> {
+    if a.n() != b.n() {
+        return Err(GSLError::BadLength);
+    }
+    generalized_symmetric(a.n(), &a.to_dense(), &b.to_dense())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eigen {
+    pub values: Box<[f64]>,
+    /// Row major, eigenvector `i` is stored in column `i`
+    pub vectors: Box<[f64]>,
+}
+
+impl Eigen {
+    pub fn vector(&self, i: usize) -> Vec<f64> {
+        let n = self.values.len();
+        (0..n).map(|j| self.vectors[j * n + i]).collect()
+    }
+}
+
+/// Symmetric band matrix, stored as its main diagonal and `bandwidth` superdiagonals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymmetricBanded {
+    /// `diagonals[k][i]` is element `A_{i, i + k}`
+    diagonals: Vec<Box<[f64]>>,
+}
+
+impl SymmetricBanded {
+    pub fn zeroes(n: usize, bandwidth: usize) -> Self {
+        assert!(n > 0);
+        assert!(bandwidth < n);
+
+        SymmetricBanded {
+            diagonals: (0..=bandwidth)
+                .map(|k| vec![0.0; n - k].into_boxed_slice())
+                .collect(),
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.diagonals[0].len()
+    }
+
+    pub fn bandwidth(&self) -> usize {
+        self.diagonals.len() - 1
+    }
+
+    /// Gets element `A_ij`, which is zero outside of the band
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        let (i, j) = if i <= j { (i, j) } else { (j, i) };
+        self.diagonals
+            .get(j - i)
+            .map(|diagonal| diagonal[i])
+            .unwrap_or(0.0)
+    }
+
+    /// Sets element `A_ij` and its mirror `A_ji`
+    pub fn set(&mut self, i: usize, j: usize, x: f64) {
+        let (i, j) = if i <= j { (i, j) } else { (j, i) };
+        assert!(j - i <= self.bandwidth());
+        self.diagonals[j - i][i] = x;
+    }
+
+    /// Row major dense representation
+    pub fn to_dense(&self) -> Box<[f64]> {
+        let n = self.n();
+        let mut out = vec![0.0; n * n].into_boxed_slice();
+        for (k, diagonal) in self.diagonals.iter().enumerate() {
+            for (i, &x) in diagonal.iter().enumerate() {
+                out[i * n + i + k] = x;
+                out[(i + k) * n + i] = x;
+            }
+        }
+        out
+    }
+}
+
+/// Solves the Sturm–Liouville problem `-(p y')' + q y = λ w y` on `[a, b]`
+/// with Dirichlet boundary conditions `y(a) = y(b) = 0`.
+///
+/// The operator is discretized with second order finite differences on `n` interior points.
+/// Returns the lowest `k` eigenvalues, with eigenfunctions normalized such that `∫ w y^2 = 1`.
+pub fn sturm_liouville<P, Q, W>(
+    k: usize,
+    a: f64,
+    b: f64,
+    n: usize,
+    mut p: P,
+    mut q: Q,
+    mut w: W,
+) -> Result<SturmLiouville>
+where
+    P: FnMut(f64) -> f64,
+    Q: FnMut(f64) -> f64,
+    W: FnMut(f64) -> f64,
+{
+    if k == 0 || k > n {
+        return Err(GSLError::Invalid);
+    }
+    if b <= a {
+        return Err(GSLError::Domain);
+    }
+
+    // Interior grid points x_i = a + (i + 1) h
+    let h = (b - a) / (n + 1) as f64;
+    let x = (0..n)
+        .map(|i| a + (i + 1) as f64 * h)
+        .collect::<Box<[f64]>>();
+
+    // p is evaluated halfway between grid points, including the boundaries
+    let p_half = (0..=n)
+        .map(|i| p(a + (i as f64 + 0.5) * h))
+        .collect::<Vec<_>>();
+
+    let mut lhs = SymmetricBanded::zeroes(n, 1.min(n - 1));
+    let mut rhs = SymmetricBanded::zeroes(n, 0);
+    let mut weights = Vec::with_capacity(n);
+    for i in 0..n {
+        lhs.set(i, i, (p_half[i] + p_half[i + 1]) / h.powi(2) + q(x[i]));
+        if i + 1 < n {
+            lhs.set(i, i + 1, -p_half[i + 1] / h.powi(2));
+        }
+
+        let wi = w(x[i]);
+        if wi <= 0.0 {
+            return Err(GSLError::Domain);
+        }
+        rhs.set(i, i, wi);
+        weights.push(wi);
+    }
+
+    let eigen = generalized_symmetric_banded(&lhs, &rhs)?;
+
+    // GSL normalizes the eigenvectors to unit length, h Σ w y^2 approximates ∫ w y^2
+    let eigenfunctions = (0..k)
+        .map(|i| {
+            let v = eigen.vector(i);
+            let norm = h * v.iter().zip(&weights).map(|(y, w)| w * y * y).sum::<f64>();
+            let scale = norm.sqrt().recip();
+            v.into_iter().map(|y| y * scale).collect()
+        })
+        .collect();
+
+    Ok(SturmLiouville {
+        x,
+        eigenvalues: eigen.values[..k].to_owned().into_boxed_slice(),
+        eigenfunctions,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SturmLiouville {
+    /// Interior grid points
+    pub x: Box<[f64]>,
+    pub eigenvalues: Box<[f64]>,
+    /// Eigenfunction values at the interior grid points
+    pub eigenfunctions: Box<[Box<[f64]>]>,
+}
+
+/// Which end of the spectrum to return
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Which {
+    Largest,
+    Smallest,
+}
+
+/// Estimates `k` extremal eigenvalues of a symmetric `n` by `n` operator,
+/// given only its action `matvec(x, y)`: `y = A x`.
+///
+/// Builds a Krylov subspace of dimension `m` with full reorthogonalization,
+/// so memory use is `O(n m)`. Eigenvalues are returned in ascending order for `Which::Smallest`
+/// and in descending order for `Which::Largest`.
+pub fn lanczos<F: FnMut(&[f64], &mut [f64])>(
+    n: usize,
+    k: usize,
+    m: usize,
+    which: Which,
+    mut matvec: F,
+) -> Result<Box<[f64]>> {
+    if n == 0 || k == 0 || m < k {
+        return Err(GSLError::Invalid);
+    }
+    let m = m.min(n);
+    if k > m {
+        return Err(GSLError::Invalid);
+    }
+
+    let mut basis = vec![krylov_start(n)];
+    let mut alpha = Vec::with_capacity(m);
+    let mut beta = Vec::with_capacity(m);
+    let mut w = vec![0.0; n];
+
+    for j in 0..m {
+        matvec(&basis[j], &mut w);
+        if w.iter().any(|x| !x.is_finite()) {
+            return Err(GSLError::BadFunction);
+        }
+
+        alpha.push(blas::dot(&w, &basis[j])?);
+
+        // Full reorthogonalization against all previous basis vectors,
+        // which also takes care of the three term recurrence
+        for v in basis.iter() {
+            let h = blas::dot(&w, v)?;
+            blas::axpy(-h, v, &mut w)?;
+        }
+
+        if j + 1 == m {
+            break;
+        }
+
+        let norm = blas::nrm2(&w);
+        if norm <= f64::EPSILON * alpha[j].abs().max(1.0) {
+            // Invariant subspace found, the Ritz values are exact
+            break;
+        }
+        beta.push(norm);
+        basis.push(w.iter().map(|x| x / norm).collect());
+    }
+
+    // Tridiagonal projection of the operator onto the Krylov subspace
+    let m = alpha.len();
+    if k > m {
+        return Err(GSLError::Invalid);
+    }
+    let mut t = vec![0.0; m * m];
+    for i in 0..m {
+        t[i * m + i] = alpha[i];
+        if i + 1 < m {
+            t[i * m + i + 1] = beta[i];
+            t[(i + 1) * m + i] = beta[i];
+        }
+    }
+
+    let ritz = symmetric(m, &t)?.values;
+    Ok(match which {
+        Which::Smallest => ritz[..k].to_owned().into_boxed_slice(),
+        Which::Largest => ritz.iter().rev().take(k).copied().collect(),
+    })
+}
+
+/// Estimates the `k` eigenvalues of largest magnitude of a general `n` by `n` operator,
+/// given only its action `matvec(x, y)`: `y = A x`.
+///
+/// Builds a Krylov subspace of dimension `m`, so memory use is `O(n m)`.
+/// Eigenvalues are returned by descending magnitude.
+pub fn arnoldi<F: FnMut(&[f64], &mut [f64])>(
+    n: usize,
+    k: usize,
+    m: usize,
+    mut matvec: F,
+) -> Result<Vec<Complex64>> {
+    if n == 0 || k == 0 || m < k {
+        return Err(GSLError::Invalid);
+    }
+    let m = m.min(n);
+    if k > m {
+        return Err(GSLError::Invalid);
+    }
+
+    let mut basis = vec![krylov_start(n)];
+    // Upper Hessenberg projection, row major m by m
+    let mut h = vec![0.0; m * m];
+    let mut w = vec![0.0; n];
+
+    let mut size = m;
+    for j in 0..m {
+        matvec(&basis[j], &mut w);
+        if w.iter().any(|x| !x.is_finite()) {
+            return Err(GSLError::BadFunction);
+        }
+
+        // Modified Gram-Schmidt
+        for (i, v) in basis.iter().enumerate() {
+            let hij = blas::dot(&w, v)?;
+            h[i * m + j] = hij;
+            blas::axpy(-hij, v, &mut w)?;
+        }
+
+        if j + 1 == m {
+            break;
+        }
+
+        let norm = blas::nrm2(&w);
+        if norm <= f64::EPSILON * h[j * m + j].abs().max(1.0) {
+            // Invariant subspace found, the Ritz values are exact
+            size = j + 1;
+            break;
+        }
+        h[(j + 1) * m + j] = norm;
+        basis.push(w.iter().map(|x| x / norm).collect());
+    }
+
+    if k > size {
+        return Err(GSLError::Invalid);
+    }
+
+    let h = (0..size)
+        .flat_map(|i| h[i * m..i * m + size].iter().copied())
+        .collect::<Vec<_>>();
+    let mut ritz = nonsymmetric(size, &h)?;
+    ritz.truncate(k);
+
+    Ok(ritz)
+}
+
+/// Deterministic normalized starting vector with no special structure,
+/// so it is unlikely to be orthogonal to any eigenvector.
+fn krylov_start(n: usize) -> Vec<f64> {
+    let v = (0..n)
+        .map(|i| 1.0 + ((i + 1) as f64 * 0.618_033_988_749_894_9).fract())
+        .collect::<Vec<_>>();
+    let norm = blas::nrm2(&v);
+    v.into_iter().map(|x| x / norm).collect()
+}
+
+#[test]
+fn test_symmetric() {
+    disable_error_handler();
+
+    let eigen = symmetric(2, &[2.0, 1.0, 1.0, 2.0]).unwrap();
+    approx::assert_abs_diff_eq!(eigen.values[0], 1.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(eigen.values[1], 3.0, epsilon = 1.0e-12);
+
+    let v = eigen.vector(1);
+    approx::assert_abs_diff_eq!(v[0].abs(), 0.5f64.sqrt(), epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(v[0], v[1], epsilon = 1.0e-12);
+
+    // Generalized eigenvectors have unit length
+    let eigen = generalized_symmetric(2, &[2.0, 1.0, 1.0, 2.0], &[4.0, 0.0, 0.0, 1.0]).unwrap();
+    for i in 0..2 {
+        let v = eigen.vector(i);
+        approx::assert_abs_diff_eq!(v[0].hypot(v[1]), 1.0, epsilon = 1.0e-12);
+    }
+}
+
+#[test]
+fn test_banded() {
+    let mut a = SymmetricBanded::zeroes(3, 1);
+    a.set(0, 0, 1.0);
+    a.set(1, 0, 2.0);
+    a.set(2, 2, 3.0);
+
+    assert_eq!(a.get(0, 1), 2.0);
+    assert_eq!(a.get(0, 2), 0.0);
+    assert_eq!(
+        a.to_dense().as_ref(),
+        &[1.0, 2.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 3.0]
+    );
+}
+
+#[test]
+fn test_sturm_liouville() {
+    disable_error_handler();
+
+    // -y'' = λ y on [0, π] has eigenvalues 1, 4, 9, ...
+    let sl = sturm_liouville(3, 0.0, std::f64::consts::PI, 200, |_| 1.0, |_| 0.0, |_| 1.0).unwrap();
+
+    for (i, &lambda) in sl.eigenvalues.iter().enumerate() {
+        approx::assert_abs_diff_eq!(lambda, ((i + 1) as f64).powi(2), epsilon = 1.0e-2);
+    }
+
+    // Normalized eigenfunctions are sqrt(2 / π) sin(x) up to sign
+    let h = sl.x[1] - sl.x[0];
+    let norm = sl.eigenfunctions[0].iter().map(|y| y * y * h).sum::<f64>();
+    approx::assert_abs_diff_eq!(norm, 1.0, epsilon = 1.0e-6);
+    for (y, x) in sl.eigenfunctions[0].iter().zip(sl.x.iter()) {
+        approx::assert_abs_diff_eq!(
+            y.abs(),
+            (2.0 / std::f64::consts::PI).sqrt() * x.sin(),
+            epsilon = 1.0e-3
+        );
+    }
+
+    // Normalized with the weight, ∫ w y^2 = 1 for a weight that isn't constant
+    let w = |x: f64| 1.0 + x * x;
+    let sl = sturm_liouville(3, 0.0, 2.0, 200, |_| 1.0, |_| 0.0, w).unwrap();
+    let h = sl.x[1] - sl.x[0];
+    for eigenfunction in sl.eigenfunctions.iter() {
+        let norm = eigenfunction
+            .iter()
+            .zip(sl.x.iter())
+            .map(|(y, &x)| w(x) * y * y * h)
+            .sum::<f64>();
+        approx::assert_abs_diff_eq!(norm, 1.0, epsilon = 1.0e-12);
+    }
+}
+
+#[test]
+fn test_nonsymmetric() {
+    disable_error_handler();
+
+    // Rotation by 90 degrees has eigenvalues ±i
+    let values = nonsymmetric(2, &[0.0, -1.0, 1.0, 0.0]).unwrap();
+    for z in values {
+        approx::assert_abs_diff_eq!(z.re, 0.0, epsilon = 1.0e-12);
+        approx::assert_abs_diff_eq!(z.im.abs(), 1.0, epsilon = 1.0e-12);
+    }
+}
+
+#[test]
+fn test_lanczos() {
+    disable_error_handler();
+
+    // Diagonal operator with eigenvalues 1, 2, ..., n
+    let n = 200;
+    let diag = |x: &[f64], y: &mut [f64]| {
+        for (i, (x, y)) in x.iter().zip(y.iter_mut()).enumerate() {
+            *y = (i + 1) as f64 * x;
+        }
+    };
+
+    let largest = lanczos(n, 2, 120, Which::Largest, diag).unwrap();
+    approx::assert_abs_diff_eq!(largest[0], 200.0, epsilon = 1.0e-6);
+    approx::assert_abs_diff_eq!(largest[1], 199.0, epsilon = 1.0e-6);
+
+    let smallest = lanczos(n, 1, 120, Which::Smallest, diag).unwrap();
+    approx::assert_abs_diff_eq!(smallest[0], 1.0, epsilon = 1.0e-6);
+
+    // A small operator is captured exactly
+    let exact = lanczos(3, 3, 10, Which::Smallest, diag).unwrap();
+    approx::assert_abs_diff_eq!(exact.as_ref(), [1.0, 2.0, 3.0].as_ref(), epsilon = 1.0e-9);
+}
+
+#[test]
+fn test_arnoldi() {
+    disable_error_handler();
+
+    // Upper bidiagonal operator with eigenvalues 1, 2, ..., n on the diagonal
+    let n = 200;
+    let values = arnoldi(n, 1, 150, |x, y| {
+        for i in 0..n {
+            let next = if i + 1 < n { 0.5 * x[i + 1] } else { 0.0 };
+            y[i] = (i + 1) as f64 * x[i] + next;
+        }
+    })
+    .unwrap();
+
+    approx::assert_abs_diff_eq!(values[0].re, 200.0, epsilon = 1.0e-6);
+    approx::assert_abs_diff_eq!(values[0].im, 0.0, epsilon = 1.0e-6);
+}
+
+#[test]
+fn test_gsl_suite() {
+    use crate::testing::*;
+
+    disable_error_handler();
+
+    // Ported from eigen/test.c: the 4 by 4 Hilbert matrix, with its eigenvalues computed to 20 digits
+    let n = 4;
+    let a = (0..n * n)
+        .map(|k| 1.0 / (k / n + k % n + 1) as f64)
+        .collect::<Vec<_>>();
+    let expected = [
+        9.670230402258689e-05,
+        0.006738273605760748,
+        0.16914122022145003,
+        1.5002142800592428,
+    ];
+    let eigen = symmetric(n, &a).unwrap();
+    for (i, (value, expected)) in eigen.values.iter().zip(expected).enumerate() {
+        assert_rel(*value, expected, 1.0e-10);
+
+        // Normalized eigenvectors with A v = λ v
+        let v = eigen.vector(i);
+        assert_rel(blas::nrm2(&v), 1.0, 1.0e-14);
+        for (j, row) in a.chunks_exact(n).enumerate() {
+            assert_abs(blas::dot(row, &v).unwrap(), value * v[j], 1.0e-14);
+        }
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Mismatched dimensions
+    symmetric(2, &[1.0, 2.0, 3.0]).unwrap_err();
+
+    // Empty domain
+    sturm_liouville(1, 1.0, 1.0, 10, |_| 1.0, |_| 0.0, |_| 1.0).unwrap_err();
+
+    // More eigenvalues than grid points
+    sturm_liouville(11, 0.0, 1.0, 10, |_| 1.0, |_| 0.0, |_| 1.0).unwrap_err();
+
+    // Nonpositive weight
+    sturm_liouville(1, 0.0, 1.0, 10, |_| 1.0, |_| 0.0, |_| 0.0).unwrap_err();
+
+    // Krylov subspace smaller than the requested amount of eigenvalues
+    lanczos(10, 5, 4, Which::Largest, |x, y| y.copy_from_slice(x)).unwrap_err();
+    arnoldi(10, 5, 4, |x, y| y.copy_from_slice(x)).unwrap_err();
+}
@@ -16,10 +16,47 @@
     along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
+#[cfg(feature = "gsl")]
 use crate::bindings::*;
+#[cfg(not(feature = "gsl"))]
+use codes::*;
+use std::any::Any;
+#[cfg(feature = "gsl")]
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::error::Error;
+#[cfg(feature = "gsl")]
+use std::ffi::CStr;
 use std::fmt;
 use std::os::raw::*;
+use std::panic::resume_unwind;
+#[cfg(feature = "gsl")]
+use std::panic::{catch_unwind, AssertUnwindSafe};
+#[cfg(feature = "gsl")]
+use std::sync::Once;
+
+/*
+
+    GSL reports an error twice: it calls its error handler with the reason and the location
+    in the GSL sources, then returns the status code, which the wrappers turn into `Err`.
+    The default handler of GSL aborts the program, so `disable_error_handler` or `set_error_handler`
+    has to be called first.
+
+    GSL has one global handler, but errors are reported on the thread that called GSL.
+    The handler installed here forwards to the `ErrorHandler` of the calling thread, if it has one,
+    so threads don't replace each other's handlers. It's installed once for the whole process,
+    and turns off the aborting default handler for all threads.
+    With the `gsl_thread_safe` feature, it's installed before `main` on Linux, macOS and Windows,
+    so GSL never aborts, not even on threads that start before `disable_error_handler` is called.
+
+    A panic can't unwind through the C code of GSL. A panic of the handler is caught,
+    and resumed when the wrapper checks the status code, after GSL has returned and cleaned up.
+    Functions without a status code resume it at the next checked call on the thread.
+
+    Without the `gsl` feature, nothing reports errors to the handlers, and the status codes
+    are the values of `gsl_errno.h`.
+
+*/
 
 pub type Result<T> = std::result::Result<T, GSLError>;
 
@@ -140,8 +177,16 @@ impl Into<c_int> for GSLError {
     }
 }
 
+#[cfg_attr(not(feature = "gsl"), allow(dead_code))]
 impl GSLError {
     pub(crate) fn from_raw(raw: c_int) -> Result<()> {
+        if let Some(payload) = PENDING_PANIC.with(Cell::take) {
+            resume_unwind(payload);
+        }
+        Self::from_code(raw)
+    }
+
+    fn from_code(raw: c_int) -> Result<()> {
         match raw {
             GSL_SUCCESS => Ok(()),
             GSL_FAILURE => Err(Self::Failure),
@@ -190,3 +235,230 @@ impl fmt::Display for GSLError {
         write!(f, "{:?}", self)
     }
 }
+
+/// Receives the errors GSL reports, with the location in the GSL sources
+pub trait ErrorHandler {
+    fn handle(&mut self, reason: &str, file: &str, line: u32, error: GSLError);
+}
+
+impl<F: FnMut(&str, &str, u32, GSLError)> ErrorHandler for F {
+    fn handle(&mut self, reason: &str, file: &str, line: u32, error: GSLError) {
+        self(reason, file, line, error)
+    }
+}
+
+/// Panics with the reason and location of the error
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PanicOnError;
+
+impl ErrorHandler for PanicOnError {
+    fn handle(&mut self, reason: &str, file: &str, line: u32, error: GSLError) {
+        panic!("gsl: {}:{}: {}: {}", file, line, error, reason);
+    }
+}
+
+/// Writes the reason and location of the error to stderr, like the default GSL handler, without aborting
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LogOnError;
+
+impl ErrorHandler for LogOnError {
+    fn handle(&mut self, reason: &str, file: &str, line: u32, error: GSLError) {
+        eprintln!("gsl: {}:{}: ERROR: {} ({})", file, line, reason, error);
+    }
+}
+
+thread_local! {
+    static HANDLER: RefCell<Option<Box<dyn ErrorHandler>>> = RefCell::new(None);
+    static PENDING_PANIC: Cell<Option<Box<dyn Any + Send>>> = Cell::new(None);
+}
+
+/// Installs `handler` for the errors GSL reports on this thread, replacing the previous handler.
+/// The errors are still returned as `Err`.
+pub fn set_error_handler<H: ErrorHandler + 'static>(handler: H) {
+    install_handler();
+    HANDLER.with(|h| *h.borrow_mut() = Some(Box::new(handler)));
+}
+
+/// Removes the handler of this thread, leaving GSL errors only as `Err`
+pub(crate) fn clear_error_handler() {
+    install_handler();
+    HANDLER.with(|h| h.borrow_mut().take());
+}
+
+/// Takes the handler of this thread out, for errors a wrapper causes on purpose.
+/// Put it back with `restore_error_handler`.
+/// Also replaces the default handler of GSL, which would abort on those errors.
+#[cfg(feature = "gsl")]
+pub(crate) fn take_error_handler() -> Option<Box<dyn ErrorHandler>> {
+    install_handler();
+    HANDLER
+        .try_with(|h| h.try_borrow_mut().ok().and_then(|mut h| h.take()))
+        .ok()
+        .flatten()
+}
+
+#[cfg(feature = "gsl")]
+pub(crate) fn restore_error_handler(handler: Option<Box<dyn ErrorHandler>>) {
+    if let Some(handler) = handler {
+        HANDLER.with(|h| *h.borrow_mut() = Some(handler));
+    }
+}
+
+#[cfg(feature = "gsl")]
+fn install_handler() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| unsafe {
+        gsl_set_error_handler(Some(handle_error));
+    });
+}
+
+#[cfg(not(feature = "gsl"))]
+fn install_handler() {}
+
+#[cfg(feature = "gsl_thread_safe")]
+#[used]
+#[cfg_attr(
+    any(target_os = "linux", target_os = "android", target_os = "freebsd"),
+    link_section = ".init_array"
+)]
+#[cfg_attr(target_vendor = "apple", link_section = "__DATA,__mod_init_func")]
+#[cfg_attr(windows, link_section = ".CRT$XCU")]
+static INSTALL_AT_STARTUP: extern "C" fn() = {
+    extern "C" fn install_at_startup() {
+        install_handler();
+    }
+    install_at_startup
+};
+
+#[cfg(feature = "gsl")]
+unsafe extern "C" fn handle_error(
+    reason: *const c_char,
+    file: *const c_char,
+    line: c_int,
+    gsl_errno: c_int,
+) {
+    // Convergence errors the policy of this thread accepts are no errors to the caller
+    let error = GSLError::from_code(gsl_errno)
+        .err()
+        .unwrap_or(GSLError::Unknown(gsl_errno));
+    if crate::policy::policy().tolerates(error) {
+        return;
+    }
+
+    // Taken out during the call, so the handler may call GSL itself
+    let handler = HANDLER
+        .try_with(|h| h.try_borrow_mut().ok().and_then(|mut h| h.take()))
+        .ok()
+        .flatten();
+    let mut handler = match handler {
+        Some(handler) => handler,
+        None => return,
+    };
+
+    let reason = c_str(reason);
+    let file = c_str(file);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        handler.handle(&reason, &file, line as u32, error)
+    }));
+
+    let _ = HANDLER.try_with(|h| {
+        let mut h = h.borrow_mut();
+        if h.is_none() {
+            *h = Some(handler);
+        }
+    });
+    if let Err(payload) = result {
+        let _ = PENDING_PANIC.try_with(|p| {
+            let first = p.take().unwrap_or(payload);
+            p.set(Some(first));
+        });
+    }
+}
+
+#[cfg(feature = "gsl")]
+unsafe fn c_str<'a>(s: *const c_char) -> Cow<'a, str> {
+    if s.is_null() {
+        Cow::Borrowed("")
+    } else {
+        CStr::from_ptr(s).to_string_lossy()
+    }
+}
+
+/// Status codes of `gsl_errno.h`
+#[cfg(not(feature = "gsl"))]
+mod codes {
+    use std::os::raw::c_int;
+
+    pub const GSL_SUCCESS: c_int = 0;
+    pub const GSL_FAILURE: c_int = -1;
+    pub const GSL_CONTINUE: c_int = -2;
+    pub const GSL_EDOM: c_int = 1;
+    pub const GSL_ERANGE: c_int = 2;
+    pub const GSL_EFAULT: c_int = 3;
+    pub const GSL_EINVAL: c_int = 4;
+    pub const GSL_EFAILED: c_int = 5;
+    pub const GSL_EFACTOR: c_int = 6;
+    pub const GSL_ESANITY: c_int = 7;
+    pub const GSL_ENOMEM: c_int = 8;
+    pub const GSL_EBADFUNC: c_int = 9;
+    pub const GSL_ERUNAWAY: c_int = 10;
+    pub const GSL_EMAXITER: c_int = 11;
+    pub const GSL_EZERODIV: c_int = 12;
+    pub const GSL_EBADTOL: c_int = 13;
+    pub const GSL_ETOL: c_int = 14;
+    pub const GSL_EUNDRFLW: c_int = 15;
+    pub const GSL_EOVRFLW: c_int = 16;
+    pub const GSL_ELOSS: c_int = 17;
+    pub const GSL_EROUND: c_int = 18;
+    pub const GSL_EBADLEN: c_int = 19;
+    pub const GSL_ENOTSQR: c_int = 20;
+    pub const GSL_ESING: c_int = 21;
+    pub const GSL_EDIVERGE: c_int = 22;
+    pub const GSL_EUNSUP: c_int = 23;
+    pub const GSL_EUNIMPL: c_int = 24;
+    pub const GSL_ECACHE: c_int = 25;
+    pub const GSL_ETABLE: c_int = 26;
+    pub const GSL_ENOPROG: c_int = 27;
+    pub const GSL_ENOPROGJ: c_int = 28;
+    pub const GSL_ETOLF: c_int = 29;
+    pub const GSL_ETOLX: c_int = 30;
+    pub const GSL_ETOLG: c_int = 31;
+    pub const GSL_EOF: c_int = 32;
+}
+
+#[test]
+#[cfg(feature = "gsl")]
+fn test_error_handler() {
+    use std::rc::Rc;
+
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let log = errors.clone();
+    set_error_handler(
+        move |reason: &str, file: &str, line: u32, error: GSLError| {
+            log.borrow_mut()
+                .push((reason.to_owned(), file.to_owned(), line, error));
+        },
+    );
+    assert_eq!(crate::sf::gamma(-1.0).unwrap_err(), GSLError::Domain);
+    assert!(crate::sf::gamma(5.0).is_ok());
+    {
+        let errors = errors.borrow();
+        assert_eq!(errors.len(), 1);
+        let (reason, file, line, error) = &errors[0];
+        assert!(reason.contains("domain"));
+        assert!(file.ends_with(".c"));
+        assert!(*line > 0);
+        assert_eq!(*error, GSLError::Domain);
+    }
+
+    // The panic surfaces in the wrapper, after GSL has returned
+    set_error_handler(PanicOnError);
+    let panic = catch_unwind(|| crate::sf::gamma(-1.0)).unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("gsl: ") && message.contains("Domain"));
+    assert!(crate::sf::gamma(5.0).is_ok());
+
+    crate::disable_error_handler();
+    assert_eq!(crate::sf::gamma(-1.0).unwrap_err(), GSLError::Domain);
+    assert_eq!(errors.borrow().len(), 1);
+}
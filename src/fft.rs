@@ -17,8 +17,11 @@
 */
 
 use crate::bindings::*;
+use crate::memory::Footprint;
 use crate::*;
+use drop_guard::guard;
 use num_complex::Complex64;
+use std::fmt;
 
 pub fn fft64_packed(data: &mut [f64]) -> Result<()> {
     unsafe {
@@ -90,6 +93,320 @@ pub fn fft64_unpack_norm(half_complex: &[f64]) -> Vec<f64> {
     fft64_unpack_iter(half_complex).map(|z| z.norm()).collect()
 }
 
+/// Inverse of `fft64_packed`, including the `1/n` normalization.
+pub fn ifft64_packed(data: &mut [f64]) -> Result<()> {
+    unsafe {
+        let n = data.len();
+
+        if n == 0 {
+            return Ok(());
+        }
+        if !n.is_power_of_two() {
+            return Err(GSLError::Invalid);
+        }
+
+        GSLError::from_raw(gsl_fft_halfcomplex_radix2_inverse(
+            data.as_mut_ptr(),
+            1,
+            n as u64,
+        ))?;
+
+        Ok(())
+    }
+}
+
+/// In place radix-2 complex transform. The length must be a power of two.
+pub fn complex_radix2(direction: Direction, data: &mut [Complex64]) -> Result<()> {
+    unsafe {
+        let n = data.len();
+
+        if n == 0 {
+            return Ok(());
+        }
+        if !n.is_power_of_two() {
+            return Err(GSLError::Invalid);
+        }
+
+        // Complex64 is repr(C) and laid out as [re, im]
+        let ptr = data.as_mut_ptr() as *mut f64;
+        GSLError::from_raw(match direction {
+            Direction::Forward => gsl_fft_complex_radix2_forward(ptr, 1, n as u64),
+            Direction::Backward => gsl_fft_complex_radix2_backward(ptr, 1, n as u64),
+            Direction::Inverse => gsl_fft_complex_radix2_inverse(ptr, 1, n as u64),
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// `exp(-2πi jk / n)`
+    Forward,
+    /// `exp(+2πi jk / n)`, without normalization
+    Backward,
+    /// Backward transform scaled by `1/n`
+    Inverse,
+}
+
+/// Mixed-radix transform of real data of a fixed length.
+///
+/// The forward transform leaves the result in place in the mixed-radix halfcomplex layout
+/// `[r0, r1, i1, r2, i2, ...]`, which differs from the radix-2 layout used by `fft64_packed`.
+pub struct RealFft {
+    n: usize,
+    wavetable: *mut gsl_fft_real_wavetable,
+    hc_wavetable: *mut gsl_fft_halfcomplex_wavetable,
+    workspace: *mut gsl_fft_real_workspace,
+    footprint: Footprint,
+}
+
+impl RealFft {
+    pub fn new(n: usize) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let wavetable = guard(gsl_fft_real_wavetable_alloc(n as u64), |wavetable| {
+                gsl_fft_real_wavetable_free(wavetable);
+            });
+            assert!(!wavetable.is_null());
+
+            let hc_wavetable = guard(gsl_fft_halfcomplex_wavetable_alloc(n as u64), |wavetable| {
+                gsl_fft_halfcomplex_wavetable_free(wavetable);
+            });
+            assert!(!hc_wavetable.is_null());
+
+            let workspace = guard(gsl_fft_real_workspace_alloc(n as u64), |workspace| {
+                gsl_fft_real_workspace_free(workspace);
+            });
+            assert!(!workspace.is_null());
+
+            // Both wavetables hold n / 2 complex twiddle factors, the workspace n doubles
+            let footprint = Footprint::new(
+                std::mem::size_of::<gsl_fft_real_wavetable>()
+                    + std::mem::size_of::<gsl_fft_halfcomplex_wavetable>()
+                    + std::mem::size_of::<gsl_fft_real_workspace>()
+                    + 3 * n * std::mem::size_of::<f64>(),
+            );
+
+            let fft = RealFft {
+                n,
+                wavetable: *wavetable,
+                hc_wavetable: *hc_wavetable,
+                workspace: *workspace,
+                footprint,
+            };
+            std::mem::forget(wavetable);
+            std::mem::forget(hc_wavetable);
+            std::mem::forget(workspace);
+
+            Ok(fft)
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Approximate memory held by the GSL wavetables and workspace, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    pub fn forward(&mut self, data: &mut [f64]) -> Result<()> {
+        unsafe {
+            if data.len() != self.n {
+                return Err(GSLError::BadLength);
+            }
+
+            GSLError::from_raw(gsl_fft_real_transform(
+                data.as_mut_ptr(),
+                1,
+                self.n as u64,
+                self.wavetable,
+                self.workspace,
+            ))?;
+
+            Ok(())
+        }
+    }
+
+    /// Inverse of `forward`, including the `1/n` normalization.
+    pub fn inverse(&mut self, data: &mut [f64]) -> Result<()> {
+        unsafe {
+            if data.len() != self.n {
+                return Err(GSLError::BadLength);
+            }
+
+            GSLError::from_raw(gsl_fft_halfcomplex_inverse(
+                data.as_mut_ptr(),
+                1,
+                self.n as u64,
+                self.hc_wavetable,
+                self.workspace,
+            ))?;
+
+            Ok(())
+        }
+    }
+
+    /// Unpacks the result of `forward` into all `n` complex coefficients.
+    pub fn unpack(&self, half_complex: &[f64]) -> Result<Vec<Complex64>> {
+        unsafe {
+            if half_complex.len() != self.n {
+                return Err(GSLError::BadLength);
+            }
+
+            let mut out = vec![Complex64::new(0.0, 0.0); self.n];
+            GSLError::from_raw(gsl_fft_halfcomplex_unpack(
+                half_complex.as_ptr(),
+                out.as_mut_ptr() as *mut f64,
+                1,
+                self.n as u64,
+            ))?;
+
+            Ok(out)
+        }
+    }
+}
+
+impl fmt::Debug for RealFft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RealFft")
+            .field("n", &self.n)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for RealFft {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_fft_real_wavetable_free(self.wavetable);
+            gsl_fft_halfcomplex_wavetable_free(self.hc_wavetable);
+            gsl_fft_real_workspace_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for RealFft {}
+unsafe impl Sync for RealFft {}
+
+/// Mixed-radix transform of complex data of a fixed length.
+pub struct ComplexFft {
+    n: usize,
+    wavetable: *mut gsl_fft_complex_wavetable,
+    workspace: *mut gsl_fft_complex_workspace,
+    footprint: Footprint,
+}
+
+impl ComplexFft {
+    pub fn new(n: usize) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let wavetable = guard(gsl_fft_complex_wavetable_alloc(n as u64), |wavetable| {
+                gsl_fft_complex_wavetable_free(wavetable);
+            });
+            assert!(!wavetable.is_null());
+
+            let workspace = guard(gsl_fft_complex_workspace_alloc(n as u64), |workspace| {
+                gsl_fft_complex_workspace_free(workspace);
+            });
+            assert!(!workspace.is_null());
+
+            // The wavetable holds n complex twiddle factors, the workspace n complex values
+            let footprint = Footprint::new(
+                std::mem::size_of::<gsl_fft_complex_wavetable>()
+                    + std::mem::size_of::<gsl_fft_complex_workspace>()
+                    + 4 * n * std::mem::size_of::<f64>(),
+            );
+
+            let fft = ComplexFft {
+                n,
+                wavetable: *wavetable,
+                workspace: *workspace,
+                footprint,
+            };
+            std::mem::forget(wavetable);
+            std::mem::forget(workspace);
+
+            Ok(fft)
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Approximate memory held by the GSL wavetables and workspace, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    pub fn transform(&mut self, direction: Direction, data: &mut [Complex64]) -> Result<()> {
+        unsafe {
+            if data.len() != self.n {
+                return Err(GSLError::BadLength);
+            }
+
+            // Complex64 is repr(C) and laid out as [re, im]
+            let ptr = data.as_mut_ptr() as *mut f64;
+            let n = self.n as u64;
+            GSLError::from_raw(match direction {
+                Direction::Forward => {
+                    gsl_fft_complex_forward(ptr, 1, n, self.wavetable, self.workspace)
+                }
+                Direction::Backward => {
+                    gsl_fft_complex_backward(ptr, 1, n, self.wavetable, self.workspace)
+                }
+                Direction::Inverse => {
+                    gsl_fft_complex_inverse(ptr, 1, n, self.wavetable, self.workspace)
+                }
+            })?;
+
+            Ok(())
+        }
+    }
+
+    pub fn forward(&mut self, data: &mut [Complex64]) -> Result<()> {
+        self.transform(Direction::Forward, data)
+    }
+
+    pub fn backward(&mut self, data: &mut [Complex64]) -> Result<()> {
+        self.transform(Direction::Backward, data)
+    }
+
+    pub fn inverse(&mut self, data: &mut [Complex64]) -> Result<()> {
+        self.transform(Direction::Inverse, data)
+    }
+}
+
+impl fmt::Debug for ComplexFft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComplexFft")
+            .field("n", &self.n)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ComplexFft {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_fft_complex_wavetable_free(self.wavetable);
+            gsl_fft_complex_workspace_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for ComplexFft {}
+unsafe impl Sync for ComplexFft {}
+
 #[test]
 fn test_fft() {
     // Generate test data
@@ -106,3 +423,86 @@ fn test_fft() {
     assert!(fft[164] > fft[163]);
     assert!(fft[164] > fft[165]);
 }
+
+#[test]
+fn test_ifft_roundtrip() {
+    disable_error_handler();
+
+    let y = (0..64).map(|x| (x as f64 * 0.3).sin()).collect::<Vec<_>>();
+    let mut data = y.clone();
+
+    fft64_packed(&mut data).unwrap();
+    ifft64_packed(&mut data).unwrap();
+
+    for (a, b) in y.iter().zip(data.iter()) {
+        approx::assert_abs_diff_eq!(a, b, epsilon = 1.0e-12);
+    }
+}
+
+#[test]
+fn test_complex_radix2_matches_mixed_radix() {
+    disable_error_handler();
+
+    let y = (0..32)
+        .map(|x| Complex64::new((x as f64 * 0.2).cos(), (x as f64 * 0.7).sin()))
+        .collect::<Vec<_>>();
+
+    let mut radix2 = y.clone();
+    complex_radix2(Direction::Forward, &mut radix2).unwrap();
+
+    let mut fft = ComplexFft::new(y.len()).unwrap();
+    let mut mixed = y.clone();
+    fft.forward(&mut mixed).unwrap();
+
+    for (a, b) in radix2.iter().zip(mixed.iter()) {
+        approx::assert_abs_diff_eq!(a.re, b.re, epsilon = 1.0e-9);
+        approx::assert_abs_diff_eq!(a.im, b.im, epsilon = 1.0e-9);
+    }
+
+    fft.inverse(&mut mixed).unwrap();
+    for (a, b) in y.iter().zip(mixed.iter()) {
+        approx::assert_abs_diff_eq!(a.re, b.re, epsilon = 1.0e-12);
+        approx::assert_abs_diff_eq!(a.im, b.im, epsilon = 1.0e-12);
+    }
+}
+
+#[test]
+fn test_real_mixed_radix() {
+    disable_error_handler();
+
+    // Length with factors 2, 3 and 5
+    let n = 30;
+    let y = (0..n)
+        .map(|x| (x as f64 / n as f64 * 3.0 * std::f64::consts::TAU).cos())
+        .collect::<Vec<_>>();
+
+    let mut fft = RealFft::new(n).unwrap();
+    let mut data = y.clone();
+    fft.forward(&mut data).unwrap();
+
+    // All power should be in bin 3 and its mirror
+    let spectrum = fft.unpack(&data).unwrap();
+    approx::assert_abs_diff_eq!(spectrum[3].norm(), n as f64 / 2.0, epsilon = 1.0e-9);
+    approx::assert_abs_diff_eq!(spectrum[n - 3].norm(), n as f64 / 2.0, epsilon = 1.0e-9);
+    approx::assert_abs_diff_eq!(spectrum[1].norm(), 0.0, epsilon = 1.0e-9);
+
+    fft.inverse(&mut data).unwrap();
+    for (a, b) in y.iter().zip(data.iter()) {
+        approx::assert_abs_diff_eq!(a, b, epsilon = 1.0e-12);
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Not a power of two
+    complex_radix2(Direction::Forward, &mut [Complex64::new(0.0, 0.0); 3]).unwrap_err();
+
+    // Empty transform
+    RealFft::new(0).unwrap_err();
+    ComplexFft::new(0).unwrap_err();
+
+    // Length mismatch
+    RealFft::new(4).unwrap().forward(&mut [0.0; 5]).unwrap_err();
+}
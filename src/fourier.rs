@@ -0,0 +1,226 @@
+/*
+    fourier.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::fft::RealFft;
+use crate::*;
+use std::f64::consts::PI;
+
+/*
+
+    For smooth periodic functions, the trapezoid rule over a full period converges exponentially,
+    which makes equispaced sampling followed by an FFT the natural way to compute Fourier coefficients.
+
+*/
+
+/// Integrates `f` over one period `[a, a + period]` using the trapezoid rule with `n` samples.
+/// This is spectrally accurate if `f` is smooth and periodic.
+pub fn integrate_periodic<F: FnMut(f64) -> f64>(
+    n: usize,
+    a: f64,
+    period: f64,
+    mut f: F,
+) -> Result<f64> {
+    if n == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if period <= 0.0 || !period.is_finite() {
+        return Err(GSLError::Domain);
+    }
+
+    let h = period / n as f64;
+    let sum = (0..n).map(|j| f(a + j as f64 * h)).sum::<f64>();
+    if !sum.is_finite() {
+        return Err(GSLError::BadFunction);
+    }
+
+    Ok(h * sum)
+}
+
+/// Truncated Fourier series
+/// `f(x) ≈ a_0 / 2 + Σ_k a_k cos(2πk (x - x_0) / T) + b_k sin(2πk (x - x_0) / T)`
+/// of a function with period `T`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FourierSeries {
+    x0: f64,
+    period: f64,
+    cos: Box<[f64]>,
+    sin: Box<[f64]>,
+}
+
+impl FourierSeries {
+    /// Computes the coefficients up to `order` of `f` over the period `[x0, x0 + period]`.
+    /// See `with_samples`, this uses `4 (order + 1)` samples.
+    pub fn new<F: FnMut(f64) -> f64>(order: usize, x0: f64, period: f64, f: F) -> Result<Self> {
+        Self::with_samples(order, 4 * (order + 1), x0, period, f)
+    }
+
+    /// Computes the coefficients up to `order` of `f` over the period `[x0, x0 + period]`,
+    /// using a real FFT of `samples` equispaced function values.
+    /// At least `2 order + 1` samples are required, more samples reduce aliasing.
+    pub fn with_samples<F: FnMut(f64) -> f64>(
+        order: usize,
+        samples: usize,
+        x0: f64,
+        period: f64,
+        mut f: F,
+    ) -> Result<Self> {
+        if samples < 2 * order + 1 {
+            return Err(GSLError::Invalid);
+        }
+        if period <= 0.0 || !period.is_finite() {
+            return Err(GSLError::Domain);
+        }
+
+        let h = period / samples as f64;
+        let mut data = (0..samples)
+            .map(|j| f(x0 + j as f64 * h))
+            .collect::<Vec<_>>();
+        if data.iter().any(|y| !y.is_finite()) {
+            return Err(GSLError::BadFunction);
+        }
+
+        RealFft::new(samples)?.forward(&mut data)?;
+
+        // Halfcomplex layout [r0, r1, i1, r2, i2, ...] of the unnormalized transform
+        let scale = 2.0 / samples as f64;
+        let cos = (0..=order)
+            .map(|k| scale * if k == 0 { data[0] } else { data[2 * k - 1] })
+            .collect();
+        let sin = (0..=order)
+            .map(|k| if k == 0 { 0.0 } else { -scale * data[2 * k] })
+            .collect();
+
+        Ok(FourierSeries {
+            x0,
+            period,
+            cos,
+            sin,
+        })
+    }
+
+    pub fn order(&self) -> usize {
+        self.cos.len() - 1
+    }
+
+    pub fn period(&self) -> f64 {
+        self.period
+    }
+
+    /// Cosine coefficients `a_k`, starting with `a_0`
+    pub fn cos_coefficients(&self) -> &[f64] {
+        &self.cos
+    }
+
+    /// Sine coefficients `b_k`, starting with `b_0 = 0`
+    pub fn sin_coefficients(&self) -> &[f64] {
+        &self.sin
+    }
+
+    /// Mean over one period, `a_0 / 2`
+    pub fn mean(&self) -> f64 {
+        self.cos[0] / 2.0
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        let theta = 2.0 * PI * (x - self.x0) / self.period;
+        self.mean()
+            + (1..=self.order())
+                .map(|k| {
+                    let (sin, cos) = (k as f64 * theta).sin_cos();
+                    self.cos[k] * cos + self.sin[k] * sin
+                })
+                .sum::<f64>()
+    }
+
+    /// Series of the derivative
+    pub fn derivative(&self) -> Self {
+        let omega = 2.0 * PI / self.period;
+        FourierSeries {
+            x0: self.x0,
+            period: self.period,
+            cos: (0..=self.order())
+                .map(|k| k as f64 * omega * self.sin[k])
+                .collect(),
+            sin: (0..=self.order())
+                .map(|k| -(k as f64) * omega * self.cos[k])
+                .collect(),
+        }
+    }
+
+    /// Power `(a_k^2 + b_k^2) / 2` in every harmonic, which sum to the mean square of `f` minus `mean^2`
+    pub fn power_spectrum(&self) -> Box<[f64]> {
+        self.cos
+            .iter()
+            .zip(self.sin.iter())
+            .enumerate()
+            .map(|(k, (a, b))| if k == 0 { 0.0 } else { (a * a + b * b) / 2.0 })
+            .collect()
+    }
+}
+
+#[test]
+fn test_integrate_periodic() {
+    // ∫ exp(cos x) dx over [0, 2π] = 2π I_0(1)
+    let integral = integrate_periodic(32, 0.0, 2.0 * PI, |x| x.cos().exp()).unwrap();
+    approx::assert_abs_diff_eq!(
+        integral,
+        2.0 * PI * 1.266_065_877_752_008_4,
+        epsilon = 1.0e-14
+    );
+}
+
+#[test]
+fn test_fourier_series() {
+    disable_error_handler();
+
+    let f = |x: f64| 1.0 + 2.0 * (3.0 * x).cos() - 0.5 * x.sin() + 0.25 * (4.0 * x).sin();
+    let series = FourierSeries::new(6, 0.0, 2.0 * PI, f).unwrap();
+
+    approx::assert_abs_diff_eq!(series.mean(), 1.0, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(series.cos_coefficients()[3], 2.0, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(series.sin_coefficients()[1], -0.5, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(series.sin_coefficients()[4], 0.25, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(series.power_spectrum()[3], 2.0, epsilon = 1.0e-14);
+
+    let derivative = series.derivative();
+    for i in 0..20 {
+        let x = i as f64 * 0.3;
+        approx::assert_abs_diff_eq!(series.eval(x), f(x), epsilon = 1.0e-13);
+        let df = -6.0 * (3.0 * x).sin() - 0.5 * x.cos() + (4.0 * x).cos();
+        approx::assert_abs_diff_eq!(derivative.eval(x), df, epsilon = 1.0e-12);
+    }
+
+    // Shifted period of a smooth non polynomial function
+    let series = FourierSeries::with_samples(20, 64, 1.0, 2.0, |x| (PI * x).sin().exp()).unwrap();
+    approx::assert_abs_diff_eq!(series.eval(1.3), (PI * 1.3).sin().exp(), epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Too few samples
+    FourierSeries::with_samples(10, 20, 0.0, 1.0, |x| x).unwrap_err();
+
+    // Empty period
+    FourierSeries::new(10, 0.0, 0.0, |x| x).unwrap_err();
+    integrate_periodic(10, 0.0, -1.0, |x| x).unwrap_err();
+
+    // Non finite function
+    integrate_periodic(10, 0.0, 1.0, |_| f64::NAN).unwrap_err();
+}
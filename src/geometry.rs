@@ -0,0 +1,584 @@
+/*
+    geometry.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::rng::Rng;
+use crate::*;
+use std::ops::Mul;
+
+/*
+
+    Rotations in space are unit quaternions `q = cos(θ/2) + sin(θ/2) (x i + y j + z k)`
+    for a rotation by the angle `θ` around the unit axis `(x, y, z)`, following the right hand rule.
+    `q` and `-q` are the same rotation.
+
+    The product `p * q` rotates by `q` first and by `p` after.
+
+    Uniformly distributed directions on the unit sphere in four dimensions
+    are uniformly distributed rotations (with respect to the Haar measure).
+
+    Points on the sphere are directions, or latitude and longitude in radians.
+    Angles between directions use `atan2(|a × b|, a · b)`, which is accurate for small and nearly opposite angles,
+    unlike the arc cosine of the dot product.
+
+    The mean direction of unit vectors is the direction of their sum. The mean resultant length `R̄`,
+    the length of the sum divided by the number of vectors, is 1 if all directions agree
+    and close to 0 if they are spread out uniformly.
+
+    Spherical coordinates are `(r, θ, φ)` with the polar angle `θ` in `[0, π]` from the `z` axis
+    and the azimuth `φ` in `(-π, π]`, cylindrical coordinates are `(ρ, φ, z)`. The Jacobian matrices
+    `∂(x, y, z) / ∂(r, θ, φ)` and `∂(x, y, z) / ∂(ρ, φ, z)` have the determinants `r² sin θ` and `ρ`,
+    the volume elements for integrating in these coordinates.
+
+    Components of vector fields are taken along the orthonormal local basis vectors,
+    `(e_r, e_θ, e_φ)` and `(e_ρ, e_φ, e_z)`, which are the normalized columns of the Jacobian.
+
+*/
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Quaternion = Quaternion {
+        w: 1.0,
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Quaternion { w, x, y, z }
+    }
+
+    /// Rotation by `angle` around `axis`, which doesn't need to be normalized
+    pub fn from_axis_angle(axis: [f64; 3], angle: f64) -> Result<Self> {
+        let [x, y, z] = normalize(axis)?;
+        let (sin, cos) = (0.5 * angle).sin_cos();
+        Ok(Quaternion::new(cos, sin * x, sin * y, sin * z))
+    }
+
+    /// Shortest rotation that turns the direction `from` into the direction `to`.
+    /// Opposite directions are turned around an arbitrary perpendicular axis.
+    pub fn between(from: [f64; 3], to: [f64; 3]) -> Result<Self> {
+        let u = normalize(from)?;
+        let v = normalize(to)?;
+        let dot = u[0] * v[0] + u[1] * v[1] + u[2] * v[2];
+        if dot < -1.0 + 1.0e-12 {
+            // Cross with the coordinate axis that is most perpendicular to u
+            let axis = if u[0].abs() < 0.5 {
+                [0.0, u[2], -u[1]]
+            } else {
+                [u[1], -u[0], 0.0]
+            };
+            return Quaternion::from_axis_angle(axis, std::f64::consts::PI);
+        }
+
+        // Half way between u and v, the rotation around the cross product by twice the half angle
+        let cross = cross(u, v);
+        Quaternion::new(1.0 + dot, cross[0], cross[1], cross[2]).normalize()
+    }
+
+    /// Uniformly distributed rotation
+    pub fn random(rng: &mut Rng) -> Self {
+        let q = rng.dir_nd(4);
+        Quaternion::new(q[0], q[1], q[2], q[3])
+    }
+
+    pub fn norm(&self) -> f64 {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    /// Unit quaternion in the same direction.
+    /// Fails with `GSLError::Domain` for the zero quaternion.
+    pub fn normalize(&self) -> Result<Self> {
+        let norm = self.norm();
+        if !(norm > 0.0 && norm.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Ok(Quaternion::new(
+            self.w / norm,
+            self.x / norm,
+            self.y / norm,
+            self.z / norm,
+        ))
+    }
+
+    /// Inverse rotation of a unit quaternion
+    pub fn conjugate(&self) -> Self {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Unit axis and angle in `[0, π]` of the rotation, with the `x` axis for the identity
+    pub fn to_axis_angle(&self) -> ([f64; 3], f64) {
+        // Pick the representative with w >= 0, which has an angle of at most π
+        let q = if self.w < 0.0 {
+            Quaternion::new(-self.w, -self.x, -self.y, -self.z)
+        } else {
+            *self
+        };
+        let sin = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        if sin == 0.0 {
+            return ([1.0, 0.0, 0.0], 0.0);
+        }
+        let angle = 2.0 * sin.atan2(q.w);
+        ([q.x / sin, q.y / sin, q.z / sin], angle)
+    }
+
+    /// Rotates the vector `v` by a unit quaternion
+    pub fn rotate(&self, v: [f64; 3]) -> [f64; 3] {
+        // v + 2 r × (r × v + w v) with r the vector part
+        let r = [self.x, self.y, self.z];
+        let c = cross(r, v);
+        let t = [
+            c[0] + self.w * v[0],
+            c[1] + self.w * v[1],
+            c[2] + self.w * v[2],
+        ];
+        let d = cross(r, t);
+        [v[0] + 2.0 * d[0], v[1] + 2.0 * d[1], v[2] + 2.0 * d[2]]
+    }
+
+    /// Row major rotation matrix of a unit quaternion
+    pub fn to_matrix(&self) -> [[f64; 3]; 3] {
+        let Quaternion { w, x, y, z } = *self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// Composition, rotating by `rhs` first
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        let (a, b) = (self, rhs);
+        Quaternion::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+    }
+}
+
+/// Mean direction of directions on the unit sphere
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MeanDirection {
+    pub direction: [f64; 3],
+    /// Length of the mean of the unit vectors, in `[0, 1]`
+    pub resultant_length: f64,
+}
+
+/// Unit vector at latitude `lat` and longitude `lon`, in radians
+pub fn from_lat_lon(lat: f64, lon: f64) -> [f64; 3] {
+    let (sin_lat, cos_lat) = lat.sin_cos();
+    let (sin_lon, cos_lon) = lon.sin_cos();
+    [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat]
+}
+
+/// Latitude in `[-π/2, π/2]` and longitude in `(-π, π]` of the direction `v`, in radians
+pub fn to_lat_lon(v: [f64; 3]) -> Result<(f64, f64)> {
+    let [x, y, z] = normalize(v)?;
+    Ok((z.atan2(x.hypot(y)), y.atan2(x)))
+}
+
+/// Angle in `[0, π]` between the directions `a` and `b`,
+/// which is the great circle distance on the unit sphere
+pub fn angle_between(a: [f64; 3], b: [f64; 3]) -> Result<f64> {
+    let a = normalize(a)?;
+    let b = normalize(b)?;
+    let c = cross(a, b);
+    let sin = (c[0] * c[0] + c[1] * c[1] + c[2] * c[2]).sqrt();
+    let cos = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    Ok(sin.atan2(cos))
+}
+
+/// Great circle distance on the unit sphere between two points given by latitude and longitude, in radians
+pub fn great_circle_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    // Vincenty's formula for the sphere
+    let (sin1, cos1) = lat1.sin_cos();
+    let (sin2, cos2) = lat2.sin_cos();
+    let (sin_dlon, cos_dlon) = (lon2 - lon1).sin_cos();
+    let a = cos2 * sin_dlon;
+    let b = cos1 * sin2 - sin1 * cos2 * cos_dlon;
+    a.hypot(b).atan2(sin1 * sin2 + cos1 * cos2 * cos_dlon)
+}
+
+/// Mean direction of the directions `v`, which don't need to be normalized.
+/// Fails with `GSLError::Domain` if the unit vectors sum to zero.
+pub fn mean_direction(v: &[[f64; 3]]) -> Result<MeanDirection> {
+    if v.is_empty() {
+        return Err(GSLError::Invalid);
+    }
+    let mut sum = [0.0; 3];
+    for vi in v {
+        let u = normalize(*vi)?;
+        for (s, uk) in sum.iter_mut().zip(u) {
+            *s += uk;
+        }
+    }
+    let length = (sum[0] * sum[0] + sum[1] * sum[1] + sum[2] * sum[2]).sqrt();
+    Ok(MeanDirection {
+        direction: normalize(sum)?,
+        resultant_length: (length / v.len() as f64).min(1.0),
+    })
+}
+
+/// Spherical coordinates `(r, θ, φ)` of the point `p`
+pub fn to_spherical(p: [f64; 3]) -> [f64; 3] {
+    let [x, y, z] = p;
+    let rho = x.hypot(y);
+    [rho.hypot(z), rho.atan2(z), y.atan2(x)]
+}
+
+/// Point with the spherical coordinates `(r, θ, φ)`
+pub fn from_spherical(s: [f64; 3]) -> [f64; 3] {
+    let [r, theta, phi] = s;
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    [
+        r * sin_theta * cos_phi,
+        r * sin_theta * sin_phi,
+        r * cos_theta,
+    ]
+}
+
+/// Cylindrical coordinates `(ρ, φ, z)` of the point `p`
+pub fn to_cylindrical(p: [f64; 3]) -> [f64; 3] {
+    let [x, y, z] = p;
+    [x.hypot(y), y.atan2(x), z]
+}
+
+/// Point with the cylindrical coordinates `(ρ, φ, z)`
+pub fn from_cylindrical(c: [f64; 3]) -> [f64; 3] {
+    let [rho, phi, z] = c;
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    [rho * cos_phi, rho * sin_phi, z]
+}
+
+/// Jacobian matrix `∂(x, y, z) / ∂(r, θ, φ)` at the spherical coordinates `s`
+pub fn spherical_jacobian(s: [f64; 3]) -> Matrix {
+    let r = s[0];
+    let [e_r, e_theta, e_phi] = spherical_basis(s);
+    let sin_theta = s[1].sin();
+    Matrix::from([0, 1, 2].map(|i| [e_r[i], r * e_theta[i], r * sin_theta * e_phi[i]]))
+}
+
+/// Jacobian matrix `∂(x, y, z) / ∂(ρ, φ, z)` at the cylindrical coordinates `c`
+pub fn cylindrical_jacobian(c: [f64; 3]) -> Matrix {
+    let rho = c[0];
+    let [e_rho, e_phi, e_z] = cylindrical_basis(c);
+    Matrix::from([0, 1, 2].map(|i| [e_rho[i], rho * e_phi[i], e_z[i]]))
+}
+
+/// Determinant `r² sin θ` of the spherical Jacobian
+pub fn spherical_volume_element(s: [f64; 3]) -> f64 {
+    s[0] * s[0] * s[1].sin()
+}
+
+/// Determinant `ρ` of the cylindrical Jacobian
+pub fn cylindrical_volume_element(c: [f64; 3]) -> f64 {
+    c[0]
+}
+
+/// Components `(v_r, v_θ, v_φ)` of the vector `v` at the point `p`
+pub fn vector_to_spherical(p: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    spherical_basis(to_spherical(p)).map(|e| dot(e, v))
+}
+
+/// Cartesian vector with the components `(v_r, v_θ, v_φ)` at the spherical coordinates `s`
+pub fn vector_from_spherical(s: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    combine(spherical_basis(s), v)
+}
+
+/// Components `(v_ρ, v_φ, v_z)` of the vector `v` at the point `p`
+pub fn vector_to_cylindrical(p: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    cylindrical_basis(to_cylindrical(p)).map(|e| dot(e, v))
+}
+
+/// Cartesian vector with the components `(v_ρ, v_φ, v_z)` at the cylindrical coordinates `c`
+pub fn vector_from_cylindrical(c: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    combine(cylindrical_basis(c), v)
+}
+
+/// Local basis `(e_r, e_θ, e_φ)`
+fn spherical_basis(s: [f64; 3]) -> [[f64; 3]; 3] {
+    let (sin_theta, cos_theta) = s[1].sin_cos();
+    let (sin_phi, cos_phi) = s[2].sin_cos();
+    [
+        [sin_theta * cos_phi, sin_theta * sin_phi, cos_theta],
+        [cos_theta * cos_phi, cos_theta * sin_phi, -sin_theta],
+        [-sin_phi, cos_phi, 0.0],
+    ]
+}
+
+/// Local basis `(e_ρ, e_φ, e_z)`
+fn cylindrical_basis(c: [f64; 3]) -> [[f64; 3]; 3] {
+    let (sin_phi, cos_phi) = c[1].sin_cos();
+    [
+        [cos_phi, sin_phi, 0.0],
+        [-sin_phi, cos_phi, 0.0],
+        [0.0, 0.0, 1.0],
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// `Σ v_k e_k`
+fn combine(basis: [[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    [0, 1, 2].map(|i| basis[0][i] * v[0] + basis[1][i] * v[1] + basis[2][i] * v[2])
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(crate) fn normalize(v: [f64; 3]) -> Result<[f64; 3]> {
+    let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if !(norm > 0.0 && norm.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+    Ok([v[0] / norm, v[1] / norm, v[2] / norm])
+}
+
+#[cfg(test)]
+fn assert_vec_eq(a: [f64; 3], b: [f64; 3]) {
+    for (a, b) in a.iter().zip(b) {
+        approx::assert_abs_diff_eq!(*a, b, epsilon = 1.0e-12);
+    }
+}
+
+#[test]
+fn test_quaternion() {
+    disable_error_handler();
+
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    // A quarter turn around z takes x to y
+    let q = Quaternion::from_axis_angle([0.0, 0.0, 2.0], FRAC_PI_2).unwrap();
+    assert_vec_eq(q.rotate([1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+    let (axis, angle) = q.to_axis_angle();
+    assert_vec_eq(axis, [0.0, 0.0, 1.0]);
+    approx::assert_abs_diff_eq!(angle, FRAC_PI_2, epsilon = 1.0e-12);
+
+    // Composition rotates by the right hand side first
+    let p = Quaternion::from_axis_angle([1.0, 0.0, 0.0], FRAC_PI_2).unwrap();
+    let v = [1.0, 2.0, 3.0];
+    assert_vec_eq((p * q).rotate(v), p.rotate(q.rotate(v)));
+    assert_vec_eq((q * q.conjugate()).rotate(v), v);
+
+    // The matrix agrees with the rotation
+    let m = (p * q).to_matrix();
+    let r = (p * q).rotate(v);
+    for i in 0..3 {
+        approx::assert_abs_diff_eq!(
+            m[i][0] * v[0] + m[i][1] * v[1] + m[i][2] * v[2],
+            r[i],
+            epsilon = 1.0e-12
+        );
+    }
+
+    // Rotations between directions, including opposite ones
+    for to in [[0.0, 3.0, 4.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]] {
+        let q = Quaternion::between([2.0, 0.0, 0.0], to).unwrap();
+        let norm = (to[0] * to[0] + to[1] * to[1] + to[2] * to[2]).sqrt();
+        assert_vec_eq(q.rotate([1.0, 0.0, 0.0]), to.map(|x| x / norm));
+    }
+    let (_, angle) = Quaternion::between([0.0, 0.0, 1.0], [0.0, 0.0, -1.0])
+        .unwrap()
+        .to_axis_angle();
+    approx::assert_abs_diff_eq!(angle, PI, epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_random_rotation() {
+    disable_error_handler();
+
+    // A fixed vector rotated uniformly is a uniform direction
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 100_000;
+    let mut mean = [0.0; 3];
+    let mut z2 = 0.0;
+    for _ in 0..n {
+        let q = Quaternion::random(&mut rng);
+        approx::assert_abs_diff_eq!(q.norm(), 1.0, epsilon = 1.0e-12);
+        let v = q.rotate([0.0, 0.0, 1.0]);
+        for i in 0..3 {
+            mean[i] += v[i] / n as f64;
+        }
+        z2 += v[2] * v[2] / n as f64;
+    }
+    for m in mean {
+        approx::assert_abs_diff_eq!(m, 0.0, epsilon = 0.01);
+    }
+    approx::assert_abs_diff_eq!(z2, 1.0 / 3.0, epsilon = 0.01);
+}
+
+#[test]
+fn test_spherical() {
+    disable_error_handler();
+
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    let v = from_lat_lon(FRAC_PI_4, -FRAC_PI_2);
+    assert_vec_eq(v, [0.0, -0.5f64.sqrt(), 0.5f64.sqrt()]);
+    let (lat, lon) = to_lat_lon(v).unwrap();
+    approx::assert_abs_diff_eq!(lat, FRAC_PI_4, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(lon, -FRAC_PI_2, epsilon = 1.0e-12);
+
+    // Both distances agree, also for tiny and nearly antipodal separations
+    for (lat1, lon1, lat2, lon2) in [
+        (0.1, 0.2, -0.7, 2.5),
+        (0.5, 1.0, 0.5, 1.0 + 1.0e-9),
+        (0.3, 0.0, -0.3, PI - 1.0e-9),
+    ] {
+        let d = great_circle_distance(lat1, lon1, lat2, lon2);
+        let angle = angle_between(from_lat_lon(lat1, lon1), from_lat_lon(lat2, lon2)).unwrap();
+        approx::assert_relative_eq!(d, angle, max_relative = 1.0e-6);
+    }
+    approx::assert_abs_diff_eq!(
+        great_circle_distance(0.0, 0.0, 0.0, FRAC_PI_2),
+        FRAC_PI_2,
+        epsilon = 1.0e-12
+    );
+    approx::assert_abs_diff_eq!(
+        great_circle_distance(FRAC_PI_2, 0.0, -FRAC_PI_2, 1.0),
+        PI,
+        epsilon = 1.0e-12
+    );
+
+    // Symmetric around the z axis
+    let mean = mean_direction(&[
+        [1.0, 0.0, 1.0],
+        [-2.0, 0.0, 2.0],
+        [0.0, 1.0, 1.0],
+        [0.0, -1.0, 1.0],
+    ])
+    .unwrap();
+    assert_vec_eq(mean.direction, [0.0, 0.0, 1.0]);
+    approx::assert_abs_diff_eq!(mean.resultant_length, 0.5f64.sqrt(), epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_coordinates() {
+    disable_error_handler();
+
+    let p = [1.0, -2.0, 0.5];
+    let s = to_spherical(p);
+    let c = to_cylindrical(p);
+    assert_vec_eq(from_spherical(s), p);
+    assert_vec_eq(from_cylindrical(c), p);
+    approx::assert_abs_diff_eq!(s[0], 5.25f64.sqrt(), epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(c[0], 5.0f64.sqrt(), epsilon = 1.0e-12);
+    assert_eq!(s[2], c[1]);
+
+    // Jacobians agree with central differences, and their determinants with the volume elements
+    type Transform = fn([f64; 3]) -> [f64; 3];
+    type Jacobian = fn([f64; 3]) -> Matrix;
+    type VolumeElement = fn([f64; 3]) -> f64;
+    let cases: [(Transform, Jacobian, VolumeElement, [f64; 3]); 2] = [
+        (
+            from_spherical,
+            spherical_jacobian,
+            spherical_volume_element,
+            s,
+        ),
+        (
+            from_cylindrical,
+            cylindrical_jacobian,
+            cylindrical_volume_element,
+            c,
+        ),
+    ];
+    for (transform, jacobian, volume_element, q) in cases {
+        let j = jacobian(q);
+        let h = 1.0e-6;
+        for k in 0..3 {
+            let (mut plus, mut minus) = (q, q);
+            plus[k] += h;
+            minus[k] -= h;
+            let (plus, minus) = (transform(plus), transform(minus));
+            for i in 0..3 {
+                approx::assert_abs_diff_eq!(
+                    j.elem_ij(i, k),
+                    (plus[i] - minus[i]) / (2.0 * h),
+                    epsilon = 1.0e-8
+                );
+            }
+        }
+        let [r0, r1, r2] = [0, 1, 2].map(|i| j.row(i).to_array());
+        let det = dot(r0, cross(r1, r2));
+        approx::assert_abs_diff_eq!(det, volume_element(q), epsilon = 1.0e-12);
+    }
+
+    // A radial field only has a radial component, and a rotation around z only an azimuthal one
+    let v = [0.2, -0.4, 0.1];
+    assert_vec_eq(vector_to_spherical(p, v), [5.25f64.sqrt() * 0.2, 0.0, 0.0]);
+    assert_vec_eq(
+        vector_to_cylindrical(p, [2.0, 1.0, 0.0]),
+        [0.0, 5.0f64.sqrt(), 0.0],
+    );
+
+    // The components transform back, and keep the length
+    let v = [0.3, 1.1, -0.7];
+    let vs = vector_to_spherical(p, v);
+    assert_vec_eq(vector_from_spherical(s, vs), v);
+    approx::assert_abs_diff_eq!(dot(vs, vs), dot(v, v), epsilon = 1.0e-12);
+    assert_vec_eq(vector_from_cylindrical(c, vector_to_cylindrical(p, v)), v);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Zero axis or direction
+    Quaternion::from_axis_angle([0.0; 3], 1.0).unwrap_err();
+    Quaternion::between([0.0; 3], [1.0, 0.0, 0.0]).unwrap_err();
+    Quaternion::new(0.0, 0.0, 0.0, 0.0).normalize().unwrap_err();
+    angle_between([1.0, 0.0, 0.0], [0.0; 3]).unwrap_err();
+    to_lat_lon([0.0; 3]).unwrap_err();
+
+    // No directions, or directions that cancel
+    mean_direction(&[]).unwrap_err();
+    mean_direction(&[[1.0, 0.0, 0.0], [-1.0, 0.0, 0.0]]).unwrap_err();
+}
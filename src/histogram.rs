@@ -0,0 +1,262 @@
+/*
+    histogram.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+use std::fmt;
+
+/// One dimensional histogram with `n` bins.
+/// Bin `i` covers the half open range `[range[i], range[i + 1])`.
+pub struct Histogram {
+    histogram: *mut gsl_histogram,
+}
+
+impl Histogram {
+    /// Histogram with `n` bins of equal width covering `[min, max)`
+    pub fn uniform(n: usize, min: f64, max: f64) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+            if max <= min || min.is_nan() || max.is_nan() {
+                return Err(GSLError::Domain);
+            }
+
+            let histogram = Self::alloc(n);
+            GSLError::from_raw(gsl_histogram_set_ranges_uniform(
+                histogram.histogram,
+                min,
+                max,
+            ))?;
+            Ok(histogram)
+        }
+    }
+
+    /// Histogram with `ranges.len() - 1` bins, the ranges must be strictly increasing
+    pub fn with_ranges(ranges: &[f64]) -> Result<Self> {
+        unsafe {
+            if ranges.len() < 2 {
+                return Err(GSLError::Invalid);
+            }
+            if ranges.iter().any(|x| x.is_nan()) || ranges.windows(2).any(|w| w[1] <= w[0]) {
+                return Err(GSLError::Domain);
+            }
+
+            let histogram = Self::alloc(ranges.len() - 1);
+            GSLError::from_raw(gsl_histogram_set_ranges(
+                histogram.histogram,
+                ranges.as_ptr(),
+                ranges.len() as u64,
+            ))?;
+            Ok(histogram)
+        }
+    }
+
+    unsafe fn alloc(n: usize) -> Self {
+        let histogram = gsl_histogram_alloc(n as u64);
+        assert!(!histogram.is_null());
+        Histogram { histogram }
+    }
+
+    pub fn n(&self) -> usize {
+        unsafe { gsl_histogram_bins(self.histogram) as usize }
+    }
+
+    /// Adds one to the bin containing `x`. Fails with `GSLError::Domain` if `x` is out of range.
+    pub fn increment(&mut self, x: f64) -> Result<()> {
+        unsafe { GSLError::from_raw(gsl_histogram_increment(self.histogram, x)) }
+    }
+
+    /// Adds `weight` to the bin containing `x`. Fails with `GSLError::Domain` if `x` is out of range.
+    pub fn accumulate(&mut self, x: f64, weight: f64) -> Result<()> {
+        unsafe { GSLError::from_raw(gsl_histogram_accumulate(self.histogram, x, weight)) }
+    }
+
+    pub fn get(&self, i: usize) -> f64 {
+        assert!(i < self.n());
+        unsafe { gsl_histogram_get(self.histogram, i as u64) }
+    }
+
+    pub fn bins(&self) -> Box<[f64]> {
+        (0..self.n()).map(|i| self.get(i)).collect()
+    }
+
+    /// Lower and upper limit of bin `i`
+    pub fn range(&self, i: usize) -> (f64, f64) {
+        assert!(i < self.n());
+        unsafe {
+            let mut lower = 0.0;
+            let mut upper = 0.0;
+            gsl_histogram_get_range(self.histogram, i as u64, &mut lower, &mut upper);
+            (lower, upper)
+        }
+    }
+
+    /// All `n + 1` bin edges
+    pub fn ranges(&self) -> Box<[f64]> {
+        let n = self.n();
+        (0..n)
+            .map(|i| self.range(i).0)
+            .chain(std::iter::once(self.max()))
+            .collect()
+    }
+
+    pub fn min(&self) -> f64 {
+        unsafe { gsl_histogram_min(self.histogram) }
+    }
+
+    pub fn max(&self) -> f64 {
+        unsafe { gsl_histogram_max(self.histogram) }
+    }
+
+    /// Index of the bin containing `x`
+    pub fn find(&self, x: f64) -> Option<usize> {
+        unsafe {
+            let mut i = 0;
+            if gsl_histogram_find(self.histogram, x, &mut i) == GSL_SUCCESS {
+                Some(i as usize)
+            } else {
+                None
+            }
+        }
+    }
+
+    pub fn sum(&self) -> f64 {
+        unsafe { gsl_histogram_sum(self.histogram) }
+    }
+
+    /// Mean of the histogrammed variable, using the bin centers
+    pub fn mean(&self) -> f64 {
+        unsafe { gsl_histogram_mean(self.histogram) }
+    }
+
+    /// Standard deviation of the histogrammed variable, using the bin centers
+    pub fn sigma(&self) -> f64 {
+        unsafe { gsl_histogram_sigma(self.histogram) }
+    }
+
+    /// Index of the bin with the largest value
+    pub fn max_bin(&self) -> usize {
+        unsafe { gsl_histogram_max_bin(self.histogram) as usize }
+    }
+
+    pub fn reset(&mut self) {
+        unsafe {
+            gsl_histogram_reset(self.histogram);
+        }
+    }
+
+    pub(crate) fn as_gsl(&self) -> *const gsl_histogram {
+        self.histogram
+    }
+
+    pub(crate) fn as_gsl_mut(&mut self) -> *mut gsl_histogram {
+        self.histogram
+    }
+}
+
+/// Bin contents
+impl From<&Histogram> for Vec<f64> {
+    fn from(h: &Histogram) -> Self {
+        h.bins().into_vec()
+    }
+}
+
+impl From<Histogram> for Vec<f64> {
+    fn from(h: Histogram) -> Self {
+        Vec::from(&h)
+    }
+}
+
+impl Clone for Histogram {
+    fn clone(&self) -> Self {
+        unsafe {
+            let histogram = gsl_histogram_clone(self.histogram);
+            assert!(!histogram.is_null());
+            Histogram { histogram }
+        }
+    }
+}
+
+impl fmt::Debug for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Histogram")
+            .field("ranges", &self.ranges())
+            .field("bins", &self.bins())
+            .finish()
+    }
+}
+
+impl Drop for Histogram {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_histogram_free(self.histogram);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for Histogram {}
+unsafe impl Sync for Histogram {}
+
+#[test]
+fn test_histogram() {
+    disable_error_handler();
+
+    let mut h = Histogram::uniform(4, 0.0, 4.0).unwrap();
+    for x in [0.5, 1.5, 1.7, 3.9] {
+        h.increment(x).unwrap();
+    }
+    h.accumulate(2.0, 0.5).unwrap();
+
+    assert_eq!(h.n(), 4);
+    assert_eq!(&*h.bins(), &[1.0, 2.0, 0.5, 1.0]);
+    assert_eq!(&*h.ranges(), &[0.0, 1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(h.range(2), (2.0, 3.0));
+    assert_eq!(h.find(2.5), Some(2));
+    assert_eq!(h.find(4.0), None);
+    assert_eq!(h.max_bin(), 1);
+    assert_eq!(h.sum(), 4.5);
+    assert_eq!(Vec::from(&h), [1.0, 2.0, 0.5, 1.0]);
+
+    let clone = h.clone();
+    h.reset();
+    assert_eq!(h.sum(), 0.0);
+    assert_eq!(clone.sum(), 4.5);
+
+    let h = Histogram::with_ranges(&[0.0, 1.0, 10.0]).unwrap();
+    assert_eq!(h.range(1), (1.0, 10.0));
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Out of range
+    let mut h = Histogram::uniform(4, 0.0, 4.0).unwrap();
+    h.increment(-1.0).unwrap_err();
+    h.increment(4.0).unwrap_err();
+
+    // Empty range
+    Histogram::uniform(4, 1.0, 1.0).unwrap_err();
+    Histogram::uniform(0, 0.0, 1.0).unwrap_err();
+
+    // Ranges not increasing
+    Histogram::with_ranges(&[0.0, 2.0, 1.0]).unwrap_err();
+    Histogram::with_ranges(&[0.0]).unwrap_err();
+}
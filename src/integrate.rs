@@ -0,0 +1,1658 @@
+/*
+    integrate.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::error::{restore_error_handler, take_error_handler};
+use crate::memory::Footprint;
+use crate::*;
+use std::fmt;
+use std::ops::ControlFlow;
+
+/// Adaptive integration over `[a, b]` with the tolerances and subintervals of the `NumericPolicy`,
+/// by default `epsabs = 1e-9`, `epsrel = 0` and 16 subintervals
+pub fn qag<F: FnMut(f64) -> f64>(a: f64, b: f64, f: F) -> Result<ValWithError<f64>> {
+    qag_with_policy(policy::policy(), a, b, f)
+}
+
+/// `qag` with `policy` instead of the policy of this thread
+pub fn qag_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    a: f64,
+    b: f64,
+    f: F,
+) -> Result<ValWithError<f64>> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-9, 0.0);
+        qag_ext(
+            policy.iterations(16),
+            a,
+            b,
+            epsabs,
+            epsrel,
+            GaussKronrodRule::Gauss15,
+            f,
+        )
+    })
+}
+
+/// Allocates a workspace for every call, see `IntegrationWorkspace` to reuse one in a loop
+pub fn qag_ext<F: FnMut(f64) -> f64>(
+    workspace_size: usize,
+    a: f64,
+    b: f64,
+    epsabs: f64,
+    epsrel: f64,
+    rule: GaussKronrodRule,
+    f: F,
+) -> Result<ValWithError<f64>> {
+    IntegrationWorkspace::new(workspace_size)?.qag(a, b, epsabs, epsrel, rule, f)
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GaussKronrodRule {
+    Gauss15 = GSL_INTEG_GAUSS15 as u32,
+    Gauss21 = GSL_INTEG_GAUSS21 as u32,
+    Gauss31 = GSL_INTEG_GAUSS31 as u32,
+    Gauss41 = GSL_INTEG_GAUSS41 as u32,
+    Gauss51 = GSL_INTEG_GAUSS51 as u32,
+    Gauss61 = GSL_INTEG_GAUSS61 as u32,
+}
+
+/// Integration over `[a, ∞)` with the tolerances and subintervals of the `NumericPolicy`,
+/// by default `epsabs = 1e-9`, `epsrel = 0` and 32 subintervals
+pub fn qagiu<F: FnMut(f64) -> f64>(a: f64, f: F) -> Result<ValWithError<f64>> {
+    qagiu_with_policy(policy::policy(), a, f)
+}
+
+/// `qagiu` with `policy` instead of the policy of this thread
+pub fn qagiu_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    a: f64,
+    f: F,
+) -> Result<ValWithError<f64>> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-9, 0.0);
+        qagiu_ext(policy.iterations(32), a, epsabs, epsrel, f)
+    })
+}
+
+/// Allocates a workspace for every call, see `IntegrationWorkspace` to reuse one in a loop
+pub fn qagiu_ext<F: FnMut(f64) -> f64>(
+    workspace_size: usize,
+    a: f64,
+    epsabs: f64,
+    epsrel: f64,
+    f: F,
+) -> Result<ValWithError<f64>> {
+    IntegrationWorkspace::new(workspace_size)?.qagiu(a, epsabs, epsrel, f)
+}
+
+/// Non-adaptive Gauss-Kronrod integration, which needs no workspace.
+///
+/// Returns the result and the amount of function evaluations.
+pub fn qng<F: FnMut(f64) -> f64>(
+    a: f64,
+    b: f64,
+    epsabs: f64,
+    epsrel: f64,
+    mut f: F,
+) -> Result<(ValWithError<f64>, usize)> {
+    unsafe {
+        let gsl_f = function(&mut f);
+
+        let mut result = 0.0f64;
+        let mut abserr = 0.0f64;
+        let mut neval = 0;
+
+        policy::converged(GSLError::from_raw(gsl_integration_qng(
+            &gsl_f,
+            a,
+            b,
+            epsabs,
+            epsrel,
+            &mut result,
+            &mut abserr,
+            &mut neval,
+        )))?;
+
+        Ok((
+            ValWithError {
+                val: result,
+                err: abserr,
+            },
+            neval as usize,
+        ))
+    }
+}
+
+/// Workspace for the adaptive integrators, holding up to `size` subintervals.
+///
+/// Reusing a workspace avoids an allocation per integral.
+pub struct IntegrationWorkspace {
+    size: usize,
+    workspace: *mut gsl_integration_workspace,
+    footprint: Footprint,
+}
+
+impl IntegrationWorkspace {
+    pub fn new(size: usize) -> Result<Self> {
+        unsafe {
+            if size == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let workspace = gsl_integration_workspace_alloc(size as u64);
+            assert!(!workspace.is_null());
+
+            // Four arrays of doubles and two of indices
+            let footprint = Footprint::of::<gsl_integration_workspace>(6 * size);
+
+            Ok(IntegrationWorkspace {
+                size,
+                workspace,
+                footprint,
+            })
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Approximate memory held by the GSL workspace, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    /// Adaptive integration over `[a, b]`.
+    pub fn qag<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        rule: GaussKronrodRule,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            policy::converged(GSLError::from_raw(gsl_integration_qag(
+                &gsl_f,
+                a,
+                b,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                rule as _,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Adaptive integration over `[a, b]` with extrapolation, for integrable singularities.
+    pub fn qags<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            policy::converged(GSLError::from_raw(gsl_integration_qags(
+                &gsl_f,
+                a,
+                b,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Like `qags`, for singularities at known points.
+    ///
+    /// `points` must be sorted and include the integration limits as its first and last element.
+    pub fn qagp<F: FnMut(f64) -> f64>(
+        &mut self,
+        points: &[f64],
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            if points.len() < 2 {
+                return Err(GSLError::Invalid);
+            }
+
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: the points aren't actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qagp(
+                &gsl_f,
+                points.as_ptr() as *mut _,
+                points.len() as u64,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Integration over `(-inf, inf)`.
+    pub fn qagi<F: FnMut(f64) -> f64>(
+        &mut self,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: gsl_f is not actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qagi(
+                &gsl_f as *const _ as *mut _,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Integration over `[a, inf)`.
+    pub fn qagiu<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: gsl_f is not actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qagiu(
+                &gsl_f as *const _ as *mut _,
+                a,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Integration over `(-inf, b]`.
+    pub fn qagil<F: FnMut(f64) -> f64>(
+        &mut self,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: gsl_f is not actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qagil(
+                &gsl_f as *const _ as *mut _,
+                b,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Cauchy principal value of `∫ f(x) / (x - c)` over `[a, b]`.
+    pub fn qawc<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        b: f64,
+        c: f64,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: gsl_f is not actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qawc(
+                &gsl_f as *const _ as *mut _,
+                a,
+                b,
+                c,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Integration over `[a, b]` of `f` times the algebraic-logarithmic weight described by `table`.
+    pub fn qaws<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        b: f64,
+        table: &mut QawsTable,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: gsl_f is not actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qaws(
+                &gsl_f as *const _ as *mut _,
+                a,
+                b,
+                table.table,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Integration over `[a, a + L]` of `f` times the oscillatory weight described by `table`.
+    pub fn qawo<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        table: &mut QawoTable,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: gsl_f is not actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qawo(
+                &gsl_f as *const _ as *mut _,
+                a,
+                epsabs,
+                epsrel,
+                self.size as u64,
+                self.workspace,
+                table.table,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Fourier integral over `[a, inf)` of `f` times the oscillatory weight described by `table`.
+    ///
+    /// The length stored in `table` is ignored.
+    /// `cycle_workspace` holds the integrals over the individual periods.
+    pub fn qawf<F: FnMut(f64) -> f64>(
+        &mut self,
+        cycle_workspace: &mut IntegrationWorkspace,
+        a: f64,
+        table: &mut QawoTable,
+        epsabs: f64,
+        mut f: F,
+    ) -> Result<ValWithError<f64>> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+
+            // Mutability: gsl_f is not actually modified, the header definition is poor.
+            policy::converged(GSLError::from_raw(gsl_integration_qawf(
+                &gsl_f as *const _ as *mut _,
+                a,
+                epsabs,
+                self.size.min(cycle_workspace.size) as u64,
+                self.workspace,
+                cycle_workspace.workspace,
+                table.table,
+                &mut result,
+                &mut abserr,
+            )))?;
+
+            Ok(ValWithError {
+                val: result,
+                err: abserr,
+            })
+        }
+    }
+
+    /// Runs the adaptive integrator `integrate` of this workspace on `f`, e.g.
+    /// `|ws, f| ws.qags(0.0, 1.0, 0.0, 1.0e-12, f)`, reporting the state of the integration
+    /// to `progress` every `every` subdivisions.
+    ///
+    /// Returning `ControlFlow::Break` from `progress` stops the integration,
+    /// which then returns the estimate and error at that point as the result.
+    pub fn with_progress<F, P, I>(
+        &mut self,
+        every: usize,
+        mut f: F,
+        mut progress: P,
+        integrate: I,
+    ) -> Result<ValWithError<f64>>
+    where
+        F: FnMut(f64) -> f64,
+        P: FnMut(IntegrationProgress) -> ControlFlow<()>,
+        I: FnOnce(&mut Self, &mut dyn FnMut(f64) -> f64) -> Result<ValWithError<f64>>,
+    {
+        if every == 0 {
+            return Err(GSLError::Invalid);
+        }
+
+        // GSL updates the subintervals in between the evaluations of f
+        let workspace = self.workspace;
+        let mut reported = 0;
+        let mut stopped = None;
+        let mut handler = None;
+        let mut g = |x| {
+            if stopped.is_some() {
+                return f64::NAN;
+            }
+            let state = unsafe { IntegrationProgress::of(workspace) };
+            if state.intervals >= reported + every {
+                reported = state.intervals;
+                if progress(state).is_break() {
+                    // The NaN makes GSL give up, which isn't an error to report
+                    stopped = Some(state);
+                    handler = Some(take_error_handler());
+                    return f64::NAN;
+                }
+            }
+            f(x)
+        };
+        let result = integrate(self, &mut g);
+
+        if let Some(handler) = handler {
+            restore_error_handler(handler);
+        }
+        match stopped {
+            Some(state) => Ok(ValWithError {
+                val: state.estimate,
+                err: state.error,
+            }),
+            None => result,
+        }
+    }
+}
+
+/// State of an adaptive integration, see `IntegrationWorkspace::with_progress`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IntegrationProgress {
+    /// Amount of subintervals so far
+    pub intervals: usize,
+    /// Sum of the integrals over the subintervals
+    pub estimate: f64,
+    /// Sum of the error estimates over the subintervals
+    pub error: f64,
+}
+
+impl IntegrationProgress {
+    unsafe fn of(workspace: *const gsl_integration_workspace) -> Self {
+        let intervals = (*workspace).size as usize;
+        let sum = |list: *const f64| (0..intervals).map(|i| *list.add(i)).sum();
+        IntegrationProgress {
+            intervals,
+            estimate: sum((*workspace).rlist),
+            error: sum((*workspace).elist),
+        }
+    }
+}
+
+impl fmt::Debug for IntegrationWorkspace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntegrationWorkspace")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for IntegrationWorkspace {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_integration_workspace_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for IntegrationWorkspace {}
+unsafe impl Sync for IntegrationWorkspace {}
+
+/// Weight `(x - a)^alpha (b - x)^beta log^mu(x - a) log^nu(b - x)` for `qaws`
+pub struct QawsTable {
+    table: *mut gsl_integration_qaws_table,
+    footprint: Footprint,
+}
+
+impl QawsTable {
+    /// Requires `alpha, beta > -1`. `mu` and `nu` toggle the logarithmic factors.
+    pub fn new(alpha: f64, beta: f64, mu: bool, nu: bool) -> Result<Self> {
+        unsafe {
+            if alpha <= -1.0 || beta <= -1.0 {
+                return Err(GSLError::Domain);
+            }
+
+            let table = gsl_integration_qaws_table_alloc(alpha, beta, mu as _, nu as _);
+            assert!(!table.is_null());
+
+            let footprint = Footprint::of::<gsl_integration_qaws_table>(0);
+
+            Ok(QawsTable { table, footprint })
+        }
+    }
+
+    /// Approximate memory held by the GSL table, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+}
+
+impl fmt::Debug for QawsTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QawsTable").finish_non_exhaustive()
+    }
+}
+
+impl Drop for QawsTable {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_integration_qaws_table_free(self.table);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for QawsTable {}
+unsafe impl Sync for QawsTable {}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Oscillation {
+    Cosine = gsl_integration_qawo_enum_GSL_INTEG_COSINE as u32,
+    Sine = gsl_integration_qawo_enum_GSL_INTEG_SINE as u32,
+}
+
+/// Weight `sin(omega x)` or `cos(omega x)` over a length `L` for `qawo` and `qawf`,
+/// with `n` levels of precomputed Chebyshev moments.
+pub struct QawoTable {
+    table: *mut gsl_integration_qawo_table,
+    footprint: Footprint,
+}
+
+impl QawoTable {
+    pub fn new(omega: f64, length: f64, oscillation: Oscillation, n: usize) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let table = gsl_integration_qawo_table_alloc(omega, length, oscillation as _, n as u64);
+            assert!(!table.is_null());
+
+            // 25 Chebyshev moments per level
+            let footprint = Footprint::of::<gsl_integration_qawo_table>(25 * n);
+
+            Ok(QawoTable { table, footprint })
+        }
+    }
+
+    /// Approximate memory held by the GSL table, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    /// Changes the weight without reallocating the table.
+    pub fn set(&mut self, omega: f64, length: f64, oscillation: Oscillation) -> Result<()> {
+        unsafe {
+            GSLError::from_raw(gsl_integration_qawo_table_set(
+                self.table,
+                omega,
+                length,
+                oscillation as _,
+            ))
+        }
+    }
+}
+
+impl fmt::Debug for QawoTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QawoTable").finish_non_exhaustive()
+    }
+}
+
+impl Drop for QawoTable {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_integration_qawo_table_free(self.table);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for QawoTable {}
+unsafe impl Sync for QawoTable {}
+
+/// CQUAD integration over `[a, b]` with the tolerances and subintervals of the `NumericPolicy`,
+/// by default `epsabs = 1e-9`, `epsrel = 0` and 100 subintervals
+pub fn cquad<F: FnMut(f64) -> f64>(a: f64, b: f64, f: F) -> Result<(ValWithError<f64>, usize)> {
+    cquad_with_policy(policy::policy(), a, b, f)
+}
+
+/// `cquad` with `policy` instead of the policy of this thread
+pub fn cquad_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    a: f64,
+    b: f64,
+    f: F,
+) -> Result<(ValWithError<f64>, usize)> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-9, 0.0);
+        CquadWorkspace::new(policy.iterations(100))?.integrate(a, b, epsabs, epsrel, f)
+    })
+}
+
+/// Workspace for the doubly-adaptive CQUAD integrator, holding up to `size` intervals.
+pub struct CquadWorkspace {
+    size: usize,
+    workspace: *mut gsl_integration_cquad_workspace,
+    footprint: Footprint,
+}
+
+impl CquadWorkspace {
+    pub fn new(size: usize) -> Result<Self> {
+        unsafe {
+            // GSL requires at least 3 intervals
+            if size < 3 {
+                return Err(GSLError::Invalid);
+            }
+
+            let workspace = gsl_integration_cquad_workspace_alloc(size as u64);
+            assert!(!workspace.is_null());
+
+            // The intervals and a heap of their indices
+            let footprint = Footprint::new(
+                std::mem::size_of::<gsl_integration_cquad_workspace>()
+                    + size
+                        * (std::mem::size_of::<gsl_integration_cquad_ival>()
+                            + std::mem::size_of::<u64>()),
+            );
+
+            Ok(CquadWorkspace {
+                size,
+                workspace,
+                footprint,
+            })
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Approximate memory held by the GSL workspace, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    /// Returns the result and the amount of function evaluations.
+    ///
+    /// Unlike the QUADPACK routines, CQUAD copes with non-finite function values.
+    pub fn integrate<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<(ValWithError<f64>, usize)> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut abserr = 0.0f64;
+            let mut neval = 0;
+
+            policy::converged(GSLError::from_raw(gsl_integration_cquad(
+                &gsl_f,
+                a,
+                b,
+                epsabs,
+                epsrel,
+                self.workspace,
+                &mut result,
+                &mut abserr,
+                &mut neval,
+            )))?;
+
+            Ok((
+                ValWithError {
+                    val: result,
+                    err: abserr,
+                },
+                neval as usize,
+            ))
+        }
+    }
+}
+
+impl fmt::Debug for CquadWorkspace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CquadWorkspace")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for CquadWorkspace {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_integration_cquad_workspace_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for CquadWorkspace {}
+unsafe impl Sync for CquadWorkspace {}
+
+/// Romberg integration over `[a, b]` with the tolerances and iterations of the `NumericPolicy`,
+/// by default `epsabs = 1e-9`, `epsrel = 0` and 20 iterations
+pub fn romberg<F: FnMut(f64) -> f64>(a: f64, b: f64, f: F) -> Result<(ValWithError<f64>, usize)> {
+    romberg_with_policy(policy::policy(), a, b, f)
+}
+
+/// `romberg` with `policy` instead of the policy of this thread
+pub fn romberg_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    a: f64,
+    b: f64,
+    f: F,
+) -> Result<(ValWithError<f64>, usize)> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-9, 0.0);
+        RombergWorkspace::new(policy.iterations(20))?.integrate(a, b, epsabs, epsrel, f)
+    })
+}
+
+/// Workspace for Romberg integration, allowing up to `2^(n - 1) + 1` function evaluations.
+pub struct RombergWorkspace {
+    n: usize,
+    workspace: *mut gsl_integration_romberg_workspace,
+    footprint: Footprint,
+}
+
+impl RombergWorkspace {
+    /// `n` must lie in `1..=30`
+    pub fn new(n: usize) -> Result<Self> {
+        unsafe {
+            if !(1..=30).contains(&n) {
+                return Err(GSLError::Invalid);
+            }
+
+            let workspace = gsl_integration_romberg_alloc(n as u64);
+            assert!(!workspace.is_null());
+
+            let footprint = Footprint::of::<gsl_integration_romberg_workspace>(2 * n);
+
+            Ok(RombergWorkspace {
+                n,
+                workspace,
+                footprint,
+            })
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Approximate memory held by the GSL workspace, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    /// Returns the result and the amount of function evaluations.
+    ///
+    /// GSL does not report an error estimate for Romberg integration.
+    /// On success the difference between the last two extrapolations is below
+    /// `max(epsabs, epsrel |result|)`, which is reported as the error.
+    pub fn integrate<F: FnMut(f64) -> f64>(
+        &mut self,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        mut f: F,
+    ) -> Result<(ValWithError<f64>, usize)> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            let mut neval = 0;
+
+            policy::converged(GSLError::from_raw(gsl_integration_romberg(
+                &gsl_f,
+                a,
+                b,
+                epsabs,
+                epsrel,
+                &mut result,
+                &mut neval,
+                self.workspace,
+            )))?;
+
+            Ok((
+                ValWithError {
+                    val: result,
+                    err: epsabs.max(epsrel * result.abs()),
+                },
+                neval as usize,
+            ))
+        }
+    }
+}
+
+impl fmt::Debug for RombergWorkspace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RombergWorkspace")
+            .field("n", &self.n)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for RombergWorkspace {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_integration_romberg_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for RombergWorkspace {}
+unsafe impl Sync for RombergWorkspace {}
+
+/// Weight function and interval of a fixed point quadrature rule
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FixedRule {
+    /// `1` on `(a, b)`
+    Legendre,
+    /// `1 / sqrt((b - x) (x - a))` on `(a, b)`
+    Chebyshev,
+    /// `((b - x) (x - a))^alpha` on `(a, b)`
+    Gegenbauer,
+    /// `(b - x)^alpha (x - a)^beta` on `(a, b)`
+    Jacobi,
+    /// `(x - a)^alpha exp(-b (x - a))` on `(a, inf)`
+    Laguerre,
+    /// `|x - a|^alpha exp(-b (x - a)^2)` on `(-inf, inf)`
+    Hermite,
+    /// `|x - (a + b) / 2|^alpha` on `(a, b)`
+    Exponential,
+    /// `(x - a)^alpha (x + b)^beta` on `(a, inf)`
+    Rational,
+    /// `sqrt((b - x) (x - a))` on `(a, b)`
+    Chebyshev2,
+}
+
+/// Precomputed `n` point interpolatory quadrature rule
+pub struct FixedQuadrature {
+    rule: FixedRule,
+    workspace: *mut gsl_integration_fixed_workspace,
+    footprint: Footprint,
+}
+
+impl FixedQuadrature {
+    /// See `FixedRule` for the meaning of `a`, `b`, `alpha` and `beta`.
+    pub fn new(rule: FixedRule, n: usize, a: f64, b: f64, alpha: f64, beta: f64) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let gsl_rule = match rule {
+                FixedRule::Legendre => gsl_integration_fixed_legendre,
+                FixedRule::Chebyshev => gsl_integration_fixed_chebyshev,
+                FixedRule::Gegenbauer => gsl_integration_fixed_gegenbauer,
+                FixedRule::Jacobi => gsl_integration_fixed_jacobi,
+                FixedRule::Laguerre => gsl_integration_fixed_laguerre,
+                FixedRule::Hermite => gsl_integration_fixed_hermite,
+                FixedRule::Exponential => gsl_integration_fixed_exponential,
+                FixedRule::Rational => gsl_integration_fixed_rational,
+                FixedRule::Chebyshev2 => gsl_integration_fixed_chebyshev2,
+            };
+
+            // GSL returns a null pointer for invalid parameters
+            let workspace = gsl_integration_fixed_alloc(gsl_rule, n as u64, a, b, alpha, beta);
+            if workspace.is_null() {
+                return Err(GSLError::Domain);
+            }
+
+            // Nodes, weights and the Jacobi matrix they are computed from
+            let footprint = Footprint::of::<gsl_integration_fixed_workspace>(4 * n);
+
+            Ok(FixedQuadrature {
+                rule,
+                workspace,
+                footprint,
+            })
+        }
+    }
+
+    pub fn rule(&self) -> FixedRule {
+        self.rule
+    }
+
+    /// Approximate memory held by the GSL workspace, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    pub fn n(&self) -> usize {
+        unsafe { gsl_integration_fixed_n(self.workspace) as usize }
+    }
+
+    pub fn nodes(&self) -> &[f64] {
+        unsafe { std::slice::from_raw_parts(gsl_integration_fixed_nodes(self.workspace), self.n()) }
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        unsafe {
+            std::slice::from_raw_parts(gsl_integration_fixed_weights(self.workspace), self.n())
+        }
+    }
+
+    /// Approximates `∫ w(x) f(x)`, where `w` is the weight function of the rule.
+    pub fn integrate<F: FnMut(f64) -> f64>(&self, mut f: F) -> Result<f64> {
+        unsafe {
+            let gsl_f = function(&mut f);
+
+            let mut result = 0.0f64;
+            GSLError::from_raw(gsl_integration_fixed(&gsl_f, &mut result, self.workspace))?;
+
+            Ok(result)
+        }
+    }
+}
+
+impl fmt::Debug for FixedQuadrature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixedQuadrature")
+            .field("rule", &self.rule)
+            .field("nodes", &self.nodes())
+            .field("weights", &self.weights())
+            .finish()
+    }
+}
+
+impl Drop for FixedQuadrature {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_integration_fixed_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for FixedQuadrature {}
+unsafe impl Sync for FixedQuadrature {}
+
+/// `n` point Gauss-Legendre rule, which can be rescaled to any interval
+pub struct GLFixedTable {
+    n: usize,
+    table: *mut gsl_integration_glfixed_table,
+    footprint: Footprint,
+}
+
+impl GLFixedTable {
+    pub fn new(n: usize) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let table = gsl_integration_glfixed_table_alloc(n as u64);
+            assert!(!table.is_null());
+
+            // Half of the symmetric nodes and weights, unless GSL has the rule precomputed
+            let footprint = Footprint::of::<gsl_integration_glfixed_table>(n + 1);
+
+            Ok(GLFixedTable {
+                n,
+                table,
+                footprint,
+            })
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Approximate memory held by the GSL table, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.footprint.bytes()
+    }
+
+    /// Approximates `∫ f` over `[a, b]`.
+    pub fn integrate<F: FnMut(f64) -> f64>(&self, a: f64, b: f64, mut f: F) -> f64 {
+        unsafe {
+            let gsl_f = function(&mut f);
+            gsl_integration_glfixed(&gsl_f, a, b, self.table)
+        }
+    }
+
+    /// Node `x_i` and weight `w_i`, rescaled to `[a, b]`, such that `∫ f ≈ Σ w_i f(x_i)`.
+    pub fn point(&self, a: f64, b: f64, i: usize) -> Result<(f64, f64)> {
+        unsafe {
+            if i >= self.n {
+                return Err(GSLError::Invalid);
+            }
+
+            let mut xi = 0.0;
+            let mut wi = 0.0;
+            GSLError::from_raw(gsl_integration_glfixed_point(
+                a, b, i as u64, &mut xi, &mut wi, self.table,
+            ))?;
+
+            Ok((xi, wi))
+        }
+    }
+
+    /// All nodes and weights rescaled to `[a, b]`, see `point`.
+    pub fn points(&self, a: f64, b: f64) -> Result<(Vec<f64>, Vec<f64>)> {
+        let points = (0..self.n)
+            .map(|i| self.point(a, b, i))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(points.into_iter().unzip())
+    }
+}
+
+impl fmt::Debug for GLFixedTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GLFixedTable")
+            .field("n", &self.n)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for GLFixedTable {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_integration_glfixed_table_free(self.table);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for GLFixedTable {}
+unsafe impl Sync for GLFixedTable {}
+
+/// The returned struct borrows `f` through a raw pointer and must not outlive it.
+fn function<F: FnMut(f64) -> f64>(f: &mut F) -> gsl_function_struct {
+    gsl_function_struct {
+        function: Some(trampoline::<F>),
+        params: f as *mut _ as *mut _,
+    }
+}
+
+#[test]
+fn test_qag65() {
+    disable_error_handler();
+
+    approx::assert_abs_diff_eq!(
+        qag_ext(4, 0.0, 1.0, 1.0e-6, 0.0, GaussKronrodRule::Gauss61, |x| x
+            .powi(3)
+            + x)
+        .unwrap()
+        .val,
+        0.75,
+        epsilon = 1.0e-6
+    );
+}
+
+#[test]
+fn test_qagiu() {
+    disable_error_handler();
+
+    approx::assert_abs_diff_eq!(
+        (qagiu(0.0, |x| { (-x.powi(2)).exp() }).unwrap().val * 2.0).powi(2),
+        std::f64::consts::PI,
+        epsilon = 1.0e-6
+    );
+}
+
+#[test]
+fn test_gsl_suite() {
+    use crate::testing::*;
+
+    disable_error_handler();
+
+    // Ported from integration/test.c, with the exact integrals as reference values.
+    // Besides the tolerance, the error estimates have to bound the actual errors.
+    let check = |result: ValWithError<f64>, exact: f64, tol: f64| {
+        assert_rel(result.val, exact, tol);
+        assert!((result.val - exact).abs() <= result.err);
+    };
+    let mut workspace = IntegrationWorkspace::new(1000).unwrap();
+
+    // f1 = x^α log(1/x) with α = 2.6, integrating to 1 / (α + 1)^2
+    let f1 = |x: f64| x.powf(2.6) * (1.0 / x).ln();
+    let exact = 1.0 / 3.6f64.powi(2);
+    check(qng(0.0, 1.0, 0.0, 1.0e-9, f1).unwrap().0, exact, 1.0e-9);
+    check(
+        workspace.qags(0.0, 1.0, 0.0, 1.0e-10, f1).unwrap(),
+        exact,
+        1.0e-10,
+    );
+
+    // f3 = cos(2^α sin x) with α = 1.3
+    let f3 = |x: f64| (2.0f64.powf(1.3) * x.sin()).cos();
+    let exact = -0.7238969575482962;
+    for rule in [GaussKronrodRule::Gauss15, GaussKronrodRule::Gauss61] {
+        check(
+            workspace.qag(0.3, 2.71, 1.0e-14, 0.0, rule, f3).unwrap(),
+            exact,
+            1.0e-13,
+        );
+    }
+
+    // f454 = x^3 log|(x^2 - 1)(x^2 - 2)|, with singular points at 1 and √2
+    let f454 = |x: f64| x.powi(3) * ((x * x - 1.0) * (x * x - 2.0)).abs().ln();
+    let points = [0.0, 1.0, 2.0f64.sqrt(), 3.0];
+    check(
+        workspace.qagp(&points, 0.0, 1.0e-3, f454).unwrap(),
+        52.74074838347145,
+        1.0e-3,
+    );
+
+    // f455 = log(x) / (1 + 100 x^2) over [0, ∞), integrating to -π log(10) / 20
+    let f455 = |x: f64| x.ln() / (1.0 + 100.0 * x * x);
+    check(
+        workspace.qagiu(0.0, 0.0, 1.0e-3, f455).unwrap(),
+        -0.36168922062077324,
+        1.0e-3,
+    );
+
+    // exp(-x - x^2) over (-∞, ∞), integrating to √π e^(1/4)
+    check(
+        workspace.qagi(1.0e-7, 0.0, |x| (-x - x * x).exp()).unwrap(),
+        2.2758757944687473,
+        1.0e-7,
+    );
+
+    // f459 = 1 / (5 x^3 + 6) with the Cauchy weight 1 / x over [-1, 5]
+    check(
+        workspace
+            .qawc(-1.0, 5.0, 0.0, 0.0, 1.0e-3, |x| {
+                1.0 / (5.0 * x.powi(3) + 6.0)
+            })
+            .unwrap(),
+        -0.08994400695771733,
+        1.0e-3,
+    );
+
+    // f458 = 1 / (1 + log(x)^2)^2 with the weight log(x) over [0, 1]
+    let mut table = QawsTable::new(0.0, 0.0, true, false).unwrap();
+    check(
+        workspace
+            .qaws(0.0, 1.0, &mut table, 0.0, 1.0e-7, |x| {
+                1.0 / (1.0 + x.ln().powi(2)).powi(2)
+            })
+            .unwrap(),
+        -0.18927518788209333,
+        1.0e-7,
+    );
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Empty workspace
+    qag_ext(0, 0.0, 1.0, 1.0e-6, 0.0, GaussKronrodRule::Gauss61, |x| {
+        x.powi(3) + x
+    })
+    .unwrap_err();
+
+    // Invalid weight exponents
+    QawsTable::new(-1.0, 0.0, false, false).unwrap_err();
+
+    // Too small workspaces
+    CquadWorkspace::new(2).unwrap_err();
+    RombergWorkspace::new(0).unwrap_err();
+    RombergWorkspace::new(31).unwrap_err();
+
+    // Zero points, and a Laguerre rule with nonpositive scale
+    FixedQuadrature::new(FixedRule::Legendre, 0, 0.0, 1.0, 0.0, 0.0).unwrap_err();
+    FixedQuadrature::new(FixedRule::Laguerre, 8, 0.0, 0.0, 0.0, 0.0).unwrap_err();
+
+    // Node index out of range
+    GLFixedTable::new(4)
+        .unwrap()
+        .point(0.0, 1.0, 4)
+        .unwrap_err();
+
+    // No singular points
+    IntegrationWorkspace::new(16)
+        .unwrap()
+        .qagp(&[0.0], 1.0e-6, 0.0, |x| x)
+        .unwrap_err();
+
+    // No progress interval
+    IntegrationWorkspace::new(16)
+        .unwrap()
+        .with_progress(
+            0,
+            |x| x,
+            |_| std::ops::ControlFlow::Continue(()),
+            |ws, f| ws.qags(0.0, 1.0, 0.0, 1.0e-6, f),
+        )
+        .unwrap_err();
+}
+
+#[test]
+fn test_qng() {
+    disable_error_handler();
+
+    let (result, neval) = qng(0.0, 1.0, 1.0e-9, 0.0, |x| x.exp()).unwrap();
+    approx::assert_abs_diff_eq!(result.val, 1.0f64.exp() - 1.0, epsilon = 1.0e-9);
+    assert!(neval > 0);
+}
+
+#[test]
+fn test_workspace_reuse() {
+    disable_error_handler();
+
+    let mut workspace = IntegrationWorkspace::new(64).unwrap();
+
+    // Singular integrand ∫ log(x) / sqrt(x) = -4
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qags(0.0, 1.0, 0.0, 1.0e-9, |x| x.ln() / x.sqrt())
+            .unwrap()
+            .val,
+        -4.0,
+        epsilon = 1.0e-6
+    );
+
+    // Known singularity at x = 1: ∫_0^2 |x - 1|^(-1/2) = 4
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qagp(&[0.0, 1.0, 2.0], 0.0, 1.0e-9, |x| (x - 1.0f64)
+                .abs()
+                .sqrt()
+                .recip())
+            .unwrap()
+            .val,
+        4.0,
+        epsilon = 1.0e-6
+    );
+
+    // Gaussian integrals over infinite ranges
+    let sqrt_pi = std::f64::consts::PI.sqrt();
+    approx::assert_abs_diff_eq!(
+        workspace.qagi(0.0, 1.0e-9, |x| (-x * x).exp()).unwrap().val,
+        sqrt_pi,
+        epsilon = 1.0e-6
+    );
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qagil(0.0, 0.0, 1.0e-9, |x| (-x * x).exp())
+            .unwrap()
+            .val,
+        sqrt_pi / 2.0,
+        epsilon = 1.0e-6
+    );
+}
+
+#[test]
+fn test_qawc() {
+    disable_error_handler();
+
+    // PV ∫_0^2 1 / (x - 1) = 0
+    let mut workspace = IntegrationWorkspace::new(64).unwrap();
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qawc(0.0, 2.0, 1.0, 1.0e-9, 0.0, |_| 1.0)
+            .unwrap()
+            .val,
+        0.0,
+        epsilon = 1.0e-6
+    );
+
+    // PV ∫_0^1 x / (x - 1/2) = 1 + log(1) / 2 = 1
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qawc(0.0, 1.0, 0.5, 1.0e-9, 0.0, |x| x)
+            .unwrap()
+            .val,
+        1.0,
+        epsilon = 1.0e-6
+    );
+}
+
+#[test]
+fn test_qaws() {
+    disable_error_handler();
+
+    // ∫_0^1 x^(-1/2) = 2
+    let mut workspace = IntegrationWorkspace::new(64).unwrap();
+    let mut table = QawsTable::new(-0.5, 0.0, false, false).unwrap();
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qaws(0.0, 1.0, &mut table, 1.0e-9, 0.0, |_| 1.0)
+            .unwrap()
+            .val,
+        2.0,
+        epsilon = 1.0e-6
+    );
+}
+
+#[test]
+fn test_qawo_qawf() {
+    disable_error_handler();
+
+    let mut workspace = IntegrationWorkspace::new(256).unwrap();
+
+    // ∫_0^π sin(x) = 2
+    let mut table = QawoTable::new(1.0, std::f64::consts::PI, Oscillation::Sine, 16).unwrap();
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qawo(0.0, &mut table, 1.0e-9, 0.0, |_| 1.0)
+            .unwrap()
+            .val,
+        2.0,
+        epsilon = 1.0e-6
+    );
+
+    // ∫_0^inf exp(-x) cos(x) = 1/2
+    let mut cycle_workspace = IntegrationWorkspace::new(256).unwrap();
+    table.set(1.0, 1.0, Oscillation::Cosine).unwrap();
+    approx::assert_abs_diff_eq!(
+        workspace
+            .qawf(&mut cycle_workspace, 0.0, &mut table, 1.0e-9, |x| (-x)
+                .exp())
+            .unwrap()
+            .val,
+        0.5,
+        epsilon = 1.0e-6
+    );
+}
+
+#[test]
+fn test_cquad() {
+    disable_error_handler();
+
+    let (result, neval) = cquad(0.0, 1.0, |x| x.sqrt()).unwrap();
+    approx::assert_abs_diff_eq!(result.val, 2.0 / 3.0, epsilon = 1.0e-9);
+    assert!(neval > 0);
+
+    // Infinite at the endpoint, which QUADPACK would choke on
+    let mut workspace = CquadWorkspace::new(200).unwrap();
+    let (result, _) = workspace
+        .integrate(0.0, 1.0, 0.0, 1.0e-6, |x| x.ln())
+        .unwrap();
+    approx::assert_abs_diff_eq!(result.val, -1.0, epsilon = 1.0e-6);
+}
+
+#[test]
+fn test_progress() {
+    use std::ops::ControlFlow::{Break, Continue};
+
+    disable_error_handler();
+
+    // ∫_0^1 x^(-1/2) = 2, which needs many subdivisions near 0
+    let mut workspace = IntegrationWorkspace::new(1000).unwrap();
+    let mut reports = Vec::new();
+    let result = workspace
+        .with_progress(
+            2,
+            |x| 1.0 / x.sqrt(),
+            |state| {
+                reports.push(state);
+                Continue(())
+            },
+            |ws, f| ws.qag(0.0, 1.0, 0.0, 1.0e-10, GaussKronrodRule::Gauss21, f),
+        )
+        .unwrap();
+    approx::assert_abs_diff_eq!(result.val, 2.0, epsilon = 1.0e-8);
+    assert!(reports.len() > 1);
+    assert!(reports
+        .windows(2)
+        .all(|w| w[1].intervals >= w[0].intervals + 2));
+
+    // Good enough, stop early
+    let mut intervals = 0;
+    let result = workspace
+        .with_progress(
+            1,
+            |x| 1.0 / x.sqrt(),
+            |state| {
+                intervals = state.intervals;
+                if state.error < 1.0e-3 {
+                    Break(())
+                } else {
+                    Continue(())
+                }
+            },
+            |ws, f| ws.qag(0.0, 1.0, 0.0, 1.0e-10, GaussKronrodRule::Gauss21, f),
+        )
+        .unwrap();
+    approx::assert_abs_diff_eq!(result.val, 2.0, epsilon = 1.0e-3);
+    assert!(result.err < 1.0e-3);
+    assert!(intervals < reports.last().unwrap().intervals);
+
+    // Stopping isn't a GSL error
+    set_error_handler(PanicOnError);
+    let result = workspace.with_progress(
+        1,
+        |x| 1.0 / x.sqrt(),
+        |_| Break(()),
+        |ws, f| ws.qags(0.0, 1.0, 0.0, 1.0e-10, f),
+    );
+    assert!(result.is_ok());
+    assert!(workspace.qags(0.0, 1.0, 0.0, 1.0e-10, |x| x).is_ok());
+    disable_error_handler();
+}
+
+#[test]
+fn test_progress_default_handler() {
+    // Stopping without ever setting a handler, which doesn't abort in the default handler of GSL
+    let result = std::thread::spawn(|| {
+        IntegrationWorkspace::new(100).unwrap().with_progress(
+            1,
+            |x| 1.0 / x.sqrt(),
+            |_| ControlFlow::Break(()),
+            |ws, f| ws.qags(0.0, 1.0, 0.0, 1.0e-10, f),
+        )
+    })
+    .join()
+    .unwrap();
+    assert!(result.unwrap().val.is_finite());
+}
+
+#[test]
+fn test_romberg() {
+    disable_error_handler();
+
+    let (result, neval) = romberg(0.0, std::f64::consts::PI, |x| x.sin()).unwrap();
+    approx::assert_abs_diff_eq!(result.val, 2.0, epsilon = 1.0e-8);
+    assert!(neval > 0);
+
+    // Too few levels to converge
+    RombergWorkspace::new(2)
+        .unwrap()
+        .integrate(0.0, 10.0, 1.0e-12, 0.0, |x| x.sin().exp())
+        .unwrap_err();
+}
+
+#[test]
+fn test_fixed_quadrature() {
+    disable_error_handler();
+
+    // An n point Gauss-Legendre rule is exact for polynomials up to degree 2n - 1
+    let legendre = FixedQuadrature::new(FixedRule::Legendre, 4, 0.0, 2.0, 0.0, 0.0).unwrap();
+    assert_eq!(legendre.n(), 4);
+    approx::assert_abs_diff_eq!(
+        legendre.weights().iter().sum::<f64>(),
+        2.0,
+        epsilon = 1.0e-12
+    );
+    approx::assert_abs_diff_eq!(
+        legendre.integrate(|x| x.powi(7)).unwrap(),
+        2.0f64.powi(8) / 8.0,
+        epsilon = 1.0e-9
+    );
+
+    // ∫ exp(-x^2) x^2 = sqrt(π) / 2
+    let hermite = FixedQuadrature::new(FixedRule::Hermite, 10, 0.0, 1.0, 0.0, 0.0).unwrap();
+    approx::assert_abs_diff_eq!(
+        hermite.integrate(|x| x * x).unwrap(),
+        std::f64::consts::PI.sqrt() / 2.0,
+        epsilon = 1.0e-12
+    );
+}
+
+#[test]
+fn test_glfixed() {
+    disable_error_handler();
+
+    let table = GLFixedTable::new(5).unwrap();
+    approx::assert_abs_diff_eq!(
+        table.integrate(-1.0, 3.0, |x| x.powi(9)),
+        (3.0f64.powi(10) - 1.0) / 10.0,
+        epsilon = 1.0e-6
+    );
+
+    // Manual evaluation using the nodes and weights
+    let (x, w) = table.points(-1.0, 3.0).unwrap();
+    assert_eq!(x.len(), 5);
+    let manual = x
+        .iter()
+        .zip(w.iter())
+        .map(|(x, w)| w * x.powi(9))
+        .sum::<f64>();
+    approx::assert_abs_diff_eq!(
+        manual,
+        table.integrate(-1.0, 3.0, |x| x.powi(9)),
+        epsilon = 1.0e-6
+    );
+}
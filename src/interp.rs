@@ -1,5 +1,5 @@
 /*
-    interpolation.rs
+    interp.rs
     Copyright (C) 2021 Pim van den Berg
 
     This program is free software: you can redistribute it and/or modify
@@ -17,6 +17,7 @@
 */
 
 use crate::bindings::*;
+use crate::memory::Footprint;
 use crate::*;
 use drop_guard::guard;
 
@@ -34,8 +35,8 @@ pub fn interpolate(
         return Err(GSLError::Invalid);
     }
 
-    sorting::sort_xy(&mut x, &mut y);
-    let (x, y) = sorting::dedup_x_mean(&x, &y)?;
+    sort::sort_xy(&mut x, &mut y);
+    let (x, y) = sort::dedup_x_mean(&x, &y)?;
 
     interpolate_monotonic(algorithm, derivative, &x, &y, x_eval)
 }
@@ -76,6 +77,8 @@ pub fn interpolate_monotonic(
             gsl_interp_accel_free(accel);
         });
         assert!(!accel.is_null());
+        let _footprint = Footprint::of::<gsl_interp>(n);
+        let _accel_footprint = Footprint::of::<gsl_interp_accel>(0);
 
         GSLError::from_raw(gsl_interp_init(
             *workspace,
@@ -122,12 +125,14 @@ pub fn interpolate_monotonic(
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Algorithm {
     Linear,
     Steffen,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Derivative {
     None,
     First,
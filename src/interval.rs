@@ -0,0 +1,248 @@
+/*
+    interval.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::integrate::{FixedQuadrature, FixedRule};
+use crate::randist::{NoncentralChiSquared, NoncentralT};
+use crate::sf::gamma_inc_q;
+use crate::*;
+use std::f64::consts::PI;
+
+/*
+
+    All intervals assume normally distributed data with unknown mean and variance,
+    estimated by the sample mean and the sample standard deviation.
+
+    A confidence interval contains the population mean,
+    a prediction interval contains a single future observation,
+    and a tolerance interval contains at least a fraction `coverage` of the population,
+    each with probability `confidence`.
+
+*/
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Interval {
+    pub fn contains(&self, x: f64) -> bool {
+        self.lower <= x && x <= self.upper
+    }
+
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+}
+
+/// Two-sided confidence interval for the population mean, `x̄ ± t s / sqrt(n)`
+pub fn mean_confidence_interval(x: &[f64], confidence: f64) -> Result<Interval> {
+    let (n, mean, sd) = sample(x)?;
+    check_probability(confidence)?;
+
+    let t = unsafe { gsl_cdf_tdist_Pinv((1.0 + confidence) / 2.0, n - 1.0) };
+    Ok(symmetric(mean, t * sd / n.sqrt()))
+}
+
+/// Two-sided prediction interval for a single future observation, `x̄ ± t s sqrt(1 + 1 / n)`
+pub fn prediction_interval(x: &[f64], confidence: f64) -> Result<Interval> {
+    let (n, mean, sd) = sample(x)?;
+    check_probability(confidence)?;
+
+    let t = unsafe { gsl_cdf_tdist_Pinv((1.0 + confidence) / 2.0, n - 1.0) };
+    Ok(symmetric(mean, t * sd * (1.0 + 1.0 / n).sqrt()))
+}
+
+/// Two-sided tolerance interval `x̄ ± k s`, see `tolerance_factor`
+pub fn tolerance_interval(x: &[f64], coverage: f64, confidence: f64) -> Result<Interval> {
+    let (n, mean, sd) = sample(x)?;
+    let k = tolerance_factor(n as usize, coverage, confidence)?;
+    Ok(symmetric(mean, k * sd))
+}
+
+/// One-sided tolerance limits `x̄ - k s` and `x̄ + k s`, see `one_sided_tolerance_factor`.
+/// Each limit on its own bounds a fraction `coverage` of the population from one side.
+pub fn one_sided_tolerance_limits(x: &[f64], coverage: f64, confidence: f64) -> Result<Interval> {
+    let (n, mean, sd) = sample(x)?;
+    let k = one_sided_tolerance_factor(n as usize, coverage, confidence)?;
+    Ok(symmetric(mean, k * sd))
+}
+
+/// Exact factor `k` of the two-sided tolerance interval `x̄ ± k s` for `n` observations.
+///
+/// For a standardized error `z` of the sample mean, the interval covers `coverage` if `k s / σ >= r`,
+/// where `r^2` is the `coverage` quantile of the noncentral chi-squared distribution
+/// with one degree of freedom and noncentrality `z^2 / n`.
+/// Averaging the chi-squared probability of this event over `z` gives the confidence,
+/// which is solved for `k`.
+pub fn tolerance_factor(n: usize, coverage: f64, confidence: f64) -> Result<f64> {
+    check_probability(coverage)?;
+    check_probability(confidence)?;
+    if n < 2 {
+        return Err(GSLError::Invalid);
+    }
+
+    // Gauss-Hermite quadrature over z with weight exp(-z^2 / 2)
+    let quadrature = FixedQuadrature::new(FixedRule::Hermite, 64, 0.0, 0.5, 0.0, 0.0)?;
+    let nf = n as f64;
+    let r2 = quadrature
+        .nodes()
+        .iter()
+        .map(|z| NoncentralChiSquared::new(1.0, z * z / nf)?.quantile(coverage))
+        .collect::<Result<Vec<_>>>()?;
+
+    let achieved = |k: f64| -> Result<f64> {
+        let mut sum = 0.0;
+        for (w, r2) in quadrature.weights().iter().zip(r2.iter()) {
+            sum += w * gamma_inc_q((nf - 1.0) / 2.0, (nf - 1.0) * r2 / (2.0 * k * k))?.val;
+        }
+        Ok(sum / (2.0 * PI).sqrt())
+    };
+
+    // The confidence increases with k
+    let mut lo = 0.0;
+    let mut hi = 1.0;
+    while achieved(hi)? < confidence {
+        lo = hi;
+        hi *= 2.0;
+        if !hi.is_finite() {
+            return Err(GSLError::Failed);
+        }
+    }
+    for _ in 0..200 {
+        let mid = 0.5 * (lo + hi);
+        if hi - lo <= 1.0e-12 * mid {
+            break;
+        }
+        if achieved(mid)? < confidence {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    Ok(0.5 * (lo + hi))
+}
+
+/// Exact factor `k` of the one-sided tolerance limit `x̄ + k s` (or `x̄ - k s`) for `n` observations,
+/// `k = t / sqrt(n)` with `t` the `confidence` quantile of the noncentral t distribution
+/// with `n - 1` degrees of freedom and noncentrality `z_coverage sqrt(n)`.
+pub fn one_sided_tolerance_factor(n: usize, coverage: f64, confidence: f64) -> Result<f64> {
+    check_probability(coverage)?;
+    check_probability(confidence)?;
+    if n < 2 {
+        return Err(GSLError::Invalid);
+    }
+
+    let nf = n as f64;
+    let z = unsafe { gsl_cdf_ugaussian_Pinv(coverage) };
+    let t = NoncentralT::new(nf - 1.0, z * nf.sqrt())?.quantile(confidence)?;
+    Ok(t / nf.sqrt())
+}
+
+/// Number of observations, mean and standard deviation
+fn sample(x: &[f64]) -> Result<(f64, f64, f64)> {
+    if x.len() < 2 {
+        return Err(GSLError::Invalid);
+    }
+    let mean = stats::mean(x);
+    let sd = stats::variance_mean(x, mean).sqrt();
+    Ok((x.len() as f64, mean, sd))
+}
+
+fn check_probability(p: f64) -> Result<()> {
+    if !(p > 0.0 && p < 1.0) {
+        return Err(GSLError::Domain);
+    }
+    Ok(())
+}
+
+fn symmetric(center: f64, half_width: f64) -> Interval {
+    Interval {
+        lower: center - half_width,
+        upper: center + half_width,
+    }
+}
+
+#[test]
+fn test_intervals() {
+    disable_error_handler();
+
+    let x = [9.8, 10.2, 10.1, 9.7, 10.4, 10.0, 9.9, 10.3, 9.6, 10.0];
+    let mean = stats::mean(&x);
+    let sd = stats::variance(&x).sqrt();
+
+    // t_{0.975, 9} = 2.262157
+    let ci = mean_confidence_interval(&x, 0.95).unwrap();
+    approx::assert_abs_diff_eq!(
+        ci.width() / 2.0,
+        2.262157 * sd / 10f64.sqrt(),
+        epsilon = 1.0e-6
+    );
+    assert!(ci.contains(mean));
+
+    let pi = prediction_interval(&x, 0.95).unwrap();
+    approx::assert_abs_diff_eq!(
+        pi.width() / 2.0,
+        2.262157 * sd * 1.1f64.sqrt(),
+        epsilon = 1.0e-6
+    );
+
+    let ti = tolerance_interval(&x, 0.9, 0.95).unwrap();
+    assert!(ti.width() > pi.width());
+}
+
+#[test]
+fn test_tolerance_factor() {
+    disable_error_handler();
+
+    // Tabulated values
+    approx::assert_abs_diff_eq!(
+        tolerance_factor(10, 0.9, 0.95).unwrap(),
+        2.839,
+        epsilon = 1.0e-3
+    );
+    approx::assert_abs_diff_eq!(
+        one_sided_tolerance_factor(10, 0.9, 0.95).unwrap(),
+        2.355,
+        epsilon = 1.0e-3
+    );
+
+    // Large samples approach the normal quantiles
+    let z = unsafe { gsl_cdf_ugaussian_Pinv(0.95) };
+    approx::assert_abs_diff_eq!(
+        tolerance_factor(100000, 0.9, 0.95).unwrap(),
+        z,
+        epsilon = 1.0e-2
+    );
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Too few observations
+    mean_confidence_interval(&[1.0], 0.95).unwrap_err();
+    tolerance_factor(1, 0.9, 0.95).unwrap_err();
+
+    // Probabilities out of range
+    prediction_interval(&[1.0, 2.0], 1.0).unwrap_err();
+    tolerance_factor(10, 1.5, 0.95).unwrap_err();
+    one_sided_tolerance_factor(10, 0.9, 0.0).unwrap_err();
+}
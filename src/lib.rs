@@ -19,26 +19,200 @@
 #![warn(clippy::all)]
 #![allow(clippy::too_many_arguments)]
 
+#[cfg(feature = "gsl")]
 use std::os::raw::*;
+#[cfg(feature = "gsl")]
 use std::panic::{catch_unwind, AssertUnwindSafe};
 
+#[cfg(feature = "gsl")]
+pub mod binary;
+pub mod blas;
+#[cfg(feature = "gsl")]
 pub mod bspline;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "gsl")]
+pub mod cheb;
+#[cfg(feature = "gsl")]
+pub mod consts;
+#[cfg(feature = "gsl")]
+pub mod cubature;
+#[cfg(feature = "gsl")]
+pub mod deriv;
+pub mod dual;
+#[cfg(feature = "gsl")]
+pub mod eigen;
+#[cfg(feature = "gsl")]
 pub mod fft;
+#[cfg(feature = "gsl")]
 pub mod filter;
-pub mod integration;
-pub mod interpolation;
+#[cfg(feature = "gsl")]
+pub mod fourier;
+#[cfg(feature = "gsl")]
+pub mod geometry;
+#[cfg(feature = "gsl")]
+pub mod histogram;
+#[cfg(feature = "gsl")]
+pub mod integrate;
+#[cfg(feature = "gsl")]
+pub mod interp;
+#[cfg(feature = "gsl")]
+pub mod interval;
+#[cfg(feature = "gsl")]
+pub mod likelihood_fit;
+#[cfg(feature = "gsl")]
+pub mod linalg;
+#[cfg(feature = "gsl")]
 pub mod linear_fit;
-pub mod minimizer;
+#[cfg(feature = "gsl")]
+pub mod markov;
+#[cfg(feature = "gsl")]
+pub mod mcmc;
+#[cfg(feature = "gsl")]
+pub mod memory;
+#[cfg(feature = "gsl")]
+pub mod min;
+#[cfg(feature = "gsl")]
+pub mod model;
+#[cfg(feature = "gsl")]
+pub mod movstat;
+#[cfg(feature = "gsl")]
+pub mod multilarge;
+#[cfg(feature = "gsl")]
+pub mod multilarge_nlinear;
+#[cfg(feature = "gsl")]
+pub mod multimin;
+#[cfg(feature = "gsl")]
 pub mod nonlinear_fit;
-pub mod sorting;
-pub mod special;
+#[cfg(feature = "gsl")]
+pub mod ntuple;
+#[cfg(feature = "gsl")]
+pub mod ode;
+#[cfg(feature = "gsl")]
+pub mod policy;
+#[cfg(feature = "gsl")]
+pub mod poly;
+#[cfg(feature = "gsl")]
+pub mod posterior;
+#[cfg(feature = "gsl")]
+pub mod power;
+pub mod prelude;
+#[cfg(feature = "gsl")]
+pub mod randist;
+#[cfg(feature = "gsl")]
+pub mod rng;
+#[cfg(feature = "gsl")]
+pub mod roots;
+#[cfg(feature = "gsl")]
+pub mod rstat;
+#[cfg(feature = "gsl")]
+pub mod sampling;
+#[cfg(feature = "gsl")]
+pub mod sde;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "gsl")]
+pub mod sf;
+#[cfg(feature = "gsl")]
+pub mod sort;
+#[cfg(feature = "gsl")]
+pub mod sparse;
+#[cfg(feature = "gsl")]
+pub mod spatial;
+#[cfg(feature = "gsl")]
+pub mod spectrum;
 pub mod stats;
+#[cfg(feature = "gsl")]
+pub mod table;
+#[cfg(feature = "gsl")]
+pub mod text;
+#[cfg(feature = "gsl")]
+pub mod unfolding;
+pub mod util;
 
+/*
+
+    The modules follow the chapters of the GSL reference manual and the prefixes of its functions,
+    e.g. `sf` for `gsl_sf_*` and `randist` for `gsl_ran_*`, so functions can be found from the GSL documentation.
+
+    Without the default `gsl` feature, GSL isn't built or linked, and only the modules written in Rust
+    are available: `blas`, `dual`, `stats` and `util`, with the error types and `ThreadLocalWorkspace`.
+    This subset compiles for targets that GSL can't be built for, like `wasm32-unknown-unknown`.
+    Of the numerical modules only `stats` has a port in Rust, the others need GSL.
+
+    The modules below are the old names, kept as deprecated aliases.
+
+*/
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `cheb`, after the GSL manual")]
+pub mod chebyshev {
+    pub use crate::cheb::*;
+}
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `randist`, after the GSL manual")]
+pub mod distribution {
+    pub use crate::randist::*;
+}
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `integrate`, after the GSL manual")]
+pub mod integration {
+    pub use crate::integrate::*;
+}
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `interp`, after the GSL manual")]
+pub mod interpolation {
+    pub use crate::interp::*;
+}
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `min`, after the GSL manual")]
+pub mod minimizer {
+    pub use crate::min::*;
+}
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `deriv`, after the GSL manual")]
+pub mod numdiff {
+    pub use crate::deriv::*;
+}
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `sort`, after the GSL manual")]
+pub mod sorting {
+    pub use crate::sort::*;
+}
+
+#[cfg(feature = "gsl")]
+#[deprecated(note = "renamed to `sf`, after the GSL manual")]
+pub mod special {
+    pub use crate::sf::*;
+}
+
+#[deprecated(note = "renamed to `stats`, after the GSL manual")]
+pub mod statistics {
+    pub use crate::stats::*;
+}
+
+#[cfg(feature = "gsl")]
+mod capabilities;
+#[cfg(feature = "gsl")]
+pub use capabilities::*;
+#[cfg(feature = "gsl")]
 mod data;
+#[cfg(feature = "gsl")]
 pub use data::*;
 mod error;
 pub use error::*;
+#[cfg(all(test, feature = "gsl"))]
+mod testing;
+mod workspace;
+pub use workspace::*;
 
+#[cfg(feature = "gsl")]
 pub mod bindings {
     #![allow(dead_code)]
     #![allow(non_upper_case_globals)]
@@ -49,12 +223,13 @@ pub mod bindings {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
+/// Stops GSL from aborting the program on errors, which are then only returned as `Err`.
+/// Removes the handler of `set_error_handler` on this thread.
 pub fn disable_error_handler() {
-    unsafe {
-        bindings::gsl_set_error_handler_off();
-    }
+    error::clear_error_handler();
 }
 
+#[cfg(feature = "gsl")]
 unsafe extern "C" fn trampoline<F: FnMut(f64) -> f64>(x: f64, params: *mut c_void) -> f64 {
     let f: &mut F = &mut *(params as *mut F);
     match catch_unwind(AssertUnwindSafe(move || f(x))) {
@@ -62,3 +237,34 @@ unsafe extern "C" fn trampoline<F: FnMut(f64) -> f64>(x: f64, params: *mut c_voi
         Err(_) => f64::NAN,
     }
 }
+
+#[cfg(feature = "gsl")]
+/// Elements of `x` where `mask` is `true`
+fn select<'a, T>(x: &'a [T], mask: &[bool]) -> Vec<&'a T> {
+    x.iter()
+        .zip(mask)
+        .filter(|(_, &m)| m)
+        .map(|(x, _)| x)
+        .collect()
+}
+
+#[cfg(feature = "gsl")]
+/// Spreads `values` over the positions where `mask` is `true`, with NaN in between
+fn unselect(values: &[f64], mask: &[bool]) -> Box<[f64]> {
+    let mut values = values.iter();
+    mask.iter()
+        .map(|&m| if m { *values.next().unwrap() } else { f64::NAN })
+        .collect()
+}
+
+#[test]
+#[cfg(feature = "gsl")]
+#[allow(deprecated)]
+fn test_deprecated_aliases() {
+    disable_error_handler();
+
+    assert_eq!(special::gamma(5.0), sf::gamma(5.0));
+    assert_eq!(special::bessel::zero_j0(1), sf::bessel::zero_j0(1));
+    assert_eq!(statistics::mean(&[1.0, 2.0]), stats::mean(&[1.0, 2.0]));
+    let _: distribution::Fisher = randist::Fisher::new([0.0, 0.0, 1.0], 1.0).unwrap();
+}
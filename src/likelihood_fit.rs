@@ -0,0 +1,660 @@
+/*
+    likelihood_fit.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::histogram::Histogram;
+use crate::integrate::GLFixedTable;
+use crate::interval::Interval;
+use crate::linalg::LU;
+use crate::multimin::{multimin_gradient_ext, GradientMethod};
+use crate::nonlinear_fit::{nonlinear_fit_ext, FitCallback, HyperParams};
+use crate::*;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+
+/*
+
+    A least squares fit assumes Gaussian errors, which is biased for bins with few counts.
+    For Poisson distributed bin counts `n_i` with expectation `μ_i` the likelihood ratio
+    with respect to the saturated model (`μ_i = n_i`) is
+
+        -2 ln λ = 2 Σ (μ_i - n_i + n_i ln(n_i / μ_i))
+
+    which is a sum of squares of the deviance residuals
+    `sign(n_i - μ_i) sqrt(2 (μ_i - n_i + n_i ln(n_i / μ_i)))`.
+    Minimizing it with the nonlinear least squares solver maximizes the likelihood,
+    and for large counts it follows a chi-squared distribution with `bins - P` degrees of freedom
+    (Baker and Cousins, 1984).
+
+    The extended unbinned likelihood of events `x_i` for a mixture of normalized densities `f_k`
+    with yields `N_k` is
+
+        -ln L = Σ_k N_k - Σ_i ln Σ_k N_k f_k(x_i)
+
+    up to a constant, where the first term accounts for the Poisson distributed number of events.
+
+*/
+
+/// Maximum likelihood fit of a histogram, with the counts Poisson distributed around
+/// the integral of the density `f(x, params)` (expected counts per unit of `x`) over each bin.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
+pub fn binned_likelihood_fit<F: FnMut(f64, [f64; P]) -> Result<f64>, const P: usize>(
+    p0: [f64; P],
+    histogram: &Histogram,
+    f: F,
+) -> Result<BinnedFitResult<P>> {
+    binned_likelihood_fit_with_policy(policy::policy(), p0, histogram, f)
+}
+
+/// `binned_likelihood_fit` with `policy` instead of the policy of this thread
+pub fn binned_likelihood_fit_with_policy<F: FnMut(f64, [f64; P]) -> Result<f64>, const P: usize>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    histogram: &Histogram,
+    f: F,
+) -> Result<BinnedFitResult<P>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        binned_likelihood_fit_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            HyperParams::default(),
+            8,
+            p0,
+            histogram,
+            f,
+            None::<fn(FitCallback<P>)>,
+        )
+    })
+}
+
+/// The bin integrals use an `order` point Gauss-Legendre rule, which is a smooth function
+/// of the parameters, unlike adaptive quadrature.
+/// The callback receives the likelihood ratio `-2 ln λ` as `residual_squared`.
+pub fn binned_likelihood_fit_ext<
+    F: FnMut(f64, [f64; P]) -> Result<f64>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    hyper_params: HyperParams,
+    order: usize,
+    p0: [f64; P],
+    histogram: &Histogram,
+    mut f: F,
+    callback: Option<C>,
+) -> Result<BinnedFitResult<P>> {
+    let bins = histogram.n();
+    if bins <= P {
+        return Err(GSLError::Invalid);
+    }
+
+    let counts = histogram.bins();
+    if counts.iter().any(|&n| !(n >= 0.0 && n.is_finite())) {
+        return Err(GSLError::Domain);
+    }
+
+    // Quadrature nodes and weights of every bin
+    let table = GLFixedTable::new(order)?;
+    let points = (0..bins)
+        .map(|i| {
+            let (a, b) = histogram.range(i);
+            table.points(a, b)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut expected = |i: usize, params: [f64; P]| -> Result<f64> {
+        let (nodes, weights) = &points[i];
+        let mut sum = 0.0;
+        for (&x, &w) in nodes.iter().zip(weights.iter()) {
+            sum += w * f(x, params)?;
+        }
+        Ok(sum)
+    };
+
+    let index = (0..bins).collect::<Vec<_>>();
+    let result = nonlinear_fit_ext(
+        max_iter,
+        xtol,
+        gtol,
+        ftol,
+        hyper_params,
+        p0,
+        &index,
+        &vec![0.0; bins],
+        |&i, params| Ok(deviance_residual(counts[i], expected(i, params)?)),
+        callback,
+    )?;
+    let params = result.params;
+
+    // Expected Fisher information Σ ∂μ_i/∂θ_j ∂μ_i/∂θ_k / μ_i, with central differences
+    let mu = (0..bins)
+        .map(|i| expected(i, params))
+        .collect::<Result<Vec<_>>>()?;
+    let mut gradients = vec![[0.0; P]; bins];
+    for j in 0..P {
+        let h = 1.0e-5 * params[j].abs().max(1.0);
+        let mut upper = params;
+        let mut lower = params;
+        upper[j] += h;
+        lower[j] -= h;
+        for (i, gradient) in gradients.iter_mut().enumerate() {
+            gradient[j] = (expected(i, upper)? - expected(i, lower)?) / (2.0 * h);
+        }
+    }
+
+    let mut information = vec![0.0; P * P];
+    for (gradient, &mu) in gradients.iter().zip(mu.iter()) {
+        if mu <= 0.0 {
+            continue;
+        }
+        for j in 0..P {
+            for k in 0..P {
+                information[j * P + k] += gradient[j] * gradient[k] / mu;
+            }
+        }
+    }
+
+    let lu = LU::new(P, &information)?;
+    let mut covariance = [[0.0; P]; P];
+    for k in 0..P {
+        let mut unit = vec![0.0; P];
+        unit[k] = 1.0;
+        let column = lu.solve(&unit)?;
+        for j in 0..P {
+            covariance[j][k] = column[j];
+        }
+    }
+
+    Ok(BinnedFitResult {
+        params,
+        covariance,
+        expected: mu.into_boxed_slice(),
+        deviance: result.final_residual_squared,
+        ndf: bins - P,
+        niter: result.niter,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinnedFitResult<const P: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::array"))]
+    pub params: [f64; P],
+    /// Inverse of the expected Fisher information
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::array2"))]
+    pub covariance: [[f64; P]; P],
+    /// Expected counts of every bin
+    pub expected: Box<[f64]>,
+    /// Likelihood ratio `-2 ln λ` with respect to the saturated model
+    pub deviance: f64,
+    /// Degrees of freedom, `bins - P`
+    pub ndf: usize,
+    pub niter: u64,
+}
+
+impl<const P: usize> BinnedFitResult<P> {
+    pub fn uncertainty(&self, i: usize) -> f64 {
+        self.covariance[i][i].sqrt()
+    }
+}
+
+/// A nonpositive expectation is clamped, giving a large residual for nonempty bins
+fn deviance_residual(n: f64, mu: f64) -> f64 {
+    let mu = mu.max(f64::MIN_POSITIVE);
+    let log_term = if n > 0.0 { n * (n / mu).ln() } else { 0.0 };
+    let d = (2.0 * (mu - n + log_term)).max(0.0).sqrt();
+    if n < mu {
+        -d
+    } else {
+        d
+    }
+}
+
+/// Extended unbinned maximum likelihood fit of a list of events to a mixture of `components`
+/// normalized densities, which share `shape` parameters and each have their own yield.
+/// The parameters are the shape parameters followed by the yields.
+pub struct UnbinnedFit<'a, X, F> {
+    events: &'a [X],
+    shape: usize,
+    components: usize,
+    pdfs: F,
+}
+
+impl<'a, X, F: FnMut(&X, &[f64], &mut [f64]) -> Result<()>> UnbinnedFit<'a, X, F> {
+    /// `pdfs(x, shape, densities)` stores the normalized density of every component at `x`
+    pub fn new(events: &'a [X], shape: usize, components: usize, pdfs: F) -> Result<Self> {
+        if events.is_empty() || components == 0 {
+            return Err(GSLError::Invalid);
+        }
+        Ok(UnbinnedFit {
+            events,
+            shape,
+            components,
+            pdfs,
+        })
+    }
+
+    /// Number of parameters, the shape parameters followed by the yields
+    pub fn n_params(&self) -> usize {
+        self.shape + self.components
+    }
+
+    /// Negative log likelihood `Σ N_k - Σ_i ln Σ_k N_k f_k(x_i)`, up to a constant.
+    /// A nonpositive total density is clamped, which strongly penalizes the event.
+    pub fn nll(&mut self, params: &[f64]) -> Result<f64> {
+        if params.len() != self.n_params() {
+            return Err(GSLError::BadLength);
+        }
+
+        let (shape, yields) = params.split_at(self.shape);
+        let mut densities = vec![0.0; self.components];
+        let mut nll = yields.iter().sum::<f64>();
+        for x in self.events {
+            (self.pdfs)(x, shape, &mut densities)?;
+            nll -= blas::dot(yields, &densities)?.max(f64::MIN_POSITIVE).ln();
+        }
+        Ok(nll)
+    }
+
+    /// Maximum likelihood estimate starting from `p0`,
+    /// with the covariance from the numerical Hessian of the negative log likelihood
+    pub fn fit(&mut self, p0: &[f64]) -> Result<UnbinnedFitResult> {
+        if p0.len() != self.n_params() {
+            return Err(GSLError::BadLength);
+        }
+
+        let (params, nll, niter) = self.minimize(p0, None)?;
+
+        // Central differences
+        let n = params.len();
+        let steps = params
+            .iter()
+            .map(|p| 1.0e-4 * p.abs().max(1.0))
+            .collect::<Vec<_>>();
+        let shifted = |this: &mut Self, j: usize, sj: f64, k: usize, sk: f64| {
+            let mut p = params.to_vec();
+            p[j] += sj * steps[j];
+            p[k] += sk * steps[k];
+            this.nll(&p)
+        };
+        let mut hessian = vec![0.0; n * n];
+        for j in 0..n {
+            let upper = shifted(self, j, 1.0, j, 0.0)?;
+            let lower = shifted(self, j, -1.0, j, 0.0)?;
+            hessian[j * n + j] = (upper - 2.0 * nll + lower) / (steps[j] * steps[j]);
+            for k in 0..j {
+                let h = (shifted(self, j, 1.0, k, 1.0)?
+                    - shifted(self, j, 1.0, k, -1.0)?
+                    - shifted(self, j, -1.0, k, 1.0)?
+                    + shifted(self, j, -1.0, k, -1.0)?)
+                    / (4.0 * steps[j] * steps[k]);
+                hessian[j * n + k] = h;
+                hessian[k * n + j] = h;
+            }
+        }
+
+        let lu = LU::new(n, &hessian)?;
+        let mut covariance = vec![0.0; n * n];
+        for k in 0..n {
+            let mut unit = vec![0.0; n];
+            unit[k] = 1.0;
+            let column = lu.solve(&unit)?;
+            for j in 0..n {
+                covariance[j * n + k] = column[j];
+            }
+        }
+
+        Ok(UnbinnedFitResult {
+            params,
+            covariance: covariance.into_boxed_slice(),
+            nll,
+            niter,
+        })
+    }
+
+    /// Profile likelihood interval of parameter `i`, see `profile_interval_ext`
+    pub fn profile_interval(&mut self, result: &UnbinnedFitResult, i: usize) -> Result<Interval> {
+        self.profile_interval_ext(result, i, 0.5)
+    }
+
+    /// Values of parameter `i` where the negative log likelihood, minimized over all other parameters,
+    /// is `up` above its minimum (like MINOS).
+    /// An `up` of 0.5 gives the one standard deviation interval, 2.0 two standard deviations.
+    /// Unlike the covariance, the interval can be asymmetric.
+    pub fn profile_interval_ext(
+        &mut self,
+        result: &UnbinnedFitResult,
+        i: usize,
+        up: f64,
+    ) -> Result<Interval> {
+        if result.params.len() != self.n_params() {
+            return Err(GSLError::BadLength);
+        }
+        if i >= result.params.len() {
+            return Err(GSLError::Invalid);
+        }
+        if !(up > 0.0 && up.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+
+        // Initial steps from the parabolic approximation
+        let step = result.uncertainty(i) * (2.0 * up).sqrt();
+        if !(step > 0.0 && step.is_finite()) {
+            return Err(GSLError::Failed);
+        }
+
+        Ok(Interval {
+            lower: self.profile_crossing(result, i, up, -step)?,
+            upper: self.profile_crossing(result, i, up, step)?,
+        })
+    }
+
+    fn profile_crossing(
+        &mut self,
+        result: &UnbinnedFitResult,
+        i: usize,
+        up: f64,
+        step: f64,
+    ) -> Result<f64> {
+        // Excess of the profile over the target, starting from the previous minimum
+        let mut start = result.params.to_vec();
+        let mut excess = |this: &mut Self, t: f64| -> Result<f64> {
+            start[i] = t;
+            let (params, nll, _) = this.minimize(&start, Some(i))?;
+            start.copy_from_slice(&params);
+            Ok(nll - result.nll - up)
+        };
+
+        // Bracket the crossing, excess(inner) < 0 <= excess(outer)
+        let mut inner = result.params[i];
+        let mut outer = inner + step;
+        let mut width = step;
+        while excess(self, outer)? < 0.0 {
+            inner = outer;
+            width *= 2.0;
+            outer += width;
+            if !outer.is_finite() || width.abs() > 1.0e6 * step.abs() {
+                return Err(GSLError::MaxIteration);
+            }
+        }
+
+        while (outer - inner).abs() > 1.0e-4 * step.abs() {
+            let mid = 0.5 * (inner + outer);
+            if excess(self, mid)? < 0.0 {
+                inner = mid;
+            } else {
+                outer = mid;
+            }
+        }
+        Ok(0.5 * (inner + outer))
+    }
+
+    /// Minimizes with BFGS and numerical gradients, optionally keeping parameter `fixed` at its start value
+    fn minimize(
+        &mut self,
+        start: &[f64],
+        fixed: Option<usize>,
+    ) -> Result<(Box<[f64]>, f64, usize)> {
+        let free = (0..start.len())
+            .filter(|&j| Some(j) != fixed)
+            .collect::<Vec<_>>();
+        if free.is_empty() {
+            let nll = self.nll(start)?;
+            return Ok((start.into(), nll, 0));
+        }
+
+        let full = |x: &[f64]| {
+            let mut params = start.to_vec();
+            for (&j, &x) in free.iter().zip(x.iter()) {
+                params[j] = x;
+            }
+            params
+        };
+
+        // The minimizer can't return errors, so they are stored
+        let this = RefCell::new(self);
+        let error = Cell::new(None);
+        let objective = |x: &[f64]| match this.borrow_mut().nll(&full(x)) {
+            Ok(nll) => nll,
+            Err(e) => {
+                error.set(Some(e));
+                f64::NAN
+            }
+        };
+        let gradient = |x: &[f64], g: &mut [f64]| {
+            let mut shifted = x.to_vec();
+            for j in 0..x.len() {
+                let h = 1.0e-6 * x[j].abs().max(1.0);
+                shifted[j] = x[j] + h;
+                let upper = objective(&shifted);
+                shifted[j] = x[j] - h;
+                let lower = objective(&shifted);
+                shifted[j] = x[j];
+                g[j] = (upper - lower) / (2.0 * h);
+            }
+        };
+
+        let x0 = free.iter().map(|&j| start[j]).collect::<Vec<_>>();
+        let result = multimin_gradient_ext(
+            1000,
+            GradientMethod::Bfgs2,
+            0.01,
+            0.1,
+            1.0e-5,
+            &x0,
+            objective,
+            gradient,
+            |_| {},
+        );
+        if let Some(e) = error.take() {
+            return Err(e);
+        }
+        let result = result?;
+
+        Ok((full(&result.x).into_boxed_slice(), result.f, result.niter))
+    }
+}
+
+impl<'a, X, F> fmt::Debug for UnbinnedFit<'a, X, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnbinnedFit")
+            .field("events", &self.events.len())
+            .field("shape", &self.shape)
+            .field("components", &self.components)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnbinnedFitResult {
+    /// Shape parameters followed by the yields
+    pub params: Box<[f64]>,
+    /// Inverse of the Hessian of the negative log likelihood, row-major
+    pub covariance: Box<[f64]>,
+    /// Minimum of the negative log likelihood
+    pub nll: f64,
+    pub niter: usize,
+}
+
+impl UnbinnedFitResult {
+    pub fn uncertainty(&self, i: usize) -> f64 {
+        self.covariance[i * self.params.len() + i].sqrt()
+    }
+}
+
+#[test]
+fn test_binned_likelihood_fit() {
+    disable_error_handler();
+
+    // Gaussian peak with few counts per bin
+    let mut rng = rng::Rng::with_seed(rng::RngType::Mt19937, 7);
+    let mut histogram = Histogram::uniform(40, -4.0, 4.0).unwrap();
+    for _ in 0..200 {
+        histogram.increment(0.5 + rng.gaussian(1.0)).unwrap_or(());
+    }
+    let total = histogram.sum();
+
+    let gaussian = |x: f64, [n, mu, sigma]: [f64; 3]| {
+        let z = (x - mu) / sigma;
+        Ok(n * (-0.5 * z * z).exp() / (sigma * (2.0 * std::f64::consts::PI).sqrt()))
+    };
+    let fit = binned_likelihood_fit([100.0, 0.0, 2.0], &histogram, gaussian).unwrap();
+
+    approx::assert_abs_diff_eq!(fit.params[1], 0.5, epsilon = 3.0 * fit.uncertainty(1));
+    approx::assert_abs_diff_eq!(fit.params[2], 1.0, epsilon = 3.0 * fit.uncertainty(2));
+
+    // With a free normalization the expected total equals the observed total,
+    // unlike for a least squares fit
+    let expected = fit.expected.iter().sum::<f64>();
+    approx::assert_relative_eq!(expected, total, max_relative = 1.0e-6);
+
+    // Poisson uncertainty of the normalization
+    approx::assert_relative_eq!(fit.uncertainty(0), total.sqrt(), max_relative = 0.05);
+    assert_eq!(fit.ndf, 37);
+    assert!(fit.deviance > 0.0);
+}
+
+#[test]
+fn test_constant_rate() {
+    disable_error_handler();
+
+    // The maximum likelihood rate is the mean count, including empty bins
+    let mut histogram = Histogram::uniform(5, 0.0, 10.0).unwrap();
+    for (i, n) in [0.0, 3.0, 1.0, 0.0, 6.0].iter().enumerate() {
+        histogram.accumulate(2.0 * i as f64 + 1.0, *n).unwrap();
+    }
+
+    let fit = binned_likelihood_fit([1.0], &histogram, |_, [rate]| Ok(rate)).unwrap();
+    approx::assert_abs_diff_eq!(fit.params[0], 1.0, epsilon = 1.0e-6);
+    approx::assert_abs_diff_eq!(fit.uncertainty(0), 0.1f64.sqrt(), epsilon = 1.0e-6);
+}
+
+#[test]
+fn test_unbinned_likelihood_fit() {
+    disable_error_handler();
+
+    // Gaussian signal on a flat background in [0, 10]
+    let mut rng = rng::Rng::with_seed(rng::RngType::Mt19937, 11);
+    let mut events = (0..300)
+        .map(|_| 5.0 + rng.gaussian(0.5))
+        .collect::<Vec<_>>();
+    events.extend((0..700).map(|_| 10.0 * rng.uniform()));
+
+    let pdfs = |&x: &f64, shape: &[f64], densities: &mut [f64]| {
+        let z = (x - shape[0]) / shape[1];
+        densities[0] = (-0.5 * z * z).exp() / (shape[1] * (2.0 * std::f64::consts::PI).sqrt());
+        densities[1] = 0.1;
+        Ok(())
+    };
+    let mut fit = UnbinnedFit::new(&events, 2, 2, pdfs).unwrap();
+    assert_eq!(fit.n_params(), 4);
+
+    let result = fit.fit(&[4.5, 1.0, 500.0, 500.0]).unwrap();
+    approx::assert_abs_diff_eq!(result.params[0], 5.0, epsilon = 3.0 * result.uncertainty(0));
+    approx::assert_abs_diff_eq!(result.params[1], 0.5, epsilon = 3.0 * result.uncertainty(1));
+    approx::assert_abs_diff_eq!(
+        result.params[2],
+        300.0,
+        epsilon = 3.0 * result.uncertainty(2)
+    );
+
+    // The yields add up to the number of events
+    approx::assert_relative_eq!(
+        result.params[2] + result.params[3],
+        1000.0,
+        max_relative = 1.0e-4
+    );
+    approx::assert_relative_eq!(
+        result.nll,
+        fit.nll(&result.params).unwrap(),
+        max_relative = 1.0e-12
+    );
+
+    // Close to the parabolic errors for this many events
+    let interval = fit.profile_interval(&result, 2).unwrap();
+    assert!(interval.contains(result.params[2]));
+    approx::assert_relative_eq!(
+        interval.width(),
+        2.0 * result.uncertainty(2),
+        max_relative = 0.1
+    );
+}
+
+#[test]
+fn test_unbinned_yield() {
+    disable_error_handler();
+
+    // Only a yield, whose profile interval is asymmetric
+    let events = (0..100).map(|i| i as f64 / 100.0).collect::<Vec<_>>();
+    let mut fit = UnbinnedFit::new(&events, 0, 1, |_, _, densities| {
+        densities[0] = 1.0;
+        Ok(())
+    })
+    .unwrap();
+
+    let result = fit.fit(&[50.0]).unwrap();
+    approx::assert_abs_diff_eq!(result.params[0], 100.0, epsilon = 1.0e-3);
+    approx::assert_relative_eq!(result.uncertainty(0), 10.0, max_relative = 1.0e-3);
+
+    // N - n - n ln(N / n) = 1/2
+    let interval = fit.profile_interval(&result, 0).unwrap();
+    approx::assert_abs_diff_eq!(interval.lower, 90.3305, epsilon = 1.0e-2);
+    approx::assert_abs_diff_eq!(interval.upper, 110.3361, epsilon = 1.0e-2);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let histogram = Histogram::uniform(2, 0.0, 1.0).unwrap();
+
+    // Fewer bins than parameters
+    binned_likelihood_fit([1.0, 1.0], &histogram, |_, [a, _]| Ok(a)).unwrap_err();
+
+    // Error in the model
+    binned_likelihood_fit([1.0], &histogram, |_, _| Err(GSLError::Domain)).unwrap_err();
+
+    // Negative counts
+    let mut negative = Histogram::uniform(3, 0.0, 1.0).unwrap();
+    negative.accumulate(0.5, -1.0).unwrap();
+    binned_likelihood_fit([1.0], &negative, |_, [a]| Ok(a)).unwrap_err();
+
+    // No events or components
+    let pdfs = |_: &f64, _: &[f64], densities: &mut [f64]| {
+        densities.fill(1.0);
+        Ok(())
+    };
+    UnbinnedFit::new(&[], 0, 1, pdfs).unwrap_err();
+    UnbinnedFit::new(&[1.0], 0, 0, pdfs).unwrap_err();
+
+    let mut fit = UnbinnedFit::new(&[1.0], 1, 1, pdfs).unwrap();
+
+    // Wrong number of parameters
+    fit.fit(&[1.0]).unwrap_err();
+    fit.nll(&[1.0, 2.0, 3.0]).unwrap_err();
+
+    // Error in the densities
+    let mut failing = UnbinnedFit::new(&[1.0], 0, 1, |_, _, _| Err(GSLError::Domain)).unwrap();
+    failing.fit(&[1.0]).unwrap_err();
+}
@@ -0,0 +1,661 @@
+/*
+    linalg.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::rng::Rng;
+use crate::*;
+use std::fmt;
+
+/*
+
+    Matrices are passed as row major slices together with their dimensions,
+    consistent with the eigenvalue module.
+
+*/
+
+/// Maximum absolute column sum of an `m` by `n` matrix
+pub fn norm_1(m: usize, n: usize, a: &[f64]) -> f64 {
+    assert_eq!(a.len(), m * n);
+    (0..n)
+        .map(|j| (0..m).map(|i| a[i * n + j].abs()).sum::<f64>())
+        .fold(0.0, f64::max)
+}
+
+/// Maximum absolute row sum of an `m` by `n` matrix
+pub fn norm_inf(m: usize, n: usize, a: &[f64]) -> f64 {
+    assert_eq!(a.len(), m * n);
+    if n == 0 {
+        return 0.0;
+    }
+    a.chunks_exact(n)
+        .map(|row| row.iter().map(|x| x.abs()).sum::<f64>())
+        .fold(0.0, f64::max)
+}
+
+/// Square root of the sum of squared elements of an `m` by `n` matrix
+pub fn norm_frobenius(m: usize, n: usize, a: &[f64]) -> f64 {
+    assert_eq!(a.len(), m * n);
+    a.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+/// Solves `A x = b` for a square `n` by `n` matrix `A` using LU decomposition.
+pub fn solve(n: usize, a: &[f64], b: &[f64]) -> Result<Box<[f64]>> {
+    if b.len() != n {
+        return Err(GSLError::BadLength);
+    }
+    LU::new(n, a)?.solve(b)
+}
+
+/// Estimates the 1-norm condition number `|A|_1 |A^-1|_1` of a square `n` by `n` matrix,
+/// using Hager's method as refined by Higham.
+///
+/// This needs two LU decompositions and a handful of solves, instead of a full inverse.
+/// The estimate is a lower bound, which is usually within a factor of 3 of the true value.
+pub fn cond_1_estimate(n: usize, a: &[f64]) -> Result<f64> {
+    if n == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if a.len() != n * n {
+        return Err(GSLError::BadLength);
+    }
+
+    let lu = LU::new(n, a)?;
+    let at = (0..n)
+        .flat_map(|i| (0..n).map(move |j| a[j * n + i]))
+        .collect::<Vec<_>>();
+    let lu_t = LU::new(n, &at)?;
+
+    let mut x = vec![1.0 / n as f64; n];
+    let mut estimate = 0.0;
+
+    for iter in 0..5 {
+        let y = lu.solve(&x)?;
+        let y_norm = y.iter().map(|y| y.abs()).sum::<f64>();
+
+        // Stop when the estimate no longer increases
+        if iter > 0 && y_norm <= estimate {
+            break;
+        }
+        estimate = y_norm;
+
+        let xi = y
+            .iter()
+            .map(|&y| if y >= 0.0 { 1.0 } else { -1.0 })
+            .collect::<Vec<_>>();
+        let z = lu_t.solve(&xi)?;
+
+        let (j, z_max) = z
+            .iter()
+            .map(|z| z.abs())
+            .enumerate()
+            .fold((0, 0.0), |max, (j, z)| if z > max.1 { (j, z) } else { max });
+        if z_max <= blas::dot(&z, &x)? {
+            break;
+        }
+
+        x.iter_mut().for_each(|x| *x = 0.0);
+        x[j] = 1.0;
+    }
+
+    // Higham's alternative lower bound guards against unlucky cancellation
+    let alt = (0..n)
+        .map(|i| {
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            sign * (1.0 + i as f64 / (n as f64 - 1.0).max(1.0))
+        })
+        .collect::<Vec<_>>();
+    let alt_norm = 2.0 * lu.solve(&alt)?.iter().map(|x| x.abs()).sum::<f64>() / (3.0 * n as f64);
+
+    Ok(norm_1(n, n, a) * estimate.max(alt_norm))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Whitening {
+    /// `z = L^-1 (x - mean)`, where `cov = L L^T`
+    Cholesky,
+    /// `z = Λ^-1/2 V^T (x - mean)`, where `cov = V Λ V^T`
+    Pca,
+}
+
+/// Whitens the observations in the rows of `data`,
+/// such that the result has zero mean and unit sample covariance.
+pub fn whiten(data: &Matrix, method: Whitening) -> Result<Matrix> {
+    let (m, n) = data.size();
+    if m < 2 || n == 0 {
+        return Err(GSLError::Invalid);
+    }
+
+    let mean = (0..n)
+        .map(|j| (0..m).map(|i| data[i * n + j]).sum::<f64>() / m as f64)
+        .collect::<Vec<_>>();
+    let centered = data
+        .chunks_exact(n)
+        .flat_map(|row| row.iter().zip(&mean).map(|(x, mu)| x - mu))
+        .collect::<Vec<_>>();
+
+    let mut cov = vec![0.0; n * n];
+    for row in centered.chunks_exact(n) {
+        for j in 0..n {
+            for k in 0..n {
+                cov[j * n + k] += row[j] * row[k] / (m - 1) as f64;
+            }
+        }
+    }
+
+    let mut out = Vec::with_capacity(m * n);
+    match method {
+        Whitening::Cholesky => {
+            let cholesky = Cholesky::new(n, &cov)?;
+            for row in centered.chunks_exact(n) {
+                out.extend(cholesky.solve_lower(row));
+            }
+        }
+        Whitening::Pca => {
+            let eigen = eigen::symmetric(n, &cov)?;
+            // Singular covariance, up to rounding
+            if eigen.values[0] <= n as f64 * f64::EPSILON * eigen.values[n - 1] {
+                return Err(GSLError::Domain);
+            }
+            let vectors = (0..n).map(|k| eigen.vector(k)).collect::<Vec<_>>();
+            for row in centered.chunks_exact(n) {
+                for (v, lambda) in vectors.iter().zip(eigen.values.iter()) {
+                    out.push(blas::dot(v, row)? / lambda.sqrt());
+                }
+            }
+        }
+    }
+
+    Ok(Matrix::new(out, m, n))
+}
+
+/// Mahalanobis distance `sqrt((x - mean)^T cov^-1 (x - mean))` for a positive definite
+/// `n` by `n` covariance matrix.
+pub fn mahalanobis(x: &Vector, mean: &Vector, cov: &Matrix) -> Result<f64> {
+    let n = x.len();
+    if n == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if mean.len() != n || cov.size() != (n, n) {
+        return Err(GSLError::BadLength);
+    }
+
+    let d = x
+        .iter()
+        .zip(mean.iter())
+        .map(|(x, mu)| x - mu)
+        .collect::<Vec<_>>();
+    let z = Cholesky::new(n, cov)?.solve_lower(&d);
+    Ok(blas::nrm2(&z))
+}
+
+/// Samples an `n` by `n` orthogonal matrix from the Haar measure, stored row major.
+///
+/// This takes the QR decomposition of a matrix of standard normal variates,
+/// fixing the signs of the columns of `Q` such that the diagonal of `R` is positive.
+pub fn random_orthogonal(n: usize, rng: &mut Rng) -> Result<Box<[f64]>> {
+    unsafe {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+
+        let mut a = Matrix::new((0..n * n).map(|_| rng.gaussian(1.0)), n, n);
+        let mut tau = Vector::zeroes(n);
+        let mut q = Matrix::zeroes(n, n);
+        let mut r = Matrix::zeroes(n, n);
+
+        GSLError::from_raw(gsl_linalg_QR_decomp(a.as_gsl_mut(), tau.as_gsl_mut()))?;
+        GSLError::from_raw(gsl_linalg_QR_unpack(
+            a.as_gsl(),
+            tau.as_gsl(),
+            q.as_gsl_mut(),
+            r.as_gsl_mut(),
+        ))?;
+
+        for j in 0..n {
+            if r.elem_ij(j, j) < 0.0 {
+                for i in 0..n {
+                    q.set_ij(i, j, -q.elem_ij(i, j));
+                }
+            }
+        }
+
+        Ok(q.to_boxed_slice())
+    }
+}
+
+/// Samples an `n` by `n` correlation matrix with density proportional to `det(R)^(eta - 1)`,
+/// stored row major. `eta = 1` is uniform over all correlation matrices,
+/// larger values concentrate around the identity.
+///
+/// Uses the onion method of Lewandowski, Kurowicka and Joe (2009).
+pub fn random_correlation(n: usize, eta: f64, rng: &mut Rng) -> Result<Box<[f64]>> {
+    if n == 0 || eta <= 0.0 || !eta.is_finite() {
+        return Err(GSLError::Invalid);
+    }
+
+    let mut corr = vec![1.0];
+    let mut beta = eta + (n as f64 - 2.0) / 2.0;
+
+    for k in 1..n {
+        // Correlations of the new variable with the previous k variables
+        let z = if k == 1 {
+            vec![2.0 * rng.beta(beta, beta) - 1.0]
+        } else {
+            beta -= 0.5;
+            let y = rng.beta(k as f64 / 2.0, beta);
+
+            // Uniform direction on the unit sphere, scaled to radius sqrt(y)
+            let mut w = (0..k).map(|_| rng.gaussian(1.0)).collect::<Vec<_>>();
+            let scale = y.sqrt() / blas::nrm2(&w);
+            w.iter_mut().for_each(|w| *w *= scale);
+
+            Cholesky::new(k, &corr)?.mul_lower(&w).into_vec()
+        };
+
+        let mut next = vec![0.0; (k + 1) * (k + 1)];
+        for i in 0..k {
+            next[i * (k + 1)..i * (k + 1) + k].copy_from_slice(&corr[i * k..(i + 1) * k]);
+            next[i * (k + 1) + k] = z[i];
+            next[k * (k + 1) + i] = z[i];
+        }
+        next[k * (k + 1) + k] = 1.0;
+        corr = next;
+    }
+
+    Ok(corr.into_boxed_slice())
+}
+
+/// LU decomposition with partial pivoting of a square matrix
+pub(crate) struct LU {
+    n: usize,
+    lu: Matrix,
+    permutation: *mut gsl_permutation,
+}
+
+impl LU {
+    pub fn new(n: usize, a: &[f64]) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+            if a.len() != n * n {
+                return Err(GSLError::BadLength);
+            }
+
+            let permutation = gsl_permutation_alloc(n as u64);
+            assert!(!permutation.is_null());
+
+            let mut lu = LU {
+                n,
+                lu: Matrix::new(a.iter().copied(), n, n),
+                permutation,
+            };
+
+            let mut signum = 0;
+            GSLError::from_raw(gsl_linalg_LU_decomp(
+                lu.lu.as_gsl_mut(),
+                lu.permutation,
+                &mut signum,
+            ))?;
+
+            Ok(lu)
+        }
+    }
+
+    /// Fails with `GSLError::Domain` if the matrix is singular
+    pub fn solve(&self, b: &[f64]) -> Result<Box<[f64]>> {
+        unsafe {
+            assert_eq!(b.len(), self.n);
+
+            let gsl_b = gsl_vector::from(b);
+            let mut x = Vector::zeroes(self.n);
+            GSLError::from_raw(gsl_linalg_LU_solve(
+                self.lu.as_gsl(),
+                self.permutation,
+                &gsl_b,
+                x.as_gsl_mut(),
+            ))?;
+
+            Ok(x.to_boxed_slice())
+        }
+    }
+}
+
+impl fmt::Debug for LU {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LU")
+            .field("lu", &self.lu)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for LU {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_permutation_free(self.permutation);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for LU {}
+unsafe impl Sync for LU {}
+
+/// Cholesky decomposition `A = L L^T` of a symmetric positive definite matrix
+#[derive(Clone, Debug)]
+pub(crate) struct Cholesky {
+    n: usize,
+    l: Matrix,
+}
+
+impl Cholesky {
+    /// Fails with `GSLError::Domain` if the matrix is not positive definite
+    pub fn new(n: usize, a: &[f64]) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+            if a.len() != n * n {
+                return Err(GSLError::BadLength);
+            }
+
+            let mut l = Matrix::new(a.iter().copied(), n, n);
+            GSLError::from_raw(gsl_linalg_cholesky_decomp1(l.as_gsl_mut()))?;
+
+            Ok(Cholesky { n, l })
+        }
+    }
+
+    /// Solves `L x = b` by forward substitution
+    pub fn solve_lower(&self, b: &[f64]) -> Box<[f64]> {
+        assert_eq!(b.len(), self.n);
+
+        let mut x = b.to_vec();
+        for i in 0..self.n {
+            for j in 0..i {
+                x[i] -= self.l.elem_ij(i, j) * x[j];
+            }
+            x[i] /= self.l.elem_ij(i, i);
+        }
+        x.into_boxed_slice()
+    }
+
+    /// Factor `L` in the lower triangle
+    pub fn as_gsl(&self) -> *const gsl_matrix {
+        self.l.as_gsl()
+    }
+
+    /// Computes `L x`
+    pub fn mul_lower(&self, x: &[f64]) -> Box<[f64]> {
+        assert_eq!(x.len(), self.n);
+
+        (0..self.n)
+            .map(|i| (0..=i).map(|j| self.l.elem_ij(i, j) * x[j]).sum())
+            .collect()
+    }
+}
+
+#[test]
+fn test_norms() {
+    let a = [1.0, -2.0, 3.0, -4.0];
+    assert_eq!(norm_1(2, 2, &a), 6.0);
+    assert_eq!(norm_inf(2, 2, &a), 7.0);
+    approx::assert_abs_diff_eq!(norm_frobenius(2, 2, &a), 30.0f64.sqrt());
+
+    // Non-square
+    assert_eq!(norm_1(1, 3, &[1.0, -5.0, 2.0]), 5.0);
+    assert_eq!(norm_inf(1, 3, &[1.0, -5.0, 2.0]), 8.0);
+}
+
+#[test]
+fn test_solve() {
+    disable_error_handler();
+
+    let x = solve(2, &[2.0, 1.0, 1.0, 3.0], &[3.0, 5.0]).unwrap();
+    approx::assert_abs_diff_eq!(x[0], 0.8, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(x[1], 1.4, epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_cond_estimate() {
+    disable_error_handler();
+
+    // Diagonal matrices have condition number max / min
+    let a = [1.0, 0.0, 0.0, 0.0, 10.0, 0.0, 0.0, 0.0, 1.0e-3];
+    approx::assert_relative_eq!(
+        cond_1_estimate(3, &a).unwrap(),
+        1.0e4,
+        max_relative = 1.0e-9
+    );
+
+    // Hilbert matrices are notoriously ill conditioned, cond_1(H_6) ≈ 2.9e7
+    let n = 6;
+    let hilbert = (0..n * n)
+        .map(|k| 1.0 / ((k / n + k % n + 1) as f64))
+        .collect::<Vec<_>>();
+    let cond = cond_1_estimate(n, &hilbert).unwrap();
+    assert!(cond > 2.9e7 / 3.0 && cond < 2.9e7 * 1.01);
+}
+
+#[test]
+fn test_mahalanobis() {
+    disable_error_handler();
+
+    let cov = Matrix::from([[4.0, 0.0], [0.0, 9.0]]);
+    let d = mahalanobis(&Vector::new([3.0, 4.0]), &Vector::new([1.0, 1.0]), &cov).unwrap();
+    approx::assert_abs_diff_eq!(d, 2.0f64.sqrt(), epsilon = 1.0e-12);
+
+    // Correlated covariance, compare with an explicit solve
+    let cov = Matrix::from([[2.0, 0.5], [0.5, 1.0]]);
+    let x = Vector::new([1.0, -2.0]);
+    let y = solve(2, &cov, &x).unwrap();
+    let d = mahalanobis(&x, &Vector::zeroes(2), &cov).unwrap();
+    approx::assert_abs_diff_eq!(d * d, blas::dot(&x, &y).unwrap(), epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_whiten() {
+    disable_error_handler();
+
+    let m = 200;
+    let data = (0..m).flat_map(|i| {
+        let t = i as f64 * 0.1;
+        [
+            t.sin() + 3.0,
+            2.0 * t.sin() + 0.5 * t.cos(),
+            (3.0 * t).cos(),
+        ]
+    });
+    let data = Matrix::new(data, m, 3);
+
+    for method in [Whitening::Cholesky, Whitening::Pca] {
+        let z = whiten(&data, method).unwrap();
+        assert_eq!(z.size(), (m, 3));
+        for j in 0..3 {
+            let mean = (0..m).map(|i| z[i * 3 + j]).sum::<f64>() / m as f64;
+            approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 1.0e-10);
+            for k in 0..3 {
+                let cov = (0..m).map(|i| z[i * 3 + j] * z[i * 3 + k]).sum::<f64>() / (m - 1) as f64;
+                let expected = if j == k { 1.0 } else { 0.0 };
+                approx::assert_abs_diff_eq!(cov, expected, epsilon = 1.0e-8);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_random_orthogonal() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 5;
+    let q = random_orthogonal(n, &mut rng).unwrap();
+
+    // Q^T Q = I
+    for j in 0..n {
+        for k in 0..n {
+            let qtq = (0..n).map(|i| q[i * n + j] * q[i * n + k]).sum::<f64>();
+            let expected = if j == k { 1.0 } else { 0.0 };
+            approx::assert_abs_diff_eq!(qtq, expected, epsilon = 1.0e-12);
+        }
+    }
+
+    // The Haar measure gives E[Q_ij] = 0 and E[Q_ij^2] = 1 / n
+    let samples = 2000;
+    let mut mean = 0.0;
+    let mut mean_sq = 0.0;
+    for _ in 0..samples {
+        let q = random_orthogonal(n, &mut rng).unwrap();
+        mean += q[0] / samples as f64;
+        mean_sq += q[0] * q[0] / samples as f64;
+    }
+    approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.03);
+    approx::assert_abs_diff_eq!(mean_sq, 1.0 / n as f64, epsilon = 0.02);
+}
+
+#[test]
+fn test_random_correlation() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 6;
+
+    for eta in [0.5, 1.0, 10.0] {
+        for _ in 0..50 {
+            let corr = random_correlation(n, eta, &mut rng).unwrap();
+            for i in 0..n {
+                assert_eq!(corr[i * n + i], 1.0);
+                for j in 0..n {
+                    assert_eq!(corr[i * n + j], corr[j * n + i]);
+                    assert!(corr[i * n + j].abs() <= 1.0);
+                }
+            }
+            // Positive definite
+            Cholesky::new(n, &corr).unwrap();
+        }
+    }
+
+    // For the LKJ distribution, marginal correlations follow Beta(b, b) on [-1, 1]
+    // with b = eta - 1 + n / 2, which has variance 1 / (2b + 1)
+    let eta = 2.0;
+    let samples = 4000;
+    let b = eta - 1.0 + n as f64 / 2.0;
+    let var = (0..samples)
+        .map(|_| random_correlation(n, eta, &mut rng).unwrap()[n - 1].powi(2))
+        .sum::<f64>()
+        / samples as f64;
+    approx::assert_relative_eq!(var, 1.0 / (2.0 * b + 1.0), max_relative = 0.1);
+}
+
+#[test]
+fn test_gsl_suite() {
+    use crate::testing::*;
+
+    disable_error_handler();
+
+    // Ported from test_LU_solve in linalg/test.c: Hilbert and Vandermonde matrices
+    // with the right hand side (1, 2, ..., n)
+    let hilbert = |n: usize| {
+        (0..n * n)
+            .map(|k| 1.0 / (k / n + k % n + 1) as f64)
+            .collect::<Vec<_>>()
+    };
+    let vandermonde = |n: usize| {
+        (0..n * n)
+            .map(|k| ((k / n + 1) as f64).powi((n - k % n - 1) as i32))
+            .collect::<Vec<_>>()
+    };
+    let rhs = |n: usize| (1..=n).map(|i| i as f64).collect::<Vec<_>>();
+
+    let hilbert_solutions: [(&[f64], f64); 4] = [
+        (&[-8.0, 18.0], 8.0 * f64::EPSILON),
+        (&[27.0, -192.0, 210.0], 64.0 * f64::EPSILON),
+        (&[-64.0, 900.0, -2520.0, 1820.0], 2048.0 * f64::EPSILON),
+        (
+            &[
+                -1728.0,
+                245388.0,
+                -8528520.0,
+                127026900.0,
+                -1009008000.0,
+                4768571808.0,
+                -14202796608.0,
+                27336497760.0,
+                -33921201600.0,
+                26189163000.0,
+                -11437874448.0,
+                2157916488.0,
+            ],
+            0.5,
+        ),
+    ];
+    for (solution, tol) in hilbert_solutions {
+        let n = solution.len();
+        let x = solve(n, &hilbert(n), &rhs(n)).unwrap();
+        for (x, expected) in x.iter().zip(solution) {
+            assert_rel(*x, *expected, tol);
+        }
+    }
+
+    // The solution is the unit vector picking the linear term
+    for (n, tol) in [
+        (2, 8.0 * f64::EPSILON),
+        (3, 64.0 * f64::EPSILON),
+        (4, 1024.0 * f64::EPSILON),
+        (12, 0.05),
+    ] {
+        let x = solve(n, &vandermonde(n), &rhs(n)).unwrap();
+        for (i, x) in x.iter().enumerate() {
+            let expected = if i == n - 2 { 1.0 } else { 0.0 };
+            assert_abs(*x, expected, tol);
+        }
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Singular matrix
+    solve(2, &[1.0, 2.0, 2.0, 4.0], &[1.0, 1.0]).unwrap_err();
+
+    // Dimension mismatch
+    solve(2, &[1.0, 0.0, 0.0, 1.0], &[1.0]).unwrap_err();
+    cond_1_estimate(2, &[1.0, 0.0, 0.0]).unwrap_err();
+    let x = Vector::new([1.0, 2.0]);
+    let identity = Matrix::from([[1.0, 0.0], [0.0, 1.0]]);
+    mahalanobis(&x, &Vector::new([0.0]), &identity).unwrap_err();
+
+    // Covariance not positive definite
+    let cov = Matrix::from([[1.0, 2.0], [2.0, 1.0]]);
+    mahalanobis(&x, &Vector::zeroes(2), &cov).unwrap_err();
+
+    // Perfectly correlated variables cannot be whitened
+    let data = Matrix::from([[1.0, 2.0], [2.0, 4.0], [3.0, 6.0]]);
+    whiten(&data, Whitening::Cholesky).unwrap_err();
+    whiten(&data, Whitening::Pca).unwrap_err();
+
+    // Single observation
+    whiten(&Matrix::from([[1.0, 2.0]]), Whitening::Pca).unwrap_err();
+
+    // Empty or invalid shape parameter
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 0);
+    random_orthogonal(0, &mut rng).unwrap_err();
+    random_correlation(0, 1.0, &mut rng).unwrap_err();
+    random_correlation(3, 0.0, &mut rng).unwrap_err();
+}
@@ -17,8 +17,11 @@
 */
 
 use crate::bindings::*;
+use crate::linalg::LU;
+use crate::memory::Footprint;
 use crate::*;
 use drop_guard::guard;
+use std::fmt;
 
 /*
 
@@ -55,15 +58,9 @@ pub fn linear_fit<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
     p: usize,
     x: &[X],
     y: &[f64],
-    mut f: F,
+    f: F,
 ) -> Result<FitResult> {
     unsafe {
-        if p == 0 {
-            return Err(GSLError::Invalid);
-        }
-        if x.len() == 0 || y.len() == 0 {
-            return Err(GSLError::Invalid);
-        }
         if x.len() != y.len() {
             return Err(GSLError::Invalid);
         }
@@ -71,27 +68,20 @@ pub fn linear_fit<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
         // Amount of datapoints
         let n = x.len();
 
+        // Prepare linear system matrix: system_ij = f_j(x_i)
+        let system = design_matrix(p, x, f)?;
+
         // Allocate workspace
         let workspace = guard(gsl_multifit_linear_alloc(n as u64, p as u64), |workspace| {
             gsl_multifit_linear_free(workspace);
         });
         assert!(!workspace.is_null());
+        let _footprint = linear_footprint(n, p);
 
         // Prepare storage
         let mut c = Vector::zeroes(p);
         let mut covariance = Matrix::zeroes(p, p);
 
-        // Prepare linear system matrix: system_ij = f_j(x_i)
-        let data = x
-            .iter()
-            .map(|x| {
-                let mut p = vec![0.0; p];
-                f(x, &mut p)?;
-                Ok(p)
-            })
-            .collect::<Result<Vec<_>>>()?;
-        let system = Matrix::new(data.into_iter().flatten(), n, p);
-
         // Convert y data to GSL format
         let gsl_y = gsl_vector::from(y);
 
@@ -129,7 +119,511 @@ pub fn linear_fit<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
     }
 }
 
+/// Weighted linear least squares fit, minimizing `Σ w_i (y_i - Σ_j c_j f_j(x_i))^2`.
+/// For data with standard deviations `σ_i` the weights are `w_i = 1 / σ_i^2`.
+/// The covariance is then `(X^T W X)^-1`, and is not scaled by the scatter of the points.
+/// `residual_squared` is the weighted chi-square, and `mean` and `r_squared` are weighted as well.
+pub fn weighted_linear_fit<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+    p: usize,
+    x: &[X],
+    w: &[f64],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult> {
+    unsafe {
+        if x.len() != y.len() || x.len() != w.len() {
+            return Err(GSLError::Invalid);
+        }
+
+        let n = x.len();
+        let system = design_matrix(p, x, f)?;
+
+        let workspace = guard(gsl_multifit_linear_alloc(n as u64, p as u64), |workspace| {
+            gsl_multifit_linear_free(workspace);
+        });
+        assert!(!workspace.is_null());
+        let _footprint = linear_footprint(n, p);
+
+        let mut c = Vector::zeroes(p);
+        let mut covariance = Matrix::zeroes(p, p);
+
+        let gsl_w = gsl_vector::from(w);
+        let gsl_y = gsl_vector::from(y);
+
+        let mut chisq = 0.0f64;
+        GSLError::from_raw(gsl_multifit_wlinear(
+            system.as_gsl(),
+            &gsl_w,
+            &gsl_y,
+            c.as_gsl_mut(),
+            covariance.as_gsl_mut(),
+            &mut chisq,
+            *workspace,
+        ))?;
+
+        let mean = gsl_stats_wmean(w.as_ptr(), 1, y.as_ptr(), 1, n as u64);
+        let tss = gsl_stats_wtss_m(w.as_ptr(), 1, y.as_ptr(), 1, n as u64, mean);
+
+        let mut residuals = Vector::zeroes(n);
+        GSLError::from_raw(gsl_multifit_linear_residuals(
+            system.as_gsl(),
+            &gsl_y,
+            c.as_gsl(),
+            residuals.as_gsl_mut(),
+        ))?;
+
+        Ok(FitResult {
+            params: c.to_boxed_slice(),
+            covariance: covariance.to_boxed_slice(),
+            residuals: residuals.to_boxed_slice(),
+            residual_squared: chisq,
+            mean,
+            r_squared: 1.0 - chisq / tss,
+        })
+    }
+}
+
+/// `linear_fit` of the observations where `mask` is `true`, e.g. to leave out missing values.
+/// The residuals keep the positions of all observations, with NaN where `mask` is `false`.
+pub fn linear_fit_masked<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+    p: usize,
+    x: &[X],
+    y: &[f64],
+    mask: &[bool],
+    mut f: F,
+) -> Result<FitResult> {
+    if x.len() != y.len() || x.len() != mask.len() {
+        return Err(GSLError::Invalid);
+    }
+
+    let y = select(y, mask).into_iter().copied().collect::<Vec<_>>();
+    let mut fit = linear_fit(p, &select(x, mask), &y, |x, row| f(x, row))?;
+    fit.residuals = unselect(&fit.residuals, mask);
+    Ok(fit)
+}
+
+/// `weighted_linear_fit` of the observations where `mask` is `true`, see `linear_fit_masked`
+pub fn weighted_linear_fit_masked<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+    p: usize,
+    x: &[X],
+    w: &[f64],
+    y: &[f64],
+    mask: &[bool],
+    mut f: F,
+) -> Result<FitResult> {
+    if x.len() != y.len() || x.len() != w.len() || x.len() != mask.len() {
+        return Err(GSLError::Invalid);
+    }
+
+    let w = select(w, mask).into_iter().copied().collect::<Vec<_>>();
+    let y = select(y, mask).into_iter().copied().collect::<Vec<_>>();
+    let mut fit = weighted_linear_fit(p, &select(x, mask), &w, &y, |x, row| f(x, row))?;
+    fit.residuals = unselect(&fit.residuals, mask);
+    Ok(fit)
+}
+
+/// Footprint of `gsl_multifit_linear_alloc(n, p)`: the design matrix, its SVD, and work vectors
+fn linear_footprint(n: usize, p: usize) -> Footprint {
+    Footprint::of::<gsl_multifit_linear_workspace>(n * p + 2 * p * p + n + 3 * p)
+}
+
+/// Linear system matrix `X_ij = f_j(x_i)`
+pub(crate) fn design_matrix<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+    p: usize,
+    x: &[X],
+    mut f: F,
+) -> Result<Matrix> {
+    if p == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if x.is_empty() {
+        return Err(GSLError::Invalid);
+    }
+
+    let data = x
+        .iter()
+        .map(|x| {
+            let mut p = vec![0.0; p];
+            f(x, &mut p)?;
+            Ok(p)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Matrix::new(data.into_iter().flatten(), x.len(), p))
+}
+
+/// Fit of a straight line `y = c0 + c1 x`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineFit {
+    pub c0: f64,
+    pub c1: f64,
+    pub cov00: f64,
+    pub cov01: f64,
+    pub cov11: f64,
+    /// Sum of squared residuals, weighted for `fit_line_weighted`
+    pub chisq: f64,
+}
+
+impl LineFit {
+    /// Value of the line at `x`, with the standard deviation following from the covariance
+    pub fn eval(&self, x: f64) -> ValWithError<f64> {
+        unsafe {
+            let mut val = 0.0f64;
+            let mut err = 0.0f64;
+            gsl_fit_linear_est(
+                x, self.c0, self.c1, self.cov00, self.cov01, self.cov11, &mut val, &mut err,
+            );
+            ValWithError { val, err }
+        }
+    }
+}
+
+/// Least squares fit of a straight line `y = c0 + c1 x`.
+/// The covariance is estimated from the scatter of the points around the line.
+pub fn fit_line(x: &[f64], y: &[f64]) -> Result<LineFit> {
+    unsafe {
+        if x.len() != y.len() {
+            return Err(GSLError::BadLength);
+        }
+        if x.len() < 2 {
+            return Err(GSLError::Invalid);
+        }
+
+        let mut fit = LineFit {
+            c0: 0.0,
+            c1: 0.0,
+            cov00: 0.0,
+            cov01: 0.0,
+            cov11: 0.0,
+            chisq: 0.0,
+        };
+        GSLError::from_raw(gsl_fit_linear(
+            x.as_ptr(),
+            1,
+            y.as_ptr(),
+            1,
+            x.len() as u64,
+            &mut fit.c0,
+            &mut fit.c1,
+            &mut fit.cov00,
+            &mut fit.cov01,
+            &mut fit.cov11,
+            &mut fit.chisq,
+        ))?;
+
+        Ok(fit)
+    }
+}
+
+/// Weighted least squares fit of a straight line `y = c0 + c1 x`, with weights `w_i = 1 / σ_i^2`.
+/// The covariance is computed from the weights only.
+pub fn fit_line_weighted(x: &[f64], w: &[f64], y: &[f64]) -> Result<LineFit> {
+    unsafe {
+        if x.len() != y.len() || x.len() != w.len() {
+            return Err(GSLError::BadLength);
+        }
+        if x.len() < 2 {
+            return Err(GSLError::Invalid);
+        }
+
+        let mut fit = LineFit {
+            c0: 0.0,
+            c1: 0.0,
+            cov00: 0.0,
+            cov01: 0.0,
+            cov11: 0.0,
+            chisq: 0.0,
+        };
+        GSLError::from_raw(gsl_fit_wlinear(
+            x.as_ptr(),
+            1,
+            w.as_ptr(),
+            1,
+            y.as_ptr(),
+            1,
+            x.len() as u64,
+            &mut fit.c0,
+            &mut fit.c1,
+            &mut fit.cov00,
+            &mut fit.cov01,
+            &mut fit.cov11,
+            &mut fit.chisq,
+        ))?;
+
+        Ok(fit)
+    }
+}
+
+/// Regularized linear least squares, minimizing `||y - X c||^2 + λ^2 ||L c||^2`
+/// for a diagonal regularization matrix `L` (ridge regression for `L = I`).
+///
+/// The singular value decomposition of the system is computed once,
+/// after which solutions for many `λ` are cheap.
+pub struct RidgeRegression {
+    n: usize,
+    p: usize,
+    system: Matrix,
+    y: Box<[f64]>,
+    /// System and data transformed to standard form, `X L^-1`
+    standard_system: Matrix,
+    standard_y: Vector,
+    l: Option<Vector>,
+    workspace: *mut gsl_multifit_linear_workspace,
+}
+
+impl RidgeRegression {
+    /// Ridge regression, with `L = I`
+    pub fn new<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+        p: usize,
+        x: &[X],
+        y: &[f64],
+        f: F,
+    ) -> Result<Self> {
+        Self::build(p, x, y, None, f)
+    }
+
+    /// Tikhonov regularization with diagonal `L`, which allows penalizing parameters individually.
+    /// All elements of `l` have to be nonzero.
+    pub fn with_diagonal<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+        p: usize,
+        x: &[X],
+        y: &[f64],
+        l: &[f64],
+        f: F,
+    ) -> Result<Self> {
+        if l.len() != p {
+            return Err(GSLError::BadLength);
+        }
+        if l.iter().any(|&l| l == 0.0 || !l.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Self::build(p, x, y, Some(l), f)
+    }
+
+    fn build<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+        p: usize,
+        x: &[X],
+        y: &[f64],
+        l: Option<&[f64]>,
+        f: F,
+    ) -> Result<Self> {
+        unsafe {
+            if x.len() != y.len() {
+                return Err(GSLError::BadLength);
+            }
+
+            // At least one degree of freedom is needed for the covariance
+            let n = x.len();
+            let system = design_matrix(p, x, f)?;
+            if n <= p {
+                return Err(GSLError::Invalid);
+            }
+
+            let workspace = gsl_multifit_linear_alloc(n as u64, p as u64);
+            assert!(!workspace.is_null());
+
+            let mut regression = RidgeRegression {
+                n,
+                p,
+                standard_system: system.clone(),
+                standard_y: Vector::new(y.iter().copied()),
+                system,
+                y: y.into(),
+                l: l.map(|l| Vector::new(l.iter().copied())),
+                workspace,
+            };
+
+            if let Some(l) = &regression.l {
+                let gsl_y = gsl_vector::from(y);
+                GSLError::from_raw(gsl_multifit_linear_stdform1(
+                    l.as_gsl(),
+                    regression.system.as_gsl(),
+                    &gsl_y,
+                    regression.standard_system.as_gsl_mut(),
+                    regression.standard_y.as_gsl_mut(),
+                    regression.workspace,
+                ))?;
+            }
+
+            GSLError::from_raw(gsl_multifit_linear_svd(
+                regression.standard_system.as_gsl(),
+                regression.workspace,
+            ))?;
+
+            Ok(regression)
+        }
+    }
+
+    /// Solution for regularization parameter `λ >= 0`.
+    /// The covariance `s^2 (A + λ^2 I)^-1 A (A + λ^2 I)^-1` with `A = X^T X` (in standard form)
+    /// uses `s^2 = χ^2 / (n - p)` like `linear_fit`, which reduces to it for `λ = 0`.
+    pub fn solve(&mut self, lambda: f64) -> Result<FitResult> {
+        unsafe {
+            if !(lambda >= 0.0 && lambda.is_finite()) {
+                return Err(GSLError::Domain);
+            }
+
+            let p = self.p;
+            let mut standard_c = Vector::zeroes(p);
+            let mut rnorm = 0.0f64;
+            let mut snorm = 0.0f64;
+            GSLError::from_raw(gsl_multifit_linear_solve(
+                lambda,
+                self.standard_system.as_gsl(),
+                self.standard_y.as_gsl(),
+                standard_c.as_gsl_mut(),
+                &mut rnorm,
+                &mut snorm,
+                self.workspace,
+            ))?;
+
+            // Transform back to the original parameters, c = L^-1 c_s
+            let mut c = standard_c.clone();
+            if let Some(l) = &self.l {
+                GSLError::from_raw(gsl_multifit_linear_genform1(
+                    l.as_gsl(),
+                    standard_c.as_gsl(),
+                    c.as_gsl_mut(),
+                    self.workspace,
+                ))?;
+            }
+
+            let gsl_y = gsl_vector::from(self.y.as_ref());
+            let mut residuals = Vector::zeroes(self.n);
+            GSLError::from_raw(gsl_multifit_linear_residuals(
+                self.system.as_gsl(),
+                &gsl_y,
+                c.as_gsl(),
+                residuals.as_gsl_mut(),
+            ))?;
+            let chisq = blas::dot(&residuals, &residuals)?;
+
+            // Covariance in standard form, then scaled by L^-1 on both sides
+            let columns = (0..p)
+                .map(|j| self.standard_system.col(j))
+                .collect::<Vec<_>>();
+            let a = (0..p * p)
+                .map(|ij| blas::dot(&columns[ij / p], &columns[ij % p]))
+                .collect::<Result<Vec<_>>>()?;
+            let mut m = a.clone();
+            for i in 0..p {
+                m[i * p + i] += lambda * lambda;
+            }
+            let lu = LU::new(p, &m)?;
+
+            // B = M^-1 A is computed by columns, which are the rows of the symmetric A
+            let b = (0..p)
+                .map(|j| lu.solve(&a[j * p..(j + 1) * p]))
+                .collect::<Result<Vec<_>>>()?;
+            // M^-1 A M^-1 = M^-1 B^T, whose columns are the rows of B^T
+            let s2 = chisq / (self.n - p) as f64;
+            let mut covariance = vec![0.0; p * p];
+            for i in 0..p {
+                let bt_col = (0..p).map(|k| b[k][i]).collect::<Vec<_>>();
+                let col = lu.solve(&bt_col)?;
+                for j in 0..p {
+                    covariance[j * p + i] = s2 * col[j];
+                }
+            }
+            if let Some(l) = &self.l {
+                for i in 0..p {
+                    for j in 0..p {
+                        covariance[i * p + j] /= l[i] * l[j];
+                    }
+                }
+            }
+
+            let mean = stats::mean(&self.y);
+            let tss = gsl_stats_tss_m(self.y.as_ptr(), 1, self.n as u64, mean);
+
+            Ok(FitResult {
+                params: c.to_boxed_slice(),
+                covariance: covariance.into_boxed_slice(),
+                residuals: residuals.to_boxed_slice(),
+                residual_squared: chisq,
+                mean,
+                r_squared: 1.0 - chisq / tss,
+            })
+        }
+    }
+
+    /// L-curve of `n >= 3` regularization parameters spanning the singular values of the system,
+    /// with its corner of maximum curvature as a suggested `λ`
+    pub fn lcurve(&mut self, n: usize) -> Result<LCurve> {
+        unsafe {
+            if n < 3 {
+                return Err(GSLError::Invalid);
+            }
+
+            let mut lambda = Vector::zeroes(n);
+            let mut rho = Vector::zeroes(n);
+            let mut eta = Vector::zeroes(n);
+            GSLError::from_raw(gsl_multifit_linear_lcurve(
+                self.standard_y.as_gsl(),
+                lambda.as_gsl_mut(),
+                rho.as_gsl_mut(),
+                eta.as_gsl_mut(),
+                self.workspace,
+            ))?;
+
+            let mut corner = 0;
+            GSLError::from_raw(gsl_multifit_linear_lcorner(
+                rho.as_gsl(),
+                eta.as_gsl(),
+                &mut corner,
+            ))?;
+
+            Ok(LCurve {
+                lambda: lambda.to_boxed_slice(),
+                residual_norm: rho.to_boxed_slice(),
+                solution_norm: eta.to_boxed_slice(),
+                corner: corner as usize,
+            })
+        }
+    }
+}
+
+impl fmt::Debug for RidgeRegression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RidgeRegression")
+            .field("n", &self.n)
+            .field("p", &self.p)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for RidgeRegression {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_multifit_linear_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for RidgeRegression {}
+unsafe impl Sync for RidgeRegression {}
+
+/// Trade-off between the residual norm `||y - X c||` and the solution norm `||L c||`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LCurve {
+    pub lambda: Box<[f64]>,
+    pub residual_norm: Box<[f64]>,
+    pub solution_norm: Box<[f64]>,
+    /// Index of the point of maximum curvature
+    pub corner: usize,
+}
+
+impl LCurve {
+    pub fn corner_lambda(&self) -> f64 {
+        self.lambda[self.corner]
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FitResult {
     pub params: Box<[f64]>,
     pub covariance: Box<[f64]>,
@@ -140,6 +634,15 @@ pub struct FitResult {
 }
 
 impl FitResult {
+    pub fn n_params(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Shape `(p, p)` of the row major covariance matrix
+    pub fn covariance_shape(&self) -> (usize, usize) {
+        (self.n_params(), self.n_params())
+    }
+
     pub fn covariance(&self, i: usize, j: usize) -> f64 {
         (self.covariance)[i * self.params.len() + j]
     }
@@ -149,6 +652,13 @@ impl FitResult {
     }
 }
 
+/// Fitted parameters
+impl From<FitResult> for Vec<f64> {
+    fn from(fit: FitResult) -> Self {
+        fit.params.into_vec()
+    }
+}
+
 /*
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct FitResultP<const P: usize> {
@@ -190,6 +700,19 @@ fn test_fit_1() {
     approx::assert_abs_diff_eq!(fit.params[2], c, epsilon = 1.0e-6);
 }
 
+#[test]
+fn test_conversions() {
+    disable_error_handler();
+
+    let x = (0..100).map(|x| x as f64 / 10.0).collect::<Vec<_>>();
+    let y = x.iter().map(|&x| 1.0 + 2.0 * x).collect::<Vec<_>>();
+    let fit = linear_fit_p(&x, &y, polynomial_basis::<3>).unwrap();
+
+    assert_eq!(fit.n_params(), 3);
+    assert_eq!(fit.covariance_shape(), (3, 3));
+    assert_eq!(Vec::from(fit.clone()), &*fit.params);
+}
+
 #[test]
 fn test_fit_2() {
     disable_error_handler();
@@ -222,6 +745,135 @@ fn test_fit_2() {
     approx::assert_abs_diff_eq!(fit.params[2], c, epsilon = 1.0e-2);
 }
 
+#[test]
+fn test_fit_masked() {
+    disable_error_handler();
+
+    let x = [0.0, 1.0, 2.0, 3.0, 4.0];
+    let y = [1.0, 3.0, f64::NAN, 7.0, 100.0];
+    let mask = [true, true, false, true, false];
+
+    let fit = linear_fit_masked(2, &x, &y, &mask, |&x, row| {
+        row.copy_from_slice(&[1.0, x]);
+        Ok(())
+    })
+    .unwrap();
+    approx::assert_abs_diff_eq!(*fit.params, [1.0, 2.0], epsilon = 1.0e-12);
+    assert_eq!(fit.residuals.len(), 5);
+    assert!(fit.residuals[2].is_nan() && fit.residuals[4].is_nan());
+    approx::assert_abs_diff_eq!(fit.residuals[3], 0.0, epsilon = 1.0e-12);
+
+    let w = [1.0, 2.0, 1.0, 0.5, 1.0];
+    let fit = weighted_linear_fit_masked(2, &x, &w, &y, &mask, |&x, row| {
+        row.copy_from_slice(&[1.0, x]);
+        Ok(())
+    })
+    .unwrap();
+    approx::assert_abs_diff_eq!(*fit.params, [1.0, 2.0], epsilon = 1.0e-12);
+
+    // Mask of the wrong length
+    linear_fit_masked(2, &x, &y, &mask[..4], |_, _| Ok(())).unwrap_err();
+}
+
+#[test]
+fn test_fit_line() {
+    disable_error_handler();
+    fastrand::seed(1);
+
+    let x = (0..50).map(|x| x as f64 / 5.0).collect::<Vec<_>>();
+    let y = x
+        .iter()
+        .map(|&x| 1.0 + 2.0 * x + 0.1 * (fastrand::f64() * 2.0 - 1.0))
+        .collect::<Vec<_>>();
+
+    // Same as a general linear fit
+    let line = fit_line(&x, &y).unwrap();
+    let fit = linear_fit_p(&x, &y, polynomial_basis::<2>).unwrap();
+    approx::assert_abs_diff_eq!(line.c0, fit.params[0], epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(line.c1, fit.params[1], epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(line.cov01, fit.covariance(0, 1), epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(line.chisq, fit.residual_squared, epsilon = 1.0e-10);
+
+    let estimate = line.eval(0.0);
+    approx::assert_abs_diff_eq!(estimate.val, line.c0);
+    approx::assert_abs_diff_eq!(estimate.err, line.cov00.sqrt(), epsilon = 1.0e-14);
+
+    // Weighted, with the second half of the data less reliable
+    let w = (0..50)
+        .map(|i| if i < 25 { 4.0 } else { 1.0 })
+        .collect::<Vec<_>>();
+    let line = fit_line_weighted(&x, &w, &y).unwrap();
+    let fit = weighted_linear_fit(2, &x, &w, &y, |&x, p| {
+        p.copy_from_slice(&[1.0, x]);
+        Ok(())
+    })
+    .unwrap();
+    approx::assert_abs_diff_eq!(line.c0, fit.params[0], epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(line.c1, fit.params[1], epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(line.cov00, fit.covariance(0, 0), epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(line.chisq, fit.residual_squared, epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(line.c1, 2.0, epsilon = 1.0e-2);
+}
+
+#[test]
+fn test_ridge_regression() {
+    disable_error_handler();
+    fastrand::seed(2);
+
+    let x = (0..100).map(|x| x as f64 / 10.0).collect::<Vec<_>>();
+    let y = x
+        .iter()
+        .map(|&x| 10.0 + 2.0 * x + 2.0 * x * x + 0.1 * (fastrand::f64() * 2.0 - 1.0))
+        .collect::<Vec<_>>();
+    let basis = |&x: &f64, p: &mut [f64]| {
+        p.copy_from_slice(&[1.0, x, x * x]);
+        Ok(())
+    };
+
+    // No regularization reduces to ordinary least squares
+    let fit = linear_fit(3, &x, &y, basis).unwrap();
+    let mut ridge = RidgeRegression::new(3, &x, &y, basis).unwrap();
+    let mut tikhonov = RidgeRegression::with_diagonal(3, &x, &y, &[0.1, 1.0, 10.0], basis).unwrap();
+    for regression in [&mut ridge, &mut tikhonov] {
+        let unregularized = regression.solve(0.0).unwrap();
+        for i in 0..3 {
+            approx::assert_abs_diff_eq!(unregularized.params[i], fit.params[i], epsilon = 1.0e-8);
+            for j in 0..3 {
+                approx::assert_relative_eq!(
+                    unregularized.covariance(i, j),
+                    fit.covariance(i, j),
+                    max_relative = 1.0e-6
+                );
+            }
+        }
+    }
+
+    // Regularization shrinks the solution and increases the residuals
+    let weak = ridge.solve(1.0).unwrap();
+    let strong = ridge.solve(100.0).unwrap();
+    assert!(blas::nrm2(&strong.params) < blas::nrm2(&weak.params));
+    assert!(strong.residual_squared > weak.residual_squared);
+
+    // Along the L-curve the residual norm increases and the solution norm decreases with λ
+    let lcurve = ridge.lcurve(20).unwrap();
+    assert_eq!(lcurve.lambda.len(), 20);
+    assert!(lcurve.corner < 20);
+    let mut points = (0..20)
+        .map(|i| {
+            (
+                lcurve.lambda[i],
+                lcurve.residual_norm[i],
+                lcurve.solution_norm[i],
+            )
+        })
+        .collect::<Vec<_>>();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for w in points.windows(2) {
+        assert!(w[1].1 >= w[0].1 * (1.0 - 1.0e-12));
+        assert!(w[1].2 <= w[0].2 * (1.0 + 1.0e-12));
+    }
+}
+
 #[test]
 fn test_invalid_params() {
     disable_error_handler();
@@ -235,4 +887,42 @@ fn test_invalid_params() {
 
     // No params
     linear_fit(0, &[1.0, 2.0, 3.0], &[0.0, 0.0, 0.0], |&_, _| Ok(())).unwrap_err();
+
+    // Length mismatch
+    fit_line(&[1.0, 2.0, 3.0], &[1.0, 2.0]).unwrap_err();
+    fit_line_weighted(&[1.0, 2.0], &[1.0], &[1.0, 2.0]).unwrap_err();
+    weighted_linear_fit(1, &[1.0, 2.0], &[1.0], &[1.0, 2.0], |&_, p| {
+        p[0] = 1.0;
+        Ok(())
+    })
+    .unwrap_err();
+
+    // Too few points for the covariance
+    RidgeRegression::new(2, &[1.0, 2.0], &[1.0, 2.0], |&x, p| {
+        p.copy_from_slice(&[1.0, x]);
+        Ok(())
+    })
+    .unwrap_err();
+
+    // Singular regularization matrix
+    RidgeRegression::with_diagonal(
+        2,
+        &[1.0, 2.0, 3.0],
+        &[1.0, 2.0, 3.0],
+        &[1.0, 0.0],
+        |&x, p| {
+            p.copy_from_slice(&[1.0, x]);
+            Ok(())
+        },
+    )
+    .unwrap_err();
+
+    // Negative regularization parameter
+    let mut ridge = RidgeRegression::new(1, &[1.0, 2.0], &[1.0, 2.0], |&_, p| {
+        p[0] = 1.0;
+        Ok(())
+    })
+    .unwrap();
+    ridge.solve(-1.0).unwrap_err();
+    ridge.lcurve(2).unwrap_err();
 }
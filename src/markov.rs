@@ -0,0 +1,272 @@
+/*
+    markov.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::linalg;
+use crate::randist::DiscreteDistribution;
+use crate::rng::Rng;
+use crate::*;
+
+/*
+
+    Transition matrices are row major and row stochastic: `p[i * n + j]` is the probability
+    to go from state `i` to state `j`, and distributions over the states are row vectors.
+
+    The stationary distribution solves `π P = π` with `Σ π = 1`, where the normalization
+    replaces one of the dependent equations of `(P^T - I) π = 0`. The system is singular
+    if the chain has more than one closed class, then there is no unique stationary distribution.
+
+    A continuous time chain with generator `Q`, with nonnegative rates off the diagonal and rows summing to zero,
+    has the transition matrix `exp(Q t)` after time `t`.
+
+*/
+
+/// Tolerance of the row sums of transition matrices and generators
+const ROW_SUM_TOLERANCE: f64 = 1.0e-10;
+
+/// Discrete time Markov chain on the states `0..n`
+#[derive(Clone, Debug)]
+pub struct MarkovChain {
+    n: usize,
+    p: Box<[f64]>,
+    rows: Vec<DiscreteDistribution>,
+}
+
+impl MarkovChain {
+    /// Chain with the `n` by `n` transition matrix `p`.
+    /// Fails with `GSLError::Domain` if `p` has negative elements or rows that don't sum to one.
+    pub fn new(n: usize, p: &[f64]) -> Result<Self> {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if p.len() != n * n {
+            return Err(GSLError::BadLength);
+        }
+        for row in p.chunks_exact(n) {
+            let sum = row.iter().sum::<f64>();
+            if row.iter().any(|x| x.is_nan() || *x < 0.0) || (sum - 1.0).abs() > ROW_SUM_TOLERANCE {
+                return Err(GSLError::Domain);
+            }
+        }
+
+        Ok(MarkovChain {
+            n,
+            p: p.into(),
+            rows: p
+                .chunks_exact(n)
+                .map(DiscreteDistribution::new)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn n_states(&self) -> usize {
+        self.n
+    }
+
+    pub fn transition_matrix(&self) -> &[f64] {
+        &self.p
+    }
+
+    /// Distribution `π` with `π P = π`.
+    /// Fails with `GSLError::Domain` if it isn't unique.
+    pub fn stationary(&self) -> Result<Box<[f64]>> {
+        let n = self.n;
+        let mut a = vec![0.0; n * n];
+        for i in 0..n - 1 {
+            for j in 0..n {
+                a[i * n + j] = self.p[j * n + i];
+            }
+            a[i * n + i] -= 1.0;
+        }
+        a[(n - 1) * n..].fill(1.0);
+
+        let mut b = vec![0.0; n];
+        b[n - 1] = 1.0;
+        linalg::solve(n, &a, &b)
+    }
+
+    /// Transition matrix `P^steps` of `steps` steps, by repeated squaring
+    pub fn power(&self, steps: usize) -> Result<Box<[f64]>> {
+        let n = self.n;
+        let mut result = (0..n * n)
+            .map(|k| if k % (n + 1) == 0 { 1.0 } else { 0.0 })
+            .collect::<Box<[f64]>>();
+        let mut square = self.p.clone();
+        let mut steps = steps;
+        while steps > 0 {
+            if steps % 2 == 1 {
+                result = multiply(n, &result, &square)?;
+            }
+            steps /= 2;
+            if steps > 0 {
+                square = multiply(n, &square, &square)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Distribution after `steps` steps, starting from the distribution `initial`
+    pub fn distribution_after(&self, initial: &[f64], steps: usize) -> Result<Box<[f64]>> {
+        let n = self.n;
+        if initial.len() != n {
+            return Err(GSLError::BadLength);
+        }
+        let power = self.power(steps)?;
+        Ok((0..n)
+            .map(|j| (0..n).map(|i| initial[i] * power[i * n + j]).sum())
+            .collect())
+    }
+
+    /// States of a trajectory of `steps` steps starting in `start`, including `start`
+    pub fn simulate(&self, start: usize, steps: usize, rng: &mut Rng) -> Result<Vec<usize>> {
+        if start >= self.n {
+            return Err(GSLError::Invalid);
+        }
+        let mut states = Vec::with_capacity(steps + 1);
+        let mut state = start;
+        states.push(state);
+        for _ in 0..steps {
+            state = self.rows[state].sample(rng);
+            states.push(state);
+        }
+        Ok(states)
+    }
+}
+
+/// Transition matrix `exp(Q t)` after time `t >= 0` of a continuous time chain with the `n` by `n` generator `q`.
+/// Fails with `GSLError::Domain` if `q` has negative rates or rows that don't sum to zero.
+pub fn continuous_transition(n: usize, q: &[f64], t: f64) -> Result<Box<[f64]>> {
+    unsafe {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if q.len() != n * n {
+            return Err(GSLError::BadLength);
+        }
+        if !(t >= 0.0 && t.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        let scale = q.iter().fold(1.0f64, |m, x| m.max(x.abs()));
+        for (i, row) in q.chunks_exact(n).enumerate() {
+            let negative = row
+                .iter()
+                .enumerate()
+                .any(|(j, x)| x.is_nan() || (j != i && *x < 0.0));
+            if negative || row.iter().sum::<f64>().abs() > ROW_SUM_TOLERANCE * scale {
+                return Err(GSLError::Domain);
+            }
+        }
+
+        let a = Matrix::new(q.iter().map(|x| x * t), n, n);
+        let mut exp = Matrix::zeroes(n, n);
+        GSLError::from_raw(gsl_linalg_exponential_ss(
+            a.as_gsl(),
+            exp.as_gsl_mut(),
+            GSL_PREC_DOUBLE,
+        ))?;
+        Ok(exp.to_boxed_slice())
+    }
+}
+
+/// Product of two `n` by `n` matrices
+fn multiply(n: usize, a: &[f64], b: &[f64]) -> Result<Box<[f64]>> {
+    unsafe {
+        let a = Matrix::new(a.iter().copied(), n, n);
+        let b = Matrix::new(b.iter().copied(), n, n);
+        let mut c = Matrix::zeroes(n, n);
+        GSLError::from_raw(gsl_blas_dgemm(
+            CBLAS_TRANSPOSE_CblasNoTrans,
+            CBLAS_TRANSPOSE_CblasNoTrans,
+            1.0,
+            a.as_gsl(),
+            b.as_gsl(),
+            0.0,
+            c.as_gsl_mut(),
+        ))?;
+        Ok(c.to_boxed_slice())
+    }
+}
+
+#[test]
+fn test_markov_chain() {
+    disable_error_handler();
+
+    // Two states with switching probabilities a and b have the stationary distribution (b, a) / (a + b)
+    let (a, b) = (0.3, 0.1);
+    let chain = MarkovChain::new(2, &[1.0 - a, a, b, 1.0 - b]).unwrap();
+    let pi = chain.stationary().unwrap();
+    approx::assert_abs_diff_eq!(pi[0], 0.25, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(pi[1], 0.75, epsilon = 1.0e-12);
+
+    // P^k converges to the stationary distribution as (1 - a - b)^k
+    let p = chain.power(3).unwrap();
+    let decay = (1.0 - a - b).powi(3);
+    approx::assert_abs_diff_eq!(p[0], 0.25 + 0.75 * decay, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(p[2], 0.25 - 0.25 * decay, epsilon = 1.0e-12);
+    assert_eq!(&*chain.power(0).unwrap(), &[1.0, 0.0, 0.0, 1.0]);
+    let d = chain.distribution_after(&[1.0, 0.0], 100).unwrap();
+    approx::assert_abs_diff_eq!(d[0], 0.25, epsilon = 1.0e-12);
+
+    // The fraction of time spent in a state approaches its stationary probability
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let states = chain.simulate(0, 100_000, &mut rng).unwrap();
+    assert_eq!(states.len(), 100_001);
+    assert_eq!(states[0], 0);
+    let fraction = states.iter().filter(|&&s| s == 1).count() as f64 / states.len() as f64;
+    approx::assert_abs_diff_eq!(fraction, 0.75, epsilon = 0.01);
+}
+
+#[test]
+fn test_continuous_transition() {
+    disable_error_handler();
+
+    // Switching from 0 to 1 at rate a and back at rate b
+    let (a, b) = (2.0, 1.0);
+    let q = [-a, a, b, -b];
+    let p = continuous_transition(2, &q, 0.5).unwrap();
+    let decay = (-(a + b) * 0.5f64).exp();
+    approx::assert_abs_diff_eq!(p[0], (b + a * decay) / (a + b), epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(p[1], a * (1.0 - decay) / (a + b), epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(p[3], (a + b * decay) / (a + b), epsilon = 1.0e-12);
+    assert!(MarkovChain::new(2, &p).is_ok());
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Not a transition matrix
+    MarkovChain::new(0, &[]).unwrap_err();
+    MarkovChain::new(2, &[1.0, 0.0, 1.0]).unwrap_err();
+    MarkovChain::new(2, &[0.5, 0.6, 0.5, 0.5]).unwrap_err();
+    MarkovChain::new(2, &[1.5, -0.5, 0.5, 0.5]).unwrap_err();
+
+    // Two closed classes
+    let chain = MarkovChain::new(2, &[1.0, 0.0, 0.0, 1.0]).unwrap();
+    chain.stationary().unwrap_err();
+
+    // Initial distribution or start of the wrong size
+    chain.distribution_after(&[1.0], 1).unwrap_err();
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 2);
+    chain.simulate(2, 10, &mut rng).unwrap_err();
+
+    // Not a generator, or a negative time
+    continuous_transition(2, &[-1.0, 1.0, 1.0, 1.0], 1.0).unwrap_err();
+    continuous_transition(2, &[1.0, -1.0, 1.0, -1.0], 1.0).unwrap_err();
+    continuous_transition(2, &[-1.0, 1.0, 1.0, -1.0], -1.0).unwrap_err();
+}
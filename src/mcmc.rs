@@ -0,0 +1,719 @@
+/*
+    mcmc.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::deriv;
+use crate::fft::RealFft;
+use crate::rng::{Reproducibility, Rng};
+use crate::stats;
+use crate::*;
+use std::fmt;
+
+/*
+
+    Markov chain Monte Carlo samplers of an unnormalized log density.
+    Every call to `sample` advances the chain by one step and returns the new state,
+    so consecutive samples are correlated. `chain` takes `n` steps with a stream of a `Reproducibility` context.
+
+    The slice sampler updates one coordinate at a time with the stepping out
+    and shrinkage procedures of Neal (2003), which needs no tuning beyond a rough width per coordinate.
+
+    Hamiltonian Monte Carlo integrates Hamilton's equations for a unit mass with the leapfrog scheme,
+    using central difference gradients of the log density.
+    The step size can be tuned during warmup with the dual averaging of Hoffman and Gelman (2014).
+
+    The convergence diagnostics follow Vehtari et al. (2021). They take a matrix with one chain per row,
+    and split every chain in half so a trend within a chain shows up as
+    disagreement between chains. Bulk diagnostics replace the draws by their normal scores,
+    `z = Φ^-1((r - 3/8) / (S + 1/4))` for rank `r` out of `S` draws, which makes them work for heavy tails.
+    The effective sample size sums the autocorrelations, computed with a zero padded FFT,
+    over Geyer's initial monotone sequence.
+
+*/
+
+/// Maximum number of stepping out or shrinkage steps of the slice sampler for one coordinate
+const MAX_SLICE_STEPS: usize = 1000;
+
+/// Initial step size of the numerical gradients
+const GRADIENT_STEP: f64 = 1.0e-4;
+
+/// Slice sampler with a separate slice width for every coordinate
+pub struct SliceSampler<F> {
+    log_pdf: F,
+    x: Box<[f64]>,
+    log_p: f64,
+    width: Box<[f64]>,
+}
+
+impl<F: FnMut(&[f64]) -> f64> SliceSampler<F> {
+    /// The log density needs to be finite at the starting point `x0`.
+    /// The widths are the initial sizes of the slice in every direction,
+    /// about the scale of the distribution.
+    pub fn new(mut log_pdf: F, x0: &[f64], width: &[f64]) -> Result<Self> {
+        if x0.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        if width.len() != x0.len() {
+            return Err(GSLError::BadLength);
+        }
+        if width.iter().any(|&w| !(w > 0.0 && w.is_finite())) {
+            return Err(GSLError::Domain);
+        }
+
+        let log_p = log_pdf(x0);
+        if !log_p.is_finite() {
+            return Err(GSLError::Domain);
+        }
+
+        Ok(SliceSampler {
+            log_pdf,
+            x: x0.into(),
+            log_p,
+            width: width.into(),
+        })
+    }
+
+    pub fn state(&self) -> &[f64] {
+        &self.x
+    }
+
+    /// Updates every coordinate once.
+    /// Fails with `GSLError::MaxIteration` if the slice doesn't close, as for an improper density.
+    pub fn sample(&mut self, rng: &mut Rng) -> Result<&[f64]> {
+        for i in 0..self.x.len() {
+            self.update(rng, i)?;
+        }
+        Ok(&self.x)
+    }
+
+    /// `n` samples drawn from the stream `stream` of `context`, one sample per row
+    pub fn chain(
+        &mut self,
+        n: usize,
+        context: &mut Reproducibility,
+        stream: &str,
+    ) -> Result<Matrix> {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        let mut rng = context.rng(stream);
+        let mut samples = Vec::with_capacity(n * self.x.len());
+        for _ in 0..n {
+            samples.extend_from_slice(self.sample(&mut rng)?);
+        }
+        Ok(Matrix::new(samples, n, self.x.len()))
+    }
+
+    fn update(&mut self, rng: &mut Rng, i: usize) -> Result<()> {
+        let x0 = self.x[i];
+        let width = self.width[i];
+
+        // Height of the slice
+        let level = self.log_p + rng.uniform_pos().ln();
+
+        // Step out from a randomly placed interval
+        let mut lower = x0 - width * rng.uniform();
+        let mut upper = lower + width;
+        let mut steps = 0;
+        while self.log_pdf_at(i, lower) > level {
+            lower -= width;
+            steps += 1;
+            if steps == MAX_SLICE_STEPS {
+                return Err(GSLError::MaxIteration);
+            }
+        }
+        while self.log_pdf_at(i, upper) > level {
+            upper += width;
+            steps += 1;
+            if steps == MAX_SLICE_STEPS {
+                return Err(GSLError::MaxIteration);
+            }
+        }
+
+        // Sample from the interval, shrinking it towards x0 on rejection
+        for _ in 0..MAX_SLICE_STEPS {
+            let x = lower + (upper - lower) * rng.uniform();
+            let log_p = self.log_pdf_at(i, x);
+            if log_p > level {
+                self.x[i] = x;
+                self.log_p = log_p;
+                return Ok(());
+            }
+            if x < x0 {
+                lower = x;
+            } else {
+                upper = x;
+            }
+        }
+
+        self.x[i] = x0;
+        Err(GSLError::MaxIteration)
+    }
+
+    /// Log density with coordinate `i` replaced by `xi`, NaN counts as outside the support
+    fn log_pdf_at(&mut self, i: usize, xi: f64) -> f64 {
+        let x0 = self.x[i];
+        self.x[i] = xi;
+        let log_p = (self.log_pdf)(&self.x);
+        self.x[i] = x0;
+        if log_p.is_nan() {
+            f64::NEG_INFINITY
+        } else {
+            log_p
+        }
+    }
+}
+
+impl<F> fmt::Debug for SliceSampler<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SliceSampler")
+            .field("x", &self.x)
+            .field("width", &self.width)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Hamiltonian Monte Carlo with a fixed number of leapfrog steps per sample,
+/// and a step size varied randomly by up to 10%
+pub struct Hmc<F> {
+    log_pdf: F,
+    x: Box<[f64]>,
+    log_p: f64,
+    gradient: Box<[f64]>,
+    step_size: f64,
+    leapfrog_steps: usize,
+    proposed: u64,
+    accepted: u64,
+}
+
+impl<F: FnMut(&[f64]) -> f64> Hmc<F> {
+    /// The log density needs to be finite and differentiable at the starting point `x0`
+    pub fn new(mut log_pdf: F, x0: &[f64], step_size: f64, leapfrog_steps: usize) -> Result<Self> {
+        if x0.is_empty() || leapfrog_steps == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if !(step_size > 0.0 && step_size.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+
+        let log_p = log_pdf(x0);
+        if !log_p.is_finite() {
+            return Err(GSLError::Domain);
+        }
+        let gradient = deriv::gradient(&mut log_pdf, x0, GRADIENT_STEP)?;
+
+        Ok(Hmc {
+            log_pdf,
+            x: x0.into(),
+            log_p,
+            gradient,
+            step_size,
+            leapfrog_steps,
+            proposed: 0,
+            accepted: 0,
+        })
+    }
+
+    pub fn state(&self) -> &[f64] {
+        &self.x
+    }
+
+    pub fn step_size(&self) -> f64 {
+        self.step_size
+    }
+
+    /// Fraction of the proposals accepted so far
+    pub fn acceptance_rate(&self) -> f64 {
+        self.accepted as f64 / self.proposed as f64
+    }
+
+    /// Runs `n` warmup steps that tune the step size with dual averaging,
+    /// such that the mean acceptance probability approaches `target`, typically 0.65 to 0.8.
+    /// Resets the acceptance rate afterwards.
+    pub fn adapt(&mut self, rng: &mut Rng, n: usize, target: f64) -> Result<()> {
+        const GAMMA: f64 = 0.05;
+        const T0: f64 = 10.0;
+        const KAPPA: f64 = 0.75;
+
+        if !(target > 0.0 && target < 1.0) {
+            return Err(GSLError::Domain);
+        }
+
+        let mu = (10.0 * self.step_size).ln();
+        let mut h_bar = 0.0;
+        let mut ln_step_bar = 0.0;
+        for m in 1..=n {
+            let m = m as f64;
+            let acceptance = self.transition(rng)?;
+
+            h_bar = (1.0 - 1.0 / (m + T0)) * h_bar + (target - acceptance) / (m + T0);
+            let ln_step = mu - m.sqrt() / GAMMA * h_bar;
+            let weight = m.powf(-KAPPA);
+            ln_step_bar = weight * ln_step + (1.0 - weight) * ln_step_bar;
+            self.step_size = ln_step.exp();
+        }
+        if n > 0 {
+            self.step_size = ln_step_bar.exp();
+        }
+
+        self.proposed = 0;
+        self.accepted = 0;
+        Ok(())
+    }
+
+    /// Fails with `GSLError::BadFunction` if the gradient can't be evaluated along the trajectory
+    pub fn sample(&mut self, rng: &mut Rng) -> Result<&[f64]> {
+        self.transition(rng)?;
+        Ok(&self.x)
+    }
+
+    /// `n` samples drawn from the stream `stream` of `context`, one sample per row
+    pub fn chain(
+        &mut self,
+        n: usize,
+        context: &mut Reproducibility,
+        stream: &str,
+    ) -> Result<Matrix> {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        let mut rng = context.rng(stream);
+        let mut samples = Vec::with_capacity(n * self.x.len());
+        for _ in 0..n {
+            samples.extend_from_slice(self.sample(&mut rng)?);
+        }
+        Ok(Matrix::new(samples, n, self.x.len()))
+    }
+
+    /// One leapfrog trajectory with a Metropolis correction, returns the acceptance probability
+    fn transition(&mut self, rng: &mut Rng) -> Result<f64> {
+        // Jittered, so trajectories can't keep returning to their starting point
+        let eps = self.step_size * (0.9 + 0.2 * rng.uniform());
+        let mut p = (0..self.x.len())
+            .map(|_| rng.gaussian(1.0))
+            .collect::<Vec<_>>();
+        let h0 = -self.log_p + 0.5 * blas::dot(&p, &p)?;
+
+        let mut x = self.x.to_vec();
+        let mut gradient = self.gradient.clone();
+        let mut log_p = self.log_p;
+        blas::axpy(0.5 * eps, &gradient, &mut p)?;
+        for step in 0..self.leapfrog_steps {
+            blas::axpy(eps, &p, &mut x)?;
+            log_p = (self.log_pdf)(&x);
+            if !log_p.is_finite() {
+                break;
+            }
+            gradient = deriv::gradient(&mut self.log_pdf, &x, GRADIENT_STEP)?;
+            let kick = if step + 1 == self.leapfrog_steps {
+                0.5 * eps
+            } else {
+                eps
+            };
+            blas::axpy(kick, &gradient, &mut p)?;
+        }
+
+        self.proposed += 1;
+        let h1 = -log_p + 0.5 * blas::dot(&p, &p)?;
+        let acceptance = if h1.is_finite() {
+            (h0 - h1).exp().min(1.0)
+        } else {
+            0.0
+        };
+        if rng.uniform() < acceptance {
+            self.x = x.into_boxed_slice();
+            self.log_p = log_p;
+            self.gradient = gradient;
+            self.accepted += 1;
+        }
+        Ok(acceptance)
+    }
+}
+
+impl<F> fmt::Debug for Hmc<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hmc")
+            .field("x", &self.x)
+            .field("step_size", &self.step_size)
+            .field("leapfrog_steps", &self.leapfrog_steps)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Normalized autocorrelation of `x` for the lags `0..x.len()`
+pub fn autocorrelation(x: &Vector) -> Result<Vector> {
+    if x.len() < 2 {
+        return Err(GSLError::Invalid);
+    }
+    let mut fft = RealFft::new(2 * x.len())?;
+    let acov = autocovariance(&mut fft, x)?;
+    if acov[0] == 0.0 {
+        return Err(GSLError::Domain);
+    }
+    Ok(acov.iter().map(|c| c / acov[0]).collect())
+}
+
+/// Classic split R̂ of the draws, close to 1 for chains that agree
+pub fn split_rhat(draws: &Matrix) -> Result<f64> {
+    rhat_of(&split_chains(draws, draws.size().0)?)
+}
+
+/// Rank normalized split R̂, the maximum of the bulk and the folded (tail) R̂.
+/// Vehtari et al. recommend using the chains only if this is below 1.01.
+pub fn rhat(draws: &Matrix) -> Result<f64> {
+    let n_chains = draws.size().0;
+    split_chains(draws, n_chains)?;
+
+    let bulk = rank_normalize(draws);
+    let median = stats::median(draws)?;
+    let folded = rank_normalize(&draws.iter().map(|x| (x - median).abs()).collect::<Vec<_>>());
+
+    let bulk = rhat_of(&split_chains(&bulk, n_chains)?)?;
+    let tail = rhat_of(&split_chains(&folded, n_chains)?)?;
+    Ok(bulk.max(tail))
+}
+
+/// Effective sample size of the mean of the draws, over all chains
+pub fn ess(draws: &Matrix) -> Result<f64> {
+    ess_of(&split_chains(draws, draws.size().0)?)
+}
+
+/// Effective sample size of the rank normalized draws, describing the center of the distribution
+pub fn ess_bulk(draws: &Matrix) -> Result<f64> {
+    let n_chains = draws.size().0;
+    split_chains(draws, n_chains)?;
+    ess_of(&split_chains(&rank_normalize(draws), n_chains)?)
+}
+
+/// Effective sample size of the 5% and 95% quantiles, whichever is smaller
+pub fn ess_tail(draws: &Matrix) -> Result<f64> {
+    let n_chains = draws.size().0;
+    split_chains(draws, n_chains)?;
+
+    let mut sorted = draws.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut ess = f64::INFINITY;
+    for p in [0.05, 0.95] {
+        let q = stats::quantile_sorted(&sorted, p)?;
+        let indicator = draws
+            .iter()
+            .map(|&x| if x <= q { 1.0 } else { 0.0 })
+            .collect::<Vec<_>>();
+        ess = ess.min(ess_of(&split_chains(&indicator, n_chains)?)?);
+    }
+    Ok(ess)
+}
+
+/// Both halves of every chain, dropping the middle draw of chains with an odd length
+fn split_chains(draws: &[f64], n_chains: usize) -> Result<Vec<&[f64]>> {
+    let n = draws.len() / n_chains;
+    if n < 4 {
+        return Err(GSLError::Invalid);
+    }
+    if draws.iter().any(|x| !x.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+
+    let half = n / 2;
+    Ok(draws
+        .chunks(n)
+        .flat_map(|chain| [&chain[..half], &chain[n - half..]])
+        .collect())
+}
+
+/// Normal scores of the ranks of the draws, ties get their average rank
+fn rank_normalize(draws: &[f64]) -> Vec<f64> {
+    let s = draws.len();
+    let mut order = (0..s).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| draws[i].partial_cmp(&draws[j]).unwrap());
+
+    let mut z = vec![0.0; s];
+    let mut i = 0;
+    while i < s {
+        let mut j = i + 1;
+        while j < s && draws[order[j]] == draws[order[i]] {
+            j += 1;
+        }
+        // Ranks i + 1 up to j share their average
+        let rank = (i + j + 1) as f64 / 2.0;
+        let score = unsafe { gsl_cdf_ugaussian_Pinv((rank - 0.375) / (s as f64 + 0.25)) };
+        for &k in &order[i..j] {
+            z[k] = score;
+        }
+        i = j;
+    }
+    z
+}
+
+fn rhat_of(chains: &[&[f64]]) -> Result<f64> {
+    let n = chains[0].len() as f64;
+    let means = chains.iter().map(|c| stats::mean(c)).collect::<Vec<_>>();
+    let within = chains.iter().map(|c| stats::variance(c)).sum::<f64>() / chains.len() as f64;
+    if within == 0.0 {
+        return Err(GSLError::Domain);
+    }
+    let between = stats::variance(&means);
+    let var_plus = (n - 1.0) / n * within + between;
+    Ok((var_plus / within).sqrt())
+}
+
+fn ess_of(chains: &[&[f64]]) -> Result<f64> {
+    let m = chains.len();
+    let n = chains[0].len();
+    let mut fft = RealFft::new(2 * n)?;
+
+    let mut mean_acov = vec![0.0; n];
+    let mut within = 0.0;
+    for chain in chains {
+        let acov = autocovariance(&mut fft, chain)?;
+        for (sum, c) in mean_acov.iter_mut().zip(acov.iter()) {
+            *sum += c / m as f64;
+        }
+        within += acov[0] * n as f64 / (n - 1) as f64 / m as f64;
+    }
+    let means = chains.iter().map(|c| stats::mean(c)).collect::<Vec<_>>();
+    let var_plus = within * (n - 1) as f64 / n as f64 + stats::variance(&means);
+    if var_plus == 0.0 {
+        return Err(GSLError::Domain);
+    }
+
+    // Sum the pairs of consecutive autocorrelations while positive, forcing them to decrease
+    let rho = |t: usize| 1.0 - (within - mean_acov[t]) / var_plus;
+    let mut sum = 0.0;
+    let mut previous = f64::INFINITY;
+    let mut t = 0;
+    while t + 1 < n {
+        let pair = rho(t) + rho(t + 1);
+        if pair <= 0.0 {
+            break;
+        }
+        previous = pair.min(previous);
+        sum += previous;
+        t += 2;
+    }
+
+    // Antithetic chains can exceed the number of draws, but not by more than log10 of it
+    let total = (m * n) as f64;
+    let tau = (2.0 * sum - 1.0).max(1.0 / total.log10());
+    Ok(total / tau)
+}
+
+/// Autocovariance for the lags `0..x.len()`, `fft` needs a length of `2 * x.len()`
+fn autocovariance(fft: &mut RealFft, x: &[f64]) -> Result<Box<[f64]>> {
+    let n = x.len();
+    let mean = stats::mean(x);
+    let mut data = vec![0.0; 2 * n];
+    for (d, xi) in data.iter_mut().zip(x) {
+        *d = xi - mean;
+    }
+
+    // Replace the half complex transform [r0, r1, i1, r2, i2, ..., r(n)] by its power spectrum
+    fft.forward(&mut data)?;
+    data[0] *= data[0];
+    for k in 1..n {
+        let (re, im) = (data[2 * k - 1], data[2 * k]);
+        data[2 * k - 1] = re * re + im * im;
+        data[2 * k] = 0.0;
+    }
+    data[2 * n - 1] *= data[2 * n - 1];
+    fft.inverse(&mut data)?;
+
+    Ok(data[..n].iter().map(|c| c / n as f64).collect())
+}
+
+#[cfg(test)]
+fn moments(samples: &[Box<[f64]>], i: usize) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|x| x[i]).sum::<f64>() / n;
+    let variance = samples.iter().map(|x| (x[i] - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance)
+}
+
+#[test]
+fn test_slice_sampler() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+
+    // Correlated bivariate normal with unit variances and correlation 0.5
+    let log_pdf = |x: &[f64]| -(x[0] * x[0] - x[0] * x[1] + x[1] * x[1]) / 1.5;
+    let mut sampler = SliceSampler::new(log_pdf, &[3.0, -3.0], &[1.0, 1.0]).unwrap();
+    let samples = (0..20_000)
+        .map(|_| Box::<[f64]>::from(sampler.sample(&mut rng).unwrap()))
+        .collect::<Vec<_>>();
+    for i in 0..2 {
+        let (mean, variance) = moments(&samples, i);
+        approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.05);
+        approx::assert_abs_diff_eq!(variance, 1.0, epsilon = 0.05);
+    }
+    let covariance = samples.iter().map(|x| x[0] * x[1]).sum::<f64>() / samples.len() as f64;
+    approx::assert_abs_diff_eq!(covariance, 0.5, epsilon = 0.05);
+
+    // Exponential with a hard edge, outside the support the log density is minus infinity
+    let log_pdf = |x: &[f64]| if x[0] < 0.0 { f64::NEG_INFINITY } else { -x[0] };
+    let mut sampler = SliceSampler::new(log_pdf, &[1.0], &[0.5]).unwrap();
+    let samples = (0..20_000)
+        .map(|_| Box::<[f64]>::from(sampler.sample(&mut rng).unwrap()))
+        .collect::<Vec<_>>();
+    assert!(samples.iter().all(|x| x[0] >= 0.0));
+    let (mean, variance) = moments(&samples, 0);
+    approx::assert_abs_diff_eq!(mean, 1.0, epsilon = 0.05);
+    approx::assert_abs_diff_eq!(variance, 1.0, epsilon = 0.1);
+
+    // Chains from the same seed agree bit for bit
+    let chain = |context: &mut Reproducibility| {
+        SliceSampler::new(log_pdf, &[1.0], &[0.5])
+            .unwrap()
+            .chain(100, context, "chain 1")
+            .unwrap()
+    };
+    let mut context = Reproducibility::new(3);
+    let samples = chain(&mut context);
+    assert_eq!(samples.size(), (100, 1));
+    assert_eq!(samples[..], chain(&mut Reproducibility::new(3))[..]);
+    assert_ne!(samples[..], chain(&mut Reproducibility::new(4))[..]);
+}
+
+#[test]
+fn test_hmc() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 2);
+
+    // Normal with standard deviations 1 and 2
+    let log_pdf = |x: &[f64]| -0.5 * (x[0] * x[0] + x[1] * x[1] / 4.0);
+    let mut hmc = Hmc::new(log_pdf, &[1.0, 1.0], 1.0, 10).unwrap();
+
+    hmc.adapt(&mut rng, 500, 0.8).unwrap();
+    assert!(hmc.step_size() > 0.5 && hmc.step_size() < 2.0);
+
+    let samples = (0..20_000)
+        .map(|_| Box::<[f64]>::from(hmc.sample(&mut rng).unwrap()))
+        .collect::<Vec<_>>();
+    assert!(hmc.acceptance_rate() > 0.7 && hmc.acceptance_rate() < 0.95);
+
+    let (mean, variance) = moments(&samples, 0);
+    approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.1);
+    approx::assert_abs_diff_eq!(variance, 1.0, epsilon = 0.1);
+    let (mean, variance) = moments(&samples, 1);
+    approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.2);
+    approx::assert_abs_diff_eq!(variance, 4.0, epsilon = 0.5);
+
+    let chain = |context: &mut Reproducibility| {
+        Hmc::new(log_pdf, &[1.0, 1.0], 1.0, 10)
+            .unwrap()
+            .chain(50, context, "hmc")
+            .unwrap()
+    };
+    let samples = chain(&mut Reproducibility::new(5));
+    assert_eq!(samples.size(), (50, 2));
+    assert_eq!(samples[..], chain(&mut Reproducibility::new(5))[..]);
+}
+
+#[test]
+fn test_diagnostics() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 4);
+
+    // Independent draws from 4 chains
+    let iid = Matrix::new((0..4000).map(|_| rng.gaussian(1.0)), 4, 1000);
+    approx::assert_abs_diff_eq!(split_rhat(&iid).unwrap(), 1.0, epsilon = 0.01);
+    approx::assert_abs_diff_eq!(rhat(&iid).unwrap(), 1.0, epsilon = 0.01);
+    for ess in [ess(&iid), ess_bulk(&iid), ess_tail(&iid)] {
+        approx::assert_relative_eq!(ess.unwrap(), 4000.0, max_relative = 0.2);
+    }
+
+    // AR(1) chains with coefficient 0.9 have an effective sample size of about N (1 - 0.9) / (1 + 0.9)
+    let mut ar = Vec::with_capacity(4000);
+    for _ in 0..4 {
+        let mut x = rng.gaussian(1.0 / 0.19f64.sqrt());
+        for _ in 0..1000 {
+            x = 0.9 * x + rng.gaussian(1.0);
+            ar.push(x);
+        }
+    }
+    let ar = Matrix::new(ar, 4, 1000);
+    approx::assert_relative_eq!(ess(&ar).unwrap(), 4000.0 / 19.0, max_relative = 0.4);
+    approx::assert_relative_eq!(ess_bulk(&ar).unwrap(), 4000.0 / 19.0, max_relative = 0.4);
+    let rho = autocorrelation(&ar.row(0)).unwrap();
+    assert_eq!(rho[0], 1.0);
+    approx::assert_abs_diff_eq!(rho[1], 0.9, epsilon = 0.05);
+
+    // A chain stuck elsewhere, or a trend within the chains
+    let mut shifted = iid.clone();
+    shifted[..1000].iter_mut().for_each(|x| *x += 2.0);
+    assert!(split_rhat(&shifted).unwrap() > 1.1);
+    assert!(rhat(&shifted).unwrap() > 1.1);
+    let trend = Matrix::new(
+        (0..4000).map(|i| (i % 1000) as f64 / 250.0 + rng.gaussian(1.0)),
+        4,
+        1000,
+    );
+    assert!(split_rhat(&trend).unwrap() > 1.1);
+
+    // Different scales only show up in the folded draws
+    let mut scaled = iid.clone();
+    scaled[..1000].iter_mut().for_each(|x| *x *= 3.0);
+    assert!(split_rhat(&scaled).unwrap() < 1.01);
+    assert!(rhat(&scaled).unwrap() > 1.05);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 3);
+    let normal = |x: &[f64]| -0.5 * x[0] * x[0];
+
+    // Empty starting point
+    SliceSampler::new(normal, &[], &[]).unwrap_err();
+    Hmc::new(normal, &[], 0.1, 10).unwrap_err();
+
+    // Nonpositive width or step size, or no leapfrog steps
+    SliceSampler::new(normal, &[0.0], &[0.0]).unwrap_err();
+    SliceSampler::new(normal, &[0.0], &[1.0, 1.0]).unwrap_err();
+    Hmc::new(normal, &[0.0], 0.0, 10).unwrap_err();
+    Hmc::new(normal, &[0.0], 0.1, 0).unwrap_err();
+
+    // Starting outside the support
+    SliceSampler::new(|x: &[f64]| x[0].ln(), &[-1.0], &[1.0]).unwrap_err();
+
+    // Improper density
+    let mut sampler = SliceSampler::new(|_: &[f64]| 0.0, &[0.0], &[1.0]).unwrap();
+    sampler.sample(&mut rng).unwrap_err();
+
+    // Target acceptance out of range
+    let mut hmc = Hmc::new(normal, &[0.0], 0.1, 10).unwrap();
+    hmc.adapt(&mut rng, 10, 1.0).unwrap_err();
+
+    // Empty chain
+    hmc.chain(0, &mut Reproducibility::new(1), "hmc")
+        .unwrap_err();
+
+    // Chains too short
+    split_rhat(&Matrix::from([[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]])).unwrap_err();
+    rhat(&Matrix::from([[0.0, 1.0, 2.0]])).unwrap_err();
+    autocorrelation(&Vector::new([1.0])).unwrap_err();
+
+    // Not finite or constant
+    ess_bulk(&Matrix::from([[0.0, 1.0, f64::NAN, 3.0]])).unwrap_err();
+    ess(&Matrix::from([[1.0; 4]; 2])).unwrap_err();
+    autocorrelation(&Vector::new([1.0; 8])).unwrap_err();
+}
@@ -0,0 +1,98 @@
+/*
+    memory.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/*
+
+    Approximate accounting of the memory held by GSL workspaces, to monitor and bound
+    the memory use of long-running programs.
+
+    Workspaces that outlive a call, like `IntegrationWorkspace`, `RealFft` or `BSplineBasis`,
+    report their footprint with `memory_usage`. Fits and interpolations allocate their workspaces
+    for the duration of the call. The crate-wide tally `allocated` counts both, and `peak` is its maximum.
+
+    Footprints are estimated from the sizes passed to GSL: the arrays GSL allocates
+    and its structs, without allocator overhead. Memory of Rust types like `Vec` isn't counted.
+
+*/
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Bytes held by GSL workspaces at the moment, over all threads
+pub fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Largest value of `allocated` since the start of the program or the last `reset_peak`
+pub fn peak() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// Resets `peak` to the current value of `allocated`
+pub fn reset_peak() {
+    PEAK.store(allocated(), Ordering::Relaxed);
+}
+
+/// Counts `bytes` towards `allocated` while it's alive
+#[derive(Debug)]
+pub(crate) struct Footprint(usize);
+
+impl Footprint {
+    pub(crate) fn new(bytes: usize) -> Self {
+        let total = ALLOCATED.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        PEAK.fetch_max(total, Ordering::Relaxed);
+        Footprint(bytes)
+    }
+
+    /// Footprint of a GSL struct `T` with `doubles` additional `f64` values
+    pub(crate) fn of<T>(doubles: usize) -> Self {
+        Self::new(std::mem::size_of::<T>() + doubles * std::mem::size_of::<f64>())
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.0
+    }
+}
+
+impl Drop for Footprint {
+    fn drop(&mut self) {
+        ALLOCATED.fetch_sub(self.0, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_memory() {
+    use crate::fft::RealFft;
+    use crate::integrate::IntegrationWorkspace;
+
+    // Other tests allocate concurrently, so only the footprints themselves are exact
+    let workspace = IntegrationWorkspace::new(1000).unwrap();
+    assert!(workspace.memory_usage() >= 1000 * 6 * 8);
+    let small = IntegrationWorkspace::new(10).unwrap();
+    assert!(small.memory_usage() < workspace.memory_usage());
+
+    let fft = RealFft::new(1024).unwrap();
+    assert!(fft.memory_usage() >= 1024 * 8);
+    assert!(peak() >= workspace.memory_usage() + fft.memory_usage());
+
+    let footprint = Footprint::of::<f64>(9);
+    assert_eq!(footprint.bytes(), 80);
+    assert!(allocated() >= 80);
+}
@@ -0,0 +1,238 @@
+/*
+    min.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::roots::Convergence;
+use crate::*;
+use drop_guard::guard;
+
+/// Minimum of `f` in `[a, b]` starting from `x0`, with the tolerances and iterations
+/// of the `NumericPolicy`, by default `epsabs = 1e-9`, `epsrel = 0` and 100 iterations
+pub fn minimize<F: FnMut(f64) -> f64, C: FnMut(MinimizerCallback)>(
+    a: f64,
+    b: f64,
+    x0: f64,
+    f: F,
+) -> Result<f64> {
+    let policy = policy::policy();
+    let (epsabs, epsrel) = policy.tolerances(1.0e-9, 0.0);
+    minimize_ext(policy.iterations(100), a, b, x0, epsabs, epsrel, f, |_| {})
+}
+
+/// `minimize` with `policy` instead of the policy of this thread
+pub fn minimize_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    a: f64,
+    b: f64,
+    x0: f64,
+    f: F,
+) -> Result<f64> {
+    minimize_result_with_policy(policy, a, b, x0, f).map(|r| r.minimum)
+}
+
+pub fn minimize_ext<F: FnMut(f64) -> f64, C: FnMut(MinimizerCallback)>(
+    max_iter: usize,
+    a: f64,
+    b: f64,
+    x0: f64,
+    epsabs: f64,
+    epsrel: f64,
+    f: F,
+    callback: C,
+) -> Result<f64> {
+    minimize_result_ext(max_iter, a, b, x0, epsabs, epsrel, f, callback).map(|r| r.minimum)
+}
+
+/// `minimize` with the function value, iterations, final bracket and convergence of the minimum
+pub fn minimize_result<F: FnMut(f64) -> f64>(a: f64, b: f64, x0: f64, f: F) -> Result<MinResult> {
+    minimize_result_with_policy(policy::policy(), a, b, x0, f)
+}
+
+/// `minimize_result` with `policy` instead of the policy of this thread
+pub fn minimize_result_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    a: f64,
+    b: f64,
+    x0: f64,
+    f: F,
+) -> Result<MinResult> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-9, 0.0);
+        minimize_result_ext(policy.iterations(100), a, b, x0, epsabs, epsrel, f, |_| {})
+    })
+}
+
+/// `minimize_ext` with the function value, iterations, final bracket and convergence of the minimum
+pub fn minimize_result_ext<F: FnMut(f64) -> f64, C: FnMut(MinimizerCallback)>(
+    max_iter: usize,
+    a: f64,
+    b: f64,
+    x0: f64,
+    epsabs: f64,
+    epsrel: f64,
+    mut f: F,
+    mut callback: C,
+) -> Result<MinResult> {
+    unsafe {
+        let minimizer = guard(
+            gsl_min_fminimizer_alloc(gsl_min_fminimizer_brent),
+            |minimizer| {
+                gsl_min_fminimizer_free(minimizer);
+            },
+        );
+        assert!(!minimizer.is_null());
+
+        let mut gsl_f = gsl_function_struct {
+            function: Some(trampoline::<F>),
+            params: &mut f as *mut _ as *mut _,
+        };
+
+        GSLError::from_raw(gsl_min_fminimizer_set(*minimizer, &mut gsl_f, x0, a, b))?;
+
+        let mut iter = 0;
+        loop {
+            GSLError::from_raw(gsl_min_fminimizer_iterate(*minimizer))?;
+
+            let x_lower = gsl_min_fminimizer_x_lower(*minimizer);
+            let x_upper = gsl_min_fminimizer_x_upper(*minimizer);
+            let y_lower = gsl_min_fminimizer_f_lower(*minimizer);
+            let y_upper = gsl_min_fminimizer_f_upper(*minimizer);
+            let x = gsl_min_fminimizer_x_minimum(*minimizer);
+            let y = gsl_min_fminimizer_f_minimum(*minimizer);
+
+            callback(MinimizerCallback {
+                iter,
+                lower_bound: (x_lower, y_lower),
+                upper_bound: (x_upper, y_upper),
+                minimum: (x, y),
+            });
+
+            let result = move |converged_by| MinResult {
+                minimum: x,
+                f_minimum: y,
+                iterations: iter + 1,
+                final_bracket: (x_lower, x_upper),
+                converged_by,
+            };
+            if gsl_min_test_interval(x_lower, x_upper, epsabs, epsrel) == GSL_SUCCESS {
+                return Ok(result(Convergence::Interval));
+            }
+
+            iter += 1;
+            if iter >= max_iter {
+                policy::converged(Err(GSLError::MaxIteration))?;
+                return Ok(result(Convergence::MaxIteration));
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinResult {
+    pub minimum: f64,
+    /// Function value at the minimum
+    pub f_minimum: f64,
+    pub iterations: usize,
+    /// Last bracket `(lower, upper)` around the minimum
+    pub final_bracket: (f64, f64),
+    /// `Interval` or `MaxIteration`
+    pub converged_by: Convergence,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MinimizerCallback {
+    pub iter: usize,
+    pub lower_bound: (f64, f64),
+    pub upper_bound: (f64, f64),
+    pub minimum: (f64, f64),
+}
+
+#[test]
+fn test_minimizer() {
+    disable_error_handler();
+
+    approx::assert_abs_diff_eq!(
+        minimize_ext(100, 1.0, 6.0, 4.0, 1.0e-6, 0.0, |x| x.sin(), |_| {}).unwrap(),
+        std::f64::consts::PI * 3.0 / 2.0,
+        epsilon = 1.0e-6
+    );
+}
+
+#[test]
+fn test_minimizer_result() {
+    disable_error_handler();
+
+    let mut history = Vec::new();
+    let result = minimize_result_ext(
+        100,
+        1.0,
+        6.0,
+        4.0,
+        1.0e-6,
+        0.0,
+        |x| x.sin(),
+        |c| history.push(c),
+    )
+    .unwrap();
+    approx::assert_abs_diff_eq!(
+        result.minimum,
+        std::f64::consts::PI * 3.0 / 2.0,
+        epsilon = 1.0e-6
+    );
+    approx::assert_abs_diff_eq!(result.f_minimum, -1.0, epsilon = 1.0e-10);
+    assert_eq!(result.converged_by, Convergence::Interval);
+    assert_eq!(result.iterations, history.len());
+    let last = history.last().unwrap();
+    assert_eq!(
+        result.final_bracket,
+        (last.lower_bound.0, last.upper_bound.0)
+    );
+    assert_eq!(
+        minimize_result(1.0, 6.0, 4.0, |x| x.sin())
+            .unwrap()
+            .converged_by,
+        Convergence::Interval
+    );
+
+    // Out of iterations, accepted by a best effort policy
+    let policy = policy::NumericPolicy {
+        errors: policy::ErrorPolicy::BestEffort,
+        ..Default::default()
+    };
+    let result = policy::with_policy(policy, || {
+        minimize_result_ext(2, 1.0, 6.0, 4.0, 1.0e-12, 0.0, |x| x.sin(), |_| {})
+    })
+    .unwrap();
+    assert_eq!(result.converged_by, Convergence::MaxIteration);
+    assert_eq!(result.iterations, 2);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // No iterations
+    minimize_ext(0, 1.0, 6.0, 4.0, 1.0e-6, 0.0, |x| x.sin(), |_| {}).unwrap_err();
+
+    // Empty domain
+    minimize_ext(100, 0.0, 0.0, 4.0, 1.0e-6, 0.0, |x| x.sin(), |_| {}).unwrap_err();
+
+    // Nonsense guess
+    minimize_ext(0, 1.0, 6.0, std::f64::NAN, 1.0e-6, 0.0, |x| x.sin(), |_| {}).unwrap_err();
+}
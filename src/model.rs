@@ -0,0 +1,400 @@
+/*
+    model.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::nonlinear_fit::{nonlinear_fit, FitResult};
+use crate::*;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Add;
+use std::sync::Arc;
+
+/*
+
+    Models of `y(x)` built from parts, e.g. a Gaussian peak on a linear baseline for spectrum fitting,
+    `gaussian() + linear_baseline()`. A model has a flat vector of parameters, in the order of its parts,
+    so it compiles down to one closure for `nonlinear_fit` and the other fits.
+    The number of parameters `P` of the fit is checked against the model.
+
+    Every parameter is named `namespace.parameter`. The namespace of a part is its name, e.g. `gaussian`,
+    which `named` replaces, e.g. to tell peaks apart. Sums number the namespaces that occur on both sides,
+    so the second `gaussian` becomes `gaussian2`.
+
+    Convolution with a `Kernel` is a weighted sum of the model at shifted arguments,
+    `Σ w_i m(x - t_i)`, e.g. for the resolution of a detector. The kernel has no parameters.
+
+*/
+
+type Eval = Arc<dyn Fn(f64, &[f64]) -> f64 + Send + Sync>;
+
+/// Model of `y(x)` with named parameters, see `nonlinear_fit`
+#[derive(Clone)]
+pub struct Model {
+    names: Vec<String>,
+    eval: Eval,
+}
+
+impl Model {
+    /// Part with the parameters `namespace.parameters[i]`, evaluated by `f(x, params)`
+    pub fn new<F: Fn(f64, &[f64]) -> f64 + Send + Sync + 'static>(
+        namespace: &str,
+        parameters: &[&str],
+        f: F,
+    ) -> Self {
+        Model {
+            names: parameters
+                .iter()
+                .map(|p| format!("{}.{}", namespace, p))
+                .collect(),
+            eval: Arc::new(f),
+        }
+    }
+
+    /// Sum of the models `a` and `b`, with the parameters of `a` first
+    pub fn sum(a: Model, b: Model) -> Model {
+        let split = a.names.len();
+        let (fa, fb) = (a.eval, b.eval);
+        Model {
+            names: merge_names(&a.names, &b.names),
+            eval: Arc::new(move |x, params| {
+                let (pa, pb) = params.split_at(split);
+                fa(x, pa) + fb(x, pb)
+            }),
+        }
+    }
+
+    /// Model times the new last parameter `scale.factor`
+    pub fn scale(self) -> Model {
+        let n = self.names.len();
+        let f = self.eval;
+        Model {
+            names: merge_names(&self.names, &["scale.factor".to_owned()]),
+            eval: Arc::new(move |x, params| params[n] * f(x, &params[..n])),
+        }
+    }
+
+    /// Convolution `Σ w_i m(x - t_i)` with the `kernel`, with the same parameters
+    pub fn convolve_with(self, kernel: Kernel) -> Model {
+        let f = self.eval;
+        Model {
+            names: self.names,
+            eval: Arc::new(move |x, params| {
+                kernel
+                    .offsets
+                    .iter()
+                    .zip(kernel.weights.iter())
+                    .map(|(t, w)| w * f(x - t, params))
+                    .sum()
+            }),
+        }
+    }
+
+    /// Replaces the namespace of all parameters by `namespace`
+    pub fn named(mut self, namespace: &str) -> Model {
+        for name in &mut self.names {
+            *name = format!("{}.{}", namespace, parameter(name));
+        }
+        self
+    }
+
+    /// Names of the parameters, in the order of the parameter vector
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn n_params(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Position of the parameter `name` in the parameter vector
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// Value of the model at `x`. Panics if `params` doesn't have `n_params()` values.
+    pub fn eval(&self, x: f64, params: &[f64]) -> f64 {
+        assert_eq!(params.len(), self.names.len());
+        (self.eval)(x, params)
+    }
+
+    /// The model as a closure for `nonlinear_fit` and the other fits with `P` parameters.
+    /// Fails with `GSLError::BadLength` if the model doesn't have `P` parameters.
+    pub fn closure<const P: usize>(
+        &self,
+    ) -> Result<impl Fn(&f64, [f64; P]) -> Result<f64> + Send + Sync + '_> {
+        if P != self.names.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(move |&x: &f64, params: [f64; P]| Ok((self.eval)(x, &params)))
+    }
+
+    /// `nonlinear_fit` of the model, starting from `p0`
+    pub fn fit<const P: usize>(&self, p0: [f64; P], x: &[f64], y: &[f64]) -> Result<FitResult<P>> {
+        nonlinear_fit(p0, x, y, self.closure()?)
+    }
+}
+
+impl Add for Model {
+    type Output = Model;
+
+    fn add(self, other: Model) -> Model {
+        Model::sum(self, other)
+    }
+}
+
+impl fmt::Debug for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Model").field("names", &self.names).finish()
+    }
+}
+
+/// Names of `a` followed by those of `b`, where the namespaces of `b` that occur in `a` get numbered
+fn merge_names(a: &[String], b: &[String]) -> Vec<String> {
+    let mut taken = a.iter().map(|n| namespace(n)).collect::<HashSet<_>>();
+    taken.extend(b.iter().map(|n| namespace(n)));
+    let mut renamed = Vec::<(&str, String)>::new();
+    let mut names = a.to_vec();
+    for name in b {
+        let ns = namespace(name);
+        let ns = match renamed.iter().find(|(old, _)| *old == ns) {
+            Some((_, new)) => new.clone(),
+            None if a.iter().any(|n| namespace(n) == ns) => {
+                let new = (2..)
+                    .map(|k| format!("{}{}", ns, k))
+                    .find(|new| {
+                        !taken.contains(new.as_str()) && !renamed.iter().any(|(_, r)| r == new)
+                    })
+                    .unwrap();
+                renamed.push((ns, new.clone()));
+                new
+            }
+            None => ns.to_owned(),
+        };
+        names.push(format!("{}.{}", ns, parameter(name)));
+    }
+    names
+}
+
+fn namespace(name: &str) -> &str {
+    name.split_once('.').map_or(name, |(ns, _)| ns)
+}
+
+fn parameter(name: &str) -> &str {
+    name.split_once('.').map_or(name, |(_, p)| p)
+}
+
+/// Gaussian peak `amplitude exp(-(x - center)^2 / 2 sigma^2)`
+pub fn gaussian() -> Model {
+    Model::new("gaussian", &["amplitude", "center", "sigma"], |x, p| {
+        let z = (x - p[1]) / p[2];
+        p[0] * (-0.5 * z * z).exp()
+    })
+}
+
+/// Lorentzian peak `amplitude gamma^2 / ((x - center)^2 + gamma^2)`, with the half width `gamma`
+pub fn lorentzian() -> Model {
+    Model::new("lorentzian", &["amplitude", "center", "gamma"], |x, p| {
+        let d = x - p[1];
+        p[0] * p[2] * p[2] / (d * d + p[2] * p[2])
+    })
+}
+
+/// Exponential decay `amplitude exp(-rate x)`
+pub fn exponential() -> Model {
+    Model::new("exponential", &["amplitude", "rate"], |x, p| {
+        p[0] * (-p[1] * x).exp()
+    })
+}
+
+/// Constant `value`
+pub fn constant() -> Model {
+    Model::new("constant", &["value"], |_, p| p[0])
+}
+
+/// Linear baseline `offset + slope x`
+pub fn linear_baseline() -> Model {
+    Model::new("baseline", &["offset", "slope"], |x, p| p[0] + p[1] * x)
+}
+
+/// Weights `w_i` at the offsets `t_i` of a convolution, see `Model::convolve_with`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel {
+    offsets: Box<[f64]>,
+    weights: Box<[f64]>,
+}
+
+impl Kernel {
+    /// Fails with `GSLError::BadLength` unless there are as many `weights` as `offsets`, and at least one
+    pub fn new(offsets: &[f64], weights: &[f64]) -> Result<Self> {
+        if offsets.is_empty() || offsets.len() != weights.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(Kernel {
+            offsets: offsets.into(),
+            weights: weights.into(),
+        })
+    }
+
+    /// Normalized Gaussian of width `sigma`, sampled at `n` points within 4 `sigma`
+    pub fn gaussian(sigma: f64, n: usize) -> Result<Self> {
+        if !(sigma > 0.0 && sigma.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+        let offsets = if n == 1 {
+            vec![0.0]
+        } else {
+            util::linspace(-4.0 * sigma, 4.0 * sigma, n)
+        };
+        let weights = offsets
+            .iter()
+            .map(|t| (-0.5 * (t / sigma).powi(2)).exp())
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f64>();
+        Kernel::new(
+            &offsets,
+            &weights.iter().map(|w| w / total).collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn offsets(&self) -> &[f64] {
+        &self.offsets
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+}
+
+#[test]
+fn test_model() {
+    disable_error_handler();
+
+    let model = gaussian() + gaussian().named("peak") + gaussian() + linear_baseline();
+    assert_eq!(
+        model.names(),
+        [
+            "gaussian.amplitude",
+            "gaussian.center",
+            "gaussian.sigma",
+            "peak.amplitude",
+            "peak.center",
+            "peak.sigma",
+            "gaussian2.amplitude",
+            "gaussian2.center",
+            "gaussian2.sigma",
+            "baseline.offset",
+            "baseline.slope",
+        ]
+    );
+    assert_eq!(model.index("gaussian2.center"), Some(7));
+    assert_eq!(model.index("gaussian3.center"), None);
+    let model = model.scale();
+    assert_eq!(model.names()[11], "scale.factor");
+    assert_eq!(model.scale().names()[12], "scale2.factor");
+
+    // Two peaks on a sloped baseline
+    let truth = [2.0, 3.0, 0.5, 1.0, 6.0, 0.8, 0.5, 0.1];
+    let model = gaussian() + gaussian() + linear_baseline();
+    let x = util::linspace(0.0, 10.0, 200);
+    let y = x.iter().map(|&x| model.eval(x, &truth)).collect::<Vec<_>>();
+    let tail = (-0.5 * (3.0f64 / 0.8).powi(2)).exp();
+    approx::assert_abs_diff_eq!(model.eval(3.0, &truth), 2.0 + tail + 0.8, epsilon = 1.0e-14);
+
+    let fit = model
+        .fit([1.5, 2.8, 0.6, 1.2, 6.2, 1.0, 0.0, 0.0], &x, &y)
+        .unwrap();
+    for (p, t) in fit.params.iter().zip(truth) {
+        approx::assert_abs_diff_eq!(*p, t, epsilon = 1.0e-6);
+    }
+
+    // The closure works with the other fits
+    let fit = nonlinear_fit::nonlinear_fit_constrained(
+        [1.5, 2.8, 0.6, 1.2, 6.2, 1.0, 0.5, 0.1],
+        &[
+            nonlinear_fit::Constraint::Free,
+            nonlinear_fit::Constraint::Free,
+            nonlinear_fit::Constraint::Free,
+            nonlinear_fit::Constraint::Free,
+            nonlinear_fit::Constraint::Free,
+            nonlinear_fit::Constraint::Free,
+            nonlinear_fit::Constraint::Fixed,
+            nonlinear_fit::Constraint::Fixed,
+        ],
+        &x,
+        &y,
+        model.closure().unwrap(),
+    )
+    .unwrap();
+    approx::assert_abs_diff_eq!(fit.params[4], 6.0, epsilon = 1.0e-6);
+
+    // Scaled model
+    let scaled = gaussian().scale();
+    assert_eq!(
+        scaled.eval(1.0, &[2.0, 1.0, 0.5, 3.0]),
+        3.0 * gaussian().eval(1.0, &[2.0, 1.0, 0.5])
+    );
+}
+
+#[test]
+fn test_convolution() {
+    disable_error_handler();
+
+    // A Gaussian smeared by a Gaussian resolution is a wider Gaussian
+    let (sigma, resolution) = (0.3f64, 0.4f64);
+    let width = sigma.hypot(resolution);
+    let smeared = gaussian().convolve_with(Kernel::gaussian(resolution, 401).unwrap());
+    assert_eq!(smeared.n_params(), 3);
+    for x in [-1.0, 0.0, 0.25, 0.7] {
+        approx::assert_abs_diff_eq!(
+            smeared.eval(x, &[1.0, 0.0, sigma]),
+            gaussian().eval(x, &[sigma / width, 0.0, width]),
+            epsilon = 5.0e-4
+        );
+    }
+
+    // A single point shifts the model
+    let shifted = lorentzian().convolve_with(Kernel::new(&[0.5], &[2.0]).unwrap());
+    assert_eq!(
+        shifted.eval(1.0, &[1.0, 0.0, 1.0]),
+        2.0 * lorentzian().eval(0.5, &[1.0, 0.0, 1.0])
+    );
+
+    let kernel = Kernel::gaussian(1.0, 5).unwrap();
+    approx::assert_abs_diff_eq!(kernel.weights().iter().sum::<f64>(), 1.0, epsilon = 1.0e-15);
+    assert_eq!(kernel.offsets(), [-4.0, -2.0, 0.0, 2.0, 4.0]);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Wrong number of parameters
+    let model = exponential() + constant();
+    model.closure::<2>().err().unwrap();
+    model
+        .fit([1.0, 1.0], &[0.0, 1.0, 2.0], &[1.0, 0.5, 0.3])
+        .unwrap_err();
+
+    // Bad kernels
+    Kernel::new(&[], &[]).unwrap_err();
+    Kernel::new(&[0.0, 1.0], &[1.0]).unwrap_err();
+    Kernel::gaussian(0.0, 5).unwrap_err();
+    Kernel::gaussian(1.0, 0).unwrap_err();
+}
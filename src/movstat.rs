@@ -0,0 +1,243 @@
+/*
+    movstat.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+use drop_guard::guard;
+use std::os::raw::c_int;
+
+/*
+
+    Moving window statistics over a symmetric window of `width` samples, centered on each sample.
+    Even widths are rounded up by GSL. The output has the same length as the input.
+
+*/
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum End {
+    /// Pads with zeros beyond the endpoints
+    PadZero = gsl_movstat_end_t_GSL_MOVSTAT_END_PADZERO as u32,
+    /// Pads with copies of the first and last sample
+    PadValue = gsl_movstat_end_t_GSL_MOVSTAT_END_PADVALUE as u32,
+    /// Shrinks the window near the endpoints
+    Truncate = gsl_movstat_end_t_GSL_MOVSTAT_END_TRUNCATE as u32,
+}
+
+pub fn mean(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_mean(end, x, y, w)
+    })
+}
+
+pub fn variance(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_variance(end, x, y, w)
+    })
+}
+
+pub fn sd(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_sd(end, x, y, w)
+    })
+}
+
+pub fn median(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_median(end, x, y, w)
+    })
+}
+
+pub fn min(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_min(end, x, y, w)
+    })
+}
+
+pub fn max(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_max(end, x, y, w)
+    })
+}
+
+pub fn sum(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_sum(end, x, y, w)
+    })
+}
+
+/// Median absolute deviation, scaled by `1.4826` to estimate the standard deviation of normally distributed data.
+/// The moving median is written to `median`.
+pub fn mad(width: usize, end: End, x: &[f64], median: &mut [f64], mad: &mut [f64]) -> Result<()> {
+    if width == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if median.len() != x.len() {
+        return Err(GSLError::BadLength);
+    }
+    if x.is_empty() {
+        return Ok(());
+    }
+    let mut gsl_median = gsl_vector::from(median);
+    movstat(width, end, x, mad, |end, x, y, w| unsafe {
+        gsl_movstat_mad(end, x, &mut gsl_median, y, w)
+    })
+}
+
+/// Median absolute deviation without scaling. The moving median is written to `median`.
+pub fn mad0(width: usize, end: End, x: &[f64], median: &mut [f64], mad: &mut [f64]) -> Result<()> {
+    if width == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if median.len() != x.len() {
+        return Err(GSLError::BadLength);
+    }
+    if x.is_empty() {
+        return Ok(());
+    }
+    let mut gsl_median = gsl_vector::from(median);
+    movstat(width, end, x, mad, |end, x, y, w| unsafe {
+        gsl_movstat_mad0(end, x, &mut gsl_median, y, w)
+    })
+}
+
+/// Robust scale estimate `Q_n` of Rousseeuw and Croux
+pub fn qn(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_Qn(end, x, y, w)
+    })
+}
+
+/// Robust scale estimate `S_n` of Rousseeuw and Croux
+pub fn sn(width: usize, end: End, x: &[f64], y: &mut [f64]) -> Result<()> {
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_Sn(end, x, y, w)
+    })
+}
+
+/// Quantile range `Q(1 - q) - Q(q)` for `q` in `[0, 0.5]`, e.g. the interquartile range for `q = 0.25`
+pub fn qqr(width: usize, end: End, q: f64, x: &[f64], y: &mut [f64]) -> Result<()> {
+    if !(0.0..=0.5).contains(&q) {
+        return Err(GSLError::Domain);
+    }
+    movstat(width, end, x, y, |end, x, y, w| unsafe {
+        gsl_movstat_qqr(end, x, q, y, w)
+    })
+}
+
+fn movstat<
+    F: FnOnce(
+        gsl_movstat_end_t,
+        *const gsl_vector,
+        *mut gsl_vector,
+        *mut gsl_movstat_workspace,
+    ) -> c_int,
+>(
+    width: usize,
+    end: End,
+    x: &[f64],
+    y: &mut [f64],
+    f: F,
+) -> Result<()> {
+    unsafe {
+        if width == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if x.len() != y.len() {
+            return Err(GSLError::BadLength);
+        }
+        if x.is_empty() {
+            return Ok(());
+        }
+
+        let workspace = guard(gsl_movstat_alloc(width as u64), |workspace| {
+            gsl_movstat_free(workspace);
+        });
+        assert!(!workspace.is_null());
+
+        let gsl_x = gsl_vector::from(x);
+        let mut gsl_y = gsl_vector::from(y);
+        GSLError::from_raw(f(end as _, &gsl_x, &mut gsl_y, *workspace))
+    }
+}
+
+#[test]
+fn test_movstat() {
+    disable_error_handler();
+
+    let x = [1.0, 2.0, 3.0, 4.0, 100.0, 6.0, 7.0];
+    let mut y = [0.0; 7];
+
+    mean(3, End::Truncate, &x, &mut y).unwrap();
+    assert_eq!(y[0], 1.5);
+    assert_eq!(y[2], 3.0);
+
+    mean(3, End::PadZero, &x, &mut y).unwrap();
+    assert_eq!(y[0], 1.0);
+
+    mean(3, End::PadValue, &x, &mut y).unwrap();
+    approx::assert_abs_diff_eq!(y[0], 4.0 / 3.0, epsilon = 1.0e-12);
+
+    median(3, End::Truncate, &x, &mut y).unwrap();
+    assert_eq!(y, [1.5, 2.0, 3.0, 4.0, 6.0, 7.0, 6.5]);
+
+    min(3, End::Truncate, &x, &mut y).unwrap();
+    assert_eq!(y, [1.0, 1.0, 2.0, 3.0, 4.0, 6.0, 6.0]);
+
+    max(3, End::Truncate, &x, &mut y).unwrap();
+    assert_eq!(y, [2.0, 3.0, 4.0, 100.0, 100.0, 100.0, 7.0]);
+
+    sum(3, End::PadZero, &x, &mut y).unwrap();
+    assert_eq!(y[1], 6.0);
+
+    variance(3, End::Truncate, &x, &mut y).unwrap();
+    approx::assert_abs_diff_eq!(y[1], 1.0, epsilon = 1.0e-12);
+
+    let mut m = [0.0; 7];
+    mad0(3, End::Truncate, &x, &mut m, &mut y).unwrap();
+    assert_eq!(m[3], 4.0);
+    assert_eq!(y[3], 1.0);
+
+    // Robust estimators ignore the outlier in most windows
+    qn(5, End::Truncate, &x, &mut y).unwrap();
+    assert!(y[3] < 10.0);
+    qqr(5, End::Truncate, 0.25, &x, &mut y).unwrap();
+    assert!(y.iter().all(|y| y.is_finite()));
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let x = [1.0, 2.0, 3.0];
+
+    // Length mismatch
+    mean(3, End::Truncate, &x, &mut [0.0; 2]).unwrap_err();
+    mad(3, End::Truncate, &x, &mut [0.0; 2], &mut [0.0; 3]).unwrap_err();
+
+    // Empty window
+    mean(0, End::Truncate, &x, &mut [0.0; 3]).unwrap_err();
+    mad(0, End::Truncate, &[], &mut [], &mut []).unwrap_err();
+
+    // Empty input
+    mad(3, End::Truncate, &[], &mut [], &mut []).unwrap();
+    mad0(3, End::Truncate, &[], &mut [], &mut []).unwrap();
+
+    // Quantile out of range
+    qqr(3, End::Truncate, 0.75, &x, &mut [0.0; 3]).unwrap_err();
+}
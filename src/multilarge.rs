@@ -0,0 +1,326 @@
+/*
+    multilarge.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::linear_fit::{design_matrix, LCurve};
+use crate::*;
+use std::fmt;
+
+/*
+
+    The data is accumulated in blocks of rows, so only a `p x p` matrix is kept in memory
+    regardless of the number of observations.
+
+    The normal equations are fastest but square the condition number of the system,
+    TSQR (tall skinny QR) is numerically stable and preferable for ill-conditioned systems.
+
+*/
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LargeLinearMethod {
+    NormalEquations,
+    Tsqr,
+}
+
+/// Solution of a large linear least squares problem
+#[derive(Clone, Debug, PartialEq)]
+pub struct LargeLinearSolution {
+    pub params: Box<[f64]>,
+    /// Residual norm `||y - X c||`
+    pub residual_norm: f64,
+    /// Solution norm `||L c||`
+    pub solution_norm: f64,
+}
+
+/// Linear least squares fit with `p` parameters, minimizing `||y - X c||^2 + λ^2 ||L c||^2`
+/// for a diagonal regularization matrix `L`, with the data accumulated in blocks
+pub struct LargeLinearFit {
+    p: usize,
+    l: Option<Vector>,
+    workspace: *mut gsl_multilarge_linear_workspace,
+}
+
+impl LargeLinearFit {
+    /// Ridge regularization, with `L = I`
+    pub fn new(method: LargeLinearMethod, p: usize) -> Result<Self> {
+        Self::alloc(method, p, None)
+    }
+
+    /// Tikhonov regularization with diagonal `L`, which allows penalizing parameters individually.
+    /// All elements of `l` have to be nonzero.
+    pub fn with_diagonal(method: LargeLinearMethod, l: &[f64]) -> Result<Self> {
+        if l.iter().any(|&l| l == 0.0 || !l.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Self::alloc(method, l.len(), Some(l))
+    }
+
+    fn alloc(method: LargeLinearMethod, p: usize, l: Option<&[f64]>) -> Result<Self> {
+        unsafe {
+            if p == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let method = match method {
+                LargeLinearMethod::NormalEquations => gsl_multilarge_linear_normal,
+                LargeLinearMethod::Tsqr => gsl_multilarge_linear_tsqr,
+            };
+
+            let workspace = gsl_multilarge_linear_alloc(method, p as u64);
+            assert!(!workspace.is_null());
+
+            Ok(LargeLinearFit {
+                p,
+                l: l.map(|l| Vector::new(l.iter().copied())),
+                workspace,
+            })
+        }
+    }
+
+    /// Number of parameters
+    pub fn p(&self) -> usize {
+        self.p
+    }
+
+    /// Adds a block of data, where `f` evaluates the `p` basis functions at every `x`
+    pub fn accumulate<X, F: FnMut(&X, &mut [f64]) -> Result<()>>(
+        &mut self,
+        x: &[X],
+        y: &[f64],
+        f: F,
+    ) -> Result<()> {
+        unsafe {
+            if x.len() != y.len() {
+                return Err(GSLError::BadLength);
+            }
+
+            let mut system = design_matrix(self.p, x, f)?;
+            let mut gsl_y = Vector::new(y.iter().copied());
+
+            // Transform to standard form, X L^-1
+            if let Some(l) = &self.l {
+                let mut standard_system = Matrix::zeroes(x.len(), self.p);
+                let mut standard_y = Vector::zeroes(y.len());
+                GSLError::from_raw(gsl_multilarge_linear_stdform1(
+                    l.as_gsl(),
+                    system.as_gsl(),
+                    gsl_y.as_gsl(),
+                    standard_system.as_gsl_mut(),
+                    standard_y.as_gsl_mut(),
+                    self.workspace,
+                ))?;
+                system = standard_system;
+                gsl_y = standard_y;
+            }
+
+            // The block is destroyed by TSQR
+            GSLError::from_raw(gsl_multilarge_linear_accumulate(
+                system.as_gsl_mut(),
+                gsl_y.as_gsl_mut(),
+                self.workspace,
+            ))
+        }
+    }
+
+    /// Discards all accumulated data
+    pub fn reset(&mut self) -> Result<()> {
+        unsafe { GSLError::from_raw(gsl_multilarge_linear_reset(self.workspace)) }
+    }
+
+    /// Solution for regularization parameter `λ >= 0`
+    pub fn solve(&mut self, lambda: f64) -> Result<LargeLinearSolution> {
+        unsafe {
+            if !(lambda >= 0.0 && lambda.is_finite()) {
+                return Err(GSLError::Domain);
+            }
+
+            let mut standard_c = Vector::zeroes(self.p);
+            let mut residual_norm = 0.0f64;
+            let mut solution_norm = 0.0f64;
+            GSLError::from_raw(gsl_multilarge_linear_solve(
+                lambda,
+                standard_c.as_gsl_mut(),
+                &mut residual_norm,
+                &mut solution_norm,
+                self.workspace,
+            ))?;
+
+            // Transform back to the original parameters, c = L^-1 c_s
+            let mut c = standard_c.clone();
+            if let Some(l) = &self.l {
+                GSLError::from_raw(gsl_multilarge_linear_genform1(
+                    l.as_gsl(),
+                    standard_c.as_gsl(),
+                    c.as_gsl_mut(),
+                    self.workspace,
+                ))?;
+            }
+
+            Ok(LargeLinearSolution {
+                params: c.to_boxed_slice(),
+                residual_norm,
+                solution_norm,
+            })
+        }
+    }
+
+    /// Estimate of the reciprocal condition number of the accumulated system (in standard form)
+    pub fn rcond(&mut self) -> Result<f64> {
+        unsafe {
+            let mut rcond = 0.0f64;
+            GSLError::from_raw(gsl_multilarge_linear_rcond(&mut rcond, self.workspace))?;
+            Ok(rcond)
+        }
+    }
+
+    /// L-curve of `n >= 3` regularization parameters, with its corner of maximum curvature
+    pub fn lcurve(&mut self, n: usize) -> Result<LCurve> {
+        unsafe {
+            if n < 3 {
+                return Err(GSLError::Invalid);
+            }
+
+            let mut lambda = Vector::zeroes(n);
+            let mut rho = Vector::zeroes(n);
+            let mut eta = Vector::zeroes(n);
+            GSLError::from_raw(gsl_multilarge_linear_lcurve(
+                lambda.as_gsl_mut(),
+                rho.as_gsl_mut(),
+                eta.as_gsl_mut(),
+                self.workspace,
+            ))?;
+
+            let mut corner = 0;
+            GSLError::from_raw(gsl_multifit_linear_lcorner(
+                rho.as_gsl(),
+                eta.as_gsl(),
+                &mut corner,
+            ))?;
+
+            Ok(LCurve {
+                lambda: lambda.to_boxed_slice(),
+                residual_norm: rho.to_boxed_slice(),
+                solution_norm: eta.to_boxed_slice(),
+                corner: corner as usize,
+            })
+        }
+    }
+}
+
+impl fmt::Debug for LargeLinearFit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LargeLinearFit")
+            .field("p", &self.p)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for LargeLinearFit {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_multilarge_linear_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for LargeLinearFit {}
+unsafe impl Sync for LargeLinearFit {}
+
+#[test]
+fn test_large_linear_fit() {
+    disable_error_handler();
+    fastrand::seed(3);
+
+    let x = (0..1000).map(|x| x as f64 / 100.0).collect::<Vec<_>>();
+    let y = x
+        .iter()
+        .map(|&x| 10.0 + 2.0 * x - 0.5 * x * x + 0.1 * (fastrand::f64() * 2.0 - 1.0))
+        .collect::<Vec<_>>();
+    let basis = |&x: &f64, p: &mut [f64]| {
+        p.copy_from_slice(&[1.0, x, x * x]);
+        Ok(())
+    };
+
+    let fit = linear_fit::linear_fit(3, &x, &y, basis).unwrap();
+
+    for method in [LargeLinearMethod::NormalEquations, LargeLinearMethod::Tsqr] {
+        let mut large = LargeLinearFit::new(method, 3).unwrap();
+        let mut tikhonov = LargeLinearFit::with_diagonal(method, &[1.0, 2.0, 4.0]).unwrap();
+        for (x, y) in x.chunks(128).zip(y.chunks(128)) {
+            large.accumulate(x, y, basis).unwrap();
+            tikhonov.accumulate(x, y, basis).unwrap();
+        }
+
+        // Same solution as fitting all data at once
+        for regression in [&mut large, &mut tikhonov] {
+            let solution = regression.solve(0.0).unwrap();
+            for i in 0..3 {
+                approx::assert_abs_diff_eq!(solution.params[i], fit.params[i], epsilon = 1.0e-8);
+            }
+            approx::assert_relative_eq!(
+                solution.residual_norm.powi(2),
+                fit.residual_squared,
+                max_relative = 1.0e-6
+            );
+        }
+
+        let rcond = large.rcond().unwrap();
+        assert!(rcond > 0.0 && rcond < 1.0);
+
+        // Regularization shrinks the solution
+        let weak = large.solve(1.0).unwrap();
+        let strong = large.solve(1000.0).unwrap();
+        assert!(strong.solution_norm < weak.solution_norm);
+        assert!(strong.residual_norm > weak.residual_norm);
+
+        let lcurve = large.lcurve(10).unwrap();
+        assert_eq!(lcurve.lambda.len(), 10);
+        assert!(lcurve.corner < 10);
+
+        // Fit a new dataset
+        large.reset().unwrap();
+        large.accumulate(&x, &y, basis).unwrap();
+        let solution = large.solve(0.0).unwrap();
+        approx::assert_abs_diff_eq!(solution.params[2], -0.5, epsilon = 1.0e-3);
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // No parameters
+    LargeLinearFit::new(LargeLinearMethod::Tsqr, 0).unwrap_err();
+    LargeLinearFit::with_diagonal(LargeLinearMethod::Tsqr, &[]).unwrap_err();
+
+    // Singular regularization matrix
+    LargeLinearFit::with_diagonal(LargeLinearMethod::Tsqr, &[1.0, 0.0]).unwrap_err();
+
+    let mut fit = LargeLinearFit::new(LargeLinearMethod::NormalEquations, 1).unwrap();
+
+    // Length mismatch
+    fit.accumulate(&[1.0, 2.0], &[1.0], |&_, p| {
+        p[0] = 1.0;
+        Ok(())
+    })
+    .unwrap_err();
+
+    // Negative regularization parameter
+    fit.solve(-1.0).unwrap_err();
+}
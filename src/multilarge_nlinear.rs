@@ -0,0 +1,660 @@
+/*
+    multilarge_nlinear.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::nonlinear_fit::{FiniteDifference, FitCallback, FitResult, Scaling};
+use crate::*;
+use drop_guard::guard;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/*
+
+    Unlike `nonlinear_fit`, the `n x p` Jacobian is never stored.
+    The solver only requests the products `J u` and `J^T u`, and the `p x p` matrix `J^T J`,
+    which are accumulated one observation at a time from the rows of the Jacobian.
+    With the Steihaug-Toint method and `LargeLinearSolver::None`, `J^T J` isn't needed either,
+    so the memory use is linear in the number of observations.
+
+    The rows of the Jacobian are computed with finite differences of the model,
+    or with a user supplied gradient of the model with respect to the parameters.
+
+*/
+
+pub type LargeHyperParams = gsl_multilarge_nlinear_parameters;
+
+/// Least squares fit of `y ≈ f(x, params)` for a large number of observations.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
+pub fn large_nonlinear_fit<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: usize>(
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    large_nonlinear_fit_with_policy(policy::policy(), p0, x, y, f)
+}
+
+/// `large_nonlinear_fit` with `policy` instead of the policy of this thread
+pub fn large_nonlinear_fit_with_policy<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: usize>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        large_nonlinear_fit_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            LargeHyperParams::default(),
+            p0,
+            x,
+            y,
+            f,
+            None::<fn(&X, [f64; P]) -> Result<[f64; P]>>,
+            None::<fn(FitCallback<P>)>,
+        )
+    })
+}
+
+/// The optional `jacobian` returns the gradient `∂f(x, params) / ∂params` of a single observation,
+/// otherwise it is approximated with finite differences of `f`.
+/// The optional `callback` is called as for `nonlinear_fit_ext`, also once before the first iteration.
+pub fn large_nonlinear_fit_ext<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    hyper_params: LargeHyperParams,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+    jacobian: Option<J>,
+    mut callback: Option<C>,
+) -> Result<FitResult<P>> {
+    unsafe {
+        if P == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        if x.len() != y.len() {
+            return Err(GSLError::BadLength);
+        }
+
+        // Amount of datapoints
+        let n = x.len() as u64;
+
+        // Allocate workspace
+        let workspace = guard(
+            gsl_multilarge_nlinear_alloc(gsl_multilarge_nlinear_trust, &hyper_params, n, P as u64),
+            |workspace| {
+                gsl_multilarge_nlinear_free(workspace);
+            },
+        );
+        assert!(!workspace.is_null());
+
+        // Information we need inside the trampolines
+        let mut ffi_params = FFIParams {
+            f,
+            jacobian,
+            central: hyper_params.fdtype
+                == gsl_multilarge_nlinear_fdtype_GSL_MULTILARGE_NLINEAR_CTRDIFF,
+            h: hyper_params.h_df,
+            x,
+            y,
+            error: GSL_SUCCESS,
+            panicked: false,
+        };
+
+        let mut fdf = gsl_multilarge_nlinear_fdf {
+            f: Some(large_fit_f::<X, F, J, P>),
+            df: Some(large_fit_df::<X, F, J, P>),
+            fvv: None,
+            n,
+            p: P as u64,
+            params: &mut ffi_params as *mut _ as *mut _,
+            nevalf: 0,
+            nevaldfu: 0,
+            nevaldf2: 0,
+            nevalfvv: 0,
+        };
+
+        // Init workspace
+        let param_guess = gsl_vector::from(p0.as_slice());
+        let status = gsl_multilarge_nlinear_init(&param_guess, &mut fdf, *workspace);
+        ffi_params.check(status)?;
+
+        // Initial cost function chi^2_0
+        let mut chisq0 = 0.0f64;
+        GSLError::from_raw(gsl_blas_ddot(
+            gsl_multilarge_nlinear_residual(*workspace),
+            gsl_multilarge_nlinear_residual(*workspace),
+            &mut chisq0,
+        ))?;
+
+        let mut _info = 0i32;
+        let status = gsl_multilarge_nlinear_driver(
+            max_iter as u64,
+            xtol,
+            gtol,
+            ftol,
+            if callback.is_some() {
+                Some(large_fit_callback::<C, P>)
+            } else {
+                None
+            },
+            &mut callback as *mut _ as *mut c_void,
+            &mut _info,
+            *workspace,
+        );
+        policy::converged(ffi_params.check(status))?;
+
+        // Numerical fit results
+        let fit_result = gsl_multilarge_nlinear_position(*workspace);
+        let fit_residuals = gsl_multilarge_nlinear_residual(*workspace);
+        let fit_niter = gsl_multilarge_nlinear_niter(*workspace);
+        let fit_neval_f = fdf.nevalf;
+
+        // Final cost function chi^2_1
+        let mut chisq1 = 0.0f64;
+        GSLError::from_raw(gsl_blas_ddot(fit_residuals, fit_residuals, &mut chisq1))?;
+
+        // Variance-covariance matrix, (J^T J)^-1 scaled by the residual variance
+        let mut fit_covariance = Matrix::zeroes(P, P);
+        let status = gsl_multilarge_nlinear_covar(fit_covariance.as_gsl_mut(), *workspace);
+        ffi_params.check(status)?;
+        GSLError::from_raw(gsl_matrix_scale(
+            fit_covariance.as_gsl_mut(),
+            chisq1 / (n as f64 - P as f64),
+        ))?;
+
+        // Calculate mean and total sum of squares wrt mean
+        let mean = stats::mean(y);
+        let tss = gsl_stats_tss_m(y.as_ptr(), 1, n, mean);
+
+        Ok(FitResult {
+            params: gsl_vector::to_array(fit_result),
+            covariance: fit_covariance.to_2d_array(),
+            niter: fit_niter,
+            neval_f: fit_neval_f,
+            initial_residual_squared: chisq0,
+            final_residuals: gsl_vector::to_boxed_slice(fit_residuals),
+            final_residual_squared: chisq1,
+            mean,
+            r_squared: 1.0 - chisq1 / tss,
+        })
+    }
+}
+
+impl Default for LargeHyperParams {
+    fn default() -> Self {
+        unsafe { gsl_multilarge_nlinear_default_parameters() }
+    }
+}
+
+/// Method for solving the trust region subproblem in every iteration
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LargeTrustRegion {
+    LevenbergMarquardt,
+    /// Levenberg-Marquardt with geodesic acceleration
+    LevenbergMarquardtAccel,
+    Dogleg,
+    DoubleDogleg,
+    /// Two dimensional subspace
+    Subspace2D,
+    /// Steihaug-Toint conjugate gradient, which only needs Jacobian-vector products
+    SteihaugToint,
+}
+
+/// Solver of the linear system `J^T J`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LargeLinearSolver {
+    Cholesky,
+    /// Modified Cholesky, which also handles a singular or indefinite `J^T J`
+    ModifiedCholesky,
+    /// No linear system, for the Steihaug-Toint method
+    None,
+}
+
+/// Configuration of the large scale trust region solver, which converts into `LargeHyperParams`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LargeFitParams {
+    pub trust_region: LargeTrustRegion,
+    pub scaling: Scaling,
+    pub solver: LargeLinearSolver,
+    pub finite_difference: FiniteDifference,
+    /// Relative step size of the finite differences for the Jacobian
+    pub fd_step: f64,
+    /// Step size of the finite differences for the second directional derivative,
+    /// used for geodesic acceleration
+    pub fvv_step: f64,
+    /// Maximum ratio `|a| / |v|` of acceleration and velocity for geodesic acceleration
+    pub avmax: f64,
+    /// Factor by which the trust region grows after a successful step
+    pub factor_up: f64,
+    /// Factor by which the trust region shrinks after a rejected step
+    pub factor_down: f64,
+    /// Maximum number of conjugate gradient iterations of the Steihaug-Toint method
+    pub cg_max_iter: usize,
+    /// Tolerance of the conjugate gradient iterations of the Steihaug-Toint method
+    pub cg_tol: f64,
+}
+
+impl Default for LargeFitParams {
+    fn default() -> Self {
+        let defaults = LargeHyperParams::default();
+        LargeFitParams {
+            trust_region: LargeTrustRegion::LevenbergMarquardt,
+            scaling: Scaling::More,
+            solver: LargeLinearSolver::Cholesky,
+            finite_difference: FiniteDifference::Forward,
+            fd_step: defaults.h_df,
+            fvv_step: defaults.h_fvv,
+            avmax: defaults.avmax,
+            factor_up: defaults.factor_up,
+            factor_down: defaults.factor_down,
+            cg_max_iter: defaults.max_iter as usize,
+            cg_tol: defaults.tol,
+        }
+    }
+}
+
+impl From<LargeFitParams> for LargeHyperParams {
+    fn from(params: LargeFitParams) -> Self {
+        unsafe {
+            LargeHyperParams {
+                trs: match params.trust_region {
+                    LargeTrustRegion::LevenbergMarquardt => gsl_multilarge_nlinear_trs_lm,
+                    LargeTrustRegion::LevenbergMarquardtAccel => gsl_multilarge_nlinear_trs_lmaccel,
+                    LargeTrustRegion::Dogleg => gsl_multilarge_nlinear_trs_dogleg,
+                    LargeTrustRegion::DoubleDogleg => gsl_multilarge_nlinear_trs_ddogleg,
+                    LargeTrustRegion::Subspace2D => gsl_multilarge_nlinear_trs_subspace2D,
+                    LargeTrustRegion::SteihaugToint => gsl_multilarge_nlinear_trs_cgst,
+                },
+                scale: match params.scaling {
+                    Scaling::More => gsl_multilarge_nlinear_scale_more,
+                    Scaling::Levenberg => gsl_multilarge_nlinear_scale_levenberg,
+                    Scaling::Marquardt => gsl_multilarge_nlinear_scale_marquardt,
+                },
+                solver: match params.solver {
+                    LargeLinearSolver::Cholesky => gsl_multilarge_nlinear_solver_cholesky,
+                    LargeLinearSolver::ModifiedCholesky => gsl_multilarge_nlinear_solver_mcholesky,
+                    LargeLinearSolver::None => gsl_multilarge_nlinear_solver_none,
+                },
+                fdtype: match params.finite_difference {
+                    FiniteDifference::Forward => {
+                        gsl_multilarge_nlinear_fdtype_GSL_MULTILARGE_NLINEAR_FWDIFF
+                    }
+                    FiniteDifference::Central => {
+                        gsl_multilarge_nlinear_fdtype_GSL_MULTILARGE_NLINEAR_CTRDIFF
+                    }
+                },
+                factor_up: params.factor_up,
+                factor_down: params.factor_down,
+                avmax: params.avmax,
+                h_df: params.fd_step,
+                h_fvv: params.fvv_step,
+                max_iter: params.cg_max_iter as u64,
+                tol: params.cg_tol,
+            }
+        }
+    }
+}
+
+struct FFIParams<'a, 'b, X, F, J> {
+    f: F,
+    jacobian: Option<J>,
+    central: bool,
+    h: f64,
+    x: &'a [X],
+    y: &'b [f64],
+    error: i32,
+    panicked: bool,
+}
+
+impl<'a, 'b, X, F, J> FFIParams<'a, 'b, X, F, J> {
+    /// Row `i` of the Jacobian
+    fn row<const P: usize>(&mut self, i: usize, params: [f64; P]) -> Result<[f64; P]>
+    where
+        F: FnMut(&X, [f64; P]) -> Result<f64>,
+        J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    {
+        let x = &self.x[i];
+        if let Some(jacobian) = &mut self.jacobian {
+            return jacobian(x, params);
+        }
+
+        // Same steps as GSL uses for the full Jacobian
+        let mut row = [0.0; P];
+        let center = if self.central {
+            0.0
+        } else {
+            (self.f)(x, params)?
+        };
+        for j in 0..P {
+            let h = if params[j] == 0.0 {
+                self.h
+            } else {
+                self.h * params[j].abs()
+            };
+            let mut shifted = params;
+            if self.central {
+                shifted[j] = params[j] + 0.5 * h;
+                let upper = (self.f)(x, shifted)?;
+                shifted[j] = params[j] - 0.5 * h;
+                row[j] = (upper - (self.f)(x, shifted)?) / h;
+            } else {
+                shifted[j] = params[j] + h;
+                row[j] = ((self.f)(x, shifted)? - center) / h;
+            }
+        }
+        Ok(row)
+    }
+
+    /// Runs `body`, storing user errors and panics for `check`
+    fn guarded<B: FnOnce(&mut Self) -> Result<()>>(&mut self, body: B) -> i32 {
+        match catch_unwind(AssertUnwindSafe(|| body(self))) {
+            Ok(Ok(())) => GSL_SUCCESS,
+            Ok(Err(e)) => {
+                let e = e.into();
+                self.error = e;
+                e
+            }
+            Err(_) => {
+                self.panicked = true;
+                GSL_EBADFUNC
+            }
+        }
+    }
+
+    /// Gives user errors priority over the status returned by GSL
+    fn check(&self, status: i32) -> Result<()> {
+        if self.panicked {
+            return Err(GSLError::BadFunction);
+        }
+        GSLError::from_raw(self.error)?;
+        GSLError::from_raw(status)
+    }
+}
+
+unsafe extern "C" fn large_fit_f<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    const P: usize,
+>(
+    params: *const gsl_vector,
+    ffi_params: *mut c_void,
+    out: *mut gsl_vector,
+) -> i32 {
+    let ffi_params: &mut FFIParams<'_, '_, X, F, J> = &mut *(ffi_params as *mut _);
+    let params = gsl_vector::to_array(params);
+
+    ffi_params.guarded(|ffi_params| {
+        for (i, (x, y)) in ffi_params.x.iter().zip(ffi_params.y.iter()).enumerate() {
+            let err = (ffi_params.f)(x, params)? - y;
+            gsl_vector_set(out, i as u64, err);
+        }
+        Ok(())
+    })
+}
+
+/// Computes `v = J u` or `v = J^T u`, and `J^T J` if requested
+unsafe extern "C" fn large_fit_df<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    const P: usize,
+>(
+    trans: CBLAS_TRANSPOSE_t,
+    params: *const gsl_vector,
+    u: *const gsl_vector,
+    ffi_params: *mut c_void,
+    v: *mut gsl_vector,
+    jtj: *mut gsl_matrix,
+) -> i32 {
+    let ffi_params: &mut FFIParams<'_, '_, X, F, J> = &mut *(ffi_params as *mut _);
+    let params = gsl_vector::to_array(params);
+    let product = !u.is_null() && !v.is_null();
+    let transpose = trans != CBLAS_TRANSPOSE_CblasNoTrans;
+
+    let mut jtu = [0.0; P];
+    let mut normal = [[0.0; P]; P];
+
+    let status = ffi_params.guarded(|ffi_params| {
+        for i in 0..ffi_params.x.len() {
+            let row = ffi_params.row(i, params)?;
+
+            if product {
+                if transpose {
+                    blas::axpy(gsl_vector_get(u, i as u64), &row, &mut jtu)?;
+                } else {
+                    let mut ju = 0.0;
+                    for (j, r) in row.iter().enumerate() {
+                        ju += r * gsl_vector_get(u, j as u64);
+                    }
+                    gsl_vector_set(v, i as u64, ju);
+                }
+            }
+
+            if !jtj.is_null() {
+                for (normal, &rj) in normal.iter_mut().zip(row.iter()) {
+                    blas::axpy(rj, &row, normal)?;
+                }
+            }
+        }
+        Ok(())
+    });
+    if status != GSL_SUCCESS {
+        return status;
+    }
+
+    if product && transpose {
+        for (j, &value) in jtu.iter().enumerate() {
+            gsl_vector_set(v, j as u64, value);
+        }
+    }
+    if !jtj.is_null() {
+        for (j, row) in normal.iter().enumerate() {
+            for (k, &value) in row.iter().enumerate() {
+                gsl_matrix_set(jtj, j as u64, k as u64, value);
+            }
+        }
+    }
+
+    GSL_SUCCESS
+}
+
+unsafe extern "C" fn large_fit_callback<C: FnMut(FitCallback<P>), const P: usize>(
+    iter: u64,
+    callback: *mut c_void,
+    workspace: *const gsl_multilarge_nlinear_workspace,
+) {
+    let callback: &mut Option<C> = &mut *(callback as *mut _);
+    let callback = match callback {
+        Some(callback) => callback,
+        None => std::hint::unreachable_unchecked(),
+    };
+
+    let params = gsl_multilarge_nlinear_position(workspace);
+
+    let residuals = gsl_multilarge_nlinear_residual(workspace);
+    let mut chisq = 0.0f64;
+    let _ = gsl_blas_ddot(residuals, residuals, &mut chisq);
+
+    // Not every method provides a condition number
+    let mut rcond = 0.0;
+    if gsl_multilarge_nlinear_rcond(&mut rcond, workspace) != GSL_SUCCESS {
+        rcond = f64::NAN;
+    }
+
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        callback(FitCallback {
+            iter: iter as usize,
+            params: gsl_vector::to_array(params),
+            cond: 1.0 / rcond,
+            rcond,
+            residual_norm: chisq.sqrt(),
+            residual_squared: chisq,
+        });
+    }));
+}
+
+#[test]
+fn test_large_nlfit() {
+    disable_error_handler();
+    fastrand::seed(5);
+
+    fn model(a: f64, b: f64, c: f64, x: f64) -> f64 {
+        a * (-b * x).exp() + c
+    }
+
+    let x = (0..20000).map(|x| x as f64 / 2000.0).collect::<Vec<_>>();
+    let y = x
+        .iter()
+        .map(|&x| model(5.0, 0.8, 1.0, x) + 0.01 * (fastrand::f64() - 0.5))
+        .collect::<Vec<_>>();
+
+    let reference = nonlinear_fit::nonlinear_fit([1.0, 1.0, 0.0], &x, &y, |&x, [a, b, c]| {
+        Ok(model(a, b, c, x))
+    })
+    .unwrap();
+
+    let fit = large_nonlinear_fit([1.0, 1.0, 0.0], &x, &y, |&x, [a, b, c]| {
+        Ok(model(a, b, c, x))
+    })
+    .unwrap();
+    for i in 0..3 {
+        approx::assert_abs_diff_eq!(fit.params[i], reference.params[i], epsilon = 1.0e-6);
+        approx::assert_relative_eq!(
+            fit.uncertainty(i),
+            reference.uncertainty(i),
+            max_relative = 1.0e-3
+        );
+    }
+    approx::assert_relative_eq!(
+        fit.final_residual_squared,
+        reference.final_residual_squared,
+        max_relative = 1.0e-6
+    );
+
+    // Analytic gradient and every method
+    for (trust_region, solver) in [
+        (
+            LargeTrustRegion::LevenbergMarquardt,
+            LargeLinearSolver::Cholesky,
+        ),
+        (
+            LargeTrustRegion::LevenbergMarquardtAccel,
+            LargeLinearSolver::Cholesky,
+        ),
+        (
+            LargeTrustRegion::Dogleg,
+            LargeLinearSolver::ModifiedCholesky,
+        ),
+        (LargeTrustRegion::DoubleDogleg, LargeLinearSolver::Cholesky),
+        (LargeTrustRegion::Subspace2D, LargeLinearSolver::Cholesky),
+        (LargeTrustRegion::SteihaugToint, LargeLinearSolver::None),
+    ] {
+        let params = LargeFitParams {
+            trust_region,
+            solver,
+            ..LargeFitParams::default()
+        };
+
+        let mut iterations = 0;
+        let fit = large_nonlinear_fit_ext(
+            1000,
+            1.0e-9,
+            1.0e-9,
+            1.0e-9,
+            params.into(),
+            [1.0, 1.0, 0.0],
+            &x,
+            &y,
+            |&x, [a, b, c]| Ok(model(a, b, c, x)),
+            Some(|&x: &f64, [a, b, _]: [f64; 3]| {
+                let e = (-b * x).exp();
+                Ok([e, -a * x * e, 1.0])
+            }),
+            Some(|_| iterations += 1),
+        )
+        .unwrap();
+
+        // Also called before the first iteration
+        assert_eq!(iterations as u64, fit.niter + 1);
+        for i in 0..3 {
+            approx::assert_abs_diff_eq!(fit.params[i], reference.params[i], epsilon = 1.0e-5);
+        }
+    }
+
+    // Defaults match GSL
+    let defaults = LargeHyperParams::from(LargeFitParams::default());
+    let gsl_defaults = LargeHyperParams::default();
+    assert_eq!(defaults.trs, gsl_defaults.trs);
+    assert_eq!(defaults.scale, gsl_defaults.scale);
+    assert_eq!(defaults.solver, gsl_defaults.solver);
+    assert_eq!(defaults.fdtype, gsl_defaults.fdtype);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // No parameters
+    large_nonlinear_fit([], &[1.0, 2.0], &[1.0, 2.0], |_, []| Ok(1.0)).unwrap_err();
+
+    // No data
+    large_nonlinear_fit([1.0], &[] as &[f64], &[], |_, [a]| Ok(a)).unwrap_err();
+
+    // Length mismatch
+    large_nonlinear_fit([1.0], &[1.0, 2.0], &[1.0], |_, [a]| Ok(a)).unwrap_err();
+
+    // Error in the model
+    large_nonlinear_fit([1.0], &[1.0, 2.0], &[1.0, 2.0], |_, _| {
+        Err(GSLError::Domain)
+    })
+    .unwrap_err();
+
+    // Panic in the gradient
+    large_nonlinear_fit_ext(
+        100,
+        1.0e-9,
+        1.0e-9,
+        1.0e-9,
+        LargeHyperParams::default(),
+        [1.0],
+        &[1.0, 2.0],
+        &[1.0, 2.0],
+        |&x, [a]| Ok(a * x),
+        Some(|_: &f64, _: [f64; 1]| -> Result<[f64; 1]> { panic!() }),
+        None::<fn(FitCallback<1>)>,
+    )
+    .unwrap_err();
+}
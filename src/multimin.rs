@@ -0,0 +1,496 @@
+/*
+    multimin.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::dual::Dual;
+use crate::*;
+use drop_guard::guard;
+use std::cell::RefCell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/*
+
+    Minimization of functions of several variables.
+
+    The Nelder-Mead simplex method only needs function values,
+    and converges when the size of the simplex drops below `epsabs`.
+    The gradient methods converge when the norm of the gradient drops below `epsabs`.
+
+    A panic in `f` or in the gradient is turned into NaN,
+    which makes the minimizer fail.
+
+*/
+
+/// Nelder-Mead minimization of `f` starting from `x0`,
+/// with initial simplex steps `step` along every coordinate.
+/// Uses `epsabs` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 1000.
+pub fn multimin<F: FnMut(&[f64]) -> f64>(x0: &[f64], step: &[f64], f: F) -> Result<MultiminResult> {
+    multimin_with_policy(policy::policy(), x0, step, f)
+}
+
+/// `multimin` with `policy` instead of the policy of this thread
+pub fn multimin_with_policy<F: FnMut(&[f64]) -> f64>(
+    policy: policy::NumericPolicy,
+    x0: &[f64],
+    step: &[f64],
+    f: F,
+) -> Result<MultiminResult> {
+    policy::with_policy(policy, || {
+        let (epsabs, _) = policy.tolerances(1.0e-9, 0.0);
+        multimin_ext(policy.iterations(1000), epsabs, x0, step, f, |_| {})
+    })
+}
+
+pub fn multimin_ext<F: FnMut(&[f64]) -> f64, C: FnMut(MultiminCallback)>(
+    max_iter: usize,
+    epsabs: f64,
+    x0: &[f64],
+    step: &[f64],
+    mut f: F,
+    mut callback: C,
+) -> Result<MultiminResult> {
+    unsafe {
+        if x0.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        if x0.len() != step.len() {
+            return Err(GSLError::BadLength);
+        }
+
+        let n = x0.len();
+        let minimizer = guard(
+            gsl_multimin_fminimizer_alloc(gsl_multimin_fminimizer_nmsimplex2, n as u64),
+            |minimizer| {
+                gsl_multimin_fminimizer_free(minimizer);
+            },
+        );
+        assert!(!minimizer.is_null());
+
+        let mut gsl_f = gsl_multimin_function_struct {
+            f: Some(multimin_f::<F>),
+            n: n as u64,
+            params: &mut f as *mut _ as *mut _,
+        };
+
+        let gsl_x0 = gsl_vector::from(x0);
+        let gsl_step = gsl_vector::from(step);
+        GSLError::from_raw(gsl_multimin_fminimizer_set(
+            *minimizer, &mut gsl_f, &gsl_x0, &gsl_step,
+        ))?;
+
+        let mut iter = 0;
+        loop {
+            GSLError::from_raw(gsl_multimin_fminimizer_iterate(*minimizer))?;
+
+            let x = gsl_vector::to_boxed_slice(gsl_multimin_fminimizer_x(*minimizer));
+            let minimum = gsl_multimin_fminimizer_minimum(*minimizer);
+            let size = gsl_multimin_fminimizer_size(*minimizer);
+
+            callback(MultiminCallback {
+                iter,
+                x: x.clone(),
+                f: minimum,
+                size,
+            });
+
+            iter += 1;
+            if gsl_multimin_test_size(size, epsabs) == GSL_SUCCESS {
+                return Ok(MultiminResult {
+                    x,
+                    f: minimum,
+                    niter: iter,
+                });
+            }
+
+            if iter >= max_iter {
+                policy::converged(Err(GSLError::MaxIteration))?;
+                return Ok(MultiminResult {
+                    x,
+                    f: minimum,
+                    niter: iter,
+                });
+            }
+        }
+    }
+}
+
+/// Minimization algorithm using the gradient
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GradientMethod {
+    /// Fletcher-Reeves conjugate gradient
+    FletcherReeves,
+    /// Polak-Ribiere conjugate gradient
+    PolakRibiere,
+    /// Broyden-Fletcher-Goldfarb-Shanno
+    Bfgs,
+    /// More efficient implementation of BFGS, usually the best choice
+    Bfgs2,
+    SteepestDescent,
+}
+
+/// BFGS minimization of `f` with gradient `g`, starting from `x0`.
+/// Uses `epsabs` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 1000.
+pub fn multimin_gradient<F: FnMut(&[f64]) -> f64, G: FnMut(&[f64], &mut [f64])>(
+    x0: &[f64],
+    f: F,
+    g: G,
+) -> Result<MultiminResult> {
+    multimin_gradient_with_policy(policy::policy(), x0, f, g)
+}
+
+/// `multimin_gradient` with `policy` instead of the policy of this thread
+pub fn multimin_gradient_with_policy<F: FnMut(&[f64]) -> f64, G: FnMut(&[f64], &mut [f64])>(
+    policy: policy::NumericPolicy,
+    x0: &[f64],
+    f: F,
+    g: G,
+) -> Result<MultiminResult> {
+    policy::with_policy(policy, || {
+        let (epsabs, _) = policy.tolerances(1.0e-9, 0.0);
+        multimin_gradient_ext(
+            policy.iterations(1000),
+            GradientMethod::Bfgs2,
+            0.01,
+            0.1,
+            epsabs,
+            x0,
+            f,
+            g,
+            |_| {},
+        )
+    })
+}
+
+/// `step_size` is the size of the first trial step, and `tol` the accuracy of the line minimizations.
+/// Stops early, with the current point, when no further progress can be made.
+pub fn multimin_gradient_ext<
+    F: FnMut(&[f64]) -> f64,
+    G: FnMut(&[f64], &mut [f64]),
+    C: FnMut(MultiminCallback),
+>(
+    max_iter: usize,
+    method: GradientMethod,
+    step_size: f64,
+    tol: f64,
+    epsabs: f64,
+    x0: &[f64],
+    f: F,
+    g: G,
+    mut callback: C,
+) -> Result<MultiminResult> {
+    unsafe {
+        if x0.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+
+        let n = x0.len();
+        let method = match method {
+            GradientMethod::FletcherReeves => gsl_multimin_fdfminimizer_conjugate_fr,
+            GradientMethod::PolakRibiere => gsl_multimin_fdfminimizer_conjugate_pr,
+            GradientMethod::Bfgs => gsl_multimin_fdfminimizer_vector_bfgs,
+            GradientMethod::Bfgs2 => gsl_multimin_fdfminimizer_vector_bfgs2,
+            GradientMethod::SteepestDescent => gsl_multimin_fdfminimizer_steepest_descent,
+        };
+        let minimizer = guard(
+            gsl_multimin_fdfminimizer_alloc(method, n as u64),
+            |minimizer| {
+                gsl_multimin_fdfminimizer_free(minimizer);
+            },
+        );
+        assert!(!minimizer.is_null());
+
+        let mut ffi_params = FFIParams { f, g };
+        let mut gsl_fdf = gsl_multimin_function_fdf_struct {
+            f: Some(multimin_fdf_f::<F, G>),
+            df: Some(multimin_fdf_df::<F, G>),
+            fdf: Some(multimin_fdf_fdf::<F, G>),
+            n: n as u64,
+            params: &mut ffi_params as *mut _ as *mut _,
+        };
+
+        let gsl_x0 = gsl_vector::from(x0);
+        GSLError::from_raw(gsl_multimin_fdfminimizer_set(
+            *minimizer,
+            &mut gsl_fdf,
+            &gsl_x0,
+            step_size,
+            tol,
+        ))?;
+
+        let mut iter = 0;
+        loop {
+            let status = gsl_multimin_fdfminimizer_iterate(*minimizer);
+
+            let x = gsl_vector::to_boxed_slice(gsl_multimin_fdfminimizer_x(*minimizer));
+            let minimum = gsl_multimin_fdfminimizer_minimum(*minimizer);
+            let gradient = gsl_multimin_fdfminimizer_gradient(*minimizer);
+
+            if status == GSL_ENOPROG {
+                return Ok(MultiminResult {
+                    x,
+                    f: minimum,
+                    niter: iter,
+                });
+            }
+            GSLError::from_raw(status)?;
+
+            callback(MultiminCallback {
+                iter,
+                x: x.clone(),
+                f: minimum,
+                size: gsl_blas_dnrm2(gradient),
+            });
+
+            iter += 1;
+            if gsl_multimin_test_gradient(gradient, epsabs) == GSL_SUCCESS {
+                return Ok(MultiminResult {
+                    x,
+                    f: minimum,
+                    niter: iter,
+                });
+            }
+
+            if iter >= max_iter {
+                policy::converged(Err(GSLError::MaxIteration))?;
+                return Ok(MultiminResult {
+                    x,
+                    f: minimum,
+                    niter: iter,
+                });
+            }
+        }
+    }
+}
+
+/// BFGS minimization of `f` starting from `x0`, with the exact gradient from forward-mode
+/// automatic differentiation. `f` evaluates the function with dual numbers,
+/// e.g. through a function written generically over `Scalar`.
+/// Uses `epsabs` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 1000.
+pub fn multimin_autodiff<F: FnMut([Dual<N>; N]) -> Dual<N>, const N: usize>(
+    x0: [f64; N],
+    f: F,
+) -> Result<MultiminResult> {
+    multimin_autodiff_with_policy(policy::policy(), x0, f)
+}
+
+/// `multimin_autodiff` with `policy` instead of the policy of this thread
+pub fn multimin_autodiff_with_policy<F: FnMut([Dual<N>; N]) -> Dual<N>, const N: usize>(
+    policy: policy::NumericPolicy,
+    x0: [f64; N],
+    f: F,
+) -> Result<MultiminResult> {
+    policy::with_policy(policy, || {
+        let (epsabs, _) = policy.tolerances(1.0e-9, 0.0);
+        multimin_autodiff_ext(
+            policy.iterations(1000),
+            GradientMethod::Bfgs2,
+            0.01,
+            0.1,
+            epsabs,
+            x0,
+            f,
+            |_| {},
+        )
+    })
+}
+
+pub fn multimin_autodiff_ext<
+    F: FnMut([Dual<N>; N]) -> Dual<N>,
+    C: FnMut(MultiminCallback),
+    const N: usize,
+>(
+    max_iter: usize,
+    method: GradientMethod,
+    step_size: f64,
+    tol: f64,
+    epsabs: f64,
+    x0: [f64; N],
+    f: F,
+    callback: C,
+) -> Result<MultiminResult> {
+    let f = RefCell::new(f);
+    let eval = |x: &[f64]| (f.borrow_mut())(Dual::variables(x.try_into().unwrap()));
+    multimin_gradient_ext(
+        max_iter,
+        method,
+        step_size,
+        tol,
+        epsabs,
+        &x0,
+        |x| eval(x).val,
+        |x, g| g.copy_from_slice(&eval(x).grad),
+        callback,
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiminResult {
+    /// Location of the minimum
+    pub x: Box<[f64]>,
+    /// Function value at the minimum
+    pub f: f64,
+    pub niter: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiminCallback {
+    pub iter: usize,
+    pub x: Box<[f64]>,
+    pub f: f64,
+    /// Size of the simplex, or norm of the gradient for the gradient methods
+    pub size: f64,
+}
+
+struct FFIParams<F, G> {
+    f: F,
+    g: G,
+}
+
+unsafe extern "C" fn multimin_f<F: FnMut(&[f64]) -> f64>(
+    x: *const gsl_vector,
+    params: *mut c_void,
+) -> f64 {
+    let f: &mut F = &mut *(params as *mut F);
+    let x = gsl_vector::to_boxed_slice(x);
+    catch_unwind(AssertUnwindSafe(move || f(&x))).unwrap_or(f64::NAN)
+}
+
+unsafe extern "C" fn multimin_fdf_f<F: FnMut(&[f64]) -> f64, G: FnMut(&[f64], &mut [f64])>(
+    x: *const gsl_vector,
+    params: *mut c_void,
+) -> f64 {
+    let ffi_params: &mut FFIParams<F, G> = &mut *(params as *mut _);
+    multimin_f::<F>(x, &mut ffi_params.f as *mut _ as *mut _)
+}
+
+unsafe extern "C" fn multimin_fdf_df<F: FnMut(&[f64]) -> f64, G: FnMut(&[f64], &mut [f64])>(
+    x: *const gsl_vector,
+    params: *mut c_void,
+    df: *mut gsl_vector,
+) {
+    let ffi_params: &mut FFIParams<F, G> = &mut *(params as *mut _);
+    let x = gsl_vector::to_boxed_slice(x);
+    let mut gradient = vec![0.0; x.len()];
+    if catch_unwind(AssertUnwindSafe(|| (ffi_params.g)(&x, &mut gradient))).is_err() {
+        gradient.fill(f64::NAN);
+    }
+    for (i, &g) in gradient.iter().enumerate() {
+        gsl_vector_set(df, i as u64, g);
+    }
+}
+
+unsafe extern "C" fn multimin_fdf_fdf<F: FnMut(&[f64]) -> f64, G: FnMut(&[f64], &mut [f64])>(
+    x: *const gsl_vector,
+    params: *mut c_void,
+    f: *mut f64,
+    df: *mut gsl_vector,
+) {
+    *f = multimin_fdf_f::<F, G>(x, params);
+    multimin_fdf_df::<F, G>(x, params, df);
+}
+
+#[test]
+fn test_multimin() {
+    disable_error_handler();
+
+    // Rosenbrock function, with its minimum at (1, 1)
+    let rosenbrock = |x: &[f64]| (1.0 - x[0]).powi(2) + 100.0 * (x[1] - x[0] * x[0]).powi(2);
+    let gradient = |x: &[f64], g: &mut [f64]| {
+        g[0] = -2.0 * (1.0 - x[0]) - 400.0 * x[0] * (x[1] - x[0] * x[0]);
+        g[1] = 200.0 * (x[1] - x[0] * x[0]);
+    };
+
+    let result = multimin(&[-1.2, 1.0], &[0.1, 0.1], rosenbrock).unwrap();
+    approx::assert_abs_diff_eq!(result.x[0], 1.0, epsilon = 1.0e-4);
+    approx::assert_abs_diff_eq!(result.x[1], 1.0, epsilon = 1.0e-4);
+    approx::assert_abs_diff_eq!(result.f, 0.0, epsilon = 1.0e-8);
+
+    for method in [
+        GradientMethod::FletcherReeves,
+        GradientMethod::PolakRibiere,
+        GradientMethod::Bfgs,
+        GradientMethod::Bfgs2,
+    ] {
+        let mut iterations = 0;
+        let result = multimin_gradient_ext(
+            10000,
+            method,
+            0.01,
+            0.1,
+            1.0e-6,
+            &[-1.2, 1.0],
+            rosenbrock,
+            gradient,
+            |_| iterations += 1,
+        )
+        .unwrap();
+        assert_eq!(iterations, result.niter);
+        approx::assert_abs_diff_eq!(result.x[0], 1.0, epsilon = 1.0e-3);
+        approx::assert_abs_diff_eq!(result.x[1], 1.0, epsilon = 1.0e-3);
+    }
+
+    // Quadratic bowl
+    let result = multimin_gradient(
+        &[0.0, 0.0, 0.0],
+        |x| (x[0] - 1.0).powi(2) + 2.0 * (x[1] + 2.0).powi(2) + 3.0 * (x[2] - 3.0).powi(2),
+        |x, g| {
+            g[0] = 2.0 * (x[0] - 1.0);
+            g[1] = 4.0 * (x[1] + 2.0);
+            g[2] = 6.0 * (x[2] - 3.0);
+        },
+    )
+    .unwrap();
+    for (x, expected) in result.x.iter().zip([1.0, -2.0, 3.0]) {
+        approx::assert_abs_diff_eq!(*x, expected, epsilon = 1.0e-6);
+    }
+}
+
+#[test]
+fn test_multimin_autodiff() {
+    use crate::dual::Scalar;
+
+    disable_error_handler();
+
+    fn rosenbrock<T: Scalar>([x, y]: [T; 2]) -> T {
+        (T::constant(1.0) - x).powi(2) + (y - x * x).powi(2) * 100.0
+    }
+
+    let result = multimin_autodiff([-1.2, 1.0], rosenbrock).unwrap();
+    approx::assert_abs_diff_eq!(result.x[0], 1.0, epsilon = 1.0e-4);
+    approx::assert_abs_diff_eq!(result.x[1], 1.0, epsilon = 1.0e-4);
+    approx::assert_abs_diff_eq!(result.f, 0.0, epsilon = 1.0e-8);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // No variables
+    multimin(&[], &[], |_| 0.0).unwrap_err();
+    multimin_gradient(&[], |_| 0.0, |_, _| {}).unwrap_err();
+
+    // Length mismatch
+    multimin(&[1.0, 2.0], &[0.1], |x| x[0] * x[0]).unwrap_err();
+
+    // No minimum
+    multimin_ext(100, 1.0e-9, &[1.0], &[0.1], |x| -x[0], |_| {}).unwrap_err();
+
+    // Panic
+    multimin(&[1.0], &[0.1], |_| panic!()).unwrap_err();
+}
@@ -17,35 +17,141 @@
 */
 
 use crate::bindings::*;
+use crate::dual::Dual;
+use crate::memory::Footprint;
 use crate::*;
-use drop_guard::guard;
+use std::cell::RefCell;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 
 pub type HyperParams = gsl_multifit_nlinear_parameters;
 
+/// Least squares fit of `y ≈ f(x, params)`.
+/// Every observation `x` can be of any type, e.g. `[f64; K]` for `K` independent variables,
+/// see `nonlinear_fit_rows` for predictors stored in a flat row-major slice.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
 pub fn nonlinear_fit<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: usize>(
     p0: [f64; P],
     x: &[X],
     y: &[f64],
     f: F,
 ) -> Result<FitResult<P>> {
-    nonlinear_fit_ext(
-        100,
-        1.0e-9,
-        1.0e-9,
-        1.0e-9,
-        HyperParams::default(),
+    nonlinear_fit_with_policy(policy::policy(), p0, x, y, f)
+}
+
+/// `nonlinear_fit` with `policy` instead of the policy of this thread
+pub fn nonlinear_fit_with_policy<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: usize>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        nonlinear_fit_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            HyperParams::default(),
+            p0,
+            x,
+            y,
+            f,
+            None::<fn(FitCallback<P>)>,
+        )
+    })
+}
+
+/// The optional `callback` is called by the driver after every iteration,
+/// e.g. to log the convergence of the fit. It's also called once before the first iteration,
+/// with `iter` 0 and the starting parameters, when there's no Jacobian yet.
+pub fn nonlinear_fit_ext<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    hyper_params: HyperParams,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+    callback: Option<C>,
+) -> Result<FitResult<P>> {
+    fit::<X, F, fn(&X, [f64; P]) -> Result<[f64; P]>, C, P>(
+        max_iter,
+        xtol,
+        gtol,
+        ftol,
+        hyper_params,
         p0,
         x,
         y,
         f,
-        None::<fn(FitCallback<P>)>,
+        None,
+        callback,
     )
 }
 
-pub fn nonlinear_fit_ext<
+/// Least squares fit of `y ≈ f(x, params)` with the gradient `j(x, params)` of `f`
+/// with respect to the parameters, instead of finite differences.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
+pub fn nonlinear_fit_jacobian<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    const P: usize,
+>(
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+    j: J,
+) -> Result<FitResult<P>> {
+    nonlinear_fit_jacobian_with_policy(policy::policy(), p0, x, y, f, j)
+}
+
+/// `nonlinear_fit_jacobian` with `policy` instead of the policy of this thread
+pub fn nonlinear_fit_jacobian_with_policy<
     X,
     F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    const P: usize,
+>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+    j: J,
+) -> Result<FitResult<P>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        nonlinear_fit_jacobian_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            HyperParams::default(),
+            p0,
+            x,
+            y,
+            f,
+            j,
+            None::<fn(FitCallback<P>)>,
+        )
+    })
+}
+
+pub fn nonlinear_fit_jacobian_ext<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
     C: FnMut(FitCallback<P>),
     const P: usize,
 >(
@@ -58,47 +164,351 @@ pub fn nonlinear_fit_ext<
     x: &[X],
     y: &[f64],
     f: F,
-    mut callback: Option<C>,
+    j: J,
+    callback: Option<C>,
 ) -> Result<FitResult<P>> {
-    unsafe {
-        if P == 0 {
-            return Err(GSLError::Invalid);
+    fit(
+        max_iter,
+        xtol,
+        gtol,
+        ftol,
+        hyper_params,
+        p0,
+        x,
+        y,
+        f,
+        Some(j),
+        callback,
+    )
+}
+
+/// Least squares fit of `y ≈ f(x, params)` with the exact Jacobian from forward-mode
+/// automatic differentiation. `f` evaluates the model with dual numbers,
+/// e.g. through a model written generically over `Scalar`.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
+pub fn nonlinear_fit_autodiff<X, F: FnMut(&X, [Dual<P>; P]) -> Result<Dual<P>>, const P: usize>(
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    nonlinear_fit_autodiff_with_policy(policy::policy(), p0, x, y, f)
+}
+
+/// `nonlinear_fit_autodiff` with `policy` instead of the policy of this thread
+pub fn nonlinear_fit_autodiff_with_policy<
+    X,
+    F: FnMut(&X, [Dual<P>; P]) -> Result<Dual<P>>,
+    const P: usize,
+>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        nonlinear_fit_autodiff_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            HyperParams::default(),
+            p0,
+            x,
+            y,
+            f,
+            None::<fn(FitCallback<P>)>,
+        )
+    })
+}
+
+/// The model is evaluated once for the residuals and once for the Jacobian.
+pub fn nonlinear_fit_autodiff_ext<
+    X,
+    F: FnMut(&X, [Dual<P>; P]) -> Result<Dual<P>>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    hyper_params: HyperParams,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+    callback: Option<C>,
+) -> Result<FitResult<P>> {
+    let f = RefCell::new(f);
+    nonlinear_fit_jacobian_ext(
+        max_iter,
+        xtol,
+        gtol,
+        ftol,
+        hyper_params,
+        p0,
+        x,
+        y,
+        |x, params| Ok((f.borrow_mut())(x, Dual::variables(params))?.val),
+        |x, params| Ok((f.borrow_mut())(x, Dual::variables(params))?.grad),
+        callback,
+    )
+}
+
+fn fit<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    hyper_params: HyperParams,
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+    j: Option<J>,
+    callback: Option<C>,
+) -> Result<FitResult<P>> {
+    check_data(x, y, P)?;
+    let mut workspace = FitWorkspace::new(&hyper_params, x.len(), P);
+    fit_with(
+        &mut workspace,
+        max_iter,
+        xtol,
+        gtol,
+        ftol,
+        &ParamMap::all_free(p0),
+        x,
+        y,
+        f,
+        j,
+        callback,
+    )
+}
+
+/// Checks the data of a fit of `p` free parameters
+fn check_data<X>(x: &[X], y: &[f64], p: usize) -> Result<()> {
+    if p == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if x.is_empty() || y.is_empty() {
+        return Err(GSLError::Invalid);
+    }
+    if x.len() != y.len() {
+        return Err(GSLError::Invalid);
+    }
+    // GSL doesn't allocate a workspace for fewer observations than parameters
+    if x.len() < p {
+        return Err(GSLError::Invalid);
+    }
+    Ok(())
+}
+
+/// Workspace for `n` observations and `p` parameters, which `gsl_multifit_nlinear_init` resets for every fit
+struct FitWorkspace {
+    workspace: *mut gsl_multifit_nlinear_workspace,
+    n: usize,
+    _footprint: Footprint,
+}
+
+impl FitWorkspace {
+    fn new(hyper_params: &HyperParams, n: usize, p: usize) -> Self {
+        unsafe {
+            let workspace = gsl_multifit_nlinear_alloc(
+                gsl_multifit_nlinear_trust,
+                hyper_params,
+                n as u64,
+                p as u64,
+            );
+            assert!(!workspace.is_null());
+
+            // The Jacobian and its QR decomposition, and work vectors of length n and p
+            let footprint =
+                Footprint::of::<gsl_multifit_nlinear_workspace>(2 * n * p + 8 * n + 12 * p);
+
+            FitWorkspace {
+                workspace,
+                n,
+                _footprint: footprint,
+            }
         }
-        if x.len() == 0 || y.len() == 0 {
-            return Err(GSLError::Invalid);
+    }
+}
+
+impl Drop for FitWorkspace {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_multifit_nlinear_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for FitWorkspace {}
+
+/// Free parameters of a fit, which GSL optimizes, and how the model gets all `P` parameters from them
+struct ParamMap<'a, const P: usize> {
+    /// Starting values, which the fixed parameters keep
+    p0: [f64; P],
+    /// Indices of the free parameters
+    free: Vec<usize>,
+    constraints: Option<&'a [Constraint<'a, P>; P]>,
+}
+
+impl<'a, const P: usize> ParamMap<'a, P> {
+    fn all_free(p0: [f64; P]) -> Self {
+        ParamMap {
+            p0,
+            free: (0..P).collect(),
+            constraints: None,
         }
-        if x.len() != y.len() {
+    }
+
+    fn new(p0: [f64; P], constraints: &'a [Constraint<'a, P>; P]) -> Result<Self> {
+        let free = (0..P)
+            .filter(|&i| matches!(constraints[i], Constraint::Free))
+            .collect::<Vec<_>>();
+        if free.is_empty() {
             return Err(GSLError::Invalid);
         }
+        Ok(ParamMap {
+            p0,
+            free,
+            constraints: Some(constraints),
+        })
+    }
 
-        // Amount of datapoints
-        let n = x.len() as u64;
+    fn initial(&self) -> Vec<f64> {
+        self.free.iter().map(|&i| self.p0[i]).collect()
+    }
 
-        // Allocate workspace
-        let workspace = guard(
-            gsl_multifit_nlinear_alloc(gsl_multifit_nlinear_trust, &hyper_params, n, P as u64),
-            |workspace| {
-                gsl_multifit_nlinear_free(workspace);
-            },
-        );
-        assert!(!workspace.is_null());
+    fn is_tied(&self, i: usize) -> bool {
+        matches!(self.constraints.map(|c| &c[i]), Some(Constraint::Tied(_)))
+    }
+
+    /// All parameters for the values `free` of the free parameters
+    fn expand(&self, free: &[f64]) -> [f64; P] {
+        let mut params = self.p0;
+        for (&i, &value) in self.free.iter().zip(free) {
+            params[i] = value;
+        }
+        // In increasing order, so ties see the tied parameters before them
+        for (i, constraint) in self.constraints.into_iter().flatten().enumerate() {
+            if let Constraint::Tied(tie) = constraint {
+                params[i] = tie(&params);
+            }
+        }
+        params
+    }
+
+    /// `expand` of the free parameters in a vector of GSL
+    unsafe fn expand_gsl(&self, free: *const gsl_vector) -> [f64; P] {
+        let mut values = [0.0; P];
+        for (k, value) in values.iter_mut().take(self.free.len()).enumerate() {
+            *value = gsl_vector_get(free, k as u64);
+        }
+        self.expand(&values[..self.free.len()])
+    }
+
+    /// Covariance of all parameters from the `covariance` of the free ones at `free`.
+    /// It's propagated linearly through the ties, with central differences.
+    fn covariance(&self, free: &[f64], covariance: &Matrix) -> [[f64; P]; P] {
+        // Derivatives of all parameters with respect to every free one
+        let derivatives = self
+            .free
+            .iter()
+            .enumerate()
+            .map(|(k, &i)| {
+                let mut column = [0.0; P];
+                column[i] = 1.0;
+                if (0..P).any(|j| self.is_tied(j)) {
+                    let h = f64::EPSILON.cbrt() * free[k].abs().max(1.0);
+                    let (mut up, mut down) = (free.to_vec(), free.to_vec());
+                    up[k] += h;
+                    down[k] -= h;
+                    let (up, down) = (self.expand(&up), self.expand(&down));
+                    for j in (0..P).filter(|&j| self.is_tied(j)) {
+                        column[j] = (up[j] - down[j]) / (2.0 * h);
+                    }
+                }
+                column
+            })
+            .collect::<Vec<_>>();
+
+        let mut out = [[0.0; P]; P];
+        for (k, dk) in derivatives.iter().enumerate() {
+            for (l, dl) in derivatives.iter().enumerate() {
+                let c = covariance.elem_ij(k, l);
+                for i in 0..P {
+                    for j in 0..P {
+                        out[i][j] += dk[i] * c * dl[j];
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Fit of the data checked by `check_data`, with a workspace for `x.len()` observations
+/// and the free parameters of `map`
+fn fit_with<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    workspace: &mut FitWorkspace,
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    map: &ParamMap<'_, P>,
+    x: &[X],
+    y: &[f64],
+    f: F,
+    j: Option<J>,
+    callback: Option<C>,
+) -> Result<FitResult<P>> {
+    debug_assert_eq!(workspace.n, x.len());
+    unsafe {
+        // Amount of datapoints and free parameters
+        let n = x.len() as u64;
+        let p = map.free.len();
+        let workspace = workspace.workspace;
 
         // Information we need inside the trampolines
+        let has_jacobian = j.is_some();
         let mut ffi_params = FFIParams {
             f,
+            j,
             x,
             y,
+            map,
             error: GSL_SUCCESS,
             panicked: false,
         };
 
-        // Function to be optimized
+        // Function to be optimized, without a Jacobian GSL uses finite differences
         let mut fdf = gsl_multifit_nlinear_fdf {
-            f: Some(fit_f::<X, F, P>),
-            df: None,
+            f: Some(fit_f::<X, F, J, P>),
+            df: if has_jacobian {
+                Some(fit_j::<X, F, J, P>)
+            } else {
+                None
+            },
             fvv: None,
             n,
-            p: P as u64,
+            p: p as u64,
             params: &mut ffi_params as *mut _ as *mut _,
             nevalf: 0,
             nevaldf: 0,
@@ -106,34 +516,32 @@ pub fn nonlinear_fit_ext<
         };
 
         // Init workspace
-        let param_guess = gsl_vector::from(p0.as_slice());
-        GSLError::from_raw(gsl_multifit_nlinear_init(
-            &param_guess,
-            &mut fdf,
-            *workspace,
-        ))?;
+        let param_guess = gsl_vector::from(map.initial().as_slice());
+        GSLError::from_raw(gsl_multifit_nlinear_init(&param_guess, &mut fdf, workspace))?;
 
         // Initial cost function chi^2_0
         let mut chisq0 = 0.0f64;
         {
-            let start_residuals = gsl_multifit_nlinear_residual(*workspace);
+            let start_residuals = gsl_multifit_nlinear_residual(workspace);
             GSLError::from_raw(gsl_blas_ddot(start_residuals, start_residuals, &mut chisq0))?;
         }
 
         let mut _info = 0i32;
+        let has_callback = callback.is_some();
+        let mut callback = CallbackParams { callback, map };
         let status = gsl_multifit_nlinear_driver(
             max_iter as u64,
             xtol,
             gtol,
             ftol,
-            if callback.is_some() {
+            if has_callback {
                 Some(fit_callback::<C, P>)
             } else {
                 None
             },
             &mut callback as *mut _ as *mut c_void,
             &mut _info,
-            *workspace,
+            workspace,
         );
 
         // Give user errors priority
@@ -143,7 +551,7 @@ pub fn nonlinear_fit_ext<
         // This will cause the process to fail, even if the driver returned success
         // This may not be desired (?)
         GSLError::from_raw(ffi_params.error)?;
-        GSLError::from_raw(status)?;
+        policy::converged(GSLError::from_raw(status))?;
 
         /*
 
@@ -152,20 +560,20 @@ pub fn nonlinear_fit_ext<
         */
 
         // Numerical fit results
-        let fit_result = gsl_multifit_nlinear_position(*workspace);
-        let fit_jacobian = gsl_multifit_nlinear_jac(*workspace);
-        let fit_residuals = gsl_multifit_nlinear_residual(*workspace);
+        let fit_result = gsl_multifit_nlinear_position(workspace);
+        let fit_jacobian = gsl_multifit_nlinear_jac(workspace);
+        let fit_residuals = gsl_multifit_nlinear_residual(workspace);
 
         // Fit evaluation statistics
-        let fit_niter = gsl_multifit_nlinear_niter(*workspace);
+        let fit_niter = gsl_multifit_nlinear_niter(workspace);
         let fit_neval_f = fdf.nevalf;
 
         // Final cost function chi^2_1
         let mut chisq1 = 0.0f64;
         GSLError::from_raw(gsl_blas_ddot(fit_residuals, fit_residuals, &mut chisq1))?;
 
-        // Calculate variance-covariance matrix
-        let mut fit_covariance = Matrix::zeroes(P, P);
+        // Calculate variance-covariance matrix of the free parameters
+        let mut fit_covariance = Matrix::zeroes(p, p);
         GSLError::from_raw(gsl_multifit_nlinear_covar(
             fit_jacobian,
             0.0,
@@ -173,45 +581,391 @@ pub fn nonlinear_fit_ext<
         ))?;
         GSLError::from_raw(gsl_matrix_scale(
             fit_covariance.as_gsl_mut(),
-            chisq1 / (n as f64 - P as f64),
+            chisq1 / (n as f64 - p as f64),
         ))?;
 
-        // Calculate mean and total sum of squares wrt mean
-        let gsl_y = gsl_vector::from(y);
-        let mean = stats::mean(y);
-        let tss = gsl_stats_tss_m(gsl_y.data, gsl_y.stride, gsl_y.size, mean);
+        // Calculate mean and total sum of squares wrt mean
+        let gsl_y = gsl_vector::from(y);
+        let mean = stats::mean(y);
+        let tss = gsl_stats_tss_m(gsl_y.data, gsl_y.stride, gsl_y.size, mean);
+
+        let free = gsl_vector::to_boxed_slice(fit_result);
+        let result = FitResult {
+            params: map.expand(&free),
+            covariance: map.covariance(&free, &fit_covariance),
+            niter: fit_niter,
+            neval_f: fit_neval_f,
+            initial_residual_squared: chisq0,
+            final_residuals: gsl_vector::to_boxed_slice(fit_residuals),
+            final_residual_squared: chisq1,
+            mean,
+            r_squared: 1.0 - chisq1 / tss,
+        };
+
+        Ok(result)
+    }
+}
+
+/// Fit with `k` independent variables per observation, stored row-major in `x`,
+/// such that observation `i` is `x[i * k..(i + 1) * k]`.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
+pub fn nonlinear_fit_rows<F: FnMut(&[f64], [f64; P]) -> Result<f64>, const P: usize>(
+    p0: [f64; P],
+    k: usize,
+    x: &[f64],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    nonlinear_fit_rows_with_policy(policy::policy(), p0, k, x, y, f)
+}
+
+/// `nonlinear_fit_rows` with `policy` instead of the policy of this thread
+pub fn nonlinear_fit_rows_with_policy<F: FnMut(&[f64], [f64; P]) -> Result<f64>, const P: usize>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    k: usize,
+    x: &[f64],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        nonlinear_fit_rows_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            HyperParams::default(),
+            p0,
+            k,
+            x,
+            y,
+            f,
+            None::<fn(FitCallback<P>)>,
+        )
+    })
+}
+
+pub fn nonlinear_fit_rows_ext<
+    F: FnMut(&[f64], [f64; P]) -> Result<f64>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    hyper_params: HyperParams,
+    p0: [f64; P],
+    k: usize,
+    x: &[f64],
+    y: &[f64],
+    mut f: F,
+    callback: Option<C>,
+) -> Result<FitResult<P>> {
+    if k == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if x.len() != k * y.len() {
+        return Err(GSLError::BadLength);
+    }
+
+    let rows = x.chunks_exact(k).collect::<Vec<_>>();
+    nonlinear_fit_ext(
+        max_iter,
+        xtol,
+        gtol,
+        ftol,
+        hyper_params,
+        p0,
+        &rows,
+        y,
+        |x, params| f(x, params),
+        callback,
+    )
+}
+
+/// `nonlinear_fit` of the observations where `mask` is `true`, e.g. to leave out missing values.
+/// The residuals keep the positions of all observations, with NaN where `mask` is `false`.
+pub fn nonlinear_fit_masked<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: usize>(
+    p0: [f64; P],
+    x: &[X],
+    y: &[f64],
+    mask: &[bool],
+    mut f: F,
+) -> Result<FitResult<P>> {
+    if x.len() != y.len() || x.len() != mask.len() {
+        return Err(GSLError::Invalid);
+    }
+
+    let y = select(y, mask).into_iter().copied().collect::<Vec<_>>();
+    let mut fit = nonlinear_fit(p0, &select(x, mask), &y, |x, params| f(x, params))?;
+    fit.final_residuals = unselect(&fit.final_residuals, mask);
+    Ok(fit)
+}
+
+/// `nonlinear_fit` with the parameters `constraints`: fixed parameters keep their value in `p0`,
+/// and tied ones are computed from the others. The model still gets all `P` parameters.
+/// Fixed parameters have zero covariance, and the covariance of the tied ones is propagated from the free ones.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
+pub fn nonlinear_fit_constrained<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: usize>(
+    p0: [f64; P],
+    constraints: &[Constraint<'_, P>; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    nonlinear_fit_constrained_with_policy(policy::policy(), p0, constraints, x, y, f)
+}
+
+/// `nonlinear_fit_constrained` with `policy` instead of the policy of this thread
+pub fn nonlinear_fit_constrained_with_policy<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    const P: usize,
+>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    constraints: &[Constraint<'_, P>; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+) -> Result<FitResult<P>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        nonlinear_fit_constrained_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            HyperParams::default(),
+            p0,
+            constraints,
+            x,
+            y,
+            f,
+            None::<fn(FitCallback<P>)>,
+        )
+    })
+}
+
+/// Fails with `GSLError::Invalid` if no parameter is free.
+/// The Jacobian is computed with finite differences of the free parameters.
+pub fn nonlinear_fit_constrained_ext<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    C: FnMut(FitCallback<P>),
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    hyper_params: HyperParams,
+    p0: [f64; P],
+    constraints: &[Constraint<'_, P>; P],
+    x: &[X],
+    y: &[f64],
+    f: F,
+    callback: Option<C>,
+) -> Result<FitResult<P>> {
+    let map = ParamMap::new(p0, constraints)?;
+    check_data(x, y, map.free.len())?;
+    let mut workspace = FitWorkspace::new(&hyper_params, x.len(), map.free.len());
+    fit_with(
+        &mut workspace,
+        max_iter,
+        xtol,
+        gtol,
+        ftol,
+        &map,
+        x,
+        y,
+        f,
+        None::<fn(&X, [f64; P]) -> Result<[f64; P]>>,
+        callback,
+    )
+}
+
+/// Fits `f` to every dataset `(x, y)` in `datasets`, starting from the same parameters `p0`.
+/// With the `rayon` feature the datasets are fitted in parallel. Every thread reuses its workspace
+/// for consecutive datasets of the same length.
+/// Uses `epsrel` and `max_iter` of the `NumericPolicy`, by default `1e-9` and 100.
+pub fn fit_many<
+    X: Sync,
+    D: AsRef<[X]> + Sync,
+    E: AsRef<[f64]> + Sync,
+    F: Fn(&X, [f64; P]) -> Result<f64> + Sync,
+    const P: usize,
+>(
+    p0: [f64; P],
+    datasets: &[(D, E)],
+    f: F,
+) -> Vec<Result<FitResult<P>>> {
+    fit_many_with_policy(policy::policy(), p0, datasets, f)
+}
+
+/// `fit_many` with `policy` instead of the policy of this thread
+pub fn fit_many_with_policy<
+    X: Sync,
+    D: AsRef<[X]> + Sync,
+    E: AsRef<[f64]> + Sync,
+    F: Fn(&X, [f64; P]) -> Result<f64> + Sync,
+    const P: usize,
+>(
+    policy: policy::NumericPolicy,
+    p0: [f64; P],
+    datasets: &[(D, E)],
+    f: F,
+) -> Vec<Result<FitResult<P>>> {
+    policy::with_policy(policy, || {
+        let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+        fit_many_ext(
+            policy.iterations(100),
+            tol,
+            tol,
+            tol,
+            FitParams::default(),
+            p0,
+            datasets,
+            f,
+        )
+    })
+}
+
+/// Takes `FitParams` instead of `HyperParams`, since those hold pointers that can't be shared between threads.
+pub fn fit_many_ext<
+    X: Sync,
+    D: AsRef<[X]> + Sync,
+    E: AsRef<[f64]> + Sync,
+    F: Fn(&X, [f64; P]) -> Result<f64> + Sync,
+    const P: usize,
+>(
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    params: FitParams,
+    p0: [f64; P],
+    datasets: &[(D, E)],
+    f: F,
+) -> Vec<Result<FitResult<P>>> {
+    fit_many_on(true, max_iter, xtol, gtol, ftol, params, p0, datasets, f)
+}
+
+/// `fit_many`, which only uses the calling thread if `context` is serial
+pub fn fit_many_reproducible<
+    X: Sync,
+    D: AsRef<[X]> + Sync,
+    E: AsRef<[f64]> + Sync,
+    F: Fn(&X, [f64; P]) -> Result<f64> + Sync,
+    const P: usize,
+>(
+    context: &rng::Reproducibility,
+    p0: [f64; P],
+    datasets: &[(D, E)],
+    f: F,
+) -> Vec<Result<FitResult<P>>> {
+    let policy = policy::policy();
+    let (_, tol) = policy.tolerances(0.0, 1.0e-9);
+    fit_many_on(
+        !context.serial(),
+        policy.iterations(100),
+        tol,
+        tol,
+        tol,
+        FitParams::default(),
+        p0,
+        datasets,
+        f,
+    )
+}
 
-        let result = FitResult {
-            params: gsl_vector::to_array(fit_result),
-            covariance: fit_covariance.to_2d_array(),
-            niter: fit_niter,
-            neval_f: fit_neval_f,
-            initial_residual_squared: chisq0,
-            final_residuals: gsl_vector::to_boxed_slice(fit_residuals),
-            final_residual_squared: chisq1,
-            mean,
-            r_squared: 1.0 - chisq1 / tss,
-        };
+/// `fit_many_ext`, in parallel with the `rayon` feature if `parallel` is set
+fn fit_many_on<
+    X: Sync,
+    D: AsRef<[X]> + Sync,
+    E: AsRef<[f64]> + Sync,
+    F: Fn(&X, [f64; P]) -> Result<f64> + Sync,
+    const P: usize,
+>(
+    parallel: bool,
+    max_iter: usize,
+    xtol: f64,
+    gtol: f64,
+    ftol: f64,
+    params: FitParams,
+    p0: [f64; P],
+    datasets: &[(D, E)],
+    f: F,
+) -> Vec<Result<FitResult<P>>> {
+    let workspaces = ThreadLocalWorkspace::new(|| Ok(None::<FitWorkspace>));
+    // The policy of the calling thread, also on the threads of rayon
+    let policy = policy::policy();
+    let fit_one = |(x, y): &(D, E)| {
+        let (x, y) = (x.as_ref(), y.as_ref());
+        check_data(x, y, P)?;
+        workspaces.with(|workspace| {
+            if !matches!(workspace, Some(w) if w.n == x.len()) {
+                *workspace = Some(FitWorkspace::new(&params.into(), x.len(), P));
+            }
+            policy::with_policy(policy, || {
+                fit_with(
+                    workspace.as_mut().unwrap(),
+                    max_iter,
+                    xtol,
+                    gtol,
+                    ftol,
+                    &ParamMap::all_free(p0),
+                    x,
+                    y,
+                    &f,
+                    None::<fn(&X, [f64; P]) -> Result<[f64; P]>>,
+                    None::<fn(FitCallback<P>)>,
+                )
+            })
+        })?
+    };
 
-        Ok(result)
+    #[cfg(feature = "rayon")]
+    if parallel {
+        use rayon::prelude::*;
+        return datasets.par_iter().map(fit_one).collect();
     }
+    #[cfg(not(feature = "rayon"))]
+    let _ = parallel;
+    datasets.iter().map(fit_one).collect()
 }
 
-struct FFIParams<'a, 'b, F, X> {
+struct FFIParams<'a, 'b, 'm, F, J, X, const P: usize> {
     f: F,
+    j: Option<J>,
     x: &'a [X],
     y: &'b [f64],
+    map: &'m ParamMap<'m, P>,
     error: i32,
     panicked: bool,
 }
 
-unsafe extern "C" fn fit_f<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: usize>(
+unsafe extern "C" fn fit_f<
+    X,
+    F: FnMut(&X, [f64; P]) -> Result<f64>,
+    J: FnMut(&X, [f64; P]) -> Result<[f64; P]>,
+    const P: usize,
+>(
     params: *const gsl_vector,
     ffi_params: *mut c_void,
     out: *mut gsl_vector,
 ) -> i32 {
-    let ffi_params: &mut FFIParams<'_, '_, F, X> = &mut *(ffi_params as *mut _);
-    let params = gsl_vector::to_array(params);
+    let ffi_params: &mut FFIParams<'_, '_, '_, F, J, X, P> = &mut *(ffi_params as *mut _);
+    // The ties are user code as well
+    let params = match catch_unwind(AssertUnwindSafe(|| ffi_params.map.expand_gsl(params))) {
+        Ok(params) => params,
+        Err(_) => {
+            ffi_params.panicked = true;
+            return GSL_EBADFUNC;
+        }
+    };
 
     for (i, (x, y)) in ffi_params.x.iter().zip(ffi_params.y.iter()).enumerate() {
         let val = catch_unwind(AssertUnwindSafe(|| (ffi_params.f)(x, params)));
@@ -233,7 +987,6 @@ unsafe extern "C" fn fit_f<X, F: FnMut(&X, [f64; P]) -> Result<f64>, const P: us
     GSL_SUCCESS
 }
 
-/*
 unsafe extern "C" fn fit_j<
     X,
     F: FnMut(&X, [f64; P]) -> Result<f64>,
@@ -244,11 +997,22 @@ unsafe extern "C" fn fit_j<
     ffi_params: *mut c_void,
     out: *mut gsl_matrix,
 ) -> i32 {
-    let ffi_params: &mut FFIParams<'_, '_, F, J, X> = &mut *(ffi_params as *mut _);
-    let params = gsl_vector_to_array(params);
+    let ffi_params: &mut FFIParams<'_, '_, '_, F, J, X, P> = &mut *(ffi_params as *mut _);
+    // The ties are user code as well
+    let params = match catch_unwind(AssertUnwindSafe(|| ffi_params.map.expand_gsl(params))) {
+        Ok(params) => params,
+        Err(_) => {
+            ffi_params.panicked = true;
+            return GSL_EBADFUNC;
+        }
+    };
+    let j = match &mut ffi_params.j {
+        Some(j) => j,
+        None => return GSL_EINVAL,
+    };
 
     for (i, x) in ffi_params.x.iter().enumerate() {
-        let val = catch_unwind(AssertUnwindSafe(|| (ffi_params.j)(x, params)));
+        let val = catch_unwind(AssertUnwindSafe(|| j(x, params)));
 
         let dvs = match val {
             Ok(Ok(dvs)) => dvs,
@@ -263,21 +1027,26 @@ unsafe extern "C" fn fit_j<
             }
         };
 
-        for (j, &dv) in dvs.iter().enumerate() {
-            gsl_matrix_set(out, i as u64, j as u64, dv);
+        // Columns of the free parameters
+        for (k, &p) in ffi_params.map.free.iter().enumerate() {
+            gsl_matrix_set(out, i as u64, k as u64, dvs[p]);
         }
     }
 
     GSL_SUCCESS
 }
-*/
+
+struct CallbackParams<'m, C, const P: usize> {
+    callback: Option<C>,
+    map: &'m ParamMap<'m, P>,
+}
 
 unsafe extern "C" fn fit_callback<C: FnMut(FitCallback<P>), const P: usize>(
     iter: u64,
     callback: *mut c_void,
     workspace: *const gsl_multifit_nlinear_workspace,
 ) {
-    let callback: &mut Option<C> = &mut *(callback as *mut _);
+    let CallbackParams { callback, map } = &mut *(callback as *mut CallbackParams<'_, C, P>);
     let callback = match callback {
         Some(callback) => callback,
         None => std::hint::unreachable_unchecked(),
@@ -295,24 +1064,35 @@ unsafe extern "C" fn fit_callback<C: FnMut(FitCallback<P>), const P: usize>(
     let _ = catch_unwind(AssertUnwindSafe(|| {
         callback(FitCallback {
             iter: iter as usize,
-            params: gsl_vector::to_array(params),
+            params: map.expand_gsl(params),
             cond: 1.0 / rcond,
+            rcond,
+            residual_norm: chisq.sqrt(),
             residual_squared: chisq,
         });
     }));
 }
 
+/// State of the solver after every iteration, passed to the callback of `nonlinear_fit_ext`
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct FitCallback<const P: usize> {
     pub iter: usize,
     pub params: [f64; P],
+    /// Condition number of the Jacobian
     pub cond: f64,
+    /// Reciprocal condition number of the Jacobian, which is zero for a singular Jacobian
+    pub rcond: f64,
+    /// Norm of the residual vector `|f|`
+    pub residual_norm: f64,
     pub residual_squared: f64,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FitResult<const P: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::array"))]
     pub params: [f64; P],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::array2"))]
     pub covariance: [[f64; P]; P],
     pub niter: u64,
     pub neval_f: u64,
@@ -327,14 +1107,154 @@ impl<const P: usize> FitResult<P> {
     pub fn uncertainty(&self, i: usize) -> f64 {
         self.covariance[i][i].sqrt()
     }
+
+    /// Covariance matrix as `P` rows
+    pub fn covariance_rows(&self) -> Vec<Vec<f64>> {
+        self.covariance.iter().map(|row| row.to_vec()).collect()
+    }
 }
 
+/// Fitted parameters
+impl<const P: usize> From<FitResult<P>> for Vec<f64> {
+    fn from(fit: FitResult<P>) -> Self {
+        fit.params.to_vec()
+    }
+}
+
+/// Role of a parameter in `nonlinear_fit_constrained`
+pub enum Constraint<'a, const P: usize> {
+    Free,
+    /// Kept at its starting value
+    Fixed,
+    /// Computed from all parameters, in which the tied ones with a higher index still have their starting value
+    Tied(Tie<'a, P>),
+}
+
+/// Expression of a tied parameter in terms of all parameters
+pub type Tie<'a, const P: usize> = Box<dyn Fn(&[f64; P]) -> f64 + 'a>;
+
 impl Default for HyperParams {
     fn default() -> Self {
         unsafe { gsl_multifit_nlinear_default_parameters() }
     }
 }
 
+/// Method for solving the trust region subproblem in every iteration
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrustRegion {
+    LevenbergMarquardt,
+    /// Levenberg-Marquardt with geodesic acceleration
+    LevenbergMarquardtAccel,
+    Dogleg,
+    DoubleDogleg,
+    /// Two dimensional subspace
+    Subspace2D,
+}
+
+/// Scaling of the parameters, which makes the trust region an ellipse
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scaling {
+    /// Scale invariant, using the largest column norms of the Jacobian so far
+    More,
+    /// No scaling, a spherical trust region
+    Levenberg,
+    /// Column norms of the current Jacobian
+    Marquardt,
+}
+
+/// Linear solver for the trust region subproblem
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinearSolver {
+    Qr,
+    /// Fastest, but sensitive to ill conditioned Jacobians
+    Cholesky,
+    /// Slowest, but most robust for ill conditioned Jacobians
+    Svd,
+}
+
+/// Finite difference approximation of the Jacobian
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FiniteDifference {
+    Forward,
+    Central,
+}
+
+/// Configuration of the trust region solver, which converts into `HyperParams`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FitParams {
+    pub trust_region: TrustRegion,
+    pub scaling: Scaling,
+    pub solver: LinearSolver,
+    pub finite_difference: FiniteDifference,
+    /// Relative step size of the finite differences for the Jacobian
+    pub fd_step: f64,
+    /// Step size of the finite differences for the second directional derivative,
+    /// used for geodesic acceleration
+    pub fvv_step: f64,
+    /// Maximum ratio `|a| / |v|` of acceleration and velocity for geodesic acceleration
+    pub avmax: f64,
+    /// Factor by which the trust region grows after a successful step
+    pub factor_up: f64,
+    /// Factor by which the trust region shrinks after a rejected step
+    pub factor_down: f64,
+}
+
+impl Default for FitParams {
+    fn default() -> Self {
+        let defaults = HyperParams::default();
+        FitParams {
+            trust_region: TrustRegion::LevenbergMarquardt,
+            scaling: Scaling::More,
+            solver: LinearSolver::Qr,
+            finite_difference: FiniteDifference::Forward,
+            fd_step: defaults.h_df,
+            fvv_step: defaults.h_fvv,
+            avmax: defaults.avmax,
+            factor_up: defaults.factor_up,
+            factor_down: defaults.factor_down,
+        }
+    }
+}
+
+impl From<FitParams> for HyperParams {
+    fn from(params: FitParams) -> Self {
+        unsafe {
+            HyperParams {
+                trs: match params.trust_region {
+                    TrustRegion::LevenbergMarquardt => gsl_multifit_nlinear_trs_lm,
+                    TrustRegion::LevenbergMarquardtAccel => gsl_multifit_nlinear_trs_lmaccel,
+                    TrustRegion::Dogleg => gsl_multifit_nlinear_trs_dogleg,
+                    TrustRegion::DoubleDogleg => gsl_multifit_nlinear_trs_ddogleg,
+                    TrustRegion::Subspace2D => gsl_multifit_nlinear_trs_subspace2D,
+                },
+                scale: match params.scaling {
+                    Scaling::More => gsl_multifit_nlinear_scale_more,
+                    Scaling::Levenberg => gsl_multifit_nlinear_scale_levenberg,
+                    Scaling::Marquardt => gsl_multifit_nlinear_scale_marquardt,
+                },
+                solver: match params.solver {
+                    LinearSolver::Qr => gsl_multifit_nlinear_solver_qr,
+                    LinearSolver::Cholesky => gsl_multifit_nlinear_solver_cholesky,
+                    LinearSolver::Svd => gsl_multifit_nlinear_solver_svd,
+                },
+                fdtype: match params.finite_difference {
+                    FiniteDifference::Forward => {
+                        gsl_multifit_nlinear_fdtype_GSL_MULTIFIT_NLINEAR_FWDIFF
+                    }
+                    FiniteDifference::Central => {
+                        gsl_multifit_nlinear_fdtype_GSL_MULTIFIT_NLINEAR_CTRDIFF
+                    }
+                },
+                factor_up: params.factor_up,
+                factor_down: params.factor_down,
+                avmax: params.avmax,
+                h_df: params.fd_step,
+                h_fvv: params.fvv_step,
+            }
+        }
+    }
+}
+
 #[test]
 fn test_nlfit_1() {
     disable_error_handler();
@@ -373,6 +1293,19 @@ fn test_nlfit_1() {
     }
 }
 
+#[test]
+fn test_conversions() {
+    disable_error_handler();
+
+    let x = (0..100).map(|x| x as f64 / 10.0).collect::<Vec<_>>();
+    let y = x.iter().map(|&x| 1.0 + 2.0 * x).collect::<Vec<_>>();
+    let fit = nonlinear_fit([1.0, 1.0], &x, &y, |&x, [a, b]| Ok(a + b * x)).unwrap();
+
+    assert_eq!(fit.covariance_rows().len(), 2);
+    assert_eq!(fit.covariance_rows()[0][1], fit.covariance[0][1]);
+    assert_eq!(Vec::from(fit.clone()), fit.params);
+}
+
 #[test]
 fn test_nlfit_2() {
     disable_error_handler();
@@ -450,6 +1383,334 @@ fn test_nlfit_3() {
     approx::assert_abs_diff_eq!(fit.params[1], b, epsilon = 1.0e-2);
 }
 
+#[test]
+fn test_nlfit_jacobian() {
+    disable_error_handler();
+
+    fn model(a: f64, b: f64, x: f64) -> f64 {
+        (a * x + b).sin()
+    }
+
+    let x = (0..100).map(|x| x as f64 / 100.0).collect::<Vec<_>>();
+    let y = x.iter().map(|&x| model(10.0, 2.0, x)).collect::<Vec<_>>();
+
+    let mut evaluations = 0;
+    let fit = nonlinear_fit_jacobian(
+        [9.0, 1.0],
+        &x,
+        &y,
+        |&x, [a, b]| Ok(model(a, b, x)),
+        |&x, [a, b]| {
+            evaluations += 1;
+            let c = (a * x + b).cos();
+            Ok([x * c, c])
+        },
+    )
+    .unwrap();
+
+    assert!(evaluations > 0);
+    approx::assert_abs_diff_eq!(fit.params[0], 10.0, epsilon = 1.0e-6);
+    approx::assert_abs_diff_eq!(fit.params[1], 2.0, epsilon = 1.0e-6);
+
+    // Errors from the Jacobian are passed on
+    nonlinear_fit_jacobian(
+        [9.0, 1.0],
+        &x,
+        &y,
+        |&x, [a, b]| Ok(model(a, b, x)),
+        |_, _| Err(GSLError::Domain),
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn test_nlfit_autodiff() {
+    use crate::dual::Scalar;
+
+    disable_error_handler();
+
+    fn model<T: Scalar>(x: f64, [a, b]: [T; 2]) -> T {
+        (a * x + b).sin()
+    }
+
+    let x = (0..100).map(|x| x as f64 / 100.0).collect::<Vec<_>>();
+    let y = x.iter().map(|&x| model(x, [10.0, 2.0])).collect::<Vec<_>>();
+
+    let fit =
+        nonlinear_fit_autodiff([9.0, 1.0], &x, &y, |&x, params| Ok(model(x, params))).unwrap();
+    approx::assert_abs_diff_eq!(fit.params[0], 10.0, epsilon = 1.0e-6);
+    approx::assert_abs_diff_eq!(fit.params[1], 2.0, epsilon = 1.0e-6);
+
+    // Errors from the model are passed on
+    nonlinear_fit_autodiff([9.0, 1.0], &x, &y, |_, _| Err(GSLError::Domain)).unwrap_err();
+}
+
+#[test]
+fn test_nlfit_masked() {
+    disable_error_handler();
+
+    let x = (0..20).map(|x| x as f64 / 4.0).collect::<Vec<_>>();
+    let mut y = x.iter().map(|x| 3.0 * (-0.5 * x).exp()).collect::<Vec<_>>();
+    y[3] = f64::NAN;
+    y[7] = 1.0e3;
+    let mask = y
+        .iter()
+        .map(|y| y.is_finite() && *y < 100.0)
+        .collect::<Vec<_>>();
+
+    let fit = nonlinear_fit_masked([1.0, 1.0], &x, &y, &mask, |&x, [a, k]| {
+        Ok(a * (-k * x).exp())
+    })
+    .unwrap();
+    approx::assert_abs_diff_eq!(fit.params[0], 3.0, epsilon = 1.0e-6);
+    approx::assert_abs_diff_eq!(fit.params[1], 0.5, epsilon = 1.0e-6);
+    assert_eq!(fit.final_residuals.len(), 20);
+    assert!(fit.final_residuals[3].is_nan() && fit.final_residuals[7].is_nan());
+
+    // Nothing selected
+    nonlinear_fit_masked([1.0], &x, &y, &[false; 20], |&x, [a]| Ok(a * x)).unwrap_err();
+}
+
+#[test]
+fn test_nlfit_constrained() {
+    disable_error_handler();
+
+    let x = (0..20).map(|x| x as f64 / 4.0).collect::<Vec<_>>();
+    let y = x
+        .iter()
+        .map(|x| 3.0 * (-0.5 * x).exp() + 0.2 + 0.01 * (7.0 * x).sin())
+        .collect::<Vec<_>>();
+    let model = |&x: &f64, [a, k, c]: [f64; 3]| Ok(a * (-k * x).exp() + c);
+
+    // Offset fixed at its starting value
+    let fit = nonlinear_fit_constrained(
+        [1.0, 1.0, 0.2],
+        &[Constraint::Free, Constraint::Free, Constraint::Fixed],
+        &x,
+        &y,
+        model,
+    )
+    .unwrap();
+    assert_eq!(fit.params[2], 0.2);
+    assert_eq!(fit.uncertainty(2), 0.0);
+    assert_eq!(fit.covariance[0][2], 0.0);
+    approx::assert_abs_diff_eq!(fit.params[0], 3.0, epsilon = 1.0e-2);
+    approx::assert_abs_diff_eq!(fit.params[1], 0.5, epsilon = 1.0e-2);
+    assert!(fit.uncertainty(0) > 0.0);
+
+    // Same as fitting the model without the offset
+    let reduced = nonlinear_fit(
+        [1.0, 1.0],
+        &x,
+        &y,
+        |&x, [a, k]| Ok(a * (-k * x).exp() + 0.2),
+    )
+    .unwrap();
+    approx::assert_relative_eq!(fit.params[0], reduced.params[0], max_relative = 1.0e-6);
+    approx::assert_relative_eq!(fit.params[1], reduced.params[1], max_relative = 1.0e-6);
+    approx::assert_relative_eq!(
+        fit.covariance[0][1],
+        reduced.covariance[0][1],
+        max_relative = 1.0e-4
+    );
+
+    // Offset tied to the amplitude
+    let fit = nonlinear_fit_constrained(
+        [1.0, 1.0, 0.0],
+        &[
+            Constraint::Free,
+            Constraint::Free,
+            Constraint::Tied(Box::new(|p| p[0] / 15.0)),
+        ],
+        &x,
+        &y,
+        model,
+    )
+    .unwrap();
+    approx::assert_relative_eq!(fit.params[2], fit.params[0] / 15.0, max_relative = 1.0e-12);
+    approx::assert_abs_diff_eq!(fit.params[0], 3.0, epsilon = 1.0e-2);
+    approx::assert_relative_eq!(
+        fit.uncertainty(2),
+        fit.uncertainty(0) / 15.0,
+        max_relative = 1.0e-6
+    );
+
+    // The callback sees all parameters
+    let mut offsets = Vec::new();
+    nonlinear_fit_constrained_ext(
+        100,
+        1.0e-9,
+        1.0e-9,
+        1.0e-9,
+        HyperParams::default(),
+        [1.0, 1.0, 0.2],
+        &[Constraint::Free, Constraint::Free, Constraint::Fixed],
+        &x,
+        &y,
+        model,
+        Some(|callback: FitCallback<3>| offsets.push(callback.params[2])),
+    )
+    .unwrap();
+    assert!(!offsets.is_empty() && offsets.iter().all(|&c| c == 0.2));
+
+    // Enough observations for the free parameters
+    let fit = nonlinear_fit_constrained(
+        [1.0, 1.0, 0.2],
+        &[Constraint::Free, Constraint::Free, Constraint::Fixed],
+        &x[..2],
+        &y[..2],
+        model,
+    )
+    .unwrap();
+    approx::assert_abs_diff_eq!(fit.params[0], 3.0, epsilon = 1.0e-2);
+    nonlinear_fit([1.0, 1.0, 0.2], &x[..2], &y[..2], model).unwrap_err();
+
+    // A panicking tie fails the fit
+    let result = nonlinear_fit_constrained(
+        [1.0, 1.0, 0.0],
+        &[
+            Constraint::Free,
+            Constraint::Free,
+            Constraint::Tied(Box::new(|_| panic!())),
+        ],
+        &x,
+        &y,
+        model,
+    );
+    assert_eq!(result.unwrap_err(), GSLError::BadFunction);
+}
+
+#[test]
+fn test_fit_many() {
+    disable_error_handler();
+
+    // Exponential decays with different rates and lengths
+    let datasets = (1..=20)
+        .map(|i| {
+            let x = (0..50 + i % 3).map(|x| x as f64 / 10.0).collect::<Vec<_>>();
+            let y = x
+                .iter()
+                .map(|x| 2.0 * (-0.1 * i as f64 * x).exp())
+                .collect::<Vec<_>>();
+            (x, y)
+        })
+        .collect::<Vec<_>>();
+    let model = |&x: &f64, [a, k]: [f64; 2]| Ok(a * (-k * x).exp());
+
+    let fits = fit_many([1.0, 1.0], &datasets, model);
+    assert_eq!(fits.len(), datasets.len());
+    for (i, (fit, (x, y))) in fits.into_iter().zip(&datasets).enumerate() {
+        let fit = fit.unwrap();
+        approx::assert_abs_diff_eq!(fit.params[0], 2.0, epsilon = 1.0e-6);
+        approx::assert_abs_diff_eq!(fit.params[1], 0.1 * (i + 1) as f64, epsilon = 1.0e-6);
+        assert_eq!(fit, nonlinear_fit([1.0, 1.0], x, y, model).unwrap());
+    }
+
+    // Failed fits don't affect the others
+    let datasets = [(vec![], vec![]), (vec![1.0, 2.0], vec![2.0, 4.0])];
+    let fits = fit_many([1.0], &datasets, |&x, [a]| Ok(a * x));
+    assert_eq!(fits[0], Err(GSLError::Invalid));
+    approx::assert_abs_diff_eq!(fits[1].as_ref().unwrap().params[0], 2.0, epsilon = 1.0e-9);
+
+    // On the calling thread only
+    let mut context = rng::Reproducibility::new(0);
+    context.set_serial(true);
+    let thread = std::thread::current().id();
+    let serial = fit_many_reproducible(&context, [1.0], &datasets, |&x, [a]| {
+        assert_eq!(std::thread::current().id(), thread);
+        Ok(a * x)
+    });
+    assert_eq!(serial, fits);
+}
+
+#[test]
+fn test_nlfit_params() {
+    disable_error_handler();
+
+    fn model(a: f64, b: f64, x: f64) -> f64 {
+        (a * x + b).sin()
+    }
+
+    let x = (0..100).map(|x| x as f64 / 100.0).collect::<Vec<_>>();
+    let y = x.iter().map(|&x| model(10.0, 2.0, x)).collect::<Vec<_>>();
+
+    for trust_region in [
+        TrustRegion::LevenbergMarquardt,
+        TrustRegion::LevenbergMarquardtAccel,
+        TrustRegion::Dogleg,
+        TrustRegion::DoubleDogleg,
+        TrustRegion::Subspace2D,
+    ] {
+        let params = FitParams {
+            trust_region,
+            scaling: Scaling::Marquardt,
+            solver: LinearSolver::Svd,
+            finite_difference: FiniteDifference::Central,
+            ..FitParams::default()
+        };
+
+        let fit = nonlinear_fit_ext(
+            1000,
+            1.0e-9,
+            1.0e-9,
+            1.0e-9,
+            params.into(),
+            [9.0, 1.0],
+            &x,
+            &y,
+            |&x, [a, b]| Ok(model(a, b, x)),
+            None::<fn(FitCallback<2>)>,
+        )
+        .unwrap();
+
+        approx::assert_abs_diff_eq!(fit.params[0], 10.0, epsilon = 1.0e-3);
+        approx::assert_abs_diff_eq!(fit.params[1], 2.0, epsilon = 1.0e-3);
+    }
+
+    // Defaults match GSL
+    let defaults = HyperParams::from(FitParams::default());
+    let gsl_defaults = HyperParams::default();
+    assert_eq!(defaults.trs, gsl_defaults.trs);
+    assert_eq!(defaults.scale, gsl_defaults.scale);
+    assert_eq!(defaults.solver, gsl_defaults.solver);
+    assert_eq!(defaults.fdtype, gsl_defaults.fdtype);
+}
+
+#[test]
+fn test_nlfit_surface() {
+    disable_error_handler();
+    fastrand::seed(1);
+
+    // Gaussian peak on an image
+    fn model(x: [f64; 2], [a, x0, y0, sigma]: [f64; 4]) -> f64 {
+        let r2 = (x[0] - x0).powi(2) + (x[1] - y0).powi(2);
+        a * (-r2 / (2.0 * sigma * sigma)).exp()
+    }
+
+    let truth = [3.0, 4.2, 5.5, 1.3];
+    let xy = (0..400)
+        .map(|i| [(i % 20) as f64 / 2.0, (i / 20) as f64 / 2.0])
+        .collect::<Vec<_>>();
+    let z = xy
+        .iter()
+        .map(|&x| model(x, truth) + 0.01 * (fastrand::f64() * 2.0 - 1.0))
+        .collect::<Vec<_>>();
+
+    let p0 = [2.0, 5.0, 5.0, 1.0];
+    let fit = nonlinear_fit(p0, &xy, &z, |&x, params| Ok(model(x, params))).unwrap();
+    for (param, truth) in fit.params.iter().zip(truth.iter()) {
+        approx::assert_abs_diff_eq!(param, truth, epsilon = 1.0e-2);
+    }
+
+    // Same data as a flat row-major slice
+    let flat = xy.iter().flatten().copied().collect::<Vec<_>>();
+    let rows = nonlinear_fit_rows(p0, 2, &flat, &z, |x, params| {
+        Ok(model([x[0], x[1]], params))
+    })
+    .unwrap();
+    assert_eq!(rows.params, fit.params);
+}
+
 #[test]
 fn test_nlfit_panic() {
     disable_error_handler();
@@ -496,6 +1757,54 @@ fn test_nlfit_error() {
     assert_eq!(fit, GSLError::Fault);
 }
 
+#[test]
+fn test_nlfit_callback() {
+    disable_error_handler();
+
+    fn model(a: f64, b: f64, x: f64) -> f64 {
+        (a * x + b).sin()
+    }
+
+    let x = (0..100).map(|x| x as f64 / 100.0).collect::<Vec<_>>();
+    let y = x.iter().map(|&x| model(10.0, 2.0, x)).collect::<Vec<_>>();
+
+    let mut iterations = Vec::new();
+    let fit = nonlinear_fit_ext(
+        1000,
+        1.0e-9,
+        1.0e-9,
+        1.0e-9,
+        HyperParams::default(),
+        [9.0, 1.0],
+        &x,
+        &y,
+        |&x, [a, b]| Ok(model(a, b, x)),
+        Some(|callback: FitCallback<2>| iterations.push(callback)),
+    )
+    .unwrap();
+
+    // Called before the first iteration and once per iteration
+    assert_eq!(iterations.len() as u64, fit.niter + 1);
+    assert_eq!(iterations[0].params, [9.0, 1.0]);
+    for (i, callback) in iterations.iter().enumerate() {
+        assert_eq!(callback.iter, i);
+        approx::assert_relative_eq!(callback.residual_norm.powi(2), callback.residual_squared);
+        if i > 0 {
+            assert!(callback.rcond > 0.0 && callback.rcond <= 1.0);
+            approx::assert_relative_eq!(callback.cond, 1.0 / callback.rcond);
+        }
+    }
+
+    // Rejected steps keep the position, so the residual never increases
+    for pair in iterations.windows(2) {
+        assert!(pair[1].residual_norm <= pair[0].residual_norm);
+    }
+
+    let last = iterations.last().unwrap();
+    assert_eq!(last.params, fit.params);
+    approx::assert_relative_eq!(last.residual_squared, fit.final_residual_squared);
+}
+
 #[test]
 fn test_invalid_params() {
     disable_error_handler();
@@ -534,6 +1843,20 @@ fn test_invalid_params() {
     )
     .unwrap_err();
 
+    // No free parameters
+    nonlinear_fit_constrained(
+        [1.0],
+        &[Constraint::Fixed],
+        &[0, 1, 2],
+        &[0.0; 3],
+        |_, [a]| Ok(a),
+    )
+    .unwrap_err();
+
+    // Rows do not match the data
+    nonlinear_fit_rows([1.0], 2, &[0.0; 5], &[0.0; 3], |_, [_]| Ok(0.0)).unwrap_err();
+    nonlinear_fit_rows([1.0], 0, &[], &[0.0; 3], |_, [_]| Ok(0.0)).unwrap_err();
+
     // No data
     nonlinear_fit_ext(
         0,
@@ -0,0 +1,326 @@
+/*
+    ntuple.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::histogram::Histogram;
+use crate::*;
+use std::ffi::CString;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+/*
+
+    An ntuple file stores rows of `n` doubles as raw binary records, without any header.
+    The row length must therefore be known when reading the file back.
+
+*/
+
+/// Writes fixed size rows to an ntuple file
+pub struct NTupleWriter {
+    ntuple: *mut gsl_ntuple,
+    // GSL keeps a pointer to this buffer
+    row: Box<[f64]>,
+}
+
+impl NTupleWriter {
+    /// Creates or truncates the file at `path`, for rows of length `n`.
+    pub fn create<P: AsRef<Path>>(path: P, n: usize) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let path = c_path(path.as_ref())?;
+            let mut row = vec![0.0; n].into_boxed_slice();
+            let ntuple = gsl_ntuple_create(
+                path.as_ptr() as *mut c_char,
+                row.as_mut_ptr() as *mut c_void,
+                (n * std::mem::size_of::<f64>()) as u64,
+            );
+            if ntuple.is_null() {
+                return Err(GSLError::Failed);
+            }
+
+            Ok(NTupleWriter { ntuple, row })
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.row.len()
+    }
+
+    pub fn write(&mut self, row: &[f64]) -> Result<()> {
+        unsafe {
+            if row.len() != self.row.len() {
+                return Err(GSLError::BadLength);
+            }
+            self.row.copy_from_slice(row);
+            GSLError::from_raw(gsl_ntuple_write(self.ntuple))
+        }
+    }
+
+    /// Closes the file, reporting errors which would be ignored when dropped.
+    pub fn close(mut self) -> Result<()> {
+        unsafe { close(&mut self.ntuple) }
+    }
+}
+
+impl fmt::Debug for NTupleWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NTupleWriter")
+            .field("n", &self.n())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for NTupleWriter {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = close(&mut self.ntuple);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for NTupleWriter {}
+unsafe impl Sync for NTupleWriter {}
+
+/// Reads fixed size rows from an ntuple file
+pub struct NTupleReader {
+    ntuple: *mut gsl_ntuple,
+    // GSL keeps a pointer to this buffer
+    row: Box<[f64]>,
+}
+
+impl NTupleReader {
+    /// Opens the file at `path`, which must contain rows of length `n`.
+    pub fn open<P: AsRef<Path>>(path: P, n: usize) -> Result<Self> {
+        unsafe {
+            if n == 0 {
+                return Err(GSLError::Invalid);
+            }
+
+            let path = c_path(path.as_ref())?;
+            let mut row = vec![0.0; n].into_boxed_slice();
+            let ntuple = gsl_ntuple_open(
+                path.as_ptr() as *mut c_char,
+                row.as_mut_ptr() as *mut c_void,
+                (n * std::mem::size_of::<f64>()) as u64,
+            );
+            if ntuple.is_null() {
+                return Err(GSLError::Failed);
+            }
+
+            Ok(NTupleReader { ntuple, row })
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.row.len()
+    }
+
+    /// Reads the next row, returning `None` at the end of the file.
+    pub fn read(&mut self) -> Result<Option<&[f64]>> {
+        unsafe {
+            match GSLError::from_raw(gsl_ntuple_read(self.ntuple)) {
+                Ok(()) => Ok(Some(&self.row)),
+                Err(GSLError::EOF) => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+    }
+
+    /// Reads all remaining rows. For every row accepted by `select`,
+    /// the bin containing `value(row)` is incremented.
+    /// Values outside the histogram range are ignored.
+    pub fn project<S: FnMut(&[f64]) -> bool, V: FnMut(&[f64]) -> f64>(
+        &mut self,
+        histogram: &mut Histogram,
+        select: S,
+        value: V,
+    ) -> Result<()> {
+        unsafe {
+            let mut select = Callback {
+                f: select,
+                n: self.n(),
+                panicked: false,
+            };
+            let mut value = Callback {
+                f: value,
+                n: self.n(),
+                panicked: false,
+            };
+
+            let mut select_fn = gsl_ntuple_select_fn {
+                function: Some(select_trampoline::<S>),
+                params: &mut select as *mut _ as *mut _,
+            };
+            let mut value_fn = gsl_ntuple_value_fn {
+                function: Some(value_trampoline::<V>),
+                params: &mut value as *mut _ as *mut _,
+            };
+
+            GSLError::from_raw(gsl_ntuple_project(
+                histogram.as_gsl_mut(),
+                self.ntuple,
+                &mut value_fn,
+                &mut select_fn,
+            ))?;
+
+            if select.panicked || value.panicked {
+                return Err(GSLError::BadFunction);
+            }
+
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Debug for NTupleReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NTupleReader")
+            .field("n", &self.n())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for NTupleReader {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = close(&mut self.ntuple);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for NTupleReader {}
+unsafe impl Sync for NTupleReader {}
+
+fn c_path(path: &Path) -> Result<CString> {
+    let path = path.to_str().ok_or(GSLError::Invalid)?;
+    CString::new(path).map_err(|_| GSLError::Invalid)
+}
+
+/// Closes the ntuple once, leaving a null pointer behind
+unsafe fn close(ntuple: &mut *mut gsl_ntuple) -> Result<()> {
+    if ntuple.is_null() {
+        return Ok(());
+    }
+    let status = gsl_ntuple_close(*ntuple);
+    *ntuple = std::ptr::null_mut();
+    GSLError::from_raw(status)
+}
+
+struct Callback<F> {
+    f: F,
+    n: usize,
+    panicked: bool,
+}
+
+unsafe extern "C" fn select_trampoline<S: FnMut(&[f64]) -> bool>(
+    data: *mut c_void,
+    params: *mut c_void,
+) -> c_int {
+    let callback = &mut *(params as *mut Callback<S>);
+    let row = std::slice::from_raw_parts(data as *const f64, callback.n);
+    match catch_unwind(AssertUnwindSafe(|| (callback.f)(row))) {
+        Ok(selected) => selected as c_int,
+        Err(_) => {
+            callback.panicked = true;
+            0
+        }
+    }
+}
+
+unsafe extern "C" fn value_trampoline<V: FnMut(&[f64]) -> f64>(
+    data: *mut c_void,
+    params: *mut c_void,
+) -> f64 {
+    let callback = &mut *(params as *mut Callback<V>);
+    let row = std::slice::from_raw_parts(data as *const f64, callback.n);
+    match catch_unwind(AssertUnwindSafe(|| (callback.f)(row))) {
+        Ok(y) => y,
+        Err(_) => {
+            callback.panicked = true;
+            f64::NAN
+        }
+    }
+}
+
+#[test]
+fn test_ntuple() {
+    disable_error_handler();
+
+    let path = std::env::temp_dir().join("gsl_rust_test_ntuple.dat");
+
+    let mut writer = NTupleWriter::create(&path, 3).unwrap();
+    for i in 0..100 {
+        let x = i as f64;
+        writer.write(&[x, x * x, (i % 2) as f64]).unwrap();
+    }
+    writer.close().unwrap();
+
+    let mut reader = NTupleReader::open(&path, 3).unwrap();
+    assert_eq!(reader.read().unwrap(), Some(&[0.0, 0.0, 0.0][..]));
+    assert_eq!(reader.read().unwrap(), Some(&[1.0, 1.0, 1.0][..]));
+    let mut count = 2;
+    while reader.read().unwrap().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 100);
+
+    // Project the odd rows, the last one falls outside the range
+    let mut h = Histogram::uniform(10, 0.0, 99.0).unwrap();
+    let mut reader = NTupleReader::open(&path, 3).unwrap();
+    reader
+        .project(&mut h, |row| row[2] == 1.0, |row| row[0])
+        .unwrap();
+    assert_eq!(h.sum(), 49.0);
+    assert_eq!(h.get(0), 5.0);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let path = std::env::temp_dir().join("gsl_rust_test_ntuple_invalid.dat");
+
+    // Wrong row length
+    let mut writer = NTupleWriter::create(&path, 2).unwrap();
+    writer.write(&[1.0]).unwrap_err();
+    writer.write(&[1.0, 2.0]).unwrap();
+    drop(writer);
+
+    // Panicking selection
+    let mut h = Histogram::uniform(10, 0.0, 1.0).unwrap();
+    let mut reader = NTupleReader::open(&path, 2).unwrap();
+    reader
+        .project(&mut h, |_| panic!(), |row| row[0])
+        .unwrap_err();
+
+    // Zero length rows
+    NTupleWriter::create(&path, 0).unwrap_err();
+
+    std::fs::remove_file(&path).unwrap();
+
+    // Missing file
+    NTupleReader::open(&path, 2).unwrap_err();
+}
@@ -0,0 +1,973 @@
+/*
+    ode.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::nonlinear_fit::{nonlinear_fit_jacobian, FitResult};
+use crate::*;
+use drop_guard::guard;
+use std::cell::RefCell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/*
+
+    Initial value problems `y' = f(t, y)` are integrated with the adaptive explicit Runge-Kutta steppers of GSL,
+    controlling the local error of every component to `epsabs + epsrel |y|`.
+    The right hand side is written into its last argument, `f(t, y, dydt)`.
+    Integrating backwards in time, with `t1 < t0`, is allowed.
+
+    Every accepted step is stored together with the derivative at its end,
+    which costs one extra evaluation of `f` per step.
+    Between the steps the solution is the cubic Hermite interpolant of the values and derivatives,
+    which is continuously differentiable and has an error of order `h^4` in the step size `h`.
+
+    Forward sensitivities `S_ij = ∂y_i/∂p_j` of a system `y' = f(t, y, p)` satisfy `S' = J_y S + J_p`,
+    which is integrated together with the state. Every column `J_y S_j + ∂f/∂p_j` is the derivative
+    of `f(t, y + ε S_j, p + ε e_j)` with respect to `ε`, taken with a central difference.
+    `ode_fit` uses the sensitivities as the Jacobian of a least squares fit of the parameters,
+    and only integrates the system once per set of parameters.
+
+    Delay differential equations `y'(t) = f(t, y(t), y(t - tau))` with a constant delay `tau`
+    are integrated with the method of steps: on `[t0 + k tau, t0 + (k + 1) tau]` the delayed state
+    is known, from the history before `t0` or from the dense output of the earlier intervals.
+    The derivative generally jumps at `t0`, so every interval starts a new integration
+    and the solution stores the steps at the interval boundaries twice, with both derivatives.
+
+*/
+
+/// Relative step of the central differences for the sensitivities, about the cube root of the machine precision
+const SENSITIVITY_STEP: f64 = 6.0e-6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stepper {
+    /// Embedded Runge-Kutta (2, 3)
+    Rk2,
+    /// Classical fourth order Runge-Kutta, with step doubling for the error estimate
+    Rk4,
+    /// Runge-Kutta-Fehlberg (4, 5)
+    Rkf45,
+    /// Runge-Kutta Cash-Karp (4, 5)
+    Rkck,
+    /// Runge-Kutta Prince-Dormand (8, 9)
+    Rk8pd,
+}
+
+impl Stepper {
+    unsafe fn as_gsl(self) -> *const gsl_odeiv2_step_type {
+        match self {
+            Stepper::Rk2 => gsl_odeiv2_step_rk2,
+            Stepper::Rk4 => gsl_odeiv2_step_rk4,
+            Stepper::Rkf45 => gsl_odeiv2_step_rkf45,
+            Stepper::Rkck => gsl_odeiv2_step_rkck,
+            Stepper::Rk8pd => gsl_odeiv2_step_rk8pd,
+        }
+    }
+}
+
+/// Steps of an integrated trajectory, with dense output in between
+#[derive(Clone, Debug)]
+pub struct OdeSolution {
+    pub(crate) dim: usize,
+    pub(crate) t: Vec<f64>,
+    pub(crate) y: Vec<f64>,
+    pub(crate) dydt: Vec<f64>,
+}
+
+impl OdeSolution {
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Times of the steps, starting at `t0` and ending at `t1`
+    pub fn t(&self) -> &[f64] {
+        &self.t
+    }
+
+    /// State at step `i`
+    pub fn y(&self, i: usize) -> &[f64] {
+        &self.y[i * self.dim..(i + 1) * self.dim]
+    }
+
+    /// Derivative at step `i`
+    pub fn dydt(&self, i: usize) -> &[f64] {
+        &self.dydt[i * self.dim..(i + 1) * self.dim]
+    }
+
+    /// State at the end of the integration
+    pub fn final_state(&self) -> &[f64] {
+        self.y(self.t.len() - 1)
+    }
+
+    /// State at time `t`, interpolated between the steps.
+    /// Fails with `GSLError::Domain` outside the integrated range.
+    pub fn eval(&self, t: f64) -> Result<Box<[f64]>> {
+        let (k, h, s) = self.interval(t)?;
+        let h00 = (2.0 * s - 3.0) * s * s + 1.0;
+        let h10 = ((s - 2.0) * s + 1.0) * s * h;
+        let h01 = (3.0 - 2.0 * s) * s * s;
+        let h11 = (s - 1.0) * s * s * h;
+        Ok(self.combine(k, h00, h10, h01, h11))
+    }
+
+    /// Derivative at time `t` of the interpolated state
+    pub fn eval_derivative(&self, t: f64) -> Result<Box<[f64]>> {
+        let (k, h, s) = self.interval(t)?;
+        let h00 = 6.0 * (s - 1.0) * s / h;
+        let h10 = (3.0 * s - 4.0) * s + 1.0;
+        let h01 = -h00;
+        let h11 = (3.0 * s - 2.0) * s;
+        Ok(self.combine(k, h00, h10, h01, h11))
+    }
+
+    /// Step `k` with `t` in `[t_k, t_k+1]`, its length and the relative position of `t`
+    fn interval(&self, t: f64) -> Result<(usize, f64, f64)> {
+        let n = self.t.len();
+        let forward = self.t[n - 1] >= self.t[0];
+        let (min, max) = if forward {
+            (self.t[0], self.t[n - 1])
+        } else {
+            (self.t[n - 1], self.t[0])
+        };
+        if !(t >= min && t <= max) {
+            return Err(GSLError::Domain);
+        }
+        if n == 1 {
+            return Ok((0, 0.0, 0.0));
+        }
+
+        let after = if forward {
+            self.t.partition_point(|&ti| ti <= t)
+        } else {
+            self.t.partition_point(|&ti| ti >= t)
+        };
+        let k = after.clamp(1, n - 1) - 1;
+        let h = self.t[k + 1] - self.t[k];
+        Ok((k, h, (t - self.t[k]) / h))
+    }
+
+    fn combine(&self, k: usize, h00: f64, h10: f64, h01: f64, h11: f64) -> Box<[f64]> {
+        if self.t.len() == 1 {
+            return self.y(0).into();
+        }
+        let (y0, y1) = (self.y(k), self.y(k + 1));
+        let (f0, f1) = (self.dydt(k), self.dydt(k + 1));
+        (0..self.dim)
+            .map(|i| h00 * y0[i] + h10 * f0[i] + h01 * y1[i] + h11 * f1[i])
+            .collect()
+    }
+
+    fn push(&mut self, t: f64, y: &[f64], dydt: &[f64]) {
+        self.t.push(t);
+        self.y.extend_from_slice(y);
+        self.dydt.extend_from_slice(dydt);
+    }
+}
+
+/// Trajectory together with its sensitivities to the parameters
+#[derive(Clone, Debug)]
+pub struct SensitivitySolution {
+    n_params: usize,
+    solution: OdeSolution,
+}
+
+impl SensitivitySolution {
+    pub fn dim(&self) -> usize {
+        self.solution.dim / (1 + self.n_params)
+    }
+
+    pub fn n_params(&self) -> usize {
+        self.n_params
+    }
+
+    /// Times of the steps, starting at `t0` and ending at `t1`
+    pub fn t(&self) -> &[f64] {
+        self.solution.t()
+    }
+
+    pub fn final_state(&self) -> &[f64] {
+        &self.solution.final_state()[..self.dim()]
+    }
+
+    /// Sensitivities at the end of the integration, see `eval_sensitivity`
+    pub fn final_sensitivity(&self) -> &[f64] {
+        &self.solution.final_state()[self.dim()..]
+    }
+
+    /// State at time `t`, interpolated between the steps
+    pub fn eval(&self, t: f64) -> Result<Box<[f64]>> {
+        Ok(self.solution.eval(t)?[..self.dim()].into())
+    }
+
+    /// Sensitivities `∂y_i/∂p_j` at time `t`, as a row-major `dim x n_params` matrix
+    pub fn eval_sensitivity(&self, t: f64) -> Result<Box<[f64]>> {
+        Ok(self.solution.eval(t)?[self.dim()..].into())
+    }
+}
+
+/// Integrates `y' = f(t, y)` from `y(t0) = y0` up to `t1` with Prince-Dormand (8, 9), see `solve_ext`.
+/// Uses the tolerances and the `max_iter` steps of the `NumericPolicy`,
+/// by default `epsabs = epsrel = 1e-10` and 100000 steps.
+pub fn solve<F: FnMut(f64, &[f64], &mut [f64])>(
+    f: F,
+    y0: &[f64],
+    t0: f64,
+    t1: f64,
+) -> Result<OdeSolution> {
+    solve_with_policy(policy::policy(), f, y0, t0, t1)
+}
+
+/// `solve` with `policy` instead of the policy of this thread
+pub fn solve_with_policy<F: FnMut(f64, &[f64], &mut [f64])>(
+    policy: policy::NumericPolicy,
+    f: F,
+    y0: &[f64],
+    t0: f64,
+    t1: f64,
+) -> Result<OdeSolution> {
+    policy::with_policy(policy, || {
+        let h0 = initial_step(t0, t1);
+        let (epsabs, epsrel) = policy.tolerances(1.0e-10, 1.0e-10);
+        let max_steps = policy.iterations(100_000);
+        solve_ext(Stepper::Rk8pd, f, y0, t0, t1, h0, epsabs, epsrel, max_steps)
+    })
+}
+
+/// Integrates `y' = f(t, y)` from `y(t0) = y0` up to `t1` with initial step size `h0`,
+/// controlling the local error to `epsabs + epsrel |y|`.
+/// Fails with `GSLError::MaxIteration` after `max_steps` steps,
+/// and with `GSLError::BadFunction` if `f` panics or returns a value that isn't finite.
+pub fn solve_ext<F: FnMut(f64, &[f64], &mut [f64])>(
+    stepper: Stepper,
+    f: F,
+    y0: &[f64],
+    t0: f64,
+    t1: f64,
+    h0: f64,
+    epsabs: f64,
+    epsrel: f64,
+    max_steps: usize,
+) -> Result<OdeSolution> {
+    unsafe {
+        let dim = y0.len();
+        if dim == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if !(t0.is_finite() && t1.is_finite() && h0 > 0.0 && epsabs >= 0.0 && epsrel >= 0.0) {
+            return Err(GSLError::Domain);
+        }
+
+        let step = guard(
+            gsl_odeiv2_step_alloc(stepper.as_gsl(), dim as u64),
+            |step| {
+                gsl_odeiv2_step_free(step);
+            },
+        );
+        assert!(!step.is_null());
+
+        let control = guard(gsl_odeiv2_control_y_new(epsabs, epsrel), |control| {
+            gsl_odeiv2_control_free(control);
+        });
+        assert!(!control.is_null());
+
+        let evolve = guard(gsl_odeiv2_evolve_alloc(dim as u64), |evolve| {
+            gsl_odeiv2_evolve_free(evolve);
+        });
+        assert!(!evolve.is_null());
+
+        let mut ffi_params = FFIParams {
+            f,
+            dim,
+            panicked: false,
+        };
+        let system = gsl_odeiv2_system {
+            function: Some(ode_function::<F>),
+            jacobian: None,
+            dimension: dim as u64,
+            params: &mut ffi_params as *mut _ as *mut _,
+        };
+
+        let mut solution = OdeSolution {
+            dim,
+            t: Vec::new(),
+            y: Vec::new(),
+            dydt: Vec::new(),
+        };
+        let mut y = y0.to_vec();
+        let mut dydt = vec![0.0; dim];
+        let mut t = t0;
+        GSLError::from_raw(ode_function::<F>(
+            t,
+            y.as_ptr(),
+            dydt.as_mut_ptr(),
+            system.params,
+        ))?;
+        solution.push(t, &y, &dydt);
+
+        let mut h = if t1 < t0 { -h0 } else { h0 };
+        while t != t1 {
+            if solution.t.len() > max_steps {
+                return Err(GSLError::MaxIteration);
+            }
+
+            let status = gsl_odeiv2_evolve_apply(
+                *evolve,
+                *control,
+                *step,
+                &system,
+                &mut t,
+                t1,
+                &mut h,
+                y.as_mut_ptr(),
+            );
+            if ffi_params.panicked {
+                return Err(GSLError::BadFunction);
+            }
+            GSLError::from_raw(status)?;
+
+            GSLError::from_raw(ode_function::<F>(
+                t,
+                y.as_ptr(),
+                dydt.as_mut_ptr(),
+                system.params,
+            ))?;
+            solution.push(t, &y, &dydt);
+        }
+
+        Ok(solution)
+    }
+}
+
+/// Integrates `y' = f(t, y, params)` from `y(t0) = y0` up to `t1` together with the sensitivities
+/// to the parameters, which start at zero, see `solve_sensitivity_ext`
+pub fn solve_sensitivity<F: FnMut(f64, &[f64], &[f64], &mut [f64])>(
+    f: F,
+    y0: &[f64],
+    params: &[f64],
+    t0: f64,
+    t1: f64,
+) -> Result<SensitivitySolution> {
+    let s0 = vec![0.0; y0.len() * params.len()];
+    let h0 = initial_step(t0, t1);
+    solve_sensitivity_ext(
+        Stepper::Rk8pd,
+        f,
+        y0,
+        &s0,
+        params,
+        t0,
+        t1,
+        h0,
+        1.0e-10,
+        1.0e-10,
+        100_000,
+    )
+}
+
+/// Integrates `y' = f(t, y, params)` together with the sensitivities `S = ∂y/∂params`,
+/// starting at the row-major `dim x n_params` matrix `s0`, which is nonzero for parameters of the initial state.
+/// The error control applies to the sensitivities as well, see `solve_ext` for the other arguments.
+pub fn solve_sensitivity_ext<F: FnMut(f64, &[f64], &[f64], &mut [f64])>(
+    stepper: Stepper,
+    mut f: F,
+    y0: &[f64],
+    s0: &[f64],
+    params: &[f64],
+    t0: f64,
+    t1: f64,
+    h0: f64,
+    epsabs: f64,
+    epsrel: f64,
+    max_steps: usize,
+) -> Result<SensitivitySolution> {
+    let n = y0.len();
+    let n_params = params.len();
+    if n_params == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if s0.len() != n * n_params {
+        return Err(GSLError::BadLength);
+    }
+
+    let mut y_shifted = vec![0.0; n];
+    let mut p_shifted = params.to_vec();
+    let mut f_plus = vec![0.0; n];
+    let mut f_minus = vec![0.0; n];
+    let augmented = |t: f64, z: &[f64], dz: &mut [f64]| {
+        let (y, s) = z.split_at(n);
+        let (dy, ds) = dz.split_at_mut(n);
+        f(t, y, params, dy);
+
+        let scale = y
+            .iter()
+            .chain(params.iter())
+            .fold(1.0f64, |m, x| m.max(x.abs()));
+        for j in 0..n_params {
+            // The direction (S_j, e_j) has a maximum norm of at least 1
+            let norm = (0..n).fold(1.0f64, |m, i| m.max(s[i * n_params + j].abs()));
+            let delta = SENSITIVITY_STEP * scale / norm;
+
+            for (sign, out) in [(1.0, &mut f_plus), (-1.0, &mut f_minus)] {
+                for i in 0..n {
+                    y_shifted[i] = y[i] + sign * delta * s[i * n_params + j];
+                }
+                p_shifted[j] = params[j] + sign * delta;
+                f(t, &y_shifted, &p_shifted, out);
+            }
+            p_shifted[j] = params[j];
+
+            for i in 0..n {
+                ds[i * n_params + j] = (f_plus[i] - f_minus[i]) / (2.0 * delta);
+            }
+        }
+    };
+
+    let mut z0 = y0.to_vec();
+    z0.extend_from_slice(s0);
+    let solution = solve_ext(
+        stepper, augmented, &z0, t0, t1, h0, epsabs, epsrel, max_steps,
+    )?;
+    Ok(SensitivitySolution { n_params, solution })
+}
+
+/// Least squares fit of the parameters of `y' = f(t, y, params)` with initial state `y0(params)` at `t0`
+/// to observations `y[i]` of component `component` of the state at times `t[i] >= t0`.
+/// The sensitivities of the initial state are taken with central differences of `y0`.
+pub fn ode_fit<F, G, const P: usize>(
+    f: F,
+    y0: G,
+    p0: [f64; P],
+    t0: f64,
+    component: usize,
+    t: &[f64],
+    y: &[f64],
+) -> Result<FitResult<P>>
+where
+    F: FnMut(f64, &[f64], &[f64], &mut [f64]),
+    G: FnMut(&[f64]) -> Vec<f64>,
+{
+    if t.iter().any(|&ti| !(ti >= t0 && ti.is_finite())) {
+        return Err(GSLError::Domain);
+    }
+
+    let model = RefCell::new(OdeModel {
+        f,
+        y0,
+        t0,
+        t1: t.iter().fold(t0, |m, &ti| m.max(ti)),
+        component,
+        cached: None,
+    });
+
+    nonlinear_fit_jacobian(
+        p0,
+        t,
+        y,
+        |&ti, params| Ok(model.borrow_mut().solution(params)?.eval(ti)?[component]),
+        |&ti, params| {
+            let mut model = model.borrow_mut();
+            let s = model.solution(params)?.eval_sensitivity(ti)?;
+            let mut gradient = [0.0; P];
+            gradient.copy_from_slice(&s[component * P..(component + 1) * P]);
+            Ok(gradient)
+        },
+    )
+}
+
+/// System of `ode_fit` with the solution for the last parameters
+struct OdeModel<F, G, const P: usize> {
+    f: F,
+    y0: G,
+    t0: f64,
+    t1: f64,
+    component: usize,
+    cached: Option<([f64; P], SensitivitySolution)>,
+}
+
+impl<F, G, const P: usize> OdeModel<F, G, P>
+where
+    F: FnMut(f64, &[f64], &[f64], &mut [f64]),
+    G: FnMut(&[f64]) -> Vec<f64>,
+{
+    fn solution(&mut self, params: [f64; P]) -> Result<&SensitivitySolution> {
+        if !matches!(&self.cached, Some((p, _)) if *p == params) {
+            let y0 = (self.y0)(&params);
+            let n = y0.len();
+            if self.component >= n {
+                return Err(GSLError::Invalid);
+            }
+
+            let mut s0 = vec![0.0; n * P];
+            for j in 0..P {
+                let delta = SENSITIVITY_STEP * (1.0 + params[j].abs());
+                let mut shifted = params;
+                shifted[j] = params[j] + delta;
+                let plus = (self.y0)(&shifted);
+                shifted[j] = params[j] - delta;
+                let minus = (self.y0)(&shifted);
+                if plus.len() != n || minus.len() != n {
+                    return Err(GSLError::BadLength);
+                }
+                for i in 0..n {
+                    s0[i * P + j] = (plus[i] - minus[i]) / (2.0 * delta);
+                }
+            }
+
+            let (t0, t1) = (self.t0, self.t1);
+            let solution = solve_sensitivity_ext(
+                Stepper::Rk8pd,
+                &mut self.f,
+                &y0,
+                &s0,
+                &params,
+                t0,
+                t1,
+                initial_step(t0, t1),
+                1.0e-10,
+                1.0e-10,
+                100_000,
+            )?;
+            self.cached = Some((params, solution));
+        }
+
+        match &self.cached {
+            Some((_, solution)) => Ok(solution),
+            None => unreachable!(),
+        }
+    }
+}
+
+/// Integrates `y'(t) = f(t, y(t), y(t - tau))` from `t0` up to `t1 > t0` with Prince-Dormand (8, 9),
+/// where the state before `t0` is given by `history`, see `solve_dde_ext`
+pub fn solve_dde<F, H>(f: F, history: H, tau: f64, t0: f64, t1: f64) -> Result<OdeSolution>
+where
+    F: FnMut(f64, &[f64], &[f64], &mut [f64]),
+    H: FnMut(f64) -> Vec<f64>,
+{
+    let h0 = initial_step(t0, t1).min(tau);
+    solve_dde_ext(
+        Stepper::Rk8pd,
+        f,
+        history,
+        tau,
+        t0,
+        t1,
+        h0,
+        1.0e-10,
+        1.0e-10,
+        100_000,
+    )
+}
+
+/// Integrates `y'(t) = f(t, y(t), y(t - tau))` with a constant delay `tau > 0` from `t0` up to `t1 > t0`,
+/// where the state up to `t0` is `history(t)` and the initial state is `history(t0)`.
+/// Fails with `GSLError::BadFunction` if `history` returns a state of a different dimension,
+/// see `solve_ext` for the other arguments.
+pub fn solve_dde_ext<F, H>(
+    stepper: Stepper,
+    mut f: F,
+    mut history: H,
+    tau: f64,
+    t0: f64,
+    t1: f64,
+    h0: f64,
+    epsabs: f64,
+    epsrel: f64,
+    max_steps: usize,
+) -> Result<OdeSolution>
+where
+    F: FnMut(f64, &[f64], &[f64], &mut [f64]),
+    H: FnMut(f64) -> Vec<f64>,
+{
+    if !(tau > 0.0 && tau.is_finite() && t0.is_finite() && t1 > t0) {
+        return Err(GSLError::Domain);
+    }
+    let mut y = history(t0);
+    let dim = y.len();
+
+    let mut solution: Option<OdeSolution> = None;
+    let mut start = t0;
+    for k in 1.. {
+        let end = (t0 + k as f64 * tau).min(t1);
+        let steps = solution.as_ref().map_or(0, |solution| solution.t.len());
+
+        let past = solution.as_ref();
+        let history = &mut history;
+        let delayed = |t: f64, y: &[f64], dydt: &mut [f64]| {
+            let t_delayed = t - tau;
+            let y_delayed = match past {
+                Some(past) if t_delayed > t0 => {
+                    // Rounding can put the delayed time just beyond the earlier intervals
+                    let last = past.t[past.t.len() - 1];
+                    past.eval(t_delayed.min(last))
+                        .map(Vec::from)
+                        .unwrap_or_default()
+                }
+                _ => history(t_delayed),
+            };
+            if y_delayed.len() == dim {
+                f(t, y, &y_delayed, dydt);
+            } else {
+                dydt.fill(f64::NAN);
+            }
+        };
+        let segment = solve_ext(
+            stepper,
+            delayed,
+            &y,
+            start,
+            end,
+            h0,
+            epsabs,
+            epsrel,
+            max_steps.saturating_sub(steps),
+        )?;
+
+        y = segment.final_state().to_vec();
+        match &mut solution {
+            Some(solution) => {
+                solution.t.extend_from_slice(&segment.t);
+                solution.y.extend_from_slice(&segment.y);
+                solution.dydt.extend_from_slice(&segment.dydt);
+            }
+            None => solution = Some(segment),
+        }
+
+        if end == t1 {
+            break;
+        }
+        start = end;
+    }
+
+    Ok(solution.unwrap())
+}
+
+fn initial_step(t0: f64, t1: f64) -> f64 {
+    (1.0e-6 * (t1 - t0).abs()).max(f64::MIN_POSITIVE)
+}
+
+struct FFIParams<F> {
+    f: F,
+    dim: usize,
+    panicked: bool,
+}
+
+unsafe extern "C" fn ode_function<F: FnMut(f64, &[f64], &mut [f64])>(
+    t: f64,
+    y: *const f64,
+    dydt: *mut f64,
+    params: *mut c_void,
+) -> c_int {
+    let ffi_params: &mut FFIParams<F> = &mut *(params as *mut _);
+    let y = std::slice::from_raw_parts(y, ffi_params.dim);
+    let dydt = std::slice::from_raw_parts_mut(dydt, ffi_params.dim);
+
+    if catch_unwind(AssertUnwindSafe(|| (ffi_params.f)(t, y, dydt))).is_err() {
+        ffi_params.panicked = true;
+        return GSL_EBADFUNC;
+    }
+    if dydt.iter().any(|d| !d.is_finite()) {
+        return GSL_EBADFUNC;
+    }
+    GSL_SUCCESS
+}
+
+#[test]
+fn test_solve() {
+    disable_error_handler();
+
+    // Harmonic oscillator y'' = -y with y(0) = 0, y'(0) = 1
+    let oscillator = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+        dydt[0] = y[1];
+        dydt[1] = -y[0];
+    };
+    let solution = solve(oscillator, &[0.0, 1.0], 0.0, 10.0).unwrap();
+    assert_eq!(solution.t()[0], 0.0);
+    assert_eq!(*solution.t().last().unwrap(), 10.0);
+    approx::assert_abs_diff_eq!(solution.final_state()[0], 10.0f64.sin(), epsilon = 1.0e-8);
+
+    // Dense output between the steps
+    for t in [0.0, 0.123, 1.0, 4.56, 7.89, 10.0] {
+        let y = solution.eval(t).unwrap();
+        approx::assert_abs_diff_eq!(y[0], t.sin(), epsilon = 1.0e-3);
+        approx::assert_abs_diff_eq!(y[1], t.cos(), epsilon = 1.0e-3);
+        let dydt = solution.eval_derivative(t).unwrap();
+        approx::assert_abs_diff_eq!(dydt[0], t.cos(), epsilon = 1.0e-2);
+    }
+
+    // Exact at the steps
+    let i = solution.t().len() / 2;
+    let y = solution.eval(solution.t()[i]).unwrap();
+    approx::assert_abs_diff_eq!(&*y, solution.y(i), epsilon = 1.0e-12);
+
+    // Finer steps give a more accurate interpolant
+    let fine = solve_ext(
+        Stepper::Rkf45,
+        oscillator,
+        &[0.0, 1.0],
+        0.0,
+        10.0,
+        1.0e-3,
+        1.0e-12,
+        0.0,
+        100_000,
+    )
+    .unwrap();
+    assert!(fine.t().len() > solution.t().len());
+    approx::assert_abs_diff_eq!(fine.eval(4.56).unwrap()[0], 4.56f64.sin(), epsilon = 1.0e-8);
+
+    // Backwards in time, y' = -y from y(1) = e^-1
+    let solution = solve(|_, y, dydt| dydt[0] = -y[0], &[(-1.0f64).exp()], 1.0, 0.0).unwrap();
+    approx::assert_abs_diff_eq!(solution.final_state()[0], 1.0, epsilon = 1.0e-9);
+    approx::assert_abs_diff_eq!(
+        solution.eval(0.5).unwrap()[0],
+        (-0.5f64).exp(),
+        epsilon = 1.0e-4
+    );
+}
+
+#[test]
+fn test_sensitivity() {
+    disable_error_handler();
+
+    // y' = -k y with y(0) = a has y = a e^-kt, ∂y/∂k = -t y and ∂y/∂a = e^-kt
+    let decay = |_: f64, y: &[f64], p: &[f64], dydt: &mut [f64]| dydt[0] = -p[0] * y[0];
+    let solution = solve_sensitivity(decay, &[2.0], &[0.7], 0.0, 3.0).unwrap();
+    assert_eq!(solution.dim(), 1);
+    assert_eq!(solution.n_params(), 1);
+    let y = 2.0 * (-0.7f64 * 3.0).exp();
+    approx::assert_abs_diff_eq!(solution.final_state()[0], y, epsilon = 1.0e-9);
+    approx::assert_abs_diff_eq!(solution.final_sensitivity()[0], -3.0 * y, epsilon = 1.0e-8);
+    let s = solution.eval_sensitivity(1.5).unwrap();
+    approx::assert_abs_diff_eq!(s[0], -1.5 * 2.0 * (-0.7f64 * 1.5).exp(), epsilon = 1.0e-4);
+
+    // The initial state as a second parameter
+    let decay = |_: f64, y: &[f64], p: &[f64], dydt: &mut [f64]| dydt[0] = -p[0] * y[0];
+    let solution = solve_sensitivity_ext(
+        Stepper::Rkck,
+        decay,
+        &[2.0],
+        &[0.0, 1.0],
+        &[0.7, 2.0],
+        0.0,
+        3.0,
+        1.0e-3,
+        1.0e-12,
+        1.0e-12,
+        100_000,
+    )
+    .unwrap();
+    let s = solution.final_sensitivity();
+    approx::assert_abs_diff_eq!(s[0], -3.0 * y, epsilon = 1.0e-7);
+    approx::assert_abs_diff_eq!(s[1], (-0.7f64 * 3.0).exp(), epsilon = 1.0e-7);
+}
+
+#[test]
+fn test_ode_fit() {
+    disable_error_handler();
+
+    // Damped oscillator x'' = -ω² x - γ x' observed in position, fitting ω, γ and x(0)
+    let oscillator = |_: f64, y: &[f64], p: &[f64], dydt: &mut [f64]| {
+        dydt[0] = y[1];
+        dydt[1] = -p[0] * p[0] * y[0] - p[1] * y[1];
+    };
+    let truth = solve_sensitivity(oscillator, &[1.5, 0.0], &[2.0, 0.3], 0.0, 5.0).unwrap();
+    let t = (1..=50).map(|i| i as f64 / 10.0).collect::<Vec<_>>();
+    let y = t
+        .iter()
+        .map(|&ti| truth.eval(ti).unwrap()[0])
+        .collect::<Vec<_>>();
+
+    let fit = ode_fit(
+        oscillator,
+        |p| vec![p[2], 0.0],
+        [1.8, 0.5, 1.0],
+        0.0,
+        0,
+        &t,
+        &y,
+    )
+    .unwrap();
+    approx::assert_abs_diff_eq!(fit.params[0], 2.0, epsilon = 1.0e-5);
+    approx::assert_abs_diff_eq!(fit.params[1], 0.3, epsilon = 1.0e-5);
+    approx::assert_abs_diff_eq!(fit.params[2], 1.5, epsilon = 1.0e-5);
+}
+
+#[test]
+fn test_dde() {
+    disable_error_handler();
+
+    // y'(t) = -y(t - 1) with y = 1 up to 0 is a polynomial of degree k on [k - 1, k]
+    let f = |_: f64, _: &[f64], y_delayed: &[f64], dydt: &mut [f64]| dydt[0] = -y_delayed[0];
+    let solution = solve_dde(f, |_| vec![1.0], 1.0, 0.0, 3.0).unwrap();
+    let exact = |t: f64| {
+        let mut y = 1.0 - t;
+        if t > 1.0 {
+            y += (t - 1.0).powi(2) / 2.0;
+        }
+        if t > 2.0 {
+            y -= (t - 2.0).powi(3) / 6.0;
+        }
+        y
+    };
+    for t in [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0] {
+        approx::assert_abs_diff_eq!(solution.eval(t).unwrap()[0], exact(t), epsilon = 1.0e-9);
+    }
+    approx::assert_abs_diff_eq!(solution.final_state()[0], -1.0 / 6.0, epsilon = 1.0e-9);
+    approx::assert_abs_diff_eq!(
+        solution.eval_derivative(1.5).unwrap()[0],
+        -0.5,
+        epsilon = 1.0e-6
+    );
+
+    // Hutchinson's delayed logistic equation settles at its capacity for small delays
+    let logistic = |_: f64, y: &[f64], y_delayed: &[f64], dydt: &mut [f64]| {
+        dydt[0] = y[0] * (1.0 - y_delayed[0]);
+    };
+    let solution = solve_dde(logistic, |_| vec![0.1], 0.5, 0.0, 60.0).unwrap();
+    approx::assert_abs_diff_eq!(solution.final_state()[0], 1.0, epsilon = 1.0e-6);
+}
+
+#[test]
+fn test_gsl_suite() {
+    use crate::testing::*;
+
+    disable_error_handler();
+
+    // Ported from test_evolve_sin and test_evolve_exp in ode-initval2/test.c, for every stepper
+    let steppers = [
+        Stepper::Rk2,
+        Stepper::Rk4,
+        Stepper::Rkf45,
+        Stepper::Rkck,
+        Stepper::Rk8pd,
+    ];
+    for stepper in steppers {
+        // y'' = -y from y(0) = 0, y'(0) = 1 to t = 2
+        let sin = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+            dydt[0] = y[1];
+            dydt[1] = -y[0];
+        };
+        let solution = solve_ext(
+            stepper,
+            sin,
+            &[0.0, 1.0],
+            0.0,
+            2.0,
+            1.0e-3,
+            1.0e-12,
+            0.0,
+            1_000_000,
+        )
+        .unwrap();
+        assert_rel(solution.final_state()[0], 2.0f64.sin(), 1.0e-6);
+        assert_rel(solution.final_state()[1], 2.0f64.cos(), 1.0e-6);
+
+        // y' = y from y(0) = 1 to t = 5
+        let exp = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = y[0];
+        let solution = solve_ext(
+            stepper,
+            exp,
+            &[1.0],
+            0.0,
+            5.0,
+            1.0e-3,
+            0.0,
+            1.0e-12,
+            1_000_000,
+        )
+        .unwrap();
+        assert_rel(solution.final_state()[0], 5.0f64.exp(), 1.0e-6);
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let decay = |_: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+
+    // Empty state
+    solve(decay, &[], 0.0, 1.0).unwrap_err();
+
+    // Nonpositive initial step or negative tolerance
+    solve_ext(Stepper::Rk4, decay, &[1.0], 0.0, 1.0, 0.0, 1.0e-6, 0.0, 100).unwrap_err();
+    solve_ext(Stepper::Rk4, decay, &[1.0], 0.0, 1.0, 0.1, -1.0, 0.0, 100).unwrap_err();
+
+    // Too many steps
+    solve_ext(
+        Stepper::Rk2,
+        decay,
+        &[1.0],
+        0.0,
+        100.0,
+        1.0e-3,
+        1.0e-12,
+        0.0,
+        10,
+    )
+    .unwrap_err();
+
+    // Blows up, or panics
+    solve(|_, y, dydt| dydt[0] = y[0].ln(), &[-1.0], 0.0, 1.0).unwrap_err();
+    solve(|_, _, _| panic!(), &[1.0], 0.0, 1.0).unwrap_err();
+
+    let solution = solve(decay, &[1.0], 0.0, 1.0).unwrap();
+
+    // Outside the integrated range
+    solution.eval(1.5).unwrap_err();
+    solution.eval(f64::NAN).unwrap_err();
+
+    // No parameters, or initial sensitivities of the wrong size
+    let decay = |_: f64, y: &[f64], p: &[f64], dydt: &mut [f64]| dydt[0] = -p[0] * y[0];
+    solve_sensitivity(decay, &[1.0], &[], 0.0, 1.0).unwrap_err();
+    solve_sensitivity_ext(
+        Stepper::Rk4,
+        decay,
+        &[1.0],
+        &[0.0, 0.0],
+        &[1.0],
+        0.0,
+        1.0,
+        0.1,
+        1.0e-6,
+        0.0,
+        100,
+    )
+    .unwrap_err();
+
+    // Observations before the initial time, or of a component that doesn't exist
+    ode_fit(
+        decay,
+        |_| vec![1.0],
+        [1.0],
+        0.0,
+        0,
+        &[-1.0, 1.0],
+        &[1.0, 1.0],
+    )
+    .unwrap_err();
+    ode_fit(
+        decay,
+        |_| vec![1.0],
+        [1.0],
+        0.0,
+        1,
+        &[1.0, 2.0],
+        &[1.0, 1.0],
+    )
+    .unwrap_err();
+}
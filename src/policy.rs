@@ -0,0 +1,228 @@
+/*
+    policy.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::*;
+use drop_guard::guard;
+use std::cell::Cell;
+
+/*
+
+    Defaults of the drivers that don't take their tolerances and iteration limits as arguments,
+    like `integrate::qag`, `min::minimize`, `roots::find_root`, `multimin::multimin` and `nonlinear_fit`.
+
+    Each of those drivers has its own defaults, which a `NumericPolicy` overrides per thread:
+    `set_policy` for the rest of the thread, or `with_policy` for the calls inside a closure.
+    Each also has a `_with_policy` variant, like `integrate::qag_with_policy`, taking the policy as argument.
+    Leaving a field `None` keeps the default of the driver, so the default policy changes nothing.
+
+    The maximum amount of iterations is the amount of subintervals for the adaptive integrators,
+    the amount of function evaluations for `cubature::adaptive`, and the amount of steps for `ode::solve`.
+    The fits only have relative tolerances, and use `epsrel` for their `xtol`, `gtol` and `ftol`.
+
+    The error policy applies to every driver, also to the `_ext` variants with explicit tolerances.
+    `ErrorPolicy::Strict` fails when a driver runs out of iterations or can't reach its tolerance.
+    `ErrorPolicy::BestEffort` returns the estimate at that point instead, for the drivers that have one:
+    the integrators, the minimizers, `roots::find_root` and the fits. The ODE solvers still fail,
+    as their solution doesn't reach the end. The error estimates of the integrators
+    tell how good the estimate is, for the others check the result.
+    The errors a policy tolerates aren't passed to the error handler, so a best effort also works
+    with `PanicOnError`. Other errors, like a domain error or a divergent integral, fail in both cases,
+    and are passed to the error handler in both cases.
+
+*/
+
+/// Defaults of the drivers for this thread, see `set_policy`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct NumericPolicy {
+    /// Absolute tolerance
+    pub epsabs: Option<f64>,
+    /// Relative tolerance
+    pub epsrel: Option<f64>,
+    /// Maximum amount of iterations
+    pub max_iter: Option<usize>,
+    pub errors: ErrorPolicy,
+}
+
+/// What a driver does when it doesn't converge
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Fail with the error of GSL or `GSLError::MaxIteration`
+    #[default]
+    Strict,
+    /// Return the estimate so far
+    BestEffort,
+}
+
+thread_local! {
+    static POLICY: Cell<NumericPolicy> = Cell::new(NumericPolicy::default());
+}
+
+/// Sets the policy of the drivers called from this thread
+pub fn set_policy(policy: NumericPolicy) {
+    POLICY.with(|p| p.set(policy));
+}
+
+/// Policy of the drivers called from this thread
+pub fn policy() -> NumericPolicy {
+    POLICY.with(Cell::get)
+}
+
+/// Calls `f` with `policy` set, restoring the previous policy afterwards, also if `f` panics
+pub fn with_policy<T, F: FnOnce() -> T>(policy: NumericPolicy, f: F) -> T {
+    let previous = POLICY.with(|p| p.replace(policy));
+    let _restore = guard(previous, set_policy);
+    f()
+}
+
+impl NumericPolicy {
+    /// Whether this policy accepts the estimate of a driver that failed with `error`
+    pub fn tolerates(&self, error: GSLError) -> bool {
+        self.errors == ErrorPolicy::BestEffort
+            && matches!(
+                error,
+                GSLError::MaxIteration
+                    | GSLError::Tolerance
+                    | GSLError::Round
+                    | GSLError::NoProgress
+                    | GSLError::NoProgressJacobian
+                    | GSLError::ToleranceF
+                    | GSLError::ToleranceX
+                    | GSLError::ToleranceG
+            )
+    }
+
+    /// The tolerances of the policy, with the defaults of a driver for the unset ones
+    pub(crate) fn tolerances(&self, epsabs: f64, epsrel: f64) -> (f64, f64) {
+        (self.epsabs.unwrap_or(epsabs), self.epsrel.unwrap_or(epsrel))
+    }
+
+    /// The iteration limit of the policy, or the default of a driver
+    pub(crate) fn iterations(&self, max_iter: usize) -> usize {
+        self.max_iter.unwrap_or(max_iter)
+    }
+}
+
+/// Accepts the convergence errors the policy of this thread tolerates
+pub(crate) fn converged(result: Result<()>) -> Result<()> {
+    match result {
+        Err(e) if policy().tolerates(e) => Ok(()),
+        result => result,
+    }
+}
+
+#[test]
+fn test_policy() {
+    assert_eq!(policy(), NumericPolicy::default());
+
+    let strict = NumericPolicy {
+        epsabs: Some(1.0e-3),
+        ..NumericPolicy::default()
+    };
+    let lenient = NumericPolicy {
+        errors: ErrorPolicy::BestEffort,
+        ..strict
+    };
+    assert_eq!(strict.tolerances(1.0e-9, 0.5), (1.0e-3, 0.5));
+    assert_eq!(strict.iterations(100), 100);
+    assert!(!strict.tolerates(GSLError::MaxIteration));
+    assert!(lenient.tolerates(GSLError::MaxIteration));
+    assert!(!lenient.tolerates(GSLError::Domain));
+
+    // Scoped, also when panicking
+    with_policy(lenient, || {
+        assert_eq!(policy(), lenient);
+        assert_eq!(converged(Err(GSLError::Round)), Ok(()));
+        assert_eq!(converged(Err(GSLError::Domain)), Err(GSLError::Domain));
+    });
+    assert_eq!(converged(Err(GSLError::Round)), Err(GSLError::Round));
+    std::panic::catch_unwind(|| with_policy(lenient, || panic!())).unwrap_err();
+    assert_eq!(policy(), NumericPolicy::default());
+
+    set_policy(strict);
+    assert_eq!(policy(), strict);
+    set_policy(NumericPolicy::default());
+}
+
+#[test]
+fn test_best_effort() {
+    disable_error_handler();
+
+    // Too few subintervals for the singularity at 0
+    let few = NumericPolicy {
+        max_iter: Some(3),
+        ..NumericPolicy::default()
+    };
+    let f = |x: f64| 1.0 / x.sqrt();
+    assert_eq!(
+        with_policy(few, || integrate::qag(0.0, 1.0, f)).unwrap_err(),
+        GSLError::MaxIteration
+    );
+    let best_effort = NumericPolicy {
+        errors: ErrorPolicy::BestEffort,
+        ..few
+    };
+    let result = with_policy(best_effort, || integrate::qag(0.0, 1.0, f)).unwrap();
+    assert!((result.val - 2.0).abs() < 0.1);
+    assert!(result.err > 1.0e-9);
+
+    // Looser tolerance, fewer iterations
+    let loose = NumericPolicy {
+        epsabs: Some(1.0e-2),
+        ..NumericPolicy::default()
+    };
+    let root = with_policy(loose, || roots::find_root(|x| x * x - 2.0, 0.0, 2.0))
+        .unwrap()
+        .root;
+    assert!((root - 2.0f64.sqrt()).abs() < 1.0e-2);
+    let one = NumericPolicy {
+        max_iter: Some(1),
+        errors: ErrorPolicy::BestEffort,
+        ..NumericPolicy::default()
+    };
+    let root = with_policy(one, || roots::find_root(|x| x * x - 2.0, 0.0, 2.0))
+        .unwrap()
+        .root;
+    assert!((0.0..=2.0).contains(&root));
+
+    // Other errors still fail
+    with_policy(best_effort, || {
+        roots::find_root(|x| x * x + 1.0, 0.0, 2.0).unwrap_err();
+    });
+}
+
+#[test]
+fn test_best_effort_handler() {
+    // Tolerated errors don't reach the handler
+    set_error_handler(PanicOnError);
+    let best_effort = NumericPolicy {
+        max_iter: Some(3),
+        errors: ErrorPolicy::BestEffort,
+        ..NumericPolicy::default()
+    };
+    let f = |x: f64| 1.0 / x.sqrt();
+    let result = with_policy(best_effort, || integrate::qag(0.0, 1.0, f)).unwrap();
+    assert!((result.val - 2.0).abs() < 0.1);
+
+    // Explicit policy, the policy of the thread stays strict
+    let result = integrate::qag_with_policy(best_effort, 0.0, 1.0, f).unwrap();
+    assert!((result.val - 2.0).abs() < 0.1);
+    assert_eq!(policy(), NumericPolicy::default());
+    let x = min::minimize_with_policy(best_effort, 0.0, 3.0, 1.0, |x| (x - 2.0).powi(2)).unwrap();
+    assert!((x - 2.0).abs() < 0.5);
+    disable_error_handler();
+}
@@ -0,0 +1,505 @@
+/*
+    poly.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::eigen;
+use crate::*;
+use drop_guard::guard;
+use num_complex::Complex64;
+use std::os::raw::c_int;
+
+/*
+
+    Polynomials are given by their coefficients in increasing order,
+    `p(x) = c[0] + c[1] x + ... + c[n] x^n`, with a nonzero leading coefficient `c[n]`.
+
+    `roots` uses GSL's balanced QR reduction of the companion matrix.
+    `roots_eigen` hands the companion matrix to the nonsymmetric eigensolver in `eigen` instead,
+    which allows comparing both on ill-conditioned polynomials.
+
+    For any `z`, the disk around `z` with radius `n |p(z)| / |p'(z)|` contains at least one root.
+    `error_bound` adds the rounding error of evaluating `p(z)` with Horner's scheme to `|p(z)|`.
+    Clusters of roots share their disks, so the bound doesn't say which root of a cluster is nearby.
+
+    The quadratic and cubic solvers use the closed formulas. The quadratic solvers accept a vanishing `a`
+    and then solve the linear equation, the cubic solvers take monic polynomials `x^3 + a x^2 + b x + c`.
+    Real solvers count a double root twice.
+
+    `DividedDifferences` is the Newton form of the interpolating polynomial through a set of points,
+    optionally matching the derivatives as well (Hermite interpolation).
+
+*/
+
+/// Complex roots of the polynomial with coefficients `c`, sorted by real and then imaginary part
+pub fn roots(c: &[f64]) -> Result<Vec<Complex64>> {
+    unsafe {
+        check_coefficients(c)?;
+        let n = c.len();
+
+        let workspace = guard(gsl_poly_complex_workspace_alloc(n as u64), |workspace| {
+            gsl_poly_complex_workspace_free(workspace);
+        });
+        assert!(!workspace.is_null());
+
+        let mut z = vec![0.0; 2 * (n - 1)];
+        GSLError::from_raw(gsl_poly_complex_solve(
+            c.as_ptr(),
+            n as u64,
+            *workspace,
+            z.as_mut_ptr(),
+        ))?;
+
+        let mut roots = z
+            .chunks_exact(2)
+            .map(|z| Complex64::new(z[0], z[1]))
+            .collect::<Vec<_>>();
+        sort(&mut roots);
+        Ok(roots)
+    }
+}
+
+/// Complex roots of the polynomial with coefficients `c` as the eigenvalues of its companion matrix,
+/// sorted by real and then imaginary part
+pub fn roots_eigen(c: &[f64]) -> Result<Vec<Complex64>> {
+    check_coefficients(c)?;
+    let n = c.len() - 1;
+
+    // Companion matrix of the monic polynomial, its characteristic polynomial is p / c[n]
+    let mut companion = vec![0.0; n * n];
+    for j in 0..n {
+        companion[j] = -c[n - 1 - j] / c[n];
+    }
+    for i in 1..n {
+        companion[i * n + i - 1] = 1.0;
+    }
+
+    let mut roots = eigen::nonsymmetric(n, &companion)?;
+    sort(&mut roots);
+    Ok(roots)
+}
+
+/// Value of the polynomial with coefficients `c` at `z`
+pub fn eval(c: &[f64], z: Complex64) -> Complex64 {
+    horner(c, z).0
+}
+
+/// Refines an approximate root `z` with at most `max_iter` Newton steps,
+/// stopping as soon as a step doesn't decrease `|p|`
+pub fn polish(c: &[f64], z: Complex64, max_iter: usize) -> Complex64 {
+    let mut z = z;
+    let (mut p, mut dp) = horner(c, z);
+    for _ in 0..max_iter {
+        if p.norm() == 0.0 || dp.norm() == 0.0 {
+            break;
+        }
+        let next = z - p / dp;
+        let (next_p, next_dp) = horner(c, next);
+        if next_p.is_nan() || next_p.norm() >= p.norm() {
+            break;
+        }
+        z = next;
+        p = next_p;
+        dp = next_dp;
+    }
+    z
+}
+
+/// Radius of a disk around `z` that contains a root of the polynomial with coefficients `c`.
+/// Infinite where the derivative vanishes. Fails with `GSLError::Invalid` without coefficients.
+pub fn error_bound(c: &[f64], z: Complex64) -> Result<f64> {
+    if c.is_empty() {
+        return Err(GSLError::Invalid);
+    }
+    let n = c.len() - 1;
+    let (p, dp) = horner(c, z);
+
+    // Rounding error of Horner's scheme, with a factor 2 for complex arithmetic
+    let r = z.norm();
+    let magnitude = c.iter().rev().fold(0.0, |sum, ci| sum * r + ci.abs());
+    let rounding = 2.0 * (2 * n) as f64 * f64::EPSILON * magnitude;
+
+    let dp = dp.norm();
+    if dp == 0.0 {
+        return Ok(f64::INFINITY);
+    }
+    Ok(n as f64 * (p.norm() + rounding) / dp)
+}
+
+/// Value of the polynomial with coefficients `c` at real `x`.
+/// Fails with `GSLError::Invalid` without coefficients, or with more than GSL can index.
+pub fn eval_real(c: &[f64], x: f64) -> Result<f64> {
+    unsafe {
+        if c.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        let len = c_int::try_from(c.len()).map_err(|_| GSLError::Invalid)?;
+        Ok(gsl_poly_eval(c.as_ptr(), len, x))
+    }
+}
+
+/// Value and the first `n_derivs` derivatives of the polynomial with coefficients `c` at `x`
+pub fn eval_derivs(c: &[f64], x: f64, n_derivs: usize) -> Result<Vec<f64>> {
+    unsafe {
+        if c.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        let mut res = vec![0.0; n_derivs + 1];
+        GSLError::from_raw(gsl_poly_eval_derivs(
+            c.as_ptr(),
+            c.len() as u64,
+            x,
+            res.as_mut_ptr(),
+            res.len() as u64,
+        ))?;
+        Ok(res)
+    }
+}
+
+/// Real roots of `a x^2 + b x + c` in ascending order
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    unsafe {
+        let mut x = [0.0; 2];
+        let n = gsl_poly_solve_quadratic(a, b, c, &mut x[0], &mut x[1]);
+        x[..n as usize].to_vec()
+    }
+}
+
+/// Complex roots of `a x^2 + b x + c`, sorted by real and then imaginary part
+pub fn complex_solve_quadratic(a: f64, b: f64, c: f64) -> Vec<Complex64> {
+    unsafe {
+        let mut z = [gsl_complex { dat: [0.0; 2] }; 2];
+        let (z0, z1) = z.split_at_mut(1);
+        let n = gsl_poly_complex_solve_quadratic(a, b, c, &mut z0[0], &mut z1[0]);
+        from_gsl_complex(&z[..n as usize])
+    }
+}
+
+/// Real roots of `x^3 + a x^2 + b x + c` in ascending order, either one or three
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    unsafe {
+        let mut x = [0.0; 3];
+        let (x0, rest) = x.split_at_mut(1);
+        let (x1, x2) = rest.split_at_mut(1);
+        let n = gsl_poly_solve_cubic(a, b, c, &mut x0[0], &mut x1[0], &mut x2[0]);
+        x[..n as usize].to_vec()
+    }
+}
+
+/// Complex roots of `x^3 + a x^2 + b x + c`, sorted by real and then imaginary part
+pub fn complex_solve_cubic(a: f64, b: f64, c: f64) -> Vec<Complex64> {
+    unsafe {
+        let mut z = [gsl_complex { dat: [0.0; 2] }; 3];
+        let (z0, rest) = z.split_at_mut(1);
+        let (z1, z2) = rest.split_at_mut(1);
+        let n = gsl_poly_complex_solve_cubic(a, b, c, &mut z0[0], &mut z1[0], &mut z2[0]);
+        from_gsl_complex(&z[..n as usize])
+    }
+}
+
+/// Interpolating polynomial in Newton form
+#[derive(Clone, Debug)]
+pub struct DividedDifferences {
+    x: Vec<f64>,
+    dd: Vec<f64>,
+}
+
+impl DividedDifferences {
+    /// Polynomial of degree `n - 1` through the `n` points `(x[i], y[i])` with distinct `x`
+    pub fn new(x: &[f64], y: &[f64]) -> Result<Self> {
+        unsafe {
+            check_nodes(x, y)?;
+            let mut dd = vec![0.0; x.len()];
+            GSLError::from_raw(gsl_poly_dd_init(
+                dd.as_mut_ptr(),
+                x.as_ptr(),
+                y.as_ptr(),
+                x.len() as u64,
+            ))?;
+            Ok(DividedDifferences { x: x.to_vec(), dd })
+        }
+    }
+
+    /// Polynomial of degree `2n - 1` through the `n` points `(x[i], y[i])` with slopes `dydx[i]`
+    pub fn hermite(x: &[f64], y: &[f64], dydx: &[f64]) -> Result<Self> {
+        unsafe {
+            check_nodes(x, y)?;
+            if dydx.len() != x.len() {
+                return Err(GSLError::BadLength);
+            }
+            let mut dd = vec![0.0; 2 * x.len()];
+            let mut z = vec![0.0; 2 * x.len()];
+            GSLError::from_raw(gsl_poly_dd_hermite_init(
+                dd.as_mut_ptr(),
+                z.as_mut_ptr(),
+                x.as_ptr(),
+                y.as_ptr(),
+                dydx.as_ptr(),
+                x.len() as u64,
+            ))?;
+            Ok(DividedDifferences { x: z, dd })
+        }
+    }
+
+    /// Divided differences, the coefficients of the Newton form
+    pub fn coefficients(&self) -> &[f64] {
+        &self.dd
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        unsafe { gsl_poly_dd_eval(self.dd.as_ptr(), self.x.as_ptr(), self.dd.len() as u64, x) }
+    }
+
+    /// Coefficients `c` of the Taylor expansion around `xp`, `p(x) = c[0] + c[1] (x - xp) + ...`
+    pub fn taylor(&self, xp: f64) -> Result<Vec<f64>> {
+        unsafe {
+            let n = self.dd.len();
+            let mut c = vec![0.0; n];
+            let mut w = vec![0.0; n];
+            GSLError::from_raw(gsl_poly_dd_taylor(
+                c.as_mut_ptr(),
+                xp,
+                self.dd.as_ptr(),
+                self.x.as_ptr(),
+                n as u64,
+                w.as_mut_ptr(),
+            ))?;
+            Ok(c)
+        }
+    }
+}
+
+/// `p(z)` and `p'(z)`
+fn horner(c: &[f64], z: Complex64) -> (Complex64, Complex64) {
+    let mut p = Complex64::new(0.0, 0.0);
+    let mut dp = Complex64::new(0.0, 0.0);
+    for &ci in c.iter().rev() {
+        dp = dp * z + p;
+        p = p * z + ci;
+    }
+    (p, dp)
+}
+
+fn check_coefficients(c: &[f64]) -> Result<()> {
+    if c.len() < 2 || c[c.len() - 1] == 0.0 {
+        return Err(GSLError::Invalid);
+    }
+    if c.iter().any(|ci| !ci.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+    Ok(())
+}
+
+fn check_nodes(x: &[f64], y: &[f64]) -> Result<()> {
+    if x.is_empty() {
+        return Err(GSLError::Invalid);
+    }
+    if x.len() != y.len() {
+        return Err(GSLError::BadLength);
+    }
+    for (i, xi) in x.iter().enumerate() {
+        if x[..i].contains(xi) {
+            return Err(GSLError::Invalid);
+        }
+    }
+    Ok(())
+}
+
+fn from_gsl_complex(z: &[gsl_complex]) -> Vec<Complex64> {
+    let mut roots = z
+        .iter()
+        .map(|z| Complex64::new(z.dat[0], z.dat[1]))
+        .collect::<Vec<_>>();
+    sort(&mut roots);
+    roots
+}
+
+fn sort(roots: &mut [Complex64]) {
+    roots.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+}
+
+#[cfg(test)]
+fn from_roots(roots: &[f64]) -> Vec<f64> {
+    let mut c = vec![1.0];
+    for r in roots {
+        let mut next = vec![0.0; c.len() + 1];
+        for (i, ci) in c.iter().enumerate() {
+            next[i] -= r * ci;
+            next[i + 1] += ci;
+        }
+        c = next;
+    }
+    c
+}
+
+#[test]
+fn test_roots() {
+    disable_error_handler();
+
+    // (x - 1)(x - 2)(x - 3)
+    let c = from_roots(&[1.0, 2.0, 3.0]);
+    for roots in [roots(&c).unwrap(), roots_eigen(&c).unwrap()] {
+        assert_eq!(roots.len(), 3);
+        for (k, z) in roots.iter().enumerate() {
+            approx::assert_abs_diff_eq!(z.re, (k + 1) as f64, epsilon = 1.0e-10);
+            approx::assert_abs_diff_eq!(z.im, 0.0, epsilon = 1.0e-10);
+        }
+    }
+
+    // x^2 + 1
+    let roots = roots_eigen(&[1.0, 0.0, 1.0]).unwrap();
+    approx::assert_abs_diff_eq!(roots[0].im, -1.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(roots[1].im, 1.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(
+        eval(&[1.0, 0.0, 1.0], roots[1]).norm(),
+        0.0,
+        epsilon = 1.0e-12
+    );
+}
+
+#[test]
+fn test_error_bound() {
+    disable_error_handler();
+
+    // Wilkinson's polynomial of degree 12 is badly conditioned
+    let exact = (1..=12).map(|k| k as f64).collect::<Vec<_>>();
+    let c = from_roots(&exact);
+    for roots in [roots(&c).unwrap(), roots_eigen(&c).unwrap()] {
+        for z in roots {
+            let distance = exact
+                .iter()
+                .map(|&r| (z - r).norm())
+                .fold(f64::INFINITY, f64::min);
+            assert!(distance <= error_bound(&c, z).unwrap());
+
+            // Polishing never increases the residual
+            let polished = polish(&c, z, 10);
+            assert!(eval(&c, polished).norm() <= eval(&c, z).norm());
+        }
+    }
+
+    // At a double root the derivative vanishes
+    assert_eq!(
+        error_bound(&[1.0, -2.0, 1.0], Complex64::new(1.0, 0.0)).unwrap(),
+        f64::INFINITY
+    );
+
+    // Newton converges from a rough estimate
+    let z = polish(&c, Complex64::new(3.1, 0.01), 50);
+    approx::assert_abs_diff_eq!(z.re, 3.0, epsilon = 1.0e-9);
+    approx::assert_abs_diff_eq!(z.im, 0.0, epsilon = 1.0e-9);
+}
+
+#[test]
+fn test_eval() {
+    disable_error_handler();
+
+    // 1 - 2x + 3x^2
+    let c = [1.0, -2.0, 3.0];
+    assert_eq!(eval_real(&c, 2.0).unwrap(), 9.0);
+    assert_eq!(eval(&c, Complex64::new(2.0, 0.0)).re, 9.0);
+    assert_eq!(eval_derivs(&c, 2.0, 3).unwrap(), [9.0, 10.0, 6.0, 0.0]);
+}
+
+#[test]
+fn test_solvers() {
+    disable_error_handler();
+
+    // (x - 1)(x - 3), a double root, none and a linear equation
+    assert_eq!(solve_quadratic(1.0, -4.0, 3.0), [1.0, 3.0]);
+    assert_eq!(solve_quadratic(1.0, -2.0, 1.0), [1.0, 1.0]);
+    assert!(solve_quadratic(1.0, 0.0, 1.0).is_empty());
+    assert_eq!(solve_quadratic(0.0, 2.0, -1.0), [0.5]);
+
+    let z = complex_solve_quadratic(1.0, 0.0, 4.0);
+    assert_eq!(z.len(), 2);
+    approx::assert_abs_diff_eq!(z[0].im, -2.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(z[1].im, 2.0, epsilon = 1.0e-12);
+
+    // (x - 1)(x - 2)(x - 3) and (x - 1)(x^2 + 1)
+    let x = solve_cubic(-6.0, 11.0, -6.0);
+    assert_eq!(x.len(), 3);
+    for (k, xk) in x.iter().enumerate() {
+        approx::assert_abs_diff_eq!(*xk, (k + 1) as f64, epsilon = 1.0e-12);
+    }
+    let x = solve_cubic(-1.0, 1.0, -1.0);
+    assert_eq!(x.len(), 1);
+    approx::assert_abs_diff_eq!(x[0], 1.0, epsilon = 1.0e-12);
+
+    let z = complex_solve_cubic(-1.0, 1.0, -1.0);
+    assert_eq!(z.len(), 3);
+    for z in z {
+        approx::assert_abs_diff_eq!(
+            eval(&[-1.0, 1.0, -1.0, 1.0], z).norm(),
+            0.0,
+            epsilon = 1.0e-12
+        );
+    }
+}
+
+#[test]
+fn test_divided_differences() {
+    disable_error_handler();
+
+    // Interpolating 1 - 2x + 3x^2 is exact
+    let c = [1.0, -2.0, 3.0];
+    let x = [-1.0, 0.5, 2.0];
+    let y = x.map(|x| eval_real(&c, x).unwrap());
+    let dd = DividedDifferences::new(&x, &y).unwrap();
+    assert_eq!(dd.coefficients().len(), 3);
+    approx::assert_abs_diff_eq!(dd.eval(1.3), eval_real(&c, 1.3).unwrap(), epsilon = 1.0e-12);
+    let taylor = dd.taylor(0.0).unwrap();
+    for (ti, ci) in taylor.iter().zip(c) {
+        approx::assert_abs_diff_eq!(*ti, ci, epsilon = 1.0e-12);
+    }
+
+    // Two points with slopes determine a cubic
+    let c = [2.0, 0.0, -1.0, 0.5];
+    let x = [0.0, 1.0];
+    let y = x.map(|x| eval_real(&c, x).unwrap());
+    let dydx = x.map(|x| eval_derivs(&c, x, 1).unwrap()[1]);
+    let dd = DividedDifferences::hermite(&x, &y, &dydx).unwrap();
+    assert_eq!(dd.coefficients().len(), 4);
+    approx::assert_abs_diff_eq!(dd.eval(2.5), eval_real(&c, 2.5).unwrap(), epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Constant polynomial
+    roots(&[1.0]).unwrap_err();
+    roots_eigen(&[]).unwrap_err();
+
+    // Vanishing leading coefficient
+    roots(&[1.0, 2.0, 0.0]).unwrap_err();
+    roots_eigen(&[1.0, 2.0, 0.0]).unwrap_err();
+
+    // Not finite
+    roots_eigen(&[f64::NAN, 1.0]).unwrap_err();
+
+    // No coefficients
+    eval_derivs(&[], 1.0, 2).unwrap_err();
+    eval_real(&[], 1.0).unwrap_err();
+    error_bound(&[], Complex64::new(1.0, 0.0)).unwrap_err();
+
+    // No points, repeated points or mismatched lengths
+    DividedDifferences::new(&[], &[]).unwrap_err();
+    DividedDifferences::new(&[1.0, 1.0], &[1.0, 2.0]).unwrap_err();
+    DividedDifferences::new(&[1.0, 2.0], &[1.0]).unwrap_err();
+    DividedDifferences::hermite(&[1.0, 2.0], &[1.0, 2.0], &[1.0]).unwrap_err();
+}
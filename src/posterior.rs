@@ -0,0 +1,374 @@
+/*
+    posterior.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::mcmc;
+use crate::stats;
+use crate::*;
+use std::fmt;
+
+/*
+
+    Summaries of the draws of one or more MCMC chains, after discarding the warmup.
+    Every chain is a row-major matrix with one state of `dim` parameters per row,
+    as collected from the `sample` methods of the samplers in `mcmc`.
+
+    Credible intervals are either equal-tailed, with probability `(1 - level) / 2` on both sides,
+    or the highest posterior density interval, the shortest interval containing a fraction `level` of the draws.
+    The latter is only meaningful for unimodal posteriors.
+
+    Bands evaluate a model at every draw and take quantiles over the draws at every grid point.
+    A model returning its expected value gives a credible band of the model curve.
+    Adding simulated observation noise inside the closure gives the posterior predictive band.
+
+*/
+
+/// Draws of all chains, stored per parameter
+#[derive(Clone, Debug)]
+pub struct Posterior {
+    dim: usize,
+    n_chains: usize,
+    n_draws: usize,
+    draws: Box<[f64]>,
+}
+
+impl Posterior {
+    /// All chains need the same number of draws
+    pub fn new(chains: &[&[f64]], dim: usize) -> Result<Self> {
+        if dim == 0 || chains.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        let len = chains[0].len();
+        if chains.iter().any(|c| c.len() != len) || !len.is_multiple_of(dim) {
+            return Err(GSLError::BadLength);
+        }
+        let n_draws = len / dim;
+        if n_draws == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if chains.iter().any(|c| c.iter().any(|x| !x.is_finite())) {
+            return Err(GSLError::Domain);
+        }
+
+        let n_chains = chains.len();
+        let mut draws = vec![0.0; dim * n_chains * n_draws];
+        for (j, chain) in chains.iter().enumerate() {
+            for (k, state) in chain.chunks(dim).enumerate() {
+                for (i, &x) in state.iter().enumerate() {
+                    draws[(i * n_chains + j) * n_draws + k] = x;
+                }
+            }
+        }
+
+        Ok(Posterior {
+            dim,
+            n_chains,
+            n_draws,
+            draws: draws.into_boxed_slice(),
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn n_chains(&self) -> usize {
+        self.n_chains
+    }
+
+    /// Number of draws per chain
+    pub fn n_draws(&self) -> usize {
+        self.n_draws
+    }
+
+    /// Draws of parameter `i`, chain after chain, or `None` if `i >= dim`
+    pub fn parameter(&self, i: usize) -> Option<&[f64]> {
+        if i >= self.dim {
+            return None;
+        }
+        Some(self.draws_of(i))
+    }
+
+    /// State of draw `k` of chain `j`, or `None` if there's no such draw
+    pub fn state(&self, j: usize, k: usize) -> Option<Box<[f64]>> {
+        if j >= self.n_chains || k >= self.n_draws {
+            return None;
+        }
+        Some(self.state_of(j, k))
+    }
+
+    /// Equal-tailed credible interval of parameter `i` with probability `level`.
+    /// Fails with `GSLError::Invalid` if `i >= dim`.
+    pub fn credible_interval(&self, i: usize, level: f64) -> Result<(f64, f64)> {
+        check_level(level)?;
+        let sorted = sorted(self.parameter(i).ok_or(GSLError::Invalid)?);
+        equal_tailed(&sorted, level)
+    }
+
+    /// Highest posterior density interval of parameter `i` with probability `level`.
+    /// Fails with `GSLError::Invalid` if `i >= dim`.
+    pub fn hpd_interval(&self, i: usize, level: f64) -> Result<(f64, f64)> {
+        check_level(level)?;
+        let sorted = sorted(self.parameter(i).ok_or(GSLError::Invalid)?);
+
+        // Shortest window over the sorted draws containing the requested number of draws
+        let n = sorted.len();
+        let width = ((level * n as f64).ceil() as usize).clamp(1, n) - 1;
+        let lower = (0..n - width)
+            .min_by(|&a, &b| {
+                let wa = sorted[a + width] - sorted[a];
+                let wb = sorted[b + width] - sorted[b];
+                wa.partial_cmp(&wb).unwrap()
+            })
+            .unwrap();
+        Ok((sorted[lower], sorted[lower + width]))
+    }
+
+    /// Quantiles of `model(state, x)` over all draws at every point of `grid`,
+    /// the median together with an equal-tailed band with probability `level`
+    pub fn band<F: FnMut(&[f64], f64) -> f64>(
+        &self,
+        mut model: F,
+        grid: &[f64],
+        level: f64,
+    ) -> Result<Band> {
+        check_level(level)?;
+
+        let n = self.n_chains * self.n_draws;
+        let mut values = vec![0.0; grid.len() * n];
+        for j in 0..self.n_chains {
+            for k in 0..self.n_draws {
+                let state = self.state_of(j, k);
+                let draw = j * self.n_draws + k;
+                for (g, &x) in grid.iter().enumerate() {
+                    values[g * n + draw] = model(&state, x);
+                }
+            }
+        }
+        if values.iter().any(|y| y.is_nan()) {
+            return Err(GSLError::BadFunction);
+        }
+
+        let mut band = Band {
+            x: grid.into(),
+            median: vec![0.0; grid.len()].into_boxed_slice(),
+            lower: vec![0.0; grid.len()].into_boxed_slice(),
+            upper: vec![0.0; grid.len()].into_boxed_slice(),
+        };
+        for g in 0..grid.len() {
+            let sorted = sorted(&values[g * n..(g + 1) * n]);
+            band.median[g] = stats::median_sorted(&sorted)?;
+            (band.lower[g], band.upper[g]) = equal_tailed(&sorted, level)?;
+        }
+        Ok(band)
+    }
+
+    /// Summary of every parameter, named by `names`.
+    /// The diagnostics need at least 4 draws per chain.
+    pub fn summary(&self, names: &[&str]) -> Result<Summary> {
+        if names.len() != self.dim {
+            return Err(GSLError::BadLength);
+        }
+
+        let mut rows = Vec::with_capacity(self.dim);
+        for (i, name) in names.iter().enumerate() {
+            let draws = self.draws_of(i);
+            let sorted = sorted(draws);
+            let chains = Matrix::new(draws.iter().copied(), self.n_chains, self.n_draws);
+            rows.push(SummaryRow {
+                name: name.to_string(),
+                mean: stats::mean(draws),
+                sd: stats::sd(draws),
+                q5: stats::quantile_sorted(&sorted, 0.05)?,
+                median: stats::median_sorted(&sorted)?,
+                q95: stats::quantile_sorted(&sorted, 0.95)?,
+                rhat: mcmc::rhat(&chains)?,
+                ess_bulk: mcmc::ess_bulk(&chains)?,
+                ess_tail: mcmc::ess_tail(&chains)?,
+            });
+        }
+        Ok(Summary { rows })
+    }
+
+    fn draws_of(&self, i: usize) -> &[f64] {
+        let n = self.n_chains * self.n_draws;
+        &self.draws[i * n..(i + 1) * n]
+    }
+
+    fn state_of(&self, j: usize, k: usize) -> Box<[f64]> {
+        (0..self.dim)
+            .map(|i| self.draws_of(i)[j * self.n_draws + k])
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Band {
+    pub x: Box<[f64]>,
+    pub median: Box<[f64]>,
+    pub lower: Box<[f64]>,
+    pub upper: Box<[f64]>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SummaryRow {
+    pub name: String,
+    pub mean: f64,
+    pub sd: f64,
+    /// 5% quantile
+    pub q5: f64,
+    pub median: f64,
+    /// 95% quantile
+    pub q95: f64,
+    /// Rank normalized split R̂
+    pub rhat: f64,
+    pub ess_bulk: f64,
+    pub ess_tail: f64,
+}
+
+/// Table with one row per parameter, printed with `Display`
+#[derive(Clone, Debug)]
+pub struct Summary {
+    pub rows: Vec<SummaryRow>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.rows.iter().map(|r| r.name.len()).max().unwrap_or(0);
+        writeln!(
+            f,
+            "{:width$} {:>10} {:>10} {:>10} {:>10} {:>10} {:>7} {:>8} {:>8}",
+            "", "mean", "sd", "5%", "50%", "95%", "rhat", "ess_bulk", "ess_tail"
+        )?;
+        for r in &self.rows {
+            writeln!(
+                f,
+                "{:width$} {:>10.4} {:>10.4} {:>10.4} {:>10.4} {:>10.4} {:>7.3} {:>8.0} {:>8.0}",
+                r.name, r.mean, r.sd, r.q5, r.median, r.q95, r.rhat, r.ess_bulk, r.ess_tail
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn check_level(level: f64) -> Result<()> {
+    if !(level > 0.0 && level < 1.0) {
+        return Err(GSLError::Domain);
+    }
+    Ok(())
+}
+
+fn sorted(x: &[f64]) -> Vec<f64> {
+    let mut sorted = x.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted
+}
+
+fn equal_tailed(sorted: &[f64], level: f64) -> Result<(f64, f64)> {
+    let alpha = (1.0 - level) / 2.0;
+    Ok((
+        stats::quantile_sorted(sorted, alpha)?,
+        stats::quantile_sorted(sorted, 1.0 - alpha)?,
+    ))
+}
+
+#[test]
+fn test_posterior() {
+    disable_error_handler();
+
+    let mut rng = rng::Rng::with_seed(rng::RngType::Mt19937, 1);
+
+    // Independent draws of a line y = a + b x with a ~ N(1, 0.1^2) and b ~ N(2, 0.2^2)
+    let chains = (0..4)
+        .map(|_| {
+            (0..1000)
+                .flat_map(|_| [1.0 + rng.gaussian(0.1), 2.0 + rng.gaussian(0.2)])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let chains = chains.iter().map(|c| &c[..]).collect::<Vec<_>>();
+    let posterior = Posterior::new(&chains, 2).unwrap();
+    assert_eq!(posterior.n_chains(), 4);
+    assert_eq!(posterior.n_draws(), 1000);
+    assert_eq!(&*posterior.state(1, 3).unwrap(), &chains[1][6..8]);
+    assert_eq!(posterior.parameter(1).unwrap()[1003], chains[1][7]);
+
+    // 90% intervals are ±1.645 sigma
+    let (lower, upper) = posterior.credible_interval(0, 0.9).unwrap();
+    approx::assert_abs_diff_eq!(lower, 1.0 - 0.1645, epsilon = 0.02);
+    approx::assert_abs_diff_eq!(upper, 1.0 + 0.1645, epsilon = 0.02);
+    let (lower, upper) = posterior.hpd_interval(1, 0.9).unwrap();
+    approx::assert_abs_diff_eq!(lower, 2.0 - 0.329, epsilon = 0.04);
+    approx::assert_abs_diff_eq!(upper, 2.0 + 0.329, epsilon = 0.04);
+
+    // At x = 1 the line has a standard deviation of sqrt(0.1^2 + 0.2^2)
+    let band = posterior
+        .band(|p, x| p[0] + p[1] * x, &[0.0, 1.0], 0.9)
+        .unwrap();
+    approx::assert_abs_diff_eq!(band.median[1], 3.0, epsilon = 0.02);
+    approx::assert_abs_diff_eq!(
+        band.upper[1] - band.lower[1],
+        2.0 * 1.645 * 0.05f64.sqrt(),
+        epsilon = 0.04
+    );
+
+    let summary = posterior.summary(&["a", "b"]).unwrap();
+    let b = &summary.rows[1];
+    approx::assert_abs_diff_eq!(b.mean, 2.0, epsilon = 0.02);
+    approx::assert_abs_diff_eq!(b.sd, 0.2, epsilon = 0.02);
+    assert!(b.q5 < b.median && b.median < b.q95);
+    assert!(b.rhat < 1.01);
+    assert!(b.ess_bulk > 3000.0);
+    assert_eq!(summary.to_string().lines().count(), 3);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Chains of different lengths, or not a whole number of states
+    Posterior::new(&[&[1.0, 2.0], &[1.0]], 1).unwrap_err();
+    Posterior::new(&[&[1.0, 2.0, 3.0]], 2).unwrap_err();
+
+    // No draws or parameters
+    Posterior::new(&[], 1).unwrap_err();
+    Posterior::new(&[&[]], 1).unwrap_err();
+    Posterior::new(&[&[1.0]], 0).unwrap_err();
+    Posterior::new(&[&[f64::NAN]], 1).unwrap_err();
+
+    let posterior = Posterior::new(&[&[1.0, 2.0, 3.0]], 1).unwrap();
+
+    // Level out of range
+    posterior.credible_interval(0, 1.0).unwrap_err();
+    posterior.hpd_interval(0, 0.0).unwrap_err();
+
+    // No such parameter or draw
+    assert!(posterior.parameter(1).is_none());
+    assert!(posterior.state(0, 3).is_none());
+    assert!(posterior.state(1, 0).is_none());
+    posterior.credible_interval(1, 0.5).unwrap_err();
+    posterior.hpd_interval(1, 0.5).unwrap_err();
+
+    // Model not defined for the draws
+    posterior
+        .band(|p, x| (p[0] - x).ln(), &[2.0], 0.5)
+        .unwrap_err();
+
+    // Wrong number of names, or too few draws for the diagnostics
+    posterior.summary(&["a", "b"]).unwrap_err();
+    posterior.summary(&["a"]).unwrap_err();
+}
@@ -0,0 +1,230 @@
+/*
+    power.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::randist::{NoncentralChiSquared, NoncentralT};
+use crate::*;
+
+/*
+
+    Effect sizes follow Cohen (1988):
+    Cohen's d, the difference in means divided by the standard deviation, for t-tests,
+    and Cohen's w, `sqrt(Σ (p1_i - p0_i)^2 / p0_i)`, for chi-squared tests.
+
+    Power is the probability of rejecting the null hypothesis at significance level `alpha`,
+    computed from the noncentral distribution of the test statistic under the alternative.
+
+*/
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TTest {
+    /// One sample of `n` observations, or `n` paired differences
+    OneSample,
+    /// Two independent samples of `n` observations each, with equal variances
+    TwoSample,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tails {
+    /// One sided test, assuming the effect is in the tested direction
+    One,
+    Two,
+}
+
+/// Power of a t-test with effect size `d` and `n` observations per sample
+pub fn t_test_power(test: TTest, tails: Tails, d: f64, n: usize, alpha: f64) -> Result<f64> {
+    if !d.is_finite() {
+        return Err(GSLError::Domain);
+    }
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(GSLError::Domain);
+    }
+    if n < 2 {
+        return Err(GSLError::Invalid);
+    }
+
+    let n = n as f64;
+    let (df, delta) = match test {
+        TTest::OneSample => (n - 1.0, d * n.sqrt()),
+        TTest::TwoSample => (2.0 * n - 2.0, d * (n / 2.0).sqrt()),
+    };
+
+    let central = NoncentralT::new(df, 0.0)?;
+    let alternative = NoncentralT::new(df, delta)?;
+    match tails {
+        Tails::One => {
+            let critical = central.quantile(1.0 - alpha)?;
+            Ok(1.0 - alternative.cdf(critical)?)
+        }
+        Tails::Two => {
+            let critical = central.quantile(1.0 - alpha / 2.0)?;
+            Ok(1.0 - alternative.cdf(critical)? + alternative.cdf(-critical)?)
+        }
+    }
+}
+
+/// Smallest number of observations per sample for which a t-test with effect size `d` reaches `power`.
+/// One sided tests need a positive `d`, the power of an effect in the other direction stays below `alpha`.
+pub fn t_test_sample_size(
+    test: TTest,
+    tails: Tails,
+    d: f64,
+    alpha: f64,
+    power: f64,
+) -> Result<usize> {
+    if !(d != 0.0 && d.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+    if tails == Tails::One && d < 0.0 {
+        return Err(GSLError::Domain);
+    }
+    sample_size(2, power, |n| t_test_power(test, tails, d, n, alpha))
+}
+
+/// Power of a chi-squared test with `df` degrees of freedom,
+/// effect size `w` and `n` observations in total
+pub fn chi_squared_power(w: f64, df: usize, n: usize, alpha: f64) -> Result<f64> {
+    if !w.is_finite() {
+        return Err(GSLError::Domain);
+    }
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(GSLError::Domain);
+    }
+    if df == 0 || n == 0 {
+        return Err(GSLError::Invalid);
+    }
+
+    let central = NoncentralChiSquared::new(df as f64, 0.0)?;
+    let alternative = NoncentralChiSquared::new(df as f64, n as f64 * w * w)?;
+    let critical = central.quantile(1.0 - alpha)?;
+    Ok(1.0 - alternative.cdf(critical)?)
+}
+
+/// Smallest number of observations for which a chi-squared test with effect size `w` reaches `power`
+pub fn chi_squared_sample_size(w: f64, df: usize, alpha: f64, power: f64) -> Result<usize> {
+    if !(w != 0.0 && w.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+    sample_size(1, power, |n| chi_squared_power(w, df, n, alpha))
+}
+
+/// Smallest `n >= min` for which the increasing `power(n)` reaches `target`
+fn sample_size<F: FnMut(usize) -> Result<f64>>(
+    min: usize,
+    target: f64,
+    mut power: F,
+) -> Result<usize> {
+    const MAX: usize = 1 << 40;
+
+    if !(target > 0.0 && target < 1.0) {
+        return Err(GSLError::Domain);
+    }
+    if power(min)? >= target {
+        return Ok(min);
+    }
+
+    // Power(lo) < target <= power(hi)
+    let mut lo = min;
+    let mut hi = 2 * min;
+    while power(hi)? < target {
+        lo = hi;
+        hi *= 2;
+        if hi > MAX {
+            return Err(GSLError::MaxIteration);
+        }
+    }
+
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if power(mid)? < target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    Ok(hi)
+}
+
+#[test]
+fn test_t_test() {
+    disable_error_handler();
+
+    // Medium effect size, Cohen (1988)
+    assert_eq!(
+        t_test_sample_size(TTest::TwoSample, Tails::Two, 0.5, 0.05, 0.8).unwrap(),
+        64
+    );
+    assert_eq!(
+        t_test_sample_size(TTest::OneSample, Tails::Two, 0.5, 0.05, 0.8).unwrap(),
+        34
+    );
+    let power = t_test_power(TTest::TwoSample, Tails::Two, 0.5, 64, 0.05).unwrap();
+    approx::assert_abs_diff_eq!(power, 0.8015, epsilon = 1.0e-3);
+
+    // One sided tests need fewer observations
+    let one = t_test_sample_size(TTest::TwoSample, Tails::One, 0.5, 0.05, 0.8).unwrap();
+    assert!(one < 64);
+
+    // Without an effect the power is the significance level
+    let power = t_test_power(TTest::OneSample, Tails::Two, 0.0, 20, 0.05).unwrap();
+    approx::assert_abs_diff_eq!(power, 0.05, epsilon = 1.0e-9);
+}
+
+#[test]
+fn test_chi_squared() {
+    disable_error_handler();
+
+    // Medium effect size, Cohen (1988)
+    assert_eq!(chi_squared_sample_size(0.3, 1, 0.05, 0.8).unwrap(), 88);
+
+    // Power increases with the sample size
+    let small = chi_squared_power(0.3, 3, 50, 0.05).unwrap();
+    let large = chi_squared_power(0.3, 3, 200, 0.05).unwrap();
+    assert!(small < large);
+
+    let power = chi_squared_power(0.0, 2, 100, 0.01).unwrap();
+    approx::assert_abs_diff_eq!(power, 0.01, epsilon = 1.0e-9);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Significance level out of range
+    t_test_power(TTest::OneSample, Tails::Two, 0.5, 10, 1.5).unwrap_err();
+    chi_squared_power(0.3, 1, 10, 0.0).unwrap_err();
+
+    // Too few observations
+    t_test_power(TTest::OneSample, Tails::Two, 0.5, 1, 0.05).unwrap_err();
+
+    // Zero degrees of freedom
+    chi_squared_power(0.3, 0, 10, 0.05).unwrap_err();
+
+    // Without an effect no sample size is large enough
+    t_test_sample_size(TTest::OneSample, Tails::Two, 0.0, 0.05, 0.8).unwrap_err();
+    t_test_sample_size(TTest::OneSample, Tails::One, 0.0, 0.05, 0.8).unwrap_err();
+
+    // Effect against the direction of a one sided test
+    assert_eq!(
+        t_test_sample_size(TTest::TwoSample, Tails::One, -0.5, 0.05, 0.8),
+        Err(GSLError::Domain)
+    );
+
+    // Target power out of range
+    chi_squared_sample_size(0.3, 1, 0.05, 1.0).unwrap_err();
+}
@@ -0,0 +1,92 @@
+/*
+    prelude.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+
+    The commonly used part of the API in one import, `use gsl_rust::prelude::*;`.
+
+    This list is kept short and stable: items are only added when they are used by most programs,
+    and names that would clash, like the `solve` of `linalg` and `ode`, stay behind their modules.
+    The modules with generic function names are re-exported as a whole instead, e.g. `stats::mean`.
+
+    `Vector` and `Matrix` are here for the functions that take or return them,
+    most functions take and return slices and arrays.
+
+*/
+
+pub use crate::{disable_error_handler, GSLError, Result};
+#[cfg(feature = "gsl")]
+pub use crate::{Matrix, ValWithError, Vector};
+
+#[cfg(feature = "gsl")]
+pub use crate::ode;
+#[cfg(feature = "gsl")]
+pub use crate::randist;
+#[cfg(feature = "gsl")]
+pub use crate::sf;
+pub use crate::stats;
+
+#[cfg(feature = "gsl")]
+pub use crate::integrate::{qag, qagiu, qng, IntegrationWorkspace};
+#[cfg(feature = "gsl")]
+pub use crate::linear_fit::{fit_line, linear_fit};
+#[cfg(feature = "gsl")]
+pub use crate::min::minimize;
+#[cfg(feature = "gsl")]
+pub use crate::nonlinear_fit::nonlinear_fit;
+#[cfg(feature = "gsl")]
+pub use crate::randist::{
+    DiscreteDistribution, HistogramDistribution, MultivariateGaussian, PiecewiseLinearDistribution,
+};
+#[cfg(feature = "gsl")]
+pub use crate::rng::{Reproducibility, Rng, RngType};
+#[cfg(feature = "gsl")]
+pub use crate::roots::find_root;
+
+#[test]
+#[cfg(feature = "gsl")]
+fn test_prelude() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(RngType::Mt19937, 0);
+    let x = (0..100).map(|i| i as f64 / 10.0).collect::<Vec<_>>();
+    let y = x
+        .iter()
+        .map(|x| 2.0 * x + 1.0 + rng.gaussian(0.01))
+        .collect::<Vec<_>>();
+    let line = fit_line(&x, &y).unwrap();
+    approx::assert_abs_diff_eq!(line.c1, 2.0, epsilon = 1.0e-2);
+
+    let fit = nonlinear_fit([1.0, 1.0], &x, &y, |x, [a, b]| Ok(a * x + b)).unwrap();
+    approx::assert_abs_diff_eq!(fit.params[0], 2.0, epsilon = 1.0e-2);
+    approx::assert_abs_diff_eq!(stats::mean(&x), 4.95, epsilon = 1.0e-12);
+
+    let integral = qag(0.0, 1.0, |x| x * x).unwrap();
+    approx::assert_abs_diff_eq!(integral.val, 1.0 / 3.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(
+        find_root(|x| x * x - 2.0, 0.0, 2.0).unwrap().root,
+        2.0f64.sqrt(),
+        epsilon = 1.0e-9
+    );
+    assert_eq!(sf::gamma(0.0).unwrap_err(), GSLError::Domain);
+
+    let weights = Vector::new([0.0, 1.0]);
+    let discrete = DiscreteDistribution::new(&weights.to_boxed_slice()).unwrap();
+    assert_eq!(discrete.sample(&mut rng), 1);
+    assert_eq!(Matrix::new([1.0, 2.0], 1, 2).size(), (1, 2));
+}
@@ -0,0 +1,2063 @@
+/*
+    randist.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::geometry::{self, Quaternion};
+use crate::histogram::Histogram;
+use crate::integrate::{IntegrationWorkspace, Oscillation, QawoTable};
+use crate::linalg::Cholesky;
+use crate::min::minimize_ext;
+use crate::rng::Rng;
+use crate::roots::find_root;
+use crate::sf::bessel::i_nu_scaled;
+use crate::sf::{beta_inc, erfc, gamma, gamma_inc_p, hurwitz_zeta, ln_gamma};
+use crate::*;
+use std::f64::consts::{PI, SQRT_2};
+use std::fmt;
+use std::ptr;
+
+/// Zipf (zeta) distribution `P(k) = k^-s / ζ(s)` on `k >= 1`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Zipf {
+    s: f64,
+}
+
+impl Zipf {
+    /// The exponent must be larger than 1
+    pub fn new(s: f64) -> Result<Self> {
+        if !(s > 1.0 && s.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Ok(Zipf { s })
+    }
+
+    pub fn s(&self) -> f64 {
+        self.s
+    }
+
+    pub fn pmf(&self, k: u64) -> Result<f64> {
+        if k == 0 {
+            return Ok(0.0);
+        }
+        Ok((k as f64).powf(-self.s) / hurwitz_zeta(self.s, 1.0)?.val)
+    }
+
+    /// Samples using the rejection method of Devroye,
+    /// "Non-Uniform Random Variate Generation" (1986), p. 551.
+    pub fn sample(&self, rng: &mut Rng) -> u64 {
+        let b = 2.0f64.powf(self.s - 1.0);
+        loop {
+            let u = rng.uniform_pos();
+            let v = rng.uniform();
+            let x = u.powf(-1.0 / (self.s - 1.0)).floor();
+
+            // Overflows for exponents close to 1
+            if x >= u64::MAX as f64 {
+                continue;
+            }
+
+            let t = (1.0 + 1.0 / x).powf(self.s - 1.0);
+            if v * x * (t - 1.0) / (b - 1.0) <= t / b {
+                return x as u64;
+            }
+        }
+    }
+}
+
+/// Discrete power law `P(x) = x^-α / ζ(α, x_min)` on `x >= x_min`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DiscretePowerLaw {
+    alpha: f64,
+    xmin: u64,
+}
+
+impl DiscretePowerLaw {
+    /// The exponent must be larger than 1 and `x_min` at least 1
+    pub fn new(alpha: f64, xmin: u64) -> Result<Self> {
+        if !(alpha > 1.0 && alpha.is_finite()) || xmin == 0 {
+            return Err(GSLError::Domain);
+        }
+        Ok(DiscretePowerLaw { alpha, xmin })
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    pub fn xmin(&self) -> u64 {
+        self.xmin
+    }
+
+    pub fn pmf(&self, x: u64) -> Result<f64> {
+        if x < self.xmin {
+            return Ok(0.0);
+        }
+        Ok((x as f64).powf(-self.alpha) / hurwitz_zeta(self.alpha, self.xmin as f64)?.val)
+    }
+
+    /// `P(X >= x)`
+    pub fn ccdf(&self, x: u64) -> Result<f64> {
+        if x <= self.xmin {
+            return Ok(1.0);
+        }
+        Ok(hurwitz_zeta(self.alpha, x as f64)?.val
+            / hurwitz_zeta(self.alpha, self.xmin as f64)?.val)
+    }
+
+    /// Samples by inverting the complementary CDF with a doubling and bisection search,
+    /// as in appendix D of Clauset et al. (2009).
+    pub fn sample(&self, rng: &mut Rng) -> Result<u64> {
+        let r = rng.uniform_pos();
+
+        // Find an upper bound with P(X >= upper) < r
+        let mut lower = self.xmin;
+        let mut upper = self.xmin.saturating_mul(2);
+        while self.ccdf(upper)? >= r {
+            lower = upper;
+            upper = upper.saturating_mul(2);
+            if upper == u64::MAX {
+                return Ok(upper);
+            }
+        }
+
+        // Largest x with P(X >= x) >= r
+        while upper - lower > 1 {
+            let mid = lower + (upper - lower) / 2;
+            if self.ccdf(mid)? >= r {
+                lower = mid;
+            } else {
+                upper = mid;
+            }
+        }
+
+        Ok(lower)
+    }
+
+    /// Fits the exponent to the data points `x >= xmin` by maximum likelihood, see `PowerLawFit`.
+    pub fn fit(data: &[u64], xmin: u64) -> Result<PowerLawFit> {
+        if xmin == 0 {
+            return Err(GSLError::Domain);
+        }
+
+        let mut tail = data
+            .iter()
+            .copied()
+            .filter(|&x| x >= xmin)
+            .collect::<Vec<_>>();
+        if tail.len() < 2 {
+            return Err(GSLError::Invalid);
+        }
+        tail.sort_unstable();
+        let n = tail.len() as f64;
+        let sum_ln = tail.iter().map(|&x| (x as f64).ln()).sum::<f64>();
+
+        // All points at xmin have an infinite likelihood for α → ∞
+        if tail[tail.len() - 1] == xmin {
+            return Err(GSLError::Domain);
+        }
+
+        // Continuous approximation as an initial guess
+        let (a, b) = (1.0 + 1.0e-6, 20.0);
+        let guess = 1.0 + n / (sum_ln - n * (xmin as f64 - 0.5).ln());
+        let guess = guess.clamp(a + 1.0e-3, b - 1.0e-3);
+
+        // Negative log likelihood
+        let nll = |alpha: f64| match hurwitz_zeta(alpha, xmin as f64) {
+            Ok(zeta) => n * zeta.val.ln() + alpha * sum_ln,
+            Err(_) => f64::NAN,
+        };
+        let alpha = minimize_ext(100, a, b, guess, 1.0e-9, 0.0, nll, |_| {})?;
+
+        let model = DiscretePowerLaw::new(alpha, xmin)?;
+        Ok(PowerLawFit {
+            alpha: ValWithError {
+                val: alpha,
+                err: (alpha - 1.0) / n.sqrt(),
+            },
+            xmin,
+            n: tail.len(),
+            ks: model.ks_distance(&tail)?,
+        })
+    }
+
+    /// Fits both the exponent and `x_min`, choosing the `x_min` for which the
+    /// Kolmogorov-Smirnov distance between the data and the fit is smallest (Clauset et al. 2009).
+    pub fn fit_xmin(data: &[u64]) -> Result<PowerLawFit> {
+        let mut candidates = data.iter().copied().filter(|&x| x > 0).collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        // A fit needs at least two distinct values in the tail
+        candidates.pop();
+
+        candidates
+            .into_iter()
+            .filter_map(|xmin| Self::fit(data, xmin).ok())
+            .min_by(|a, b| a.ks.total_cmp(&b.ks))
+            .ok_or(GSLError::Invalid)
+    }
+
+    /// Largest difference between the empirical and model CDF, for sorted data `>= x_min`
+    fn ks_distance(&self, sorted: &[u64]) -> Result<f64> {
+        let n = sorted.len() as f64;
+        let mut distance = 0.0f64;
+        let mut i = 0;
+        while i < sorted.len() {
+            let x = sorted[i];
+            while i < sorted.len() && sorted[i] == x {
+                i += 1;
+            }
+            let empirical = i as f64 / n;
+            let model = 1.0 - self.ccdf(x + 1)?;
+            distance = distance.max((empirical - model).abs());
+        }
+        Ok(distance)
+    }
+}
+
+/// Maximum likelihood fit of a discrete power law
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PowerLawFit {
+    /// Exponent with its asymptotic standard error `(α - 1) / sqrt(n)`
+    pub alpha: ValWithError<f64>,
+    pub xmin: u64,
+    /// Number of data points in the tail
+    pub n: usize,
+    /// Kolmogorov-Smirnov distance between the tail data and the fit
+    pub ks: f64,
+}
+
+/// Lévy alpha-stable distribution with characteristic function
+/// `φ(t) = exp(iμt - |ct|^α (1 - iβ sign(t) tan(πα/2)))`,
+/// where `tan(πα/2)` is replaced by `-(2/π) log|t|` for `α = 1`.
+/// This matches the parametrization of `gsl_ran_levy_skew`, with an additional location `μ`.
+///
+/// Special cases are the normal distribution with variance `2c^2` for `α = 2`
+/// and the Cauchy distribution for `α = 1, β = 0`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stable {
+    alpha: f64,
+    beta: f64,
+    c: f64,
+    mu: f64,
+}
+
+impl Stable {
+    /// Requires `0 < α <= 2`, `-1 <= β <= 1` and `c > 0`
+    pub fn new(alpha: f64, beta: f64, c: f64, mu: f64) -> Result<Self> {
+        let valid = alpha > 0.0
+            && alpha <= 2.0
+            && (-1.0..=1.0).contains(&beta)
+            && c > 0.0
+            && c.is_finite()
+            && mu.is_finite();
+        if !valid {
+            return Err(GSLError::Domain);
+        }
+        Ok(Stable { alpha, beta, c, mu })
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    pub fn c(&self) -> f64 {
+        self.c
+    }
+
+    pub fn mu(&self) -> f64 {
+        self.mu
+    }
+
+    /// Inverts the characteristic function,
+    /// `p(x) = 1/π ∫_0^∞ exp(-(ct)^α) cos(t (x - μ) - g(t)) dt`
+    /// with `g(t) = β (ct)^α tan(πα/2)`.
+    pub fn pdf(&self, x: f64) -> Result<f64> {
+        let integral = self.fourier_integral(
+            x - self.mu,
+            |t, z| self.damping(t) * (t * z - self.phase(t)).cos(),
+            |t| self.damping(t) * self.phase(t).cos(),
+            |t| self.damping(t) * self.phase(t).sin(),
+        )?;
+        Ok(integral / PI)
+    }
+
+    /// Inverts the characteristic function using the Gil-Pelaez formula,
+    /// `F(x) = 1/2 + 1/π ∫_0^∞ exp(-(ct)^α) sin(t (x - μ) - g(t)) / t dt`.
+    pub fn cdf(&self, x: f64) -> Result<f64> {
+        let integral = self.fourier_integral(
+            x - self.mu,
+            |t, z| self.damping(t) * (t * z - self.phase(t)).sin() / t,
+            |t| -self.damping(t) * self.phase(t).sin() / t,
+            |t| self.damping(t) * self.phase(t).cos() / t,
+        )?;
+        Ok(0.5 + integral / PI)
+    }
+
+    pub fn log_likelihood(&self, data: &[f64]) -> Result<f64> {
+        data.iter().map(|&x| Ok(self.pdf(x)?.ln())).sum()
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        self.mu + rng.draw(|rng| unsafe { gsl_ran_levy_skew(rng, self.c, self.alpha, self.beta) })
+    }
+
+    fn damping(&self, t: f64) -> f64 {
+        (-(self.c * t).powf(self.alpha)).exp()
+    }
+
+    fn phase(&self, t: f64) -> f64 {
+        if t == 0.0 || self.beta == 0.0 {
+            0.0
+        } else if self.alpha == 1.0 {
+            -self.beta * self.c * t * 2.0 / PI * t.ln()
+        } else {
+            self.beta * (self.c * t).powf(self.alpha) * (PI * self.alpha / 2.0).tan()
+        }
+    }
+
+    /// Computes `∫_0^∞ full(t, z) dt`, where `full(t, z) = a(t) cos(zt) + b(t) sin(zt)`.
+    ///
+    /// For slow oscillations the integrand is integrated directly.
+    /// Otherwise the first period is integrated directly, which handles the singularities at 0,
+    /// and the remainder is a Fourier integral for `qawf`.
+    fn fourier_integral<F, A, B>(&self, z: f64, mut full: F, a: A, mut b: B) -> Result<f64>
+    where
+        F: FnMut(f64, f64) -> f64,
+        A: FnMut(f64) -> f64,
+        B: FnMut(f64) -> f64,
+    {
+        const EPSABS: f64 = 1.0e-12;
+        const EPSREL: f64 = 1.0e-10;
+
+        let mut workspace = IntegrationWorkspace::new(512)?;
+        if z.abs() * self.c <= 1.0 {
+            return Ok(workspace.qagiu(0.0, EPSABS, EPSREL, |t| full(t, z))?.val);
+        }
+
+        let omega = z.abs();
+        let t0 = 2.0 * PI / omega;
+        let head = workspace.qags(0.0, t0, EPSABS, EPSREL, |t| full(t, z))?;
+
+        let mut cycle_workspace = IntegrationWorkspace::new(512)?;
+        let mut table = QawoTable::new(omega, 1.0, Oscillation::Cosine, 25)?;
+        let cos = workspace.qawf(&mut cycle_workspace, t0, &mut table, EPSABS, a)?;
+        table.set(omega, 1.0, Oscillation::Sine)?;
+        let sin = workspace.qawf(&mut cycle_workspace, t0, &mut table, EPSABS, |t| {
+            z.signum() * b(t)
+        })?;
+
+        Ok(head.val + cos.val + sin.val)
+    }
+}
+
+/// Generalized extreme value distribution
+/// `F(x) = exp(-(1 + ξ (x - μ) / σ)^(-1/ξ))`,
+/// the limit distribution of block maxima.
+///
+/// `ξ = 0` is the Gumbel distribution, `ξ > 0` the Fréchet distribution
+/// with a lower bound `μ - σ / ξ` and `ξ < 0` the reversed Weibull distribution
+/// with an upper bound `μ - σ / ξ`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GeneralizedExtremeValue {
+    mu: f64,
+    sigma: f64,
+    xi: f64,
+}
+
+impl GeneralizedExtremeValue {
+    pub fn new(mu: f64, sigma: f64, xi: f64) -> Result<Self> {
+        if !(mu.is_finite() && sigma > 0.0 && sigma.is_finite() && xi.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Ok(GeneralizedExtremeValue { mu, sigma, xi })
+    }
+
+    pub fn mu(&self) -> f64 {
+        self.mu
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    pub fn xi(&self) -> f64 {
+        self.xi
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        match self.t(x) {
+            Some(t) => t.powf(self.xi + 1.0) * (-t).exp() / self.sigma,
+            None => 0.0,
+        }
+    }
+
+    pub fn cdf(&self, x: f64) -> f64 {
+        match self.t(x) {
+            Some(t) => (-t).exp(),
+            None if self.xi > 0.0 => 0.0,
+            None => 1.0,
+        }
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(GSLError::Domain);
+        }
+        let ln_y = (-p.ln()).ln();
+        if self.xi == 0.0 {
+            Ok(self.mu - self.sigma * ln_y)
+        } else {
+            Ok(self.mu + self.sigma * (-self.xi * ln_y).exp_m1() / self.xi)
+        }
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        let ln_y = (-rng.uniform_pos().ln()).ln();
+        if self.xi == 0.0 {
+            self.mu - self.sigma * ln_y
+        } else {
+            self.mu + self.sigma * (-self.xi * ln_y).exp_m1() / self.xi
+        }
+    }
+
+    /// Fit using probability weighted moments (Hosking, Wallis and Wood 1985),
+    /// which is robust for small samples.
+    /// The approximation of the shape is accurate for `-0.5 < ξ < 0.5`,
+    /// and the fit fails for `ξ >= 1` where the mean does not exist.
+    pub fn fit_pwm(data: &[f64]) -> Result<Self> {
+        let sorted = sorted_finite(data)?;
+        if sorted.len() < 3 {
+            return Err(GSLError::Invalid);
+        }
+
+        let n = sorted.len() as f64;
+        let (mut b0, mut b1, mut b2) = (0.0, 0.0, 0.0);
+        for (i, x) in sorted.iter().enumerate() {
+            let i = i as f64;
+            b0 += x;
+            b1 += x * i / (n - 1.0);
+            b2 += x * i * (i - 1.0) / ((n - 1.0) * (n - 2.0));
+        }
+        let (b0, b1, b2) = (b0 / n, b1 / n, b2 / n);
+        if 2.0 * b1 - b0 <= 0.0 {
+            return Err(GSLError::Domain);
+        }
+
+        const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+        // Hosking's shape k = -ξ
+        let c = (2.0 * b1 - b0) / (3.0 * b2 - b0) - 2.0f64.ln() / 3.0f64.ln();
+        let k = 7.8590 * c + 2.9554 * c * c;
+        if k.abs() < 1.0e-8 {
+            let sigma = (2.0 * b1 - b0) / 2.0f64.ln();
+            return Self::new(b0 - EULER_GAMMA * sigma, sigma, 0.0);
+        }
+
+        let gamma = gamma(1.0 + k)?.val;
+        let sigma = (2.0 * b1 - b0) * k / (gamma * -(-k * 2.0f64.ln()).exp_m1());
+        let mu = b0 + sigma * (gamma - 1.0) / k;
+        Self::new(mu, sigma, -k)
+    }
+
+    /// `(1 + ξ (x - μ) / σ)^(-1/ξ)`, or `None` outside of the support
+    fn t(&self, x: f64) -> Option<f64> {
+        let s = (x - self.mu) / self.sigma;
+        if self.xi == 0.0 {
+            return Some((-s).exp());
+        }
+        if 1.0 + self.xi * s <= 0.0 {
+            return None;
+        }
+        Some((-(self.xi * s).ln_1p() / self.xi).exp())
+    }
+}
+
+/// Generalized Pareto distribution
+/// `F(x) = 1 - (1 + ξ (x - μ) / σ)^(-1/ξ)` for `x >= μ`,
+/// the limit distribution of exceedances over a high threshold `μ`.
+///
+/// `ξ = 0` is the exponential distribution, and for `ξ < 0` the support has an upper bound `μ - σ / ξ`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GeneralizedPareto {
+    mu: f64,
+    sigma: f64,
+    xi: f64,
+}
+
+impl GeneralizedPareto {
+    pub fn new(mu: f64, sigma: f64, xi: f64) -> Result<Self> {
+        if !(mu.is_finite() && sigma > 0.0 && sigma.is_finite() && xi.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Ok(GeneralizedPareto { mu, sigma, xi })
+    }
+
+    pub fn mu(&self) -> f64 {
+        self.mu
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    pub fn xi(&self) -> f64 {
+        self.xi
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        let s = (x - self.mu) / self.sigma;
+        if s < 0.0 || 1.0 + self.xi * s <= 0.0 {
+            0.0
+        } else if self.xi == 0.0 {
+            (-s).exp() / self.sigma
+        } else {
+            (-(1.0 / self.xi + 1.0) * (self.xi * s).ln_1p()).exp() / self.sigma
+        }
+    }
+
+    pub fn cdf(&self, x: f64) -> f64 {
+        let s = (x - self.mu) / self.sigma;
+        if s < 0.0 {
+            0.0
+        } else if 1.0 + self.xi * s <= 0.0 {
+            1.0
+        } else if self.xi == 0.0 {
+            -(-s).exp_m1()
+        } else {
+            -(-(self.xi * s).ln_1p() / self.xi).exp_m1()
+        }
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(GSLError::Domain);
+        }
+        Ok(self.quantile_of_ln_survival((-p).ln_1p()))
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        self.quantile_of_ln_survival(rng.uniform_pos().ln())
+    }
+
+    /// Fit using probability weighted moments (Hosking and Wallis 1987) for a known threshold `μ`.
+    /// All data have to be at least `μ`, and the fit fails for `ξ >= 1` where the mean does not exist.
+    pub fn fit_pwm(data: &[f64], mu: f64) -> Result<Self> {
+        let sorted = sorted_finite(data)?;
+        if sorted.len() < 2 {
+            return Err(GSLError::Invalid);
+        }
+        if sorted[0] < mu {
+            return Err(GSLError::Domain);
+        }
+
+        let n = sorted.len() as f64;
+        let (mut a0, mut a1) = (0.0, 0.0);
+        for (i, x) in sorted.iter().enumerate() {
+            let y = x - mu;
+            a0 += y;
+            a1 += y * (n - 1.0 - i as f64) / (n - 1.0);
+        }
+        let (a0, a1) = (a0 / n, a1 / n);
+        if a1 <= 0.0 || a0 - 2.0 * a1 <= 0.0 {
+            return Err(GSLError::Domain);
+        }
+
+        // Hosking's shape k = -ξ
+        let k = a0 / (a0 - 2.0 * a1) - 2.0;
+        let sigma = 2.0 * a0 * a1 / (a0 - 2.0 * a1);
+        Self::new(mu, sigma, -k)
+    }
+
+    /// Quantile for `ln(1 - p)`
+    fn quantile_of_ln_survival(&self, ln_q: f64) -> f64 {
+        if self.xi == 0.0 {
+            self.mu - self.sigma * ln_q
+        } else {
+            self.mu + self.sigma * (-self.xi * ln_q).exp_m1() / self.xi
+        }
+    }
+}
+
+/// Copy of the data sorted in ascending order, which have to be finite
+fn sorted_finite(data: &[f64]) -> Result<Vec<f64>> {
+    if data.iter().any(|x| !x.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+    let mut sorted = data.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Ok(sorted)
+}
+
+/// Noncentral chi-squared distribution with `k` degrees of freedom and noncentrality `λ`,
+/// the distribution of `Σ (Z_i + μ_i)^2` with `λ = Σ μ_i^2`.
+/// Evaluated as a Poisson mixture of central chi-squared distributions.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NoncentralChiSquared {
+    k: f64,
+    lambda: f64,
+}
+
+impl NoncentralChiSquared {
+    pub fn new(k: f64, lambda: f64) -> Result<Self> {
+        if !(k > 0.0 && k.is_finite() && lambda >= 0.0 && lambda.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Ok(NoncentralChiSquared { k, lambda })
+    }
+
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    pub fn pdf(&self, x: f64) -> Result<f64> {
+        if x < 0.0 {
+            return Ok(0.0);
+        }
+        poisson_mixture(self.lambda / 2.0, |j, w| unsafe {
+            Ok(w * gsl_ran_chisq_pdf(x, self.k + 2.0 * j as f64))
+        })
+    }
+
+    pub fn cdf(&self, x: f64) -> Result<f64> {
+        if x <= 0.0 {
+            return Ok(0.0);
+        }
+        poisson_mixture(self.lambda / 2.0, |j, w| {
+            Ok(w * gamma_inc_p(self.k / 2.0 + j as f64, x / 2.0)?.val)
+        })
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        invert_cdf(p, 0.0, self.k + self.lambda, |x| self.cdf(x))
+    }
+}
+
+/// Noncentral Student's t distribution with `ν` degrees of freedom and noncentrality `δ`,
+/// the distribution of `(Z + δ) / sqrt(V / ν)` with `V` chi-squared distributed with `ν` degrees of freedom.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NoncentralT {
+    nu: f64,
+    delta: f64,
+}
+
+impl NoncentralT {
+    pub fn new(nu: f64, delta: f64) -> Result<Self> {
+        if !(nu > 0.0 && nu.is_finite() && delta.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Ok(NoncentralT { nu, delta })
+    }
+
+    pub fn nu(&self) -> f64 {
+        self.nu
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    /// Uses `f(t) = ν / t (F_{ν+2}(t sqrt(1 + 2 / ν)) - F_ν(t))`,
+    /// which loses relative accuracy for `|t|` close to, but not at, zero.
+    pub fn pdf(&self, t: f64) -> Result<f64> {
+        let nu = self.nu;
+        if t == 0.0 {
+            let ln_norm = ln_gamma((nu + 1.0) / 2.0)?.val - ln_gamma(nu / 2.0)?.val;
+            return Ok((ln_norm - self.delta * self.delta / 2.0).exp() / (nu * PI).sqrt());
+        }
+
+        let wider = NoncentralT::new(nu + 2.0, self.delta)?;
+        Ok(nu / t * (wider.cdf(t * (1.0 + 2.0 / nu).sqrt())? - self.cdf(t)?))
+    }
+
+    /// Uses the series of Guenther (1978) in incomplete beta functions
+    pub fn cdf(&self, t: f64) -> Result<f64> {
+        if t < 0.0 {
+            return Ok(1.0 - Self::cdf_nonnegative(self.nu, -self.delta, -t)?);
+        }
+        Self::cdf_nonnegative(self.nu, self.delta, t)
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        invert_cdf(p, f64::NEG_INFINITY, self.delta, |t| self.cdf(t))
+    }
+
+    fn cdf_nonnegative(nu: f64, delta: f64, t: f64) -> Result<f64> {
+        let x = t * t / (t * t + nu);
+        let normal = 0.5 * erfc(delta / SQRT_2)?.val;
+        let series = poisson_mixture(delta * delta / 2.0, |j, w| {
+            let j = j as f64;
+            let ratio = (ln_gamma(j + 1.0)?.val - ln_gamma(j + 1.5)?.val).exp();
+            let q = w * delta / SQRT_2 * ratio;
+            Ok(w * beta_inc(j + 0.5, nu / 2.0, x)?.val + q * beta_inc(j + 1.0, nu / 2.0, x)?.val)
+        })?;
+        Ok((normal + 0.5 * series).clamp(0.0, 1.0))
+    }
+}
+
+/// Noncentral F distribution with `d1` and `d2` degrees of freedom and noncentrality `λ`,
+/// the distribution of `(U / d1) / (V / d2)` with `U` noncentral chi-squared distributed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NoncentralF {
+    d1: f64,
+    d2: f64,
+    lambda: f64,
+}
+
+impl NoncentralF {
+    pub fn new(d1: f64, d2: f64, lambda: f64) -> Result<Self> {
+        let valid = d1 > 0.0
+            && d1.is_finite()
+            && d2 > 0.0
+            && d2.is_finite()
+            && lambda >= 0.0
+            && lambda.is_finite();
+        if !valid {
+            return Err(GSLError::Domain);
+        }
+        Ok(NoncentralF { d1, d2, lambda })
+    }
+
+    pub fn d1(&self) -> f64 {
+        self.d1
+    }
+
+    pub fn d2(&self) -> f64 {
+        self.d2
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    pub fn pdf(&self, x: f64) -> Result<f64> {
+        if x < 0.0 {
+            return Ok(0.0);
+        }
+        poisson_mixture(self.lambda / 2.0, |j, w| unsafe {
+            // Rescaled central F distribution with d1 + 2j degrees of freedom
+            let d1 = self.d1 + 2.0 * j as f64;
+            let scale = self.d1 / d1;
+            Ok(w * scale * gsl_ran_fdist_pdf(x * scale, d1, self.d2))
+        })
+    }
+
+    pub fn cdf(&self, x: f64) -> Result<f64> {
+        if x <= 0.0 {
+            return Ok(0.0);
+        }
+        let y = self.d1 * x / (self.d1 * x + self.d2);
+        poisson_mixture(self.lambda / 2.0, |j, w| {
+            Ok(w * beta_inc(self.d1 / 2.0 + j as f64, self.d2 / 2.0, y)?.val)
+        })
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        let guess = 1.0 + self.lambda / self.d1;
+        invert_cdf(p, 0.0, guess, |x| self.cdf(x))
+    }
+}
+
+/// Distribution given by a tabulated pdf, sampled by inverse transform.
+/// The CDF is integrated from the table with the trapezoidal rule
+/// and interpolated with a monotone Steffen spline, so a zero pdf gives a flat CDF.
+pub struct InverseCdfSampler {
+    x: Box<[f64]>,
+    cdf: Box<[f64]>,
+    interp: *mut gsl_interp,
+}
+
+impl InverseCdfSampler {
+    /// The pdf, which doesn't need to be normalized, is tabulated at at least 3 strictly increasing `x`.
+    /// The sampled range is `[x[0], x[n - 1]]`.
+    pub fn new(x: &[f64], pdf: &[f64]) -> Result<Self> {
+        if x.len() != pdf.len() {
+            return Err(GSLError::BadLength);
+        }
+        if x.len() < 3 {
+            return Err(GSLError::Invalid);
+        }
+        if x.iter().any(|x| !x.is_finite()) || x.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(GSLError::Domain);
+        }
+        if pdf.iter().any(|&p| !(p >= 0.0 && p.is_finite())) {
+            return Err(GSLError::Domain);
+        }
+
+        let mut cdf = vec![0.0; x.len()];
+        for i in 1..x.len() {
+            cdf[i] = cdf[i - 1] + 0.5 * (pdf[i - 1] + pdf[i]) * (x[i] - x[i - 1]);
+        }
+        let total = cdf[x.len() - 1];
+        if total == 0.0 {
+            return Err(GSLError::Domain);
+        }
+        for c in &mut cdf {
+            *c /= total;
+        }
+
+        Self::with_cdf(x.into(), cdf.into_boxed_slice())
+    }
+
+    fn with_cdf(x: Box<[f64]>, cdf: Box<[f64]>) -> Result<Self> {
+        unsafe {
+            let interp = gsl_interp_alloc(gsl_interp_steffen, x.len() as u64);
+            assert!(!interp.is_null());
+
+            // Frees the workspace if the initialization fails
+            let sampler = InverseCdfSampler { x, cdf, interp };
+            GSLError::from_raw(gsl_interp_init(
+                sampler.interp,
+                sampler.x.as_ptr(),
+                sampler.cdf.as_ptr(),
+                sampler.x.len() as u64,
+            ))?;
+            Ok(sampler)
+        }
+    }
+
+    pub fn min(&self) -> f64 {
+        self.x[0]
+    }
+
+    pub fn max(&self) -> f64 {
+        self.x[self.x.len() - 1]
+    }
+
+    pub fn cdf(&self, x: f64) -> f64 {
+        if x <= self.min() {
+            return 0.0;
+        }
+        if x >= self.max() {
+            return 1.0;
+        }
+        unsafe {
+            let mut y = 0.0;
+            gsl_interp_eval_e(
+                self.interp,
+                self.x.as_ptr(),
+                self.cdf.as_ptr(),
+                x,
+                ptr::null_mut(),
+                &mut y,
+            );
+            y.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`.
+    /// Where the CDF is flat the smallest `x` is returned.
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(GSLError::Domain);
+        }
+
+        // First node at or above p, the spline is monotone in between nodes
+        let i = self.cdf.partition_point(|&c| c < p);
+        if i == 0 {
+            return Ok(self.min());
+        }
+
+        let mut lo = self.x[i - 1];
+        let mut hi = self.x[i];
+        for _ in 0..100 {
+            let mid = 0.5 * (lo + hi);
+            if mid <= lo || mid >= hi {
+                break;
+            }
+            if self.cdf(mid) < p {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(hi)
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        self.quantile(rng.uniform())
+            .expect("uniform deviates lie in [0, 1)")
+    }
+}
+
+impl Clone for InverseCdfSampler {
+    fn clone(&self) -> Self {
+        Self::with_cdf(self.x.clone(), self.cdf.clone()).expect("the table was valid before")
+    }
+}
+
+impl fmt::Debug for InverseCdfSampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InverseCdfSampler")
+            .field("x", &self.x)
+            .field("cdf", &self.cdf)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for InverseCdfSampler {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_interp_free(self.interp);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for InverseCdfSampler {}
+unsafe impl Sync for InverseCdfSampler {}
+
+/// Piecewise constant distribution given by the bins of a histogram,
+/// uniform within every bin
+pub struct HistogramDistribution {
+    ranges: Box<[f64]>,
+    cdf: Box<[f64]>,
+    pdf: *mut gsl_histogram_pdf,
+}
+
+impl HistogramDistribution {
+    /// Fails with `GSLError::Domain` if a bin is negative or all bins are empty
+    pub fn new(histogram: &Histogram) -> Result<Self> {
+        unsafe {
+            let n = histogram.n();
+            let total = histogram.sum();
+            if !(total > 0.0 && total.is_finite()) {
+                return Err(GSLError::Domain);
+            }
+
+            let pdf = gsl_histogram_pdf_alloc(n as u64);
+            assert!(!pdf.is_null());
+
+            let mut cdf = vec![0.0; n + 1];
+            for i in 0..n {
+                cdf[i + 1] = cdf[i] + histogram.get(i) / total;
+            }
+
+            let distribution = HistogramDistribution {
+                ranges: histogram.ranges(),
+                cdf: cdf.into_boxed_slice(),
+                pdf,
+            };
+            GSLError::from_raw(gsl_histogram_pdf_init(pdf, histogram.as_gsl()))?;
+            Ok(distribution)
+        }
+    }
+
+    pub fn min(&self) -> f64 {
+        self.ranges[0]
+    }
+
+    pub fn max(&self) -> f64 {
+        self.ranges[self.ranges.len() - 1]
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        if !(x >= self.min() && x < self.max()) {
+            return 0.0;
+        }
+        let i = self.ranges.partition_point(|&r| r <= x) - 1;
+        (self.cdf[i + 1] - self.cdf[i]) / (self.ranges[i + 1] - self.ranges[i])
+    }
+
+    pub fn cdf(&self, x: f64) -> f64 {
+        if x <= self.min() {
+            return 0.0;
+        }
+        if x >= self.max() {
+            return 1.0;
+        }
+        let i = self.ranges.partition_point(|&r| r <= x) - 1;
+        let fraction = (x - self.ranges[i]) / (self.ranges[i + 1] - self.ranges[i]);
+        self.cdf[i] + fraction * (self.cdf[i + 1] - self.cdf[i])
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        unsafe {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(GSLError::Domain);
+            }
+            if p == 1.0 {
+                return Ok(self.max());
+            }
+            Ok(gsl_histogram_pdf_sample(self.pdf, p))
+        }
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        unsafe { gsl_histogram_pdf_sample(self.pdf, rng.uniform()) }
+    }
+}
+
+impl fmt::Debug for HistogramDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HistogramDistribution")
+            .field("ranges", &self.ranges)
+            .field("cdf", &self.cdf)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for HistogramDistribution {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_histogram_pdf_free(self.pdf);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for HistogramDistribution {}
+unsafe impl Sync for HistogramDistribution {}
+
+/// Distribution on `0..n` with probabilities proportional to the given weights,
+/// sampled in constant time with Walker's alias method
+pub struct DiscreteDistribution {
+    p: Box<[f64]>,
+    table: *mut gsl_ran_discrete_t,
+}
+
+impl DiscreteDistribution {
+    /// Fails with `GSLError::Domain` if a weight is negative or all weights vanish
+    pub fn new(weights: &[f64]) -> Result<Self> {
+        unsafe {
+            if weights.is_empty() {
+                return Err(GSLError::Invalid);
+            }
+            let total = weights.iter().sum::<f64>();
+            if weights.iter().any(|w| w.is_nan() || *w < 0.0) || !(total > 0.0 && total.is_finite())
+            {
+                return Err(GSLError::Domain);
+            }
+
+            let table = gsl_ran_discrete_preproc(weights.len() as u64, weights.as_ptr());
+            assert!(!table.is_null());
+
+            Ok(DiscreteDistribution {
+                p: weights.iter().map(|w| w / total).collect(),
+                table,
+            })
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.p.len()
+    }
+
+    /// Normalized probability of `k`
+    pub fn pdf(&self, k: usize) -> f64 {
+        self.p.get(k).copied().unwrap_or(0.0)
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> usize {
+        rng.draw(|rng| unsafe { gsl_ran_discrete(rng, self.table) as usize })
+    }
+}
+
+impl Clone for DiscreteDistribution {
+    fn clone(&self) -> Self {
+        Self::new(&self.p).expect("the weights were valid before")
+    }
+}
+
+impl fmt::Debug for DiscreteDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscreteDistribution")
+            .field("p", &self.p)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for DiscreteDistribution {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_ran_discrete_free(self.table);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for DiscreteDistribution {}
+unsafe impl Sync for DiscreteDistribution {}
+
+/// Distribution with a pdf interpolated linearly between tabulated points,
+/// the pdf doesn't need to be normalized
+#[derive(Clone, Debug, PartialEq)]
+pub struct PiecewiseLinearDistribution {
+    x: Box<[f64]>,
+    pdf: Box<[f64]>,
+    cdf: Box<[f64]>,
+}
+
+impl PiecewiseLinearDistribution {
+    /// The pdf is tabulated at at least 2 strictly increasing `x`, and vanishes outside `[x[0], x[n - 1]]`
+    pub fn new(x: &[f64], pdf: &[f64]) -> Result<Self> {
+        if x.len() != pdf.len() {
+            return Err(GSLError::BadLength);
+        }
+        if x.len() < 2 {
+            return Err(GSLError::Invalid);
+        }
+        if x.iter().any(|x| !x.is_finite()) || x.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(GSLError::Domain);
+        }
+        if pdf.iter().any(|&p| !(p >= 0.0 && p.is_finite())) {
+            return Err(GSLError::Domain);
+        }
+
+        let mut cdf = vec![0.0; x.len()];
+        for i in 1..x.len() {
+            cdf[i] = cdf[i - 1] + 0.5 * (pdf[i - 1] + pdf[i]) * (x[i] - x[i - 1]);
+        }
+        let total = cdf[x.len() - 1];
+        if total == 0.0 {
+            return Err(GSLError::Domain);
+        }
+
+        Ok(PiecewiseLinearDistribution {
+            x: x.into(),
+            pdf: pdf.iter().map(|p| p / total).collect(),
+            cdf: cdf.iter().map(|c| c / total).collect(),
+        })
+    }
+
+    pub fn min(&self) -> f64 {
+        self.x[0]
+    }
+
+    pub fn max(&self) -> f64 {
+        self.x[self.x.len() - 1]
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        if !(x >= self.min() && x <= self.max()) {
+            return 0.0;
+        }
+        let i = self.segment(x);
+        let t = (x - self.x[i]) / (self.x[i + 1] - self.x[i]);
+        self.pdf[i] + t * (self.pdf[i + 1] - self.pdf[i])
+    }
+
+    pub fn cdf(&self, x: f64) -> f64 {
+        if x <= self.min() {
+            return 0.0;
+        }
+        if x >= self.max() {
+            return 1.0;
+        }
+        let i = self.segment(x);
+        let dx = x - self.x[i];
+        self.cdf[i] + 0.5 * (self.pdf[i] + self.pdf(x)) * dx
+    }
+
+    /// Inverse of the CDF, for `p` in `[0, 1]`.
+    /// Where the CDF is flat the smallest `x` is returned.
+    pub fn quantile(&self, p: f64) -> Result<f64> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(GSLError::Domain);
+        }
+
+        let k = self.cdf.partition_point(|&c| c < p);
+        if k == 0 {
+            return Ok(self.min());
+        }
+
+        // Solves f_i t + s t^2 / 2 = q for the offset t into segment i,
+        // in a form that is stable for a vanishing slope s
+        let i = k - 1;
+        let h = self.x[k] - self.x[i];
+        let slope = (self.pdf[k] - self.pdf[i]) / h;
+        let q = p - self.cdf[i];
+        let discriminant = (self.pdf[i] * self.pdf[i] + 2.0 * slope * q).max(0.0);
+        let t = 2.0 * q / (self.pdf[i] + discriminant.sqrt());
+        Ok(self.x[i] + t.clamp(0.0, h))
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        self.quantile(rng.uniform())
+            .expect("uniform deviates lie in [0, 1)")
+    }
+
+    pub fn mean(&self) -> f64 {
+        // Exact integral of x f(x) over every segment
+        self.x
+            .windows(2)
+            .zip(self.pdf.windows(2))
+            .map(|(x, f)| {
+                (x[1] - x[0]) * (f[0] * (2.0 * x[0] + x[1]) + f[1] * (x[0] + 2.0 * x[1])) / 6.0
+            })
+            .sum()
+    }
+
+    /// Index of the segment containing `x`
+    fn segment(&self, x: f64) -> usize {
+        (self.x.partition_point(|&xi| xi <= x) - 1).min(self.x.len() - 2)
+    }
+}
+
+/// Density of the bivariate normal distribution with zero mean, standard deviations `sigma_x` and `sigma_y`
+/// and correlation coefficient `rho`, sampled by `Rng::bivariate_gaussian`
+pub fn bivariate_gaussian_pdf(x: f64, y: f64, sigma_x: f64, sigma_y: f64, rho: f64) -> f64 {
+    unsafe { gsl_ran_bivariate_gaussian_pdf(x, y, sigma_x, sigma_y, rho) }
+}
+
+/// Multivariate normal distribution with mean `μ` and covariance matrix `Σ` in `k` dimensions
+#[derive(Clone, Debug)]
+pub struct MultivariateGaussian {
+    mean: Vector,
+    covariance: Matrix,
+    cholesky: Cholesky,
+}
+
+impl MultivariateGaussian {
+    /// The `k x k` covariance matrix has to be positive definite
+    pub fn new(mean: &Vector, covariance: &Matrix) -> Result<Self> {
+        let k = mean.len();
+        if covariance.size() != (k, k) {
+            return Err(GSLError::BadLength);
+        }
+        if mean.iter().chain(covariance.iter()).any(|x| !x.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+
+        Ok(MultivariateGaussian {
+            mean: mean.clone(),
+            covariance: covariance.clone(),
+            cholesky: Cholesky::new(k, covariance)?,
+        })
+    }
+
+    /// Maximum likelihood estimate from `n` samples in `k` dimensions, stored one sample per row.
+    /// The covariance is normalized by `n`.
+    pub fn fit(samples: &Matrix) -> Result<Self> {
+        unsafe {
+            let (n, k) = samples.size();
+            if n < 2 {
+                return Err(GSLError::Invalid);
+            }
+
+            let mut mean = Vector::zeroes(k);
+            let mut covariance = Matrix::zeroes(k, k);
+            GSLError::from_raw(gsl_ran_multivariate_gaussian_mean(
+                samples.as_gsl(),
+                mean.as_gsl_mut(),
+            ))?;
+            GSLError::from_raw(gsl_ran_multivariate_gaussian_vcov(
+                samples.as_gsl(),
+                covariance.as_gsl_mut(),
+            ))?;
+
+            Self::new(&mean, &covariance)
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.mean.len()
+    }
+
+    pub fn mean(&self) -> &Vector {
+        &self.mean
+    }
+
+    pub fn covariance(&self) -> &Matrix {
+        &self.covariance
+    }
+
+    pub fn pdf(&self, x: &Vector) -> Result<f64> {
+        self.density(x, false)
+    }
+
+    pub fn log_pdf(&self, x: &Vector) -> Result<f64> {
+        self.density(x, true)
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> Vector {
+        let mut x = Vector::zeroes(self.dim());
+        rng.draw(|rng| unsafe {
+            gsl_ran_multivariate_gaussian(
+                rng,
+                self.mean.as_gsl(),
+                self.cholesky.as_gsl(),
+                x.as_gsl_mut(),
+            )
+        });
+        x
+    }
+
+    fn density(&self, x: &Vector, log: bool) -> Result<f64> {
+        unsafe {
+            if x.len() != self.dim() {
+                return Err(GSLError::BadLength);
+            }
+
+            let mut work = Vector::zeroes(self.dim());
+            let mut result = 0.0;
+            let density = if log {
+                gsl_ran_multivariate_gaussian_log_pdf
+            } else {
+                gsl_ran_multivariate_gaussian_pdf
+            };
+            GSLError::from_raw(density(
+                x.as_gsl(),
+                self.mean.as_gsl(),
+                self.cholesky.as_gsl(),
+                &mut result,
+                work.as_gsl_mut(),
+            ))?;
+            Ok(result)
+        }
+    }
+}
+
+/// Fisher (von Mises-Fisher) distribution on the unit sphere with mean direction `μ` and concentration `κ`,
+/// with density `C(κ) exp(κ μ · x)` where `C(κ) = κ^(1/2) / ((2π)^(3/2) I_(1/2)(κ))`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fisher {
+    mean: [f64; 3],
+    kappa: f64,
+    rotation: Quaternion,
+}
+
+impl Fisher {
+    /// The mean direction doesn't need to be normalized, and `κ = 0` is the uniform distribution
+    pub fn new(mean: [f64; 3], kappa: f64) -> Result<Self> {
+        if !(kappa >= 0.0 && kappa.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        let mean = geometry::normalize(mean)?;
+        Ok(Fisher {
+            mean,
+            kappa,
+            rotation: Quaternion::between([0.0, 0.0, 1.0], mean)?,
+        })
+    }
+
+    /// Maximum likelihood estimate from directions, which don't need to be normalized.
+    /// `κ` solves `A(κ) = I_(3/2)(κ) / I_(1/2)(κ) = R̄` with `R̄` the mean resultant length.
+    pub fn fit(data: &[[f64; 3]]) -> Result<Self> {
+        let mean = geometry::mean_direction(data)?;
+        let r = mean.resultant_length;
+        if r >= 1.0 {
+            return Err(GSLError::Domain);
+        }
+
+        const KAPPA_MIN: f64 = 1.0e-8;
+        if mean_resultant_length(KAPPA_MIN)? >= r {
+            return Self::new(mean.direction, 0.0);
+        }
+        // The approximation of Banerjee et al. (2005) is close, which makes this a safe upper bound
+        let upper = 2.0 * r * (3.0 - r * r) / (1.0 - r * r) + 1.0;
+        let kappa = find_root(
+            |kappa| mean_resultant_length(kappa).map_or(f64::NAN, |a| a - r),
+            KAPPA_MIN,
+            upper,
+        )?
+        .root;
+        Self::new(mean.direction, kappa)
+    }
+
+    pub fn mean(&self) -> [f64; 3] {
+        self.mean
+    }
+
+    pub fn kappa(&self) -> f64 {
+        self.kappa
+    }
+
+    /// Density with respect to the area on the unit sphere, at the direction `x`
+    pub fn pdf(&self, x: [f64; 3]) -> Result<f64> {
+        Ok(self.log_pdf(x)?.exp())
+    }
+
+    pub fn log_pdf(&self, x: [f64; 3]) -> Result<f64> {
+        let x = geometry::normalize(x)?;
+        let kappa = self.kappa;
+        if kappa == 0.0 {
+            return Ok(-(4.0 * PI).ln());
+        }
+        // With the scaled Bessel function, I_(1/2)(κ) = e^κ I_(1/2)^s(κ)
+        let log_c = 0.5 * kappa.ln() - 1.5 * (2.0 * PI).ln() - i_nu_scaled(0.5, kappa)?.val.ln();
+        let cos = self.mean[0] * x[0] + self.mean[1] * x[1] + self.mean[2] * x[2];
+        Ok(log_c + kappa * (cos - 1.0))
+    }
+
+    /// Samples the cosine to the mean direction by inversion, as in Wood (1994),
+    /// and the direction around it uniformly
+    pub fn sample(&self, rng: &mut Rng) -> [f64; 3] {
+        let u = rng.uniform_pos();
+        let w = if self.kappa == 0.0 {
+            2.0 * u - 1.0
+        } else {
+            // 1 + ln(u + (1 - u) e^(-2κ)) / κ, without cancellation for small κ
+            1.0 + ((1.0 - u) * (-2.0 * self.kappa).exp_m1()).ln_1p() / self.kappa
+        };
+        let w = w.clamp(-1.0, 1.0);
+        let [a, b] = rng.dir_2d();
+        let s = (1.0 - w * w).sqrt();
+        self.rotation.rotate([s * a, s * b, w])
+    }
+}
+
+/// Mean resultant length `A(κ) = I_(3/2)(κ) / I_(1/2)(κ) = coth(κ) - 1/κ` of the Fisher distribution
+fn mean_resultant_length(kappa: f64) -> Result<f64> {
+    Ok(i_nu_scaled(1.5, kappa)?.val / i_nu_scaled(0.5, kappa)?.val)
+}
+
+/// Sums `Σ_j term(j, w_j)` over the Poisson weights `w_j = exp(-mean) mean^j / j!`,
+/// outward from the mode until the weights become negligible
+fn poisson_mixture<F: FnMut(u64, f64) -> Result<f64>>(mean: f64, mut term: F) -> Result<f64> {
+    const CUTOFF: f64 = 1.0e-20;
+
+    if mean == 0.0 {
+        return term(0, 1.0);
+    }
+
+    let mode = mean.floor();
+    let w_mode = (-mean + mode * mean.ln() - ln_gamma(mode + 1.0)?.val).exp();
+    let mode = mode as u64;
+
+    let mut sum = 0.0;
+    let mut w = w_mode;
+    let mut j = mode;
+    while w >= CUTOFF || j == mode {
+        sum += term(j, w)?;
+        j += 1;
+        w *= mean / j as f64;
+    }
+
+    let mut w = w_mode;
+    let mut j = mode;
+    while j > 0 {
+        w *= j as f64 / mean;
+        j -= 1;
+        if w < CUTOFF {
+            break;
+        }
+        sum += term(j, w)?;
+    }
+
+    Ok(sum)
+}
+
+/// Inverts a continuous nondecreasing `cdf` with support bounded below by `lower` using bisection,
+/// expanding a bracket around `guess` first
+fn invert_cdf<F: FnMut(f64) -> Result<f64>>(
+    p: f64,
+    lower: f64,
+    guess: f64,
+    mut cdf: F,
+) -> Result<f64> {
+    if !(0.0..=1.0).contains(&p) {
+        return Err(GSLError::Domain);
+    }
+    if p == 0.0 {
+        return Ok(lower);
+    }
+    if p == 1.0 {
+        return Ok(f64::INFINITY);
+    }
+
+    let mut step = guess.abs().max(1.0);
+    let mut hi = guess + step;
+    while cdf(hi)? < p {
+        step *= 2.0;
+        hi = guess + step;
+        if !hi.is_finite() {
+            return Err(GSLError::Failed);
+        }
+    }
+
+    let mut step = guess.abs().max(1.0);
+    let mut lo = (guess - step).max(lower);
+    while lo > lower && cdf(lo)? > p {
+        step *= 2.0;
+        lo = (guess - step).max(lower);
+        if !lo.is_finite() {
+            return Err(GSLError::Failed);
+        }
+    }
+
+    for _ in 0..200 {
+        let mid = 0.5 * (lo + hi);
+        if hi - lo <= 1.0e-12 * mid.abs().max(1.0) {
+            break;
+        }
+        if cdf(mid)? < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    Ok(0.5 * (lo + hi))
+}
+
+#[test]
+fn test_zipf() {
+    disable_error_handler();
+
+    let zipf = Zipf::new(4.0).unwrap();
+    let total = (1..1000).map(|k| zipf.pmf(k).unwrap()).sum::<f64>();
+    approx::assert_abs_diff_eq!(total, 1.0, epsilon = 1.0e-6);
+
+    // Mean is ζ(s - 1) / ζ(s)
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 100_000;
+    let mean = (0..n).map(|_| zipf.sample(&mut rng) as f64).sum::<f64>() / n as f64;
+    let exact = hurwitz_zeta(3.0, 1.0).unwrap().val / hurwitz_zeta(4.0, 1.0).unwrap().val;
+    approx::assert_relative_eq!(mean, exact, max_relative = 0.02);
+
+    // Frequency of 1 is 1 / ζ(s)
+    let ones = (0..n).filter(|_| zipf.sample(&mut rng) == 1).count() as f64 / n as f64;
+    approx::assert_abs_diff_eq!(ones, zipf.pmf(1).unwrap(), epsilon = 0.01);
+}
+
+#[test]
+fn test_discrete_power_law() {
+    disable_error_handler();
+
+    let law = DiscretePowerLaw::new(2.5, 5).unwrap();
+    assert_eq!(law.pmf(4).unwrap(), 0.0);
+    assert_eq!(law.ccdf(5).unwrap(), 1.0);
+    approx::assert_abs_diff_eq!(
+        law.ccdf(6).unwrap(),
+        1.0 - law.pmf(5).unwrap(),
+        epsilon = 1.0e-12
+    );
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 2);
+    let data = (0..20_000)
+        .map(|_| law.sample(&mut rng).unwrap())
+        .collect::<Vec<_>>();
+    assert!(data.iter().all(|&x| x >= 5));
+
+    let fit = DiscretePowerLaw::fit(&data, 5).unwrap();
+    assert_eq!(fit.n, data.len());
+    assert!((fit.alpha.val - 2.5).abs() < 3.0 * fit.alpha.err);
+    assert!(fit.ks < 0.02);
+
+    // Add a non power law body below the tail
+    let mut mixed = data.clone();
+    mixed.extend((0..5000).map(|i| 1 + i % 4));
+    let fit = DiscretePowerLaw::fit_xmin(&mixed).unwrap();
+    assert!(fit.xmin >= 5 && fit.xmin <= 10);
+    approx::assert_abs_diff_eq!(fit.alpha.val, 2.5, epsilon = 0.2);
+}
+
+#[test]
+fn test_stable() {
+    disable_error_handler();
+
+    // Normal distribution with variance 2c^2
+    let c = 1.5;
+    let normal = Stable::new(2.0, 0.0, c, 1.0).unwrap();
+    for x in [-3.0, 0.0, 1.0, 2.5, 8.0] {
+        let z = x - 1.0;
+        let exact = (-z * z / (4.0 * c * c)).exp() / (2.0 * c * PI.sqrt());
+        approx::assert_abs_diff_eq!(normal.pdf(x).unwrap(), exact, epsilon = 1.0e-9);
+    }
+    approx::assert_abs_diff_eq!(normal.cdf(1.0).unwrap(), 0.5, epsilon = 1.0e-9);
+
+    // Cauchy distribution
+    let cauchy = Stable::new(1.0, 0.0, 2.0, 0.0).unwrap();
+    for x in [-10.0, -1.0, 0.0, 0.5, 30.0] {
+        let pdf = 2.0 / (PI * (4.0 + x * x));
+        let cdf = 0.5 + (x / 2.0).atan() / PI;
+        approx::assert_abs_diff_eq!(cauchy.pdf(x).unwrap(), pdf, epsilon = 1.0e-9);
+        approx::assert_abs_diff_eq!(cauchy.cdf(x).unwrap(), cdf, epsilon = 1.0e-8);
+    }
+
+    // Skewed pdf is the derivative of the cdf
+    let skewed = Stable::new(0.7, 0.6, 1.0, 0.0).unwrap();
+    for x in [-2.0, 0.3, 4.0] {
+        let h = 1.0e-4;
+        let derivative = (skewed.cdf(x + h).unwrap() - skewed.cdf(x - h).unwrap()) / (2.0 * h);
+        approx::assert_abs_diff_eq!(skewed.pdf(x).unwrap(), derivative, epsilon = 1.0e-6);
+    }
+
+    // Samples follow the cdf
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 3);
+    let n = 20_000;
+    let below = (0..n).filter(|_| skewed.sample(&mut rng) < 0.3).count() as f64 / n as f64;
+    approx::assert_abs_diff_eq!(below, skewed.cdf(0.3).unwrap(), epsilon = 0.015);
+}
+
+#[test]
+fn test_generalized_extreme_value() {
+    disable_error_handler();
+
+    // Gumbel
+    let gumbel = GeneralizedExtremeValue::new(1.0, 2.0, 0.0).unwrap();
+    approx::assert_abs_diff_eq!(gumbel.cdf(1.0), (-1.0f64).exp(), epsilon = 1.0e-15);
+    approx::assert_abs_diff_eq!(gumbel.pdf(1.0), (-1.0f64).exp() / 2.0, epsilon = 1.0e-15);
+
+    // Fréchet with lower bound μ - σ / ξ = -1
+    let frechet = GeneralizedExtremeValue::new(1.0, 1.0, 0.5).unwrap();
+    assert_eq!(frechet.cdf(-2.0), 0.0);
+    assert_eq!(frechet.pdf(-2.0), 0.0);
+    assert_eq!(frechet.quantile(0.0).unwrap(), -1.0);
+    approx::assert_abs_diff_eq!(frechet.cdf(3.0), (-1.0f64 / 4.0).exp(), epsilon = 1.0e-15);
+
+    // Weibull with upper bound 3
+    let weibull = GeneralizedExtremeValue::new(1.0, 1.0, -0.5).unwrap();
+    assert_eq!(weibull.cdf(4.0), 1.0);
+    assert_eq!(weibull.quantile(1.0).unwrap(), 3.0);
+
+    // Small shapes approach the Gumbel distribution smoothly
+    let almost_gumbel = GeneralizedExtremeValue::new(1.0, 2.0, 1.0e-12).unwrap();
+    for x in [-3.0, 0.0, 4.0] {
+        approx::assert_abs_diff_eq!(almost_gumbel.cdf(x), gumbel.cdf(x), epsilon = 1.0e-10);
+    }
+
+    // Quantile is the inverse of the cdf, and the pdf its derivative
+    for gev in [gumbel, frechet, weibull] {
+        for p in [0.01, 0.3, 0.5, 0.9, 0.999] {
+            let x = gev.quantile(p).unwrap();
+            approx::assert_abs_diff_eq!(gev.cdf(x), p, epsilon = 1.0e-12);
+
+            let h = 1.0e-6;
+            let derivative = (gev.cdf(x + h) - gev.cdf(x - h)) / (2.0 * h);
+            approx::assert_abs_diff_eq!(gev.pdf(x), derivative, epsilon = 1.0e-6);
+        }
+    }
+
+    // Fit recovers the parameters of samples
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let gev = GeneralizedExtremeValue::new(10.0, 2.0, 0.2).unwrap();
+    let data = (0..20000).map(|_| gev.sample(&mut rng)).collect::<Vec<_>>();
+    let fit = GeneralizedExtremeValue::fit_pwm(&data).unwrap();
+    approx::assert_abs_diff_eq!(fit.mu(), 10.0, epsilon = 0.05);
+    approx::assert_abs_diff_eq!(fit.sigma(), 2.0, epsilon = 0.05);
+    approx::assert_abs_diff_eq!(fit.xi(), 0.2, epsilon = 0.03);
+}
+
+#[test]
+fn test_generalized_pareto() {
+    disable_error_handler();
+
+    // Exponential
+    let exponential = GeneralizedPareto::new(1.0, 2.0, 0.0).unwrap();
+    assert_eq!(exponential.cdf(0.5), 0.0);
+    approx::assert_abs_diff_eq!(
+        exponential.cdf(3.0),
+        1.0 - (-1.0f64).exp(),
+        epsilon = 1.0e-15
+    );
+    approx::assert_abs_diff_eq!(
+        exponential.pdf(3.0),
+        (-1.0f64).exp() / 2.0,
+        epsilon = 1.0e-15
+    );
+
+    // Pareto tail
+    let pareto = GeneralizedPareto::new(0.0, 1.0, 0.5).unwrap();
+    approx::assert_abs_diff_eq!(pareto.cdf(2.0), 1.0 - 0.25, epsilon = 1.0e-15);
+
+    // Uniform on [0, 1] for ξ = -1
+    let uniform = GeneralizedPareto::new(0.0, 1.0, -1.0).unwrap();
+    approx::assert_abs_diff_eq!(uniform.cdf(0.3), 0.3, epsilon = 1.0e-15);
+    approx::assert_abs_diff_eq!(uniform.pdf(0.3), 1.0, epsilon = 1.0e-15);
+    assert_eq!(uniform.cdf(1.5), 1.0);
+    assert_eq!(uniform.pdf(1.5), 0.0);
+
+    for gpd in [exponential, pareto, uniform] {
+        for p in [0.0, 0.01, 0.5, 0.9, 0.999] {
+            let x = gpd.quantile(p).unwrap();
+            approx::assert_abs_diff_eq!(gpd.cdf(x), p, epsilon = 1.0e-12);
+        }
+    }
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 2);
+    let gpd = GeneralizedPareto::new(5.0, 1.5, 0.25).unwrap();
+    let data = (0..20000).map(|_| gpd.sample(&mut rng)).collect::<Vec<_>>();
+    let fit = GeneralizedPareto::fit_pwm(&data, 5.0).unwrap();
+    approx::assert_abs_diff_eq!(fit.sigma(), 1.5, epsilon = 0.05);
+    approx::assert_abs_diff_eq!(fit.xi(), 0.25, epsilon = 0.03);
+}
+
+#[test]
+fn test_noncentral() {
+    disable_error_handler();
+
+    let normal_cdf = |x: f64| 0.5 * erfc(-x / SQRT_2).unwrap().val;
+
+    // One degree of freedom is the square of a shifted normal
+    let lambda = 2.5f64;
+    let chisq = NoncentralChiSquared::new(1.0, lambda).unwrap();
+    for x in [0.1f64, 1.0, 3.0, 10.0] {
+        let r = x.sqrt();
+        let exact = normal_cdf(r - lambda.sqrt()) - normal_cdf(-r - lambda.sqrt());
+        approx::assert_abs_diff_eq!(chisq.cdf(x).unwrap(), exact, epsilon = 1.0e-12);
+    }
+
+    // Central t distribution with one degree of freedom is the Cauchy distribution
+    let cauchy = NoncentralT::new(1.0, 0.0).unwrap();
+    approx::assert_abs_diff_eq!(cauchy.cdf(1.0).unwrap(), 0.75, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(cauchy.cdf(-1.0).unwrap(), 0.25, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(cauchy.pdf(1.0).unwrap(), 0.5 / PI, epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(cauchy.pdf(0.0).unwrap(), 1.0 / PI, epsilon = 1.0e-12);
+
+    // Squared noncentral t is noncentral F with d1 = 1
+    let t = NoncentralT::new(7.0, 1.5).unwrap();
+    let f = NoncentralF::new(1.0, 7.0, 1.5 * 1.5).unwrap();
+    for x in [0.2f64, 1.0, 4.0, 12.0] {
+        let r = x.sqrt();
+        let exact = t.cdf(r).unwrap() - t.cdf(-r).unwrap();
+        approx::assert_abs_diff_eq!(f.cdf(x).unwrap(), exact, epsilon = 1.0e-10);
+    }
+
+    // Densities are derivatives of the distribution functions
+    let chisq = NoncentralChiSquared::new(4.0, 30.0).unwrap();
+    let f = NoncentralF::new(3.0, 12.0, 5.0).unwrap();
+    let h = 1.0e-5;
+    for x in [0.5, 2.0, 35.0] {
+        let derivative = (chisq.cdf(x + h).unwrap() - chisq.cdf(x - h).unwrap()) / (2.0 * h);
+        approx::assert_abs_diff_eq!(chisq.pdf(x).unwrap(), derivative, epsilon = 1.0e-7);
+
+        let derivative = (f.cdf(x + h).unwrap() - f.cdf(x - h).unwrap()) / (2.0 * h);
+        approx::assert_abs_diff_eq!(f.pdf(x).unwrap(), derivative, epsilon = 1.0e-7);
+    }
+    for x in [-1.0, 0.5, 3.0] {
+        let derivative = (t.cdf(x + h).unwrap() - t.cdf(x - h).unwrap()) / (2.0 * h);
+        approx::assert_abs_diff_eq!(t.pdf(x).unwrap(), derivative, epsilon = 1.0e-7);
+    }
+
+    // Quantiles invert the distribution functions
+    for p in [0.01, 0.5, 0.95] {
+        approx::assert_abs_diff_eq!(
+            chisq.cdf(chisq.quantile(p).unwrap()).unwrap(),
+            p,
+            epsilon = 1.0e-10
+        );
+        approx::assert_abs_diff_eq!(t.cdf(t.quantile(p).unwrap()).unwrap(), p, epsilon = 1.0e-10);
+        approx::assert_abs_diff_eq!(f.cdf(f.quantile(p).unwrap()).unwrap(), p, epsilon = 1.0e-10);
+    }
+}
+
+#[test]
+fn test_inverse_cdf_sampler() {
+    disable_error_handler();
+
+    // Exponential distribution, truncated at 10
+    let x = (0..=200).map(|i| i as f64 * 0.05).collect::<Vec<_>>();
+    let pdf = x.iter().map(|x| (-x).exp()).collect::<Vec<_>>();
+    let sampler = InverseCdfSampler::new(&x, &pdf).unwrap();
+
+    assert_eq!(sampler.cdf(-1.0), 0.0);
+    assert_eq!(sampler.cdf(10.0), 1.0);
+    approx::assert_abs_diff_eq!(sampler.cdf(1.0), 1.0 - (-1.0f64).exp(), epsilon = 1.0e-3);
+    approx::assert_abs_diff_eq!(
+        sampler.quantile(0.5).unwrap(),
+        2.0f64.ln(),
+        epsilon = 1.0e-3
+    );
+    assert_eq!(sampler.quantile(0.0).unwrap(), 0.0);
+    approx::assert_abs_diff_eq!(sampler.quantile(1.0).unwrap(), 10.0);
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 100_000;
+    let samples = (0..n).map(|_| sampler.sample(&mut rng)).collect::<Vec<_>>();
+    assert!(samples.iter().all(|x| (0.0..=10.0).contains(x)));
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 1.0, epsilon = 0.02);
+
+    // Nothing is sampled where the pdf vanishes
+    let gap =
+        InverseCdfSampler::new(&[0.0, 1.0, 2.0, 3.0, 4.0], &[1.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+    let clone = gap.clone();
+    approx::assert_abs_diff_eq!(clone.quantile(0.5).unwrap(), 1.0, epsilon = 1.0e-9);
+    for _ in 0..1000 {
+        let x = gap.sample(&mut rng);
+        assert!(x <= 1.0 || x >= 3.0);
+    }
+}
+
+#[test]
+fn test_histogram_distribution() {
+    disable_error_handler();
+
+    let mut h = Histogram::with_ranges(&[0.0, 1.0, 3.0, 4.0]).unwrap();
+    h.accumulate(0.5, 1.0).unwrap();
+    h.accumulate(2.0, 2.0).unwrap();
+    h.accumulate(3.5, 1.0).unwrap();
+    let d = HistogramDistribution::new(&h).unwrap();
+
+    assert_eq!(d.pdf(0.5), 0.25);
+    assert_eq!(d.pdf(2.0), 0.25);
+    assert_eq!(d.pdf(4.0), 0.0);
+    assert_eq!(d.cdf(2.0), 0.5);
+    assert_eq!(d.cdf(5.0), 1.0);
+    approx::assert_abs_diff_eq!(d.quantile(0.5).unwrap(), 2.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(d.quantile(0.125).unwrap(), 0.5, epsilon = 1.0e-12);
+    assert_eq!(d.quantile(1.0).unwrap(), 4.0);
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 100_000;
+    let mean = (0..n).map(|_| d.sample(&mut rng)).sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 0.25 * 0.5 + 0.5 * 2.0 + 0.25 * 3.5, epsilon = 0.02);
+}
+
+#[test]
+fn test_discrete_distribution() {
+    disable_error_handler();
+
+    let d = DiscreteDistribution::new(&[1.0, 0.0, 3.0]).unwrap();
+    assert_eq!(d.n(), 3);
+    assert_eq!(d.pdf(0), 0.25);
+    assert_eq!(d.pdf(1), 0.0);
+    assert_eq!(d.pdf(3), 0.0);
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 100_000;
+    let mut counts = [0; 3];
+    for _ in 0..n {
+        counts[d.sample(&mut rng)] += 1;
+    }
+    assert_eq!(counts[1], 0);
+    approx::assert_abs_diff_eq!(counts[2] as f64 / n as f64, 0.75, epsilon = 0.01);
+}
+
+#[test]
+fn test_piecewise_linear_distribution() {
+    disable_error_handler();
+
+    // Triangular distribution on [0, 2] with mode 1
+    let d = PiecewiseLinearDistribution::new(&[0.0, 1.0, 2.0], &[0.0, 2.0, 0.0]).unwrap();
+    assert_eq!(d.pdf(1.0), 1.0);
+    assert_eq!(d.pdf(0.5), 0.5);
+    assert_eq!(d.pdf(3.0), 0.0);
+    approx::assert_abs_diff_eq!(d.cdf(0.5), 0.125);
+    approx::assert_abs_diff_eq!(d.cdf(1.5), 0.875);
+    approx::assert_abs_diff_eq!(d.mean(), 1.0);
+    for p in [0.0, 0.01, 0.125, 0.5, 0.875, 0.99, 1.0] {
+        approx::assert_abs_diff_eq!(d.cdf(d.quantile(p).unwrap()), p, epsilon = 1.0e-12);
+    }
+
+    // Uniform segments have a vanishing slope
+    let u = PiecewiseLinearDistribution::new(&[1.0, 3.0], &[1.0, 1.0]).unwrap();
+    approx::assert_abs_diff_eq!(u.quantile(0.25).unwrap(), 1.5);
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 100_000;
+    let samples = (0..n).map(|_| d.sample(&mut rng)).collect::<Vec<_>>();
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 1.0, epsilon = 0.01);
+    let below = samples.iter().filter(|&&x| x < 0.5).count() as f64 / n as f64;
+    approx::assert_abs_diff_eq!(below, 0.125, epsilon = 0.005);
+}
+
+#[test]
+fn test_multivariate_gaussian() {
+    disable_error_handler();
+
+    let mean = Vector::new([1.0, -1.0]);
+    let covariance = Matrix::from([[2.0, 0.6], [0.6, 1.0]]);
+    let d = MultivariateGaussian::new(&mean, &covariance).unwrap();
+
+    let det = 2.0 - 0.6 * 0.6;
+    approx::assert_abs_diff_eq!(
+        d.pdf(&mean).unwrap(),
+        1.0 / (2.0 * PI * f64::sqrt(det)),
+        epsilon = 1.0e-12
+    );
+
+    // Mahalanobis distance of (1, 0) from the mean is Σ^-1_22 = 2 / det
+    let x = Vector::new([1.0, 0.0]);
+    approx::assert_abs_diff_eq!(
+        d.log_pdf(&x).unwrap(),
+        -(2.0 * PI).ln() - 0.5 * det.ln() - 0.5 * 2.0 / det,
+        epsilon = 1.0e-12
+    );
+    approx::assert_abs_diff_eq!(
+        d.pdf(&x).unwrap().ln(),
+        d.log_pdf(&x).unwrap(),
+        epsilon = 1.0e-12
+    );
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let samples = (0..50_000).flat_map(|_| Vec::from(d.sample(&mut rng)));
+    let fit = MultivariateGaussian::fit(&Matrix::new(samples, 50_000, 2)).unwrap();
+    assert_eq!(fit.dim(), 2);
+    approx::assert_abs_diff_eq!(&fit.mean()[..], &mean[..], epsilon = 0.02);
+    approx::assert_abs_diff_eq!(&fit.covariance()[..], &covariance[..], epsilon = 0.04);
+}
+
+#[test]
+fn test_fisher() {
+    disable_error_handler();
+
+    // The density integrates to one over the sphere, and is uniform for κ = 0
+    for kappa in [0.0, 0.5, 20.0] {
+        let fisher = Fisher::new([0.0, 0.0, 2.0], kappa).unwrap();
+        let n = 10_000;
+        let integral = (0..n)
+            .map(|i| {
+                let cos = -1.0 + (i as f64 + 0.5) * 2.0 / n as f64;
+                let sin = (1.0 - cos * cos).sqrt();
+                fisher.pdf([sin, 0.0, cos]).unwrap() * 2.0 * PI * 2.0 / n as f64
+            })
+            .sum::<f64>();
+        approx::assert_abs_diff_eq!(integral, 1.0, epsilon = 1.0e-6);
+    }
+    let fisher = Fisher::new([1.0, 1.0, 0.0], 3.0).unwrap();
+    approx::assert_abs_diff_eq!(
+        fisher.pdf([1.0, 1.0, 0.0]).unwrap(),
+        3.0 / (4.0 * PI * 3.0f64.sinh()) * 3.0f64.exp(),
+        epsilon = 1.0e-12
+    );
+
+    // The mean cosine to the mean direction is A(κ) = coth(κ) - 1/κ,
+    // and fitting the samples recovers the parameters
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let kappa = fisher.kappa();
+    let samples = (0..100_000)
+        .map(|_| fisher.sample(&mut rng))
+        .collect::<Vec<_>>();
+    let mean_cos = samples
+        .iter()
+        .map(|x| (x[0] + x[1]) * 0.5f64.sqrt())
+        .sum::<f64>()
+        / samples.len() as f64;
+    approx::assert_abs_diff_eq!(mean_cos, 1.0 / kappa.tanh() - 1.0 / kappa, epsilon = 0.002);
+
+    let fit = Fisher::fit(&samples).unwrap();
+    approx::assert_abs_diff_eq!(fit.kappa(), kappa, epsilon = 0.05);
+    assert!(geometry::angle_between(fit.mean(), [1.0, 1.0, 0.0]).unwrap() < 0.01);
+
+    // Uniform directions have a concentration close to zero
+    let uniform = Fisher::new([0.0, 0.0, 1.0], 0.0).unwrap();
+    let samples = (0..10_000)
+        .map(|_| uniform.sample(&mut rng))
+        .collect::<Vec<_>>();
+    assert!(Fisher::fit(&samples).unwrap().kappa() < 0.1);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Exponent too small
+    Zipf::new(1.0).unwrap_err();
+    DiscretePowerLaw::new(0.5, 1).unwrap_err();
+
+    // Zero xmin
+    DiscretePowerLaw::new(2.0, 0).unwrap_err();
+
+    // Stable parameters out of range
+    Stable::new(2.5, 0.0, 1.0, 0.0).unwrap_err();
+    Stable::new(1.5, 1.5, 1.0, 0.0).unwrap_err();
+    Stable::new(1.5, 0.0, 0.0, 0.0).unwrap_err();
+
+    // Non positive scale
+    GeneralizedExtremeValue::new(0.0, 0.0, 0.1).unwrap_err();
+    GeneralizedPareto::new(0.0, -1.0, 0.1).unwrap_err();
+
+    // Probability out of range
+    let gev = GeneralizedExtremeValue::new(0.0, 1.0, 0.1).unwrap();
+    gev.quantile(1.5).unwrap_err();
+
+    // Too few or non finite data
+    GeneralizedExtremeValue::fit_pwm(&[1.0, 2.0]).unwrap_err();
+    GeneralizedExtremeValue::fit_pwm(&[1.0, 2.0, f64::NAN]).unwrap_err();
+
+    // Data below the threshold
+    GeneralizedPareto::fit_pwm(&[1.0, 2.0, 3.0], 1.5).unwrap_err();
+
+    // Invalid degrees of freedom or noncentrality
+    NoncentralChiSquared::new(0.0, 1.0).unwrap_err();
+    NoncentralChiSquared::new(2.0, -1.0).unwrap_err();
+    NoncentralT::new(-1.0, 0.0).unwrap_err();
+    NoncentralF::new(1.0, 0.0, 1.0).unwrap_err();
+    NoncentralT::new(3.0, 1.0)
+        .unwrap()
+        .quantile(1.5)
+        .unwrap_err();
+
+    // Tabulated pdf too short, not increasing, negative or zero
+    InverseCdfSampler::new(&[0.0, 1.0], &[1.0, 1.0]).unwrap_err();
+    InverseCdfSampler::new(&[0.0, 2.0, 1.0], &[1.0, 1.0, 1.0]).unwrap_err();
+    InverseCdfSampler::new(&[0.0, 1.0, 2.0], &[1.0, -1.0, 1.0]).unwrap_err();
+    InverseCdfSampler::new(&[0.0, 1.0, 2.0], &[0.0, 0.0, 0.0]).unwrap_err();
+    InverseCdfSampler::new(&[0.0, 1.0, 2.0], &[1.0, 1.0]).unwrap_err();
+
+    // Empty or negative histogram
+    let mut h = Histogram::uniform(2, 0.0, 1.0).unwrap();
+    HistogramDistribution::new(&h).unwrap_err();
+    h.accumulate(0.2, 1.0).unwrap();
+    h.accumulate(0.7, -0.5).unwrap();
+    HistogramDistribution::new(&h).unwrap_err();
+
+    // Piecewise linear pdf too short, negative or zero
+    PiecewiseLinearDistribution::new(&[0.0], &[1.0]).unwrap_err();
+    PiecewiseLinearDistribution::new(&[0.0, 1.0], &[1.0, -1.0]).unwrap_err();
+    PiecewiseLinearDistribution::new(&[0.0, 1.0], &[0.0, 0.0]).unwrap_err();
+    PiecewiseLinearDistribution::new(&[0.0, 1.0], &[1.0, 1.0])
+        .unwrap()
+        .quantile(-0.1)
+        .unwrap_err();
+
+    // Covariance not positive definite or of the wrong size
+    let origin = Vector::zeroes(2);
+    MultivariateGaussian::new(&origin, &Matrix::from([[1.0, 2.0], [2.0, 1.0]])).unwrap_err();
+    MultivariateGaussian::new(&origin, &Matrix::from([[1.0]])).unwrap_err();
+    MultivariateGaussian::new(&origin, &Matrix::from([[1.0, f64::NAN], [0.0, 1.0]])).unwrap_err();
+
+    // Point of the wrong dimension
+    MultivariateGaussian::new(&Vector::new([0.0]), &Matrix::from([[1.0]]))
+        .unwrap()
+        .pdf(&origin)
+        .unwrap_err();
+
+    // Too few samples
+    MultivariateGaussian::fit(&Matrix::from([[1.0, 2.0]])).unwrap_err();
+
+    // Not enough data in the tail
+    DiscretePowerLaw::fit(&[1, 2, 3], 10).unwrap_err();
+    DiscretePowerLaw::fit(&[3, 3, 3], 3).unwrap_err();
+    DiscretePowerLaw::fit_xmin(&[3, 3]).unwrap_err();
+
+    // No weights, a negative one or all zero
+    DiscreteDistribution::new(&[]).unwrap_err();
+    DiscreteDistribution::new(&[1.0, -1.0]).unwrap_err();
+    DiscreteDistribution::new(&[0.0, 0.0]).unwrap_err();
+
+    // Negative concentration, zero mean direction or zero point
+    Fisher::new([0.0, 0.0, 1.0], -1.0).unwrap_err();
+    Fisher::new([0.0; 3], 1.0).unwrap_err();
+    Fisher::new([0.0, 0.0, 1.0], 1.0)
+        .unwrap()
+        .pdf([0.0; 3])
+        .unwrap_err();
+
+    // No data, or all in the same direction
+    Fisher::fit(&[]).unwrap_err();
+    Fisher::fit(&[[1.0, 0.0, 0.0], [2.0, 0.0, 0.0]]).unwrap_err();
+}
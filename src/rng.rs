@@ -0,0 +1,1156 @@
+/*
+    rng.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+#[cfg(feature = "rand")]
+use std::any::Any;
+use std::ffi::CStr;
+use std::fmt;
+#[cfg(feature = "rand")]
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Random number generator algorithm.
+/// The `random8` to `random256` variants of the BSD, glibc and libc5 generators are left out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RngType {
+    /// Borosh-Niederreiter linear congruential generator
+    Borosh13,
+    /// Combined multiple recursive generator of L'Ecuyer
+    Cmrg,
+    /// Coveyou's quadratic generator
+    Coveyou,
+    /// Fishman linear congruential generator with modulus `2^31 - 1`
+    Fishman18,
+    /// Fishman linear congruential generator with multiplier 48271
+    Fishman20,
+    /// L'Ecuyer-Fishman combined generator
+    Fishman2x,
+    /// Four-tap lagged-fibonacci generator of Ziff
+    Gfsr4,
+    /// Knuth's lagged-fibonacci generator
+    Knuthran,
+    /// Knuth's second order multiple recursive generator
+    Knuthran2,
+    /// Knuth's lagged-fibonacci generator, 2002 revision
+    Knuthran2002,
+    /// L'Ecuyer linear congruential generator
+    Lecuyer21,
+    /// Park and Miller's minimal standard generator
+    Minstd,
+    /// Fifth order multiple recursive generator of L'Ecuyer, Blouin and Coutre
+    Mrg,
+    /// Mersenne twister, GSL's default generator
+    Mt19937,
+    /// Mersenne twister with the 1998 seeding procedure
+    Mt19937_1998,
+    /// Mersenne twister with the 1999 seeding procedure
+    Mt19937_1999,
+    /// Shift-register generator of Kirkpatrick and Stoll
+    R250,
+    /// `ran0` of Numerical Recipes
+    Ran0,
+    /// `ran1` of Numerical Recipes
+    Ran1,
+    /// `ran2` of Numerical Recipes
+    Ran2,
+    /// `ran3` of Numerical Recipes
+    Ran3,
+    /// BSD `rand`
+    Rand,
+    /// Unix `rand48`
+    Rand48,
+    /// BSD `random`
+    RandomBsd,
+    /// glibc 2 `random`
+    RandomGlibc2,
+    /// libc5 `random`
+    RandomLibc5,
+    /// IBM's RANDU
+    Randu,
+    /// CRAY `RANF`
+    Ranf,
+    /// Lüscher's RANLUX at luxury level 3
+    Ranlux,
+    /// Lüscher's RANLUX at luxury level 4
+    Ranlux389,
+    /// Lüscher's RANLXD in double precision at luxury level 1
+    Ranlxd1,
+    /// Lüscher's RANLXD in double precision at luxury level 2
+    Ranlxd2,
+    /// Lüscher's RANLXS in single precision at luxury level 0
+    Ranlxs0,
+    /// Lüscher's RANLXS in single precision at luxury level 1
+    Ranlxs1,
+    /// Lüscher's RANLXS in single precision at luxury level 2
+    Ranlxs2,
+    /// RANMAR lagged-fibonacci generator of Marsaglia, Zaman and Tsang
+    Ranmar,
+    /// SLATEC `rand`
+    Slatec,
+    /// Maximally equidistributed combined Tausworthe generator of L'Ecuyer
+    Taus,
+    /// Tausworthe generator with the improved seeding procedure
+    Taus2,
+    /// Maximally equidistributed combined Tausworthe generator with four components
+    Taus113,
+    /// INMOS Transputer generator
+    Transputer,
+    /// TT800 twisted generalized feedback shift register generator
+    Tt800,
+    /// SLATEC `uni`
+    Uni,
+    /// SLATEC `uni32`
+    Uni32,
+    /// VAX `MTH$RANDOM`
+    Vax,
+    /// Waterman linear congruential generator
+    Waterman14,
+    /// ZUFALL lagged-fibonacci generator of Petersen
+    Zuf,
+}
+
+impl RngType {
+    pub const ALL: [RngType; 47] = [
+        RngType::Borosh13,
+        RngType::Cmrg,
+        RngType::Coveyou,
+        RngType::Fishman18,
+        RngType::Fishman20,
+        RngType::Fishman2x,
+        RngType::Gfsr4,
+        RngType::Knuthran,
+        RngType::Knuthran2,
+        RngType::Knuthran2002,
+        RngType::Lecuyer21,
+        RngType::Minstd,
+        RngType::Mrg,
+        RngType::Mt19937,
+        RngType::Mt19937_1998,
+        RngType::Mt19937_1999,
+        RngType::R250,
+        RngType::Ran0,
+        RngType::Ran1,
+        RngType::Ran2,
+        RngType::Ran3,
+        RngType::Rand,
+        RngType::Rand48,
+        RngType::RandomBsd,
+        RngType::RandomGlibc2,
+        RngType::RandomLibc5,
+        RngType::Randu,
+        RngType::Ranf,
+        RngType::Ranlux,
+        RngType::Ranlux389,
+        RngType::Ranlxd1,
+        RngType::Ranlxd2,
+        RngType::Ranlxs0,
+        RngType::Ranlxs1,
+        RngType::Ranlxs2,
+        RngType::Ranmar,
+        RngType::Slatec,
+        RngType::Taus,
+        RngType::Taus2,
+        RngType::Taus113,
+        RngType::Transputer,
+        RngType::Tt800,
+        RngType::Uni,
+        RngType::Uni32,
+        RngType::Vax,
+        RngType::Waterman14,
+        RngType::Zuf,
+    ];
+
+    /// Name used by GSL, as in `GSL_RNG_TYPE`
+    pub fn name(self) -> &'static str {
+        match self {
+            RngType::Borosh13 => "borosh13",
+            RngType::Cmrg => "cmrg",
+            RngType::Coveyou => "coveyou",
+            RngType::Fishman18 => "fishman18",
+            RngType::Fishman20 => "fishman20",
+            RngType::Fishman2x => "fishman2x",
+            RngType::Gfsr4 => "gfsr4",
+            RngType::Knuthran => "knuthran",
+            RngType::Knuthran2 => "knuthran2",
+            RngType::Knuthran2002 => "knuthran2002",
+            RngType::Lecuyer21 => "lecuyer21",
+            RngType::Minstd => "minstd",
+            RngType::Mrg => "mrg",
+            RngType::Mt19937 => "mt19937",
+            RngType::Mt19937_1998 => "mt19937_1998",
+            RngType::Mt19937_1999 => "mt19937_1999",
+            RngType::R250 => "r250",
+            RngType::Ran0 => "ran0",
+            RngType::Ran1 => "ran1",
+            RngType::Ran2 => "ran2",
+            RngType::Ran3 => "ran3",
+            RngType::Rand => "rand",
+            RngType::Rand48 => "rand48",
+            RngType::RandomBsd => "random_bsd",
+            RngType::RandomGlibc2 => "random_glibc2",
+            RngType::RandomLibc5 => "random_libc5",
+            RngType::Randu => "randu",
+            RngType::Ranf => "ranf",
+            RngType::Ranlux => "ranlux",
+            RngType::Ranlux389 => "ranlux389",
+            RngType::Ranlxd1 => "ranlxd1",
+            RngType::Ranlxd2 => "ranlxd2",
+            RngType::Ranlxs0 => "ranlxs0",
+            RngType::Ranlxs1 => "ranlxs1",
+            RngType::Ranlxs2 => "ranlxs2",
+            RngType::Ranmar => "ranmar",
+            RngType::Slatec => "slatec",
+            RngType::Taus => "taus",
+            RngType::Taus2 => "taus2",
+            RngType::Taus113 => "taus113",
+            RngType::Transputer => "transputer",
+            RngType::Tt800 => "tt800",
+            RngType::Uni => "uni",
+            RngType::Uni32 => "uni32",
+            RngType::Vax => "vax",
+            RngType::Waterman14 => "waterman14",
+            RngType::Zuf => "zuf",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RngType> {
+        Self::ALL.iter().copied().find(|t| t.name() == name)
+    }
+
+    fn as_gsl(self) -> *const gsl_rng_type {
+        unsafe {
+            match self {
+                RngType::Borosh13 => gsl_rng_borosh13,
+                RngType::Cmrg => gsl_rng_cmrg,
+                RngType::Coveyou => gsl_rng_coveyou,
+                RngType::Fishman18 => gsl_rng_fishman18,
+                RngType::Fishman20 => gsl_rng_fishman20,
+                RngType::Fishman2x => gsl_rng_fishman2x,
+                RngType::Gfsr4 => gsl_rng_gfsr4,
+                RngType::Knuthran => gsl_rng_knuthran,
+                RngType::Knuthran2 => gsl_rng_knuthran2,
+                RngType::Knuthran2002 => gsl_rng_knuthran2002,
+                RngType::Lecuyer21 => gsl_rng_lecuyer21,
+                RngType::Minstd => gsl_rng_minstd,
+                RngType::Mrg => gsl_rng_mrg,
+                RngType::Mt19937 => gsl_rng_mt19937,
+                RngType::Mt19937_1998 => gsl_rng_mt19937_1998,
+                RngType::Mt19937_1999 => gsl_rng_mt19937_1999,
+                RngType::R250 => gsl_rng_r250,
+                RngType::Ran0 => gsl_rng_ran0,
+                RngType::Ran1 => gsl_rng_ran1,
+                RngType::Ran2 => gsl_rng_ran2,
+                RngType::Ran3 => gsl_rng_ran3,
+                RngType::Rand => gsl_rng_rand,
+                RngType::Rand48 => gsl_rng_rand48,
+                RngType::RandomBsd => gsl_rng_random_bsd,
+                RngType::RandomGlibc2 => gsl_rng_random_glibc2,
+                RngType::RandomLibc5 => gsl_rng_random_libc5,
+                RngType::Randu => gsl_rng_randu,
+                RngType::Ranf => gsl_rng_ranf,
+                RngType::Ranlux => gsl_rng_ranlux,
+                RngType::Ranlux389 => gsl_rng_ranlux389,
+                RngType::Ranlxd1 => gsl_rng_ranlxd1,
+                RngType::Ranlxd2 => gsl_rng_ranlxd2,
+                RngType::Ranlxs0 => gsl_rng_ranlxs0,
+                RngType::Ranlxs1 => gsl_rng_ranlxs1,
+                RngType::Ranlxs2 => gsl_rng_ranlxs2,
+                RngType::Ranmar => gsl_rng_ranmar,
+                RngType::Slatec => gsl_rng_slatec,
+                RngType::Taus => gsl_rng_taus,
+                RngType::Taus2 => gsl_rng_taus2,
+                RngType::Taus113 => gsl_rng_taus113,
+                RngType::Transputer => gsl_rng_transputer,
+                RngType::Tt800 => gsl_rng_tt800,
+                RngType::Uni => gsl_rng_uni,
+                RngType::Uni32 => gsl_rng_uni32,
+                RngType::Vax => gsl_rng_vax,
+                RngType::Waterman14 => gsl_rng_waterman14,
+                RngType::Zuf => gsl_rng_zuf,
+            }
+        }
+    }
+}
+
+/// Random number generator, owning its state.
+/// Cloning copies the state, so the clone produces the same sequence.
+pub struct Rng {
+    rng: *mut gsl_rng,
+    // State of a `rand_core` adapter, its address is the GSL state
+    #[cfg(feature = "rand")]
+    source: Option<Box<RandCoreState>>,
+}
+
+impl Rng {
+    /// Generator with the default seed of the algorithm
+    pub fn new(rng_type: RngType) -> Self {
+        unsafe {
+            let rng = gsl_rng_alloc(rng_type.as_gsl());
+            assert!(!rng.is_null());
+            Rng::from_raw(rng)
+        }
+    }
+
+    /// Generator selected by the environment variables `GSL_RNG_TYPE` and `GSL_RNG_SEED`,
+    /// as with `gsl_rng_env_setup`, but without changing the global defaults of GSL.
+    /// Defaults to `Mt19937` with its default seed.
+    /// Fails with `GSLError::Invalid` if `GSL_RNG_TYPE` is not a known generator or `GSL_RNG_SEED` not a number.
+    pub fn from_env() -> Result<Self> {
+        let var = |name| match std::env::var(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(std::env::VarError::NotUnicode(_)) => Err(GSLError::Invalid),
+        };
+        let (rng_type, seed) = parse_env(
+            var("GSL_RNG_TYPE")?.as_deref(),
+            var("GSL_RNG_SEED")?.as_deref(),
+        )?;
+        Ok(Rng::with_seed(rng_type, seed))
+    }
+
+    fn from_raw(rng: *mut gsl_rng) -> Self {
+        Rng {
+            rng,
+            #[cfg(feature = "rand")]
+            source: None,
+        }
+    }
+
+    pub fn with_seed(rng_type: RngType, seed: u64) -> Self {
+        let mut rng = Self::new(rng_type);
+        rng.set_seed(seed);
+        rng
+    }
+
+    /// Restarts the sequence, a seed of zero selects the default seed of the algorithm
+    pub fn set_seed(&mut self, seed: u64) {
+        unsafe {
+            gsl_rng_set(self.rng, seed);
+        }
+    }
+
+    /// Copy of the internal state, to resume the sequence later with `load_state`
+    pub fn save_state(&self) -> RngState {
+        unsafe {
+            let size = gsl_rng_size(self.rng) as usize;
+            let state = gsl_rng_state(self.rng) as *const u8;
+            RngState {
+                name: self.name(),
+                state: std::slice::from_raw_parts(state, size).into(),
+            }
+        }
+    }
+
+    /// Restores a state saved by `save_state`.
+    /// Fails with `GSLError::Invalid` if it was saved from a different algorithm or for a `rand_core` adapter.
+    pub fn load_state(&mut self, state: &RngState) -> Result<()> {
+        unsafe {
+            #[cfg(feature = "rand")]
+            if self.source.is_some() {
+                return Err(GSLError::Invalid);
+            }
+
+            // The generators index their buffers with stored indices, so only states of the same algorithm are valid
+            if state.name != self.name() {
+                return Err(GSLError::Invalid);
+            }
+            let size = gsl_rng_size(self.rng) as usize;
+            assert_eq!(state.state.len(), size);
+            let target = gsl_rng_state(self.rng) as *mut u8;
+            ptr::copy_nonoverlapping(state.state.as_ptr(), target, size);
+            Ok(())
+        }
+    }
+
+    /// Name of the algorithm, see `RngType::name`
+    pub fn name(&self) -> &'static str {
+        unsafe {
+            CStr::from_ptr(gsl_rng_name(self.rng))
+                .to_str()
+                .expect("generator names are ASCII")
+        }
+    }
+
+    /// Uniform on `[0, 1)`
+    pub fn uniform(&mut self) -> f64 {
+        self.draw(|rng| unsafe { gsl_rng_uniform(rng) })
+    }
+
+    /// Uniform integer on `[0, n)`, where `n` can be at most the range `max - min` of the generator
+    pub fn uniform_int(&mut self, n: u64) -> Result<u64> {
+        unsafe {
+            let range = gsl_rng_max(self.rng) - gsl_rng_min(self.rng);
+            if n == 0 || n > range {
+                return Err(GSLError::Invalid);
+            }
+            Ok(self.draw(|rng| gsl_rng_uniform_int(rng, n)))
+        }
+    }
+
+    /// Uniform on `(0, 1)`
+    pub fn uniform_pos(&mut self) -> f64 {
+        self.draw(|rng| unsafe { gsl_rng_uniform_pos(rng) })
+    }
+
+    /// Gaussian with zero mean
+    pub fn gaussian(&mut self, sigma: f64) -> f64 {
+        self.draw(|rng| unsafe { gsl_ran_gaussian_ziggurat(rng, sigma) })
+    }
+
+    /// Correlated pair `(x, y)` with zero means, standard deviations `sigma_x` and `sigma_y`
+    /// and correlation coefficient `rho` in `[-1, 1]`
+    pub fn bivariate_gaussian(&mut self, sigma_x: f64, sigma_y: f64, rho: f64) -> (f64, f64) {
+        let mut x = 0.0;
+        let mut y = 0.0;
+        self.draw(|rng| unsafe {
+            gsl_ran_bivariate_gaussian(rng, sigma_x, sigma_y, rho, &mut x, &mut y)
+        });
+        (x, y)
+    }
+
+    /// `n` pairs from `bivariate_gaussian`, split into the `x` and `y` coordinates
+    pub fn bivariate_gaussian_n(
+        &mut self,
+        n: usize,
+        sigma_x: f64,
+        sigma_y: f64,
+        rho: f64,
+    ) -> (Vec<f64>, Vec<f64>) {
+        (0..n)
+            .map(|_| self.bivariate_gaussian(sigma_x, sigma_y, rho))
+            .unzip()
+    }
+
+    pub fn beta(&mut self, a: f64, b: f64) -> f64 {
+        self.draw(|rng| unsafe { gsl_ran_beta(rng, a, b) })
+    }
+
+    /// Uniformly distributed direction in the plane, as a unit vector
+    pub fn dir_2d(&mut self) -> [f64; 2] {
+        let mut v = [0.0; 2];
+        let [x, y] = &mut v;
+        self.draw(|rng| unsafe { gsl_ran_dir_2d(rng, x, y) });
+        v
+    }
+
+    /// Uniformly distributed direction in space, as a unit vector
+    pub fn dir_3d(&mut self) -> [f64; 3] {
+        let mut v = [0.0; 3];
+        let [x, y, z] = &mut v;
+        self.draw(|rng| unsafe { gsl_ran_dir_3d(rng, x, y, z) });
+        v
+    }
+
+    /// Uniformly distributed unit vector in `n` dimensions
+    pub fn dir_nd(&mut self, n: usize) -> Vec<f64> {
+        let mut v = vec![0.0; n];
+        if n > 0 {
+            self.draw(|rng| unsafe { gsl_ran_dir_nd(rng, n as u64, v.as_mut_ptr()) });
+        }
+        v
+    }
+
+    /// Calls `f` with the GSL generator,
+    /// then resumes a panic of the `rand_core` generator of an adapter during the call
+    pub(crate) fn draw<T, F: FnOnce(*mut gsl_rng) -> T>(&mut self, f: F) -> T {
+        let x = f(self.rng);
+        #[cfg(feature = "rand")]
+        if let Some(payload) = self.source.as_mut().and_then(|s| s.panic.take()) {
+            resume_unwind(payload);
+        }
+        x
+    }
+}
+
+/// Generator type and seed from the values of `GSL_RNG_TYPE` and `GSL_RNG_SEED`.
+/// Like `strtoul` in `gsl_rng_env_setup`, the seed can be hexadecimal with `0x` or octal with a leading `0`.
+fn parse_env(rng_type: Option<&str>, seed: Option<&str>) -> Result<(RngType, u64)> {
+    let rng_type = match rng_type {
+        Some(name) => RngType::from_name(name).ok_or(GSLError::Invalid)?,
+        None => RngType::Mt19937,
+    };
+    let seed = match seed.map(str::trim) {
+        Some(seed) => {
+            let (digits, radix) =
+                if let Some(hex) = seed.strip_prefix("0x").or(seed.strip_prefix("0X")) {
+                    (hex, 16)
+                } else if seed.len() > 1 && seed.starts_with('0') {
+                    (&seed[1..], 8)
+                } else {
+                    (seed, 10)
+                };
+            u64::from_str_radix(digits, radix).map_err(|_| GSLError::Invalid)?
+        }
+        // The default seed of the algorithm
+        None => 0,
+    };
+    Ok((rng_type, seed))
+}
+
+/// Internal state of a generator, see `Rng::save_state`.
+/// It can only be restored into a generator of the same algorithm.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RngState {
+    name: &'static str,
+    state: Box<[u8]>,
+}
+
+impl RngState {
+    /// Name of the algorithm the state belongs to
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl fmt::Debug for RngState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RngState")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Clone for Rng {
+    fn clone(&self) -> Self {
+        unsafe {
+            let rng = gsl_rng_clone(self.rng);
+            assert!(!rng.is_null());
+
+            #[allow(unused_mut)]
+            let mut clone = Rng::from_raw(rng);
+            #[cfg(feature = "rand")]
+            if let Some(source) = &self.source {
+                clone.attach(Box::new(RandCoreState::new(source.source.box_clone())));
+            }
+            clone
+        }
+    }
+}
+
+impl fmt::Debug for Rng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rng").finish_non_exhaustive()
+    }
+}
+
+impl Drop for Rng {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_rng_free(self.rng);
+        }
+    }
+}
+
+// GSL is thread safe
+unsafe impl Send for Rng {}
+unsafe impl Sync for Rng {}
+
+/*
+
+    A `Reproducibility` context hands out the generators of an analysis from one master seed,
+    so publishing the seed is enough to re-run it bit for bit.
+    Every consumer, like a Monte Carlo integration, an MCMC chain or the generation of synthetic data,
+    asks for a generator by name, and gets a stream seeded from the master seed and the name only.
+    Adding or reordering consumers therefore doesn't change the draws of the others.
+
+    All samplers in this crate draw from the `Rng` they are given, on the calling thread.
+    The Monte Carlo drivers also take the context and the name of their stream instead:
+    `spatial::csr_envelope_reproducible`, the MCMC chains of `mcmc::SliceSampler::chain` and `mcmc::Hmc::chain`,
+    and `sampling::bootstrap`. `set_serial` keeps the parallel drivers, like `nonlinear_fit::fit_many_reproducible`,
+    on the calling thread as well, so a re-run doesn't depend on the thread pool.
+
+    The seeds of the streams are derived with FNV-1a of the name and SplitMix64, and are kept
+    to 32 bits, which all GSL generators use. They are fixed, so they don't change between versions.
+
+*/
+
+/// Named random streams derived from one master seed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reproducibility {
+    rng_type: RngType,
+    seed: u64,
+    serial: bool,
+    streams: Vec<(String, u64)>,
+}
+
+impl Reproducibility {
+    /// Context with `Mt19937` generators
+    pub fn new(seed: u64) -> Self {
+        Self::with_rng_type(RngType::Mt19937, seed)
+    }
+
+    pub fn with_rng_type(rng_type: RngType, seed: u64) -> Self {
+        Reproducibility {
+            rng_type,
+            seed,
+            serial: false,
+            streams: Vec::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng_type(&self) -> RngType {
+        self.rng_type
+    }
+
+    /// Runs the parallel drivers given this context on the calling thread only
+    pub fn set_serial(&mut self, serial: bool) {
+        self.serial = serial;
+    }
+
+    pub fn serial(&self) -> bool {
+        self.serial
+    }
+
+    /// Generator of the stream `name`, which starts from the beginning every time it's requested
+    pub fn rng(&mut self, name: &str) -> Rng {
+        let seed = self.stream_seed(name);
+        if !self.streams.iter().any(|(n, _)| n == name) {
+            self.streams.push((name.to_owned(), seed));
+        }
+        Rng::with_seed(self.rng_type, seed)
+    }
+
+    /// Seed of the stream `name`, without registering it
+    pub fn stream_seed(&self, name: &str) -> u64 {
+        // FNV-1a
+        let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+
+        // SplitMix64
+        let mut z = (self.seed ^ hash).wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        (z ^ (z >> 31)) >> 32
+    }
+
+    /// Names and seeds of the requested streams, in order of first request
+    pub fn streams(&self) -> &[(String, u64)] {
+        &self.streams
+    }
+}
+
+impl fmt::Display for Reproducibility {
+    /// Report of the master seed and the streams, to publish with the results
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} with seed {}", self.rng_type.name(), self.seed)?;
+        if self.serial {
+            write!(f, ", serial")?;
+        }
+        for (name, seed) in &self.streams {
+            write!(f, "\n  {}: {}", name, seed)?;
+        }
+        Ok(())
+    }
+}
+
+/*
+
+    With the `rand` feature, `Rng` implements `rand_core::RngCore`,
+    and `Rng::from_rand_core` wraps any `rand_core` generator as a GSL generator,
+    so it can drive the samplers of this crate.
+
+    The adapter registers a GSL generator type whose state is the address of the boxed Rust generator.
+    GSL copies the state when cloning, after which the clone gets its own copy of the Rust generator.
+
+    A panic of the Rust generator can't unwind through GSL. The adapter stores it,
+    and `Rng` resumes it once the GSL call returns. Until then the adapter draws from SplitMix64,
+    so rejection loops in GSL still end.
+
+*/
+
+#[cfg(feature = "rand")]
+trait RandSource: rand_core::RngCore + Send + Sync {
+    fn box_clone(&self) -> Box<dyn RandSource>;
+}
+
+#[cfg(feature = "rand")]
+impl<R: rand_core::RngCore + Clone + Send + Sync + 'static> RandSource for R {
+    fn box_clone(&self) -> Box<dyn RandSource> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(feature = "rand")]
+struct RandCoreType(gsl_rng_type);
+
+// The type is immutable
+#[cfg(feature = "rand")]
+unsafe impl Sync for RandCoreType {}
+
+#[cfg(feature = "rand")]
+static RAND_CORE_TYPE: RandCoreType = RandCoreType(gsl_rng_type {
+    name: c"rand_core".as_ptr(),
+    max: u32::MAX as c_ulong,
+    min: 0,
+    size: std::mem::size_of::<*mut RandCoreState>() as _,
+    set: Some(rand_core_set),
+    get: Some(rand_core_get),
+    get_double: Some(rand_core_get_double),
+});
+
+#[cfg(feature = "rand")]
+struct RandCoreState {
+    source: Box<dyn RandSource>,
+    // First panic of `source` during the current GSL call
+    panic: Option<Box<dyn Any + Send>>,
+    // SplitMix64 state for the draws after a panic
+    fallback: u64,
+}
+
+#[cfg(feature = "rand")]
+impl RandCoreState {
+    fn new(source: Box<dyn RandSource>) -> Self {
+        RandCoreState {
+            source,
+            panic: None,
+            fallback: 0,
+        }
+    }
+
+    /// Draws from `source` with `f`, or from the fallback once `source` panicked
+    fn next<F: FnOnce(&mut dyn RandSource) -> u64>(&mut self, f: F) -> u64 {
+        if self.panic.is_none() {
+            match catch_unwind(AssertUnwindSafe(|| f(&mut *self.source))) {
+                Ok(x) => return x,
+                Err(payload) => self.panic = Some(payload),
+            }
+        }
+        self.fallback = self.fallback.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.fallback;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(feature = "rand")]
+unsafe fn rand_core_state<'a>(state: *mut c_void) -> &'a mut RandCoreState {
+    &mut **(state as *mut *mut RandCoreState)
+}
+
+// Called with uninitialized state by gsl_rng_alloc, seeding is left to the Rust generator
+#[cfg(feature = "rand")]
+unsafe extern "C" fn rand_core_set(_state: *mut c_void, _seed: c_ulong) {}
+
+#[cfg(feature = "rand")]
+unsafe extern "C" fn rand_core_get(state: *mut c_void) -> c_ulong {
+    let state = rand_core_state(state);
+    state.next(|source| source.next_u32() as u64) as u32 as c_ulong
+}
+
+#[cfg(feature = "rand")]
+unsafe extern "C" fn rand_core_get_double(state: *mut c_void) -> f64 {
+    let state = rand_core_state(state);
+    // 53 random bits on [0, 1)
+    (state.next(|source| source.next_u64()) >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+#[cfg(feature = "rand")]
+impl Rng {
+    /// GSL generator drawing from a `rand_core` generator.
+    /// Seeding with `set_seed` has no effect, and the state can't be loaded with `load_state`.
+    pub fn from_rand_core<R: rand_core::RngCore + Clone + Send + Sync + 'static>(
+        source: R,
+    ) -> Self {
+        unsafe {
+            let rng = gsl_rng_alloc(&RAND_CORE_TYPE.0);
+            assert!(!rng.is_null());
+
+            let mut rng = Rng::from_raw(rng);
+            rng.attach(Box::new(RandCoreState::new(Box::new(source))));
+            rng
+        }
+    }
+
+    unsafe fn attach(&mut self, mut state: Box<RandCoreState>) {
+        let address: *mut RandCoreState = &mut *state;
+        *(gsl_rng_state(self.rng) as *mut *mut RandCoreState) = address;
+        self.source = Some(state);
+    }
+
+    /// Draws `bits` uniform random bits, at most 64
+    fn next_bits(&mut self, bits: u32) -> u64 {
+        unsafe {
+            let min = gsl_rng_min(self.rng);
+            let range = gsl_rng_max(self.rng) - min;
+
+            // Bits per draw: all of them if the range is a power of two, otherwise uniform_int scales down
+            let (per_draw, full) = if range.count_ones() == 64 - range.leading_zeros() {
+                (range.count_ones(), true)
+            } else {
+                (63 - range.leading_zeros(), false)
+            };
+
+            let mut x = 0u64;
+            let mut n = 0;
+            while n < bits {
+                let draw = if full {
+                    self.draw(|rng| gsl_rng_get(rng)) - min
+                } else {
+                    self.draw(|rng| gsl_rng_uniform_int(rng, 1 << per_draw))
+                };
+                x = x.checked_shl(per_draw).unwrap_or(0) | draw;
+                n += per_draw;
+            }
+            if bits < 64 {
+                x & ((1 << bits) - 1)
+            } else {
+                x
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_bits(32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next_bits(64)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Seeds the default algorithm, `Mt19937`, with a little-endian `u64`
+#[cfg(feature = "rand")]
+impl rand_core::SeedableRng for Rng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Rng::with_seed(RngType::Mt19937, u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Rng::with_seed(RngType::Mt19937, state)
+    }
+}
+
+#[test]
+fn test_rng() {
+    let mut a = Rng::with_seed(RngType::Mt19937, 42);
+    let mut b = Rng::new(RngType::Mt19937);
+    b.set_seed(42);
+
+    for _ in 0..10 {
+        let x = a.uniform();
+        assert!((0.0..1.0).contains(&x));
+        assert_eq!(x, b.uniform());
+    }
+
+    // A clone continues the same sequence independently
+    let mut c = a.clone();
+    for _ in 0..10 {
+        assert_eq!(a.uniform(), c.uniform());
+    }
+
+    // Reseeding restarts the sequence
+    let first = a.uniform();
+    a.set_seed(7);
+    c.set_seed(7);
+    assert_eq!(a.uniform(), c.uniform());
+    assert_ne!(first, c.uniform());
+
+    let n = 100_000;
+    let mean = (0..n).map(|_| a.gaussian(2.0)).sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.05);
+
+    // Mean of Beta(a, b) is a / (a + b)
+    let mean = (0..n).map(|_| a.beta(2.0, 6.0)).sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 0.25, epsilon = 0.01);
+
+    let mut counts = [0; 6];
+    for _ in 0..60_000 {
+        counts[a.uniform_int(6).unwrap() as usize] += 1;
+    }
+    for count in counts {
+        approx::assert_abs_diff_eq!(count as f64, 10_000.0, epsilon = 500.0);
+    }
+}
+
+#[test]
+fn test_reproducibility() {
+    let draws = |context: &mut Reproducibility, name: &str| {
+        let mut rng = context.rng(name);
+        (0..10).map(|_| rng.gaussian(1.0)).collect::<Vec<_>>()
+    };
+
+    // Streams only depend on the master seed and their name, not on the order of requests
+    let mut a = Reproducibility::new(42);
+    let mut b = Reproducibility::new(42);
+    let mcmc = draws(&mut a, "mcmc");
+    let bootstrap = draws(&mut a, "bootstrap");
+    assert_eq!(draws(&mut b, "bootstrap"), bootstrap);
+    assert_eq!(draws(&mut b, "mcmc"), mcmc);
+    assert_ne!(mcmc, bootstrap);
+    assert_eq!(draws(&mut a, "mcmc"), mcmc);
+    assert_ne!(draws(&mut Reproducibility::new(43), "mcmc"), mcmc);
+
+    // The derived seeds are fixed
+    assert_eq!(
+        a.streams(),
+        [
+            ("mcmc".to_owned(), 757906581),
+            ("bootstrap".to_owned(), 4127110805)
+        ]
+    );
+    assert_eq!(Reproducibility::new(43).stream_seed("mcmc"), 304177954);
+    assert_eq!(
+        a.to_string(),
+        "mt19937 with seed 42\n  mcmc: 757906581\n  bootstrap: 4127110805"
+    );
+
+    let mut c = Reproducibility::with_rng_type(RngType::Taus2, 42);
+    assert_eq!(c.rng("mcmc").name(), "taus2");
+    assert!(!c.serial());
+    c.set_serial(true);
+    assert_eq!(
+        c.to_string(),
+        "taus2 with seed 42, serial\n  mcmc: 757906581"
+    );
+}
+
+#[test]
+fn test_bivariate_gaussian() {
+    let mut rng = Rng::with_seed(RngType::Mt19937, 5);
+
+    let (x, y) = rng.bivariate_gaussian_n(100_000, 1.0, 3.0, -0.6);
+    assert_eq!(x.len(), 100_000);
+    approx::assert_abs_diff_eq!(stats::sd(&x), 1.0, epsilon = 0.02);
+    approx::assert_abs_diff_eq!(stats::sd(&y), 3.0, epsilon = 0.05);
+    approx::assert_abs_diff_eq!(stats::correlation(&x, &y).unwrap(), -0.6, epsilon = 0.01);
+
+    // Perfectly correlated
+    let (x, y) = rng.bivariate_gaussian(1.0, 2.0, 1.0);
+    approx::assert_abs_diff_eq!(y, 2.0 * x, epsilon = 1.0e-12);
+
+    approx::assert_abs_diff_eq!(
+        randist::bivariate_gaussian_pdf(0.0, 0.0, 1.0, 3.0, -0.6),
+        1.0 / (2.0 * std::f64::consts::PI * 3.0 * 0.8),
+        epsilon = 1.0e-12
+    );
+}
+
+#[test]
+fn test_directions() {
+    let mut rng = Rng::with_seed(RngType::Mt19937, 6);
+
+    let [x, y] = rng.dir_2d();
+    approx::assert_abs_diff_eq!(x * x + y * y, 1.0, epsilon = 1.0e-12);
+    let v = rng.dir_nd(5);
+    approx::assert_abs_diff_eq!(v.iter().map(|x| x * x).sum::<f64>(), 1.0, epsilon = 1.0e-12);
+
+    // Every coordinate of a uniform direction in space is uniform on [-1, 1]
+    let z = (0..100_000).map(|_| rng.dir_3d()[2]).collect::<Vec<_>>();
+    approx::assert_abs_diff_eq!(stats::mean(&z), 0.0, epsilon = 0.01);
+    approx::assert_abs_diff_eq!(stats::variance(&z), 1.0 / 3.0, epsilon = 0.01);
+}
+
+#[test]
+fn test_rng_types() {
+    disable_error_handler();
+
+    for rng_type in RngType::ALL {
+        let mut rng = Rng::with_seed(rng_type, 1);
+        assert_eq!(rng.name(), rng_type.name());
+        assert_eq!(RngType::from_name(rng_type.name()), Some(rng_type));
+
+        let mut clone = rng.clone();
+        for _ in 0..100 {
+            let x = rng.uniform();
+            assert!((0.0..1.0).contains(&x));
+            assert_eq!(x, clone.uniform());
+        }
+    }
+    assert_eq!(RngType::from_name("mt"), None);
+}
+
+#[test]
+fn test_parse_env() {
+    assert_eq!(parse_env(None, None), Ok((RngType::Mt19937, 0)));
+    assert_eq!(
+        parse_env(Some("taus2"), Some("123")),
+        Ok((RngType::Taus2, 123))
+    );
+    assert_eq!(parse_env(None, Some("0x1f")), Ok((RngType::Mt19937, 31)));
+    assert_eq!(parse_env(None, Some("017")), Ok((RngType::Mt19937, 15)));
+    assert_eq!(parse_env(None, Some("0")), Ok((RngType::Mt19937, 0)));
+
+    parse_env(Some("unknown"), None).unwrap_err();
+    parse_env(Some(""), None).unwrap_err();
+    parse_env(None, Some("seed")).unwrap_err();
+    parse_env(None, Some("-1")).unwrap_err();
+}
+
+#[test]
+fn test_state() {
+    for rng_type in [RngType::Mt19937, RngType::Ranlxd2, RngType::Gfsr4] {
+        let mut rng = Rng::with_seed(rng_type, 3);
+        for _ in 0..1000 {
+            rng.uniform();
+        }
+
+        let state = rng.save_state();
+        assert_eq!(state.name(), rng_type.name());
+        let expected = (0..100).map(|_| rng.uniform()).collect::<Vec<_>>();
+
+        // A fresh generator continues where the saved one left off
+        let mut resumed = Rng::new(rng_type);
+        resumed.load_state(&state).unwrap();
+        for x in expected {
+            assert_eq!(resumed.uniform(), x);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+#[derive(Clone)]
+struct SplitMix64(u64);
+
+#[cfg(all(test, feature = "rand"))]
+impl rand_core::RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn test_rand_core() {
+    use rand_core::{RngCore, SeedableRng};
+
+    disable_error_handler();
+
+    // Generators with a 32 bit, a 31 bit and a non power of two range
+    for rng_type in [
+        RngType::Mt19937,
+        RngType::Rand,
+        RngType::Ranlxd2,
+        RngType::Minstd,
+    ] {
+        let mut rng = Rng::with_seed(rng_type, 5);
+        let n = 20_000;
+        let mut ones = [0; 64];
+        for _ in 0..n {
+            let x = rng.next_u64();
+            for (bit, count) in ones.iter_mut().enumerate() {
+                *count += (x >> bit) & 1;
+            }
+        }
+        for count in ones {
+            approx::assert_abs_diff_eq!(count as f64 / n as f64, 0.5, epsilon = 0.02);
+        }
+    }
+
+    let mut a = Rng::seed_from_u64(9);
+    let mut b = Rng::from_seed(9u64.to_le_bytes());
+    let mut bytes = [0; 13];
+    a.fill_bytes(&mut bytes);
+    assert_eq!(b.next_u64().to_le_bytes(), bytes[..8]);
+
+    // The adapter draws from the Rust generator
+    let mut source = SplitMix64(1);
+    let mut rng = Rng::from_rand_core(source.clone());
+    assert_eq!(rng.name(), "rand_core");
+    let expected = (source.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    assert_eq!(rng.uniform(), expected);
+
+    // A clone continues with its own copy
+    let mut clone = rng.clone();
+    assert_eq!(rng.uniform(), clone.uniform());
+    drop(rng);
+    let mean = (0..100_000).map(|_| clone.gaussian(1.0)).sum::<f64>() / 100_000.0;
+    approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.02);
+    clone.load_state(&clone.save_state()).unwrap_err();
+
+    // A panic of the Rust generator is resumed once GSL returns
+    #[derive(Clone)]
+    struct Exhausted;
+    impl RngCore for Exhausted {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+        fn next_u64(&mut self) -> u64 {
+            panic!("exhausted")
+        }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+    let mut rng = Rng::from_rand_core(Exhausted);
+    let payload = catch_unwind(AssertUnwindSafe(|| rng.gaussian(1.0))).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"exhausted"));
+    catch_unwind(AssertUnwindSafe(|| rng.uniform_int(10))).unwrap_err();
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let mut rng = Rng::new(RngType::Mt19937);
+
+    // Empty range
+    rng.uniform_int(0).unwrap_err();
+
+    // Larger than the range max - min = 2^32 - 1 of the generator, which itself is fine
+    let range = u32::MAX as u64;
+    assert!(rng.uniform_int(range).unwrap() < range);
+    rng.uniform_int(range + 1).unwrap_err();
+    rng.uniform_int(u64::MAX).unwrap_err();
+
+    // State of a different algorithm
+    let state = Rng::new(RngType::Taus2).save_state();
+    rng.load_state(&state).unwrap_err();
+}
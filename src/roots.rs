@@ -0,0 +1,363 @@
+/*
+    roots.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::cheb::ChebSeries;
+use crate::min;
+use crate::*;
+use drop_guard::guard;
+
+/*
+
+    `find_all_roots` evaluates `f` on a uniform grid and polishes every sign change with Brent's method.
+    Roots closer together than the grid spacing can be missed. A Chebyshev proxy of `f`
+    is cheap to evaluate on a much finer grid, so sign changes of the proxy add extra brackets.
+    Roots of even multiplicity don't change sign, so local minima of `|f|` on the grid without a root nearby
+    are minimized and accepted if `|f|` vanishes there relative to its size on the grid.
+    These are only accurate to about the square root of the machine precision.
+
+    The multiplicity `m` is estimated from `f(r + h) ~ h^m` close to the root,
+    which is only a hint: it's unreliable when roots are close together.
+
+*/
+
+/// Relative size of `|f|` on the grid below which a touching minimum counts as a root
+const TOUCH_TOLERANCE: f64 = 1.0e-8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Root {
+    pub x: f64,
+    /// Estimated multiplicity of the root
+    pub multiplicity: usize,
+}
+
+/// How a bracketing solver stopped
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Convergence {
+    /// The bracket passed the interval test with `epsabs` and `epsrel`
+    Interval,
+    /// `f` vanishes exactly at the root
+    ExactZero,
+    /// The iteration limit was reached, accepted by a best effort `NumericPolicy`
+    MaxIteration,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RootResult {
+    pub root: f64,
+    /// Function value at the root
+    pub f_root: f64,
+    pub iterations: usize,
+    /// Last bracket `(lower, upper)` around the root
+    pub final_bracket: (f64, f64),
+    pub converged_by: Convergence,
+}
+
+/// Root of `f` in `[a, b]`, where `f(a)` and `f(b)` have opposite signs, with the tolerances
+/// and iterations of the `NumericPolicy`, by default `epsabs = 1e-10`, `epsrel = 0` and 100 iterations
+pub fn find_root<F: FnMut(f64) -> f64>(f: F, a: f64, b: f64) -> Result<RootResult> {
+    find_root_with_policy(policy::policy(), f, a, b)
+}
+
+/// `find_root` with `policy` instead of the policy of this thread
+pub fn find_root_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    f: F,
+    a: f64,
+    b: f64,
+) -> Result<RootResult> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-10, 0.0);
+        find_root_ext(policy.iterations(100), f, a, b, epsabs, epsrel)
+    })
+}
+
+/// Root of `f` in `[a, b]` with Brent's method, where `f(a)` and `f(b)` have opposite signs.
+/// Converges once the bracket satisfies `gsl_root_test_interval` with `epsabs` and `epsrel`.
+pub fn find_root_ext<F: FnMut(f64) -> f64>(
+    max_iter: usize,
+    mut f: F,
+    a: f64,
+    b: f64,
+    epsabs: f64,
+    epsrel: f64,
+) -> Result<RootResult> {
+    unsafe {
+        let solver = guard(gsl_root_fsolver_alloc(gsl_root_fsolver_brent), |solver| {
+            gsl_root_fsolver_free(solver);
+        });
+        assert!(!solver.is_null());
+
+        let mut gsl_f = gsl_function_struct {
+            function: Some(trampoline::<F>),
+            params: &mut f as *mut _ as *mut _,
+        };
+
+        GSLError::from_raw(gsl_root_fsolver_set(*solver, &mut gsl_f, a, b))?;
+
+        let mut iterations = 0;
+        let mut converged_by = Convergence::MaxIteration;
+        while iterations < max_iter {
+            GSLError::from_raw(gsl_root_fsolver_iterate(*solver))?;
+            iterations += 1;
+
+            let x_lower = gsl_root_fsolver_x_lower(*solver);
+            let x_upper = gsl_root_fsolver_x_upper(*solver);
+            if gsl_root_test_interval(x_lower, x_upper, epsabs, epsrel) == GSL_SUCCESS {
+                converged_by = Convergence::Interval;
+                break;
+            }
+        }
+        if converged_by == Convergence::MaxIteration {
+            policy::converged(Err(GSLError::MaxIteration))?;
+        }
+
+        let root = gsl_root_fsolver_root(*solver);
+        let final_bracket = (
+            gsl_root_fsolver_x_lower(*solver),
+            gsl_root_fsolver_x_upper(*solver),
+        );
+        let f_root = f(root);
+        if f_root == 0.0 {
+            converged_by = Convergence::ExactZero;
+        }
+        Ok(RootResult {
+            root,
+            f_root,
+            iterations,
+            final_bracket,
+            converged_by,
+        })
+    }
+}
+
+/// All roots of `f` in `[a, b]` found on a grid of `n_subdivisions` intervals, see `find_all_roots_ext`.
+/// Uses the tolerances of the `NumericPolicy`, by default `epsabs = 1e-10` and `epsrel = 0`.
+pub fn find_all_roots<F: FnMut(f64) -> f64>(
+    f: F,
+    a: f64,
+    b: f64,
+    n_subdivisions: usize,
+) -> Result<Vec<Root>> {
+    find_all_roots_with_policy(policy::policy(), f, a, b, n_subdivisions)
+}
+
+/// `find_all_roots` with `policy` instead of the policy of this thread
+pub fn find_all_roots_with_policy<F: FnMut(f64) -> f64>(
+    policy: policy::NumericPolicy,
+    f: F,
+    a: f64,
+    b: f64,
+    n_subdivisions: usize,
+) -> Result<Vec<Root>> {
+    policy::with_policy(policy, || {
+        let (epsabs, epsrel) = policy.tolerances(1.0e-10, 0.0);
+        find_all_roots_ext(f, a, b, n_subdivisions, None, epsabs, epsrel)
+    })
+}
+
+/// All roots of `f` in `[a, b]` in increasing order.
+/// The sign changes of `f` on a grid of `n_subdivisions` intervals,
+/// and of its Chebyshev proxy of order `chebyshev_order` if given, are polished to `epsabs` and `epsrel`.
+pub fn find_all_roots_ext<F: FnMut(f64) -> f64>(
+    mut f: F,
+    a: f64,
+    b: f64,
+    n_subdivisions: usize,
+    chebyshev_order: Option<usize>,
+    epsabs: f64,
+    epsrel: f64,
+) -> Result<Vec<Root>> {
+    if n_subdivisions == 0 {
+        return Err(GSLError::Invalid);
+    }
+    if !(a < b && a.is_finite() && b.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+
+    let step = (b - a) / n_subdivisions as f64;
+    let x = (0..=n_subdivisions)
+        .map(|i| a + i as f64 * step)
+        .collect::<Vec<_>>();
+    let y = x.iter().map(|&xi| f(xi)).collect::<Vec<_>>();
+    if y.iter().any(|yi| yi.is_nan()) {
+        return Err(GSLError::BadFunction);
+    }
+
+    let mut roots = Vec::new();
+    for i in 0..=n_subdivisions {
+        if y[i] == 0.0 {
+            roots.push(x[i]);
+        }
+        if i < n_subdivisions && y[i] * y[i + 1] < 0.0 {
+            roots.push(find_root_ext(100, &mut f, x[i], x[i + 1], epsabs, epsrel)?.root);
+        }
+    }
+
+    // Sign changes of the proxy between the grid points
+    if let Some(order) = chebyshev_order {
+        let proxy = ChebSeries::new(order, a, b, &mut f)?;
+        let n_fine = 8 * order.max(n_subdivisions);
+        let fine_step = (b - a) / n_fine as f64;
+        let mut lower = (a, proxy.eval(a));
+        for i in 1..=n_fine {
+            let xi = a + i as f64 * fine_step;
+            let upper = (xi, proxy.eval(xi));
+            if lower.1 * upper.1 < 0.0 && f(lower.0) * f(upper.0) < 0.0 {
+                roots.push(find_root_ext(100, &mut f, lower.0, upper.0, epsabs, epsrel)?.root);
+            }
+            lower = upper;
+        }
+    }
+
+    // Touching roots show up as a local minimum of |f| without a sign change around it
+    let scale = y.iter().fold(0.0f64, |m, yi| m.max(yi.abs()));
+    for i in 1..n_subdivisions {
+        let touching = y[i] != 0.0
+            && y[i] * y[i - 1] > 0.0
+            && y[i] * y[i + 1] > 0.0
+            && y[i].abs() < y[i - 1].abs()
+            && y[i].abs() < y[i + 1].abs();
+        if !touching || roots.iter().any(|&r| r > x[i - 1] && r < x[i + 1]) {
+            continue;
+        }
+        let tolerance = epsabs.max(f64::EPSILON.sqrt() * (x[i].abs() + step));
+        let minimum = min::minimize_result_ext(
+            100,
+            x[i - 1],
+            x[i + 1],
+            x[i],
+            tolerance,
+            epsrel,
+            |x| f(x).abs(),
+            |_| {},
+        );
+        // A failed minimization means |f| doesn't approach zero
+        if let Ok(minimum) = minimum {
+            if minimum.f_minimum <= TOUCH_TOLERANCE * scale {
+                roots.push(minimum.minimum);
+            }
+        }
+    }
+
+    // The proxy finds most roots a second time, polished to the same tolerance
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots.dedup_by(|r, s| (*r - *s).abs() <= 2.0 * (epsabs + epsrel * r.abs()) + 1.0e-9 * step);
+
+    Ok(roots
+        .into_iter()
+        .map(|r| Root {
+            x: r,
+            multiplicity: multiplicity(&mut f, r, step * 1.0e-3, a, b),
+        })
+        .collect())
+}
+
+/// Estimate of `m` in `f(r + h) ~ h^m` from doubling `h` on both sides of the root
+fn multiplicity<F: FnMut(f64) -> f64>(f: &mut F, r: f64, h: f64, a: f64, b: f64) -> usize {
+    let mut sum = 0.0;
+    let mut count = 0;
+    for h in [h, -h] {
+        if r + 2.0 * h < a || r + 2.0 * h > b {
+            continue;
+        }
+        let m = (f(r + 2.0 * h) / f(r + h)).abs().log2();
+        if m.is_finite() {
+            sum += m;
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return 1;
+    }
+    (sum / count as f64).round().max(1.0) as usize
+}
+
+#[test]
+fn test_find_root() {
+    disable_error_handler();
+
+    let result = find_root(f64::cos, 1.0, 2.0).unwrap();
+    approx::assert_abs_diff_eq!(result.root, std::f64::consts::FRAC_PI_2, epsilon = 1.0e-9);
+    approx::assert_abs_diff_eq!(result.f_root, 0.0, epsilon = 1.0e-9);
+    assert_eq!(result.converged_by, Convergence::Interval);
+    assert!(result.iterations > 0 && result.iterations <= 100);
+    let (lower, upper) = result.final_bracket;
+    assert!(lower <= result.root && result.root <= upper && upper - lower <= 2.0e-10);
+
+    // Exact roots are reported as such
+    let result = find_root(|x| x - 0.5, 0.0, 1.0).unwrap();
+    assert_eq!(result.root, 0.5);
+    assert_eq!(result.converged_by, Convergence::ExactZero);
+}
+
+#[test]
+fn test_find_all_roots() {
+    disable_error_handler();
+
+    use std::f64::consts::PI;
+
+    // sin has roots at multiples of pi
+    let roots = find_all_roots(f64::sin, -1.0, 10.0, 50).unwrap();
+    assert_eq!(roots.len(), 4);
+    for (k, root) in roots.iter().enumerate() {
+        approx::assert_abs_diff_eq!(root.x, k as f64 * PI, epsilon = 1.0e-9);
+        assert_eq!(root.multiplicity, 1);
+    }
+
+    // A double root without a sign change and a triple root with one
+    let f = |x: f64| (x - 1.0).powi(2) * (x - 2.0).powi(3);
+    let roots = find_all_roots(f, 0.0, 3.0, 31).unwrap();
+    assert_eq!(roots.len(), 2);
+    approx::assert_abs_diff_eq!(roots[0].x, 1.0, epsilon = 1.0e-6);
+    assert_eq!(roots[0].multiplicity, 2);
+    approx::assert_abs_diff_eq!(roots[1].x, 2.0, epsilon = 1.0e-3);
+    assert_eq!(roots[1].multiplicity, 3);
+
+    // Close to zero without touching it
+    let roots = find_all_roots(|x| x * x + 1.0e-3, -1.0, 1.0, 11).unwrap();
+    assert!(roots.is_empty());
+
+    // Two roots within one grid interval only show up with the proxy
+    let f = |x: f64| (x - 0.51) * (x - 0.53) * (x + 2.0);
+    assert!(find_all_roots(f, 0.0, 1.0, 5).unwrap().len() < 2);
+    let roots = find_all_roots_ext(f, 0.0, 1.0, 5, Some(10), 1.0e-12, 0.0).unwrap();
+    assert_eq!(roots.len(), 2);
+    approx::assert_abs_diff_eq!(roots[0].x, 0.51, epsilon = 1.0e-10);
+    approx::assert_abs_diff_eq!(roots[1].x, 0.53, epsilon = 1.0e-10);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // No sign change
+    find_root(f64::cos, 0.0, 1.0).unwrap_err();
+
+    // No iterations
+    find_root_ext(0, f64::cos, 1.0, 2.0, 1.0e-10, 0.0).unwrap_err();
+
+    // Empty interval or no subdivisions
+    find_all_roots(f64::sin, 1.0, 1.0, 10).unwrap_err();
+    find_all_roots(f64::sin, 0.0, 1.0, 0).unwrap_err();
+
+    // Not defined on the grid
+    find_all_roots(f64::ln, -1.0, 1.0, 10).unwrap_err();
+}
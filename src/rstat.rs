@@ -0,0 +1,230 @@
+/*
+    rstat.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+use std::fmt;
+
+/// Statistics accumulated one value at a time, without storing the values
+pub struct RunningStats {
+    workspace: *mut gsl_rstat_workspace,
+}
+
+impl RunningStats {
+    pub fn new() -> Self {
+        unsafe {
+            let workspace = gsl_rstat_alloc();
+            assert!(!workspace.is_null());
+            RunningStats { workspace }
+        }
+    }
+
+    pub fn add(&mut self, x: f64) -> Result<()> {
+        unsafe { GSLError::from_raw(gsl_rstat_add(x, self.workspace)) }
+    }
+
+    pub fn reset(&mut self) {
+        unsafe {
+            gsl_rstat_reset(self.workspace);
+        }
+    }
+
+    /// Number of values added
+    pub fn n(&self) -> usize {
+        unsafe { gsl_rstat_n(self.workspace) as usize }
+    }
+
+    pub fn min(&self) -> f64 {
+        unsafe { gsl_rstat_min(self.workspace) }
+    }
+
+    pub fn max(&self) -> f64 {
+        unsafe { gsl_rstat_max(self.workspace) }
+    }
+
+    pub fn mean(&self) -> f64 {
+        unsafe { gsl_rstat_mean(self.workspace) }
+    }
+
+    /// Sample variance, normalized by `n - 1`
+    pub fn variance(&self) -> f64 {
+        unsafe { gsl_rstat_variance(self.workspace) }
+    }
+
+    pub fn sd(&self) -> f64 {
+        unsafe { gsl_rstat_sd(self.workspace) }
+    }
+
+    /// Standard deviation of the mean, `sd / sqrt(n)`
+    pub fn sd_mean(&self) -> f64 {
+        unsafe { gsl_rstat_sd_mean(self.workspace) }
+    }
+
+    pub fn rms(&self) -> f64 {
+        unsafe { gsl_rstat_rms(self.workspace) }
+    }
+
+    pub fn skew(&self) -> f64 {
+        unsafe { gsl_rstat_skew(self.workspace) }
+    }
+
+    /// Excess kurtosis
+    pub fn kurtosis(&self) -> f64 {
+        unsafe { gsl_rstat_kurtosis(self.workspace) }
+    }
+
+    /// Estimate of the median using the P² algorithm
+    pub fn median(&self) -> f64 {
+        unsafe { gsl_rstat_median(self.workspace) }
+    }
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RunningStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RunningStats")
+            .field("n", &self.n())
+            .field("mean", &self.mean())
+            .field("sd", &self.sd())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for RunningStats {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_rstat_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe. Not Sync, since `median` sorts the first few values in place
+unsafe impl Send for RunningStats {}
+
+/// Estimate of a single quantile using the P² algorithm of Jain and Chlamtac,
+/// accumulated one value at a time
+pub struct RunningQuantile {
+    p: f64,
+    workspace: *mut gsl_rstat_quantile_workspace,
+}
+
+impl RunningQuantile {
+    /// Estimates quantile `p` in `[0, 1]`
+    pub fn new(p: f64) -> Result<Self> {
+        unsafe {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(GSLError::Domain);
+            }
+
+            let workspace = gsl_rstat_quantile_alloc(p);
+            assert!(!workspace.is_null());
+            Ok(RunningQuantile { p, workspace })
+        }
+    }
+
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+
+    pub fn add(&mut self, x: f64) -> Result<()> {
+        unsafe { GSLError::from_raw(gsl_rstat_quantile_add(x, self.workspace)) }
+    }
+
+    pub fn reset(&mut self) {
+        unsafe {
+            gsl_rstat_quantile_reset(self.workspace);
+        }
+    }
+
+    pub fn get(&self) -> f64 {
+        unsafe { gsl_rstat_quantile_get(self.workspace) }
+    }
+}
+
+impl fmt::Debug for RunningQuantile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RunningQuantile")
+            .field("p", &self.p)
+            .field("value", &self.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for RunningQuantile {
+    fn drop(&mut self) {
+        unsafe {
+            gsl_rstat_quantile_free(self.workspace);
+        }
+    }
+}
+
+// GSL is thread safe. Not Sync, since `get` sorts the first few values in place
+unsafe impl Send for RunningQuantile {}
+
+#[test]
+fn test_running_stats() {
+    disable_error_handler();
+
+    let x = [1.0, 2.0, 3.0, 4.0, 10.0, 200.0, -10.0, 0.0];
+    let mut rstat = RunningStats::new();
+    for &x in &x {
+        rstat.add(x).unwrap();
+    }
+
+    assert_eq!(rstat.n(), x.len());
+    assert_eq!(rstat.min(), -10.0);
+    assert_eq!(rstat.max(), 200.0);
+    approx::assert_abs_diff_eq!(rstat.mean(), stats::mean(&x), epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(rstat.variance(), stats::variance(&x), epsilon = 1.0e-9);
+
+    rstat.reset();
+    assert_eq!(rstat.n(), 0);
+}
+
+#[test]
+fn test_running_quantile() {
+    disable_error_handler();
+
+    // Uniform on [0, 1)
+    let mut q = RunningQuantile::new(0.9).unwrap();
+    let mut rstat = RunningStats::new();
+    for i in 0..10000 {
+        let x = (i * 7919 % 10000) as f64 / 10000.0;
+        q.add(x).unwrap();
+        rstat.add(x).unwrap();
+    }
+
+    approx::assert_abs_diff_eq!(q.get(), 0.9, epsilon = 1.0e-2);
+    approx::assert_abs_diff_eq!(rstat.median(), 0.5, epsilon = 1.0e-2);
+    approx::assert_abs_diff_eq!(rstat.skew(), 0.0, epsilon = 1.0e-2);
+    approx::assert_abs_diff_eq!(rstat.kurtosis(), -1.2, epsilon = 1.0e-2);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Quantile out of range
+    RunningQuantile::new(1.5).unwrap_err();
+    RunningQuantile::new(-0.1).unwrap_err();
+}
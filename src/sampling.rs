@@ -0,0 +1,557 @@
+/*
+    sampling.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::rng::{Reproducibility, Rng};
+use crate::*;
+use std::fmt;
+
+/*
+
+    Adaptive rejection sampling uses the derivative-free envelope of Gilks (1992).
+    For a concave log density `h` tabulated at the abscissae `x_0 < ... < x_{k-1}`,
+    the chord through two neighbouring abscissae lies above `h` outside of them and below `h` in between.
+    On `[x_i, x_{i+1}]` the upper hull is the lower of the extended chords on both sides,
+    and the chord through `x_i` and `x_{i+1}` itself is the squeeze.
+    Every rejected point is added to the abscissae, so the envelope tightens as sampling proceeds.
+
+    The bootstrap resamples the data with replacement, and draws from a stream of a `Reproducibility` context,
+    so the replicates of a published analysis can be recomputed exactly.
+
+*/
+
+/// Maximum number of proposals for a single sample
+const MAX_PROPOSALS: usize = 10_000;
+
+/// Maximum number of abscissae of the adaptive envelope, after which it stops refining
+const MAX_ABSCISSAE: usize = 64;
+
+/// Rejection sampling of an unnormalized `pdf` using a proposal density `g`
+/// with `pdf(x) <= bound * g(x)` everywhere
+pub struct RejectionSampler<F, G, S> {
+    pdf: F,
+    proposal_pdf: G,
+    proposal_sample: S,
+    bound: f64,
+    proposed: u64,
+    accepted: u64,
+}
+
+impl<F, G, S> RejectionSampler<F, G, S>
+where
+    F: FnMut(f64) -> f64,
+    G: FnMut(f64) -> f64,
+    S: FnMut(&mut Rng) -> f64,
+{
+    pub fn new(pdf: F, proposal_pdf: G, proposal_sample: S, bound: f64) -> Result<Self> {
+        if !(bound > 0.0 && bound.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        Ok(RejectionSampler {
+            pdf,
+            proposal_pdf,
+            proposal_sample,
+            bound,
+            proposed: 0,
+            accepted: 0,
+        })
+    }
+
+    /// Fails with `GSLError::Domain` if the pdf exceeds the envelope at a proposed point,
+    /// and with `GSLError::MaxIteration` if no proposal is accepted in a reasonable amount of tries.
+    pub fn sample(&mut self, rng: &mut Rng) -> Result<f64> {
+        for _ in 0..MAX_PROPOSALS {
+            let x = (self.proposal_sample)(rng);
+            let envelope = self.bound * (self.proposal_pdf)(x);
+            let pdf = (self.pdf)(x);
+            self.proposed += 1;
+
+            if pdf > envelope || pdf.is_nan() {
+                return Err(GSLError::Domain);
+            }
+            if rng.uniform() * envelope < pdf {
+                self.accepted += 1;
+                return Ok(x);
+            }
+        }
+        Err(GSLError::MaxIteration)
+    }
+
+    /// Fraction of the proposals accepted so far, which estimates `∫ pdf / bound`
+    pub fn acceptance_rate(&self) -> f64 {
+        self.accepted as f64 / self.proposed as f64
+    }
+}
+
+impl<F, G, S> fmt::Debug for RejectionSampler<F, G, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RejectionSampler")
+            .field("bound", &self.bound)
+            .field("proposed", &self.proposed)
+            .field("accepted", &self.accepted)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Line through `(x, h)`
+#[derive(Copy, Clone, Debug)]
+struct Line {
+    x: f64,
+    h: f64,
+    slope: f64,
+}
+
+impl Line {
+    fn through(x0: f64, h0: f64, x1: f64, h1: f64) -> Self {
+        Line {
+            x: x0,
+            h: h0,
+            slope: (h1 - h0) / (x1 - x0),
+        }
+    }
+
+    fn eval(&self, x: f64) -> f64 {
+        if self.slope == 0.0 {
+            self.h
+        } else {
+            self.h + self.slope * (x - self.x)
+        }
+    }
+}
+
+/// Piece of the upper hull on `[a, b]`
+#[derive(Copy, Clone, Debug)]
+struct Piece {
+    a: f64,
+    b: f64,
+    line: Line,
+}
+
+impl Piece {
+    /// Logarithm of the integral of `exp(line)` over the piece
+    fn ln_integral(&self) -> f64 {
+        let width = self.b - self.a;
+        let slope = self.line.slope;
+        if width == 0.0 {
+            f64::NEG_INFINITY
+        } else if slope > 0.0 {
+            self.line.eval(self.b) + (-(-slope * width).exp_m1()).ln() - slope.ln()
+        } else if slope < 0.0 {
+            self.line.eval(self.a) + (-(slope * width).exp_m1()).ln() - (-slope).ln()
+        } else {
+            self.line.h + width.ln()
+        }
+    }
+
+    /// Inverse of the CDF of the normalized `exp(line)` on the piece
+    fn quantile(&self, u: f64) -> f64 {
+        let width = self.b - self.a;
+        let slope = self.line.slope;
+        let x = if slope > 0.0 {
+            self.b + (u + (1.0 - u) * (-slope * width).exp()).ln() / slope
+        } else if slope < 0.0 {
+            self.a + ((1.0 - u) + u * (slope * width).exp()).ln() / slope
+        } else {
+            self.a + u * width
+        };
+        x.clamp(self.a, self.b)
+    }
+}
+
+/// Adaptive rejection sampling of a log-concave density,
+/// given by its unnormalized logarithm on the support `(lower, upper)`
+pub struct AdaptiveRejectionSampler<F> {
+    log_pdf: F,
+    lower: f64,
+    upper: f64,
+    x: Vec<f64>,
+    h: Vec<f64>,
+    hull: Vec<Piece>,
+    cumulative: Vec<f64>,
+}
+
+impl<F: FnMut(f64) -> f64> AdaptiveRejectionSampler<F> {
+    /// The envelope starts from at least 3 strictly increasing initial points inside the support.
+    /// An unbounded support requires initial points on both sides of the mode.
+    /// Fails with `GSLError::Domain` if the log density is not concave at the initial points.
+    pub fn new(mut log_pdf: F, initial: &[f64], lower: f64, upper: f64) -> Result<Self> {
+        if initial.len() < 3 {
+            return Err(GSLError::Invalid);
+        }
+        if lower.is_nan() || upper.is_nan() || lower >= upper {
+            return Err(GSLError::Domain);
+        }
+        if initial.windows(2).any(|w| w[1] <= w[0])
+            || initial[0] <= lower
+            || initial[initial.len() - 1] >= upper
+        {
+            return Err(GSLError::Domain);
+        }
+
+        let h = initial.iter().map(|&x| log_pdf(x)).collect::<Vec<_>>();
+        if h.iter().any(|h| !h.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+
+        let mut sampler = AdaptiveRejectionSampler {
+            log_pdf,
+            lower,
+            upper,
+            x: initial.to_vec(),
+            h,
+            hull: vec![],
+            cumulative: vec![],
+        };
+        sampler.build_hull()?;
+        Ok(sampler)
+    }
+
+    /// Number of abscissae of the current envelope
+    pub fn n_abscissae(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Fails with `GSLError::Domain` if the log density turns out not to be concave
+    pub fn sample(&mut self, rng: &mut Rng) -> Result<f64> {
+        for _ in 0..MAX_PROPOSALS {
+            // Draw a piece of the hull, then a point within it
+            let total = self.cumulative[self.cumulative.len() - 1];
+            let target = rng.uniform() * total;
+            let i = self
+                .cumulative
+                .partition_point(|&c| c <= target)
+                .min(self.hull.len() - 1);
+            let piece = self.hull[i];
+            let x = piece.quantile(rng.uniform_pos());
+
+            let envelope = piece.line.eval(x);
+            let ln_u = rng.uniform_pos().ln();
+            if ln_u <= self.squeeze(x) - envelope {
+                return Ok(x);
+            }
+
+            let h = (self.log_pdf)(x);
+            if h > envelope + 1.0e-9 * envelope.abs().max(1.0) || h.is_nan() {
+                return Err(GSLError::Domain);
+            }
+            let accept = ln_u <= h - envelope;
+
+            if h.is_finite() && self.x.len() < MAX_ABSCISSAE {
+                let j = self.x.partition_point(|&xi| xi < x);
+                if self.x.get(j) != Some(&x) {
+                    self.x.insert(j, x);
+                    self.h.insert(j, h);
+                    self.build_hull()?;
+                }
+            }
+
+            if accept {
+                return Ok(x);
+            }
+        }
+        Err(GSLError::MaxIteration)
+    }
+
+    /// Chord between the abscissae around `x`, or minus infinity outside of them
+    fn squeeze(&self, x: f64) -> f64 {
+        let k = self.x.len();
+        if x < self.x[0] || x > self.x[k - 1] {
+            return f64::NEG_INFINITY;
+        }
+        let i = (self.x.partition_point(|&xi| xi <= x) - 1).min(k - 2);
+        self.chord(i).eval(x)
+    }
+
+    fn chord(&self, i: usize) -> Line {
+        Line::through(self.x[i], self.h[i], self.x[i + 1], self.h[i + 1])
+    }
+
+    fn build_hull(&mut self) -> Result<()> {
+        let k = self.x.len();
+        let chords = (0..k - 1).map(|i| self.chord(i)).collect::<Vec<_>>();
+
+        // The chord slopes of a concave function are nonincreasing
+        if chords.windows(2).any(|c| {
+            let tolerance = 1.0e-9 * c[0].slope.abs().max(c[1].slope.abs()).max(1.0);
+            c[1].slope > c[0].slope + tolerance
+        }) {
+            return Err(GSLError::Domain);
+        }
+
+        // The tails need to decay, which also requires the initial points to enclose the mode
+        if (self.lower == f64::NEG_INFINITY && chords[0].slope <= 0.0)
+            || (self.upper == f64::INFINITY && chords[k - 2].slope >= 0.0)
+        {
+            return Err(GSLError::Domain);
+        }
+
+        let mut hull = Vec::with_capacity(2 * k);
+        hull.push(Piece {
+            a: self.lower,
+            b: self.x[0],
+            line: chords[0],
+        });
+        for i in 0..k - 1 {
+            let (a, b) = (self.x[i], self.x[i + 1]);
+            match (i.checked_sub(1).map(|j| chords[j]), chords.get(i + 1)) {
+                (Some(left), Some(&right)) => push_min(&mut hull, a, b, left, right),
+                (Some(left), None) => hull.push(Piece { a, b, line: left }),
+                (None, Some(&right)) => hull.push(Piece { a, b, line: right }),
+                (None, None) => unreachable!("at least 3 abscissae"),
+            }
+        }
+        hull.push(Piece {
+            a: self.x[k - 1],
+            b: self.upper,
+            line: chords[k - 2],
+        });
+
+        // Cumulative integrals relative to the largest piece
+        let ln_integrals = hull.iter().map(|p| p.ln_integral()).collect::<Vec<_>>();
+        let max = ln_integrals
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if !max.is_finite() {
+            return Err(GSLError::Domain);
+        }
+        let mut sum = 0.0;
+        self.cumulative = ln_integrals
+            .iter()
+            .map(|l| {
+                sum += (l - max).exp();
+                sum
+            })
+            .collect();
+        self.hull = hull;
+
+        Ok(())
+    }
+}
+
+impl<F> fmt::Debug for AdaptiveRejectionSampler<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdaptiveRejectionSampler")
+            .field("lower", &self.lower)
+            .field("upper", &self.upper)
+            .field("abscissae", &self.x)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Pushes the pieces of `min(left, right)` on `[a, b]`
+fn push_min(hull: &mut Vec<Piece>, a: f64, b: f64, left: Line, right: Line) {
+    let lower = |x: f64| {
+        if left.eval(x) <= right.eval(x) {
+            left
+        } else {
+            right
+        }
+    };
+
+    let z = if left.slope == right.slope {
+        f64::NAN
+    } else {
+        (right.h - left.h + left.slope * left.x - right.slope * right.x)
+            / (left.slope - right.slope)
+    };
+    if z > a && z < b {
+        hull.push(Piece {
+            a,
+            b: z,
+            line: lower(0.5 * (a + z)),
+        });
+        hull.push(Piece {
+            a: z,
+            b,
+            line: lower(0.5 * (z + b)),
+        });
+    } else {
+        hull.push(Piece {
+            a,
+            b,
+            line: lower(0.5 * (a + b)),
+        });
+    }
+}
+
+/// `replicates` values of `statistic` on resamples of `x`, drawn with replacement from the stream `stream` of `context`
+pub fn bootstrap<F: FnMut(&[f64]) -> Result<f64>>(
+    x: &[f64],
+    replicates: usize,
+    context: &mut Reproducibility,
+    stream: &str,
+    mut statistic: F,
+) -> Result<Box<[f64]>> {
+    if x.is_empty() || replicates == 0 {
+        return Err(GSLError::Invalid);
+    }
+
+    let mut rng = context.rng(stream);
+    let mut resample = vec![0.0; x.len()];
+    (0..replicates)
+        .map(|_| {
+            for y in resample.iter_mut() {
+                *y = x[rng.uniform_int(x.len() as u64)? as usize];
+            }
+            statistic(&resample)
+        })
+        .collect()
+}
+
+#[test]
+fn test_rejection() {
+    use std::f64::consts::PI;
+
+    disable_error_handler();
+
+    // Standard normal from a Cauchy proposal
+    let mut sampler = RejectionSampler::new(
+        |x: f64| (-0.5 * x * x).exp(),
+        |x: f64| 1.0 / (PI * (1.0 + x * x)),
+        |rng: &mut Rng| (PI * (rng.uniform() - 0.5)).tan(),
+        2.0 * PI * (-0.5f64).exp(),
+    )
+    .unwrap();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let n = 50_000;
+    let samples = (0..n)
+        .map(|_| sampler.sample(&mut rng).unwrap())
+        .collect::<Vec<_>>();
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    let variance = samples.iter().map(|x| x * x).sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.02);
+    approx::assert_abs_diff_eq!(variance, 1.0, epsilon = 0.03);
+    approx::assert_abs_diff_eq!(
+        sampler.acceptance_rate(),
+        (2.0 * PI).sqrt() / (2.0 * PI * (-0.5f64).exp()),
+        epsilon = 0.01
+    );
+}
+
+#[test]
+fn test_adaptive_rejection() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 2);
+    let n = 50_000;
+
+    // Standard normal
+    let mut sampler = AdaptiveRejectionSampler::new(
+        |x: f64| -0.5 * x * x,
+        &[-1.0, 0.5, 2.0],
+        f64::NEG_INFINITY,
+        f64::INFINITY,
+    )
+    .unwrap();
+    let samples = (0..n)
+        .map(|_| sampler.sample(&mut rng).unwrap())
+        .collect::<Vec<_>>();
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    let variance = samples.iter().map(|x| x * x).sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.02);
+    approx::assert_abs_diff_eq!(variance, 1.0, epsilon = 0.03);
+    assert!(sampler.n_abscissae() > 3);
+    assert!(sampler.n_abscissae() <= MAX_ABSCISSAE);
+
+    // Gamma(3, 1) on the half line, with mean and variance 3
+    let mut sampler = AdaptiveRejectionSampler::new(
+        |x: f64| 2.0 * x.ln() - x,
+        &[0.5, 2.0, 5.0],
+        0.0,
+        f64::INFINITY,
+    )
+    .unwrap();
+    let samples = (0..n)
+        .map(|_| sampler.sample(&mut rng).unwrap())
+        .collect::<Vec<_>>();
+    assert!(samples.iter().all(|&x| x > 0.0));
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    let variance = samples.iter().map(|x| (x - 3.0).powi(2)).sum::<f64>() / n as f64;
+    approx::assert_abs_diff_eq!(mean, 3.0, epsilon = 0.05);
+    approx::assert_abs_diff_eq!(variance, 3.0, epsilon = 0.15);
+
+    // Uniform on a bounded support
+    let mut sampler = AdaptiveRejectionSampler::new(|_| 0.0, &[1.0, 2.0, 3.0], 0.0, 4.0).unwrap();
+    let mean = (0..n)
+        .map(|_| sampler.sample(&mut rng).unwrap())
+        .sum::<f64>()
+        / n as f64;
+    approx::assert_abs_diff_eq!(mean, 2.0, epsilon = 0.03);
+}
+
+#[test]
+fn test_bootstrap() {
+    disable_error_handler();
+
+    // Standard error of the mean of 0, 1, ..., 99
+    let x = (0..100).map(|x| x as f64).collect::<Vec<_>>();
+    let mut context = Reproducibility::new(7);
+    let means = bootstrap(&x, 2000, &mut context, "mean", |x| Ok(stats::mean(x))).unwrap();
+    approx::assert_abs_diff_eq!(stats::mean(&means), 49.5, epsilon = 0.3);
+    approx::assert_abs_diff_eq!(stats::sd(&means), 2.87, epsilon = 0.15);
+
+    // Bit for bit again from the same seed
+    let again = bootstrap(&x, 2000, &mut Reproducibility::new(7), "mean", |x| {
+        Ok(stats::mean(x))
+    })
+    .unwrap();
+    assert_eq!(again, means);
+    assert_eq!(context.streams()[0].0, "mean");
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 3);
+
+    // Nonpositive bound
+    RejectionSampler::new(|_| 1.0, |_| 1.0, |rng: &mut Rng| rng.uniform(), 0.0).unwrap_err();
+
+    // Envelope below the pdf
+    let mut sampler =
+        RejectionSampler::new(|_| 2.0, |_| 1.0, |rng: &mut Rng| rng.uniform(), 1.0).unwrap();
+    sampler.sample(&mut rng).unwrap_err();
+
+    let normal = |x: f64| -0.5 * x * x;
+    let (lower, upper) = (f64::NEG_INFINITY, f64::INFINITY);
+
+    // Too few initial points
+    AdaptiveRejectionSampler::new(normal, &[-1.0, 1.0], lower, upper).unwrap_err();
+
+    // Initial points not increasing or outside the support
+    AdaptiveRejectionSampler::new(normal, &[-1.0, 1.0, 0.0], lower, upper).unwrap_err();
+    AdaptiveRejectionSampler::new(normal, &[-1.0, 0.0, 1.0], 0.0, upper).unwrap_err();
+
+    // Initial points on one side of the mode of an unbounded density
+    AdaptiveRejectionSampler::new(normal, &[1.0, 2.0, 3.0], lower, upper).unwrap_err();
+
+    // Not log-concave
+    AdaptiveRejectionSampler::new(|x: f64| x * x, &[1.0, 2.0, 3.0], 0.0, 4.0).unwrap_err();
+    AdaptiveRejectionSampler::new(|x: f64| x.abs().sqrt(), &[-1.0, 0.5, 1.0], -2.0, 2.0)
+        .unwrap_err();
+
+    // Log density not finite at an initial point
+    AdaptiveRejectionSampler::new(|x: f64| x.ln(), &[-1.0, 1.0, 2.0], -2.0, 3.0).unwrap_err();
+
+    // Nothing to resample
+    let mut context = Reproducibility::new(0);
+    bootstrap(&[], 10, &mut context, "empty", |x| Ok(x[0])).unwrap_err();
+    bootstrap(&[1.0], 0, &mut context, "none", |x| Ok(x[0])).unwrap_err();
+}
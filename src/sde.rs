@@ -0,0 +1,630 @@
+/*
+    sde.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::rng::Rng;
+use crate::stats;
+use crate::*;
+
+/*
+
+    Stochastic differential equations `dX = a(t, X) dt + b(t, X) dW` have diagonal noise:
+    every component is driven by its own independent Wiener process, `dX_i = a_i dt + b_i dW_i`.
+    The drift `a` and the diffusion `b` are written into their last argument, like the right hand side in `ode`.
+
+    Euler-Maruyama converges with strong order 1/2. Milstein adds `b_i ∂b_i/∂x_i (ΔW_i^2 - h) / 2`
+    and converges with strong order 1. The derivative is a central difference,
+    which costs two evaluations of `b` per component and step.
+
+    The adaptive integrators compare a step with two half steps driven by the same Brownian path,
+    drawing the increment at the midpoint from the Brownian bridge. A rejected step is retried on its first half,
+    and the second half of its increment is kept for the step after, so the rejections don't bias the Brownian path.
+    Both the midpoint and the end of an accepted step are stored.
+
+    Paths store the Wiener processes next to the states, to compare with exact solutions.
+
+*/
+
+/// Relative step of the central differences for `∂b_i/∂x_i`, about the cube root of the machine precision
+const DIFF_STEP: f64 = 6.0e-6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    EulerMaruyama,
+    Milstein,
+}
+
+impl Scheme {
+    /// Strong order of convergence
+    pub fn order(self) -> f64 {
+        match self {
+            Scheme::EulerMaruyama => 0.5,
+            Scheme::Milstein => 1.0,
+        }
+    }
+}
+
+/// Sample path of the states and the driving Wiener processes
+#[derive(Clone, Debug)]
+pub struct SdePath {
+    dim: usize,
+    t: Vec<f64>,
+    x: Vec<f64>,
+    w: Vec<f64>,
+}
+
+impl SdePath {
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Times of the steps, starting at `t0` and ending at `t1`
+    pub fn t(&self) -> &[f64] {
+        &self.t
+    }
+
+    /// State at step `i`
+    pub fn x(&self, i: usize) -> &[f64] {
+        &self.x[i * self.dim..(i + 1) * self.dim]
+    }
+
+    /// Wiener processes at step `i`, which start at zero
+    pub fn w(&self, i: usize) -> &[f64] {
+        &self.w[i * self.dim..(i + 1) * self.dim]
+    }
+
+    /// State at the end of the integration
+    pub fn final_state(&self) -> &[f64] {
+        self.x(self.t.len() - 1)
+    }
+
+    fn new(dim: usize) -> Self {
+        SdePath {
+            dim,
+            t: Vec::new(),
+            x: Vec::new(),
+            w: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, t: f64, x: &[f64], w: &[f64]) {
+        self.t.push(t);
+        self.x.extend_from_slice(x);
+        self.w.extend_from_slice(w);
+    }
+}
+
+/// Independent sample paths on the same fixed steps
+#[derive(Clone, Debug)]
+pub struct Ensemble {
+    paths: Vec<SdePath>,
+}
+
+impl Ensemble {
+    pub fn paths(&self) -> &[SdePath] {
+        &self.paths
+    }
+
+    /// Times of the steps, shared by all paths
+    pub fn t(&self) -> &[f64] {
+        self.paths[0].t()
+    }
+
+    /// Mean of component `component` over the paths at every step
+    pub fn mean(&self, component: usize) -> Vec<f64> {
+        (0..self.t().len())
+            .map(|i| stats::mean(&self.values(component, i)))
+            .collect()
+    }
+
+    /// Sample variance of component `component` over the paths at every step
+    pub fn variance(&self, component: usize) -> Vec<f64> {
+        (0..self.t().len())
+            .map(|i| stats::variance(&self.values(component, i)))
+            .collect()
+    }
+
+    /// Quantile `p` in `[0, 1]` of component `component` over the paths at every step
+    pub fn quantile(&self, component: usize, p: f64) -> Result<Vec<f64>> {
+        (0..self.t().len())
+            .map(|i| {
+                let mut values = self.values(component, i);
+                values.sort_by(f64::total_cmp);
+                stats::quantile_sorted(&values, p)
+            })
+            .collect()
+    }
+
+    fn values(&self, component: usize, i: usize) -> Vec<f64> {
+        self.paths.iter().map(|path| path.x(i)[component]).collect()
+    }
+}
+
+/// Integrates `dX = a(t, X) dt + b(t, X) dW` from `X(t0) = x0` up to `t1` in `n_steps` steps with Milstein,
+/// see `solve_ext`
+pub fn solve<A, B>(
+    drift: A,
+    diffusion: B,
+    x0: &[f64],
+    t0: f64,
+    t1: f64,
+    n_steps: usize,
+    rng: &mut Rng,
+) -> Result<SdePath>
+where
+    A: FnMut(f64, &[f64], &mut [f64]),
+    B: FnMut(f64, &[f64], &mut [f64]),
+{
+    solve_ext(Scheme::Milstein, drift, diffusion, x0, t0, t1, n_steps, rng)
+}
+
+/// Integrates `dX = a(t, X) dt + b(t, X) dW` from `X(t0) = x0` up to `t1 > t0` in `n_steps` equal steps.
+/// Fails with `GSLError::BadFunction` if the state doesn't stay finite.
+pub fn solve_ext<A, B>(
+    scheme: Scheme,
+    drift: A,
+    diffusion: B,
+    x0: &[f64],
+    t0: f64,
+    t1: f64,
+    n_steps: usize,
+    rng: &mut Rng,
+) -> Result<SdePath>
+where
+    A: FnMut(f64, &[f64], &mut [f64]),
+    B: FnMut(f64, &[f64], &mut [f64]),
+{
+    check_params(x0, t0, t1)?;
+    if n_steps == 0 {
+        return Err(GSLError::Invalid);
+    }
+
+    let dim = x0.len();
+    let mut integrator = Integrator::new(scheme, drift, diffusion, dim);
+    let h = (t1 - t0) / n_steps as f64;
+
+    let mut path = SdePath::new(dim);
+    let mut x = x0.to_vec();
+    let mut next = vec![0.0; dim];
+    let mut w = vec![0.0; dim];
+    let mut dw = vec![0.0; dim];
+    path.push(t0, &x, &w);
+
+    for k in 1..=n_steps {
+        for i in 0..dim {
+            dw[i] = rng.gaussian(h.sqrt());
+            w[i] += dw[i];
+        }
+        integrator.step(t0 + (k - 1) as f64 * h, &x, h, &dw, &mut next)?;
+        std::mem::swap(&mut x, &mut next);
+
+        let t = if k == n_steps { t1 } else { t0 + k as f64 * h };
+        path.push(t, &x, &w);
+    }
+
+    Ok(path)
+}
+
+/// Integrates `dX = a(t, X) dt + b(t, X) dW` from `X(t0) = x0` up to `t1` with adaptive Milstein steps,
+/// see `solve_adaptive_ext`
+pub fn solve_adaptive<A, B>(
+    drift: A,
+    diffusion: B,
+    x0: &[f64],
+    t0: f64,
+    t1: f64,
+    tol: f64,
+    rng: &mut Rng,
+) -> Result<SdePath>
+where
+    A: FnMut(f64, &[f64], &mut [f64]),
+    B: FnMut(f64, &[f64], &mut [f64]),
+{
+    let h0 = (t1 - t0) / 100.0;
+    solve_adaptive_ext(
+        Scheme::Milstein,
+        drift,
+        diffusion,
+        x0,
+        t0,
+        t1,
+        h0,
+        tol,
+        1_000_000,
+        rng,
+    )
+}
+
+/// Integrates `dX = a(t, X) dt + b(t, X) dW` from `X(t0) = x0` up to `t1 > t0`, starting with step size `h0`.
+/// A step is accepted if the difference with two half steps is below `tol (1 + |x|)` in every component,
+/// and the step size follows the strong order of the scheme.
+/// Fails with `GSLError::MaxIteration` after `max_steps` attempted steps.
+pub fn solve_adaptive_ext<A, B>(
+    scheme: Scheme,
+    drift: A,
+    diffusion: B,
+    x0: &[f64],
+    t0: f64,
+    t1: f64,
+    h0: f64,
+    tol: f64,
+    max_steps: usize,
+    rng: &mut Rng,
+) -> Result<SdePath>
+where
+    A: FnMut(f64, &[f64], &mut [f64]),
+    B: FnMut(f64, &[f64], &mut [f64]),
+{
+    check_params(x0, t0, t1)?;
+    if !(h0 > 0.0 && tol > 0.0) {
+        return Err(GSLError::Domain);
+    }
+
+    let dim = x0.len();
+    let mut integrator = Integrator::new(scheme, drift, diffusion, dim);
+
+    let mut path = SdePath::new(dim);
+    let mut x = x0.to_vec();
+    let mut w = vec![0.0; dim];
+    path.push(t0, &x, &w);
+
+    let mut full = vec![0.0; dim];
+    let mut mid = vec![0.0; dim];
+    let mut half = vec![0.0; dim];
+    let mut dw_first = vec![0.0; dim];
+    let mut dw_second = vec![0.0; dim];
+
+    // Ends and increments of the halves of rejected steps, the next one last
+    let mut pending: Vec<(f64, Vec<f64>)> = Vec::new();
+    let mut t = t0;
+    let mut h_next = h0;
+    let mut steps = 0;
+    while t < t1 {
+        if steps == max_steps {
+            return Err(GSLError::MaxIteration);
+        }
+        steps += 1;
+
+        let (end, dw) = pending.pop().unwrap_or_else(|| {
+            let end = if h_next >= t1 - t { t1 } else { t + h_next };
+            let dw = (0..dim).map(|_| rng.gaussian((end - t).sqrt())).collect();
+            (end, dw)
+        });
+        let h = end - t;
+        let midpoint = t + 0.5 * h;
+
+        // Brownian bridge at the midpoint
+        for i in 0..dim {
+            dw_first[i] = 0.5 * dw[i] + rng.gaussian((0.25 * h).sqrt());
+            dw_second[i] = dw[i] - dw_first[i];
+        }
+
+        integrator.step(t, &x, h, &dw, &mut full)?;
+        if scheme == Scheme::EulerMaruyama {
+            integrator.ito_term(t, &x);
+        }
+        integrator.step(t, &x, 0.5 * h, &dw_first, &mut mid)?;
+        integrator.step(midpoint, &mid, 0.5 * h, &dw_second, &mut half)?;
+
+        // The half steps of Euler-Maruyama differ from the full step by `b b' ΔW_1 ΔW_2`,
+        // the realized part of the term that Milstein adds. Accepting only the steps where it happens
+        // to be small biases the solution, so it's replaced by the root mean square `|b b'| h / √2`.
+        let error = (0..dim)
+            .map(|i| {
+                let difference = match scheme {
+                    Scheme::EulerMaruyama => {
+                        let bdb = integrator.bdb[i];
+                        (full[i] - half[i] + bdb * dw_first[i] * dw_second[i]).abs()
+                            + bdb.abs() * h * std::f64::consts::FRAC_1_SQRT_2
+                    }
+                    Scheme::Milstein => (full[i] - half[i]).abs(),
+                };
+                difference / (tol * (1.0 + half[i].abs()))
+            })
+            .fold(0.0f64, f64::max);
+        if error <= 1.0 {
+            for i in 0..dim {
+                w[i] += dw_first[i];
+            }
+            path.push(midpoint, &mid, &w);
+            for i in 0..dim {
+                w[i] += dw_second[i];
+            }
+            path.push(end, &half, &w);
+
+            std::mem::swap(&mut x, &mut half);
+            t = end;
+
+            // The local error of a scheme of strong order p scales as h^(p + 1/2)
+            let factor = if error == 0.0 {
+                2.0
+            } else {
+                0.9 * error.powf(-1.0 / (scheme.order() + 0.5))
+            };
+            h_next = h * factor.clamp(0.2, 2.0);
+        } else {
+            pending.push((end, dw_second.clone()));
+            pending.push((midpoint, dw_first.clone()));
+        }
+    }
+
+    Ok(path)
+}
+
+/// `n_paths` independent paths of `dX = a(t, X) dt + b(t, X) dW` from `X(t0) = x0` up to `t1`
+/// in `n_steps` equal steps, see `solve_ext`
+pub fn ensemble<A, B>(
+    scheme: Scheme,
+    mut drift: A,
+    mut diffusion: B,
+    x0: &[f64],
+    t0: f64,
+    t1: f64,
+    n_steps: usize,
+    n_paths: usize,
+    rng: &mut Rng,
+) -> Result<Ensemble>
+where
+    A: FnMut(f64, &[f64], &mut [f64]),
+    B: FnMut(f64, &[f64], &mut [f64]),
+{
+    if n_paths == 0 {
+        return Err(GSLError::Invalid);
+    }
+
+    let paths = (0..n_paths)
+        .map(|_| solve_ext(scheme, &mut drift, &mut diffusion, x0, t0, t1, n_steps, rng))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Ensemble { paths })
+}
+
+fn check_params(x0: &[f64], t0: f64, t1: f64) -> Result<()> {
+    if x0.is_empty() {
+        return Err(GSLError::Invalid);
+    }
+    if !(t0.is_finite() && t1.is_finite() && t1 > t0) {
+        return Err(GSLError::Domain);
+    }
+    Ok(())
+}
+
+struct Integrator<A, B> {
+    scheme: Scheme,
+    drift: A,
+    diffusion: B,
+    a: Vec<f64>,
+    b: Vec<f64>,
+    shifted: Vec<f64>,
+    b_plus: Vec<f64>,
+    b_minus: Vec<f64>,
+    bdb: Vec<f64>,
+}
+
+impl<A, B> Integrator<A, B>
+where
+    A: FnMut(f64, &[f64], &mut [f64]),
+    B: FnMut(f64, &[f64], &mut [f64]),
+{
+    fn new(scheme: Scheme, drift: A, diffusion: B, dim: usize) -> Self {
+        Integrator {
+            scheme,
+            drift,
+            diffusion,
+            a: vec![0.0; dim],
+            b: vec![0.0; dim],
+            shifted: vec![0.0; dim],
+            b_plus: vec![0.0; dim],
+            b_minus: vec![0.0; dim],
+            bdb: vec![0.0; dim],
+        }
+    }
+
+    /// Step of size `h` from `x` at `t` with the Wiener increments `dw`
+    fn step(&mut self, t: f64, x: &[f64], h: f64, dw: &[f64], out: &mut [f64]) -> Result<()> {
+        (self.drift)(t, x, &mut self.a);
+        (self.diffusion)(t, x, &mut self.b);
+        for i in 0..x.len() {
+            out[i] = x[i] + self.a[i] * h + self.b[i] * dw[i];
+        }
+
+        if self.scheme == Scheme::Milstein {
+            self.ito_term(t, x);
+            for i in 0..x.len() {
+                out[i] += 0.5 * self.bdb[i] * (dw[i] * dw[i] - h);
+            }
+        }
+
+        if out.iter().any(|x| !x.is_finite()) {
+            return Err(GSLError::BadFunction);
+        }
+        Ok(())
+    }
+
+    /// `b_i ∂b_i/∂x_i` at `x` into `bdb`, with `b` at `x` already in `b`
+    fn ito_term(&mut self, t: f64, x: &[f64]) {
+        self.shifted.copy_from_slice(x);
+        for (i, &xi) in x.iter().enumerate() {
+            let delta = DIFF_STEP * (1.0 + xi.abs());
+            self.shifted[i] = xi + delta;
+            (self.diffusion)(t, &self.shifted, &mut self.b_plus);
+            self.shifted[i] = xi - delta;
+            (self.diffusion)(t, &self.shifted, &mut self.b_minus);
+            self.shifted[i] = xi;
+
+            self.bdb[i] = self.b[i] * (self.b_plus[i] - self.b_minus[i]) / (2.0 * delta);
+        }
+    }
+}
+
+#[cfg(test)]
+fn gbm_exact(path: &SdePath, mu: f64, sigma: f64) -> f64 {
+    let n = path.t().len() - 1;
+    let t = path.t()[n] - path.t()[0];
+    path.x(0)[0] * ((mu - 0.5 * sigma * sigma) * t + sigma * path.w(n)[0]).exp()
+}
+
+#[test]
+fn test_solve() {
+    disable_error_handler();
+
+    // Geometric Brownian motion has an exact solution in terms of the Wiener process
+    let (mu, sigma) = (0.5, 0.8);
+    let drift = |_: f64, x: &[f64], a: &mut [f64]| a[0] = mu * x[0];
+    let diffusion = |_: f64, x: &[f64], b: &mut [f64]| b[0] = sigma * x[0];
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 1);
+    let mut errors = [0.0; 2];
+    for _ in 0..200 {
+        for (k, scheme) in [Scheme::EulerMaruyama, Scheme::Milstein]
+            .into_iter()
+            .enumerate()
+        {
+            let path =
+                solve_ext(scheme, drift, diffusion, &[1.0], 0.0, 1.0, 256, &mut rng).unwrap();
+            assert_eq!(path.t().len(), 257);
+            assert_eq!(path.t()[256], 1.0);
+            errors[k] += (path.final_state()[0] - gbm_exact(&path, mu, sigma)).abs() / 200.0;
+        }
+    }
+    assert!(errors[1] < 0.2 * errors[0]);
+    assert!(errors[1] < 5.0e-3);
+
+    // Independent noise in every component
+    let drift = |_: f64, _: &[f64], a: &mut [f64]| a.fill(0.0);
+    let diffusion = |_: f64, _: &[f64], b: &mut [f64]| b.copy_from_slice(&[1.0, 2.0]);
+    let path = solve(drift, diffusion, &[0.0, 1.0], 0.0, 1.0, 10, &mut rng).unwrap();
+    let x = path.final_state();
+    let w = path.w(10);
+    approx::assert_abs_diff_eq!(x[0], w[0], epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(x[1], 1.0 + 2.0 * w[1], epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_adaptive() {
+    disable_error_handler();
+
+    let (mu, sigma) = (0.5, 0.8);
+    let drift = |_: f64, x: &[f64], a: &mut [f64]| a[0] = mu * x[0];
+    let diffusion = |_: f64, x: &[f64], b: &mut [f64]| b[0] = sigma * x[0];
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 2);
+    for (scheme, tolerance) in [(Scheme::EulerMaruyama, 0.03), (Scheme::Milstein, 0.01)] {
+        let mut error = 0.0;
+        for _ in 0..50 {
+            let path = solve_adaptive_ext(
+                scheme,
+                drift,
+                diffusion,
+                &[1.0],
+                0.0,
+                1.0,
+                0.01,
+                1.0e-4,
+                1_000_000,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(*path.t().last().unwrap(), 1.0);
+            assert!(path.t().windows(2).all(|t| t[0] < t[1]));
+            error += (path.final_state()[0] - gbm_exact(&path, mu, sigma)).abs() / 50.0;
+        }
+        assert!(error < tolerance);
+    }
+}
+
+#[test]
+fn test_ensemble() {
+    disable_error_handler();
+
+    // Ornstein-Uhlenbeck process
+    let (theta, sigma) = (2.0, 0.5);
+    let drift = |_: f64, x: &[f64], a: &mut [f64]| a[0] = -theta * x[0];
+    let diffusion = |_: f64, _: &[f64], b: &mut [f64]| b[0] = sigma;
+
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 3);
+    let ensemble = ensemble(
+        Scheme::EulerMaruyama,
+        drift,
+        diffusion,
+        &[1.0],
+        0.0,
+        1.0,
+        200,
+        4000,
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(ensemble.paths().len(), 4000);
+    assert_eq!(ensemble.t().len(), 201);
+
+    let variance = sigma * sigma / (2.0 * theta) * (1.0 - (-2.0 * theta).exp());
+    let mean = ensemble.mean(0);
+    approx::assert_abs_diff_eq!(mean[0], 1.0);
+    approx::assert_abs_diff_eq!(mean[200], (-theta).exp(), epsilon = 0.01);
+    approx::assert_abs_diff_eq!(ensemble.variance(0)[200], variance, epsilon = 0.005);
+    approx::assert_abs_diff_eq!(
+        ensemble.quantile(0, 0.5).unwrap()[200],
+        (-theta).exp(),
+        epsilon = 0.015
+    );
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let drift = |_: f64, x: &[f64], a: &mut [f64]| a[0] = x[0];
+    let diffusion = |_: f64, x: &[f64], b: &mut [f64]| b[0] = x[0];
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 4);
+
+    // No steps, no state or an empty interval
+    solve(drift, diffusion, &[1.0], 0.0, 1.0, 0, &mut rng).unwrap_err();
+    solve(drift, diffusion, &[], 0.0, 1.0, 10, &mut rng).unwrap_err();
+    solve(drift, diffusion, &[1.0], 1.0, 1.0, 10, &mut rng).unwrap_err();
+    solve_adaptive(drift, diffusion, &[1.0], 0.0, 1.0, 0.0, &mut rng).unwrap_err();
+    ensemble(
+        Scheme::Milstein,
+        drift,
+        diffusion,
+        &[1.0],
+        0.0,
+        1.0,
+        10,
+        0,
+        &mut rng,
+    )
+    .unwrap_err();
+
+    // Too few steps
+    solve_adaptive_ext(
+        Scheme::Milstein,
+        drift,
+        diffusion,
+        &[1.0],
+        0.0,
+        1.0,
+        0.1,
+        1.0e-12,
+        10,
+        &mut rng,
+    )
+    .unwrap_err();
+
+    // Blows up
+    let drift = |_: f64, x: &[f64], a: &mut [f64]| a[0] = x[0] * x[0];
+    solve(drift, diffusion, &[1.0], 0.0, 20.0, 20, &mut rng).unwrap_err();
+}
@@ -0,0 +1,395 @@
+/*
+    serialize.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bspline::{BSpline, BSplineBasis};
+use crate::cheb::ChebSeries;
+use crate::histogram::Histogram;
+use crate::ode::OdeSolution;
+use crate::*;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::result::Result;
+
+/*
+
+    Serialization with serde, behind the `serde` feature.
+
+    Plain data types, like `ValWithError` and the fit results, derive `Serialize` and `Deserialize`.
+    Types wrapping a GSL object are serialized as the data that defines them, and are rebuilt from it:
+
+        Histogram       { ranges, bins }
+        ChebSeries      { a, b, coefficients }
+        BSplineBasis    { order, breakpoints }
+        BSpline         { fit, basis }
+        Vector          [x_0, x_1, ...]
+        Matrix          { m, n, data } with the data in row major order
+        OdeSolution     { dim, t, y, dydt } with the states and derivatives of the steps after each other
+
+    Deserializing data that doesn't define a valid object, like decreasing histogram ranges,
+    fails with a custom error of the deserializer.
+
+    Fixed size arrays `[f64; P]` and `[[f64; P]; P]`, as in the nonlinear fit results, are sequences.
+    Serde only supports them up to 32 elements, so `array` and `array2` handle them for any `P`.
+
+*/
+
+/// Serializes `[f64; P]` as a sequence, for `#[serde(with = "crate::serialize::array")]`
+pub(crate) mod array {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const P: usize>(x: &[f64; P], s: S) -> Result<S::Ok, S::Error> {
+        x.as_slice().serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const P: usize>(
+        d: D,
+    ) -> Result<[f64; P], D::Error> {
+        let x = Vec::<f64>::deserialize(d)?;
+        x.try_into()
+            .map_err(|x: Vec<f64>| de::Error::invalid_length(x.len(), &"P elements"))
+    }
+}
+
+/// Serializes `[[f64; P]; P]` as a sequence of rows, for `#[serde(with = "crate::serialize::array2")]`
+pub(crate) mod array2 {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const P: usize>(
+        x: &[[f64; P]; P],
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        s.collect_seq(x.iter().map(|row| row.as_slice()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const P: usize>(
+        d: D,
+    ) -> Result<[[f64; P]; P], D::Error> {
+        let rows = Vec::<Vec<f64>>::deserialize(d)?;
+        let len = rows.len();
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                row.try_into()
+                    .map_err(|row: Vec<f64>| de::Error::invalid_length(row.len(), &"P columns"))
+            })
+            .collect::<Result<Vec<[f64; P]>, D::Error>>()?;
+        rows.try_into()
+            .map_err(|_| de::Error::invalid_length(len, &"P rows"))
+    }
+}
+
+fn invalid<E: de::Error>(what: &str, e: GSLError) -> E {
+    E::custom(format_args!("invalid {}: {}", what, e))
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistogramData {
+    ranges: Box<[f64]>,
+    bins: Box<[f64]>,
+}
+
+impl Serialize for Histogram {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        HistogramData {
+            ranges: self.ranges(),
+            bins: self.bins(),
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Histogram {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let data = HistogramData::deserialize(d)?;
+        let mut histogram =
+            Histogram::with_ranges(&data.ranges).map_err(|e| invalid("histogram", e))?;
+        if data.bins.len() != histogram.n() {
+            return Err(de::Error::invalid_length(
+                data.bins.len(),
+                &"a bin per range",
+            ));
+        }
+        for (&lower, &bin) in data.ranges.iter().zip(data.bins.iter()) {
+            // The lower end of a bin lies inside it
+            histogram
+                .accumulate(lower, bin)
+                .map_err(|e| invalid("histogram", e))?;
+        }
+        Ok(histogram)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChebSeriesData {
+    a: f64,
+    b: f64,
+    coefficients: Box<[f64]>,
+}
+
+impl Serialize for ChebSeries {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let (a, b) = self.domain();
+        ChebSeriesData {
+            a,
+            b,
+            coefficients: self.coefficients().into(),
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChebSeries {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let data = ChebSeriesData::deserialize(d)?;
+        ChebSeries::from_coefficients(data.a, data.b, &data.coefficients)
+            .map_err(|e| invalid("Chebyshev series", e))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BSplineBasisData {
+    order: usize,
+    breakpoints: Box<[f64]>,
+}
+
+impl Serialize for BSplineBasis {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        BSplineBasisData {
+            order: self.order(),
+            breakpoints: self.breakpoints(),
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for BSplineBasis {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let data = BSplineBasisData::deserialize(d)?;
+        BSplineBasis::with_breakpoints(data.order, &data.breakpoints)
+            .map_err(|e| invalid("B-spline basis", e))
+    }
+}
+
+#[derive(Serialize)]
+struct BSplineRef<'a> {
+    fit: &'a linear_fit::FitResult,
+    basis: &'a BSplineBasis,
+}
+
+#[derive(Deserialize)]
+struct BSplineData {
+    fit: linear_fit::FitResult,
+    basis: BSplineBasis,
+}
+
+impl Serialize for BSpline {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        BSplineRef {
+            fit: &self.fit,
+            basis: self.basis(),
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for BSpline {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let BSplineData { fit, basis } = BSplineData::deserialize(d)?;
+        let p = basis.ncoeffs();
+        if fit.params.len() != p || fit.covariance.len() != p * p {
+            return Err(de::Error::custom("B-spline fit doesn't match its basis"));
+        }
+        Ok(BSpline { fit, basis })
+    }
+}
+
+impl Serialize for Vector {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.to_boxed_slice().serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Vector {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let data = Vec::<f64>::deserialize(d)?;
+        if data.is_empty() {
+            return Err(de::Error::invalid_length(0, &"a nonempty vector"));
+        }
+        Ok(Vector::new(data))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MatrixData {
+    m: usize,
+    n: usize,
+    data: Box<[f64]>,
+}
+
+impl Serialize for Matrix {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let (m, n) = self.size();
+        MatrixData {
+            m,
+            n,
+            data: self.to_boxed_slice(),
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Matrix {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let MatrixData { m, n, data } = MatrixData::deserialize(d)?;
+        if m == 0 || n == 0 || m.checked_mul(n) != Some(data.len()) {
+            return Err(de::Error::invalid_length(data.len(), &"m × n elements"));
+        }
+        Ok(Matrix::new(data.into_vec(), m, n))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct OdeSolutionData {
+    dim: usize,
+    t: Vec<f64>,
+    y: Vec<f64>,
+    dydt: Vec<f64>,
+}
+
+impl Serialize for OdeSolution {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        OdeSolutionData {
+            dim: self.dim,
+            t: self.t.clone(),
+            y: self.y.clone(),
+            dydt: self.dydt.clone(),
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for OdeSolution {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let OdeSolutionData { dim, t, y, dydt } = OdeSolutionData::deserialize(d)?;
+        if dim == 0 || t.is_empty() {
+            return Err(de::Error::invalid_length(t.len(), &"a nonempty solution"));
+        }
+        let len = dim.checked_mul(t.len());
+        if len != Some(y.len()) || len != Some(dydt.len()) {
+            return Err(de::Error::invalid_length(y.len(), &"dim elements per step"));
+        }
+        Ok(OdeSolution { dim, t, y, dydt })
+    }
+}
+
+#[test]
+fn test_serde() {
+    disable_error_handler();
+
+    let x = ValWithError {
+        val: 1.5,
+        err: 0.25,
+    };
+    let json = serde_json::to_string(&x).unwrap();
+    assert_eq!(json, r#"{"val":1.5,"err":0.25}"#);
+    assert_eq!(serde_json::from_str::<ValWithError<f64>>(&json).unwrap(), x);
+
+    let mut h = Histogram::with_ranges(&[0.0, 1.0, 10.0]).unwrap();
+    h.accumulate(0.5, 3.0).unwrap();
+    h.accumulate(5.0, 0.25).unwrap();
+    let json = serde_json::to_string(&h).unwrap();
+    assert_eq!(json, r#"{"ranges":[0.0,1.0,10.0],"bins":[3.0,0.25]}"#);
+    let g = serde_json::from_str::<Histogram>(&json).unwrap();
+    assert_eq!(g.ranges(), h.ranges());
+    assert_eq!(g.bins(), h.bins());
+
+    let series = ChebSeries::new(8, 0.0, 1.0, f64::exp).unwrap();
+    let copy =
+        serde_json::from_str::<ChebSeries>(&serde_json::to_string(&series).unwrap()).unwrap();
+    assert_eq!(copy.domain(), series.domain());
+    assert_eq!(copy.coefficients(), series.coefficients());
+    assert_eq!(copy.eval(0.3), series.eval(0.3));
+
+    let x = (0..50).map(|i| i as f64 / 49.0).collect::<Vec<_>>();
+    let y = x.iter().map(|x| (3.0 * x).sin()).collect::<Vec<_>>();
+    let spline = BSpline::fit(4, 0.0, 1.0, 6, &x, &y).unwrap();
+    let copy = serde_json::from_str::<BSpline>(&serde_json::to_string(&spline).unwrap()).unwrap();
+    assert_eq!(copy.fit, spline.fit);
+    assert_eq!(copy.basis().breakpoints(), spline.basis().breakpoints());
+    assert_eq!(
+        copy.eval::<1>(&[0.3]).unwrap(),
+        spline.eval::<1>(&[0.3]).unwrap()
+    );
+
+    // Arrays of any size
+    let fit = nonlinear_fit::nonlinear_fit(
+        [1.0, 1.0],
+        &x,
+        &y.iter().map(|y| 2.0 * y + 1.0).collect::<Vec<_>>(),
+        |&x, [a, b]| Ok(a * (3.0 * x).sin() + b),
+    )
+    .unwrap();
+    let json = serde_json::to_string(&fit).unwrap();
+    assert_eq!(
+        serde_json::from_str::<nonlinear_fit::FitResult<2>>(&json).unwrap(),
+        fit
+    );
+    serde_json::from_str::<nonlinear_fit::FitResult<3>>(&json).unwrap_err();
+
+    let m = Matrix::new([1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3);
+    let json = serde_json::to_string(&m).unwrap();
+    assert_eq!(json, r#"{"m":2,"n":3,"data":[1.0,2.0,3.0,4.0,5.0,6.0]}"#);
+    let copy = serde_json::from_str::<Matrix>(&json).unwrap();
+    assert_eq!(copy.size(), (2, 3));
+    assert_eq!(copy.to_boxed_slice(), m.to_boxed_slice());
+    let v = serde_json::from_str::<Vector>("[1.0,2.0]").unwrap();
+    assert_eq!(*v.to_boxed_slice(), [1.0, 2.0]);
+
+    let solution = ode::solve(|_, y, dydt| dydt[0] = -y[0], &[1.0], 0.0, 1.0).unwrap();
+    let copy =
+        serde_json::from_str::<OdeSolution>(&serde_json::to_string(&solution).unwrap()).unwrap();
+    assert_eq!(copy.t(), solution.t());
+    assert_eq!(copy.final_state(), solution.final_state());
+    assert_eq!(copy.eval(0.5).unwrap(), solution.eval(0.5).unwrap());
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Decreasing ranges, and a missing bin
+    serde_json::from_str::<Histogram>(r#"{"ranges":[1.0,0.0],"bins":[1.0]}"#).unwrap_err();
+    serde_json::from_str::<Histogram>(r#"{"ranges":[0.0,1.0,2.0],"bins":[1.0]}"#).unwrap_err();
+
+    // Empty domain, no coefficients
+    serde_json::from_str::<ChebSeries>(r#"{"a":1.0,"b":1.0,"coefficients":[1.0,2.0]}"#)
+        .unwrap_err();
+    serde_json::from_str::<ChebSeries>(r#"{"a":0.0,"b":1.0,"coefficients":[]}"#).unwrap_err();
+
+    // Wrong sizes
+    serde_json::from_str::<Vector>("[]").unwrap_err();
+    serde_json::from_str::<Matrix>(r#"{"m":2,"n":2,"data":[1.0]}"#).unwrap_err();
+    serde_json::from_str::<Matrix>(r#"{"m":0,"n":2,"data":[]}"#).unwrap_err();
+
+    // No steps, no dimensions, and missing derivatives
+    serde_json::from_str::<OdeSolution>(r#"{"dim":1,"t":[],"y":[],"dydt":[]}"#).unwrap_err();
+    serde_json::from_str::<OdeSolution>(r#"{"dim":0,"t":[0.0],"y":[],"dydt":[]}"#).unwrap_err();
+    serde_json::from_str::<OdeSolution>(r#"{"dim":1,"t":[0.0,1.0],"y":[1.0,2.0],"dydt":[1.0]}"#)
+        .unwrap_err();
+}
@@ -0,0 +1,425 @@
+/*
+    sf.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+use num_complex::Complex64;
+
+pub mod bessel;
+mod check;
+pub mod ellint;
+pub mod legendre;
+
+pub fn gamma(x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_gamma_e(x, &mut result))?;
+        check::neighbours("gamma", x, &result, |x, r| gsl_sf_gamma_e(x, r));
+        Ok(result.into())
+    }
+}
+
+pub fn ln_gamma(x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_lngamma_e(x, &mut result))?;
+        check::neighbours("ln_gamma", x, &result, |x, r| gsl_sf_lngamma_e(x, r));
+        Ok(result.into())
+    }
+}
+
+/// Regularized lower incomplete gamma function `P(a, x) = γ(a, x) / Γ(a)`
+pub fn gamma_inc_p(a: f64, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_gamma_inc_P_e(a, x, &mut result))?;
+        check::neighbours("gamma_inc_p", x, &result, |x, r| {
+            gsl_sf_gamma_inc_P_e(a, x, r)
+        });
+        Ok(result.into())
+    }
+}
+
+/// Regularized upper incomplete gamma function `Q(a, x) = 1 - P(a, x)`
+pub fn gamma_inc_q(a: f64, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_gamma_inc_Q_e(a, x, &mut result))?;
+        check::neighbours("gamma_inc_q", x, &result, |x, r| {
+            gsl_sf_gamma_inc_Q_e(a, x, r)
+        });
+        Ok(result.into())
+    }
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`
+pub fn beta_inc(a: f64, b: f64, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_beta_inc_e(a, b, x, &mut result))?;
+        check::neighbours("beta_inc", x, &result, |x, r| gsl_sf_beta_inc_e(a, b, x, r));
+        Ok(result.into())
+    }
+}
+
+/// Complementary error function
+pub fn erfc(x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_erfc_e(x, &mut result))?;
+        check::neighbours("erfc", x, &result, |x, r| gsl_sf_erfc_e(x, r));
+        Ok(result.into())
+    }
+}
+
+pub fn ln_gamma_complex(z: Complex64) -> Result<ValWithError<Complex64>> {
+    unsafe {
+        let mut ln_r = gsl_sf_result { val: 0.0, err: 0.0 };
+        let mut arg = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_lngamma_complex_e(z.re, z.im, &mut ln_r, &mut arg))?;
+
+        Ok(ValWithError {
+            val: Complex64::from_polar(ln_r.val, arg.val),
+            err: Complex64::from_polar(ln_r.err, arg.err),
+        })
+    }
+}
+
+pub fn gamma_complex(z: Complex64) -> Result<ValWithError<Complex64>> {
+    unsafe {
+        let mut ln_r = gsl_sf_result { val: 0.0, err: 0.0 };
+        let mut arg = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_lngamma_complex_e(z.re, z.im, &mut ln_r, &mut arg))?;
+
+        Ok(ValWithError {
+            val: Complex64::from_polar(ln_r.val.exp(), arg.val),
+            err: Complex64::from_polar(ln_r.err.exp(), arg.err),
+        })
+    }
+}
+
+/// Riemann zeta function `ζ(s)` for `s != 1`
+pub fn zeta(s: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_zeta_e(s, &mut result))?;
+        check::neighbours("zeta", s, &result, |s, r| gsl_sf_zeta_e(s, r));
+        Ok(result.into())
+    }
+}
+
+/// `ζ(n)` for integers `n != 1`
+pub fn zeta_int(n: i32) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_zeta_int_e(n, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `ζ(s) - 1`, accurate for large `s`
+pub fn zetam1(s: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_zetam1_e(s, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `ζ(s)` for every `s` in `s_values`, e.g. to fill a table.
+/// Integer values use the tabulated `zeta_int`. Fails at the first `s` where `zeta` fails.
+pub fn zeta_range(s_values: &[f64]) -> Result<Vec<ValWithError<f64>>> {
+    s_values
+        .iter()
+        .map(|&s| {
+            if s.fract() == 0.0 && s.abs() <= i32::MAX as f64 {
+                zeta_int(s as i32)
+            } else {
+                zeta(s)
+            }
+        })
+        .collect()
+}
+
+/// Hurwitz zeta function `ζ(s, a)` for `s > 1` and `a > 0`
+pub fn hurwitz_zeta(s: f64, a: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_hzeta_e(s, a, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Dirichlet eta function `η(s) = (1 - 2^(1 - s)) ζ(s)`
+pub fn eta(s: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_eta_e(s, &mut result))?;
+        check::neighbours("eta", s, &result, |s, r| gsl_sf_eta_e(s, r));
+        Ok(result.into())
+    }
+}
+
+/// `η(n)` for integers `n`
+pub fn eta_int(n: i32) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_eta_int_e(n, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Reduces the angle `theta` to `(-π, π]`, using an extended precision value of `π`.
+/// Fails with `GSLError::Loss` if `theta` is too large for the result to be meaningful.
+pub fn angle_restrict_symm(theta: f64) -> Result<f64> {
+    unsafe {
+        let mut theta = theta;
+        GSLError::from_raw(gsl_sf_angle_restrict_symm_e(&mut theta))?;
+        Ok(theta)
+    }
+}
+
+/// Reduces the angle `theta` to `[0, 2π)`, see `angle_restrict_symm`
+pub fn angle_restrict_pos(theta: f64) -> Result<f64> {
+    unsafe {
+        let mut theta = theta;
+        GSLError::from_raw(gsl_sf_angle_restrict_pos_e(&mut theta))?;
+        Ok(theta)
+    }
+}
+
+/// `sin(x)` for `x` with the absolute uncertainty `dx`, which is included in the error estimate
+pub fn sin_err(x: f64, dx: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_sin_err_e(x, dx, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `cos(x)` for `x` with the absolute uncertainty `dx`, which is included in the error estimate
+pub fn cos_err(x: f64, dx: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_cos_err_e(x, dx, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `sqrt(x^2 + y^2)` without overflow or underflow in the intermediate squares
+pub fn hypot(x: f64, y: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_hypot_e(x, y, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `x * y`, with the rounding error of the product as the error estimate.
+/// Fails with `GSLError::OverFlow` or `GSLError::UnderFlow` if the product is out of range.
+pub fn multiply(x: f64, y: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_multiply_e(x, y, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `x * y` for `x` and `y` with the absolute uncertainties `dx` and `dy`, see `multiply`.
+/// Unlike the `*` operator of `ValWithError`, this checks the product for overflow and underflow.
+pub fn multiply_err(x: f64, dx: f64, y: f64, dy: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_multiply_err_e(x, dx, y, dy, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+#[test]
+fn test_gamma() {
+    disable_error_handler();
+
+    approx::assert_abs_diff_eq!(
+        gamma(5.0).unwrap().val,
+        gamma_complex(Complex64::from(5.0)).unwrap().val.re,
+        epsilon = 1.0e-9
+    );
+
+    approx::assert_abs_diff_eq!(
+        gamma_complex(Complex64::new(1.0, 1.0)).unwrap().val.re,
+        0.49801566811835,
+        epsilon = 1.0e-9
+    );
+    approx::assert_abs_diff_eq!(
+        gamma_complex(Complex64::new(1.0, 1.0)).unwrap().val.im,
+        -0.1549498283018,
+        epsilon = 1.0e-9
+    );
+}
+
+#[test]
+fn test_incomplete() {
+    disable_error_handler();
+
+    let x = 0.7f64;
+    approx::assert_abs_diff_eq!(
+        gamma_inc_p(1.0, x).unwrap().val,
+        1.0 - (-x).exp(),
+        epsilon = 1.0e-14
+    );
+    approx::assert_abs_diff_eq!(
+        gamma_inc_q(1.0, x).unwrap().val,
+        (-x).exp(),
+        epsilon = 1.0e-14
+    );
+    approx::assert_abs_diff_eq!(beta_inc(1.0, 1.0, x).unwrap().val, x, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(beta_inc(2.0, 1.0, x).unwrap().val, x * x, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(erfc(0.0).unwrap().val, 1.0, epsilon = 1.0e-15);
+    approx::assert_abs_diff_eq!(ln_gamma(5.0).unwrap().val, 24.0f64.ln(), epsilon = 1.0e-14);
+}
+
+#[test]
+fn test_zeta() {
+    use crate::testing::*;
+    use std::f64::consts::{LN_2, PI};
+
+    disable_error_handler();
+
+    // Reference values computed in high precision
+    assert_sf(zeta(2.0), PI * PI / 6.0, TOL0);
+    assert_sf(zeta(0.5), -1.4603545088095868, TOL0);
+    assert_sf(zeta(-2.5), 0.008516928777850331, TOL1);
+    assert_sf(zeta_int(3), 1.2020569031595943, TOL0);
+    assert_sf(zeta_int(-1), -1.0 / 12.0, TOL0);
+    assert_sf(zetam1(20.0), 9.539620338727962e-7, TOL0);
+    assert_sf(hurwitz_zeta(2.0, 3.5), 0.3303577561002349, TOL0);
+    assert_sf(eta(0.5), 0.6048986434216304, TOL0);
+    assert_sf(eta(-1.5), 0.11868087071984021, TOL1);
+    assert_sf(eta_int(1), LN_2, TOL0);
+    assert_sf(eta_int(2), PI * PI / 12.0, TOL0);
+
+    // η(1) is finite, although ζ has a pole there
+    assert_sf(eta(1.0), LN_2, TOL0);
+    approx::assert_relative_eq!(
+        hurwitz_zeta(2.0, 1.0).unwrap().val,
+        zeta(2.0).unwrap().val,
+        max_relative = 1.0e-15
+    );
+
+    let values = zeta_range(&[2.0, 2.5, 3.0, -2.5]).unwrap();
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0], zeta_int(2).unwrap());
+    assert_eq!(values[1], zeta(2.5).unwrap());
+    assert_eq!(values[3], zeta(-2.5).unwrap());
+    assert!(zeta_range(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_trig() {
+    disable_error_handler();
+
+    use std::f64::consts::PI;
+
+    approx::assert_abs_diff_eq!(
+        angle_restrict_symm(10.0 * PI + 0.1).unwrap(),
+        0.1,
+        epsilon = 1.0e-13
+    );
+    approx::assert_abs_diff_eq!(
+        angle_restrict_symm(-3.0 * PI / 2.0).unwrap(),
+        PI / 2.0,
+        epsilon = 1.0e-15
+    );
+    approx::assert_abs_diff_eq!(
+        angle_restrict_pos(-0.1).unwrap(),
+        2.0 * PI - 0.1,
+        epsilon = 1.0e-15
+    );
+    let theta = angle_restrict_pos(1.0e6).unwrap();
+    assert!((0.0..2.0 * PI).contains(&theta));
+    approx::assert_abs_diff_eq!(theta.sin(), 1.0e6f64.sin(), epsilon = 1.0e-9);
+
+    // The uncertainty of the argument propagates into the error estimate
+    let x = 1.0f64;
+    let sin = sin_err(x, 1.0e-3).unwrap();
+    approx::assert_abs_diff_eq!(sin.val, x.sin(), epsilon = 1.0e-15);
+    assert!(sin.err >= x.cos() * 1.0e-3);
+    let cos = cos_err(x, 1.0e-3).unwrap();
+    approx::assert_abs_diff_eq!(cos.val, x.cos(), epsilon = 1.0e-15);
+    assert!(cos.err >= x.sin() * 1.0e-3);
+
+    assert_eq!(hypot(3.0, 4.0).unwrap().val, 5.0);
+    approx::assert_relative_eq!(
+        hypot(1.0e300, 1.0e300).unwrap().val,
+        2.0f64.sqrt() * 1.0e300,
+        max_relative = 1.0e-15
+    );
+}
+
+#[test]
+fn test_multiply() {
+    disable_error_handler();
+
+    let product = multiply(3.0, -0.5).unwrap();
+    assert_eq!(product.val, -1.5);
+    assert!(product.err > 0.0 && product.err < 1.0e-15);
+
+    // The uncertainties add up to first order
+    let product = multiply_err(2.0, 0.1, 3.0, 0.2).unwrap();
+    assert_eq!(product.val, 6.0);
+    approx::assert_abs_diff_eq!(product.err, 0.7, epsilon = 1.0e-14);
+
+    // Exact values add no error, cancellation keeps the absolute error
+    let x = ValWithError { val: 1.0, err: 0.1 };
+    assert_eq!(
+        x - ValWithError::from(1.0),
+        ValWithError { val: 0.0, err: 0.1 }
+    );
+    assert_eq!((-x).err, 0.1);
+    approx::assert_abs_diff_eq!((x / 2.0.into()).err, 0.05, epsilon = 1.0e-15);
+    approx::assert_abs_diff_eq!((x + x).err, 0.2, epsilon = 1.0e-15);
+
+    // Chained special functions keep their error bounds, Γ(5) Γ(3) / Γ(4) = 8
+    let result = gamma(5.0).unwrap() * gamma(3.0).unwrap() / gamma(4.0).unwrap();
+    approx::assert_abs_diff_eq!(result.val, 8.0, epsilon = 1.0e-13);
+    assert!(result.err > 0.0 && (result.val - 8.0).abs() <= result.err);
+    let (a, b) = (gamma(5.0).unwrap(), gamma(3.0).unwrap());
+    let same = multiply_err(a.val, a.err, b.val, b.err).unwrap();
+    let product = a * b;
+    assert_eq!(same.val, product.val);
+    approx::assert_relative_eq!(same.err, product.err, max_relative = 1.0e-10);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Pole of ζ at 1
+    zeta(1.0).unwrap_err();
+    zeta_int(1).unwrap_err();
+    zeta_range(&[2.0, 1.0]).unwrap_err();
+    hurwitz_zeta(1.0, 1.0).unwrap_err();
+
+    // Too large to reduce meaningfully
+    angle_restrict_symm(1.0e20).unwrap_err();
+    angle_restrict_pos(-1.0e20).unwrap_err();
+
+    // Out of range products
+    multiply(1.0e200, 1.0e200).unwrap_err();
+    multiply_err(1.0e-200, 0.0, 1.0e-200, 0.0).unwrap_err();
+}
@@ -0,0 +1,556 @@
+/*
+    bessel.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use super::check;
+use crate::bindings::*;
+use crate::*;
+use std::os::raw::{c_int, c_uint};
+
+/*
+
+    The `_array` functions fill a range of orders in one call using the recurrence relations,
+    which is much faster than evaluating every order on its own. They return values without error estimates.
+    Cylindrical functions cover the orders `n_min..=n_max`, spherical functions the orders `0..=l_max`.
+
+    Scaled functions remove the exponential growth or decay of the modified functions,
+    so they don't overflow or underflow for large arguments.
+
+    Zeros of the regular cylindrical functions are the positive zeros, counted from `s = 1`.
+    The `zeros_` functions return the first `s_max` zeros, for example to find many eigenvalues
+    of the Laplacian on a disk at once.
+
+*/
+
+/// Regular cylindrical Bessel function `J_n(x)` of integer order
+pub fn j_n(n: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_Jn_e(n, x, &mut result))?;
+        check::neighbours("j_n", x, &result, |x, r| gsl_sf_bessel_Jn_e(n, x, r));
+        Ok(result.into())
+    }
+}
+
+/// Irregular cylindrical Bessel function `Y_n(x)` of integer order, for `x > 0`
+pub fn y_n(n: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_Yn_e(n, x, &mut result))?;
+        check::neighbours("y_n", x, &result, |x, r| gsl_sf_bessel_Yn_e(n, x, r));
+        Ok(result.into())
+    }
+}
+
+/// Regular modified cylindrical Bessel function `I_n(x)` of integer order
+pub fn i_n(n: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_In_e(n, x, &mut result))?;
+        check::neighbours("i_n", x, &result, |x, r| gsl_sf_bessel_In_e(n, x, r));
+        Ok(result.into())
+    }
+}
+
+/// Scaled regular modified cylindrical Bessel function `exp(-|x|) I_n(x)`
+pub fn i_n_scaled(n: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_In_scaled_e(n, x, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Irregular modified cylindrical Bessel function `K_n(x)` of integer order, for `x > 0`
+pub fn k_n(n: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_Kn_e(n, x, &mut result))?;
+        check::neighbours("k_n", x, &result, |x, r| gsl_sf_bessel_Kn_e(n, x, r));
+        Ok(result.into())
+    }
+}
+
+/// Scaled irregular modified cylindrical Bessel function `exp(x) K_n(x)`, for `x > 0`
+pub fn k_n_scaled(n: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_Kn_scaled_e(n, x, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Regular modified cylindrical Bessel function `I_ν(x)` of fractional order `ν >= 0`, for `x > 0`
+pub fn i_nu(nu: f64, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_Inu_e(nu, x, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Scaled regular modified Bessel function `exp(-|x|) I_ν(x)` of fractional order `ν >= 0`, for `x > 0`
+pub fn i_nu_scaled(nu: f64, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_Inu_scaled_e(nu, x, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Regular spherical Bessel function `j_l(x)`, for `l >= 0`
+pub fn j_l(l: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_jl_e(l, x, &mut result))?;
+        check::neighbours("j_l", x, &result, |x, r| gsl_sf_bessel_jl_e(l, x, r));
+        Ok(result.into())
+    }
+}
+
+/// Irregular spherical Bessel function `y_l(x)`, for `l >= 0`
+pub fn y_l(l: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_yl_e(l, x, &mut result))?;
+        check::neighbours("y_l", x, &result, |x, r| gsl_sf_bessel_yl_e(l, x, r));
+        Ok(result.into())
+    }
+}
+
+/// Scaled regular modified spherical Bessel function `exp(-|x|) i_l(x)`, for `l >= 0`
+pub fn i_l_scaled(l: i32, x: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_il_scaled_e(l, x, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `J_n(x)` for the orders `n_min..=n_max`
+pub fn j_n_array(n_min: usize, n_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(n_min, n_max)?;
+        let mut result = vec![0.0; n_max - n_min + 1];
+        GSLError::from_raw(gsl_sf_bessel_Jn_array(
+            n_min as c_int,
+            n_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `Y_n(x)` for the orders `n_min..=n_max`, for `x > 0`
+pub fn y_n_array(n_min: usize, n_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(n_min, n_max)?;
+        let mut result = vec![0.0; n_max - n_min + 1];
+        GSLError::from_raw(gsl_sf_bessel_Yn_array(
+            n_min as c_int,
+            n_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `I_n(x)` for the orders `n_min..=n_max`
+pub fn i_n_array(n_min: usize, n_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(n_min, n_max)?;
+        let mut result = vec![0.0; n_max - n_min + 1];
+        GSLError::from_raw(gsl_sf_bessel_In_array(
+            n_min as c_int,
+            n_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `exp(-|x|) I_n(x)` for the orders `n_min..=n_max`
+pub fn i_n_scaled_array(n_min: usize, n_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(n_min, n_max)?;
+        let mut result = vec![0.0; n_max - n_min + 1];
+        GSLError::from_raw(gsl_sf_bessel_In_scaled_array(
+            n_min as c_int,
+            n_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `K_n(x)` for the orders `n_min..=n_max`, for `x > 0`
+pub fn k_n_array(n_min: usize, n_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(n_min, n_max)?;
+        let mut result = vec![0.0; n_max - n_min + 1];
+        GSLError::from_raw(gsl_sf_bessel_Kn_array(
+            n_min as c_int,
+            n_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `exp(x) K_n(x)` for the orders `n_min..=n_max`, for `x > 0`
+pub fn k_n_scaled_array(n_min: usize, n_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(n_min, n_max)?;
+        let mut result = vec![0.0; n_max - n_min + 1];
+        GSLError::from_raw(gsl_sf_bessel_Kn_scaled_array(
+            n_min as c_int,
+            n_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `j_l(x)` for the orders `0..=l_max`
+pub fn j_l_array(l_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(0, l_max)?;
+        let mut result = vec![0.0; l_max + 1];
+        GSLError::from_raw(gsl_sf_bessel_jl_array(
+            l_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `j_l(x)` for the orders `0..=l_max` with Steed's method, which is more accurate for large orders
+pub fn j_l_steed_array(l_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(0, l_max)?;
+        let mut result = vec![0.0; l_max + 1];
+        GSLError::from_raw(gsl_sf_bessel_jl_steed_array(
+            l_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `y_l(x)` for the orders `0..=l_max`
+pub fn y_l_array(l_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(0, l_max)?;
+        let mut result = vec![0.0; l_max + 1];
+        GSLError::from_raw(gsl_sf_bessel_yl_array(
+            l_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `exp(-|x|) i_l(x)` for the orders `0..=l_max`
+pub fn i_l_scaled_array(l_max: usize, x: f64) -> Result<Vec<f64>> {
+    unsafe {
+        check_orders(0, l_max)?;
+        let mut result = vec![0.0; l_max + 1];
+        GSLError::from_raw(gsl_sf_bessel_il_scaled_array(
+            l_max as c_int,
+            x,
+            result.as_mut_ptr(),
+        ))?;
+        Ok(result)
+    }
+}
+
+/// `s`-th positive zero of `J_0(x)`, for `s >= 1`
+pub fn zero_j0(s: u32) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_zero_J0_e(s as c_uint, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `s`-th positive zero of `J_1(x)`, for `s >= 1`
+pub fn zero_j1(s: u32) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_zero_J1_e(s as c_uint, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// `s`-th positive zero of `J_ν(x)` of real order `ν >= 0`, for `s >= 1`
+pub fn zero_j_nu(nu: f64, s: u32) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_bessel_zero_Jnu_e(nu, s as c_uint, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// The first `s_max` positive zeros of `J_0(x)`
+pub fn zeros_j0(s_max: usize) -> Result<Vec<f64>> {
+    zeros(s_max, zero_j0)
+}
+
+/// The first `s_max` positive zeros of `J_1(x)`
+pub fn zeros_j1(s_max: usize) -> Result<Vec<f64>> {
+    zeros(s_max, zero_j1)
+}
+
+/// The first `s_max` positive zeros of `J_ν(x)` of real order `ν >= 0`
+pub fn zeros_j_nu(nu: f64, s_max: usize) -> Result<Vec<f64>> {
+    zeros(s_max, |s| zero_j_nu(nu, s))
+}
+
+fn zeros<F: FnMut(u32) -> Result<ValWithError<f64>>>(
+    s_max: usize,
+    mut zero: F,
+) -> Result<Vec<f64>> {
+    if s_max > u32::MAX as usize {
+        return Err(GSLError::Invalid);
+    }
+    let mut result = Vec::with_capacity(s_max);
+    for s in 1..=s_max as u32 {
+        result.push(zero(s)?.val);
+    }
+    Ok(result)
+}
+
+fn check_orders(n_min: usize, n_max: usize) -> Result<()> {
+    if n_min > n_max || n_max > c_int::MAX as usize {
+        return Err(GSLError::Invalid);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_bessel() {
+    disable_error_handler();
+
+    let x = 2.5f64;
+    approx::assert_abs_diff_eq!(
+        j_n(0, x).unwrap().val,
+        -0.048383776468198,
+        epsilon = 1.0e-14
+    );
+    approx::assert_abs_diff_eq!(
+        j_n(-1, x).unwrap().val,
+        -j_n(1, x).unwrap().val,
+        epsilon = 1.0e-14
+    );
+    approx::assert_abs_diff_eq!(
+        k_n_scaled(1, x).unwrap().val,
+        x.exp() * k_n(1, x).unwrap().val,
+        epsilon = 1.0e-12
+    );
+    approx::assert_abs_diff_eq!(
+        i_nu(2.0, x).unwrap().val,
+        i_n(2, x).unwrap().val,
+        epsilon = 1.0e-12
+    );
+    approx::assert_abs_diff_eq!(
+        i_nu_scaled(0.5, x).unwrap().val,
+        (-x).exp() * (2.0 / (std::f64::consts::PI * x)).sqrt() * x.sinh(),
+        epsilon = 1.0e-12
+    );
+
+    // Spherical functions of order zero
+    approx::assert_abs_diff_eq!(j_l(0, x).unwrap().val, x.sin() / x, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(y_l(0, x).unwrap().val, -x.cos() / x, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(
+        i_l_scaled(0, x).unwrap().val,
+        (-x).exp() * x.sinh() / x,
+        epsilon = 1.0e-14
+    );
+}
+
+#[test]
+fn test_bessel_arrays() {
+    disable_error_handler();
+
+    // Every array agrees with the single orders
+    let x = 3.7;
+    type Single = fn(i32, f64) -> Result<ValWithError<f64>>;
+    type Array = fn(usize, usize, f64) -> Result<Vec<f64>>;
+    let cylindrical: [(Single, Array); 6] = [
+        (j_n, j_n_array),
+        (y_n, y_n_array),
+        (i_n, i_n_array),
+        (i_n_scaled, i_n_scaled_array),
+        (k_n, k_n_array),
+        (k_n_scaled, k_n_scaled_array),
+    ];
+    for (single, array) in cylindrical {
+        let values = array(2, 9, x).unwrap();
+        assert_eq!(values.len(), 8);
+        for (k, value) in values.iter().enumerate() {
+            let expected = single(k as i32 + 2, x).unwrap().val;
+            approx::assert_relative_eq!(*value, expected, max_relative = 1.0e-10);
+        }
+    }
+
+    type Spherical = fn(usize, f64) -> Result<Vec<f64>>;
+    let spherical: [(Single, Spherical); 4] = [
+        (j_l, j_l_array),
+        (j_l, j_l_steed_array),
+        (y_l, y_l_array),
+        (i_l_scaled, i_l_scaled_array),
+    ];
+    for (single, array) in spherical {
+        let values = array(6, x).unwrap();
+        assert_eq!(values.len(), 7);
+        for (l, value) in values.iter().enumerate() {
+            let expected = single(l as i32, x).unwrap().val;
+            approx::assert_relative_eq!(*value, expected, max_relative = 1.0e-10);
+        }
+    }
+
+    // Recurrence J_{n-1} + J_{n+1} = 2n / x J_n
+    let j = j_n_array(0, 20, x).unwrap();
+    for n in 1..20 {
+        approx::assert_abs_diff_eq!(
+            j[n - 1] + j[n + 1],
+            2.0 * n as f64 / x * j[n],
+            epsilon = 1.0e-13
+        );
+    }
+}
+
+#[test]
+fn test_bessel_zeros() {
+    disable_error_handler();
+
+    approx::assert_abs_diff_eq!(
+        zero_j0(1).unwrap().val,
+        2.404825557695773,
+        epsilon = 1.0e-12
+    );
+    approx::assert_abs_diff_eq!(
+        zero_j1(1).unwrap().val,
+        3.831705970207512,
+        epsilon = 1.0e-12
+    );
+
+    // J_(1/2)(x) is proportional to sin(x) / x^(1/2), with the zeros s π
+    let zeros = zeros_j_nu(0.5, 10).unwrap();
+    assert_eq!(zeros.len(), 10);
+    for (k, zero) in zeros.iter().enumerate() {
+        approx::assert_relative_eq!(
+            *zero,
+            (k + 1) as f64 * std::f64::consts::PI,
+            max_relative = 1.0e-12
+        );
+    }
+
+    // The zeros are increasing and agree with the single zeros and with J_n
+    let zeros = zeros_j1(50).unwrap();
+    assert!(zeros.windows(2).all(|w| w[0] < w[1]));
+    for (k, zero) in zeros.iter().enumerate() {
+        assert_eq!(*zero, zero_j1(k as u32 + 1).unwrap().val);
+        approx::assert_abs_diff_eq!(j_n(1, *zero).unwrap().val, 0.0, epsilon = 1.0e-12);
+    }
+    for (a, b) in zeros_j0(20)
+        .unwrap()
+        .iter()
+        .zip(zeros_j_nu(0.0, 20).unwrap())
+    {
+        approx::assert_relative_eq!(*a, b, max_relative = 1.0e-12);
+    }
+    assert!(zeros_j0(0).unwrap().is_empty());
+}
+
+#[test]
+fn test_gsl_suite() {
+    use crate::testing::*;
+
+    disable_error_handler();
+
+    // Ported from specfunc/test_bessel.c
+    assert_sf(j_n(0, 0.1), 0.99750156206604, TOL0);
+    assert_sf(j_n(0, 2.0), 0.22389077914123567, TOL0);
+    assert_sf(j_n(0, 100.0), 0.019985850304223122, TOL0);
+    assert_sf(j_n(1, 0.1), 0.049937526036242, TOL0);
+    assert_sf(j_n(1, 2.0), 0.5767248077568734, TOL0);
+    assert_sf(j_n(1, 100.0), -0.07714535201411216, TOL0);
+    assert_sf(j_n(4, 0.1), 2.602864854568403e-07, TOL0);
+    assert_sf(j_n(5, 2.0), 0.007039629755871685, TOL0);
+    assert_sf(j_n(10, 20.0), 0.1864825580239451, TOL0);
+    assert_sf(j_n(100, 100.0), 0.09636667329586156, TOL0);
+
+    assert_sf(y_n(4, 0.1), -305832.2979335316, TOL1);
+    assert_sf(y_n(5, 2.0), -9.935989128481975, TOL1);
+    assert_sf(y_n(10, 20.0), -0.0438946535156584, TOL1);
+    assert_sf(y_n(100, 100.0), -0.1669214114175765, TOL3);
+
+    assert_sf(i_n_scaled(4, 0.1), 2.3575258620054605e-07, TOL0);
+    assert_sf(i_n_scaled(5, 2.0), 0.0013297610941881578, TOL0);
+    assert_sf(i_n_scaled(10, 20.0), 0.0072968964849783255, TOL1);
+    assert_sf(i_n_scaled(100, 100.0), 1.7266862628167697e-22, TOL2);
+
+    assert_sf(k_n_scaled(4, 0.1), 530040.2483725626, TOL0);
+    assert_sf(k_n_scaled(5, 2.0), 69.68655087607675, TOL0);
+    assert_sf(k_n_scaled(10, 20.0), 3.0644074558832957, TOL1);
+    assert_sf(k_n_scaled(100, 100.0), 2.0475736731166757e+19, TOL1);
+
+    assert_sf(j_l(0, 1.0), 0.8414709848078965, TOL0);
+    assert_sf(j_l(1, 10.0), 0.07846694179875155, TOL0);
+    assert_sf(j_l(5, 1.0), 9.256115861125816e-05, TOL0);
+    assert_sf(j_l(100, 100.0), 0.010880477011438336, TOL2);
+
+    // Ported from the zeros in specfunc/test_sf.c
+    assert_sf(zero_j0(1), 2.404825557695773, TOL0);
+    assert_sf(zero_j0(2), 5.520078110286311, TOL0);
+    assert_sf(zero_j0(20), 62.048469190227166, TOL0);
+    assert_sf(zero_j1(1), 3.8317059702075125, TOL0);
+    assert_sf(zero_j1(2), 7.015586669815619, TOL0);
+    assert_sf(zero_j1(20), 63.61135669848123, TOL0);
+    assert_sf(zero_j_nu(1.5, 5), 17.22075527193077, TOL2);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Irregular functions at zero or negative arguments
+    y_n(0, 0.0).unwrap_err();
+    k_n(1, -1.0).unwrap_err();
+    y_n_array(0, 3, 0.0).unwrap_err();
+
+    // Negative orders
+    j_l(-1, 1.0).unwrap_err();
+    i_nu(-0.5, 1.0).unwrap_err();
+
+    // Empty range of orders
+    j_n_array(3, 2, 1.0).unwrap_err();
+
+    // Zeros counted from zero, or of a negative order
+    zero_j0(0).unwrap_err();
+    zeros_j_nu(-1.0, 3).unwrap_err();
+}
@@ -0,0 +1,143 @@
+/*
+    check.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::error::{restore_error_handler, take_error_handler};
+use std::os::raw::c_int;
+
+/*
+
+    Cross-checks of the special functions against their neighbours, enabled with the `debug-checks` feature.
+    They catch binding mistakes and accuracy regressions of GSL where the crate is used, not only in its tests.
+
+    A result `f(x) ± err` is compared with `f(x - ulp)` and `f(x + ulp)`. Their mean cancels the slope,
+    so it has to agree with `f(x)` within the error estimates. A wrong argument order, a swapped output,
+    or an error estimate that doesn't cover the actual error show up as a disagreement.
+    The error estimate itself has to be nonnegative and finite.
+
+    Failed checks panic, like debug assertions. Arguments whose neighbours fail to evaluate,
+    e.g. at the edge of the domain, aren't checked.
+
+*/
+
+/// Relative slack for the rounding of the mean of the neighbours
+const ROUNDING: f64 = 4.0 * f64::EPSILON;
+/// Allowed multiple of the error estimates
+const SIGMA: f64 = 2.0;
+
+/// Checks `result = f(x)` against the neighbours of `x`, with the `debug-checks` feature
+#[inline]
+pub(crate) fn neighbours<F: Fn(f64, &mut gsl_sf_result) -> c_int>(
+    name: &str,
+    x: f64,
+    result: &gsl_sf_result,
+    f: F,
+) {
+    if cfg!(feature = "debug-checks") {
+        if let Err(reason) = check_neighbours(x, result, f) {
+            panic!("{}({:e}) failed its debug check: {}", name, x, reason);
+        }
+    }
+}
+
+fn check_neighbours<F: Fn(f64, &mut gsl_sf_result) -> c_int>(
+    x: f64,
+    result: &gsl_sf_result,
+    f: F,
+) -> Result<(), String> {
+    if !result.val.is_finite() || !x.is_finite() {
+        return Ok(());
+    }
+    if !(result.err >= 0.0 && result.err.is_finite()) {
+        return Err(format!("bad error estimate {:e}", result.err));
+    }
+
+    let eval = |x: f64| {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        let status = f(x, &mut result);
+        Some(result).filter(|r| status == GSL_SUCCESS && r.val.is_finite() && r.err.is_finite())
+    };
+    // The neighbours may be outside the domain, which is no error of the caller
+    let handler = take_error_handler();
+    let neighbours = (eval(x.next_down()), eval(x.next_up()));
+    restore_error_handler(handler);
+    let (lower, upper) = match neighbours {
+        (Some(lower), Some(upper)) => (lower, upper),
+        _ => return Ok(()),
+    };
+
+    let mean = 0.5 * (lower.val + upper.val);
+    let diff = (result.val - mean).abs();
+    let tol = SIGMA * (result.err + 0.5 * (lower.err + upper.err)) + ROUNDING * mean.abs();
+    if diff <= tol {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:e} ± {:e} differs from its neighbours {:e} and {:e} by {:e}",
+            result.val, result.err, lower.val, upper.val, diff
+        ))
+    }
+}
+
+#[test]
+fn test_check_neighbours() {
+    // `f` with the relative error estimate `rel_err`
+    let eval = |f: fn(f64) -> f64, rel_err: f64| {
+        move |x: f64, result: &mut gsl_sf_result| {
+            let val = f(x);
+            *result = gsl_sf_result {
+                val,
+                err: rel_err * val.abs(),
+            };
+            GSL_SUCCESS
+        }
+    };
+    let at = |f: &dyn Fn(f64, &mut gsl_sf_result) -> c_int, x: f64| {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        f(x, &mut result);
+        result
+    };
+
+    let exp = eval(f64::exp, 2.0 * f64::EPSILON);
+    for &x in &[-3.0, 0.0, 0.5, 10.0] {
+        check_neighbours(x, &at(&exp, x), exp).unwrap();
+    }
+
+    // Jumps between neighbours, beyond the error estimate
+    let noisy = eval(|x| 1.0 + (x.to_bits() % 2) as f64 * 1.0e-6, 1.0e-12);
+    check_neighbours(1.5, &at(&noisy, 1.5), noisy).unwrap_err();
+
+    // Negative error estimate
+    let negative = eval(f64::sin, -1.0);
+    check_neighbours(1.0, &at(&negative, 1.0), negative).unwrap_err();
+
+    // The special functions pass
+    let gamma = |x, r: &mut gsl_sf_result| unsafe { gsl_sf_gamma_e(x, r) };
+    let j0 = |x, r: &mut gsl_sf_result| unsafe { gsl_sf_bessel_Jn_e(0, x, r) };
+    for &x in &[0.1, 1.5, 7.25, 30.0, 170.0] {
+        check_neighbours(x, &at(&gamma, x), gamma).unwrap();
+        check_neighbours(x, &at(&j0, x), j0).unwrap();
+    }
+
+    // The neighbour 0 of the smallest subnormal is outside the domain of the logarithm
+    crate::set_error_handler(crate::PanicOnError);
+    let log = |x, r: &mut gsl_sf_result| unsafe { gsl_sf_log_e(x, r) };
+    let x = f64::from_bits(1);
+    check_neighbours(x, &at(&log, x), log).unwrap();
+    crate::disable_error_handler();
+}
@@ -0,0 +1,247 @@
+/*
+    ellint.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use super::check;
+use crate::bindings::*;
+use crate::*;
+
+/*
+
+    Elliptic integrals in the Legendre forms and the Carlson forms, and the Jacobi elliptic functions.
+
+    Like the rest of `sf`, the integrals return the value with its error estimate, as the `_e` functions
+    of GSL do. The plain value is the `val` field of the result. All of them are evaluated in double precision.
+
+    The Legendre forms take the modulus `k`, with `k^2 < 1` for the complete integrals.
+    The integrals of the third kind use the sign convention of GSL, with `1 + n sin^2 t` in the denominator,
+    which is the opposite of Abramowitz and Stegun.
+    The Jacobi functions take the parameter `m = k^2` instead, with `|m| <= 1`.
+
+*/
+
+/// Jacobi elliptic functions at one argument
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Jacobi {
+    pub sn: f64,
+    pub cn: f64,
+    pub dn: f64,
+}
+
+/// Complete elliptic integral of the first kind `K(k)`
+pub fn kcomp(k: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_Kcomp_e(k, GSL_PREC_DOUBLE, &mut result))?;
+        check::neighbours("kcomp", k, &result, |k, r| {
+            gsl_sf_ellint_Kcomp_e(k, GSL_PREC_DOUBLE, r)
+        });
+        Ok(result.into())
+    }
+}
+
+/// Complete elliptic integral of the second kind `E(k)`
+pub fn ecomp(k: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_Ecomp_e(k, GSL_PREC_DOUBLE, &mut result))?;
+        check::neighbours("ecomp", k, &result, |k, r| {
+            gsl_sf_ellint_Ecomp_e(k, GSL_PREC_DOUBLE, r)
+        });
+        Ok(result.into())
+    }
+}
+
+/// Complete elliptic integral of the third kind `Π(k, n)`
+pub fn pcomp(k: f64, n: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_Pcomp_e(k, n, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Complete elliptic integral `D(k) = (K(k) - E(k)) / k^2`
+pub fn dcomp(k: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_Dcomp_e(k, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Incomplete elliptic integral of the first kind `F(φ, k)`
+pub fn f(phi: f64, k: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_F_e(phi, k, GSL_PREC_DOUBLE, &mut result))?;
+        check::neighbours("f", phi, &result, |phi, r| {
+            gsl_sf_ellint_F_e(phi, k, GSL_PREC_DOUBLE, r)
+        });
+        Ok(result.into())
+    }
+}
+
+/// Incomplete elliptic integral of the second kind `E(φ, k)`
+pub fn e(phi: f64, k: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_E_e(phi, k, GSL_PREC_DOUBLE, &mut result))?;
+        check::neighbours("e", phi, &result, |phi, r| {
+            gsl_sf_ellint_E_e(phi, k, GSL_PREC_DOUBLE, r)
+        });
+        Ok(result.into())
+    }
+}
+
+/// Incomplete elliptic integral of the third kind `Π(φ, k, n)`
+pub fn p(phi: f64, k: f64, n: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_P_e(phi, k, n, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Incomplete elliptic integral `D(φ, k) = (F(φ, k) - E(φ, k)) / k^2`
+pub fn d(phi: f64, k: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_D_e(phi, k, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Carlson's degenerate integral `RC(x, y)`
+pub fn rc(x: f64, y: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_RC_e(x, y, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Carlson's integral of the second kind `RD(x, y, z)`
+pub fn rd(x: f64, y: f64, z: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_RD_e(x, y, z, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Carlson's integral of the first kind `RF(x, y, z)`
+pub fn rf(x: f64, y: f64, z: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_RF_e(x, y, z, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Carlson's integral of the third kind `RJ(x, y, z, p)`
+pub fn rj(x: f64, y: f64, z: f64, p: f64) -> Result<ValWithError<f64>> {
+    unsafe {
+        let mut result = gsl_sf_result { val: 0.0, err: 0.0 };
+        GSLError::from_raw(gsl_sf_ellint_RJ_e(x, y, z, p, GSL_PREC_DOUBLE, &mut result))?;
+        Ok(result.into())
+    }
+}
+
+/// Jacobi elliptic functions `sn(u|m)`, `cn(u|m)` and `dn(u|m)` for the parameter `m = k^2`
+pub fn elljac(u: f64, m: f64) -> Result<Jacobi> {
+    let (mut sn, mut cn, mut dn) = (0.0, 0.0, 0.0);
+    unsafe {
+        GSLError::from_raw(gsl_sf_elljac_e(u, m, &mut sn, &mut cn, &mut dn))?;
+    }
+    Ok(Jacobi { sn, cn, dn })
+}
+
+#[test]
+fn test_ellint() {
+    use crate::testing::*;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4};
+
+    disable_error_handler();
+
+    // Reference values computed in high precision
+    assert_sf(kcomp(0.5), 1.685750354812596, TOL0);
+    assert_sf(ecomp(0.5), 1.4674622093394272, TOL0);
+    assert_sf(f(FRAC_PI_3, 0.5), 1.0895506700518853, TOL0);
+    assert_sf(e(FRAC_PI_3, 0.5), 1.007555555144472, TOL0);
+    assert_sf(rc(1.0, 2.0), FRAC_PI_4, TOL0);
+    assert_sf(rd(0.0, 2.0, 1.0), 1.7972103521033884, TOL0);
+    assert_sf(rf(1.0, 2.0, 0.0), 1.3110287771460598, TOL0);
+    assert_sf(rj(2.0, 3.0, 4.0, 5.0), 0.14297579667156754, TOL0);
+    assert_rel(pcomp(0.5, 0.3).unwrap().val, 1.4715681939859635, 1.0e-14);
+    assert_rel(dcomp(0.5).unwrap().val, 0.8731525818926755, 1.0e-14);
+    assert_rel(
+        p(FRAC_PI_3, 0.5, 0.3).unwrap().val,
+        1.0035637821389787,
+        1.0e-14,
+    );
+    assert_rel(d(FRAC_PI_3, 0.5).unwrap().val, 0.3279804596296535, 1.0e-14);
+
+    // The incomplete integrals at φ = π/2 are the complete ones
+    let k = 0.8;
+    assert_rel(f(FRAC_PI_2, k).unwrap().val, kcomp(k).unwrap().val, 1.0e-14);
+    assert_rel(e(FRAC_PI_2, k).unwrap().val, ecomp(k).unwrap().val, 1.0e-14);
+    assert_rel(
+        p(FRAC_PI_2, k, 0.3).unwrap().val,
+        pcomp(k, 0.3).unwrap().val,
+        1.0e-14,
+    );
+    assert_rel(d(FRAC_PI_2, k).unwrap().val, dcomp(k).unwrap().val, 1.0e-14);
+    assert_rel(
+        rf(0.0, 1.0 - k * k, 1.0).unwrap().val,
+        kcomp(k).unwrap().val,
+        1.0e-14,
+    );
+
+    let jacobi = elljac(0.7, 0.36).unwrap();
+    assert_rel(jacobi.sn, 0.6299171153234868, 1.0e-14);
+    assert_rel(jacobi.cn, 0.7766623641084567, 1.0e-14);
+    assert_rel(jacobi.dn, 0.9258258983286832, 1.0e-14);
+    assert_abs(jacobi.sn.powi(2) + jacobi.cn.powi(2), 1.0, 1.0e-15);
+    assert_abs(jacobi.dn.powi(2) + 0.36 * jacobi.sn.powi(2), 1.0, 1.0e-15);
+
+    // Circular functions for m = 0, hyperbolic ones for m = 1
+    let circular = elljac(0.7, 0.0).unwrap();
+    assert_abs(circular.sn, 0.7f64.sin(), 1.0e-15);
+    assert_abs(circular.cn, 0.7f64.cos(), 1.0e-15);
+    assert_abs(circular.dn, 1.0, 1.0e-15);
+    let hyperbolic = elljac(0.7, 1.0).unwrap();
+    assert_abs(hyperbolic.sn, 0.7f64.tanh(), 1.0e-15);
+    assert_abs(hyperbolic.cn, 1.0 / 0.7f64.cosh(), 1.0e-15);
+    assert_abs(hyperbolic.dn, 1.0 / 0.7f64.cosh(), 1.0e-15);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Complete integrals diverge at k = 1
+    kcomp(1.0).unwrap_err();
+    dcomp(1.5).unwrap_err();
+
+    // Negative arguments of the Carlson forms
+    rf(-1.0, 1.0, 1.0).unwrap_err();
+    rj(1.0, 1.0, 1.0, 0.0).unwrap_err();
+
+    // Parameter beyond 1
+    elljac(0.5, 1.5).unwrap_err();
+}
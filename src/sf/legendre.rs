@@ -0,0 +1,304 @@
+/*
+    sf/legendre.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::bindings::*;
+use crate::*;
+
+/*
+
+    Associated Legendre functions `P_l^m(x)` for all `0 <= m <= l <= l_max` in one call,
+    for `-1 <= x <= 1`. The values are stored by degree, `P_l^m` at `array_index(l, m)`,
+    and the arrays hold `array_len(l_max)` values.
+
+    By default the Condon-Shortley phase `(-1)^m` is left out, as in GSL.
+    The `_ext` functions include it if `condon_shortley` is set.
+
+    Derivatives are with respect to `x`, or with respect to `θ` for `x = cos θ` in the `_alt` functions.
+    The derivatives with respect to `x` diverge at `x = ±1`, which have to be excluded.
+
+*/
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// Unnormalized `P_l^m(x)`
+    None,
+    /// Schmidt semi-normalized, `sqrt(2 (l - m)! / (l + m)!) P_l^m(x)` for `m > 0`, as in geomagnetism
+    Schmidt,
+    /// Spherical harmonic normalization `sqrt((2l + 1) / 4π (l - m)! / (l + m)!) P_l^m(x)`
+    SphericalHarmonic,
+    /// Fully normalized `sqrt((2l + 1) / 2 (l - m)! / (l + m)!) P_l^m(x)`, with unit norm on `[-1, 1]`
+    Full,
+}
+
+impl Normalization {
+    fn as_gsl(self) -> gsl_sf_legendre_t {
+        match self {
+            Normalization::None => gsl_sf_legendre_t_GSL_SF_LEGENDRE_NONE,
+            Normalization::Schmidt => gsl_sf_legendre_t_GSL_SF_LEGENDRE_SCHMIDT,
+            Normalization::SphericalHarmonic => gsl_sf_legendre_t_GSL_SF_LEGENDRE_SPHARM,
+            Normalization::Full => gsl_sf_legendre_t_GSL_SF_LEGENDRE_FULL,
+        }
+    }
+}
+
+/// Amount of functions `(l_max + 1) (l_max + 2) / 2` up to degree `l_max`
+pub fn array_len(l_max: usize) -> usize {
+    unsafe { gsl_sf_legendre_nlm(l_max as u64) as usize }
+}
+
+/// Position of `P_l^m` in the arrays, for `m <= l`
+pub fn array_index(l: usize, m: usize) -> usize {
+    unsafe { gsl_sf_legendre_array_index(l as u64, m as u64) as usize }
+}
+
+/// `P_l^m(x)` for all `0 <= m <= l <= l_max`, see `array_index`
+pub fn array(norm: Normalization, l_max: usize, x: f64) -> Result<Vec<f64>> {
+    array_ext(norm, l_max, x, false)
+}
+
+/// `P_l^m(x)` for all `0 <= m <= l <= l_max`, including the Condon-Shortley phase if `condon_shortley` is set
+pub fn array_ext(
+    norm: Normalization,
+    l_max: usize,
+    x: f64,
+    condon_shortley: bool,
+) -> Result<Vec<f64>> {
+    unsafe {
+        if !(-1.0..=1.0).contains(&x) {
+            return Err(GSLError::Domain);
+        }
+
+        let mut result = vec![0.0; gsl_sf_legendre_array_n(l_max as u64) as usize];
+        GSLError::from_raw(gsl_sf_legendre_array_e(
+            norm.as_gsl(),
+            l_max as u64,
+            x,
+            csphase(condon_shortley),
+            result.as_mut_ptr(),
+        ))?;
+        result.truncate(array_len(l_max));
+        Ok(result)
+    }
+}
+
+/// `P_l^m(x)` and `dP_l^m(x) / dx` for all `0 <= m <= l <= l_max`, for `-1 < x < 1`
+pub fn deriv_array(norm: Normalization, l_max: usize, x: f64) -> Result<(Vec<f64>, Vec<f64>)> {
+    deriv_array_ext(norm, l_max, x, false)
+}
+
+/// `P_l^m(x)` and `dP_l^m(x) / dx` for all `0 <= m <= l <= l_max`, for `-1 < x < 1`,
+/// including the Condon-Shortley phase if `condon_shortley` is set
+pub fn deriv_array_ext(
+    norm: Normalization,
+    l_max: usize,
+    x: f64,
+    condon_shortley: bool,
+) -> Result<(Vec<f64>, Vec<f64>)> {
+    derivs(
+        gsl_sf_legendre_deriv_array_e,
+        norm,
+        l_max,
+        x,
+        condon_shortley,
+    )
+}
+
+/// `P_l^m(cos θ)` and `dP_l^m(cos θ) / dθ` for all `0 <= m <= l <= l_max`, for `x = cos θ` in `(-1, 1)`
+pub fn deriv_alt_array(norm: Normalization, l_max: usize, x: f64) -> Result<(Vec<f64>, Vec<f64>)> {
+    deriv_alt_array_ext(norm, l_max, x, false)
+}
+
+/// `P_l^m(cos θ)` and `dP_l^m(cos θ) / dθ` for all `0 <= m <= l <= l_max`, for `x = cos θ` in `(-1, 1)`,
+/// including the Condon-Shortley phase if `condon_shortley` is set
+pub fn deriv_alt_array_ext(
+    norm: Normalization,
+    l_max: usize,
+    x: f64,
+    condon_shortley: bool,
+) -> Result<(Vec<f64>, Vec<f64>)> {
+    derivs(
+        gsl_sf_legendre_deriv_alt_array_e,
+        norm,
+        l_max,
+        x,
+        condon_shortley,
+    )
+}
+
+type DerivArray = unsafe extern "C" fn(
+    gsl_sf_legendre_t,
+    u64,
+    f64,
+    f64,
+    *mut f64,
+    *mut f64,
+) -> std::os::raw::c_int;
+
+fn derivs(
+    f: DerivArray,
+    norm: Normalization,
+    l_max: usize,
+    x: f64,
+    condon_shortley: bool,
+) -> Result<(Vec<f64>, Vec<f64>)> {
+    unsafe {
+        if !(x > -1.0 && x < 1.0) {
+            return Err(GSLError::Domain);
+        }
+
+        let n = gsl_sf_legendre_array_n(l_max as u64) as usize;
+        let mut result = vec![0.0; n];
+        let mut deriv = vec![0.0; n];
+        GSLError::from_raw(f(
+            norm.as_gsl(),
+            l_max as u64,
+            x,
+            csphase(condon_shortley),
+            result.as_mut_ptr(),
+            deriv.as_mut_ptr(),
+        ))?;
+        result.truncate(array_len(l_max));
+        deriv.truncate(array_len(l_max));
+        Ok((result, deriv))
+    }
+}
+
+fn csphase(condon_shortley: bool) -> f64 {
+    if condon_shortley {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+#[test]
+fn test_legendre_array() {
+    disable_error_handler();
+
+    assert_eq!(array_len(3), 10);
+    assert_eq!(array_index(0, 0), 0);
+    assert_eq!(array_index(2, 1), 4);
+    assert_eq!(array_index(3, 3), 9);
+
+    // Low degrees in closed form
+    let x = 0.3f64;
+    let s = (1.0 - x * x).sqrt();
+    let p = array(Normalization::None, 2, x).unwrap();
+    assert_eq!(p.len(), 6);
+    let expected = [
+        (0, 0, 1.0),
+        (1, 0, x),
+        (1, 1, s),
+        (2, 0, 0.5 * (3.0 * x * x - 1.0)),
+        (2, 1, 3.0 * x * s),
+        (2, 2, 3.0 * s * s),
+    ];
+    for (l, m, value) in expected {
+        approx::assert_abs_diff_eq!(p[array_index(l, m)], value, epsilon = 1.0e-14);
+    }
+
+    // The phase flips the odd orders
+    let phased = array_ext(Normalization::None, 2, x, true).unwrap();
+    approx::assert_abs_diff_eq!(phased[array_index(2, 1)], -3.0 * x * s, epsilon = 1.0e-14);
+    approx::assert_abs_diff_eq!(phased[array_index(2, 2)], 3.0 * s * s, epsilon = 1.0e-14);
+
+    // Schmidt and spherical harmonic normalizations of P_2^1
+    let schmidt = array(Normalization::Schmidt, 2, x).unwrap();
+    approx::assert_abs_diff_eq!(
+        schmidt[array_index(2, 1)],
+        (2.0f64 / 6.0).sqrt() * 3.0 * x * s,
+        epsilon = 1.0e-14
+    );
+    let spherical = array(Normalization::SphericalHarmonic, 2, x).unwrap();
+    approx::assert_abs_diff_eq!(
+        spherical[array_index(2, 1)],
+        (5.0 / (4.0 * std::f64::consts::PI) / 6.0).sqrt() * 3.0 * x * s,
+        epsilon = 1.0e-14
+    );
+
+    // Fully normalized functions of the same order are orthonormal on [-1, 1]
+    let l_max = 8;
+    let table = integrate::GLFixedTable::new(20).unwrap();
+    let (nodes, weights) = table.points(-1.0, 1.0).unwrap();
+    let values = nodes
+        .iter()
+        .map(|x| array(Normalization::Full, l_max, *x).unwrap())
+        .collect::<Vec<_>>();
+    for m in 0..=l_max {
+        for l1 in m..=l_max {
+            for l2 in m..=l_max {
+                let (i, j) = (array_index(l1, m), array_index(l2, m));
+                let product = values
+                    .iter()
+                    .zip(&weights)
+                    .map(|(p, w)| w * p[i] * p[j])
+                    .sum::<f64>();
+                let expected = if l1 == l2 { 1.0 } else { 0.0 };
+                approx::assert_abs_diff_eq!(product, expected, epsilon = 1.0e-12);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_legendre_derivs() {
+    disable_error_handler();
+
+    // Derivatives agree with central differences, and dP/dθ = -sin θ dP/dx
+    let (l_max, x, h) = (6, 0.4f64, 1.0e-6);
+    for norm in [
+        Normalization::None,
+        Normalization::Schmidt,
+        Normalization::SphericalHarmonic,
+        Normalization::Full,
+    ] {
+        let (p, dp) = deriv_array_ext(norm, l_max, x, true).unwrap();
+        let (p_alt, dp_alt) = deriv_alt_array_ext(norm, l_max, x, true).unwrap();
+        let plus = array_ext(norm, l_max, x + h, true).unwrap();
+        let minus = array_ext(norm, l_max, x - h, true).unwrap();
+        let values = array_ext(norm, l_max, x, true).unwrap();
+        for k in 0..array_len(l_max) {
+            let scale = dp[k].abs().max(p[k].abs()).max(1.0);
+            approx::assert_relative_eq!(p[k], values[k], max_relative = 1.0e-14);
+            approx::assert_relative_eq!(p_alt[k], values[k], max_relative = 1.0e-14);
+            approx::assert_abs_diff_eq!(
+                dp[k],
+                (plus[k] - minus[k]) / (2.0 * h),
+                epsilon = 1.0e-6 * scale
+            );
+            approx::assert_abs_diff_eq!(
+                dp_alt[k],
+                -(1.0 - x * x).sqrt() * dp[k],
+                epsilon = 1.0e-12 * scale
+            );
+        }
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Outside of [-1, 1]
+    array(Normalization::None, 3, 1.5).unwrap_err();
+    array(Normalization::Full, 3, f64::NAN).unwrap_err();
+
+    // Derivatives with respect to x diverge at the end points
+    deriv_array(Normalization::None, 3, 1.0).unwrap_err();
+    deriv_alt_array(Normalization::Schmidt, 3, -1.0).unwrap_err();
+}
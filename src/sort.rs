@@ -1,5 +1,5 @@
 /*
-    sorting.rs
+    sort.rs
     Copyright (C) 2021 Pim van den Berg
 
     This program is free software: you can redistribute it and/or modify
@@ -29,6 +29,108 @@ pub fn sort_xy(x: &mut [f64], y: &mut [f64]) {
     }
 }
 
+/// Sorts `x` in ascending order with heapsort, which isn't stable
+pub fn sort(x: &mut [f64]) {
+    unsafe { gsl_sort(x.as_mut_ptr(), 1, x.len() as u64) }
+}
+
+/// Permutation that sorts `x`: `x[p[0]]` is the smallest element
+pub fn sort_index(x: &[f64]) -> Vec<usize> {
+    unsafe {
+        let mut p = vec![0u64; x.len()];
+        gsl_sort_index(p.as_mut_ptr(), x.as_ptr(), 1, x.len() as u64);
+        p.into_iter().map(|i| i as usize).collect()
+    }
+}
+
+/// The `k` smallest elements of `x` in ascending order
+pub fn smallest(x: &[f64], k: usize) -> Result<Vec<f64>> {
+    unsafe {
+        check_subset(x, k)?;
+        let mut dest = vec![0.0; k];
+        GSLError::from_raw(gsl_sort_smallest(
+            dest.as_mut_ptr(),
+            k as u64,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))?;
+        Ok(dest)
+    }
+}
+
+/// The `k` largest elements of `x` in descending order
+pub fn largest(x: &[f64], k: usize) -> Result<Vec<f64>> {
+    unsafe {
+        check_subset(x, k)?;
+        let mut dest = vec![0.0; k];
+        GSLError::from_raw(gsl_sort_largest(
+            dest.as_mut_ptr(),
+            k as u64,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))?;
+        Ok(dest)
+    }
+}
+
+/// Indices of the `k` smallest elements of `x`, in ascending order of the elements
+pub fn smallest_index(x: &[f64], k: usize) -> Result<Vec<usize>> {
+    unsafe {
+        check_subset(x, k)?;
+        let mut p = vec![0u64; k];
+        GSLError::from_raw(gsl_sort_smallest_index(
+            p.as_mut_ptr(),
+            k as u64,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))?;
+        Ok(p.into_iter().map(|i| i as usize).collect())
+    }
+}
+
+/// Indices of the `k` largest elements of `x`, in descending order of the elements
+pub fn largest_index(x: &[f64], k: usize) -> Result<Vec<usize>> {
+    unsafe {
+        check_subset(x, k)?;
+        let mut p = vec![0u64; k];
+        GSLError::from_raw(gsl_sort_largest_index(
+            p.as_mut_ptr(),
+            k as u64,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))?;
+        Ok(p.into_iter().map(|i| i as usize).collect())
+    }
+}
+
+/// The `k`-th smallest element of `x`, counting from zero, which is `sort(x)[k]`.
+/// The data is copied, because GSL reorders it.
+pub fn select(x: &[f64], k: usize) -> Result<f64> {
+    unsafe {
+        if k >= x.len() {
+            return Err(GSLError::Invalid);
+        }
+        let mut copy = x.to_vec();
+        Ok(gsl_stats_select(
+            copy.as_mut_ptr(),
+            1,
+            copy.len() as u64,
+            k as u64,
+        ))
+    }
+}
+
+fn check_subset(x: &[f64], k: usize) -> Result<()> {
+    if k > x.len() {
+        return Err(GSLError::Invalid);
+    }
+    Ok(())
+}
+
 /// This function assumes the data is sorted and uses the mean as a reducing function.
 ///
 /// See `dedup_x`.
@@ -146,3 +248,40 @@ fn test_dedup() {
     assert_eq!(&xdedup, &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h']);
     assert_eq!(&ydedup, &['3', 'b', 'c', 'd', 'e', '2', '2', 'h']);
 }
+
+#[test]
+fn test_sort() {
+    disable_error_handler();
+
+    let x = [3.0, -1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+    let mut sorted = x;
+    sort(&mut sorted);
+    assert_eq!(sorted, [-1.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 9.0]);
+
+    let p = sort_index(&x);
+    assert_eq!(p, [1, 3, 6, 0, 2, 4, 7, 5]);
+    for (i, &pi) in p.iter().enumerate() {
+        assert_eq!(x[pi], sorted[i]);
+    }
+
+    assert_eq!(smallest(&x, 3).unwrap(), [-1.0, 1.0, 2.0]);
+    assert_eq!(largest(&x, 2).unwrap(), [9.0, 6.0]);
+    assert_eq!(smallest_index(&x, 3).unwrap(), [1, 3, 6]);
+    assert_eq!(largest_index(&x, 2).unwrap(), [5, 7]);
+    assert!(smallest(&x, 0).unwrap().is_empty());
+
+    for (k, &xk) in sorted.iter().enumerate() {
+        assert_eq!(select(&x, k).unwrap(), xk);
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // More elements than the data has
+    smallest(&[1.0, 2.0], 3).unwrap_err();
+    largest_index(&[1.0, 2.0], 3).unwrap_err();
+    select(&[1.0, 2.0], 2).unwrap_err();
+    select(&[], 0).unwrap_err();
+}
@@ -0,0 +1,568 @@
+/*
+    sparse.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::*;
+
+/// Square sparse matrix in compressed sparse row format
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMatrix {
+    n: usize,
+    /// Start of each row in `cols` and `values`, of length `n + 1`
+    row_ptr: Box<[usize]>,
+    /// Column indices, sorted within each row
+    cols: Box<[usize]>,
+    values: Box<[f64]>,
+}
+
+impl SparseMatrix {
+    /// Builds an `n` by `n` matrix from `(i, j, A_ij)` triplets. Duplicate entries are summed.
+    pub fn from_triplets<T: IntoIterator<Item = (usize, usize, f64)>>(
+        n: usize,
+        triplets: T,
+    ) -> Result<Self> {
+        if n == 0 {
+            return Err(GSLError::Invalid);
+        }
+
+        let mut triplets = triplets.into_iter().collect::<Vec<_>>();
+        if triplets.iter().any(|&(i, j, _)| i >= n || j >= n) {
+            return Err(GSLError::BadLength);
+        }
+        triplets.sort_by_key(|&(i, j, _)| (i, j));
+
+        let mut row_ptr = vec![0; n + 1];
+        let mut cols: Vec<usize> = Vec::with_capacity(triplets.len());
+        let mut values: Vec<f64> = Vec::with_capacity(triplets.len());
+        let mut last = None;
+
+        for (i, j, x) in triplets {
+            if last == Some((i, j)) {
+                *values.last_mut().unwrap() += x;
+                continue;
+            }
+            last = Some((i, j));
+
+            row_ptr[i + 1] += 1;
+            cols.push(j);
+            values.push(x);
+        }
+
+        // Convert row counts to offsets
+        for i in 0..n {
+            row_ptr[i + 1] += row_ptr[i];
+        }
+
+        Ok(SparseMatrix {
+            n,
+            row_ptr: row_ptr.into_boxed_slice(),
+            cols: cols.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+        })
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Amount of stored elements
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        let row = self.row_ptr[i]..self.row_ptr[i + 1];
+        match self.cols[row.clone()].binary_search(&j) {
+            Ok(k) => self.values[row.start + k],
+            Err(_) => 0.0,
+        }
+    }
+
+    /// Iterates over the stored `(j, A_ij)` of row `i`
+    pub fn row(&self, i: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let row = self.row_ptr[i]..self.row_ptr[i + 1];
+        self.cols[row.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[row].iter().copied())
+    }
+
+    /// `y = A x`
+    pub fn matvec(&self, x: &[f64], y: &mut [f64]) {
+        assert_eq!(x.len(), self.n);
+        assert_eq!(y.len(), self.n);
+
+        for (i, y) in y.iter_mut().enumerate() {
+            *y = self.row(i).map(|(j, a)| a * x[j]).sum();
+        }
+    }
+}
+
+/// Approximate inverse `z = M^-1 r` used to accelerate iterative solvers
+pub trait Preconditioner {
+    fn apply(&self, r: &[f64], z: &mut [f64]);
+}
+
+/// No preconditioning
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Identity;
+
+impl Preconditioner for Identity {
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        z.copy_from_slice(r);
+    }
+}
+
+/// Diagonal scaling
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jacobi {
+    inv_diag: Box<[f64]>,
+}
+
+impl Jacobi {
+    pub fn new(a: &SparseMatrix) -> Result<Self> {
+        let inv_diag = (0..a.n())
+            .map(|i| {
+                let d = a.get(i, i);
+                if d == 0.0 {
+                    Err(GSLError::ZeroDiv)
+                } else {
+                    Ok(d.recip())
+                }
+            })
+            .collect::<Result<Box<[f64]>>>()?;
+        Ok(Jacobi { inv_diag })
+    }
+}
+
+impl Preconditioner for Jacobi {
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        for ((z, r), d) in z.iter_mut().zip(r.iter()).zip(self.inv_diag.iter()) {
+            *z = r * d;
+        }
+    }
+}
+
+/// Incomplete LU factorization without fill-in
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ilu0 {
+    /// Strictly lower part holds `L` (with implicit unit diagonal), the rest holds `U`
+    factors: SparseMatrix,
+    /// Position of the diagonal element of each row in `factors`
+    diag: Box<[usize]>,
+}
+
+impl Ilu0 {
+    pub fn new(a: &SparseMatrix) -> Result<Self> {
+        let n = a.n();
+        let mut factors = a.clone();
+
+        let diag = (0..n)
+            .map(|i| {
+                let row = factors.row_ptr[i]..factors.row_ptr[i + 1];
+                match factors.cols[row.clone()].binary_search(&i) {
+                    Ok(k) if factors.values[row.start + k] != 0.0 => Ok(row.start + k),
+                    _ => Err(GSLError::ZeroDiv),
+                }
+            })
+            .collect::<Result<Box<[usize]>>>()?;
+
+        // Position of column j in the current row, if stored
+        let mut position = vec![usize::MAX; n];
+
+        for i in 1..n {
+            let row = factors.row_ptr[i]..factors.row_ptr[i + 1];
+            for p in row.clone() {
+                position[factors.cols[p]] = p;
+            }
+
+            for p in row.clone() {
+                let k = factors.cols[p];
+                if k >= i {
+                    break;
+                }
+
+                let pivot = factors.values[diag[k]];
+                if pivot == 0.0 {
+                    return Err(GSLError::ZeroDiv);
+                }
+                let l_ik = factors.values[p] / pivot;
+                factors.values[p] = l_ik;
+
+                // Eliminate using the upper part of row k, restricted to the existing pattern
+                for q in diag[k] + 1..factors.row_ptr[k + 1] {
+                    let j = factors.cols[q];
+                    if position[j] != usize::MAX {
+                        factors.values[position[j]] -= l_ik * factors.values[q];
+                    }
+                }
+            }
+
+            for p in row {
+                position[factors.cols[p]] = usize::MAX;
+            }
+        }
+
+        Ok(Ilu0 { factors, diag })
+    }
+}
+
+impl Preconditioner for Ilu0 {
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        let f = &self.factors;
+        let n = f.n;
+
+        // Forward substitution L y = r
+        for i in 0..n {
+            let mut sum = r[i];
+            for p in f.row_ptr[i]..self.diag[i] {
+                sum -= f.values[p] * z[f.cols[p]];
+            }
+            z[i] = sum;
+        }
+
+        // Backward substitution U z = y
+        for i in (0..n).rev() {
+            let mut sum = z[i];
+            for p in self.diag[i] + 1..f.row_ptr[i + 1] {
+                sum -= f.values[p] * z[f.cols[p]];
+            }
+            z[i] = sum / f.values[self.diag[i]];
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterativeSolution {
+    pub x: Box<[f64]>,
+    pub niter: usize,
+    /// Final `|b - A x| / |b|`
+    pub relative_residual: f64,
+}
+
+/// Solves `A x = b` for a symmetric positive definite sparse `A` using conjugate gradients.
+pub fn cg(a: &SparseMatrix, b: &[f64]) -> Result<IterativeSolution> {
+    if b.len() != a.n() {
+        return Err(GSLError::BadLength);
+    }
+    cg_ext(
+        1000,
+        1.0e-9,
+        b,
+        None,
+        |x, y| a.matvec(x, y),
+        &Jacobi::new(a)?,
+    )
+}
+
+/// Matrix-free conjugate gradients for a symmetric positive definite operator `matvec(x, y)`: `y = A x`.
+///
+/// Iterates until `|b - A x| <= tol |b|`.
+pub fn cg_ext<F: FnMut(&[f64], &mut [f64]), P: Preconditioner>(
+    max_iter: usize,
+    tol: f64,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    mut matvec: F,
+    preconditioner: &P,
+) -> Result<IterativeSolution> {
+    let n = b.len();
+    let (mut x, b_norm) = iterative_init(tol, b, x0)?;
+
+    // r = b - A x
+    let mut r = vec![0.0; n];
+    matvec(&x, &mut r);
+    for (r, b) in r.iter_mut().zip(b.iter()) {
+        *r = b - *r;
+    }
+
+    let mut z = vec![0.0; n];
+    preconditioner.apply(&r, &mut z);
+    let mut p = z.clone();
+    let mut rz = blas::dot(&r, &z)?;
+    let mut ap = vec![0.0; n];
+
+    for iter in 0..=max_iter {
+        let relative_residual = blas::nrm2(&r) / b_norm;
+        if !relative_residual.is_finite() {
+            return Err(GSLError::BadFunction);
+        }
+        if relative_residual <= tol {
+            return Ok(IterativeSolution {
+                x: x.into_boxed_slice(),
+                niter: iter,
+                relative_residual,
+            });
+        }
+        if iter == max_iter {
+            break;
+        }
+
+        matvec(&p, &mut ap);
+        let pap = blas::dot(&p, &ap)?;
+        if pap <= 0.0 {
+            // The operator is not positive definite
+            return Err(GSLError::Domain);
+        }
+
+        let alpha = rz / pap;
+        blas::axpy(alpha, &p, &mut x)?;
+        blas::axpy(-alpha, &ap, &mut r)?;
+
+        preconditioner.apply(&r, &mut z);
+        let rz_new = blas::dot(&r, &z)?;
+        let beta = rz_new / rz;
+        rz = rz_new;
+
+        for (p, z) in p.iter_mut().zip(z.iter()) {
+            *p = z + beta * *p;
+        }
+    }
+
+    Err(GSLError::MaxIteration)
+}
+
+/// Solves `A x = b` for a general sparse `A` using BiCGSTAB.
+pub fn bicgstab(a: &SparseMatrix, b: &[f64]) -> Result<IterativeSolution> {
+    if b.len() != a.n() {
+        return Err(GSLError::BadLength);
+    }
+    bicgstab_ext(1000, 1.0e-9, b, None, |x, y| a.matvec(x, y), &Ilu0::new(a)?)
+}
+
+/// Matrix-free, right preconditioned BiCGSTAB for a general operator `matvec(x, y)`: `y = A x`.
+///
+/// Iterates until `|b - A x| <= tol |b|`.
+pub fn bicgstab_ext<F: FnMut(&[f64], &mut [f64]), P: Preconditioner>(
+    max_iter: usize,
+    tol: f64,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    mut matvec: F,
+    preconditioner: &P,
+) -> Result<IterativeSolution> {
+    let n = b.len();
+    let (mut x, b_norm) = iterative_init(tol, b, x0)?;
+
+    // r = b - A x
+    let mut r = vec![0.0; n];
+    matvec(&x, &mut r);
+    for (r, b) in r.iter_mut().zip(b.iter()) {
+        *r = b - *r;
+    }
+
+    let r_hat = r.clone();
+    let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+    let mut v = vec![0.0; n];
+    let mut p = vec![0.0; n];
+    let mut p_hat = vec![0.0; n];
+    let mut s_hat = vec![0.0; n];
+    let mut t = vec![0.0; n];
+
+    for iter in 0..=max_iter {
+        let relative_residual = blas::nrm2(&r) / b_norm;
+        if !relative_residual.is_finite() {
+            return Err(GSLError::BadFunction);
+        }
+        if relative_residual <= tol {
+            return Ok(IterativeSolution {
+                x: x.into_boxed_slice(),
+                niter: iter,
+                relative_residual,
+            });
+        }
+        if iter == max_iter {
+            break;
+        }
+
+        let rho_new = blas::dot(&r_hat, &r)?;
+        if rho_new == 0.0 || omega == 0.0 {
+            // Breakdown of the biorthogonalization
+            return Err(GSLError::Failed);
+        }
+
+        let beta = (rho_new / rho) * (alpha / omega);
+        rho = rho_new;
+        for ((p, r), v) in p.iter_mut().zip(r.iter()).zip(v.iter()) {
+            *p = r + beta * (*p - omega * v);
+        }
+
+        preconditioner.apply(&p, &mut p_hat);
+        matvec(&p_hat, &mut v);
+        alpha = rho / blas::dot(&r_hat, &v)?;
+
+        // s = r - alpha v, stored in r
+        blas::axpy(alpha, &p_hat, &mut x)?;
+        blas::axpy(-alpha, &v, &mut r)?;
+        if blas::nrm2(&r) / b_norm <= tol {
+            continue;
+        }
+
+        preconditioner.apply(&r, &mut s_hat);
+        matvec(&s_hat, &mut t);
+        let tt = blas::dot(&t, &t)?;
+        omega = if tt == 0.0 {
+            0.0
+        } else {
+            blas::dot(&t, &r)? / tt
+        };
+
+        blas::axpy(omega, &s_hat, &mut x)?;
+        blas::axpy(-omega, &t, &mut r)?;
+    }
+
+    Err(GSLError::MaxIteration)
+}
+
+/// Validates parameters and returns the starting vector and `|b|`
+fn iterative_init(tol: f64, b: &[f64], x0: Option<&[f64]>) -> Result<(Vec<f64>, f64)> {
+    if b.is_empty() {
+        return Err(GSLError::Invalid);
+    }
+    if tol.is_nan() || tol < 0.0 {
+        return Err(GSLError::BadTolerance);
+    }
+
+    let x = match x0 {
+        Some(x0) if x0.len() != b.len() => return Err(GSLError::BadLength),
+        Some(x0) => x0.to_owned(),
+        None => vec![0.0; b.len()],
+    };
+
+    // Avoid dividing by zero for a homogeneous system
+    let b_norm = blas::nrm2(b);
+    let b_norm = if b_norm == 0.0 { 1.0 } else { b_norm };
+
+    Ok((x, b_norm))
+}
+
+#[cfg(test)]
+fn laplacian_1d(n: usize, convection: f64) -> SparseMatrix {
+    let triplets = (0..n).flat_map(|i| {
+        let mut row = vec![(i, i, 2.0)];
+        if i > 0 {
+            row.push((i, i - 1, -1.0 - convection));
+        }
+        if i + 1 < n {
+            row.push((i, i + 1, -1.0 + convection));
+        }
+        row
+    });
+    SparseMatrix::from_triplets(n, triplets).unwrap()
+}
+
+#[test]
+fn test_sparse_matrix() {
+    let a = SparseMatrix::from_triplets(2, [(0, 0, 1.0), (1, 0, 2.0), (0, 0, 3.0)]).unwrap();
+    assert_eq!(a.nnz(), 2);
+    assert_eq!(a.get(0, 0), 4.0);
+    assert_eq!(a.get(1, 0), 2.0);
+    assert_eq!(a.get(1, 1), 0.0);
+
+    let mut y = [0.0; 2];
+    a.matvec(&[1.0, 1.0], &mut y);
+    assert_eq!(y, [4.0, 2.0]);
+}
+
+#[test]
+fn test_cg() {
+    let n = 100;
+    let a = laplacian_1d(n, 0.0);
+    let b = vec![1.0; n];
+
+    let check = |solution: &IterativeSolution| {
+        let mut ax = vec![0.0; n];
+        a.matvec(&solution.x, &mut ax);
+        for (ax, b) in ax.iter().zip(b.iter()) {
+            approx::assert_abs_diff_eq!(ax, b, epsilon = 1.0e-6);
+        }
+    };
+
+    check(&cg(&a, &b).unwrap());
+    check(&cg_ext(1000, 1.0e-10, &b, None, |x, y| a.matvec(x, y), &Identity).unwrap());
+
+    // ILU(0) is exact for a tridiagonal matrix
+    let ilu = cg_ext(
+        10,
+        1.0e-10,
+        &b,
+        None,
+        |x, y| a.matvec(x, y),
+        &Ilu0::new(&a).unwrap(),
+    )
+    .unwrap();
+    assert!(ilu.niter <= 2);
+    check(&ilu);
+}
+
+#[test]
+fn test_bicgstab() {
+    let n = 100;
+    let a = laplacian_1d(n, 0.3);
+    let b = (0..n).map(|i| (i as f64).sin()).collect::<Vec<_>>();
+
+    for solution in [
+        bicgstab(&a, &b).unwrap(),
+        bicgstab_ext(
+            1000,
+            1.0e-10,
+            &b,
+            None,
+            |x, y| a.matvec(x, y),
+            &Jacobi::new(&a).unwrap(),
+        )
+        .unwrap(),
+    ] {
+        let mut ax = vec![0.0; n];
+        a.matvec(&solution.x, &mut ax);
+        for (ax, b) in ax.iter().zip(b.iter()) {
+            approx::assert_abs_diff_eq!(ax, b, epsilon = 1.0e-6);
+        }
+    }
+}
+
+#[test]
+fn test_invalid_params() {
+    let a = laplacian_1d(3, 0.0);
+
+    // Out of bounds triplet
+    SparseMatrix::from_triplets(2, [(2, 0, 1.0)]).unwrap_err();
+
+    // Length mismatch
+    cg(&a, &[1.0; 2]).unwrap_err();
+    bicgstab(&a, &[1.0; 4]).unwrap_err();
+
+    // Not enough iterations
+    cg_ext(0, 1.0e-9, &[1.0; 3], None, |x, y| a.matvec(x, y), &Identity).unwrap_err();
+
+    // Zero diagonal
+    Jacobi::new(&SparseMatrix::from_triplets(2, [(0, 1, 1.0), (1, 0, 1.0)]).unwrap()).unwrap_err();
+
+    // Negative definite
+    cg_ext(
+        10,
+        1.0e-9,
+        &[1.0; 3],
+        None,
+        |x, y| {
+            a.matvec(x, y);
+            y.iter_mut().for_each(|y| *y = -*y);
+        },
+        &Identity,
+    )
+    .unwrap_err();
+}
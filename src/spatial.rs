@@ -0,0 +1,488 @@
+/*
+    spatial.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::rng::{Reproducibility, Rng};
+use crate::*;
+use std::f64::consts::PI;
+
+/*
+
+    Second order statistics of 2D point patterns observed in a rectangular window.
+
+    For complete spatial randomness (a homogeneous Poisson process)
+    `K(r) = π r^2`, `L(r) = r` and `g(r) = 1`.
+    Larger values indicate clustering at distance `r`, smaller values regularity.
+
+    Pairs near the boundary of the window are undercounted,
+    because their neighbours outside of the window are not observed,
+    which is what the edge corrections compensate for.
+
+*/
+
+/// Rectangular observation window
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Window {
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+}
+
+impl Window {
+    pub fn new(x_min: f64, x_max: f64, y_min: f64, y_max: f64) -> Result<Self> {
+        let valid = x_min < x_max
+            && y_min < y_max
+            && (x_max - x_min).is_finite()
+            && (y_max - y_min).is_finite();
+        if !valid {
+            return Err(GSLError::Domain);
+        }
+        Ok(Window {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+        })
+    }
+
+    pub fn width(&self) -> f64 {
+        self.x_max - self.x_min
+    }
+
+    pub fn height(&self) -> f64 {
+        self.y_max - self.y_min
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    pub fn contains(&self, [x, y]: [f64; 2]) -> bool {
+        self.x_min <= x && x <= self.x_max && self.y_min <= y && y <= self.y_max
+    }
+
+    /// Distance from a point inside the window to the boundary
+    fn boundary_distance(&self, [x, y]: [f64; 2]) -> f64 {
+        (x - self.x_min)
+            .min(self.x_max - x)
+            .min(y - self.y_min)
+            .min(self.y_max - y)
+    }
+
+    /// Fraction of the circle around a point inside the window with radius `r` that lies inside
+    fn circle_fraction(&self, [x, y]: [f64; 2], r: f64) -> f64 {
+        // Half angle of the arc beyond each edge, in counterclockwise order
+        let half_angle = |e: f64| if e < r { (e / r).acos() } else { 0.0 };
+        let edges = [
+            x - self.x_min,
+            y - self.y_min,
+            self.x_max - x,
+            self.y_max - y,
+        ];
+        let angles = edges.map(half_angle);
+
+        // Arcs beyond adjacent edges overlap if the corner lies inside the circle
+        let mut outside = 2.0 * angles.iter().sum::<f64>();
+        for i in 0..4 {
+            let (a, b) = (angles[i], angles[(i + 1) % 4]);
+            outside -= (a + b - PI / 2.0).max(0.0);
+        }
+
+        (1.0 - outside / (2.0 * PI)).max(0.0)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EdgeCorrection {
+    /// No correction, biased downwards for larger distances
+    None,
+    /// Only points at least `r` from the boundary are used as centers
+    Border,
+    /// Pairs are weighted by the inverse of the area of the window
+    /// intersected with its translation by the pair separation (Ohser and Stoyan)
+    Translation,
+    /// Pairs are weighted by the inverse of the fraction of the circle through the second point,
+    /// centered at the first, that lies inside the window (Ripley)
+    Isotropic,
+}
+
+/// Ripley's K function at the distances `r`
+pub fn ripley_k(
+    points: &[[f64; 2]],
+    window: &Window,
+    r: &[f64],
+    correction: EdgeCorrection,
+) -> Result<Box<[f64]>> {
+    check_points(points, window)?;
+    if r.iter().any(|&r| !(r >= 0.0 && r.is_finite())) {
+        return Err(GSLError::Domain);
+    }
+
+    let n = points.len() as f64;
+    let area = window.area();
+
+    if correction == EdgeCorrection::Border {
+        let boundary = points
+            .iter()
+            .map(|&p| window.boundary_distance(p))
+            .collect::<Vec<_>>();
+        return Ok(r
+            .iter()
+            .map(|&r| {
+                let mut centers = 0;
+                let mut count = 0;
+                for (i, &p) in points.iter().enumerate() {
+                    if boundary[i] < r {
+                        continue;
+                    }
+                    centers += 1;
+                    count += points
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, &q)| j != i && distance(p, q) <= r)
+                        .count();
+                }
+                if centers == 0 {
+                    f64::NAN
+                } else {
+                    area * count as f64 / ((n - 1.0) * centers as f64)
+                }
+            })
+            .collect());
+    }
+
+    let pairs = weighted_pairs(points, window, correction);
+    Ok(r.iter()
+        .map(|&r| {
+            let sum = pairs
+                .iter()
+                .filter(|(d, _)| *d <= r)
+                .map(|(_, w)| w)
+                .sum::<f64>();
+            area * sum / (n * (n - 1.0))
+        })
+        .collect())
+}
+
+/// Besag's L function `sqrt(K(r) / π)`, which is `r` for complete spatial randomness
+pub fn ripley_l(
+    points: &[[f64; 2]],
+    window: &Window,
+    r: &[f64],
+    correction: EdgeCorrection,
+) -> Result<Box<[f64]>> {
+    let mut l = ripley_k(points, window, r, correction)?;
+    for l in l.iter_mut() {
+        *l = (*l / PI).sqrt();
+    }
+    Ok(l)
+}
+
+/// Pair correlation function `g(r) = K'(r) / (2πr)` at the distances `r > 0`,
+/// estimated with an Epanechnikov kernel of half width `bandwidth`.
+/// The border correction is not supported.
+pub fn pair_correlation(
+    points: &[[f64; 2]],
+    window: &Window,
+    r: &[f64],
+    bandwidth: f64,
+    correction: EdgeCorrection,
+) -> Result<Box<[f64]>> {
+    check_points(points, window)?;
+    if correction == EdgeCorrection::Border {
+        return Err(GSLError::Invalid);
+    }
+    if !(bandwidth > 0.0 && bandwidth.is_finite()) {
+        return Err(GSLError::Domain);
+    }
+    if r.iter().any(|&r| !(r > 0.0 && r.is_finite())) {
+        return Err(GSLError::Domain);
+    }
+
+    let n = points.len() as f64;
+    let area = window.area();
+    let kernel = |u: f64| {
+        let u = u / bandwidth;
+        if u.abs() < 1.0 {
+            0.75 * (1.0 - u * u) / bandwidth
+        } else {
+            0.0
+        }
+    };
+
+    let pairs = weighted_pairs(points, window, correction);
+    Ok(r.iter()
+        .map(|&r| {
+            let sum = pairs.iter().map(|(d, w)| w * kernel(r - d)).sum::<f64>();
+            area * sum / (n * (n - 1.0) * 2.0 * PI * r)
+        })
+        .collect())
+}
+
+/// Pointwise Monte Carlo envelope of a summary statistic under complete spatial randomness
+#[derive(Clone, Debug, PartialEq)]
+pub struct Envelope {
+    /// Statistic of the observed pattern
+    pub observed: Box<[f64]>,
+    /// Pointwise minimum over the simulations
+    pub lower: Box<[f64]>,
+    /// Pointwise maximum over the simulations
+    pub upper: Box<[f64]>,
+    /// Pointwise mean over the simulations
+    pub mean: Box<[f64]>,
+}
+
+impl Envelope {
+    /// Indices where the observed statistic lies outside of the envelope
+    pub fn outside(&self) -> Vec<usize> {
+        (0..self.observed.len())
+            .filter(|&i| self.observed[i] < self.lower[i] || self.observed[i] > self.upper[i])
+            .collect()
+    }
+}
+
+/// Evaluates `statistic` on the observed points and on `nsim` simulated patterns
+/// of the same number of uniformly distributed points in the window.
+/// With `nsim = 99` a point outside of the envelope is significant at the 2% level (two-sided).
+pub fn csr_envelope<F: FnMut(&[[f64; 2]]) -> Result<Box<[f64]>>>(
+    points: &[[f64; 2]],
+    window: &Window,
+    nsim: usize,
+    rng: &mut Rng,
+    mut statistic: F,
+) -> Result<Envelope> {
+    check_points(points, window)?;
+    if nsim == 0 {
+        return Err(GSLError::Invalid);
+    }
+
+    let observed = statistic(points)?;
+    let m = observed.len();
+    let mut lower = vec![f64::INFINITY; m].into_boxed_slice();
+    let mut upper = vec![f64::NEG_INFINITY; m].into_boxed_slice();
+    let mut mean = vec![0.0; m].into_boxed_slice();
+
+    let mut simulated = vec![[0.0; 2]; points.len()];
+    for _ in 0..nsim {
+        for p in simulated.iter_mut() {
+            *p = [
+                window.x_min + window.width() * rng.uniform(),
+                window.y_min + window.height() * rng.uniform(),
+            ];
+        }
+
+        let values = statistic(&simulated)?;
+        if values.len() != m {
+            return Err(GSLError::BadLength);
+        }
+        for i in 0..m {
+            lower[i] = lower[i].min(values[i]);
+            upper[i] = upper[i].max(values[i]);
+            mean[i] += values[i] / nsim as f64;
+        }
+    }
+
+    Ok(Envelope {
+        observed,
+        lower,
+        upper,
+        mean,
+    })
+}
+
+/// `csr_envelope` with the simulations drawn from the stream `stream` of `context`
+pub fn csr_envelope_reproducible<F: FnMut(&[[f64; 2]]) -> Result<Box<[f64]>>>(
+    points: &[[f64; 2]],
+    window: &Window,
+    nsim: usize,
+    context: &mut Reproducibility,
+    stream: &str,
+    statistic: F,
+) -> Result<Envelope> {
+    csr_envelope(points, window, nsim, &mut context.rng(stream), statistic)
+}
+
+fn check_points(points: &[[f64; 2]], window: &Window) -> Result<()> {
+    if points.len() < 2 {
+        return Err(GSLError::Invalid);
+    }
+    if points.iter().any(|&p| !window.contains(p)) {
+        return Err(GSLError::Domain);
+    }
+    Ok(())
+}
+
+fn distance(p: [f64; 2], q: [f64; 2]) -> f64 {
+    (p[0] - q[0]).hypot(p[1] - q[1])
+}
+
+/// Distances and edge correction weights of all ordered pairs of distinct points
+fn weighted_pairs(
+    points: &[[f64; 2]],
+    window: &Window,
+    correction: EdgeCorrection,
+) -> Vec<(f64, f64)> {
+    let area = window.area();
+    let mut pairs = Vec::with_capacity(points.len() * (points.len() - 1));
+    for (i, &p) in points.iter().enumerate() {
+        for (j, &q) in points.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let d = distance(p, q);
+            let w = match correction {
+                EdgeCorrection::None | EdgeCorrection::Border => 1.0,
+                EdgeCorrection::Translation => {
+                    let overlap = (window.width() - (p[0] - q[0]).abs())
+                        * (window.height() - (p[1] - q[1]).abs());
+                    area / overlap
+                }
+                EdgeCorrection::Isotropic => {
+                    if d == 0.0 {
+                        1.0
+                    } else {
+                        1.0 / window.circle_fraction(p, d)
+                    }
+                }
+            };
+            pairs.push((d, w));
+        }
+    }
+    pairs
+}
+
+#[test]
+fn test_circle_fraction() {
+    let window = Window::new(0.0, 1.0, 0.0, 1.0).unwrap();
+
+    // Fully inside
+    approx::assert_abs_diff_eq!(window.circle_fraction([0.5, 0.5], 0.2), 1.0);
+
+    // Half beyond a single edge
+    approx::assert_abs_diff_eq!(
+        window.circle_fraction([0.0, 0.5], 0.2),
+        0.5,
+        epsilon = 1.0e-14
+    );
+
+    // Quarter in a corner
+    approx::assert_abs_diff_eq!(
+        window.circle_fraction([0.0, 0.0], 0.2),
+        0.25,
+        epsilon = 1.0e-14
+    );
+}
+
+#[test]
+fn test_ripley() {
+    disable_error_handler();
+
+    let window = Window::new(0.0, 1.0, 0.0, 2.0).unwrap();
+    let r = [0.05, 0.1, 0.15, 0.2];
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 4);
+
+    // Complete spatial randomness
+    let points = (0..1000)
+        .map(|_| [rng.uniform(), 2.0 * rng.uniform()])
+        .collect::<Vec<_>>();
+    for correction in [
+        EdgeCorrection::Border,
+        EdgeCorrection::Translation,
+        EdgeCorrection::Isotropic,
+    ] {
+        let l = ripley_l(&points, &window, &r, correction).unwrap();
+        for (l, r) in l.iter().zip(r.iter()) {
+            approx::assert_abs_diff_eq!(l, r, epsilon = 0.015);
+        }
+    }
+
+    // Without a correction the boundary causes a bias
+    let l = ripley_l(&points, &window, &r, EdgeCorrection::None).unwrap();
+    assert!(l[3] < 0.99 * r[3]);
+
+    let g = pair_correlation(&points, &window, &r, 0.02, EdgeCorrection::Translation).unwrap();
+    for g in g.iter() {
+        approx::assert_abs_diff_eq!(*g, 1.0, epsilon = 0.15);
+    }
+
+    // Clusters of 10 points with radius 0.02
+    let mut clustered = Vec::new();
+    for _ in 0..50 {
+        let center = [0.1 + 0.8 * rng.uniform(), 0.1 + 1.8 * rng.uniform()];
+        for _ in 0..10 {
+            let angle = 2.0 * PI * rng.uniform();
+            let radius = 0.02 * rng.uniform().sqrt();
+            clustered.push([
+                center[0] + radius * angle.cos(),
+                center[1] + radius * angle.sin(),
+            ]);
+        }
+    }
+
+    let envelope = csr_envelope(&clustered, &window, 19, &mut rng, |points| {
+        ripley_l(points, &window, &r, EdgeCorrection::Isotropic)
+    })
+    .unwrap();
+    assert_eq!(envelope.outside(), vec![0, 1, 2, 3]);
+    for i in 0..4 {
+        assert!(envelope.lower[i] <= envelope.mean[i] && envelope.mean[i] <= envelope.upper[i]);
+    }
+
+    // The same stream gives the same envelope
+    let envelope = |context: &mut Reproducibility| {
+        csr_envelope_reproducible(&clustered, &window, 19, context, "envelope", |points| {
+            ripley_l(points, &window, &r, EdgeCorrection::Isotropic)
+        })
+        .unwrap()
+    };
+    let mut context = Reproducibility::new(1);
+    assert_eq!(
+        envelope(&mut context),
+        envelope(&mut Reproducibility::new(1))
+    );
+    assert_eq!(context.streams().len(), 1);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Empty window
+    Window::new(0.0, 0.0, 0.0, 1.0).unwrap_err();
+
+    let window = Window::new(0.0, 1.0, 0.0, 1.0).unwrap();
+
+    // Too few points
+    ripley_k(&[[0.5, 0.5]], &window, &[0.1], EdgeCorrection::None).unwrap_err();
+
+    // Point outside of the window
+    let points = [[0.5, 0.5], [1.5, 0.5]];
+    ripley_k(&points, &window, &[0.1], EdgeCorrection::None).unwrap_err();
+
+    // Negative distance
+    let points = [[0.5, 0.5], [0.2, 0.5]];
+    ripley_k(&points, &window, &[-0.1], EdgeCorrection::None).unwrap_err();
+
+    // Unsupported correction and invalid bandwidth
+    pair_correlation(&points, &window, &[0.1], 0.01, EdgeCorrection::Border).unwrap_err();
+    pair_correlation(&points, &window, &[0.1], 0.0, EdgeCorrection::None).unwrap_err();
+
+    // No simulations
+    let mut rng = Rng::with_seed(rng::RngType::Mt19937, 0);
+    csr_envelope(&points, &window, 0, &mut rng, |_| Ok(Box::new([]))).unwrap_err();
+}
@@ -0,0 +1,294 @@
+/*
+    spectrum.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::histogram::Histogram;
+use crate::*;
+
+/*
+
+    Rebinning assumes the content of every bin to be uniformly distributed over its range,
+    so an old bin contributes to a new bin in proportion to their overlap.
+    The errors of the old bins are taken as independent. Splitting one old bin over several new bins
+    correlates those, which the propagated errors don't describe.
+
+    Merging takes the inverse-variance weighted mean of every bin,
+    `x = Σ x_k / σ_k^2 / Σ 1 / σ_k^2` with error `1 / sqrt(Σ 1 / σ_k^2)`.
+    A measurement with an infinite error in some bin doesn't contribute to that bin.
+
+*/
+
+/// Binned spectrum with an uncertainty for every bin.
+/// Bin `i` covers the range `[edges[i], edges[i + 1])`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+    edges: Box<[f64]>,
+    values: Box<[f64]>,
+    errors: Box<[f64]>,
+}
+
+impl Spectrum {
+    /// Spectrum with `edges.len() - 1` bins, the edges must be strictly increasing
+    pub fn new(edges: &[f64], values: &[f64], errors: &[f64]) -> Result<Self> {
+        check_edges(edges)?;
+        if values.len() != edges.len() - 1 || errors.len() != values.len() {
+            return Err(GSLError::BadLength);
+        }
+        if errors.iter().any(|&e| e.is_nan() || e < 0.0) {
+            return Err(GSLError::Domain);
+        }
+
+        Ok(Spectrum {
+            edges: edges.into(),
+            values: values.into(),
+            errors: errors.into(),
+        })
+    }
+
+    /// Spectrum of the counts in a histogram, with Poisson errors `sqrt(|n|)`
+    pub fn from_histogram(histogram: &Histogram) -> Self {
+        let values = histogram.bins();
+        let errors = values.iter().map(|n| n.abs().sqrt()).collect();
+        Spectrum {
+            edges: histogram.ranges(),
+            values,
+            errors,
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn edges(&self) -> &[f64] {
+        &self.edges
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn errors(&self) -> &[f64] {
+        &self.errors
+    }
+
+    /// Sum of all bins, with its error
+    pub fn sum(&self) -> (f64, f64) {
+        let sum = self.values.iter().sum();
+        let error = self.errors.iter().map(|e| e * e).sum::<f64>().sqrt();
+        (sum, error)
+    }
+
+    /// Spectrum on new bin edges, which have to lie within the current range.
+    /// Fails with `GSLError::Domain` otherwise.
+    pub fn rebin(&self, edges: &[f64]) -> Result<Spectrum> {
+        check_edges(edges)?;
+        let (min, max) = (self.edges[0], self.edges[self.n()]);
+        if edges[0] < min || edges[edges.len() - 1] > max {
+            return Err(GSLError::Domain);
+        }
+
+        let n = edges.len() - 1;
+        let mut values = vec![0.0; n];
+        let mut variances = vec![0.0; n];
+
+        // Both edge lists are sorted, so sweep through the overlapping pairs of bins
+        let mut i = 0;
+        for j in 0..n {
+            while self.edges[i + 1] <= edges[j] {
+                i += 1;
+            }
+            let mut k = i;
+            while k < self.n() && self.edges[k] < edges[j + 1] {
+                let lower = self.edges[k].max(edges[j]);
+                let upper = self.edges[k + 1].min(edges[j + 1]);
+                let fraction = (upper - lower) / (self.edges[k + 1] - self.edges[k]);
+                values[j] += fraction * self.values[k];
+                variances[j] += fraction * fraction * self.errors[k] * self.errors[k];
+                k += 1;
+            }
+        }
+
+        Ok(Spectrum {
+            edges: edges.into(),
+            values: values.into_boxed_slice(),
+            errors: variances.iter().map(|v| v.sqrt()).collect(),
+        })
+    }
+
+    /// Spectrum with every `factor` consecutive bins combined.
+    /// The number of bins has to be a multiple of `factor`.
+    pub fn merge_bins(&self, factor: usize) -> Result<Spectrum> {
+        if factor == 0 || !self.n().is_multiple_of(factor) {
+            return Err(GSLError::Invalid);
+        }
+
+        let edges = self
+            .edges
+            .iter()
+            .step_by(factor)
+            .copied()
+            .collect::<Vec<_>>();
+        self.rebin(&edges)
+    }
+}
+
+/// Inverse-variance weighted mean of several measurements of the same spectrum.
+/// Fails with `GSLError::Invalid` if the bin edges differ
+/// and with `GSLError::Domain` if a bin has a zero error or no measurement with a finite error.
+pub fn merge(spectra: &[Spectrum]) -> Result<Spectrum> {
+    let first = spectra.first().ok_or(GSLError::Invalid)?;
+    if spectra.iter().any(|s| s.edges != first.edges) {
+        return Err(GSLError::Invalid);
+    }
+
+    let n = first.n();
+    let mut values = vec![0.0; n];
+    let mut errors = vec![0.0; n];
+    for i in 0..n {
+        let mut weights = 0.0;
+        let mut sum = 0.0;
+        for spectrum in spectra {
+            let error = spectrum.errors[i];
+            if error == 0.0 {
+                return Err(GSLError::Domain);
+            }
+            if error.is_finite() {
+                let weight = 1.0 / (error * error);
+                weights += weight;
+                sum += weight * spectrum.values[i];
+            }
+        }
+        if weights == 0.0 {
+            return Err(GSLError::Domain);
+        }
+        values[i] = sum / weights;
+        errors[i] = 1.0 / weights.sqrt();
+    }
+
+    Ok(Spectrum {
+        edges: first.edges.clone(),
+        values: values.into_boxed_slice(),
+        errors: errors.into_boxed_slice(),
+    })
+}
+
+fn check_edges(edges: &[f64]) -> Result<()> {
+    if edges.len() < 2 {
+        return Err(GSLError::Invalid);
+    }
+    if edges.iter().any(|x| !x.is_finite()) || edges.windows(2).any(|w| w[1] <= w[0]) {
+        return Err(GSLError::Domain);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_rebin() {
+    disable_error_handler();
+
+    let s = Spectrum::new(
+        &[0.0, 1.0, 2.0, 3.0, 4.0],
+        &[4.0, 8.0, 2.0, 6.0],
+        &[2.0, 1.0, 2.0, 3.0],
+    )
+    .unwrap();
+
+    // Combining bins adds the values and the variances
+    let merged = s.merge_bins(2).unwrap();
+    assert_eq!(merged.edges(), &[0.0, 2.0, 4.0]);
+    assert_eq!(merged.values(), &[12.0, 8.0]);
+    approx::assert_abs_diff_eq!(merged.errors()[0], 5.0f64.sqrt());
+    approx::assert_abs_diff_eq!(merged.errors()[1], 13.0f64.sqrt());
+
+    // Splitting bins shares the content and scales the error
+    let split = s.rebin(&[0.5, 1.0, 1.25, 2.5]).unwrap();
+    approx::assert_abs_diff_eq!(split.values(), &[2.0, 2.0, 7.0][..]);
+    approx::assert_abs_diff_eq!(split.errors()[0], 1.0);
+    approx::assert_abs_diff_eq!(split.errors()[1], 0.25);
+    approx::assert_abs_diff_eq!(split.errors()[2], (0.75f64.powi(2) + 1.0).sqrt());
+
+    // Rebinning onto the full range conserves the sum
+    let coarse = s.rebin(&[0.0, 0.3, 2.7, 4.0]).unwrap();
+    approx::assert_abs_diff_eq!(coarse.sum().0, s.sum().0, epsilon = 1.0e-12);
+
+    // Identical edges change nothing
+    assert_eq!(s.rebin(s.edges()).unwrap(), s);
+
+    let mut h = Histogram::uniform(2, 0.0, 2.0).unwrap();
+    for x in [0.5, 1.5, 1.5, 1.5, 1.5] {
+        h.increment(x).unwrap();
+    }
+    let s = Spectrum::from_histogram(&h);
+    assert_eq!(s.values(), &[1.0, 4.0]);
+    assert_eq!(s.errors(), &[1.0, 2.0]);
+}
+
+#[test]
+fn test_merge() {
+    disable_error_handler();
+
+    let edges = [0.0, 1.0, 2.0];
+    let a = Spectrum::new(&edges, &[1.0, 5.0], &[1.0, 2.0]).unwrap();
+    let b = Spectrum::new(&edges, &[3.0, 7.0], &[1.0, f64::INFINITY]).unwrap();
+    let merged = merge(&[a.clone(), b]).unwrap();
+
+    approx::assert_abs_diff_eq!(merged.values()[0], 2.0);
+    approx::assert_abs_diff_eq!(merged.errors()[0], 0.5f64.sqrt());
+
+    // The infinite error leaves the first measurement
+    approx::assert_abs_diff_eq!(merged.values()[1], 5.0);
+    approx::assert_abs_diff_eq!(merged.errors()[1], 2.0);
+
+    // Merging a single measurement changes nothing
+    assert_eq!(merge(std::slice::from_ref(&a)).unwrap(), a);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Edges not increasing
+    Spectrum::new(&[0.0, 2.0, 1.0], &[1.0, 1.0], &[1.0, 1.0]).unwrap_err();
+    Spectrum::new(&[0.0], &[], &[]).unwrap_err();
+
+    // Length mismatch
+    Spectrum::new(&[0.0, 1.0, 2.0], &[1.0], &[1.0]).unwrap_err();
+
+    // Negative error
+    Spectrum::new(&[0.0, 1.0], &[1.0], &[-1.0]).unwrap_err();
+
+    let s = Spectrum::new(&[0.0, 1.0, 2.0], &[1.0, 2.0], &[1.0, 0.0]).unwrap();
+
+    // Outside the range
+    s.rebin(&[-1.0, 1.0]).unwrap_err();
+    s.rebin(&[1.0, 3.0]).unwrap_err();
+
+    // Not a divisor of the number of bins
+    s.merge_bins(3).unwrap_err();
+    s.merge_bins(0).unwrap_err();
+
+    // Zero error
+    merge(&[s.clone(), s.clone()]).unwrap_err();
+
+    // Different edges
+    let t = Spectrum::new(&[0.0, 1.0, 3.0], &[1.0, 2.0], &[1.0, 1.0]).unwrap();
+    merge(&[t.clone(), t.rebin(&[0.0, 1.0, 2.0]).unwrap()]).unwrap_err();
+
+    // Nothing to merge
+    merge(&[]).unwrap_err();
+}
@@ -16,7 +16,35 @@
     along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-//use crate::bindings::*;
+#[cfg(feature = "gsl")]
+use crate::bindings::*;
+use crate::*;
+#[cfg(not(feature = "gsl"))]
+use native::*;
+use std::borrow::Cow;
+
+#[cfg(not(feature = "gsl"))]
+mod native;
+
+/*
+
+    Functions with the suffix `_sorted` require the data to be sorted in ascending order.
+    This is not checked.
+
+    Variances are sample variances, normalized by `n - 1`.
+
+    The row and column reductions take a row major `m` by `n` matrix. Columns are passed to GSL
+    with a stride, so no data is copied. The optional `mask` of the same size as the matrix
+    selects the elements to include, e.g. to skip missing values. Rows and columns without
+    selected elements have a NaN mean, and a variance needs two of them.
+
+    GSL passes NaN values on to the result. `with_nan_policy` applies any statistic
+    with NaN values left out, replaced, or rejected instead, and `try_with_nan_policy`
+    does so for the statistics that return a `Result`.
+
+    Without the `gsl` feature, the statistics are computed by Rust ports of the GSL functions.
+
+*/
 
 pub fn mean(x: &[f64]) -> f64 {
     // unsafe {
@@ -47,7 +75,481 @@ pub fn variance_mean(x: &[f64], mean: f64) -> f64 {
         / (x.len() - 1) as f64
 }
 
+/// Variance around a known population mean, normalized by `n`
+pub fn variance_with_fixed_mean(x: &[f64], mean: f64) -> f64 {
+    unsafe { gsl_stats_variance_with_fixed_mean(x.as_ptr(), 1, x.len() as u64, mean) }
+}
+
+pub fn sd(x: &[f64]) -> f64 {
+    unsafe { gsl_stats_sd(x.as_ptr(), 1, x.len() as u64) }
+}
+
+pub fn sd_mean(x: &[f64], mean: f64) -> f64 {
+    unsafe { gsl_stats_sd_m(x.as_ptr(), 1, x.len() as u64, mean) }
+}
+
+/// Total sum of squares around the mean
+pub fn tss(x: &[f64]) -> f64 {
+    unsafe { gsl_stats_tss(x.as_ptr(), 1, x.len() as u64) }
+}
+
+/// Mean absolute deviation from the mean
+pub fn absdev(x: &[f64]) -> f64 {
+    unsafe { gsl_stats_absdev(x.as_ptr(), 1, x.len() as u64) }
+}
+
+pub fn skew(x: &[f64]) -> f64 {
+    unsafe { gsl_stats_skew(x.as_ptr(), 1, x.len() as u64) }
+}
+
+/// Excess kurtosis
+pub fn kurtosis(x: &[f64]) -> f64 {
+    unsafe { gsl_stats_kurtosis(x.as_ptr(), 1, x.len() as u64) }
+}
+
+/// Fails with `GSLError::Invalid` for empty data
+pub fn lag1_autocorrelation(x: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        Ok(gsl_stats_lag1_autocorrelation(
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+pub fn covariance(x: &[f64], y: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.len() != y.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_covariance(
+            x.as_ptr(),
+            1,
+            y.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+/// Pearson correlation coefficient
+pub fn correlation(x: &[f64], y: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.len() != y.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_correlation(
+            x.as_ptr(),
+            1,
+            y.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+/// Spearman rank correlation coefficient
+pub fn spearman(x: &[f64], y: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.len() != y.len() {
+            return Err(GSLError::BadLength);
+        }
+        let mut work = vec![0.0; 2 * x.len()];
+        Ok(gsl_stats_spearman(
+            x.as_ptr(),
+            1,
+            y.as_ptr(),
+            1,
+            x.len() as u64,
+            work.as_mut_ptr(),
+        ))
+    }
+}
+
+pub fn weighted_mean(w: &[f64], x: &[f64]) -> Result<f64> {
+    unsafe {
+        if w.len() != x.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_wmean(
+            w.as_ptr(),
+            1,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+/// Weighted variance with the weights interpreted as reliability weights
+pub fn weighted_variance(w: &[f64], x: &[f64]) -> Result<f64> {
+    unsafe {
+        if w.len() != x.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_wvariance(
+            w.as_ptr(),
+            1,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+pub fn weighted_sd(w: &[f64], x: &[f64]) -> Result<f64> {
+    unsafe {
+        if w.len() != x.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_wsd(w.as_ptr(), 1, x.as_ptr(), 1, x.len() as u64))
+    }
+}
+
+pub fn weighted_absdev(w: &[f64], x: &[f64]) -> Result<f64> {
+    unsafe {
+        if w.len() != x.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_wabsdev(
+            w.as_ptr(),
+            1,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+pub fn weighted_skew(w: &[f64], x: &[f64]) -> Result<f64> {
+    unsafe {
+        if w.len() != x.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_wskew(
+            w.as_ptr(),
+            1,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+pub fn weighted_kurtosis(w: &[f64], x: &[f64]) -> Result<f64> {
+    unsafe {
+        if w.len() != x.len() {
+            return Err(GSLError::BadLength);
+        }
+        Ok(gsl_stats_wkurtosis(
+            w.as_ptr(),
+            1,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+/// Fails with `GSLError::Invalid` for empty data
+pub fn min(x: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        Ok(gsl_stats_min(x.as_ptr(), 1, x.len() as u64))
+    }
+}
+
+/// Fails with `GSLError::Invalid` for empty data
+pub fn max(x: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        Ok(gsl_stats_max(x.as_ptr(), 1, x.len() as u64))
+    }
+}
+
+/// Index of the first minimum. Fails with `GSLError::Invalid` for empty data.
+pub fn min_index(x: &[f64]) -> Result<usize> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        Ok(gsl_stats_min_index(x.as_ptr(), 1, x.len() as u64) as usize)
+    }
+}
+
+/// Index of the first maximum. Fails with `GSLError::Invalid` for empty data.
+pub fn max_index(x: &[f64]) -> Result<usize> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        Ok(gsl_stats_max_index(x.as_ptr(), 1, x.len() as u64) as usize)
+    }
+}
+
+/// Median of unsorted data, which is copied. Fails with `GSLError::Invalid` for empty data.
+pub fn median(x: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        let mut copy = x.to_vec();
+        Ok(gsl_stats_median(copy.as_mut_ptr(), 1, copy.len() as u64))
+    }
+}
+
+/// Fails with `GSLError::Invalid` for empty data
+pub fn median_sorted(x: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        Ok(gsl_stats_median_from_sorted_data(
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+/// Quantile `p` in `[0, 1]`, linearly interpolated between the data points
+pub fn quantile_sorted(x: &[f64], p: f64) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        if !(0.0..=1.0).contains(&p) {
+            return Err(GSLError::Domain);
+        }
+        Ok(gsl_stats_quantile_from_sorted_data(
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+            p,
+        ))
+    }
+}
+
+/// Mean after discarding the fraction `alpha` in `[0, 0.5]` of the data at both ends
+pub fn trimmed_mean_sorted(x: &[f64], alpha: f64) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        if !(0.0..=0.5).contains(&alpha) {
+            return Err(GSLError::Domain);
+        }
+        Ok(gsl_stats_trmean_from_sorted_data(
+            alpha,
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+        ))
+    }
+}
+
+/// Median absolute deviation from the median, scaled by `1.4826`
+/// to estimate the standard deviation of normally distributed data.
+/// Fails with `GSLError::Invalid` for empty data.
+pub fn mad(x: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        let mut work = vec![0.0; x.len()];
+        Ok(gsl_stats_mad(
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+            work.as_mut_ptr(),
+        ))
+    }
+}
+
+/// Median absolute deviation from the median, without scaling.
+/// Fails with `GSLError::Invalid` for empty data.
+pub fn mad0(x: &[f64]) -> Result<f64> {
+    unsafe {
+        if x.is_empty() {
+            return Err(GSLError::Invalid);
+        }
+        let mut work = vec![0.0; x.len()];
+        Ok(gsl_stats_mad0(
+            x.as_ptr(),
+            1,
+            x.len() as u64,
+            work.as_mut_ptr(),
+        ))
+    }
+}
+
+/// Treatment of NaN values by `with_nan_policy` and `try_with_nan_policy`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NanPolicy {
+    /// Pass them on to the statistic, which usually makes it NaN
+    Propagate,
+    /// Leave them out
+    Ignore,
+    /// Fail with `GSLError::Domain`
+    Error,
+    /// Replace them by the given value
+    Impute(f64),
+}
+
+/// Statistic `f` of `x` with the NaN values treated according to `policy`,
+/// e.g. `with_nan_policy(&x, NanPolicy::Ignore, mean)`
+pub fn with_nan_policy<T, F: FnOnce(&[f64]) -> T>(x: &[f64], policy: NanPolicy, f: F) -> Result<T> {
+    Ok(f(&apply_nan_policy(&[x], policy)?[0]))
+}
+
+/// `with_nan_policy` for a statistic that can fail, like `median`
+pub fn try_with_nan_policy<T, F: FnOnce(&[f64]) -> Result<T>>(
+    x: &[f64],
+    policy: NanPolicy,
+    f: F,
+) -> Result<T> {
+    f(&apply_nan_policy(&[x], policy)?[0])
+}
+
+/// Statistic `f` of the pairs `(x[i], y[i])`, like `covariance` or `weighted_mean`,
+/// where a NaN in either slice makes the pair NaN
+pub fn with_nan_policy_pairs<T, F: FnOnce(&[f64], &[f64]) -> Result<T>>(
+    x: &[f64],
+    y: &[f64],
+    policy: NanPolicy,
+    f: F,
+) -> Result<T> {
+    if x.len() != y.len() {
+        return Err(GSLError::BadLength);
+    }
+    let pairs = apply_nan_policy(&[x, y], policy)?;
+    f(&pairs[0], &pairs[1])
+}
+
+/// Applies `policy` to the equally long `columns`, borrowing them if they have no NaN
+fn apply_nan_policy<'a>(columns: &[&'a [f64]], policy: NanPolicy) -> Result<Vec<Cow<'a, [f64]>>> {
+    let nan = |i: usize| columns.iter().any(|c| c[i].is_nan());
+    let len = columns.first().map_or(0, |c| c.len());
+    if policy == NanPolicy::Propagate || !(0..len).any(nan) {
+        return Ok(columns.iter().map(|&c| Cow::Borrowed(c)).collect());
+    }
+
+    Ok(match policy {
+        NanPolicy::Propagate => unreachable!(),
+        NanPolicy::Error => return Err(GSLError::Domain),
+        NanPolicy::Ignore => columns
+            .iter()
+            .map(|c| Cow::Owned((0..len).filter(|&i| !nan(i)).map(|i| c[i]).collect()))
+            .collect(),
+        NanPolicy::Impute(value) => columns
+            .iter()
+            .map(|c| {
+                Cow::Owned(
+                    c.iter()
+                        .map(|&x| if x.is_nan() { value } else { x })
+                        .collect(),
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Sums of the columns of the `m` by `n` matrix `a`
+pub fn col_sums(m: usize, n: usize, a: &[f64], mask: Option<&[bool]>) -> Result<Box<[f64]>> {
+    reduce_lines(m, n, a, mask, true, sum_strided)
+}
+
+/// Means of the columns of the `m` by `n` matrix `a`
+pub fn col_means(m: usize, n: usize, a: &[f64], mask: Option<&[bool]>) -> Result<Box<[f64]>> {
+    reduce_lines(m, n, a, mask, true, mean_strided)
+}
+
+/// Variances of the columns of the `m` by `n` matrix `a`
+pub fn col_variances(m: usize, n: usize, a: &[f64], mask: Option<&[bool]>) -> Result<Box<[f64]>> {
+    reduce_lines(m, n, a, mask, true, variance_strided)
+}
+
+/// Sums of the rows of the `m` by `n` matrix `a`
+pub fn row_sums(m: usize, n: usize, a: &[f64], mask: Option<&[bool]>) -> Result<Box<[f64]>> {
+    reduce_lines(m, n, a, mask, false, sum_strided)
+}
+
+/// Means of the rows of the `m` by `n` matrix `a`
+pub fn row_means(m: usize, n: usize, a: &[f64], mask: Option<&[bool]>) -> Result<Box<[f64]>> {
+    reduce_lines(m, n, a, mask, false, mean_strided)
+}
+
+/// Variances of the rows of the `m` by `n` matrix `a`
+pub fn row_variances(m: usize, n: usize, a: &[f64], mask: Option<&[bool]>) -> Result<Box<[f64]>> {
+    reduce_lines(m, n, a, mask, false, variance_strided)
+}
+
+/// Applies `f(data, stride, len)` to every column or row of `a`,
+/// gathering the selected elements first if there's a mask
+fn reduce_lines<F: Fn(&[f64], usize, usize) -> f64>(
+    m: usize,
+    n: usize,
+    a: &[f64],
+    mask: Option<&[bool]>,
+    columns: bool,
+    f: F,
+) -> Result<Box<[f64]>> {
+    if a.len() != m * n || mask.is_some_and(|mask| mask.len() != a.len()) {
+        return Err(GSLError::BadLength);
+    }
+
+    // Line `k` has its elements at `k * step + i * stride` for `i < len`
+    let (lines, len, step, stride) = if columns { (n, m, 1, n) } else { (m, n, n, 1) };
+    let mut selected = Vec::with_capacity(len);
+    Ok((0..lines)
+        .map(|k| {
+            let line = &a[(k * step).min(a.len())..];
+            match mask {
+                Some(mask) => {
+                    let mask = &mask[(k * step).min(a.len())..];
+                    selected.clear();
+                    selected.extend(
+                        (0..len)
+                            .map(|i| i * stride)
+                            .filter(|&i| mask[i])
+                            .map(|i| line[i]),
+                    );
+                    f(&selected, 1, selected.len())
+                }
+                None => f(line, stride, len),
+            }
+        })
+        .collect())
+}
+
+fn sum_strided(data: &[f64], stride: usize, len: usize) -> f64 {
+    data.iter().step_by(stride).take(len).sum()
+}
+
+fn mean_strided(data: &[f64], stride: usize, len: usize) -> f64 {
+    if len == 0 {
+        return f64::NAN;
+    }
+    unsafe { gsl_stats_mean(data.as_ptr(), stride as u64, len as u64) }
+}
+
+fn variance_strided(data: &[f64], stride: usize, len: usize) -> f64 {
+    if len < 2 {
+        return f64::NAN;
+    }
+    unsafe { gsl_stats_variance(data.as_ptr(), stride as u64, len as u64) }
+}
+
 #[test]
+#[cfg(feature = "gsl")]
 fn test_variance_compare_rs_gsl() {
     let x = [1.0, 2.0, 3.0, 4.0, 10.0, 200.0, -10.0, 0.0];
     let gsl_variance = unsafe {
@@ -59,3 +561,184 @@ fn test_variance_compare_rs_gsl() {
     let rust_variance = variance(&x);
     approx::assert_abs_diff_eq!(gsl_variance, rust_variance);
 }
+
+#[test]
+fn test_statistics() {
+    disable_error_handler();
+
+    let x = [1.0, 2.0, 3.0, 4.0, 10.0, 200.0, -10.0, 0.0];
+    approx::assert_abs_diff_eq!(mean(&x), 26.25, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(
+        variance(&x),
+        x.iter().map(|x| (x - 26.25).powi(2)).sum::<f64>() / 7.0,
+        epsilon = 1.0e-9
+    );
+    approx::assert_abs_diff_eq!(sd(&x), variance(&x).sqrt(), epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(tss(&x), variance(&x) * 7.0, epsilon = 1.0e-9);
+    assert_eq!(min(&x).unwrap(), -10.0);
+    assert_eq!(max(&x).unwrap(), 200.0);
+    assert_eq!(min_index(&x).unwrap(), 6);
+    assert_eq!(max_index(&x).unwrap(), 5);
+    assert_eq!(median(&x).unwrap(), 2.5);
+    assert_eq!(mad0(&x).unwrap(), 2.0);
+
+    let mut sorted = x;
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(median_sorted(&sorted).unwrap(), 2.5);
+    assert_eq!(quantile_sorted(&sorted, 0.0).unwrap(), -10.0);
+    assert_eq!(quantile_sorted(&sorted, 1.0).unwrap(), 200.0);
+    assert_eq!(trimmed_mean_sorted(&sorted, 0.25).unwrap(), 2.5);
+
+    // Symmetric data has no skew
+    let y = [1.0, 2.0, 3.0, 4.0, 5.0];
+    approx::assert_abs_diff_eq!(skew(&y), 0.0);
+    approx::assert_abs_diff_eq!(absdev(&y), 1.2);
+
+    // Equal weights reduce to the unweighted statistics
+    let w = [2.0; 5];
+    approx::assert_abs_diff_eq!(weighted_mean(&w, &y).unwrap(), 3.0);
+    approx::assert_abs_diff_eq!(
+        weighted_variance(&w, &y).unwrap(),
+        variance(&y),
+        epsilon = 1.0e-12
+    );
+
+    // Perfect monotonic relation
+    let z = y.map(|y: f64| y.exp());
+    approx::assert_abs_diff_eq!(correlation(&y, &y).unwrap(), 1.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(spearman(&y, &z).unwrap(), 1.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(covariance(&y, &y).unwrap(), variance(&y), epsilon = 1.0e-12);
+}
+
+#[test]
+fn test_nan_policy() {
+    disable_error_handler();
+
+    let x = [1.0, f64::NAN, 3.0, 5.0];
+    assert!(with_nan_policy(&x, NanPolicy::Propagate, mean)
+        .unwrap()
+        .is_nan());
+    assert_eq!(with_nan_policy(&x, NanPolicy::Ignore, mean).unwrap(), 3.0);
+    assert_eq!(
+        with_nan_policy(&x, NanPolicy::Impute(7.0), mean).unwrap(),
+        4.0
+    );
+    assert_eq!(
+        with_nan_policy(&x, NanPolicy::Error, mean).unwrap_err(),
+        GSLError::Domain
+    );
+    assert_eq!(
+        with_nan_policy(&x, NanPolicy::Ignore, |x| x.len()).unwrap(),
+        3
+    );
+
+    // Without NaN, every policy gives the plain statistic
+    let y = [2.0, 4.0, 6.0, 11.0];
+    for policy in [
+        NanPolicy::Propagate,
+        NanPolicy::Ignore,
+        NanPolicy::Error,
+        NanPolicy::Impute(0.0),
+    ] {
+        assert_eq!(try_with_nan_policy(&y, policy, median).unwrap(), 5.0);
+    }
+
+    // Pairs stay aligned
+    let w = [1.0, 1.0, f64::NAN, 1.0];
+    approx::assert_abs_diff_eq!(
+        with_nan_policy_pairs(&w, &y, NanPolicy::Ignore, weighted_mean).unwrap(),
+        17.0 / 3.0,
+        epsilon = 1.0e-12
+    );
+    assert_eq!(
+        with_nan_policy_pairs(&x, &w, NanPolicy::Ignore, |x, w| Ok((
+            x.to_vec(),
+            w.to_vec()
+        )))
+        .unwrap(),
+        (vec![1.0, 5.0], vec![1.0, 1.0])
+    );
+    with_nan_policy_pairs(&x, &w, NanPolicy::Error, covariance).unwrap_err();
+    with_nan_policy_pairs(&x, &w[..3], NanPolicy::Ignore, covariance).unwrap_err();
+}
+
+#[test]
+fn test_matrix_statistics() {
+    disable_error_handler();
+
+    #[rustfmt::skip]
+    let a = [
+        1.0, 2.0, 3.0,
+        4.0, 6.0, 8.0,
+        f64::NAN, 10.0, 1.0,
+        7.0, 2.0, 0.0,
+    ];
+    let (m, n) = (4, 3);
+
+    // Against the statistics of every row and column on its own
+    let column = |j: usize| (0..m).map(|i| a[i * n + j]).collect::<Vec<_>>();
+    let row = |i: usize| a[i * n..(i + 1) * n].to_vec();
+    let means = col_means(m, n, &a, None).unwrap();
+    let variances = col_variances(m, n, &a, None).unwrap();
+    assert!(means[0].is_nan());
+    for j in 1..n {
+        assert_eq!(means[j], mean(&column(j)));
+        assert_eq!(variances[j], variance(&column(j)));
+    }
+    assert_eq!(col_sums(m, n, &a, None).unwrap()[1], 20.0);
+    assert_eq!(row_sums(m, n, &a, None).unwrap()[1], 18.0);
+    assert_eq!(row_means(m, n, &a, None).unwrap()[3], 3.0);
+    assert_eq!(row_variances(m, n, &a, None).unwrap()[1], variance(&row(1)));
+
+    // Masking out the missing value
+    let mask = a.map(|x| !x.is_nan());
+    let means = col_means(m, n, &a, Some(&mask)).unwrap();
+    assert_eq!(means[0], 4.0);
+    assert_eq!(col_variances(m, n, &a, Some(&mask)).unwrap()[0], 9.0);
+    assert_eq!(row_sums(m, n, &a, Some(&mask)).unwrap()[2], 11.0);
+    assert_eq!(row_means(m, n, &a, Some(&mask)).unwrap()[2], 5.5);
+
+    // Nothing selected
+    let none = [false; 12];
+    assert_eq!(col_sums(m, n, &a, Some(&none)).unwrap()[0], 0.0);
+    assert!(row_means(m, n, &a, Some(&none)).unwrap()[0].is_nan());
+    assert!(col_variances(1, 3, &a[..3], None).unwrap()[0].is_nan());
+
+    // Empty matrices
+    assert!(col_means(0, 3, &[], None)
+        .unwrap()
+        .iter()
+        .all(|x| x.is_nan()));
+    assert!(row_means(0, 3, &[], None).unwrap().is_empty());
+    assert_eq!(*row_sums(2, 0, &[], None).unwrap(), [0.0, 0.0]);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Length mismatch
+    covariance(&[1.0, 2.0], &[1.0]).unwrap_err();
+    weighted_mean(&[1.0], &[1.0, 2.0]).unwrap_err();
+
+    // Out of range fractions
+    quantile_sorted(&[1.0, 2.0], 1.5).unwrap_err();
+    trimmed_mean_sorted(&[1.0, 2.0], 0.6).unwrap_err();
+
+    // No data, which GSL would index out of bounds
+    quantile_sorted(&[], 0.5).unwrap_err();
+    trimmed_mean_sorted(&[], 0.1).unwrap_err();
+    median(&[]).unwrap_err();
+    median_sorted(&[]).unwrap_err();
+    mad(&[]).unwrap_err();
+    mad0(&[]).unwrap_err();
+    min(&[]).unwrap_err();
+    max(&[]).unwrap_err();
+    min_index(&[]).unwrap_err();
+    max_index(&[]).unwrap_err();
+    lag1_autocorrelation(&[]).unwrap_err();
+
+    // Matrix and mask sizes
+    col_means(2, 2, &[1.0, 2.0, 3.0], None).unwrap_err();
+    row_sums(1, 2, &[1.0, 2.0], Some(&[true])).unwrap_err();
+}
@@ -0,0 +1,452 @@
+/*
+    native.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+
+    Ports of the GSL statistics functions that `stats` uses, for builds without the `gsl` feature.
+    They have the signatures of the bindings, so `stats` calls them the same way, and follow
+    the algorithms of GSL, e.g. the running means, so results agree up to rounding.
+
+    GSL accumulates in `long double`, these ports in `f64`.
+    Empty data gives NaN instead of reading out of bounds, except for the medians and quantiles,
+    which are 0 like in GSL.
+
+*/
+
+#[allow(non_camel_case_types)]
+pub(super) type size_t = u64;
+
+/// `n` elements of `data`, `stride` apart
+unsafe fn values(data: *const f64, stride: size_t, n: size_t) -> impl Iterator<Item = f64> + Clone {
+    let stride = stride as usize;
+    (0..n as usize).map(move |i| *data.add(i * stride))
+}
+
+/// Pairs of weights and elements, leaving out nonpositive weights
+unsafe fn weighted(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> impl Iterator<Item = (f64, f64)> + Clone {
+    values(w, wstride, n)
+        .zip(values(data, stride, n))
+        .filter(|&(w, _)| w > 0.0)
+}
+
+fn running_mean<I: Iterator<Item = f64>>(x: I) -> f64 {
+    let mut mean = 0.0;
+    for (i, x) in x.enumerate() {
+        mean += (x - mean) / (i + 1) as f64;
+    }
+    mean
+}
+
+fn running_weighted_mean<I: Iterator<Item = (f64, f64)>>(x: I) -> f64 {
+    let mut total = 0.0;
+    let mut mean = 0.0;
+    for (w, x) in x {
+        total += w;
+        mean += (x - mean) * (w / total);
+    }
+    mean
+}
+
+fn empty_to_nan(n: size_t, x: f64) -> f64 {
+    if n == 0 {
+        f64::NAN
+    } else {
+        x
+    }
+}
+
+pub(super) unsafe fn gsl_stats_mean(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    empty_to_nan(n, running_mean(values(data, stride, n)))
+}
+
+pub(super) unsafe fn gsl_stats_variance_with_fixed_mean(
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+    mean: f64,
+) -> f64 {
+    let squares = values(data, stride, n).map(|x| (x - mean) * (x - mean));
+    empty_to_nan(n, running_mean(squares))
+}
+
+pub(super) unsafe fn gsl_stats_variance_m(
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+    mean: f64,
+) -> f64 {
+    gsl_stats_variance_with_fixed_mean(data, stride, n, mean) * n as f64 / (n as f64 - 1.0)
+}
+
+pub(super) unsafe fn gsl_stats_variance(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    gsl_stats_variance_m(data, stride, n, gsl_stats_mean(data, stride, n))
+}
+
+pub(super) unsafe fn gsl_stats_sd_m(data: *const f64, stride: size_t, n: size_t, mean: f64) -> f64 {
+    gsl_stats_variance_m(data, stride, n, mean).sqrt()
+}
+
+pub(super) unsafe fn gsl_stats_sd(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    gsl_stats_variance(data, stride, n).sqrt()
+}
+
+pub(super) unsafe fn gsl_stats_tss(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    let mean = gsl_stats_mean(data, stride, n);
+    values(data, stride, n)
+        .map(|x| (x - mean) * (x - mean))
+        .sum()
+}
+
+pub(super) unsafe fn gsl_stats_absdev(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    let mean = gsl_stats_mean(data, stride, n);
+    values(data, stride, n)
+        .map(|x| (x - mean).abs())
+        .sum::<f64>()
+        / n as f64
+}
+
+/// Running mean of the `k`th power of the standardized data
+unsafe fn standardized_moment(data: *const f64, stride: size_t, n: size_t, k: i32) -> f64 {
+    let mean = gsl_stats_mean(data, stride, n);
+    let sd = gsl_stats_sd_m(data, stride, n, mean);
+    empty_to_nan(
+        n,
+        running_mean(values(data, stride, n).map(|x| ((x - mean) / sd).powi(k))),
+    )
+}
+
+pub(super) unsafe fn gsl_stats_skew(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    standardized_moment(data, stride, n, 3)
+}
+
+pub(super) unsafe fn gsl_stats_kurtosis(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    standardized_moment(data, stride, n, 4) - 3.0
+}
+
+pub(super) unsafe fn gsl_stats_lag1_autocorrelation(
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    if n == 0 {
+        return f64::NAN;
+    }
+    let mean = gsl_stats_mean(data, stride, n);
+    let x = values(data, stride, n)
+        .map(|x| x - mean)
+        .collect::<Vec<_>>();
+    let mut q = 0.0;
+    let mut v = x[0] * x[0];
+    for i in 1..x.len() {
+        q += (x[i - 1] * x[i] - q) / (i + 1) as f64;
+        v += (x[i] * x[i] - v) / (i + 1) as f64;
+    }
+    q / v
+}
+
+pub(super) unsafe fn gsl_stats_covariance(
+    data1: *const f64,
+    stride1: size_t,
+    data2: *const f64,
+    stride2: size_t,
+    n: size_t,
+) -> f64 {
+    let mean1 = gsl_stats_mean(data1, stride1, n);
+    let mean2 = gsl_stats_mean(data2, stride2, n);
+    let products = values(data1, stride1, n)
+        .zip(values(data2, stride2, n))
+        .map(|(x, y)| (x - mean1) * (y - mean2));
+    empty_to_nan(n, running_mean(products)) * n as f64 / (n as f64 - 1.0)
+}
+
+pub(super) unsafe fn gsl_stats_correlation(
+    data1: *const f64,
+    stride1: size_t,
+    data2: *const f64,
+    stride2: size_t,
+    n: size_t,
+) -> f64 {
+    let mut pairs = values(data1, stride1, n).zip(values(data2, stride2, n));
+    let (mut mean_x, mut mean_y) = match pairs.next() {
+        Some(first) => first,
+        None => return f64::NAN,
+    };
+    let (mut sum_xsq, mut sum_ysq, mut sum_cross) = (0.0, 0.0, 0.0);
+    for (i, (x, y)) in pairs.enumerate() {
+        let i = (i + 1) as f64;
+        let ratio = i / (i + 1.0);
+        let delta_x = x - mean_x;
+        let delta_y = y - mean_y;
+        sum_xsq += delta_x * delta_x * ratio;
+        sum_ysq += delta_y * delta_y * ratio;
+        sum_cross += delta_x * delta_y * ratio;
+        mean_x += delta_x / (i + 1.0);
+        mean_y += delta_y / (i + 1.0);
+    }
+    sum_cross / (sum_xsq.sqrt() * sum_ysq.sqrt())
+}
+
+/// Ranks starting at 1, with the mean rank for ties
+fn ranks<I: Iterator<Item = f64>>(x: I) -> Vec<f64> {
+    let x = x.collect::<Vec<_>>();
+    let mut order = (0..x.len()).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| x[i].total_cmp(&x[j]));
+
+    let mut ranks = vec![0.0; x.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && x[order[end]] == x[order[start]] {
+            end += 1;
+        }
+        let rank = (start + 1 + end) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+pub(super) unsafe fn gsl_stats_spearman(
+    data1: *const f64,
+    stride1: size_t,
+    data2: *const f64,
+    stride2: size_t,
+    n: size_t,
+    _work: *mut f64,
+) -> f64 {
+    let ranks1 = ranks(values(data1, stride1, n));
+    let ranks2 = ranks(values(data2, stride2, n));
+    gsl_stats_correlation(ranks1.as_ptr(), 1, ranks2.as_ptr(), 1, n)
+}
+
+pub(super) unsafe fn gsl_stats_wmean(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    running_weighted_mean(weighted(w, wstride, data, stride, n))
+}
+
+pub(super) unsafe fn gsl_stats_wvariance(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    let pairs = weighted(w, wstride, data, stride, n);
+    let mean = running_weighted_mean(pairs.clone());
+    let variance = running_weighted_mean(pairs.clone().map(|(w, x)| (w, (x - mean) * (x - mean))));
+
+    // Correction for reliability weights
+    let a = pairs.clone().map(|(w, _)| w).sum::<f64>();
+    let b = pairs.map(|(w, _)| w * w).sum::<f64>();
+    a * a / (a * a - b) * variance
+}
+
+pub(super) unsafe fn gsl_stats_wsd(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    gsl_stats_wvariance(w, wstride, data, stride, n).sqrt()
+}
+
+pub(super) unsafe fn gsl_stats_wabsdev(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    let pairs = weighted(w, wstride, data, stride, n);
+    let mean = running_weighted_mean(pairs.clone());
+    running_weighted_mean(pairs.map(|(w, x)| (w, (x - mean).abs())))
+}
+
+/// Running weighted mean of the `k`th power of the standardized data
+unsafe fn weighted_standardized_moment(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+    k: i32,
+) -> f64 {
+    let pairs = weighted(w, wstride, data, stride, n);
+    let mean = running_weighted_mean(pairs.clone());
+    let sd = gsl_stats_wsd(w, wstride, data, stride, n);
+    running_weighted_mean(pairs.map(|(w, x)| (w, ((x - mean) / sd).powi(k))))
+}
+
+pub(super) unsafe fn gsl_stats_wskew(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    weighted_standardized_moment(w, wstride, data, stride, n, 3)
+}
+
+pub(super) unsafe fn gsl_stats_wkurtosis(
+    w: *const f64,
+    wstride: size_t,
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    weighted_standardized_moment(w, wstride, data, stride, n, 4) - 3.0
+}
+
+/// Index and value of the first extreme by `better`, or of the first NaN
+unsafe fn extreme<F: Fn(f64, f64) -> bool>(
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+    better: F,
+) -> (size_t, f64) {
+    let mut best = (0, f64::NAN);
+    for (i, x) in values(data, stride, n).enumerate() {
+        if x.is_nan() {
+            return (i as size_t, x);
+        }
+        if i == 0 || better(x, best.1) {
+            best = (i as size_t, x);
+        }
+    }
+    best
+}
+
+pub(super) unsafe fn gsl_stats_min(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    extreme(data, stride, n, |x, min| x < min).1
+}
+
+pub(super) unsafe fn gsl_stats_max(data: *const f64, stride: size_t, n: size_t) -> f64 {
+    extreme(data, stride, n, |x, max| x > max).1
+}
+
+pub(super) unsafe fn gsl_stats_min_index(data: *const f64, stride: size_t, n: size_t) -> size_t {
+    extreme(data, stride, n, |x, min| x < min).0
+}
+
+pub(super) unsafe fn gsl_stats_max_index(data: *const f64, stride: size_t, n: size_t) -> size_t {
+    extreme(data, stride, n, |x, max| x > max).0
+}
+
+pub(super) unsafe fn gsl_stats_median_from_sorted_data(
+    sorted_data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    if n == 0 {
+        return 0.0;
+    }
+    let x = |i: size_t| *sorted_data.add((i * stride) as usize);
+    let (lhs, rhs) = ((n - 1) / 2, n / 2);
+    if lhs == rhs {
+        x(lhs)
+    } else {
+        (x(lhs) + x(rhs)) / 2.0
+    }
+}
+
+/// Median, reordering the data
+pub(super) unsafe fn gsl_stats_median(data: *mut f64, stride: size_t, n: size_t) -> f64 {
+    let mut sorted = values(data, stride, n).collect::<Vec<_>>();
+    sorted.sort_by(f64::total_cmp);
+    for (i, &x) in sorted.iter().enumerate() {
+        *data.add(i * stride as usize) = x;
+    }
+    gsl_stats_median_from_sorted_data(data, stride, n)
+}
+
+pub(super) unsafe fn gsl_stats_quantile_from_sorted_data(
+    sorted_data: *const f64,
+    stride: size_t,
+    n: size_t,
+    f: f64,
+) -> f64 {
+    if n == 0 {
+        return 0.0;
+    }
+    let index = f * (n - 1) as f64;
+    let lhs = index as size_t;
+    let delta = index - lhs as f64;
+    let x = |i: size_t| *sorted_data.add((i * stride) as usize);
+    if lhs == n - 1 {
+        x(lhs)
+    } else {
+        (1.0 - delta) * x(lhs) + delta * x(lhs + 1)
+    }
+}
+
+pub(super) unsafe fn gsl_stats_trmean_from_sorted_data(
+    trim: f64,
+    sorted_data: *const f64,
+    stride: size_t,
+    n: size_t,
+) -> f64 {
+    if trim >= 0.5 {
+        return gsl_stats_median_from_sorted_data(sorted_data, stride, n);
+    }
+    let low = (trim * n as f64).floor() as size_t;
+    let high = n - low;
+    let kept = values(sorted_data, stride, n)
+        .skip(low as usize)
+        .take((high - low) as usize);
+    running_mean(kept)
+}
+
+/// Median absolute deviation from the median, without scaling
+pub(super) unsafe fn gsl_stats_mad0(
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+    work: *mut f64,
+) -> f64 {
+    for (i, x) in values(data, stride, n).enumerate() {
+        *work.add(i) = x;
+    }
+    let median = gsl_stats_median(work, 1, n);
+    for (i, x) in values(data, stride, n).enumerate() {
+        *work.add(i) = (x - median).abs();
+    }
+    gsl_stats_median(work, 1, n)
+}
+
+pub(super) unsafe fn gsl_stats_mad(
+    data: *const f64,
+    stride: size_t,
+    n: size_t,
+    work: *mut f64,
+) -> f64 {
+    1.482602218505602 * gsl_stats_mad0(data, stride, n, work)
+}
@@ -0,0 +1,300 @@
+/*
+    table.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::histogram::Histogram;
+use crate::linear_fit::{fit_line, fit_line_weighted, LineFit};
+use crate::nonlinear_fit::{nonlinear_fit, FitResult};
+use crate::*;
+use std::fmt;
+use std::ops::Index;
+
+/*
+
+    A table of named `f64` columns of equal length, to keep columns like x, y and their errors
+    aligned while rows are filtered and columns are derived. It's not a data frame:
+    there are no indexes, joins or other column types.
+
+    Columns are taken by name, and an unknown name fails with `GSLError::Invalid`.
+    `columns` hands out several aligned columns at once for any function of the crate,
+    and the adapters cover the common fits, statistics and histograms.
+
+*/
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    names: Vec<String>,
+    columns: Vec<Vec<f64>>,
+    rows: usize,
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the column `name`, or replaces it if it exists.
+    /// Fails with `GSLError::BadLength` if the table already has a different amount of rows.
+    pub fn with_column<V: Into<Vec<f64>>>(mut self, name: &str, values: V) -> Result<Self> {
+        self.set_column(name, values)?;
+        Ok(self)
+    }
+
+    /// Adds the column `name`, or replaces it if it exists, see `with_column`
+    pub fn set_column<V: Into<Vec<f64>>>(&mut self, name: &str, values: V) -> Result<()> {
+        let values = values.into();
+        let replaces_only_column = self.names.len() == 1 && self.names[0] == name;
+        if !self.names.is_empty() && !replaces_only_column && values.len() != self.rows {
+            return Err(GSLError::BadLength);
+        }
+
+        self.rows = values.len();
+        match self.position(name) {
+            Some(j) => self.columns[j] = values,
+            None => {
+                self.names.push(name.to_owned());
+                self.columns.push(values);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds or replaces the column `name` with `f` of every row, e.g. `|row| row["y"] / row["x"]`
+    pub fn derive<F: FnMut(Row<'_>) -> f64>(&mut self, name: &str, mut f: F) -> Result<()> {
+        let values = self.iter().map(&mut f).collect::<Vec<_>>();
+        self.set_column(name, values)
+    }
+
+    /// Removes the column `name` and returns its values
+    pub fn remove_column(&mut self, name: &str) -> Result<Vec<f64>> {
+        let j = self.position(name).ok_or(GSLError::Invalid)?;
+        self.names.remove(j);
+        if self.names.is_empty() {
+            self.rows = 0;
+        }
+        Ok(self.columns.remove(j))
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn column(&self, name: &str) -> Result<&[f64]> {
+        self.position(name)
+            .map(|j| self.columns[j].as_slice())
+            .ok_or(GSLError::Invalid)
+    }
+
+    /// The columns `names`, e.g. `let [x, y] = table.columns(["x", "y"])?;`
+    pub fn columns<const N: usize>(&self, names: [&str; N]) -> Result<[&[f64]; N]> {
+        let mut columns = [&[][..]; N];
+        for (column, name) in columns.iter_mut().zip(names) {
+            *column = self.column(name)?;
+        }
+        Ok(columns)
+    }
+
+    pub fn row(&self, i: usize) -> Row<'_> {
+        assert!(i < self.rows);
+        Row { table: self, i }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Row<'_>> + '_ {
+        (0..self.rows).map(move |i| Row { table: self, i })
+    }
+
+    /// Whether `predicate` holds for every row, as a mask for e.g. `linear_fit_masked`
+    pub fn mask<P: FnMut(Row<'_>) -> bool>(&self, predicate: P) -> Vec<bool> {
+        self.iter().map(predicate).collect()
+    }
+
+    /// Table of the rows for which `predicate` holds, e.g. `|row| row["err"] > 0.0`
+    pub fn filter<P: FnMut(Row<'_>) -> bool>(&self, predicate: P) -> Table {
+        let mask = self.mask(predicate);
+        Table {
+            names: self.names.clone(),
+            columns: self
+                .columns
+                .iter()
+                .map(|c| select(c, &mask).into_iter().copied().collect())
+                .collect(),
+            rows: mask.iter().filter(|&&m| m).count(),
+        }
+    }
+
+    /// Statistic `f` of the column `name`, e.g. `table.stat("y", stats::mean)`
+    pub fn stat<T, F: FnOnce(&[f64]) -> T>(&self, name: &str, f: F) -> Result<T> {
+        Ok(f(self.column(name)?))
+    }
+
+    /// Straight line fit of the column `y` against `x`
+    pub fn fit_line(&self, x: &str, y: &str) -> Result<LineFit> {
+        let [x, y] = self.columns([x, y])?;
+        fit_line(x, y)
+    }
+
+    /// Straight line fit of the column `y` against `x`, weighted by `1 / err²`
+    pub fn fit_line_err(&self, x: &str, y: &str, err: &str) -> Result<LineFit> {
+        let [x, y, err] = self.columns([x, y, err])?;
+        let w = err.iter().map(|e| 1.0 / (e * e)).collect::<Vec<_>>();
+        fit_line_weighted(x, &w, y)
+    }
+
+    /// Fit of the model `f` to the column `y` against `x`
+    pub fn nonlinear_fit<F: FnMut(&f64, [f64; P]) -> Result<f64>, const P: usize>(
+        &self,
+        x: &str,
+        y: &str,
+        p0: [f64; P],
+        f: F,
+    ) -> Result<FitResult<P>> {
+        let [x, y] = self.columns([x, y])?;
+        nonlinear_fit(p0, x, y, f)
+    }
+
+    /// Histogram of the column `name` with `n` bins covering `[min, max)`, leaving out values outside it
+    pub fn histogram(&self, name: &str, n: usize, min: f64, max: f64) -> Result<Histogram> {
+        let mut histogram = Histogram::uniform(n, min, max)?;
+        for &x in self.column(name)? {
+            if min <= x && x < max {
+                histogram.increment(x)?;
+            }
+        }
+        Ok(histogram)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+}
+
+/// Row `i` of a table, indexed by column name
+#[derive(Copy, Clone)]
+pub struct Row<'a> {
+    table: &'a Table,
+    i: usize,
+}
+
+impl Row<'_> {
+    pub fn index(&self) -> usize {
+        self.i
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.table
+            .position(name)
+            .map(|j| self.table.columns[j][self.i])
+    }
+}
+
+impl Index<&str> for Row<'_> {
+    type Output = f64;
+
+    /// Panics if there's no column `name`
+    fn index(&self, name: &str) -> &f64 {
+        let j = self
+            .table
+            .position(name)
+            .unwrap_or_else(|| panic!("no column {:?}", name));
+        &self.table.columns[j][self.i]
+    }
+}
+
+impl fmt::Debug for Row<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.table
+                    .names
+                    .iter()
+                    .zip(&self.table.columns)
+                    .map(|(name, c)| (name, c[self.i])),
+            )
+            .finish()
+    }
+}
+
+#[test]
+fn test_table() {
+    disable_error_handler();
+
+    let x = (0..10).map(|x| x as f64).collect::<Vec<_>>();
+    let mut table = Table::new()
+        .with_column("x", x.clone())
+        .unwrap()
+        .with_column("y", x.iter().map(|x| 2.0 * x + 1.0).collect::<Vec<_>>())
+        .unwrap()
+        .with_column("err", [0.5; 10])
+        .unwrap();
+    table.derive("y2", |row| row["y"] * row["y"]).unwrap();
+    assert_eq!(table.rows(), 10);
+    assert_eq!(table.names(), ["x", "y", "err", "y2"]);
+    assert_eq!(table.row(3)["y2"], 49.0);
+    assert_eq!(table.row(3).get("z"), None);
+
+    // Rows stay aligned through a filter
+    let odd = table.filter(|row| row["x"] % 2.0 == 1.0);
+    assert_eq!(odd.rows(), 5);
+    let [x, y] = odd.columns(["x", "y"]).unwrap();
+    assert_eq!(x, [1.0, 3.0, 5.0, 7.0, 9.0]);
+    assert_eq!(y, [3.0, 7.0, 11.0, 15.0, 19.0]);
+    assert_eq!(table.mask(|row| row["x"] < 2.0)[..3], [true, true, false]);
+
+    // Adapters
+    assert_eq!(table.stat("x", stats::mean).unwrap(), 4.5);
+    let line = odd.fit_line("x", "y").unwrap();
+    approx::assert_abs_diff_eq!(line.c1, 2.0, epsilon = 1.0e-12);
+    let line = table.fit_line_err("x", "y", "err").unwrap();
+    approx::assert_abs_diff_eq!(line.c0, 1.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(line.cov11, 0.25 / 82.5, epsilon = 1.0e-12);
+    let fit = table
+        .nonlinear_fit("x", "y2", [1.0, 1.0], |&x, [a, b]| Ok((a * x + b).powi(2)))
+        .unwrap();
+    approx::assert_abs_diff_eq!(fit.params[0], 2.0, epsilon = 1.0e-6);
+    let histogram = table.histogram("x", 2, 0.0, 8.0).unwrap();
+    assert_eq!(*histogram.bins(), [4.0, 4.0]);
+
+    // Replacing and removing columns
+    table.set_column("y", [0.0; 10]).unwrap();
+    assert_eq!(table.column("y").unwrap(), [0.0; 10]);
+    assert_eq!(table.remove_column("y2").unwrap()[1], 9.0);
+    assert_eq!(table.names(), ["x", "y", "err"]);
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    let mut table = Table::new().with_column("x", [1.0, 2.0]).unwrap();
+
+    // Length mismatch
+    table.set_column("y", [1.0]).unwrap_err();
+
+    // Unknown columns
+    table.column("y").unwrap_err();
+    table.columns(["x", "y"]).unwrap_err();
+    table.fit_line("x", "y").unwrap_err();
+    table.remove_column("y").unwrap_err();
+
+    // The only column can change length
+    table.set_column("x", [1.0, 2.0, 3.0]).unwrap();
+    assert_eq!(table.rows(), 3);
+}
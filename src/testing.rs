@@ -0,0 +1,116 @@
+/*
+    testing.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::*;
+
+/*
+
+    Checks for the test cases ported from the C test suites of GSL, so ported cases keep
+    the reference values and tolerances of upstream. Every module with ported cases has a
+    `test_gsl_suite` test, which names the upstream test file it follows.
+
+    `assert_sf` follows `test_sf_check_result` of `specfunc/test_sf.c`: the fractional difference
+    `|a - b| / |a + b|` has to be within `TEST_FACTOR` times the tolerance, and the error estimate
+    has to be nonnegative, finite and consistent with the actual error.
+    The tolerances `TOL0` to `TOL3` are `TEST_TOL0` to `TEST_TOL3` of `specfunc/test_sf.h`.
+
+    `assert_rel` and `assert_abs` follow `gsl_test_rel` and `gsl_test_abs` of `test/results.c`,
+    which the other test suites use.
+
+    Reference values that upstream only gives as the output of GSL itself are replaced by exact values,
+    computed in high precision.
+
+*/
+
+pub(crate) const TOL0: f64 = 2.0 * f64::EPSILON;
+pub(crate) const TOL1: f64 = 16.0 * f64::EPSILON;
+pub(crate) const TOL2: f64 = 256.0 * f64::EPSILON;
+pub(crate) const TOL3: f64 = 2048.0 * f64::EPSILON;
+
+const TEST_FACTOR: f64 = 100.0;
+const TEST_SIGMA: f64 = 1.5;
+
+/// Checks the result of a special function against `expected` with the tolerance `tol`
+#[track_caller]
+pub(crate) fn assert_sf(result: Result<ValWithError<f64>>, expected: f64, tol: f64) {
+    let result = result.unwrap_or_else(|e| panic!("expected {:e}, got {:?}", expected, e));
+    let diff = (result.val - expected).abs();
+    assert!(
+        frac_diff(expected, result.val) <= TEST_FACTOR * tol,
+        "value {:e} differs from {:e} beyond tolerance {:e}",
+        result.val,
+        expected,
+        tol
+    );
+    assert!(
+        result.err >= 0.0 && result.err.is_finite(),
+        "bad error estimate {:e}",
+        result.err
+    );
+    assert!(
+        diff <= TEST_SIGMA * result.err.max(f64::MIN_POSITIVE) || diff <= TOL0 * expected.abs(),
+        "error {:e} of {:e} inconsistent with its estimate {:e}",
+        diff,
+        result.val,
+        result.err
+    );
+}
+
+/// Checks `|result - expected| <= tol |expected|`, or `|result| <= tol` for `expected = 0`
+#[track_caller]
+pub(crate) fn assert_rel(result: f64, expected: f64, tol: f64) {
+    let error = if expected == 0.0 {
+        result.abs()
+    } else {
+        (result - expected).abs() / expected.abs()
+    };
+    assert!(
+        error <= tol,
+        "{:e} differs from {:e} by the relative error {:e}, more than {:e}",
+        result,
+        expected,
+        error,
+        tol
+    );
+}
+
+/// Checks `|result - expected| <= tol`
+#[track_caller]
+pub(crate) fn assert_abs(result: f64, expected: f64, tol: f64) {
+    let error = (result - expected).abs();
+    assert!(
+        error <= tol,
+        "{:e} differs from {:e} by {:e}, more than {:e}",
+        result,
+        expected,
+        error,
+        tol
+    );
+}
+
+fn frac_diff(a: f64, b: f64) -> f64 {
+    if a == 0.0 && b == 0.0 {
+        0.0
+    } else if a == 0.0 {
+        b.abs()
+    } else if a + b != 0.0 && a.is_finite() && b.is_finite() {
+        ((a - b) / (a + b)).abs()
+    } else {
+        1.0
+    }
+}
@@ -0,0 +1,196 @@
+/*
+    text.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::binary::io_error;
+use crate::histogram::Histogram;
+use crate::*;
+use std::io::{Read, Write};
+
+/*
+
+    Readers and writers for the text files of `gsl_vector_fprintf`, `gsl_matrix_fprintf` and
+    `gsl_histogram_fprintf`, the text counterparts of the files in `binary`.
+
+    A vector is written as one element per line, and a matrix as one element per line in row major order.
+    A histogram has one line per bin with its lower end, its upper end and its value.
+    Reading follows `gsl_vector_fscanf` and friends, which read any whitespace separated doubles,
+    so the layout of the lines doesn't matter and files written with any `printf` format can be read.
+
+    Values are written with the shortest representation that reads back to the same `f64`,
+    which the `%lg` of the GSL readers reads back exactly as well.
+
+    A file that ends early fails with `GSLError::EOF`, a value that isn't a number with `GSLError::Failed`.
+    Reading stops right after the expected values, so several objects can be read from one file.
+    The readers read a byte at a time, so files are best passed in a `BufReader`.
+
+*/
+
+/// Reads the `n` elements of a vector written by `gsl_vector_fprintf`
+pub fn read_vector<R: Read>(reader: R, n: usize) -> Result<Box<[f64]>> {
+    read_doubles(reader, n)
+}
+
+/// Reads the row major `m × n` elements of a matrix written by `gsl_matrix_fprintf`
+pub fn read_matrix<R: Read>(reader: R, m: usize, n: usize) -> Result<Box<[f64]>> {
+    let len = m.checked_mul(n).ok_or(GSLError::Invalid)?;
+    read_doubles(reader, len)
+}
+
+/// Reads a histogram with `n` bins written by `gsl_histogram_fprintf`.
+/// Fails with `GSLError::Domain` if the ranges read aren't strictly increasing.
+pub fn read_histogram<R: Read>(reader: R, n: usize) -> Result<Histogram> {
+    if n == 0 {
+        return Err(GSLError::Invalid);
+    }
+    let lines = read_doubles(reader, n.checked_mul(3).ok_or(GSLError::Invalid)?)?;
+
+    // Like GSL, the upper end of a bin is only read for the last bin
+    let mut ranges = lines.chunks_exact(3).map(|l| l[0]).collect::<Vec<_>>();
+    ranges.push(lines[3 * n - 2]);
+    let mut histogram = Histogram::with_ranges(&ranges)?;
+    for (&lower, line) in ranges.iter().zip(lines.chunks_exact(3)) {
+        histogram.accumulate(lower, line[2])?;
+    }
+    Ok(histogram)
+}
+
+/// Writes a vector like `gsl_vector_fprintf`
+pub fn write_vector<W: Write>(writer: W, x: &[f64]) -> Result<()> {
+    write_lines(writer, x.chunks(1))
+}
+
+/// Writes the row major `m × n` matrix `a` like `gsl_matrix_fprintf`
+pub fn write_matrix<W: Write>(writer: W, m: usize, n: usize, a: &[f64]) -> Result<()> {
+    if m.checked_mul(n) != Some(a.len()) {
+        return Err(GSLError::BadLength);
+    }
+    write_lines(writer, a.chunks(1))
+}
+
+/// Writes a histogram like `gsl_histogram_fprintf`
+pub fn write_histogram<W: Write>(writer: W, histogram: &Histogram) -> Result<()> {
+    let ranges = histogram.ranges();
+    let lines = ranges
+        .windows(2)
+        .zip(histogram.bins().iter())
+        .map(|(range, &bin)| [range[0], range[1], bin])
+        .collect::<Vec<_>>();
+    write_lines(writer, lines.iter().map(|line| &line[..]))
+}
+
+// Byte by byte, so nothing after the last value is consumed
+#[allow(clippy::unbuffered_bytes)]
+fn read_doubles<R: Read>(reader: R, n: usize) -> Result<Box<[f64]>> {
+    let mut bytes = reader.bytes();
+    let mut values = Vec::with_capacity(n.min(1 << 16));
+    let mut token = Vec::new();
+    while values.len() < n {
+        token.clear();
+        for byte in bytes.by_ref() {
+            let byte = byte.map_err(io_error)?;
+            if !byte.is_ascii_whitespace() {
+                token.push(byte);
+            } else if !token.is_empty() {
+                break;
+            }
+        }
+        if token.is_empty() {
+            return Err(GSLError::EOF);
+        }
+        let value = std::str::from_utf8(&token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or(GSLError::Failed)?;
+        values.push(value);
+    }
+    Ok(values.into_boxed_slice())
+}
+
+fn write_lines<'a, W: Write, L: Iterator<Item = &'a [f64]>>(mut writer: W, lines: L) -> Result<()> {
+    for line in lines {
+        let line = line.iter().map(|x| format!("{:?}", x)).collect::<Vec<_>>();
+        writeln!(writer, "{}", line.join(" ")).map_err(io_error)?;
+    }
+    writer.flush().map_err(io_error)
+}
+
+#[test]
+fn test_text() {
+    disable_error_handler();
+
+    // As written by gsl_vector_fprintf with "%g", and by hand
+    let file = "1\n-2.5\n1e-20\ninf\n";
+    assert_eq!(
+        *read_vector(file.as_bytes(), 4).unwrap(),
+        [1.0, -2.5, 1.0e-20, f64::INFINITY]
+    );
+    assert_eq!(
+        *read_matrix("  1 2\n\t3 4 ".as_bytes(), 2, 2).unwrap(),
+        [1.0, 2.0, 3.0, 4.0]
+    );
+
+    // Round trips, with several objects in one file
+    let a = [1.0 / 3.0, 2.0, -3.0e300, 4.0, 5.0e-324, f64::NAN];
+    let mut h = Histogram::with_ranges(&[0.0, 1.0, 10.0]).unwrap();
+    h.accumulate(0.5, 3.0).unwrap();
+    h.accumulate(5.0, 0.25).unwrap();
+    let mut file = Vec::new();
+    write_matrix(&mut file, 2, 3, &a).unwrap();
+    write_histogram(&mut file, &h).unwrap();
+    write_vector(&mut file, &a[..2]).unwrap();
+    assert_eq!(
+        String::from_utf8(file.clone()).unwrap().lines().nth(6),
+        Some("0.0 1.0 3.0")
+    );
+
+    let mut reader = &file[..];
+    let b = read_matrix(&mut reader, 2, 3).unwrap();
+    assert_eq!(b[..5], a[..5]);
+    assert!(b[5].is_nan());
+    let g = read_histogram(&mut reader, 2).unwrap();
+    assert_eq!(g.ranges(), h.ranges());
+    assert_eq!(g.bins(), h.bins());
+    assert_eq!(*read_vector(&mut reader, 2).unwrap(), a[..2]);
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // File too short
+    assert_eq!(
+        read_vector("1 2 ".as_bytes(), 3).unwrap_err(),
+        GSLError::EOF
+    );
+    read_histogram("0 1 2\n".as_bytes(), 2).unwrap_err();
+
+    // Not a number
+    assert_eq!(
+        read_vector("1 x 3".as_bytes(), 3).unwrap_err(),
+        GSLError::Failed
+    );
+
+    // Ranges not increasing
+    read_histogram("1 0 2\n".as_bytes(), 1).unwrap_err();
+
+    // Bad sizes
+    read_histogram("0 1 2\n".as_bytes(), 0).unwrap_err();
+    read_matrix("".as_bytes(), usize::MAX, 2).unwrap_err();
+    write_matrix(Vec::new(), 2, 2, &[1.0; 3]).unwrap_err();
+}
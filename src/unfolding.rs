@@ -0,0 +1,455 @@
+/*
+    unfolding.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::linalg::{Cholesky, LU};
+use crate::*;
+
+/*
+
+    A true spectrum `x` with `n` bins is measured as `y = R x` with `m` bins,
+    where the response `R_ij` is the probability that an event in true bin `j`
+    is measured in bin `i`. The efficiency of true bin `j` is the column sum `Σ_i R_ij`.
+
+    Inverting `R` directly amplifies the statistical fluctuations of `y`,
+    which both methods suppress at the cost of a bias:
+    Tikhonov unfolding penalizes the norm or the curvature of the solution,
+    iterative Bayesian unfolding (D'Agostini, 1995) stops after a few iterations
+    starting from a smooth prior.
+
+    The covariance of the measured spectrum defaults to Poisson, `diag(max(y_i, 1))`,
+    and is propagated linearly to the unfolded spectrum.
+
+*/
+
+/// Penalty term of Tikhonov unfolding
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Regularization {
+    /// Norm `|x|^2` of the unfolded spectrum
+    Norm,
+    /// Curvature `Σ (x_{j-1} - 2 x_j + x_{j+1})^2` of the unfolded spectrum
+    Curvature,
+}
+
+/// Unfolded spectrum with its covariance matrix
+#[derive(Clone, Debug)]
+pub struct UnfoldingResult {
+    pub spectrum: Vector,
+    pub covariance: Matrix,
+}
+
+impl UnfoldingResult {
+    pub fn uncertainty(&self, i: usize) -> f64 {
+        self.covariance.elem_ij(i, i).sqrt()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Unfolding {
+    m: usize,
+    n: usize,
+    response: Matrix,
+    efficiency: Vector,
+}
+
+impl Unfolding {
+    /// Unfolding with the `m x n` response matrix.
+    /// Every true bin needs a nonzero efficiency of at most 1.
+    pub fn new(response: &Matrix) -> Result<Self> {
+        let (m, n) = response.size();
+        if response.iter().any(|&r| !(0.0..=1.0).contains(&r)) {
+            return Err(GSLError::Domain);
+        }
+
+        let efficiency = (0..n)
+            .map(|j| (0..m).map(|i| response[i * n + j]).sum::<f64>())
+            .collect::<Vector>();
+        if efficiency.iter().any(|&e| !(e > 0.0 && e <= 1.0 + 1.0e-12)) {
+            return Err(GSLError::Domain);
+        }
+
+        Ok(Unfolding {
+            m,
+            n,
+            response: response.clone(),
+            efficiency,
+        })
+    }
+
+    /// Number of measured bins
+    pub fn m(&self) -> usize {
+        self.m
+    }
+
+    /// Number of true bins
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    pub fn efficiency(&self) -> &Vector {
+        &self.efficiency
+    }
+
+    /// Expected measured spectrum `R x` of the true spectrum `x`
+    pub fn fold(&self, x: &Vector) -> Result<Vector> {
+        if x.len() != self.n {
+            return Err(GSLError::BadLength);
+        }
+        let folded = self
+            .response
+            .chunks_exact(self.n)
+            .map(|r| blas::dot(r, x))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Vector::new(folded))
+    }
+
+    /// Tikhonov unfolding with Poisson uncertainties, see `tikhonov_ext`
+    pub fn tikhonov(
+        &self,
+        measured: &Vector,
+        tau: f64,
+        regularization: Regularization,
+    ) -> Result<UnfoldingResult> {
+        let covariance = self.poisson_covariance(measured)?;
+        self.tikhonov_ext(measured, &covariance, tau, regularization)
+    }
+
+    /// Minimizes `(y - R x)^T V^-1 (y - R x) + τ^2 |L x|^2`, with `V` the `m x m` covariance
+    /// of the measured spectrum and `L` the regularization operator.
+    /// A larger `τ` gives a smoother spectrum with smaller uncertainties but a larger bias.
+    pub fn tikhonov_ext(
+        &self,
+        measured: &Vector,
+        covariance: &Matrix,
+        tau: f64,
+        regularization: Regularization,
+    ) -> Result<UnfoldingResult> {
+        let (m, n) = (self.m, self.n);
+        if measured.len() != m || covariance.size() != (m, m) {
+            return Err(GSLError::BadLength);
+        }
+        if !(tau >= 0.0 && tau.is_finite()) {
+            return Err(GSLError::Domain);
+        }
+        if regularization == Regularization::Curvature && n < 3 {
+            return Err(GSLError::Invalid);
+        }
+
+        // Whitened system, with V = C C^T
+        let cholesky = Cholesky::new(m, covariance)?;
+        let white_y = cholesky.solve_lower(measured);
+        let white_columns = (0..n)
+            .map(|j| cholesky.solve_lower(&self.response.col(j)))
+            .collect::<Vec<_>>();
+
+        // Normal equations A x = b, with A = R^T V^-1 R + τ^2 L^T L
+        let mut fisher = vec![0.0; n * n];
+        for j in 0..n {
+            for k in 0..n {
+                fisher[j * n + k] = blas::dot(&white_columns[j], &white_columns[k])?;
+            }
+        }
+        let mut a = fisher.clone();
+        let tau2 = tau * tau;
+        match regularization {
+            Regularization::Norm => {
+                for j in 0..n {
+                    a[j * n + j] += tau2;
+                }
+            }
+            Regularization::Curvature => {
+                for row in 0..n - 2 {
+                    let stencil = [(row, 1.0), (row + 1, -2.0), (row + 2, 1.0)];
+                    for &(j, lj) in &stencil {
+                        for &(k, lk) in &stencil {
+                            a[j * n + k] += tau2 * lj * lk;
+                        }
+                    }
+                }
+            }
+        }
+        let b = white_columns
+            .iter()
+            .map(|column| blas::dot(column, &white_y))
+            .collect::<Result<Vec<_>>>()?;
+
+        let lu = LU::new(n, &a)?;
+        let spectrum = lu.solve(&b)?;
+
+        // Cov(x) = A^-1 R^T V^-1 R A^-1
+        let mut inverse = vec![0.0; n * n];
+        for k in 0..n {
+            let mut unit = vec![0.0; n];
+            unit[k] = 1.0;
+            let column = lu.solve(&unit)?;
+            for j in 0..n {
+                inverse[j * n + k] = column[j];
+            }
+        }
+        let covariance = sandwich(n, n, &inverse, &fisher)?;
+
+        Ok(UnfoldingResult {
+            spectrum: Vector::new(spectrum.iter().copied()),
+            covariance,
+        })
+    }
+
+    /// Iterative Bayesian unfolding with Poisson uncertainties and a flat prior, see `bayesian_ext`
+    pub fn bayesian(&self, measured: &Vector, iterations: usize) -> Result<UnfoldingResult> {
+        let covariance = self.poisson_covariance(measured)?;
+        let prior = Vector::new(vec![1.0; self.n]);
+        self.bayesian_ext(measured, &covariance, &prior, iterations)
+    }
+
+    /// Every iteration updates the spectrum with Bayes' theorem,
+    /// `x_j = Σ_i y_i R_ij p_j / (ε_j Σ_k R_ik p_k)`, using the previous spectrum `p` as the prior.
+    /// Fewer iterations give a spectrum closer to the prior with smaller uncertainties.
+    /// The covariance neglects the dependence of the prior on the measurement.
+    pub fn bayesian_ext(
+        &self,
+        measured: &Vector,
+        covariance: &Matrix,
+        prior: &Vector,
+        iterations: usize,
+    ) -> Result<UnfoldingResult> {
+        let (m, n) = (self.m, self.n);
+        if measured.len() != m || covariance.size() != (m, m) || prior.len() != n {
+            return Err(GSLError::BadLength);
+        }
+        if iterations == 0 {
+            return Err(GSLError::Invalid);
+        }
+        if measured.iter().any(|&y| !(y >= 0.0 && y.is_finite())) {
+            return Err(GSLError::Domain);
+        }
+        if prior.iter().any(|&p| !(p >= 0.0 && p.is_finite())) || prior.iter().all(|&p| p == 0.0) {
+            return Err(GSLError::Domain);
+        }
+
+        let mut spectrum = prior.clone();
+        let mut unfolding = vec![0.0; n * m];
+        for _ in 0..iterations {
+            let folded = self.fold(&spectrum)?;
+
+            // Unfolding matrix U_ji = R_ij p_j / (ε_j Σ_k R_ik p_k)
+            for i in 0..m {
+                for j in 0..n {
+                    unfolding[j * m + i] = if folded[i] > 0.0 {
+                        self.response.elem_ij(i, j) * spectrum[j] / (self.efficiency[j] * folded[i])
+                    } else {
+                        0.0
+                    };
+                }
+            }
+
+            spectrum = Vector::new(
+                unfolding
+                    .chunks_exact(m)
+                    .map(|u| blas::dot(u, measured))
+                    .collect::<Result<Vec<_>>>()?,
+            );
+        }
+
+        Ok(UnfoldingResult {
+            spectrum,
+            covariance: sandwich(n, m, &unfolding, covariance)?,
+        })
+    }
+
+    fn poisson_covariance(&self, measured: &Vector) -> Result<Matrix> {
+        if measured.len() != self.m {
+            return Err(GSLError::BadLength);
+        }
+        let mut covariance = Matrix::zeroes(self.m, self.m);
+        for (i, &y) in measured.iter().enumerate() {
+            covariance.set_ij(i, i, y.max(1.0));
+        }
+        Ok(covariance)
+    }
+}
+
+/// `A V A^T` for an `r x c` matrix `A` and `c x c` matrix `V`
+fn sandwich(r: usize, c: usize, a: &[f64], v: &[f64]) -> Result<Matrix> {
+    let av = (0..r * c)
+        .map(|index| {
+            let (i, k) = (index / c, index % c);
+            (0..c).map(|l| a[i * c + l] * v[l * c + k]).sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+    let ava = (0..r * r)
+        .map(|index| {
+            let (i, j) = (index / r, index % r);
+            blas::dot(&av[i * c..(i + 1) * c], &a[j * c..(j + 1) * c])
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Matrix::new(ava, r, r))
+}
+
+#[cfg(test)]
+fn smearing(n: usize, efficiency: f64, sigma: f64) -> Matrix {
+    let mut response = Matrix::zeroes(n, n);
+    for j in 0..n {
+        let weights = (0..n)
+            .map(|i| (-0.5 * ((i as f64 - j as f64) / sigma).powi(2)).exp())
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f64>();
+        for (i, w) in weights.iter().enumerate() {
+            response.set_ij(i, j, efficiency * w / total);
+        }
+    }
+    response
+}
+
+#[test]
+fn test_tikhonov() {
+    disable_error_handler();
+
+    let n = 20;
+    let unfolding = Unfolding::new(&smearing(n, 0.8, 1.0)).unwrap();
+    let truth = (0..n)
+        .map(|j| 1000.0 * (-0.5 * ((j as f64 - 8.0) / 3.0).powi(2)).exp() + 50.0)
+        .collect::<Vector>();
+    let measured = unfolding.fold(&truth).unwrap();
+
+    // Without regularization the exact measurement is inverted
+    let exact = unfolding
+        .tikhonov(&measured, 0.0, Regularization::Norm)
+        .unwrap();
+    for (x, t) in exact.spectrum.iter().zip(truth.iter()) {
+        approx::assert_relative_eq!(x, t, max_relative = 1.0e-6);
+    }
+
+    // The truth is smooth, so a small curvature penalty hardly biases it,
+    // while regularization reduces the uncertainties
+    let smooth = unfolding
+        .tikhonov(&measured, 0.002, Regularization::Curvature)
+        .unwrap();
+    for (x, t) in smooth.spectrum.iter().zip(truth.iter()) {
+        approx::assert_relative_eq!(x, t, max_relative = 0.01);
+    }
+    for j in 0..n {
+        assert!(smooth.uncertainty(j) < exact.uncertainty(j));
+    }
+
+    let strong = unfolding
+        .tikhonov(&measured, 10.0, Regularization::Norm)
+        .unwrap();
+    let total = |r: &UnfoldingResult| (0..n).map(|j| r.uncertainty(j)).sum::<f64>();
+    assert!(total(&strong) < total(&exact));
+}
+
+#[test]
+fn test_bayesian() {
+    disable_error_handler();
+
+    let n = 20;
+    let unfolding = Unfolding::new(&smearing(n, 0.8, 1.0)).unwrap();
+    let truth = (0..n)
+        .map(|j| 1000.0 * (-0.5 * ((j as f64 - 8.0) / 3.0).powi(2)).exp() + 50.0)
+        .collect::<Vector>();
+    let measured = unfolding.fold(&truth).unwrap();
+
+    let few = unfolding.bayesian(&measured, 4).unwrap();
+    let many = unfolding.bayesian(&measured, 1000).unwrap();
+
+    // Every iteration conserves the number of measured events
+    for result in [&few, &many] {
+        let detected = blas::dot(&result.spectrum, unfolding.efficiency()).unwrap();
+        approx::assert_relative_eq!(
+            detected,
+            measured.iter().sum::<f64>(),
+            max_relative = 1.0e-10
+        );
+    }
+
+    // More iterations approach the truth, with larger uncertainties
+    for (x, t) in many.spectrum.iter().zip(truth.iter()) {
+        approx::assert_relative_eq!(x, t, max_relative = 0.02);
+    }
+    let error = |r: &UnfoldingResult| {
+        r.spectrum
+            .iter()
+            .zip(truth.iter())
+            .map(|(x, t)| (x - t).abs())
+            .sum::<f64>()
+    };
+    assert!(error(&many) < error(&few));
+    assert!(many.uncertainty(8) > few.uncertainty(8));
+
+    // Without smearing the first iteration corrects for the efficiency
+    let diagonal = Unfolding::new(&Matrix::from([[0.5, 0.0], [0.0, 0.25]])).unwrap();
+    let result = diagonal.bayesian(&Vector::new([10.0, 20.0]), 1).unwrap();
+    approx::assert_abs_diff_eq!(result.spectrum[0], 20.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(result.spectrum[1], 80.0, epsilon = 1.0e-12);
+    approx::assert_abs_diff_eq!(
+        result.uncertainty(1),
+        20.0f64.sqrt() / 0.25,
+        epsilon = 1.0e-9
+    );
+}
+
+#[test]
+fn test_invalid_params() {
+    disable_error_handler();
+
+    // Zero efficiency
+    Unfolding::new(&Matrix::from([[1.0, 0.0], [0.0, 0.0]])).unwrap_err();
+
+    // Efficiency above 1
+    Unfolding::new(&Matrix::from([[0.8], [0.8]])).unwrap_err();
+
+    let unfolding = Unfolding::new(&Matrix::from([[0.5, 0.1], [0.1, 0.5]])).unwrap();
+    let measured = Vector::new([1.0, 2.0]);
+
+    // Wrong number of measured bins
+    unfolding
+        .tikhonov(&Vector::new([1.0]), 1.0, Regularization::Norm)
+        .unwrap_err();
+
+    // Wrong size of the covariance
+    unfolding
+        .tikhonov_ext(
+            &measured,
+            &Matrix::from([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]),
+            1.0,
+            Regularization::Norm,
+        )
+        .unwrap_err();
+
+    // Negative regularization
+    unfolding
+        .tikhonov(&measured, -1.0, Regularization::Norm)
+        .unwrap_err();
+
+    // Too few bins for the curvature
+    unfolding
+        .tikhonov(&measured, 1.0, Regularization::Curvature)
+        .unwrap_err();
+
+    // No iterations
+    unfolding.bayesian(&measured, 0).unwrap_err();
+
+    // Covariance not positive definite
+    unfolding
+        .tikhonov_ext(
+            &measured,
+            &Matrix::from([[1.0, 0.0], [0.0, -1.0]]),
+            1.0,
+            Regularization::Norm,
+        )
+        .unwrap_err();
+}
@@ -0,0 +1,141 @@
+/*
+    util.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::*;
+use std::io::{self, Write};
+
+/*
+
+    Small helpers shared by the examples: grids to evaluate functions on,
+    and a CSV writer to export results for plotting.
+
+    The CSV output has a header line and one line per row, with the columns separated by commas.
+    Values are written with the shortest representation that reads back to the same `f64`.
+
+*/
+
+/// `n` evenly spaced points from `a` to `b`, including both ends
+pub fn linspace(a: f64, b: f64, n: usize) -> Vec<f64> {
+    match n {
+        0 => Vec::new(),
+        1 => vec![a],
+        _ => {
+            let step = (b - a) / (n - 1) as f64;
+            (0..n)
+                .map(|i| if i == n - 1 { b } else { a + i as f64 * step })
+                .collect()
+        }
+    }
+}
+
+/// `n` logarithmically spaced points from `a` to `b`, including both ends.
+/// Fails with `GSLError::Domain` unless `a` and `b` are positive.
+pub fn logspace(a: f64, b: f64, n: usize) -> Result<Vec<f64>> {
+    if !(a > 0.0 && b > 0.0) {
+        return Err(GSLError::Domain);
+    }
+    let mut points = linspace(a.ln(), b.ln(), n)
+        .into_iter()
+        .map(f64::exp)
+        .collect::<Vec<_>>();
+    // Exact ends
+    if let Some(first) = points.first_mut() {
+        *first = a;
+    }
+    if n > 1 {
+        points[n - 1] = b;
+    }
+    Ok(points)
+}
+
+/// All points `[x[i], y[j]]` of the grid spanned by `x` and `y`, with `x` varying fastest
+pub fn grid_2d(x: &[f64], y: &[f64]) -> Vec<[f64; 2]> {
+    y.iter()
+        .flat_map(|&yj| x.iter().map(move |&xi| [xi, yj]))
+        .collect()
+}
+
+/// Writes the `columns` as CSV with the column names `headers`.
+/// Fails with `io::ErrorKind::InvalidInput` if the amounts of headers and columns differ,
+/// or if the columns have different lengths.
+pub fn write_csv<W: Write>(mut writer: W, headers: &[&str], columns: &[&[f64]]) -> io::Result<()> {
+    let rows = columns.first().map_or(0, |c| c.len());
+    if headers.len() != columns.len() || columns.iter().any(|c| c.len() != rows) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "columns don't match the headers or each other",
+        ));
+    }
+
+    writeln!(writer, "{}", headers.join(","))?;
+    for i in 0..rows {
+        let row = columns
+            .iter()
+            .map(|c| format!("{:?}", c[i]))
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    writer.flush()
+}
+
+#[test]
+fn test_grids() {
+    assert_eq!(linspace(0.0, 1.0, 5), [0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert_eq!(linspace(2.0, 3.0, 1), [2.0]);
+    assert!(linspace(2.0, 3.0, 0).is_empty());
+    assert_eq!(*linspace(0.0, 0.3, 7).last().unwrap(), 0.3);
+
+    let points = logspace(1.0, 1000.0, 4).unwrap();
+    assert_eq!(points[0], 1.0);
+    assert_eq!(points[3], 1000.0);
+    approx::assert_relative_eq!(points[1], 10.0, max_relative = 1.0e-14);
+    approx::assert_relative_eq!(points[2], 100.0, max_relative = 1.0e-14);
+
+    let grid = grid_2d(&[1.0, 2.0], &[3.0, 4.0, 5.0]);
+    assert_eq!(grid.len(), 6);
+    assert_eq!(grid[1], [2.0, 3.0]);
+    assert_eq!(grid[2], [1.0, 4.0]);
+}
+
+#[test]
+fn test_csv() {
+    let mut out = Vec::new();
+    write_csv(&mut out, &["x", "y"], &[&[1.0, 0.1], &[-2.5, 1.0e-20]]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "x,y\n1.0,-2.5\n0.1,1e-20\n"
+    );
+
+    // Values read back exactly
+    let x = 1.0 / 3.0;
+    let mut out = Vec::new();
+    write_csv(&mut out, &["x"], &[&[x]]).unwrap();
+    let line = String::from_utf8(out).unwrap();
+    assert_eq!(line.lines().nth(1).unwrap().parse::<f64>().unwrap(), x);
+}
+
+#[test]
+fn test_invalid_params() {
+    // Nonpositive ends
+    logspace(0.0, 1.0, 3).unwrap_err();
+    logspace(1.0, f64::NAN, 3).unwrap_err();
+
+    // Mismatched columns
+    write_csv(Vec::new(), &["x", "y"], &[&[1.0]]).unwrap_err();
+    write_csv(Vec::new(), &["x", "y"], &[&[1.0], &[1.0, 2.0]]).unwrap_err();
+}
@@ -0,0 +1,190 @@
+/*
+    workspace.rs
+    Copyright (C) 2021 Pim van den Berg
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread::{self, ThreadId};
+
+/*
+
+    Thread safety of the wrapper types.
+
+    Besides the error handler, GSL keeps the default generator type and seed of `gsl_rng_env_setup`
+    in globals. This crate never sets those, so GSL objects can be used from any thread.
+    All handle types, like `Rng`, the integration and FFT workspaces, `BSplineBasis`, `Histogram`
+    and the distributions, are `Send`: methods that change the GSL object take `&mut self`.
+    Most are also `Sync`, since their methods taking `&self` only read the GSL object.
+    The exceptions use it as scratch memory: `BSplineBasis`, and so `BSpline`, whose `eval` writes
+    to the workspace, and `RunningStats` and `RunningQuantile`, which sort their first few values
+    when a quantile is read.
+
+    The error handler is process-wide, see `disable_error_handler` and the `gsl_thread_safe` feature.
+
+    Workspaces are expensive to allocate compared to a single integral or fit. `ThreadLocalWorkspace`
+    keeps one for every thread that uses it, for reuse in parallel loops, e.g. with rayon,
+    whose threads live as long as the pool.
+
+*/
+
+/// Workspaces created by `new`, one for every thread that calls `with`
+pub struct ThreadLocalWorkspace<T, F = fn() -> Result<T>> {
+    new: F,
+    workspaces: Mutex<HashMap<ThreadId, T>>,
+}
+
+impl<T: Send, F: Fn() -> Result<T>> ThreadLocalWorkspace<T, F> {
+    pub fn new(new: F) -> Self {
+        ThreadLocalWorkspace {
+            new,
+            workspaces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Calls `f` with the workspace of this thread, creating it on first use.
+    /// A workspace is dropped instead of reused if `f` panics, since it may be in an inconsistent state.
+    pub fn with<R, G: FnOnce(&mut T) -> R>(&self, f: G) -> Result<R> {
+        let id = thread::current().id();
+        // Taken out while in use, so a nested call on the same thread creates a second one
+        let own = self.lock().remove(&id);
+        let mut workspace = match own {
+            Some(workspace) => workspace,
+            None => (self.new)()?,
+        };
+        let result = f(&mut workspace);
+        self.lock().insert(id, workspace);
+        Ok(result)
+    }
+
+    /// Number of threads with a workspace
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the workspaces that aren't in use, e.g. of threads that have finished
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<ThreadId, T>> {
+        self.workspaces
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T, F> fmt::Debug for ThreadLocalWorkspace<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadLocalWorkspace")
+            .finish_non_exhaustive()
+    }
+}
+
+#[test]
+#[cfg(feature = "gsl")]
+fn test_send_sync() {
+    fn assert_send<T: Send>() {}
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send::<bspline::BSplineBasis>();
+    assert_send::<bspline::BSpline>();
+    assert_send::<rstat::RunningStats>();
+    assert_send::<rstat::RunningQuantile>();
+    assert_send_sync::<cheb::ChebSeries>();
+    assert_send_sync::<Matrix>();
+    assert_send_sync::<MatrixView<'static>>();
+    assert_send_sync::<Vector>();
+    assert_send_sync::<fft::RealFft>();
+    assert_send_sync::<fft::ComplexFft>();
+    assert_send_sync::<histogram::Histogram>();
+    assert_send_sync::<integrate::IntegrationWorkspace>();
+    assert_send_sync::<integrate::QawsTable>();
+    assert_send_sync::<integrate::QawoTable>();
+    assert_send_sync::<integrate::CquadWorkspace>();
+    assert_send_sync::<integrate::RombergWorkspace>();
+    assert_send_sync::<integrate::FixedQuadrature>();
+    assert_send_sync::<integrate::GLFixedTable>();
+    assert_send_sync::<linear_fit::RidgeRegression>();
+    assert_send_sync::<multilarge::LargeLinearFit>();
+    assert_send_sync::<ntuple::NTupleWriter>();
+    assert_send_sync::<ntuple::NTupleReader>();
+    assert_send_sync::<randist::InverseCdfSampler>();
+    assert_send_sync::<randist::HistogramDistribution>();
+    assert_send_sync::<randist::DiscreteDistribution>();
+    assert_send_sync::<rng::Rng>();
+    assert_send_sync::<ThreadLocalWorkspace<integrate::IntegrationWorkspace>>();
+}
+
+#[test]
+#[cfg(feature = "gsl")]
+fn test_thread_local_workspace() {
+    use integrate::{GaussKronrodRule, IntegrationWorkspace};
+
+    disable_error_handler();
+
+    let workspaces = ThreadLocalWorkspace::new(|| IntegrationWorkspace::new(100));
+    let integrals = std::thread::scope(|s| {
+        let threads = (1..=4)
+            .map(|k| {
+                let workspaces = &workspaces;
+                s.spawn(move || {
+                    (0..10)
+                        .map(|_| {
+                            workspaces
+                                .with(|ws| {
+                                    ws.qag(0.0, 1.0, 0.0, 1.0e-12, GaussKronrodRule::Gauss21, |x| {
+                                        x.powi(k)
+                                    })
+                                    .unwrap()
+                                    .val
+                                })
+                                .unwrap()
+                        })
+                        .sum::<f64>()
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    for (k, integral) in (1..=4).zip(integrals) {
+        approx::assert_relative_eq!(integral, 10.0 / (k + 1) as f64, max_relative = 1.0e-12);
+    }
+    assert_eq!(workspaces.len(), 4);
+
+    // The workspace of a thread is reused, other threads get their own
+    workspaces.clear();
+    workspaces.with(|_| {}).unwrap();
+    workspaces.with(|_| {}).unwrap();
+    assert_eq!(workspaces.len(), 1);
+    std::thread::scope(|s| {
+        s.spawn(|| workspaces.with(|_| {}).unwrap());
+    });
+    assert_eq!(workspaces.len(), 2);
+
+    // Errors creating a workspace are passed on
+    let invalid = ThreadLocalWorkspace::new(|| IntegrationWorkspace::new(0));
+    invalid.with(|_| {}).unwrap_err();
+}
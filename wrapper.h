@@ -1,16 +1,41 @@
 #include <gsl_blas.h>
 #include <gsl_bspline.h>
+#include <gsl_cdf.h>
+#include <gsl_chebyshev.h>
+#include <gsl_const_cgsm.h>
+#include <gsl_const_mksa.h>
+#include <gsl_const_num.h>
+#include <gsl_deriv.h>
+#include <gsl_eigen.h>
 #include <gsl_errno.h>
+#include <gsl_fft_complex.h>
+#include <gsl_fft_halfcomplex.h>
 #include <gsl_fft_real.h>
 #include <gsl_filter.h>
+#include <gsl_fit.h>
+#include <gsl_histogram.h>
 #include <gsl_integration.h>
 #include <gsl_interp.h>
+#include <gsl_linalg.h>
 #include <gsl_matrix.h>
 #include <gsl_min.h>
+#include <gsl_movstat.h>
 #include <gsl_multifit.h>
 #include <gsl_multifit_nlinear.h>
+#include <gsl_multilarge.h>
+#include <gsl_multilarge_nlinear.h>
+#include <gsl_multimin.h>
+#include <gsl_ntuple.h>
+#include <gsl_odeiv2.h>
+#include <gsl_poly.h>
+#include <gsl_randist.h>
+#include <gsl_rng.h>
+#include <gsl_roots.h>
+#include <gsl_rstat.h>
+#include <gsl_sort_double.h>
 #include <gsl_sort_vector_double.h>
 #include <gsl_statistics_double.h>
 #include <gsl_types.h>
 #include <gsl_vector.h>
+#include <gsl_version.h>
 #include <gsl_sf.h>
\ No newline at end of file